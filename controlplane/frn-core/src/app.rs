@@ -11,9 +11,11 @@
 use crate::{
     Config, Error,
     authorization::Authorize,
-    compute::{Hypervisors, Instances, Zones},
+    compute::{
+        Hypervisors, Images, InstanceBackups, Instances, Overlays, SecurityGroups, Volumes, Zones,
+    },
     identity::{IAM, Invitations, ServiceAccounts, SessionKey, Users},
-    resourcemanager::{Organizations, Projects},
+    resourcemanager::{Organizations, Projects, Quotas},
 };
 use auth::OpenID;
 use spicedb::SpiceDB;
@@ -33,12 +35,18 @@ pub struct App<A: Authorize> {
 
     // services
     pub hypervisors: Hypervisors<A>,
+    pub images: Images<A>,
+    pub instance_backups: InstanceBackups<A>,
     pub instances: Instances<A>,
     pub invitations: Invitations<A>,
     pub organizations: Organizations<A>,
+    pub overlays: Overlays,
     pub projects: Projects<A>,
+    pub quotas: Quotas<A>,
+    pub security_groups: SecurityGroups<A>,
     pub service_accounts: ServiceAccounts<A>,
     pub users: Users<A>,
+    pub volumes: Volumes<A>,
     pub zones: Zones<A>,
 }
 
@@ -62,13 +70,20 @@ impl App<SpiceDB> {
         let session_key = session_key_from_env()?;
         let iam = IAM::new(db.clone(), openid.clone(), session_key);
 
-        let hypervisors = Hypervisors::new(auth.clone(), db.clone());
+        let resolver = config.hypervisor_resolver.clone();
+        let hypervisors = Hypervisors::new(auth.clone(), db.clone(), resolver.clone());
+        let images = Images::new(auth.clone(), db.clone(), resolver.clone());
         let organizations = Organizations::new(auth.clone(), db.clone());
-        let instances = Instances::new(auth.clone(), db.clone());
+        let instances = Instances::new(auth.clone(), db.clone(), resolver.clone());
         let invitations = Invitations::new(auth.clone(), db.clone(), organizations.clone());
+        let overlays = Overlays::new(db.clone(), resolver.clone());
         let projects = Projects::new(auth.clone(), db.clone());
+        let quotas = Quotas::new(auth.clone(), db.clone());
         let service_accounts = ServiceAccounts::new(auth.clone(), db.clone());
         let users = Users::new(auth.clone(), db.clone());
+        let instance_backups = InstanceBackups::new(auth.clone(), db.clone(), resolver.clone());
+        let security_groups = SecurityGroups::new(auth.clone(), db.clone(), resolver.clone());
+        let volumes = Volumes::new(auth.clone(), db.clone(), resolver.clone());
         let zones = Zones::new(auth.clone(), db.clone());
 
         let app = Self {
@@ -78,13 +93,19 @@ impl App<SpiceDB> {
             iam,
             openid,
             hypervisors,
+            images,
+            instance_backups,
             instances,
             invitations,
 
             organizations,
+            overlays,
             projects,
+            quotas,
+            security_groups,
             service_accounts,
             users,
+            volumes,
             zones,
         };
 
@@ -108,13 +129,20 @@ impl App<SpiceDB> {
             Some(SessionKey::from_bytes(crate::identity::TEST_SESSION_KEY)),
         );
 
-        let instances = Instances::new(auth.clone(), db.clone());
-        let hypervisors = Hypervisors::new(auth.clone(), db.clone());
+        let resolver = config.hypervisor_resolver.clone();
+        let instances = Instances::new(auth.clone(), db.clone(), resolver.clone());
+        let hypervisors = Hypervisors::new(auth.clone(), db.clone(), resolver.clone());
+        let images = Images::new(auth.clone(), db.clone(), resolver.clone());
         let organizations = Organizations::new(auth.clone(), db.clone());
         let invitations = Invitations::new(auth.clone(), db.clone(), organizations.clone());
+        let overlays = Overlays::new(db.clone(), resolver.clone());
         let projects = Projects::new(auth.clone(), db.clone());
+        let quotas = Quotas::new(auth.clone(), db.clone());
         let service_accounts = ServiceAccounts::new(auth.clone(), db.clone());
         let users = Users::new(auth.clone(), db.clone());
+        let instance_backups = InstanceBackups::new(auth.clone(), db.clone(), resolver.clone());
+        let security_groups = SecurityGroups::new(auth.clone(), db.clone(), resolver.clone());
+        let volumes = Volumes::new(auth.clone(), db.clone(), resolver.clone());
         let zones = Zones::new(auth.clone(), db.clone());
 
         let app = Self {
@@ -125,12 +153,18 @@ impl App<SpiceDB> {
             openid,
             instances,
             hypervisors,
+            images,
+            instance_backups,
             invitations,
 
             organizations,
+            overlays,
             projects,
+            quotas,
+            security_groups,
             service_accounts,
             users,
+            volumes,
             zones,
         };
 
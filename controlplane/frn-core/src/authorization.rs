@@ -13,6 +13,7 @@ mod authorize;
 mod check;
 mod lookup;
 mod permission;
+mod platform;
 mod principal;
 mod relationship;
 mod resource;
@@ -21,6 +22,7 @@ mod zookie;
 pub use authorize::*;
 pub use frn_derive::*;
 pub use permission::*;
+pub use platform::*;
 pub use principal::*;
 pub use relationship::*;
 pub use resource::*;
@@ -5,14 +5,41 @@ use strum_macros::{Display, EnumString};
 #[derive(Debug, Default, Display, EnumString)]
 #[strum(serialize_all = "snake_case")]
 pub enum Permission {
+    Attach,
     Clone,
+    Console,
+    CreateBackup,
+    CreateHypervisor,
     CreateInstance,
+    CreateSecurityGroup,
+    CreateSnapshot,
+    CreateVolume,
+    CreateZeroTrustNetwork,
+    CreateZone,
     Delete,
+    DeleteBackup,
+    DeleteSnapshot,
+    Detach,
+    Drain,
     Get,
     List,
+    ListBackups,
+    ListSnapshots,
+    InjectSshKey,
     InviteMember,
+    ManageBackups,
+    ManageImages,
+    Reboot,
+    Reset,
+    ResetPassword,
+    Resize,
+    RestoreBackup,
+    Resume,
+    RollbackSnapshot,
+    Shutdown,
     Start,
     Stop,
+    Suspend,
     Update,
     #[default]
     Unspecified,
@@ -0,0 +1,23 @@
+use crate::authorization::Resource;
+
+/// The platform itself, which platform admins are related to.
+///
+/// The infrastructure shared by every tenant, such as hypervisors and zones,
+/// is related to the platform so that only its admins may change it.
+#[derive(Debug, Resource)]
+pub struct Platform {
+    id: String,
+}
+
+impl Platform {
+    /// The identifier of the platform.
+    pub const ID: &'static str = "plateforme";
+}
+
+impl Default for Platform {
+    fn default() -> Self {
+        Self {
+            id: Self::ID.to_owned(),
+        }
+    }
+}
@@ -10,8 +10,11 @@ use strum_macros::{Display, EnumString};
 #[strum(serialize_all = "snake_case")]
 #[serde(rename_all = "snake_case")]
 pub enum Relation {
+    Admin,
     Member,
     Parent,
+    Platform,
+    Viewer,
     #[default]
     Unspecified,
 }
@@ -54,6 +57,21 @@ impl Relationship {
             subject_type: subject.name().to_string(),
         }
     }
+
+    /// Relates every subject of the `Subject` type to the object, as SpiceDB
+    /// wildcards (`user:*`) do.
+    pub fn wildcard<Subject: Resource, Object: Resource>(
+        relation: Relation,
+        object: &Object,
+    ) -> Self {
+        Self {
+            object_id: object.id().to_string(),
+            object_type: object.name().to_string(),
+            relation,
+            subject_id: "*".to_owned(),
+            subject_type: Subject::RESOURCE_NAME.to_owned(),
+        }
+    }
 }
 
 impl From<&Relationship> for RelationshipRef {
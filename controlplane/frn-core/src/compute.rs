@@ -1,7 +1,30 @@
+mod backup;
 mod hypervisor;
+mod image;
 mod instance;
+mod overlay;
+mod scheduler;
+mod security_group;
+mod volume;
 mod zone;
 
+pub use backup::*;
 pub use hypervisor::*;
+pub use image::*;
 pub use instance::*;
+pub use overlay::*;
+pub use security_group::*;
+pub use volume::*;
 pub use zone::*;
+
+use crate::Error;
+use crate::authorization::Principal;
+
+/// Restricts an operation on the infrastructure to platform admins.
+pub(crate) fn require_admin<P: Principal>(principal: &P) -> Result<(), Error> {
+    if principal.is_platform_admin() {
+        Ok(())
+    } else {
+        Err(Error::Forbidden)
+    }
+}
@@ -0,0 +1,365 @@
+//! Instance backups.
+//!
+//! Backs instances up with vzdump to the backup storage of their hypervisor,
+//! on demand or on a schedule run by the hypervisor itself, and restores
+//! backups into new instances. The schedule and retention of the backups of
+//! an instance are recorded as its backup policy, with authorization checks
+//! against the instance.
+
+use crate::Error;
+use crate::authorization::{Authorize, Permission, Principal, Relation, Relationship, Resource};
+use crate::compute::{Hypervisor, Instance};
+use crate::resourcemanager::Project;
+use chrono::{DateTime, Utc};
+use fabrique::{Delete, Factory, Model, Persist, Query};
+use hypervisor::Resolver;
+use hypervisor::instance::{
+    Backup, BackupRetention, BackupSchedule, Instances as HypervisorInstancesTrait, Status,
+};
+use sqlx::{Pool, Postgres};
+use std::cmp::Reverse;
+use uuid::Uuid;
+
+#[derive(Clone, Debug, Default, Factory, Model)]
+#[fabrique(table = "backup_policies")]
+pub struct BackupPolicy {
+    /// Unique identifier for the policy
+    #[fabrique(primary_key)]
+    pub id: Uuid,
+    /// The instance backed up by this policy
+    pub instance_id: Uuid,
+    /// The systemd calendar event backups are taken on (e.g. `daily`)
+    pub schedule: String,
+    /// Number of most recent backups to keep, 0 for no limit
+    pub keep_last: i32,
+    /// Number of days to keep the last backup of, 0 for no limit
+    pub keep_daily: i32,
+    /// Number of weeks to keep the last backup of, 0 for no limit
+    pub keep_weekly: i32,
+    /// Number of months to keep the last backup of, 0 for no limit
+    pub keep_monthly: i32,
+    // Creation time of the policy
+    pub created_at: DateTime<Utc>,
+    // Time of the policy last update
+    pub updated_at: DateTime<Utc>,
+}
+
+impl BackupPolicy {
+    /// Gets the retention applied to the backups of the instance.
+    pub fn retention(&self) -> BackupRetention {
+        let keep = |count: i32| (count > 0).then_some(count as u32);
+
+        BackupRetention {
+            keep_last: keep(self.keep_last),
+            keep_daily: keep(self.keep_daily),
+            keep_weekly: keep(self.keep_weekly),
+            keep_monthly: keep(self.keep_monthly),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct BackupPolicyUpdateRequest {
+    /// The instance to back up.
+    pub instance_id: Uuid,
+
+    /// The systemd calendar event backups are taken on.
+    pub schedule: String,
+
+    /// The retention applied to the backups of the instance.
+    pub retention: BackupRetention,
+}
+
+#[derive(Clone, Debug)]
+pub struct BackupRestoreRequest {
+    /// The instance the backup was taken from.
+    pub instance_id: Uuid,
+
+    /// The volume id of the backup to restore.
+    pub backup_id: String,
+
+    /// The name of the new instance, the one of the backed up instance when
+    /// unset.
+    pub name: Option<String>,
+}
+
+/// Service for managing the backups of instances.
+#[derive(Clone)]
+pub struct InstanceBackups<A: Authorize> {
+    auth: A,
+    db: Pool<Postgres>,
+    resolver: Resolver,
+}
+
+impl<A: Authorize> InstanceBackups<A> {
+    /// Creates a new instance backups service.
+    pub fn new(auth: A, db: Pool<Postgres>, resolver: Resolver) -> Self {
+        Self { auth, db, resolver }
+    }
+
+    /// Lists the backups of an instance, newest first.
+    pub async fn list<P: Principal + Sync>(
+        &mut self,
+        principal: &P,
+        instance_id: Uuid,
+    ) -> Result<Vec<Backup>, Error> {
+        self.auth
+            .can(principal)
+            .perform(Permission::ListBackups)
+            .over::<Instance>(&instance_id)
+            .await?;
+
+        let instance = Instance::find(&self.db, instance_id).await?;
+        let mut backups = self
+            .connector(&instance)
+            .await?
+            .list_backups(&instance.distant_id, instance.id)
+            .await?;
+        backups.sort_by_key(|backup| Reverse(backup.created_at));
+
+        Ok(backups)
+    }
+
+    /// Backs an instance up right away.
+    ///
+    /// The retention of the backup policy of the instance, if any, is applied
+    /// once the backup is done.
+    pub async fn create<P: Principal + Sync>(
+        &mut self,
+        principal: &P,
+        instance_id: Uuid,
+    ) -> Result<Backup, Error> {
+        self.auth
+            .can(principal)
+            .perform(Permission::CreateBackup)
+            .over::<Instance>(&instance_id)
+            .await?;
+
+        let instance = Instance::find(&self.db, instance_id).await?;
+        let retention = self
+            .find_policy(instance_id)
+            .await?
+            .map(|policy| policy.retention())
+            .unwrap_or_default();
+
+        self.connector(&instance)
+            .await?
+            .create_backup(&instance.distant_id, instance.id, &retention)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Restores a backup of an instance into a new instance.
+    ///
+    /// The new instance gets a new distant id, and belongs to the project of
+    /// the backed up instance.
+    pub async fn restore<P: Principal + Sync>(
+        &mut self,
+        principal: &P,
+        request: BackupRestoreRequest,
+    ) -> Result<Instance, Error> {
+        self.auth
+            .can(principal)
+            .perform(Permission::RestoreBackup)
+            .over::<Instance>(&request.instance_id)
+            .await?;
+
+        let existing = Instance::find(&self.db, request.instance_id).await?;
+
+        self.auth
+            .can(principal)
+            .perform(Permission::CreateInstance)
+            .over::<Project>(&existing.project_slug)
+            .await?;
+
+        let name = request.name.unwrap_or_else(|| existing.name.clone());
+        let connector = self.connector(&existing).await?;
+        let (source_id, owner) = (existing.distant_id.clone(), existing.id);
+
+        // The restored instance has the shape of the instance, checked
+        // against the quotas before the hypervisor restores it.
+        let instance = Instance {
+            id: Uuid::new_v4(),
+            name: name.clone(),
+            ip_v4: String::new(),
+            cpu_usage_percent: 0.0,
+            memory_usage_bytes: 0,
+            status: Status::Stopped,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            // The restored instance joins no zero trust network until
+            // attached to one.
+            zero_trust_network_id: None,
+            missing_since: None,
+            ..existing
+        }
+        .create_within_quotas(&self.db, async {
+            Ok(connector
+                .restore_backup(&source_id, owner, &request.backup_id, &name)
+                .await?)
+        })
+        .await?;
+
+        self.auth
+            .write_relationship(&Relationship::new(
+                &Project::some(instance.project_slug.clone()),
+                Relation::Parent,
+                &instance,
+            ))
+            .await?;
+
+        Ok(instance)
+    }
+
+    /// Deletes a backup of an instance.
+    pub async fn delete<P: Principal + Sync>(
+        &mut self,
+        principal: &P,
+        instance_id: Uuid,
+        backup_id: &str,
+    ) -> Result<(), Error> {
+        self.auth
+            .can(principal)
+            .perform(Permission::DeleteBackup)
+            .over::<Instance>(&instance_id)
+            .await?;
+
+        let instance = Instance::find(&self.db, instance_id).await?;
+        self.connector(&instance)
+            .await?
+            .delete_backup(&instance.distant_id, instance.id, backup_id)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Gets the backup policy of an instance.
+    pub async fn get_policy<P: Principal + Sync>(
+        &mut self,
+        principal: &P,
+        instance_id: Uuid,
+    ) -> Result<BackupPolicy, Error> {
+        self.auth
+            .can(principal)
+            .perform(Permission::ListBackups)
+            .over::<Instance>(&instance_id)
+            .await?;
+
+        self.find_policy(instance_id)
+            .await?
+            .ok_or(Error::BackupPolicyNotFound(instance_id))
+    }
+
+    /// Sets the schedule and retention of the backups of an instance.
+    ///
+    /// The schedule is run by the hypervisor, so backups keep being taken
+    /// while the control plane is down.
+    pub async fn update_policy<P: Principal + Sync>(
+        &mut self,
+        principal: &P,
+        request: BackupPolicyUpdateRequest,
+    ) -> Result<BackupPolicy, Error> {
+        self.auth
+            .can(principal)
+            .perform(Permission::ManageBackups)
+            .over::<Instance>(&request.instance_id)
+            .await?;
+
+        let instance = Instance::find(&self.db, request.instance_id).await?;
+        self.connector(&instance)
+            .await?
+            .schedule_backups(
+                &instance.distant_id,
+                instance.id,
+                Some(BackupSchedule {
+                    schedule: request.schedule.clone(),
+                    retention: request.retention.clone(),
+                }),
+            )
+            .await?;
+
+        let keep = |count: Option<u32>| count.unwrap_or_default() as i32;
+        match self.find_policy(instance.id).await? {
+            Some(policy) => {
+                BackupPolicy::update()
+                    .set(BackupPolicy::SCHEDULE, request.schedule.clone())
+                    .set(BackupPolicy::KEEP_LAST, keep(request.retention.keep_last))
+                    .set(BackupPolicy::KEEP_DAILY, keep(request.retention.keep_daily))
+                    .set(
+                        BackupPolicy::KEEP_WEEKLY,
+                        keep(request.retention.keep_weekly),
+                    )
+                    .set(
+                        BackupPolicy::KEEP_MONTHLY,
+                        keep(request.retention.keep_monthly),
+                    )
+                    .set(BackupPolicy::UPDATED_AT, Utc::now())
+                    .r#where(BackupPolicy::ID, "=", policy.id)
+                    .execute(&self.db)
+                    .await?;
+
+                BackupPolicy::find(&self.db, policy.id)
+                    .await
+                    .map_err(Into::into)
+            }
+            None => BackupPolicy {
+                id: Uuid::new_v4(),
+                instance_id: instance.id,
+                schedule: request.schedule,
+                keep_last: keep(request.retention.keep_last),
+                keep_daily: keep(request.retention.keep_daily),
+                keep_weekly: keep(request.retention.keep_weekly),
+                keep_monthly: keep(request.retention.keep_monthly),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            }
+            .create(&self.db)
+            .await
+            .map_err(Into::into),
+        }
+    }
+
+    /// Stops the scheduled backups of an instance, keeping its backups.
+    pub async fn delete_policy<P: Principal + Sync>(
+        &mut self,
+        principal: &P,
+        instance_id: Uuid,
+    ) -> Result<(), Error> {
+        self.auth
+            .can(principal)
+            .perform(Permission::ManageBackups)
+            .over::<Instance>(&instance_id)
+            .await?;
+
+        let policy = self
+            .find_policy(instance_id)
+            .await?
+            .ok_or(Error::BackupPolicyNotFound(instance_id))?;
+
+        let instance = Instance::find(&self.db, instance_id).await?;
+        self.connector(&instance)
+            .await?
+            .schedule_backups(&instance.distant_id, instance.id, None)
+            .await?;
+
+        BackupPolicy::destroy(&self.db, policy.id)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Finds the backup policy of an instance, if any.
+    async fn find_policy(&self, instance_id: Uuid) -> Result<Option<BackupPolicy>, Error> {
+        BackupPolicy::query()
+            .select()
+            .r#where(BackupPolicy::INSTANCE_ID, "=", instance_id)
+            .first(&self.db)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Resolves the hypervisor connector of an instance.
+    async fn connector(&self, instance: &Instance) -> Result<impl HypervisorInstancesTrait, Error> {
+        let hypervisor = Hypervisor::find(&self.db, instance.hypervisor_id).await?;
+
+        hypervisor.resolve(&self.resolver)
+    }
+}
@@ -1,11 +1,55 @@
 use crate::Error;
-use crate::authorization::{Authorize, Permission, Principal, Relation, Relationship, Resource};
-use crate::compute::{Zone, ZoneFactory, ZoneIdColumn};
+use crate::authorization::{
+    Authorize, Permission, Platform, Principal, Relation, Relationship, Resource,
+};
+use crate::compute::{Instance, InstanceMigration, Zone, ZoneFactory, ZoneIdColumn, require_admin};
 use crate::resourcemanager::Organization;
+use chrono::{DateTime, Utc};
 use fabrique::{Delete, Factory, Model, Query};
+use fake::Dummy;
+use frn_crypto::{EnvelopeCiphertext, Kek};
+use hypervisor::instance::Instances as HypervisorInstancesTrait;
+use hypervisor::placement::NodeCapacity;
+use hypervisor::{ConnectorConfig, HypervisorKind, Resolver, SnippetsTransport};
 use sqlx::{Pool, Postgres};
+use std::str::FromStr;
+use std::time::Duration;
+use strum_macros::{Display, EnumString};
 use uuid::Uuid;
 
+/// Upper bound on a hypervisor probe, so a cluster that stopped answering does
+/// not stall the synchronizer pass.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Result of the most recent probe of a hypervisor.
+#[derive(Clone, Copy, Debug, Default, Display, Dummy, EnumString, PartialEq)]
+#[strum(serialize_all = "UPPERCASE")]
+pub enum HypervisorHealth {
+    /// The hypervisor answered the last probe.
+    #[dummy(skip)]
+    Healthy,
+
+    /// The hypervisor did not answer the last probe.
+    #[dummy(skip)]
+    Unreachable,
+
+    /// The hypervisor was not probed yet.
+    #[default]
+    Unknown,
+}
+
+impl From<String> for HypervisorHealth {
+    fn from(value: String) -> Self {
+        HypervisorHealth::from_str(&value).expect("could not parse value to hypervisor health")
+    }
+}
+
+impl From<HypervisorHealth> for String {
+    fn from(value: HypervisorHealth) -> Self {
+        value.to_string()
+    }
+}
+
 #[derive(Debug, Default, Factory, Model, Resource)]
 pub struct Hypervisor {
     /// The hypervisor id
@@ -21,16 +65,132 @@ pub struct Hypervisor {
     /// The hypervisor url
     pub url: String,
 
-    /// The hypervisor authentication token
-    pub authorization_token: String,
+    /// The kind of platform the hypervisor runs on
+    #[fabrique(as = "String")]
+    pub kind: HypervisorKind,
+
+    /// The hypervisor authentication token, envelope-encrypted and bound to
+    /// the hypervisor id
+    pub token_ciphertext: Vec<u8>,
+
+    /// The nonce the authentication token is encrypted with
+    pub token_nonce: Vec<u8>,
+
+    /// The key the authentication token is encrypted with, wrapped by the KEK
+    pub token_dek_ciphertext: Vec<u8>,
+
+    /// The nonce the key of the authentication token is wrapped with
+    pub token_dek_nonce: Vec<u8>,
+
+    /// The version of the KEK wrapping the key of the authentication token
+    pub token_key_version: i32,
+
+    /// The algorithm the authentication token is encrypted with
+    pub token_algorithm: String,
 
     /// The hypervisor storage name
     pub storage_name: String,
+
+    /// The transport the cloud-init snippets are written to the hypervisor with
+    #[fabrique(as = "String")]
+    pub snippets_transport: SnippetsTransport,
+
+    /// The result of the last probe of the hypervisor
+    #[fabrique(as = "String")]
+    pub health: HypervisorHealth,
+
+    /// The time of the last probe of the hypervisor
+    pub last_health_check_at: Option<DateTime<Utc>>,
+
+    /// The version of the hypervisor API, as of the last successful probe
+    pub version: Option<String>,
+
+    /// The number of online nodes of the hypervisor
+    pub node_count: i32,
+
+    /// The number of CPUs of the online nodes
+    pub cpu_total: i32,
+
+    /// The number of CPUs left idle on the online nodes
+    pub cpu_free: f64,
+
+    /// The memory of the online nodes (in bytes)
+    pub memory_total_bytes: i64,
+
+    /// The memory left on the online nodes (in bytes)
+    pub memory_free_bytes: i64,
+
+    /// The size of the image storage of the online nodes (in bytes)
+    pub storage_total_bytes: i64,
+
+    /// The space left on the image storage of the online nodes (in bytes)
+    pub storage_free_bytes: i64,
+}
+
+impl Hypervisor {
+    /// Resolves the connector of the hypervisor, decrypting its authentication
+    /// token with the key of `resolver`.
+    pub fn resolve(
+        &self,
+        resolver: &Resolver,
+    ) -> Result<impl HypervisorInstancesTrait + use<>, Error> {
+        resolver
+            .resolve(ConnectorConfig {
+                id: self.id,
+                url: self.url.clone(),
+                kind: self.kind,
+                token: self.authorization_token(),
+                token_algorithm: self.token_algorithm.clone(),
+                storage: self.storage_name.clone(),
+                snippets_transport: self.snippets_transport,
+            })
+            .map_err(Into::into)
+    }
+
+    /// Gets the envelope of the authentication token.
+    fn authorization_token(&self) -> EnvelopeCiphertext {
+        EnvelopeCiphertext {
+            ciphertext: self.token_ciphertext.clone(),
+            nonce: self.token_nonce.clone(),
+            dek_ciphertext: self.token_dek_ciphertext.clone(),
+            dek_nonce: self.token_dek_nonce.clone(),
+            key_version: self.token_key_version,
+        }
+    }
+}
+
+impl HypervisorFactory {
+    /// Sets the authentication token, encrypted with `kek` under a freshly
+    /// generated hypervisor id it is bound to.
+    pub fn authorization_token_with(self, kek: &Kek, token: &str) -> Result<Self, Error> {
+        let id = Uuid::new_v4();
+        let envelope = hypervisor::token::encrypt(kek, id, token)?;
+
+        Ok(self
+            .id(id)
+            .token_ciphertext(envelope.ciphertext)
+            .token_nonce(envelope.nonce)
+            .token_dek_ciphertext(envelope.dek_ciphertext)
+            .token_dek_nonce(envelope.dek_nonce)
+            .token_key_version(envelope.key_version)
+            .token_algorithm(frn_crypto::ALGORITHM.to_owned()))
+    }
+
+    /// Sets the authentication token, encrypted with the key of the test
+    /// configuration.
+    #[cfg(feature = "mock")]
+    pub fn authorization_token(self, token: &str) -> Self {
+        let kek = Kek::from_bytes(crate::config::TEST_HYPERVISOR_TOKEN_KEK);
+        self.authorization_token_with(&kek, token)
+            .expect("could not encrypt the hypervisor token")
+    }
 }
 
 pub struct HypervisorCreateRequest {
     pub authorization_token: String,
+    pub kind: HypervisorKind,
     pub storage_name: String,
+    pub snippets_transport: SnippetsTransport,
     pub organization_slug: String,
     pub url: String,
     pub zone_id: Uuid,
@@ -40,57 +200,91 @@ pub struct HypervisorCreateRequest {
 pub struct Hypervisors<Auth: Authorize> {
     auth: Auth,
     db: Pool<Postgres>,
+    resolver: Resolver,
 }
 
 impl<Auth: Authorize> Hypervisors<Auth> {
     /// Creates a new hypervisors service.
-    pub fn new(auth: Auth, db: Pool<Postgres>) -> Self {
-        Self { auth, db }
+    pub fn new(auth: Auth, db: Pool<Postgres>, resolver: Resolver) -> Self {
+        Self { auth, db, resolver }
     }
 
     /// Lists all hypervisors accessible to the principal.
-    pub async fn list<P: Principal>(&mut self, _principal: &P) -> Result<Vec<Hypervisor>, Error> {
-        // self.auth
-        //     .can(principal)
-        //     .perform(Permission::List)
-        //     .over(&Hypervisor::any())
-        //     .check()
-        //     .await?;
+    ///
+    /// Platform admins list the whole fleet, other principals the hypervisors
+    /// of their organizations.
+    pub async fn list<P: Principal>(&mut self, principal: &P) -> Result<Vec<Hypervisor>, Error> {
+        if principal.is_platform_admin() {
+            return Hypervisor::all(&self.db).await.map_err(Into::into);
+        }
 
-        Hypervisor::all(&self.db).await.map_err(Into::into)
+        self.auth
+            .lookup::<Hypervisor>()
+            .on_behalf_of(principal)
+            .with(Permission::List)
+            .against(&self.db)
+            .await
     }
 
-    /// Creates a new hypervisor.
+    /// Creates a new hypervisor. Restricted to platform admins.
     pub async fn create<P: Principal>(
         &mut self,
-        _principal: &P,
+        principal: &P,
         request: HypervisorCreateRequest,
     ) -> Result<Hypervisor, Error> {
-        // self.auth
-        //     .can(principal)
-        //     .perform(Permission::Create)
-        //     .over(&Hypervisor::any())
-        //     .check()
-        //     .await?;
+        require_admin(principal)?;
+        self.auth
+            .can::<P>(principal)
+            .perform(Permission::CreateHypervisor)
+            .over::<Platform>(&Platform::ID.to_owned())
+            .await?;
 
         let hypervisor = Hypervisor::factory()
+            .authorization_token_with(self.resolver.kek(), &request.authorization_token)?
+            .kind(request.kind)
             .storage_name(request.storage_name)
+            .snippets_transport(request.snippets_transport)
             .url(request.url)
-            .authorization_token(request.authorization_token)
             .zone_id(request.zone_id)
             .organization_slug(request.organization_slug.clone())
             .create(&self.db)
             .await?;
 
+        self.relate(&hypervisor).await?;
+
+        Ok(hypervisor)
+    }
+
+    /// Relates the hypervisors to their organization and to the platform.
+    ///
+    /// Hypervisors registered before they were related to the platform cannot
+    /// be deleted nor drained by its admins until related here. Relationships
+    /// are idempotent, so this is safe to run on every boot.
+    pub async fn relate_all(&mut self) -> Result<(), Error> {
+        for hypervisor in Hypervisor::all(&self.db).await? {
+            self.relate(&hypervisor).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn relate(&mut self, hypervisor: &Hypervisor) -> Result<(), Error> {
         self.auth
             .write_relationship(&Relationship::new(
-                &Organization::some(request.organization_slug.clone()),
+                &Organization::some(hypervisor.organization_slug.clone()),
                 Relation::Parent,
-                &hypervisor,
+                hypervisor,
+            ))
+            .await?;
+        self.auth
+            .write_relationship(&Relationship::new(
+                &Platform::default(),
+                Relation::Platform,
+                hypervisor,
             ))
             .await?;
 
-        Ok(hypervisor)
+        Ok(())
     }
 
     pub async fn read<P: Principal>(
@@ -107,8 +301,9 @@ impl<Auth: Authorize> Hypervisors<Auth> {
         Hypervisor::find(&self.db, id).await.map_err(Into::into)
     }
 
-    /// Deletes a hypervisor.
+    /// Deletes a hypervisor. Restricted to platform admins.
     pub async fn delete<P: Principal>(&mut self, principal: &P, id: Uuid) -> Result<(), Error> {
+        require_admin(principal)?;
         self.auth
             .can::<P>(principal)
             .perform(Permission::Delete)
@@ -117,4 +312,197 @@ impl<Auth: Authorize> Hypervisors<Auth> {
 
         Hypervisor::destroy(&self.db, id).await.map_err(Into::into)
     }
+
+    /// Migrates every instance off a node of a hypervisor onto its other
+    /// nodes. Restricted to platform admins.
+    ///
+    /// Nothing moves when the other nodes cannot hold all the instances.
+    /// Instances are moved to another hypervisor one at a time, through
+    /// [`Instances::migrate`](crate::compute::Instances::migrate).
+    pub async fn drain<P: Principal>(
+        &self,
+        principal: &P,
+        id: Uuid,
+        node: &str,
+    ) -> Result<Vec<InstanceMigration>, Error> {
+        require_admin(principal)?;
+        self.auth
+            .can::<P>(principal)
+            .perform(Permission::Drain)
+            .over::<Hypervisor>(&id)
+            .await?;
+
+        let hypervisor = Hypervisor::find(&self.db, id).await?;
+        let migrations = hypervisor.resolve(&self.resolver)?.drain(node).await?;
+
+        let instances = Instance::query()
+            .select()
+            .r#where(Instance::HYPERVISOR_ID, "=", id)
+            .get(&self.db)
+            .await?;
+
+        Ok(migrations
+            .into_iter()
+            .map(|migration| InstanceMigration::new(migration, id, &instances))
+            .collect())
+    }
+
+    /// Probes a hypervisor, recording its reachability, the version of its
+    /// API and the capacity aggregated over its online nodes.
+    ///
+    /// A hypervisor failing to answer within [`PROBE_TIMEOUT`] is marked
+    /// unreachable, keeping the version and capacity it last reported.
+    pub async fn probe(&self, hypervisor: &Hypervisor) -> Result<Hypervisor, Error> {
+        let connector = hypervisor.resolve(&self.resolver)?;
+        let report = tokio::time::timeout(PROBE_TIMEOUT, async {
+            let nodes = connector.capacity().await?;
+            let version = connector.version().await?;
+            Ok::<_, hypervisor::Error>((nodes, version))
+        })
+        .await;
+
+        match report {
+            Ok(Ok((nodes, version))) => {
+                let capacity = Capacity::from_nodes(&nodes);
+
+                Hypervisor::query()
+                    .update()
+                    .set(Hypervisor::HEALTH, HypervisorHealth::Healthy.to_string())
+                    .set(Hypervisor::LAST_HEALTH_CHECK_AT, Some(Utc::now()))
+                    .set(Hypervisor::VERSION, Some(version))
+                    .set(Hypervisor::NODE_COUNT, nodes.len() as i32)
+                    .set(Hypervisor::CPU_TOTAL, capacity.cpu_total)
+                    .set(Hypervisor::CPU_FREE, capacity.cpu_free)
+                    .set(Hypervisor::MEMORY_TOTAL_BYTES, capacity.memory_total_bytes)
+                    .set(Hypervisor::MEMORY_FREE_BYTES, capacity.memory_free_bytes)
+                    .set(
+                        Hypervisor::STORAGE_TOTAL_BYTES,
+                        capacity.storage_total_bytes,
+                    )
+                    .set(Hypervisor::STORAGE_FREE_BYTES, capacity.storage_free_bytes)
+                    .r#where(Hypervisor::ID, "=", hypervisor.id)
+                    .execute(&self.db)
+                    .await?;
+            }
+            report => {
+                let error = match report {
+                    Ok(Err(err)) => err.to_string(),
+                    _ => String::from("probe timed out"),
+                };
+                tracing::warn!(hypervisor_id = %hypervisor.id, error, "hypervisor is unreachable");
+
+                Hypervisor::query()
+                    .update()
+                    .set(
+                        Hypervisor::HEALTH,
+                        HypervisorHealth::Unreachable.to_string(),
+                    )
+                    .set(Hypervisor::LAST_HEALTH_CHECK_AT, Some(Utc::now()))
+                    .r#where(Hypervisor::ID, "=", hypervisor.id)
+                    .execute(&self.db)
+                    .await?;
+            }
+        }
+
+        Hypervisor::find(&self.db, hypervisor.id)
+            .await
+            .map_err(Into::into)
+    }
+}
+
+/// Encrypts the authentication tokens still stored in plaintext.
+///
+/// The tokens of the hypervisors registered before they were encrypted at rest
+/// are left in the legacy `authorization_token` column, which the migrations
+/// cannot encrypt without the KEK. Run by `server hypervisors encrypt-tokens`
+/// after the migrations and by the control plane at startup, each token is
+/// encrypted with `kek`, then cleared from the legacy column. Returns the
+/// number of tokens encrypted, none once the legacy column is dropped by a
+/// later release.
+pub async fn encrypt_plaintext_tokens(db: &Pool<Postgres>, kek: &Kek) -> Result<u64, Error> {
+    let legacy: bool = sqlx::query_scalar(
+        r#"SELECT EXISTS (
+             SELECT 1 FROM information_schema.columns
+             WHERE table_schema = 'public'
+               AND table_name = 'hypervisors'
+               AND column_name = 'authorization_token'
+           )"#,
+    )
+    .fetch_one(db)
+    .await?;
+    if !legacy {
+        return Ok(0);
+    }
+
+    // Raw SQL: the legacy column is not part of the model.
+    let rows = sqlx::query_as::<_, (Uuid, String)>(
+        "SELECT id, authorization_token FROM hypervisors WHERE authorization_token IS NOT NULL",
+    )
+    .fetch_all(db)
+    .await?;
+
+    let mut encrypted = 0;
+    for (id, token) in rows {
+        let envelope = hypervisor::token::encrypt(kek, id, &token)?;
+
+        encrypted += sqlx::query(
+            r#"UPDATE hypervisors
+               SET token_ciphertext = $2,
+                   token_nonce = $3,
+                   token_dek_ciphertext = $4,
+                   token_dek_nonce = $5,
+                   token_key_version = $6,
+                   token_algorithm = $7,
+                   authorization_token = NULL
+               WHERE id = $1 AND authorization_token = $8"#,
+        )
+        .bind(id)
+        .bind(envelope.ciphertext)
+        .bind(envelope.nonce)
+        .bind(envelope.dek_ciphertext)
+        .bind(envelope.dek_nonce)
+        .bind(envelope.key_version)
+        .bind(frn_crypto::ALGORITHM)
+        .bind(token)
+        .execute(db)
+        .await?
+        .rows_affected();
+    }
+
+    if encrypted > 0 {
+        tracing::info!(encrypted, "encrypted plaintext hypervisor tokens");
+    }
+
+    Ok(encrypted)
+}
+
+/// The capacity of a hypervisor, summed over its online nodes.
+struct Capacity {
+    cpu_total: i32,
+    cpu_free: f64,
+    memory_total_bytes: i64,
+    memory_free_bytes: i64,
+    storage_total_bytes: i64,
+    storage_free_bytes: i64,
+}
+
+impl Capacity {
+    fn from_nodes(nodes: &[NodeCapacity]) -> Self {
+        Capacity {
+            cpu_total: nodes.iter().map(|node| node.max_cpu as i32).sum(),
+            cpu_free: nodes.iter().map(|node| node.free_cpu).sum(),
+            memory_total_bytes: nodes.iter().map(|node| node.max_memory_bytes as i64).sum(),
+            memory_free_bytes: nodes.iter().map(|node| node.free_memory_bytes as i64).sum(),
+            storage_total_bytes: nodes
+                .iter()
+                .filter_map(|node| node.max_disk_bytes)
+                .map(|bytes| bytes as i64)
+                .sum(),
+            storage_free_bytes: nodes
+                .iter()
+                .filter_map(|node| node.free_disk_bytes)
+                .map(|bytes| bytes as i64)
+                .sum(),
+        }
+    }
 }
@@ -0,0 +1,232 @@
+//! Disk image catalogue of hypervisors.
+//!
+//! Provides the Images service listing the ISO and cloud images held by the
+//! storage of a hypervisor, and importing or deleting them, with
+//! authorization checks. Images live on the hypervisor only, and are
+//! identified by their volume id on its storage.
+//!
+//! The hypervisor downloads imported images itself, so their URL must point
+//! to a public host over https: a private or link-local one would let the
+//! hypervisor be used to reach the internal network.
+
+use crate::Error;
+use crate::authorization::{Authorize, Permission, Principal};
+use crate::compute::Hypervisor;
+use fabrique::Query;
+use hypervisor::HypervisorKind;
+use hypervisor::Resolver;
+use hypervisor::instance::Instances as HypervisorInstancesTrait;
+use hypervisor::instance::{Image, ImageKind};
+use reqwest::Url;
+use sqlx::{Pool, Postgres};
+use std::net::{IpAddr, Ipv4Addr};
+use uuid::Uuid;
+
+#[derive(Clone, Debug)]
+pub struct ImageImportRequest {
+    /// The hypervisor to import the image into.
+    pub hypervisor_id: Uuid,
+
+    /// The URL to download the image from.
+    pub url: String,
+
+    /// The file name of the image, ending with `.qcow2`, `.raw` or `.iso`.
+    pub name: String,
+}
+
+/// Service for managing the disk images of hypervisors.
+#[derive(Clone)]
+pub struct Images<A: Authorize> {
+    auth: A,
+    db: Pool<Postgres>,
+    resolver: Resolver,
+}
+
+impl<A: Authorize> Images<A> {
+    /// Creates a new images service.
+    pub fn new(auth: A, db: Pool<Postgres>, resolver: Resolver) -> Self {
+        Self { auth, db, resolver }
+    }
+
+    /// Lists the images available on a hypervisor.
+    pub async fn list<P: Principal + Sync>(
+        &mut self,
+        principal: &P,
+        hypervisor_id: Uuid,
+    ) -> Result<Vec<Image>, Error> {
+        self.auth
+            .can(principal)
+            .perform(Permission::Get)
+            .over::<Hypervisor>(&hypervisor_id)
+            .await?;
+
+        let hypervisor = Hypervisor::find(&self.db, hypervisor_id).await?;
+        hypervisor
+            .resolve(&self.resolver)?
+            .list_images(&hypervisor.storage_name)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Imports an image from a URL into the storage of a hypervisor.
+    pub async fn import<P: Principal + Sync>(
+        &mut self,
+        principal: &P,
+        request: ImageImportRequest,
+    ) -> Result<Image, Error> {
+        self.auth
+            .can(principal)
+            .perform(Permission::ManageImages)
+            .over::<Hypervisor>(&request.hypervisor_id)
+            .await?;
+        ensure_public_url(&request.url).await?;
+
+        let hypervisor = Hypervisor::find(&self.db, request.hypervisor_id).await?;
+        let options = hypervisor::instance::ImageImportRequest {
+            url: request.url,
+            name: request.name,
+        };
+
+        hypervisor
+            .resolve(&self.resolver)?
+            .import_image(&hypervisor.storage_name, options)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Deletes an image from the storage of a hypervisor.
+    pub async fn delete<P: Principal + Sync>(
+        &mut self,
+        principal: &P,
+        hypervisor_id: Uuid,
+        id: &str,
+    ) -> Result<(), Error> {
+        self.auth
+            .can(principal)
+            .perform(Permission::ManageImages)
+            .over::<Hypervisor>(&hypervisor_id)
+            .await?;
+        validate_image_id(id)?;
+
+        let hypervisor = Hypervisor::find(&self.db, hypervisor_id).await?;
+        hypervisor
+            .resolve(&self.resolver)?
+            .delete_image(&hypervisor.storage_name, id)
+            .await
+            .map_err(Into::into)
+    }
+}
+
+/// Checks an image id is the volume id of a disk image or an ISO (e.g.
+/// `local:import/debian-12-genericcloud-amd64.qcow2`), so that it cannot
+/// designate other volumes nor other paths of the hypervisor API.
+fn validate_image_id(id: &str) -> Result<(), Error> {
+    let portable = |part: &str| {
+        !part.is_empty()
+            && part
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+    };
+    let valid = id.len() <= 255
+        && id.split_once(':').is_some_and(|(storage, path)| {
+            portable(storage)
+                && path.split_once('/').is_some_and(|(content, name)| {
+                    matches!(content, "import" | "iso")
+                        && portable(name)
+                        && !matches!(name, "." | "..")
+                })
+        });
+
+    if !valid {
+        return Err(Error::InvalidImageId(id.to_owned()));
+    }
+
+    Ok(())
+}
+
+/// Checks an image URL points to a public host over https, every address its
+/// host resolves to included.
+async fn ensure_public_url(url: &str) -> Result<(), Error> {
+    let invalid = |reason: &str| Error::InvalidImageUrl(format!("{url}: {reason}"));
+
+    let parsed = Url::parse(url).map_err(|_| invalid("not a valid URL"))?;
+    if parsed.scheme() != "https" {
+        return Err(invalid("only https URLs are supported"));
+    }
+    let host = parsed.host_str().ok_or_else(|| invalid("no host"))?;
+    let port = parsed.port_or_known_default().unwrap_or(443);
+
+    let addresses = tokio::net::lookup_host((host.trim_matches(['[', ']']), port))
+        .await
+        .map_err(|_| invalid("the host does not resolve"))?
+        .map(|address| address.ip())
+        .collect::<Vec<_>>();
+    if addresses.is_empty() || !addresses.iter().all(is_public) {
+        return Err(invalid("the host is not public"));
+    }
+
+    Ok(())
+}
+
+/// Whether an address is reachable on the internet, as opposed to private,
+/// shared, loopback or link-local ones.
+fn is_public(address: &IpAddr) -> bool {
+    match address {
+        IpAddr::V4(address) => is_public_v4(address),
+        IpAddr::V6(address) => match address.to_ipv4_mapped() {
+            Some(address) => is_public_v4(&address),
+            None => {
+                !(address.is_loopback()
+                    || address.is_unspecified()
+                    || address.is_unique_local()
+                    || address.is_unicast_link_local()
+                    || address.is_multicast())
+            }
+        },
+    }
+}
+
+/// Whether an IPv4 address is reachable on the internet.
+fn is_public_v4(address: &Ipv4Addr) -> bool {
+    // The shared address space (100.64.0.0/10) of carrier-grade NATs, which
+    // also holds the overlay of the zero trust networks.
+    let shared = address.octets()[0] == 100 && (address.octets()[1] & 0xc0) == 64;
+
+    !(address.is_private()
+        || address.is_loopback()
+        || address.is_link_local()
+        || address.is_unspecified()
+        || address.is_broadcast()
+        || address.is_multicast()
+        || address.is_documentation()
+        || shared)
+}
+
+/// Checks a disk image exists on a hypervisor before creating an instance
+/// from it.
+///
+/// Only images referenced by their volume id are checked, names of base
+/// images predating the catalogue are passed through as is. KubeVirt
+/// hypervisors have no catalogue, their images being imported on creation.
+pub(crate) async fn ensure_disk_image(
+    resolver: &Resolver,
+    hypervisor: &Hypervisor,
+    image: &str,
+) -> Result<(), Error> {
+    if hypervisor.kind != HypervisorKind::Proxmox || !image.contains(':') {
+        return Ok(());
+    }
+
+    let exists = hypervisor
+        .resolve(resolver)?
+        .list_images(&hypervisor.storage_name)
+        .await?
+        .iter()
+        .any(|candidate| candidate.id == image && candidate.kind == ImageKind::Disk);
+
+    if exists {
+        Ok(())
+    } else {
+        Err(Error::ImageNotFound(image.to_owned()))
+    }
+}
@@ -5,17 +5,28 @@
 
 use crate::Error;
 use crate::authorization::{Authorize, Permission, Principal, Relation, Relationship, Resource};
-use crate::compute::{Hypervisor, HypervisorFactory, HypervisorIdColumn};
-use crate::resourcemanager::Project;
-use base64::Engine;
+use crate::compute::{
+    BackupPolicy, Hypervisor, HypervisorFactory, HypervisorIdColumn, Overlays, Volume, image,
+    require_admin, scheduler,
+};
+use crate::resourcemanager::{Project, Usage, enforce_quotas, enforce_scoped_quotas};
+use crate::workflow::WorkflowScheduler;
 use chrono::{DateTime, Utc};
 use fabrique::{Delete, Factory, Model, Persist, Query};
+use hypervisor::Resolver;
 use hypervisor::instance::Instances as HypervisorInstancesTrait;
-use hypervisor::instance::Status;
+use hypervisor::instance::{
+    BackupSchedule, Console, ConsoleKind, Metrics, MetricsRequest, Migration, Snapshot, Status,
+};
+use hypervisor::placement::PlacementRequest;
 use sqlx::{Pool, Postgres};
-use ssh_key::{Algorithm, LineEnding, PrivateKey};
+use ssh_key::PublicKey;
 use uuid::Uuid;
 
+mod provisioning;
+
+pub use provisioning::*;
+
 #[derive(Clone, Debug, Default, Factory, Model, Resource)]
 pub struct Instance {
     /// Unique identifier for the instance
@@ -53,6 +64,8 @@ pub struct Instance {
     pub created_at: DateTime<Utc>,
     // Time of the instance last update
     pub updated_at: DateTime<Utc>,
+    // Time the instance went missing from its hypervisor, if it did
+    pub missing_since: Option<DateTime<Utc>>,
 }
 
 #[derive(Clone, Debug)]
@@ -66,7 +79,8 @@ pub struct InstanceCreateRequest {
     /// The disk size in bytes.
     pub disk_size: u64,
 
-    /// The disk image to create the instance from.
+    /// The disk image to create the instance from, either the volume id of an
+    /// image of the catalogue or the name of a base image.
     pub disk_image: String,
 
     /// Memory properties.
@@ -77,6 +91,13 @@ pub struct InstanceCreateRequest {
 
     /// The Cloud-Init snippet.
     pub snippet: String,
+
+    /// The zone to deploy the instance in, any zone when unset.
+    pub zone_id: Option<Uuid>,
+
+    /// The zero trust network of its organization to attach the instance
+    /// to, none when unset.
+    pub zero_trust_network_id: Option<Uuid>,
 }
 
 #[derive(Clone, Debug)]
@@ -89,143 +110,297 @@ pub struct InstanceUpdateRequest {
 
     /// The optional new project to move the instance to.
     pub project_slug: Option<String>,
+
+    /// The optional new number of cores.
+    pub cores: Option<u8>,
+
+    /// The optional new memory size in bytes.
+    pub memory_bytes: Option<u64>,
 }
 
-/// Service for managing compute instances.
 #[derive(Clone, Debug)]
+pub struct InstanceSnapshotCreateRequest {
+    /// The instance identifier.
+    pub id: Uuid,
+
+    /// The snapshot name.
+    pub name: String,
+
+    /// An optional description of the snapshot.
+    pub description: Option<String>,
+
+    /// Whether to include the instance memory state.
+    pub include_memory: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct InstanceMetricsRequest {
+    /// The instance identifier.
+    pub id: Uuid,
+
+    /// The start of the window.
+    pub start: DateTime<Utc>,
+
+    /// The end of the window.
+    pub end: DateTime<Utc>,
+
+    /// The requested time between two samples in seconds, the finest
+    /// available when 0.
+    pub resolution_seconds: u64,
+}
+
+/// Password length bounds accepted by the guest agent.
+const MIN_PASSWORD_LENGTH: usize = 5;
+const MAX_PASSWORD_LENGTH: usize = 1024;
+
+/// Checks a username is a portable user name of the guest, which cannot be
+/// mistaken for an option of the commands it is passed to.
+fn validate_username(username: &str) -> Result<(), Error> {
+    let portable = username
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+
+    if username.is_empty() || username.len() > 32 || username.starts_with('-') || !portable {
+        return Err(Error::InvalidGuestCredentials(format!(
+            "{:?} is not a valid username",
+            username
+        )));
+    }
+
+    Ok(())
+}
+
+/// Snapshot name length bounds accepted by the hypervisor.
+const MIN_SNAPSHOT_NAME_LENGTH: usize = 2;
+const MAX_SNAPSHOT_NAME_LENGTH: usize = 40;
+
+/// Checks a snapshot name starts with a letter, and only holds alphanumerics,
+/// underscores and hyphens, so that it is a single segment of the paths of
+/// the hypervisor API.
+fn validate_snapshot_name(name: &str) -> Result<(), Error> {
+    let mut chars = name.chars();
+    let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-'))
+        && (MIN_SNAPSHOT_NAME_LENGTH..=MAX_SNAPSHOT_NAME_LENGTH).contains(&name.len());
+
+    if !valid {
+        return Err(Error::InvalidSnapshotName(name.to_owned()));
+    }
+
+    Ok(())
+}
+
+/// The granularity of the memory of the instances, which hypervisors size in
+/// mebibytes.
+const MEMORY_GRANULARITY_BYTES: u64 = 1024 * 1024;
+
+/// Checks the new shape of a resized instance is applied as is by its
+/// hypervisor: at least a core, and a memory size in whole mebibytes.
+fn validate_shape(cores: Option<u8>, memory_bytes: Option<u64>) -> Result<(), Error> {
+    if cores == Some(0) {
+        return Err(Error::InvalidInstanceShape(
+            "an instance needs at least one core".to_owned(),
+        ));
+    }
+
+    if let Some(memory_bytes) = memory_bytes
+        && (memory_bytes == 0 || memory_bytes % MEMORY_GRANULARITY_BYTES != 0)
+    {
+        return Err(Error::InvalidInstanceShape(format!(
+            "memory of {} bytes is not a positive number of mebibytes",
+            memory_bytes
+        )));
+    }
+
+    Ok(())
+}
+
+/// A migration of an instance between the nodes of its hypervisor, or to
+/// another hypervisor.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InstanceMigration {
+    /// The migrated instance, unset when the hypervisor holds it unrecorded
+    pub instance_id: Option<Uuid>,
+    /// The hypervisor holding the instance once migrated
+    pub hypervisor_id: Uuid,
+    /// ID used by the hypervisor to identify the instance remotely
+    pub distant_id: String,
+    /// The node the instance was migrated from
+    pub source_node: String,
+    /// The node the instance was migrated to
+    pub target_node: String,
+}
+
+impl InstanceMigration {
+    /// Matches a migration of the hypervisor with the recorded instances.
+    pub(crate) fn new(migration: Migration, hypervisor_id: Uuid, instances: &[Instance]) -> Self {
+        Self {
+            instance_id: instances
+                .iter()
+                .find(|instance| instance.distant_id == migration.id)
+                .map(|instance| instance.id),
+            hypervisor_id,
+            distant_id: migration.id,
+            source_node: migration.source_node,
+            target_node: migration.target_node,
+        }
+    }
+}
+
+/// Service for managing compute instances.
+#[derive(Clone)]
 pub struct Instances<A: Authorize> {
     auth: A,
     db: Pool<Postgres>,
+    resolver: Resolver,
 }
 
 impl<A: Authorize> Instances<A> {
     /// Creates a new instances service.
-    pub fn new(auth: A, db: Pool<Postgres>) -> Self {
-        Self { auth, db }
+    pub fn new(auth: A, db: Pool<Postgres>, resolver: Resolver) -> Self {
+        Self { auth, db, resolver }
     }
 
     /// Lists all instances accessible to the principal.
+    ///
+    /// Platform admins list the whole fleet, other principals the instances
+    /// of the projects they have access to.
     pub async fn list<P: Principal + Sync>(
         &mut self,
-        _principal: &P,
+        principal: &P,
     ) -> Result<Vec<Instance>, Error> {
-        // self.auth
-        //     .can(principal)
-        //     .perform(Permission::List)
-        //     .over(&Instance::any())
-        //     .check()
-        //     .await?;
-
-        tracing::info!("before fetching instances...");
+        if principal.is_platform_admin() {
+            return Instance::all(&self.db).await.map_err(Into::into);
+        }
 
-        Instance::all(&self.db).await.map_err(Into::into)
+        self.auth
+            .lookup::<Instance>()
+            .on_behalf_of(principal)
+            .with(Permission::List)
+            .against(&self.db)
+            .await
     }
 
-    /// Creates a new instance.
-    pub async fn create<P: Principal + Sync>(
+    /// Accepts the creation of a new instance.
+    ///
+    /// The instance is recorded with a provisioning status, and created on
+    /// its hypervisor by the workflow scheduled through `scheduler`.
+    pub async fn create<
+        P: Principal<Id = Uuid> + Sync,
+        S: WorkflowScheduler<ProvisionInstanceParams>,
+    >(
         &mut self,
         principal: &P,
+        scheduler: &S,
         request: InstanceCreateRequest,
-    ) -> Result<Instance, Error> {
+    ) -> Result<InstanceProvisioning, Error> {
         self.auth
             .can(principal)
             .perform(Permission::CreateInstance)
             .over::<Project>(&request.project_slug)
             .await?;
 
-        // Select a hypervisor to deploy the instance on.
-        let hypervisors = Hypervisor::all(&self.db).await?;
-        let hypervisor = hypervisors
-            .first()
-            .ok_or_else(|| Error::NoHypervisorsAvailable)?;
-
-        // Create the instance.
-        let api = hypervisor::resolve(
-            hypervisor.url.clone(),
-            hypervisor.authorization_token.clone(),
-        );
-
-        let next_id = ::hypervisor::proxmox::api::cluster_next_id(
-            &hypervisor.url,
-            &reqwest::Client::new(),
-            &hypervisor.authorization_token,
-        )
-        .await
-        .map_err(|_| Error::Other("could not get next id".to_owned()))?
-        .data
-        .to_string();
-
-        tracing::info!("next id is: {}", &next_id);
-
-        // Setup Hoop SSH bastion access
-        let snippet = self
-            .setup_hoop_access(&request.name, request.snippet)
+        if let Some(zero_trust_network_id) = request.zero_trust_network_id {
+            // Raw SQL: zero trust networks are not modeled in this crate.
+            // Networks of other organizations are reported as not found.
+            let found = sqlx::query_scalar::<_, bool>(
+                r#"SELECT EXISTS (
+                       SELECT 1
+                       FROM zero_trust_networks n
+                       JOIN projects p ON p.organization_slug = n.organization_slug
+                       WHERE n.id = $1 AND p.slug = $2
+                   )"#,
+            )
+            .bind(zero_trust_network_id)
+            .bind(&request.project_slug)
+            .fetch_one(&self.db)
             .await?;
+            if !found {
+                return Err(Error::ZeroTrustNetworkNotFound(zero_trust_network_id));
+            }
+        }
 
-        let instance_id = api
-            .create(hypervisor::instance::InstanceCreateRequest {
-                id: next_id.clone(),
-                cores: request.cores,
-                disk_bytes: request.disk_size,
-                disk_image: request.disk_image,
+        // Select a hypervisor to deploy the instance on.
+        let hypervisor = scheduler::schedule(
+            &self.db,
+            &self.resolver,
+            request.zone_id,
+            &PlacementRequest {
+                cores: request.cores as u32,
                 memory_bytes: request.memory,
-                name: request.name.clone(),
-                snippet,
-            })
-            .await?;
+                disk_bytes: request.disk_size,
+            },
+        )
+        .await?;
 
-        let maybe_instance = Instance::query()
-            .select()
-            .r#where(Instance::DISTANT_ID, "=", next_id.clone())
-            .r#where(Instance::HYPERVISOR_ID, "=", hypervisor.id)
-            .first(&self.db)
-            .await?;
+        // Check the disk image is available on the selected hypervisor.
+        image::ensure_disk_image(&self.resolver, &hypervisor, &request.disk_image).await?;
+
+        // Check the quotas in the transaction recording the instance, which
+        // holds the lock of the organization until the instance is recorded.
+        // The hypervisor is selected beforehand to keep this lock short.
+        let mut tx = self.db.begin().await?;
+        enforce_quotas(
+            &mut tx,
+            &request.project_slug,
+            &Usage {
+                cpu_cores: request.cores.into(),
+                memory_bytes: request.memory as i64,
+                disk_bytes: request.disk_size as i64,
+                instances: 1,
+                ..Default::default()
+            },
+        )
+        .await?;
 
-        let instance = match maybe_instance {
-            None => {
-                // Save the created instance in database
-                Instance {
-                    id: instance_id,
+        // Record the instance, until the workflow provisions it.
+        let instance = Instance {
+            id: Uuid::new_v4(),
+            hypervisor_id: hypervisor.id,
+            project_slug: request.project_slug.clone(),
+            zero_trust_network_id: request.zero_trust_network_id,
+            distant_id: String::new(),
+            cpu_usage_percent: 0.0,
+            disk_usage_bytes: 0,
+            ip_v4: String::new(),
+            max_cpu_cores: request.cores as i32,
+            max_disk_bytes: request.disk_size as i64,
+            max_memory_bytes: request.memory as i64,
+            memory_usage_bytes: 0,
+            name: request.name.clone(),
+            status: Status::Provisioning,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+            missing_since: None,
+        }
+        .create(&mut *tx)
+        .await?;
+
+        let execution_id = scheduler
+            .schedule(
+                &mut tx,
+                ProvisionInstanceParams {
+                    instance_id: instance.id,
                     hypervisor_id: hypervisor.id,
-                    project_slug: request.project_slug.clone(),
-                    zero_trust_network_id: None,
-                    distant_id: next_id,
-                    cpu_usage_percent: 0.0,
-                    disk_usage_bytes: 0,
-                    ip_v4: String::new(),
-                    max_cpu_cores: request.cores as i32,
-                    max_disk_bytes: request.disk_size as i64,
-                    max_memory_bytes: request.memory as i64,
-                    memory_usage_bytes: 0,
+                    project_slug: request.project_slug,
                     name: request.name,
-                    status: Status::default(),
-                    created_at: chrono::Utc::now(),
-                    updated_at: chrono::Utc::now(),
-                }
-                .create(&self.db)
-                .await?
-            }
-            Some(instance) => {
-                Instance::update()
-                    .set(Instance::PROJECT_SLUG, request.project_slug.clone())
-                    .r#where(Instance::ID, "=", instance.id)
-                    .execute(&self.db)
-                    .await?;
-
-                instance
-            }
-        };
-
-        // Write the relationship synchronously to SpiceDB
-        self.auth
-            .write_relationship(&Relationship::new(
-                &Project::some(request.project_slug.clone()),
-                Relation::Parent,
-                &instance,
-            ))
-            .await?;
+                    cores: request.cores,
+                    disk_bytes: request.disk_size,
+                    disk_image: request.disk_image,
+                    memory_bytes: request.memory,
+                    snippet: request.snippet,
+                    zero_trust_network_id: request.zero_trust_network_id,
+                    principal_id: *principal.id(),
+                },
+            )
+            .await
+            .map_err(Error::Other)?;
+        tx.commit().await?;
 
-        Ok(instance)
+        Ok(InstanceProvisioning {
+            instance,
+            execution_id,
+        })
     }
 
     /// Deletes an instance.
@@ -242,152 +417,163 @@ impl<A: Authorize> Instances<A> {
 
         let instance = Instance::find(&self.db, id).await?;
         let hypervisor = Hypervisor::find(&self.db, instance.hypervisor_id).await?;
-        let connector = hypervisor::resolve(hypervisor.url, hypervisor.authorization_token);
+        let connector = hypervisor.resolve(&self.resolver)?;
 
         // Cleanup Hoop SSH bastion access (best effort)
         self.cleanup_hoop_access(&instance.name).await;
 
-        connector.delete(&instance.distant_id).await?;
+        // An instance still provisioning has no distant counterpart yet, its
+        // workflow rolls back once it finds the instance gone.
+        if !instance.distant_id.is_empty() {
+            connector.delete(&instance.distant_id).await?;
+        }
 
         Instance::destroy(&self.db, instance.id).await?;
+        self.auth
+            .delete_relationship(&Relationship::new(
+                &Project::some(instance.project_slug.clone()),
+                Relation::Parent,
+                &instance,
+            ))
+            .await?;
+
+        // The peer of the instance went along with it, the remaining peers of
+        // its overlay forget it (best effort).
+        if let Some(zero_trust_network_id) = instance.zero_trust_network_id {
+            let overlays = Overlays::new(self.db.clone(), self.resolver.clone());
+            if let Err(err) = overlays.push(zero_trust_network_id).await {
+                tracing::warn!(%zero_trust_network_id, error = %err, "could not update the overlay");
+            }
+        }
 
         Ok(())
     }
 
-    /// Sets up Hoop SSH bastion access for a new instance.
-    ///
-    /// Generates an SSH keypair, creates a Hoop agent and connection,
-    /// and injects the credentials into the cloud-init snippet.
-    async fn setup_hoop_access(
-        &self,
-        instance_name: &str,
-        snippet: String,
-    ) -> Result<String, Error> {
-        let hoop_api_url = match std::env::var("HOOP_API_URL") {
-            Ok(url) => url,
-            Err(_) => {
-                tracing::warn!("HOOP_API_URL not set, skipping Hoop setup");
-                return Ok(snippet);
-            }
-        };
+    /// Starts a stopped instance.
+    pub async fn start<P: Principal + Sync>(
+        &mut self,
+        principal: &P,
+        id: Uuid,
+    ) -> Result<(), Error> {
+        self.auth
+            .can(principal)
+            .perform(Permission::Start)
+            .over::<Instance>(&id)
+            .await?;
 
-        let hoop_api_key = match std::env::var("HOOP_API_KEY") {
-            Ok(key) => key,
-            Err(_) => {
-                tracing::warn!("HOOP_API_KEY not set, skipping Hoop setup");
-                return Ok(snippet);
-            }
-        };
+        let instance = Instance::find(&self.db, id).await?;
+        let hypervisor = Hypervisor::find(&self.db, instance.hypervisor_id).await?;
+        let connector = hypervisor.resolve(&self.resolver)?;
 
-        // Generate SSH keypair
-        let private_key = PrivateKey::random(&mut rand::thread_rng(), Algorithm::Ed25519)
-            .map_err(|e| Error::Other(format!("Failed to generate SSH key: {}", e)))?;
-        let public_key = private_key
-            .public_key()
-            .to_openssh()
-            .map_err(|e| Error::Other(format!("Failed to format public key: {}", e)))?;
-        let private_key_pem = private_key
-            .to_openssh(LineEnding::LF)
-            .map_err(|e| Error::Other(format!("Failed to format private key: {}", e)))?;
-        let private_key_base64 =
-            base64::engine::general_purpose::STANDARD.encode(private_key_pem.as_bytes());
-
-        let client = reqwest::Client::new();
-
-        // Create Hoop agent
-        let agent_token =
-            hoop::api::create_agent(&hoop_api_url, &client, &hoop_api_key, instance_name)
-                .await
-                .map_err(|e| Error::Other(format!("Failed to create Hoop agent: {}", e)))?;
+        connector.start(&instance.distant_id).await?;
 
-        // Get agent UUID (required for creating connection)
-        let agent = hoop::api::get_agent(&hoop_api_url, &client, &hoop_api_key, instance_name)
-            .await
-            .map_err(|e| Error::Other(format!("Failed to get Hoop agent: {}", e)))?;
-
-        // Create Hoop connection with SSH credentials
-        hoop::api::create_connection(
-            &hoop_api_url,
-            &client,
-            &hoop_api_key,
-            instance_name,
-            &agent.id,
-            "francenuage",
-            &private_key_base64,
-        )
-        .await
-        .map_err(|e| Error::Other(format!("Failed to create Hoop connection: {}", e)))?;
+        Instance::update()
+            .set(Instance::STATUS, Status::Running.to_string())
+            .r#where(Instance::ID, "=", instance.id)
+            .execute(&self.db)
+            .await?;
 
-        // Inject credentials into snippet
-        let snippet = snippet
-            .replace("${HOOP_AGENT_TOKEN}", &agent_token)
-            .replace("${HOOP_SSH_PUBLIC_KEY}", &public_key);
+        Ok(())
+    }
 
-        tracing::info!(
-            "Hoop SSH bastion access configured for instance {}",
-            instance_name
-        );
+    /// Stops a running instance.
+    pub async fn stop<P: Principal + Sync>(
+        &mut self,
+        principal: &P,
+        id: Uuid,
+    ) -> Result<(), Error> {
+        self.auth
+            .can(principal)
+            .perform(Permission::Stop)
+            .over::<Instance>(&id)
+            .await?;
 
-        Ok(snippet)
+        let instance = Instance::find(&self.db, id).await?;
+        let hypervisor = Hypervisor::find(&self.db, instance.hypervisor_id).await?;
+        let connector = hypervisor.resolve(&self.resolver)?;
+
+        connector.stop(&instance.distant_id).await?;
+
+        Instance::update()
+            .set(Instance::STATUS, Status::Stopped.to_string())
+            .r#where(Instance::ID, "=", instance.id)
+            .execute(&self.db)
+            .await?;
+
+        Ok(())
     }
 
-    /// Cleans up Hoop SSH bastion access for an instance.
-    ///
-    /// Best effort - errors are logged but don't fail the deletion.
-    async fn cleanup_hoop_access(&self, instance_name: &str) {
-        let hoop_api_url = match std::env::var("HOOP_API_URL") {
-            Ok(url) => url,
-            Err(_) => return,
-        };
+    /// Shuts a running instance down gracefully.
+    pub async fn shutdown<P: Principal + Sync>(
+        &mut self,
+        principal: &P,
+        id: Uuid,
+    ) -> Result<(), Error> {
+        self.auth
+            .can(principal)
+            .perform(Permission::Shutdown)
+            .over::<Instance>(&id)
+            .await?;
 
-        let hoop_api_key = match std::env::var("HOOP_API_KEY") {
-            Ok(key) => key,
-            Err(_) => return,
-        };
+        let instance = Instance::find(&self.db, id).await?;
+        let hypervisor = Hypervisor::find(&self.db, instance.hypervisor_id).await?;
+        let connector = hypervisor.resolve(&self.resolver)?;
 
-        let client = reqwest::Client::new();
+        connector.shutdown(&instance.distant_id).await?;
 
-        // Delete connection first
-        if let Err(e) =
-            hoop::api::delete_connection(&hoop_api_url, &client, &hoop_api_key, instance_name).await
-        {
-            tracing::warn!(
-                "Failed to delete Hoop connection for {}: {}",
-                instance_name,
-                e
-            );
-        }
+        Instance::update()
+            .set(Instance::STATUS, Status::Stopped.to_string())
+            .r#where(Instance::ID, "=", instance.id)
+            .execute(&self.db)
+            .await?;
 
-        // Delete agent
-        if let Err(e) =
-            hoop::api::delete_agent(&hoop_api_url, &client, &hoop_api_key, instance_name).await
-        {
-            tracing::warn!("Failed to delete Hoop agent for {}: {}", instance_name, e);
-        }
+        Ok(())
+    }
+
+    /// Reboots a running instance gracefully.
+    pub async fn reboot<P: Principal + Sync>(
+        &mut self,
+        principal: &P,
+        id: Uuid,
+    ) -> Result<(), Error> {
+        self.auth
+            .can(principal)
+            .perform(Permission::Reboot)
+            .over::<Instance>(&id)
+            .await?;
+
+        let instance = Instance::find(&self.db, id).await?;
+        let hypervisor = Hypervisor::find(&self.db, instance.hypervisor_id).await?;
+        let connector = hypervisor.resolve(&self.resolver)?;
+
+        connector.reboot(&instance.distant_id).await?;
+
+        Instance::update()
+            .set(Instance::STATUS, Status::Running.to_string())
+            .r#where(Instance::ID, "=", instance.id)
+            .execute(&self.db)
+            .await?;
 
-        tracing::info!(
-            "Hoop SSH bastion access cleaned up for instance {}",
-            instance_name
-        );
+        Ok(())
     }
 
-    /// Starts a stopped instance.
-    pub async fn start<P: Principal + Sync>(
+    /// Resets a running instance, without notifying its guest.
+    pub async fn reset<P: Principal + Sync>(
         &mut self,
         principal: &P,
         id: Uuid,
     ) -> Result<(), Error> {
         self.auth
             .can(principal)
-            .perform(Permission::Start)
+            .perform(Permission::Reset)
             .over::<Instance>(&id)
             .await?;
 
         let instance = Instance::find(&self.db, id).await?;
         let hypervisor = Hypervisor::find(&self.db, instance.hypervisor_id).await?;
-        let connector = hypervisor::resolve(hypervisor.url, hypervisor.authorization_token);
+        let connector = hypervisor.resolve(&self.resolver)?;
 
-        connector.start(&instance.distant_id).await?;
+        connector.reset(&instance.distant_id).await?;
 
         Instance::update()
             .set(Instance::STATUS, Status::Running.to_string())
@@ -398,26 +584,33 @@ impl<A: Authorize> Instances<A> {
         Ok(())
     }
 
-    /// Stops a running instance.
-    pub async fn stop<P: Principal + Sync>(
+    /// Suspends a running instance, in memory or to disk.
+    pub async fn suspend<P: Principal + Sync>(
         &mut self,
         principal: &P,
         id: Uuid,
+        to_disk: bool,
     ) -> Result<(), Error> {
         self.auth
             .can(principal)
-            .perform(Permission::Stop)
+            .perform(Permission::Suspend)
             .over::<Instance>(&id)
             .await?;
 
         let instance = Instance::find(&self.db, id).await?;
         let hypervisor = Hypervisor::find(&self.db, instance.hypervisor_id).await?;
-        let connector = hypervisor::resolve(hypervisor.url, hypervisor.authorization_token);
+        let connector = hypervisor.resolve(&self.resolver)?;
 
-        connector.stop(&instance.distant_id).await?;
+        connector.suspend(&instance.distant_id, to_disk).await?;
+
+        let status = if to_disk {
+            Status::Suspended
+        } else {
+            Status::Paused
+        };
 
         Instance::update()
-            .set(Instance::STATUS, Status::Stopped.to_string())
+            .set(Instance::STATUS, status.to_string())
             .r#where(Instance::ID, "=", instance.id)
             .execute(&self.db)
             .await?;
@@ -425,6 +618,124 @@ impl<A: Authorize> Instances<A> {
         Ok(())
     }
 
+    /// Resumes a paused or suspended instance.
+    pub async fn resume<P: Principal + Sync>(
+        &mut self,
+        principal: &P,
+        id: Uuid,
+    ) -> Result<(), Error> {
+        self.auth
+            .can(principal)
+            .perform(Permission::Resume)
+            .over::<Instance>(&id)
+            .await?;
+
+        let instance = Instance::find(&self.db, id).await?;
+        let hypervisor = Hypervisor::find(&self.db, instance.hypervisor_id).await?;
+        let connector = hypervisor.resolve(&self.resolver)?;
+
+        connector.resume(&instance.distant_id).await?;
+
+        Instance::update()
+            .set(Instance::STATUS, Status::Running.to_string())
+            .r#where(Instance::ID, "=", instance.id)
+            .execute(&self.db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Opens a console on an instance.
+    ///
+    /// The returned console holds the credentials of a short-lived session,
+    /// it must only be handed over to the principal it was opened for.
+    pub async fn console<P: Principal + Sync>(
+        &mut self,
+        principal: &P,
+        id: Uuid,
+        kind: ConsoleKind,
+    ) -> Result<Console, Error> {
+        self.auth
+            .can(principal)
+            .perform(Permission::Console)
+            .over::<Instance>(&id)
+            .await?;
+
+        let instance = Instance::find(&self.db, id).await?;
+        let hypervisor = Hypervisor::find(&self.db, instance.hypervisor_id).await?;
+        let connector = hypervisor.resolve(&self.resolver)?;
+
+        Ok(connector.console(&instance.distant_id, kind).await?)
+    }
+
+    /// Resets the password of a user of an instance, through its guest agent.
+    ///
+    /// Lets users locked out of an instance log back in without reinstalling
+    /// it. The instance must be running its guest agent.
+    pub async fn reset_password<P: Principal + Sync>(
+        &mut self,
+        principal: &P,
+        id: Uuid,
+        username: &str,
+        password: &str,
+    ) -> Result<(), Error> {
+        self.auth
+            .can(principal)
+            .perform(Permission::ResetPassword)
+            .over::<Instance>(&id)
+            .await?;
+
+        validate_username(username)?;
+        if !(MIN_PASSWORD_LENGTH..=MAX_PASSWORD_LENGTH).contains(&password.chars().count()) {
+            return Err(Error::InvalidGuestCredentials(format!(
+                "the password must be {} to {} characters long",
+                MIN_PASSWORD_LENGTH, MAX_PASSWORD_LENGTH
+            )));
+        }
+
+        let instance = Instance::find(&self.db, id).await?;
+        let hypervisor = Hypervisor::find(&self.db, instance.hypervisor_id).await?;
+        let connector = hypervisor.resolve(&self.resolver)?;
+
+        Ok(connector
+            .set_user_password(&instance.distant_id, username, password)
+            .await?)
+    }
+
+    /// Authorizes an SSH public key to log in as a user of an instance,
+    /// through its guest agent.
+    ///
+    /// The keys already authorized for the user are kept. The instance must
+    /// be running its guest agent.
+    pub async fn inject_ssh_key<P: Principal + Sync>(
+        &mut self,
+        principal: &P,
+        id: Uuid,
+        username: &str,
+        public_key: &str,
+    ) -> Result<(), Error> {
+        self.auth
+            .can(principal)
+            .perform(Permission::InjectSshKey)
+            .over::<Instance>(&id)
+            .await?;
+
+        validate_username(username)?;
+        let public_key = PublicKey::from_openssh(public_key)
+            .and_then(|key| key.to_openssh())
+            .map_err(|e| {
+                Error::InvalidGuestCredentials(format!("malformed SSH public key: {}", e))
+            })?;
+
+        let instance = Instance::find(&self.db, id).await?;
+        let hypervisor = Hypervisor::find(&self.db, instance.hypervisor_id).await?;
+        let connector = hypervisor.resolve(&self.resolver)?;
+
+        Ok(connector
+            .authorize_ssh_key(&instance.distant_id, username, &public_key)
+            .await?)
+    }
+
     /// Clones an existing instance.
     pub async fn clone_instance<P: Principal + Sync>(
         &mut self,
@@ -440,19 +751,24 @@ impl<A: Authorize> Instances<A> {
 
         let existing = Instance::find(&self.db, id).await?;
         let hypervisor = Hypervisor::find(&self.db, existing.hypervisor_id).await?;
-        let connector = hypervisor::resolve(hypervisor.url, hypervisor.authorization_token);
-
-        let new_id =
-            hypervisor::instance::Instances::clone(&connector, &existing.distant_id).await?;
+        let connector = hypervisor.resolve(&self.resolver)?;
+        let source_id = existing.distant_id.clone();
 
+        // The clone has the shape of the instance, checked against the quotas
+        // before the hypervisor clones it.
         let instance = Instance {
             id: Uuid::new_v4(),
-            distant_id: new_id,
-            name: name.unwrap_or(existing.name),
+            name: name.unwrap_or(existing.name.clone()),
+            // The clone joins no zero trust network until attached to one,
+            // and is not missing from its hypervisor.
+            zero_trust_network_id: None,
+            missing_since: None,
             ..existing
-        };
-
-        let instance = instance.create(&self.db).await?;
+        }
+        .create_within_quotas(&self.db, async {
+            Ok(hypervisor::instance::Instances::clone(&connector, &source_id).await?)
+        })
+        .await?;
 
         self.auth
             .write_relationship(&Relationship::new(
@@ -487,8 +803,90 @@ impl<A: Authorize> Instances<A> {
                 .await?;
         }
 
+        validate_shape(request.cores, request.memory_bytes)?;
+
         let instance = Instance::find(&self.db, request.id).await?;
-        let old_project_slug = instance.project_slug;
+        let old_project_slug = instance.project_slug.clone();
+        let moved = request
+            .project_slug
+            .as_ref()
+            .filter(|new_project_slug| **new_project_slug != old_project_slug);
+        let resized = request.cores.is_some() || request.memory_bytes.is_some();
+
+        let max_cpu_cores = request
+            .cores
+            .map_or(instance.max_cpu_cores, |cores| cores as i32);
+        let max_memory_bytes = request
+            .memory_bytes
+            .map_or(instance.max_memory_bytes, |bytes| bytes as i64);
+        let growth = Usage {
+            cpu_cores: (max_cpu_cores - instance.max_cpu_cores).into(),
+            memory_bytes: max_memory_bytes - instance.max_memory_bytes,
+            ..Default::default()
+        };
+
+        // Check the quotas in the transaction recording the new shape and
+        // project, which holds the lock of the organization until then.
+        let mut tx = self.db.begin().await?;
+        match moved {
+            Some(new_project_slug) => {
+                // The instance brings its whole shape and its volumes to its
+                // new project, and only its growth to its organization unless
+                // it leaves it.
+                let volumes_bytes: i64 = Volume::query()
+                    .select()
+                    .r#where(Volume::INSTANCE_ID, "=", instance.id)
+                    .get(&mut *tx)
+                    .await?
+                    .iter()
+                    .map(|volume| volume.size_bytes)
+                    .sum();
+                let shape = Usage {
+                    cpu_cores: max_cpu_cores.into(),
+                    memory_bytes: max_memory_bytes,
+                    disk_bytes: instance.max_disk_bytes + volumes_bytes,
+                    instances: 1,
+                    ..Default::default()
+                };
+                let old_project = Project::find(&mut *tx, old_project_slug.clone()).await?;
+                let new_project = Project::find(&mut *tx, new_project_slug.clone()).await?;
+                let organization_demand =
+                    if old_project.organization_slug == new_project.organization_slug {
+                        &growth
+                    } else {
+                        &shape
+                    };
+
+                enforce_scoped_quotas(&mut tx, new_project_slug, organization_demand, &shape)
+                    .await?;
+            }
+            None if resized => enforce_quotas(&mut tx, &old_project_slug, &growth).await?,
+            None => {}
+        }
+
+        // Resize the instance on its hypervisor, and record its new shape
+        // right away so it does not wait for the next synchronization.
+        if resized {
+            let hypervisor = Hypervisor::find(&self.db, instance.hypervisor_id).await?;
+            let connector = hypervisor.resolve(&self.resolver)?;
+
+            connector
+                .resize(
+                    &instance.distant_id,
+                    hypervisor::instance::InstanceResizeRequest {
+                        cores: request.cores,
+                        memory_bytes: request.memory_bytes,
+                    },
+                )
+                .await?;
+
+            Instance::update()
+                .set(Instance::MAX_CPU_CORES, max_cpu_cores)
+                .set(Instance::MAX_MEMORY_BYTES, max_memory_bytes)
+                .r#where(Instance::ID, "=", instance.id)
+                .execute(&mut *tx)
+                .await?;
+        }
 
         // Build the update query dynamically based on provided fields
         let updated_instance = sqlx::query_as!(
@@ -506,33 +904,339 @@ impl<A: Authorize> Instances<A> {
             request.name,
             request.project_slug,
         )
-        .fetch_one(&self.db)
+        .fetch_one(&mut *tx)
         .await?;
 
+        // The volumes of the instance move along with it.
+        let volumes = match moved {
+            Some(new_project_slug) => {
+                Volume::update()
+                    .set(Volume::PROJECT_SLUG, new_project_slug.clone())
+                    .r#where(Volume::INSTANCE_ID, "=", instance.id)
+                    .execute(&mut *tx)
+                    .await?;
+
+                Volume::query()
+                    .select()
+                    .r#where(Volume::INSTANCE_ID, "=", instance.id)
+                    .get(&mut *tx)
+                    .await?
+            }
+            None => Vec::new(),
+        };
+        tx.commit().await?;
+
         // If the project changed, update the authorization relationships
-        if let Some(new_project_slug) = request.project_slug
-            && new_project_slug != old_project_slug
-        {
+        if let Some(new_project_slug) = moved {
+            let old_project = Project::some(old_project_slug.clone());
+            let new_project = Project::some(new_project_slug.clone());
+
             // Remove old project relationship from SpiceDB
-            let old_relationship = Relationship::new(
-                &Project::some(old_project_slug.clone()),
-                Relation::Parent,
-                &updated_instance,
-            );
-            self.auth.delete_relationship(&old_relationship).await?;
+            self.auth
+                .delete_relationship(&Relationship::new(
+                    &old_project,
+                    Relation::Parent,
+                    &updated_instance,
+                ))
+                .await?;
 
             // Add new project relationship synchronously to SpiceDB
             self.auth
                 .write_relationship(&Relationship::new(
-                    &Project::some(new_project_slug.clone()),
+                    &new_project,
                     Relation::Parent,
                     &updated_instance,
                 ))
                 .await?;
+
+            for volume in &volumes {
+                self.auth
+                    .delete_relationship(&Relationship::new(&old_project, Relation::Parent, volume))
+                    .await?;
+                self.auth
+                    .write_relationship(&Relationship::new(&new_project, Relation::Parent, volume))
+                    .await?;
+            }
         }
 
         Ok(updated_instance)
     }
+
+    /// Takes a snapshot of an instance.
+    pub async fn create_snapshot<P: Principal + Sync>(
+        &mut self,
+        principal: &P,
+        request: InstanceSnapshotCreateRequest,
+    ) -> Result<Snapshot, Error> {
+        self.auth
+            .can(principal)
+            .perform(Permission::CreateSnapshot)
+            .over::<Instance>(&request.id)
+            .await?;
+
+        validate_snapshot_name(&request.name)?;
+
+        let instance = Instance::find(&self.db, request.id).await?;
+        let hypervisor = Hypervisor::find(&self.db, instance.hypervisor_id).await?;
+        let connector = hypervisor.resolve(&self.resolver)?;
+
+        connector
+            .create_snapshot(
+                &instance.distant_id,
+                hypervisor::instance::SnapshotCreateRequest {
+                    name: request.name.clone(),
+                    description: request.description,
+                    include_memory: request.include_memory,
+                },
+            )
+            .await?;
+
+        connector
+            .list_snapshots(&instance.distant_id)
+            .await?
+            .into_iter()
+            .find(|snapshot| snapshot.name == request.name)
+            .ok_or_else(|| Error::SnapshotNotFound(request.name))
+    }
+
+    /// Lists the snapshots of an instance.
+    pub async fn list_snapshots<P: Principal + Sync>(
+        &mut self,
+        principal: &P,
+        id: Uuid,
+    ) -> Result<Vec<Snapshot>, Error> {
+        self.auth
+            .can(principal)
+            .perform(Permission::ListSnapshots)
+            .over::<Instance>(&id)
+            .await?;
+
+        let instance = Instance::find(&self.db, id).await?;
+        let hypervisor = Hypervisor::find(&self.db, instance.hypervisor_id).await?;
+        let connector = hypervisor.resolve(&self.resolver)?;
+
+        connector
+            .list_snapshots(&instance.distant_id)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Gets the CPU, memory, disk and network usage history of an instance.
+    ///
+    /// The history is read from the hypervisor, which keeps it at a coarser
+    /// resolution as it ages.
+    pub async fn metrics<P: Principal + Sync>(
+        &mut self,
+        principal: &P,
+        request: InstanceMetricsRequest,
+    ) -> Result<Metrics, Error> {
+        self.auth
+            .can(principal)
+            .perform(Permission::Get)
+            .over::<Instance>(&request.id)
+            .await?;
+
+        if request.start >= request.end {
+            return Err(Error::InvalidMetricsWindow {
+                start: request.start,
+                end: request.end,
+            });
+        }
+
+        let instance = Instance::find(&self.db, request.id).await?;
+        let hypervisor = Hypervisor::find(&self.db, instance.hypervisor_id).await?;
+        let connector = hypervisor.resolve(&self.resolver)?;
+
+        let request = MetricsRequest {
+            start: request.start.timestamp().max(0) as u64,
+            end: request.end.timestamp().max(0) as u64,
+            resolution_seconds: request.resolution_seconds,
+        };
+
+        connector
+            .metrics(&instance.distant_id, &request)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Rolls an instance back to one of its snapshots.
+    ///
+    /// Rolling back to a snapshot taken without its memory state leaves the
+    /// instance stopped, so the instance status is refreshed afterwards.
+    pub async fn rollback_snapshot<P: Principal + Sync>(
+        &mut self,
+        principal: &P,
+        id: Uuid,
+        name: &str,
+    ) -> Result<(), Error> {
+        self.auth
+            .can(principal)
+            .perform(Permission::RollbackSnapshot)
+            .over::<Instance>(&id)
+            .await?;
+
+        validate_snapshot_name(name)?;
+
+        let instance = Instance::find(&self.db, id).await?;
+        let hypervisor = Hypervisor::find(&self.db, instance.hypervisor_id).await?;
+        let connector = hypervisor.resolve(&self.resolver)?;
+
+        connector
+            .rollback_snapshot(&instance.distant_id, name)
+            .await?;
+
+        let status = connector.status(&instance.distant_id).await?;
+
+        Instance::update()
+            .set(Instance::STATUS, status.to_string())
+            .r#where(Instance::ID, "=", instance.id)
+            .execute(&self.db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Deletes a snapshot of an instance.
+    pub async fn delete_snapshot<P: Principal + Sync>(
+        &mut self,
+        principal: &P,
+        id: Uuid,
+        name: &str,
+    ) -> Result<(), Error> {
+        self.auth
+            .can(principal)
+            .perform(Permission::DeleteSnapshot)
+            .over::<Instance>(&id)
+            .await?;
+
+        validate_snapshot_name(name)?;
+
+        let instance = Instance::find(&self.db, id).await?;
+        let hypervisor = Hypervisor::find(&self.db, instance.hypervisor_id).await?;
+        let connector = hypervisor.resolve(&self.resolver)?;
+
+        connector
+            .delete_snapshot(&instance.distant_id, name)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Migrates an instance to another node of its hypervisor, live when it
+    /// runs, or to another hypervisor. Restricted to platform admins.
+    ///
+    /// Moving to another hypervisor is done offline: the instance is stopped,
+    /// exported to the backup storage the hypervisors share, imported on the
+    /// target hypervisor and restarted when it was running, before the source
+    /// VM is deleted along with the backups taken on the source hypervisor.
+    /// The source VM is restarted when the import fails. Instances holding
+    /// volumes only move within their hypervisor.
+    pub async fn migrate<P: Principal + Sync>(
+        &mut self,
+        principal: &P,
+        id: Uuid,
+        hypervisor_id: Option<Uuid>,
+        node: Option<&str>,
+    ) -> Result<InstanceMigration, Error> {
+        require_admin(principal)?;
+
+        let instance = Instance::find(&self.db, id).await?;
+        let hypervisor = Hypervisor::find(&self.db, instance.hypervisor_id).await?;
+        let connector = hypervisor.resolve(&self.resolver)?;
+
+        let target = match hypervisor_id {
+            Some(hypervisor_id) if hypervisor_id != hypervisor.id => {
+                Hypervisor::find(&self.db, hypervisor_id).await?
+            }
+            _ => {
+                let migration = connector.migrate(&instance.distant_id, node).await?;
+                return Ok(InstanceMigration::new(
+                    migration,
+                    hypervisor.id,
+                    &[instance],
+                ));
+            }
+        };
+
+        let volumes = Volume::query()
+            .select()
+            .r#where(Volume::INSTANCE_ID, "=", instance.id)
+            .get(&self.db)
+            .await?;
+        if !volumes.is_empty() {
+            return Err(Error::InstanceHoldsVolumes(instance.id));
+        }
+
+        let target_connector = target.resolve(&self.resolver)?;
+        let request = PlacementRequest {
+            cores: instance.max_cpu_cores as u32,
+            memory_bytes: instance.max_memory_bytes as u64,
+            disk_bytes: instance.max_disk_bytes as u64,
+        };
+
+        let export = connector.export(&instance.distant_id, instance.id).await?;
+        let migration = match target_connector.import(&export, node, &request).await {
+            Ok(migration) => migration,
+            Err(err) => {
+                // The source VM was left untouched by the failed import, it
+                // takes over again (best effort)
+                if export.running
+                    && let Err(err) = connector.start(&instance.distant_id).await
+                {
+                    tracing::warn!(%id, error = %err, "could not restart the exported instance");
+                }
+                if let Err(err) = connector
+                    .delete_backup(&instance.distant_id, instance.id, &export.archive)
+                    .await
+                {
+                    tracing::warn!(%id, error = %err, "could not delete the export");
+                }
+                return Err(err.into());
+            }
+        };
+
+        Instance::update()
+            .set(Instance::HYPERVISOR_ID, target.id)
+            .set(Instance::DISTANT_ID, migration.id.clone())
+            .set(Instance::UPDATED_AT, Utc::now())
+            .r#where(Instance::ID, "=", instance.id)
+            .execute(&self.db)
+            .await?;
+
+        // The backups of the instance are scheduled by the hypervisor holding
+        // it, the schedule follows it (best effort).
+        let policy = BackupPolicy::query()
+            .select()
+            .r#where(BackupPolicy::INSTANCE_ID, "=", instance.id)
+            .first(&self.db)
+            .await?;
+        if let Some(policy) = policy
+            && let Err(err) = target_connector
+                .schedule_backups(
+                    &migration.id,
+                    instance.id,
+                    Some(BackupSchedule {
+                        schedule: policy.schedule.clone(),
+                        retention: policy.retention(),
+                    }),
+                )
+                .await
+        {
+            tracing::warn!(%id, error = %err, "could not schedule the backups of the migrated instance");
+        }
+
+        // The source VM goes along with its backups, the export included
+        if let Err(err) = connector.delete(&instance.distant_id).await {
+            tracing::warn!(%id, error = %err, "could not delete the source of the migrated instance");
+        }
+
+        Ok(InstanceMigration {
+            instance_id: Some(instance.id),
+            hypervisor_id: target.id,
+            distant_id: migration.id,
+            source_node: migration.source_node,
+            target_node: migration.target_node,
+        })
+    }
 }
 
 impl Instance {
@@ -575,6 +1279,7 @@ impl Instance {
             ip_v4 = EXCLUDED.ip_v4,
             disk_usage_bytes = EXCLUDED.disk_usage_bytes,
             max_disk_bytes = EXCLUDED.max_disk_bytes,
+            missing_since = NULL,
             updated_at = NOW()
         RETURNING *
     "#,
@@ -595,4 +1300,61 @@ impl Instance {
     .fetch_all(pool)
     .await
     }
+
+    /// Records the instance once its shape fits in the quotas of its
+    /// project, then creates it on its hypervisor with `create`, which
+    /// returns its distant identifier.
+    ///
+    /// The lock of the organization is held only while the instance is
+    /// recorded as provisioning, which counts it against the quotas until it
+    /// is created or its record removed on failure.
+    pub(crate) async fn create_within_quotas(
+        self,
+        db: &Pool<Postgres>,
+        create: impl Future<Output = Result<String, Error>>,
+    ) -> Result<Instance, Error> {
+        let status = self.status.clone();
+
+        let mut tx = db.begin().await?;
+        enforce_quotas(
+            &mut tx,
+            &self.project_slug,
+            &Usage {
+                cpu_cores: self.max_cpu_cores.into(),
+                memory_bytes: self.max_memory_bytes,
+                disk_bytes: self.max_disk_bytes,
+                instances: 1,
+                ..Default::default()
+            },
+        )
+        .await?;
+        let instance = Instance {
+            distant_id: String::new(),
+            status: Status::Provisioning,
+            ..self
+        }
+        .create(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        let distant_id = match create.await {
+            Ok(distant_id) => distant_id,
+            Err(err) => {
+                Instance::destroy(db, instance.id).await?;
+                return Err(err);
+            }
+        };
+        Instance::update()
+            .set(Instance::DISTANT_ID, distant_id.clone())
+            .set(Instance::STATUS, status.to_string())
+            .r#where(Instance::ID, "=", instance.id)
+            .execute(db)
+            .await?;
+
+        Ok(Instance {
+            distant_id,
+            status,
+            ..instance
+        })
+    }
 }
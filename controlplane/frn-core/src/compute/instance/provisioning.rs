@@ -0,0 +1,319 @@
+//! Provisioning of compute instances.
+//!
+//! Holds the steps the instance provisioning workflow runs once the creation
+//! of an instance was accepted, each of them undone by another when a later
+//! step fails.
+
+use super::{Instance, Instances};
+use crate::Error;
+use crate::authorization::{Authorize, Relation, Relationship, Resource};
+use crate::compute::Hypervisor;
+use crate::resourcemanager::Project;
+use base64::Engine;
+use fabrique::{Delete, Query};
+use hypervisor::instance::Instances as HypervisorInstancesTrait;
+use hypervisor::instance::{InstanceCreateRequest, Status};
+use ssh_key::{Algorithm, LineEnding, PrivateKey};
+use uuid::Uuid;
+
+/// Parameters of the workflow provisioning an instance.
+#[derive(Debug)]
+pub struct ProvisionInstanceParams {
+    pub instance_id: Uuid,
+    pub hypervisor_id: Uuid,
+    pub project_slug: String,
+    pub name: String,
+    pub cores: u8,
+    pub disk_bytes: u64,
+    pub disk_image: String,
+    pub memory_bytes: u64,
+    pub snippet: String,
+    /// The zero trust network the instance joins the overlay of, if any.
+    pub zero_trust_network_id: Option<Uuid>,
+    /// The principal which requested the instance, allowed to follow the
+    /// execution of the workflow.
+    pub principal_id: Uuid,
+}
+
+/// An instance accepted for creation, and the workflow execution
+/// provisioning it.
+#[derive(Clone, Debug)]
+pub struct InstanceProvisioning {
+    pub instance: Instance,
+    pub execution_id: Uuid,
+}
+
+impl<A: Authorize> Instances<A> {
+    /// Checks an instance awaiting its provisioning is still recorded, and
+    /// relates it to its project.
+    ///
+    /// The instance is recorded along with the workflow provisioning it, and
+    /// may have been deleted before the workflow started. An instance whose
+    /// previous provisioning attempt failed awaits its provisioning again.
+    pub async fn ensure_reserved(&self, id: Uuid) -> Result<(), Error> {
+        let instance = Instance::find(&self.db, id).await?;
+
+        Instance::update()
+            .set(Instance::STATUS, Status::Provisioning.to_string())
+            .r#where(Instance::ID, "=", instance.id)
+            .r#where(Instance::STATUS, "=", Status::Failed.to_string())
+            .execute(&self.db)
+            .await?;
+
+        self.auth
+            .clone()
+            .write_relationship(&Relationship::new(
+                &Project::some(instance.project_slug.clone()),
+                Relation::Parent,
+                &instance,
+            ))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Marks as failed an instance whose provisioning attempt failed.
+    ///
+    /// The instance is kept along with its relationship to its project, for
+    /// the workflow to retry its provisioning, or its owner to delete it.
+    pub async fn release(&self, id: Uuid) -> Result<(), Error> {
+        Instance::update()
+            .set(Instance::STATUS, Status::Failed.to_string())
+            .r#where(Instance::ID, "=", id)
+            .r#where(Instance::STATUS, "=", Status::Provisioning.to_string())
+            .execute(&self.db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Creates the instance on its hypervisor, returning the id the
+    /// hypervisor identifies it with.
+    pub async fn create_distant(
+        &self,
+        hypervisor_id: Uuid,
+        request: InstanceCreateRequest,
+    ) -> Result<String, Error> {
+        let hypervisor = Hypervisor::find(&self.db, hypervisor_id).await?;
+        let api = hypervisor.resolve(&self.resolver)?;
+
+        let next_id = api
+            .next_id()
+            .await
+            .map_err(|_| Error::Other("could not get next id".to_owned()))?;
+
+        tracing::info!("next id is: {}", &next_id);
+
+        api.create(InstanceCreateRequest {
+            id: next_id.clone(),
+            ..request
+        })
+        .await?;
+
+        Ok(next_id)
+    }
+
+    /// Lists the distant ids of the instances of a project on a hypervisor,
+    /// which a new instance of the project is kept apart from.
+    pub async fn anti_affinity(
+        &self,
+        hypervisor_id: Uuid,
+        project_slug: &str,
+    ) -> Result<Vec<String>, Error> {
+        let instances = Instance::query()
+            .select()
+            .r#where(Instance::HYPERVISOR_ID, "=", hypervisor_id)
+            .r#where(Instance::PROJECT_SLUG, "=", project_slug.to_owned())
+            .r#where(Instance::DISTANT_ID, "!=", String::new())
+            .get(&self.db)
+            .await?;
+
+        Ok(instances
+            .into_iter()
+            .map(|instance| instance.distant_id)
+            .collect())
+    }
+
+    /// Deletes the instance identified by `distant_id` from its hypervisor.
+    pub async fn delete_distant(&self, hypervisor_id: Uuid, distant_id: &str) -> Result<(), Error> {
+        let hypervisor = Hypervisor::find(&self.db, hypervisor_id).await?;
+
+        hypervisor
+            .resolve(&self.resolver)?
+            .delete(distant_id)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Records the instance provisioned on its hypervisor under `distant_id`.
+    ///
+    /// The synchronizer may have recorded the instance first, in the default
+    /// project, in which case its record is replaced.
+    pub async fn complete_provisioning(&self, id: Uuid, distant_id: &str) -> Result<(), Error> {
+        let instance = Instance::find(&self.db, id).await?;
+
+        let mut tx = self.db.begin().await?;
+        let synchronized = Instance::query()
+            .select()
+            .r#where(Instance::DISTANT_ID, "=", distant_id.to_owned())
+            .r#where(Instance::HYPERVISOR_ID, "=", instance.hypervisor_id)
+            .r#where(Instance::ID, "!=", instance.id)
+            .get(&mut *tx)
+            .await?;
+        for duplicate in &synchronized {
+            Instance::destroy(&mut *tx, duplicate.id).await?;
+        }
+        Instance::update()
+            .set(Instance::DISTANT_ID, distant_id.to_owned())
+            .set(Instance::STATUS, Status::Unknown.to_string())
+            .r#where(Instance::ID, "=", instance.id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        // The records replaced are no longer part of the default project.
+        for duplicate in &synchronized {
+            self.auth
+                .clone()
+                .delete_relationship(&Relationship::new(
+                    &Project::some(duplicate.project_slug.clone()),
+                    Relation::Parent,
+                    duplicate,
+                ))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Puts an instance back to provisioning, before its provisioning is
+    /// undone.
+    pub async fn reset_provisioning(&self, id: Uuid) -> Result<(), Error> {
+        Instance::update()
+            .set(Instance::DISTANT_ID, String::new())
+            .set(Instance::STATUS, Status::Provisioning.to_string())
+            .r#where(Instance::ID, "=", id)
+            .execute(&self.db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Sets up Hoop SSH bastion access for a new instance.
+    ///
+    /// Generates an SSH keypair, creates a Hoop agent and connection,
+    /// and injects the credentials into the cloud-init snippet.
+    pub async fn setup_hoop_access(
+        &self,
+        instance_name: &str,
+        snippet: String,
+    ) -> Result<String, Error> {
+        let hoop_api_url = match std::env::var("HOOP_API_URL") {
+            Ok(url) => url,
+            Err(_) => {
+                tracing::warn!("HOOP_API_URL not set, skipping Hoop setup");
+                return Ok(snippet);
+            }
+        };
+
+        let hoop_api_key = match std::env::var("HOOP_API_KEY") {
+            Ok(key) => key,
+            Err(_) => {
+                tracing::warn!("HOOP_API_KEY not set, skipping Hoop setup");
+                return Ok(snippet);
+            }
+        };
+
+        // Generate SSH keypair
+        let private_key = PrivateKey::random(&mut rand::thread_rng(), Algorithm::Ed25519)
+            .map_err(|e| Error::Other(format!("Failed to generate SSH key: {}", e)))?;
+        let public_key = private_key
+            .public_key()
+            .to_openssh()
+            .map_err(|e| Error::Other(format!("Failed to format public key: {}", e)))?;
+        let private_key_pem = private_key
+            .to_openssh(LineEnding::LF)
+            .map_err(|e| Error::Other(format!("Failed to format private key: {}", e)))?;
+        let private_key_base64 =
+            base64::engine::general_purpose::STANDARD.encode(private_key_pem.as_bytes());
+
+        let client = reqwest::Client::new();
+
+        // Create Hoop agent
+        let agent_token =
+            hoop::api::create_agent(&hoop_api_url, &client, &hoop_api_key, instance_name)
+                .await
+                .map_err(|e| Error::Other(format!("Failed to create Hoop agent: {}", e)))?;
+
+        // Get agent UUID (required for creating connection)
+        let agent = hoop::api::get_agent(&hoop_api_url, &client, &hoop_api_key, instance_name)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get Hoop agent: {}", e)))?;
+
+        // Create Hoop connection with SSH credentials
+        hoop::api::create_connection(
+            &hoop_api_url,
+            &client,
+            &hoop_api_key,
+            instance_name,
+            &agent.id,
+            "francenuage",
+            &private_key_base64,
+        )
+        .await
+        .map_err(|e| Error::Other(format!("Failed to create Hoop connection: {}", e)))?;
+
+        // Inject credentials into snippet
+        let snippet = snippet
+            .replace("${HOOP_AGENT_TOKEN}", &agent_token)
+            .replace("${HOOP_SSH_PUBLIC_KEY}", &public_key);
+
+        tracing::info!(
+            "Hoop SSH bastion access configured for instance {}",
+            instance_name
+        );
+
+        Ok(snippet)
+    }
+
+    /// Cleans up Hoop SSH bastion access for an instance.
+    ///
+    /// Best effort - errors are logged but don't fail the deletion.
+    pub async fn cleanup_hoop_access(&self, instance_name: &str) {
+        let hoop_api_url = match std::env::var("HOOP_API_URL") {
+            Ok(url) => url,
+            Err(_) => return,
+        };
+
+        let hoop_api_key = match std::env::var("HOOP_API_KEY") {
+            Ok(key) => key,
+            Err(_) => return,
+        };
+
+        let client = reqwest::Client::new();
+
+        // Delete connection first
+        if let Err(e) =
+            hoop::api::delete_connection(&hoop_api_url, &client, &hoop_api_key, instance_name).await
+        {
+            tracing::warn!(
+                "Failed to delete Hoop connection for {}: {}",
+                instance_name,
+                e
+            );
+        }
+
+        // Delete agent
+        if let Err(e) =
+            hoop::api::delete_agent(&hoop_api_url, &client, &hoop_api_key, instance_name).await
+        {
+            tracing::warn!("Failed to delete Hoop agent for {}: {}", instance_name, e);
+        }
+
+        tracing::info!(
+            "Hoop SSH bastion access cleaned up for instance {}",
+            instance_name
+        );
+    }
+}
@@ -0,0 +1,629 @@
+//! WireGuard overlays of zero trust networks.
+//!
+//! Each instance attached to a zero trust network is a peer of its overlay:
+//! it is given a WireGuard keypair, whose private key is sealed at rest, and
+//! an address of the overlay network. The configuration of a peer lists every
+//! other peer of the network, so it is rendered into the cloud-init snippet of
+//! the instances created in a network, and pushed through the guest agent to
+//! the running peers whenever the network gains or loses one.
+
+use crate::Error;
+use crate::compute::{Hypervisor, Instance, InstanceFactory, InstanceIdColumn};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use chrono::{DateTime, Utc};
+use curve25519_dalek::MontgomeryPoint;
+use fabrique::{Delete, Factory, Model, Persist, Query};
+use futures::future::join_all;
+use hypervisor::Resolver;
+use hypervisor::instance::Instances as HypervisorInstancesTrait;
+use rand::RngCore;
+use serde_yaml::{Mapping, Value};
+use sqlx::{PgConnection, Pool, Postgres};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::net::Ipv4Addr;
+use uuid::Uuid;
+use zeroize::Zeroizing;
+
+/// The WireGuard interface of the overlay in the instances.
+pub const OVERLAY_INTERFACE: &str = "wg-frn";
+
+/// The network the overlay addresses are allocated from.
+const OVERLAY_NETWORK: Ipv4Addr = Ipv4Addr::new(100, 64, 0, 0);
+
+/// The prefix length of the overlay network.
+const OVERLAY_PREFIX: u8 = 16;
+
+/// The port the peers listen on.
+const LISTEN_PORT: u16 = 51820;
+
+/// The interval of the keepalives keeping the peers behind a NAT reachable,
+/// in seconds.
+const PERSISTENT_KEEPALIVE: u16 = 25;
+
+/// The additional data binding a sealed private key to its peer.
+const PRIVATE_KEY_AAD: &[u8] = b"frn-wireguard-private-key-v1";
+
+#[derive(Clone, Debug, Default, Factory, Model)]
+#[fabrique(table = "zero_trust_network_peers")]
+pub struct ZeroTrustNetworkPeer {
+    /// Unique identifier for the peer
+    #[fabrique(primary_key)]
+    pub id: Uuid,
+    /// The zero trust network this peer belongs to
+    pub zero_trust_network_id: Uuid,
+    /// The instance this peer is
+    #[fabrique(belongs_to = Instance)]
+    pub instance_id: Uuid,
+    /// The WireGuard public key of the peer, in base64
+    pub public_key: String,
+    /// The WireGuard private key of the peer, sealed with the key encryption
+    /// key
+    pub sealed_private_key: String,
+    /// The address of the peer in the overlay network
+    pub overlay_ip: String,
+    // Creation time of the peer
+    pub created_at: DateTime<Utc>,
+}
+
+/// A remote peer, as listed in the configuration of another.
+#[derive(Clone, Debug, PartialEq)]
+struct RemotePeer {
+    public_key: String,
+    overlay_ip: Ipv4Addr,
+    /// The address the peer is reached on, unknown until its hypervisor
+    /// reports it.
+    endpoint: Option<Ipv4Addr>,
+}
+
+/// Service for managing the WireGuard overlays of zero trust networks.
+#[derive(Clone)]
+pub struct Overlays {
+    db: Pool<Postgres>,
+    resolver: Resolver,
+}
+
+impl Overlays {
+    /// Creates a new overlays service.
+    pub fn new(db: Pool<Postgres>, resolver: Resolver) -> Self {
+        Self { db, resolver }
+    }
+
+    /// Makes an instance a peer of the overlay of a zero trust network,
+    /// unless it already is.
+    ///
+    /// The peer is given a new keypair and the lowest address of the overlay
+    /// network no other peer holds.
+    pub async fn join(
+        &self,
+        zero_trust_network_id: Uuid,
+        instance_id: Uuid,
+    ) -> Result<ZeroTrustNetworkPeer, Error> {
+        let mut tx = self.db.begin().await?;
+        let peer = self
+            .join_on(zero_trust_network_id, instance_id, &mut tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(peer)
+    }
+
+    /// Makes an instance a peer of the overlay of a zero trust network on a
+    /// connection, for the caller to commit along with its own changes.
+    ///
+    /// See [`Self::join`].
+    pub async fn join_on(
+        &self,
+        zero_trust_network_id: Uuid,
+        instance_id: Uuid,
+        conn: &mut PgConnection,
+    ) -> Result<ZeroTrustNetworkPeer, Error> {
+        // Raw SQL: row locks are not expressible with fabrique. Locking the
+        // network serializes the allocations of its addresses.
+        sqlx::query_scalar::<_, Uuid>(
+            r#"SELECT id FROM zero_trust_networks WHERE id = $1 FOR NO KEY UPDATE"#,
+        )
+        .bind(zero_trust_network_id)
+        .fetch_one(&mut *conn)
+        .await?;
+
+        let existing = ZeroTrustNetworkPeer::query()
+            .select()
+            .r#where(ZeroTrustNetworkPeer::INSTANCE_ID, "=", instance_id)
+            .first(&mut *conn)
+            .await?;
+        match existing {
+            Some(peer) if peer.zero_trust_network_id == zero_trust_network_id => {
+                return Ok(peer);
+            }
+            Some(peer) => peer.delete(&mut *conn).await?,
+            None => {}
+        }
+
+        let allocated = ZeroTrustNetworkPeer::query()
+            .select()
+            .r#where(
+                ZeroTrustNetworkPeer::ZERO_TRUST_NETWORK_ID,
+                "=",
+                zero_trust_network_id,
+            )
+            .get(&mut *conn)
+            .await?
+            .iter()
+            .filter_map(|peer| peer.overlay_ip.parse().ok())
+            .collect::<Vec<Ipv4Addr>>();
+        let overlay_ip =
+            allocate(&allocated).ok_or(Error::OverlayExhausted(zero_trust_network_id))?;
+
+        let id = Uuid::new_v4();
+        let (private_key, public_key) = generate_keypair();
+        let sealed_private_key = frn_crypto::seal(
+            self.resolver.kek(),
+            private_key.as_bytes(),
+            &private_key_aad(id),
+        )
+        .map_err(|err| Error::Other(format!("could not seal private key: {}", err)))?;
+
+        let peer = ZeroTrustNetworkPeer {
+            id,
+            zero_trust_network_id,
+            instance_id,
+            public_key,
+            sealed_private_key,
+            overlay_ip: overlay_ip.to_string(),
+            created_at: Utc::now(),
+        }
+        .create(&mut *conn)
+        .await?;
+
+        Ok(peer)
+    }
+
+    /// Removes an instance from the overlay it is a peer of, returning the
+    /// zero trust network it left.
+    pub async fn leave(&self, instance_id: Uuid) -> Result<Option<Uuid>, Error> {
+        let Some(peer) = self.find(instance_id).await? else {
+            return Ok(None);
+        };
+        let zero_trust_network_id = peer.zero_trust_network_id;
+        peer.delete(&self.db).await?;
+
+        Ok(Some(zero_trust_network_id))
+    }
+
+    /// Renders the WireGuard configuration of an instance, none when it is
+    /// not a peer of any overlay.
+    pub async fn configuration(&self, instance_id: Uuid) -> Result<Option<String>, Error> {
+        let Some(peer) = self.find(instance_id).await? else {
+            return Ok(None);
+        };
+        let members = self.members(peer.zero_trust_network_id).await?;
+
+        self.configuration_of(&peer, &members).map(Some)
+    }
+
+    /// Injects the WireGuard configuration of an instance into its
+    /// cloud-init snippet, which is left untouched when the instance is not
+    /// a peer of any overlay.
+    pub async fn inject(&self, instance_id: Uuid, snippet: &str) -> Result<String, Error> {
+        match self.configuration(instance_id).await? {
+            Some(configuration) => inject(snippet, &configuration),
+            None => Ok(snippet.to_owned()),
+        }
+    }
+
+    /// Pushes their configuration to the running peers of the overlay of a
+    /// zero trust network, through their guest agent.
+    ///
+    /// Best effort - the peers which could not be configured are logged and
+    /// returned, they are configured again on the next change.
+    pub async fn push(&self, zero_trust_network_id: Uuid) -> Result<Vec<Uuid>, Error> {
+        let members = self.members(zero_trust_network_id).await?;
+
+        let mut connectors = HashMap::new();
+        let mut configurations = Vec::new();
+        for (peer, instance) in &members {
+            // Instances still provisioning receive their configuration in
+            // their cloud-init snippet.
+            if instance.distant_id.is_empty() {
+                continue;
+            }
+
+            if !connectors.contains_key(&instance.hypervisor_id) {
+                let hypervisor = Hypervisor::find(&self.db, instance.hypervisor_id).await?;
+                connectors.insert(instance.hypervisor_id, hypervisor.resolve(&self.resolver)?);
+            }
+            configurations.push((instance, self.configuration_of(peer, &members)?));
+        }
+
+        let configured = join_all(configurations.iter().map(|(instance, configuration)| {
+            connectors[&instance.hypervisor_id].configure_wireguard(
+                &instance.distant_id,
+                OVERLAY_INTERFACE,
+                Some(configuration),
+            )
+        }))
+        .await;
+
+        let mut unreachable = Vec::new();
+        for ((instance, _), configured) in configurations.iter().zip(configured) {
+            if let Err(err) = configured {
+                tracing::warn!(
+                    instance_id = %instance.id,
+                    %zero_trust_network_id,
+                    error = %err,
+                    "could not push the overlay configuration"
+                );
+                unreachable.push(instance.id);
+            }
+        }
+
+        Ok(unreachable)
+    }
+
+    /// Tears the overlay interface of an instance down.
+    pub async fn tear_down(&self, instance: &Instance) -> Result<(), Error> {
+        if instance.distant_id.is_empty() {
+            return Ok(());
+        }
+
+        self.configure(instance, None).await
+    }
+
+    /// Applies a WireGuard configuration to the overlay interface of an
+    /// instance, or tears it down when unset.
+    async fn configure(
+        &self,
+        instance: &Instance,
+        configuration: Option<&str>,
+    ) -> Result<(), Error> {
+        let hypervisor = Hypervisor::find(&self.db, instance.hypervisor_id).await?;
+
+        hypervisor
+            .resolve(&self.resolver)?
+            .configure_wireguard(&instance.distant_id, OVERLAY_INTERFACE, configuration)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Finds the peer an instance is.
+    async fn find(&self, instance_id: Uuid) -> Result<Option<ZeroTrustNetworkPeer>, Error> {
+        ZeroTrustNetworkPeer::query()
+            .select()
+            .r#where(ZeroTrustNetworkPeer::INSTANCE_ID, "=", instance_id)
+            .first(&self.db)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Renders the WireGuard configuration of a peer, listing the other
+    /// members of its overlay.
+    fn configuration_of(
+        &self,
+        peer: &ZeroTrustNetworkPeer,
+        members: &[(ZeroTrustNetworkPeer, Instance)],
+    ) -> Result<String, Error> {
+        let private_key = Zeroizing::new(
+            frn_crypto::open(
+                self.resolver.kek(),
+                &peer.sealed_private_key,
+                &private_key_aad(peer.id),
+            )
+            .map_err(|err| Error::Other(format!("could not open private key: {}", err)))?,
+        );
+        let private_key = Zeroizing::new(
+            String::from_utf8(private_key.to_vec())
+                .map_err(|_| Error::Other("private key is not valid UTF-8".to_owned()))?,
+        );
+
+        let remotes = members
+            .iter()
+            .filter(|(remote, _)| remote.id != peer.id)
+            .map(|(remote, instance)| {
+                Ok(RemotePeer {
+                    public_key: remote.public_key.clone(),
+                    overlay_ip: parse_overlay_ip(&remote.overlay_ip)?,
+                    endpoint: instance.ip_v4.parse().ok(),
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        Ok(render(
+            &private_key,
+            parse_overlay_ip(&peer.overlay_ip)?,
+            &remotes,
+        ))
+    }
+
+    /// Lists the peers of the overlay of a zero trust network along with
+    /// their instance, by address.
+    ///
+    /// Two queries regardless of the peer count: the query builder returns
+    /// the columns of a single model, so the peers and their instances are
+    /// fetched separately and paired in memory.
+    async fn members(
+        &self,
+        zero_trust_network_id: Uuid,
+    ) -> Result<Vec<(ZeroTrustNetworkPeer, Instance)>, Error> {
+        let peers = self.peers(zero_trust_network_id).await?;
+        let mut instances: HashMap<Uuid, Instance> = Instance::query()
+            .join::<ZeroTrustNetworkPeer>()
+            .select()
+            .r#where(
+                ZeroTrustNetworkPeer::ZERO_TRUST_NETWORK_ID,
+                "=",
+                zero_trust_network_id,
+            )
+            .get(&self.db)
+            .await?
+            .into_iter()
+            .map(|instance| (instance.id, instance))
+            .collect();
+
+        Ok(peers
+            .into_iter()
+            .filter_map(|peer| {
+                let instance = instances.remove(&peer.instance_id)?;
+                Some((peer, instance))
+            })
+            .collect())
+    }
+
+    /// Lists the peers of the overlay of a zero trust network, by address.
+    async fn peers(&self, zero_trust_network_id: Uuid) -> Result<Vec<ZeroTrustNetworkPeer>, Error> {
+        let mut peers = ZeroTrustNetworkPeer::query()
+            .select()
+            .r#where(
+                ZeroTrustNetworkPeer::ZERO_TRUST_NETWORK_ID,
+                "=",
+                zero_trust_network_id,
+            )
+            .get(&self.db)
+            .await?;
+        peers.sort_by_key(|peer| peer.overlay_ip.parse::<Ipv4Addr>().ok());
+
+        Ok(peers)
+    }
+}
+
+/// Generates a WireGuard keypair, both keys encoded in base64.
+fn generate_keypair() -> (Zeroizing<String>, String) {
+    let mut secret = Zeroizing::new([0u8; 32]);
+    rand::rngs::OsRng.fill_bytes(secret.as_mut());
+
+    // Clamp the private key as `wg genkey` does.
+    secret[0] &= 248;
+    secret[31] &= 127;
+    secret[31] |= 64;
+
+    let public = MontgomeryPoint::mul_base_clamped(*secret);
+
+    (
+        Zeroizing::new(STANDARD.encode(secret.as_ref())),
+        STANDARD.encode(public.as_bytes()),
+    )
+}
+
+/// Gets the additional data binding the sealed private key of a peer to it.
+fn private_key_aad(peer_id: Uuid) -> Vec<u8> {
+    let mut aad = PRIVATE_KEY_AAD.to_vec();
+    aad.extend_from_slice(peer_id.as_bytes());
+    aad
+}
+
+/// Allocates the lowest host address of the overlay network which is not
+/// allocated yet, none when the network is full.
+fn allocate(allocated: &[Ipv4Addr]) -> Option<Ipv4Addr> {
+    let network = u32::from(OVERLAY_NETWORK);
+    let broadcast = network | (u32::MAX >> OVERLAY_PREFIX);
+
+    ((network + 1)..broadcast)
+        .map(Ipv4Addr::from)
+        .find(|address| !allocated.contains(address))
+}
+
+/// Parses the recorded address of a peer.
+fn parse_overlay_ip(overlay_ip: &str) -> Result<Ipv4Addr, Error> {
+    overlay_ip
+        .parse()
+        .map_err(|_| Error::Other(format!("invalid overlay address {}", overlay_ip)))
+}
+
+/// Renders the `wg-quick` configuration of a peer.
+fn render(private_key: &str, address: Ipv4Addr, remotes: &[RemotePeer]) -> String {
+    let mut configuration = format!(
+        "[Interface]\nPrivateKey = {}\nAddress = {}/{}\nListenPort = {}\n",
+        private_key, address, OVERLAY_PREFIX, LISTEN_PORT
+    );
+
+    for remote in remotes {
+        let _ = write!(
+            configuration,
+            "\n[Peer]\nPublicKey = {}\nAllowedIPs = {}/32\n",
+            remote.public_key, remote.overlay_ip
+        );
+        if let Some(endpoint) = remote.endpoint {
+            let _ = writeln!(configuration, "Endpoint = {}:{}", endpoint, LISTEN_PORT);
+        }
+        let _ = writeln!(
+            configuration,
+            "PersistentKeepalive = {}",
+            PERSISTENT_KEEPALIVE
+        );
+    }
+
+    configuration
+}
+
+/// Adds to a cloud-init snippet the installation of WireGuard, and the
+/// interface of the overlay brought up on boot with the given configuration.
+fn inject(snippet: &str, configuration: &str) -> Result<String, Error> {
+    let mut cloud_config = match serde_yaml::from_str::<Value>(snippet)
+        .map_err(|err| Error::InvalidSnippet(err.to_string()))?
+    {
+        Value::Null => Mapping::new(),
+        Value::Mapping(mapping) => mapping,
+        _ => {
+            return Err(Error::InvalidSnippet(
+                "the snippet is not a cloud-config mapping".to_owned(),
+            ));
+        }
+    };
+
+    let mut write_file = Mapping::new();
+    write_file.insert(
+        "path".into(),
+        format!("/etc/wireguard/{}.conf", OVERLAY_INTERFACE).into(),
+    );
+    write_file.insert("permissions".into(), "0600".into());
+    write_file.insert("content".into(), configuration.into());
+
+    for (key, value) in [
+        ("packages", Value::from("wireguard-tools")),
+        ("write_files", Value::Mapping(write_file)),
+        (
+            "runcmd",
+            Value::from(format!(
+                "systemctl enable --now wg-quick@{}",
+                OVERLAY_INTERFACE
+            )),
+        ),
+    ] {
+        match cloud_config
+            .entry(key.into())
+            .or_insert_with(|| Value::Sequence(Vec::new()))
+        {
+            Value::Sequence(sequence) => sequence.push(value),
+            _ => {
+                return Err(Error::InvalidSnippet(format!("{} is not a sequence", key)));
+            }
+        }
+    }
+
+    let rendered = serde_yaml::to_string(&cloud_config)
+        .map_err(|err| Error::InvalidSnippet(err.to_string()))?;
+
+    Ok(format!("#cloud-config\n{}", rendered))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_keypair() {
+        let (private_key, public_key) = generate_keypair();
+
+        let secret: [u8; 32] = STANDARD
+            .decode(private_key.as_bytes())
+            .unwrap()
+            .try_into()
+            .unwrap();
+        assert_eq!(secret[0] & 7, 0);
+        assert_eq!(secret[31] & 192, 64);
+        assert_eq!(
+            public_key,
+            STANDARD.encode(MontgomeryPoint::mul_base_clamped(secret).as_bytes())
+        );
+        assert_ne!(generate_keypair().1, public_key);
+    }
+
+    #[test]
+    fn test_allocate() {
+        assert_eq!(allocate(&[]), Some(Ipv4Addr::new(100, 64, 0, 1)));
+        assert_eq!(
+            allocate(&[Ipv4Addr::new(100, 64, 0, 1), Ipv4Addr::new(100, 64, 0, 3)]),
+            Some(Ipv4Addr::new(100, 64, 0, 2))
+        );
+
+        let full = (1..u16::MAX)
+            .map(|host| Ipv4Addr::from(u32::from(OVERLAY_NETWORK) + u32::from(host)))
+            .collect::<Vec<_>>();
+        assert_eq!(allocate(&full), None);
+    }
+
+    #[test]
+    fn test_render() {
+        let configuration = render(
+            "cHJpdmF0ZQ==",
+            Ipv4Addr::new(100, 64, 0, 1),
+            &[
+                RemotePeer {
+                    public_key: "cHVibGljMg==".to_owned(),
+                    overlay_ip: Ipv4Addr::new(100, 64, 0, 2),
+                    endpoint: Some(Ipv4Addr::new(192, 0, 2, 10)),
+                },
+                RemotePeer {
+                    public_key: "cHVibGljMw==".to_owned(),
+                    overlay_ip: Ipv4Addr::new(100, 64, 0, 3),
+                    endpoint: None,
+                },
+            ],
+        );
+
+        assert_eq!(
+            configuration,
+            "[Interface]\n\
+             PrivateKey = cHJpdmF0ZQ==\n\
+             Address = 100.64.0.1/16\n\
+             ListenPort = 51820\n\
+             \n\
+             [Peer]\n\
+             PublicKey = cHVibGljMg==\n\
+             AllowedIPs = 100.64.0.2/32\n\
+             Endpoint = 192.0.2.10:51820\n\
+             PersistentKeepalive = 25\n\
+             \n\
+             [Peer]\n\
+             PublicKey = cHVibGljMw==\n\
+             AllowedIPs = 100.64.0.3/32\n\
+             PersistentKeepalive = 25\n"
+        );
+    }
+
+    #[test]
+    fn test_inject() {
+        let snippet = "#cloud-config\npackages:\n  - curl\nruncmd:\n  - echo hello\n";
+
+        let injected = inject(snippet, "[Interface]\n").unwrap();
+
+        assert!(injected.starts_with("#cloud-config\n"));
+        let cloud_config: Value = serde_yaml::from_str(&injected).unwrap();
+        assert_eq!(
+            cloud_config["packages"],
+            serde_yaml::from_str::<Value>("[curl, wireguard-tools]").unwrap()
+        );
+        assert_eq!(
+            cloud_config["runcmd"],
+            serde_yaml::from_str::<Value>("[echo hello, systemctl enable --now wg-quick@wg-frn]")
+                .unwrap()
+        );
+        assert_eq!(
+            cloud_config["write_files"][0]["path"],
+            "/etc/wireguard/wg-frn.conf"
+        );
+        assert_eq!(cloud_config["write_files"][0]["permissions"], "0600");
+        assert_eq!(cloud_config["write_files"][0]["content"], "[Interface]\n");
+    }
+
+    #[test]
+    fn test_inject_into_empty_snippet() {
+        let injected = inject("", "[Interface]\n").unwrap();
+
+        let cloud_config: Value = serde_yaml::from_str(&injected).unwrap();
+        assert_eq!(cloud_config["packages"][0], "wireguard-tools");
+    }
+
+    #[test]
+    fn test_inject_rejects_invalid_snippets() {
+        assert!(matches!(
+            inject("- not a mapping", "[Interface]\n"),
+            Err(Error::InvalidSnippet(_))
+        ));
+        assert!(matches!(
+            inject("runcmd: echo hello", "[Interface]\n"),
+            Err(Error::InvalidSnippet(_))
+        ));
+    }
+}
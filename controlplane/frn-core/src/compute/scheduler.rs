@@ -0,0 +1,116 @@
+//! Hypervisor scheduling for new instances.
+//!
+//! Picks the hypervisor hosting a new instance among the ones registered in a
+//! zone, based on the capacity they report live. Hypervisors the last probe
+//! found unreachable, or failing to report their capacity in time, are
+//! skipped so a single failing cluster does not block provisioning.
+
+use crate::Error;
+use crate::compute::{Hypervisor, HypervisorHealth};
+use fabrique::Query;
+use futures::future::join_all;
+use hypervisor::Resolver;
+use hypervisor::instance::Instances as HypervisorInstancesTrait;
+use hypervisor::placement::{NodeCapacity, PlacementRequest};
+use sqlx::{Pool, Postgres};
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Time a hypervisor is given to report its capacity.
+const CAPACITY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Selects the hypervisor with the most headroom able to host the request.
+///
+/// When `zone_id` is set, only the hypervisors registered in that zone are
+/// considered. Hypervisors are ranked by the score of their best node fitting
+/// the request.
+pub(crate) async fn schedule(
+    db: &Pool<Postgres>,
+    resolver: &Resolver,
+    zone_id: Option<Uuid>,
+    request: &PlacementRequest,
+) -> Result<Hypervisor, Error> {
+    let hypervisors = match zone_id {
+        Some(zone_id) => {
+            Hypervisor::query()
+                .select()
+                .r#where(Hypervisor::ZONE_ID, "=", zone_id)
+                .get(db)
+                .await?
+        }
+        None => Hypervisor::all(db).await?,
+    };
+
+    if hypervisors.is_empty() {
+        return Err(Error::NoHypervisorsAvailable);
+    }
+
+    let hypervisors = hypervisors
+        .into_iter()
+        .filter(|hypervisor| {
+            if hypervisor.health == HypervisorHealth::Unreachable {
+                tracing::debug!("skipping hypervisor {} marked unreachable", hypervisor.id);
+                return false;
+            }
+            true
+        })
+        .collect::<Vec<_>>();
+    let reports = join_all(
+        hypervisors
+            .iter()
+            .map(|hypervisor| capacity(resolver, hypervisor)),
+    )
+    .await;
+
+    let mut reachable = 0;
+    let mut candidates = Vec::with_capacity(hypervisors.len());
+    for (hypervisor, report) in hypervisors.into_iter().zip(reports) {
+        let nodes = match report {
+            Ok(nodes) => nodes,
+            Err(err) => {
+                tracing::warn!("skipping unreachable hypervisor {}: {}", hypervisor.id, err);
+                continue;
+            }
+        };
+        reachable += 1;
+
+        let best = nodes
+            .iter()
+            .filter(|node| node.fits(request))
+            .map(|node| node.score(request))
+            .max_by(f64::total_cmp);
+
+        if let Some(score) = best {
+            candidates.push((score, hypervisor));
+        }
+    }
+
+    if reachable == 0 {
+        return Err(Error::HypervisorsUnreachable);
+    }
+
+    candidates
+        .into_iter()
+        .max_by(|(a, _), (b, _)| a.total_cmp(b))
+        .map(|(_, hypervisor)| hypervisor)
+        .ok_or(Error::InsufficientCapacity {
+            zone_id,
+            cores: request.cores,
+            memory_bytes: request.memory_bytes,
+            disk_bytes: request.disk_bytes,
+        })
+}
+
+/// Gets the capacity left on the nodes of a hypervisor, failing when it does
+/// not answer within [`CAPACITY_TIMEOUT`].
+async fn capacity(
+    resolver: &Resolver,
+    hypervisor: &Hypervisor,
+) -> Result<Vec<NodeCapacity>, Error> {
+    let connector = hypervisor.resolve(resolver)?;
+
+    tokio::time::timeout(CAPACITY_TIMEOUT, connector.capacity())
+        .await
+        .map_err(|_| Error::Other(format!("no capacity reported in {:?}", CAPACITY_TIMEOUT)))?
+        .map_err(Into::into)
+}
@@ -0,0 +1,546 @@
+//! Security groups.
+//!
+//! Provides the SecurityGroup data model and SecurityGroups service for
+//! managing sets of ingress and egress rules scoped to a project, and
+//! attaching them to the instances of the project, with authorization checks.
+//!
+//! Rules only let traffic through: an instance with security groups attached
+//! drops the incoming traffic none of their rules match, and the outgoing
+//! traffic as well once one of them holds an egress rule. Groups are enforced
+//! by the firewall of the hypervisor, which is re-applied on each change and
+//! by the synchronizer when it drifts.
+
+use crate::Error;
+use crate::authorization::{Authorize, Permission, Principal, Relation, Relationship, Resource};
+use crate::compute::{Hypervisor, Instance, InstanceFactory, InstanceIdColumn};
+use crate::resourcemanager::Project;
+use chrono::{DateTime, Utc};
+use fabrique::{Delete, Factory, Model, Persist, Query};
+use hypervisor::Resolver;
+use hypervisor::instance::{
+    Firewall, FirewallDirection, FirewallRule, Instances as HypervisorInstancesTrait,
+};
+use sqlx::{Pool, Postgres};
+use std::net::IpAddr;
+use uuid::Uuid;
+
+/// The protocols rules can match.
+const PROTOCOLS: [&str; 3] = ["tcp", "udp", "icmp"];
+
+#[derive(Clone, Debug, Default, Factory, Model, Resource)]
+#[fabrique(table = "security_groups")]
+pub struct SecurityGroup {
+    /// Unique identifier for the security group
+    #[fabrique(primary_key)]
+    pub id: Uuid,
+    /// The project this security group belongs to
+    pub project_slug: String,
+    /// Human-readable name, unique within the project
+    pub name: String,
+    /// Free-form description of the security group
+    pub description: String,
+    // Creation time of the security group
+    pub created_at: DateTime<Utc>,
+    // Time of the security group last update
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug, Default, Factory, Model)]
+#[fabrique(table = "security_group_rules")]
+pub struct SecurityGroupRule {
+    /// Unique identifier for the rule
+    #[fabrique(primary_key)]
+    pub id: Uuid,
+    /// The security group holding this rule
+    #[fabrique(belongs_to = SecurityGroup)]
+    pub security_group_id: Uuid,
+    /// The direction of the traffic matched
+    #[fabrique(as = "String")]
+    pub direction: FirewallDirection,
+    /// The protocol matched (`tcp`, `udp` or `icmp`), any when unset
+    pub protocol: Option<String>,
+    /// The destination ports matched (e.g. `22` or `8000:8080`), any when unset
+    pub port_range: Option<String>,
+    /// The remote addresses matched in CIDR notation, any when unset
+    pub cidr: Option<String>,
+    // Creation time of the rule
+    pub created_at: DateTime<Utc>,
+}
+
+impl SecurityGroupRule {
+    /// Gets the firewall rule enforcing this rule.
+    fn firewall_rule(&self) -> FirewallRule {
+        FirewallRule {
+            direction: self.direction,
+            protocol: self.protocol.clone(),
+            port_range: self.port_range.clone(),
+            cidr: self.cidr.clone(),
+            group: self.security_group_id.to_string(),
+        }
+    }
+}
+
+/// Attachment of a security group to an instance (join row).
+#[derive(Clone, Debug, Default, Factory, Model)]
+#[fabrique(table = "instance_security_groups")]
+pub struct InstanceSecurityGroup {
+    #[fabrique(primary_key)]
+    pub id: Uuid,
+    #[fabrique(belongs_to = Instance)]
+    pub instance_id: Uuid,
+    #[fabrique(belongs_to = SecurityGroup)]
+    pub security_group_id: Uuid,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug)]
+pub struct SecurityGroupCreateRequest {
+    /// The project to create the security group in.
+    pub project_slug: String,
+
+    /// The security group human-readable name.
+    pub name: String,
+
+    /// The security group description.
+    pub description: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct SecurityGroupRuleCreateRequest {
+    /// The security group to add the rule to.
+    pub security_group_id: Uuid,
+
+    /// The direction of the traffic matched.
+    pub direction: FirewallDirection,
+
+    /// The protocol matched, any when unset.
+    pub protocol: Option<String>,
+
+    /// The destination ports matched, any when unset. Requires the `tcp` or
+    /// `udp` protocol.
+    pub port_range: Option<String>,
+
+    /// The remote addresses matched in CIDR notation, any when unset.
+    pub cidr: Option<String>,
+}
+
+impl SecurityGroupRuleCreateRequest {
+    /// Checks the rule can be enforced by the firewall of the hypervisor.
+    fn validate(&self) -> Result<(), Error> {
+        let invalid = |reason: String| Err(Error::InvalidSecurityGroupRule(reason));
+
+        if let Some(protocol) = &self.protocol
+            && !PROTOCOLS.contains(&protocol.as_str())
+        {
+            return invalid(format!("unsupported protocol {}", protocol));
+        }
+
+        if let Some(port_range) = &self.port_range {
+            if !matches!(self.protocol.as_deref(), Some("tcp" | "udp")) {
+                return invalid("ports require the tcp or udp protocol".to_owned());
+            }
+
+            let ports = port_range
+                .split_once(':')
+                .unwrap_or((port_range, port_range));
+            match (ports.0.parse::<u16>(), ports.1.parse::<u16>()) {
+                (Ok(first), Ok(last)) if first > 0 && first <= last => {}
+                _ => return invalid(format!("invalid port range {}", port_range)),
+            }
+        }
+
+        if let Some(cidr) = &self.cidr {
+            let valid = cidr.split_once('/').is_some_and(|(address, prefix)| {
+                let max_prefix = match address.parse::<IpAddr>() {
+                    Ok(IpAddr::V4(_)) => 32,
+                    Ok(IpAddr::V6(_)) => 128,
+                    Err(_) => return false,
+                };
+                prefix
+                    .parse::<u8>()
+                    .is_ok_and(|prefix| prefix <= max_prefix)
+            });
+            if !valid {
+                return invalid(format!("invalid CIDR {}", cidr));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Service for managing security groups.
+#[derive(Clone)]
+pub struct SecurityGroups<A: Authorize> {
+    auth: A,
+    db: Pool<Postgres>,
+    resolver: Resolver,
+}
+
+impl<A: Authorize> SecurityGroups<A> {
+    /// Creates a new security groups service.
+    pub fn new(auth: A, db: Pool<Postgres>, resolver: Resolver) -> Self {
+        Self { auth, db, resolver }
+    }
+
+    /// Lists all security groups accessible to the principal.
+    pub async fn list<P: Principal + Sync>(
+        &mut self,
+        principal: &P,
+    ) -> Result<Vec<SecurityGroup>, Error> {
+        self.auth
+            .lookup::<SecurityGroup>()
+            .on_behalf_of(principal)
+            .with(Permission::Get)
+            .against(&self.db)
+            .await
+    }
+
+    /// Creates an empty security group in a project.
+    pub async fn create<P: Principal + Sync>(
+        &mut self,
+        principal: &P,
+        request: SecurityGroupCreateRequest,
+    ) -> Result<SecurityGroup, Error> {
+        self.auth
+            .can(principal)
+            .perform(Permission::CreateSecurityGroup)
+            .over::<Project>(&request.project_slug)
+            .await?;
+
+        let security_group = SecurityGroup {
+            id: Uuid::new_v4(),
+            project_slug: request.project_slug.clone(),
+            name: request.name,
+            description: request.description,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+        .create(&self.db)
+        .await?;
+
+        // Write the relationship synchronously to SpiceDB
+        self.auth
+            .write_relationship(&Relationship::new(
+                &Project::some(request.project_slug),
+                Relation::Parent,
+                &security_group,
+            ))
+            .await?;
+
+        Ok(security_group)
+    }
+
+    /// Deletes a security group, lifting its rules from the instances it is
+    /// attached to.
+    ///
+    /// Best effort on the instances - those whose firewall could not be
+    /// updated are logged, the group is deleted nonetheless.
+    pub async fn delete<P: Principal + Sync>(
+        &mut self,
+        principal: &P,
+        id: Uuid,
+    ) -> Result<(), Error> {
+        self.auth
+            .can(principal)
+            .perform(Permission::Delete)
+            .over::<SecurityGroup>(&id)
+            .await?;
+
+        let security_group = SecurityGroup::find(&self.db, id).await?;
+        let attachments = self.attachments(id).await?;
+
+        // Rules and attachments are deleted along with the group
+        SecurityGroup::destroy(&self.db, id).await?;
+        self.auth
+            .delete_relationship(&Relationship::new(
+                &Project::some(security_group.project_slug.clone()),
+                Relation::Parent,
+                &security_group,
+            ))
+            .await?;
+
+        for attachment in attachments {
+            let lifted = match Instance::find(&self.db, attachment.instance_id).await {
+                Ok(instance) => self.apply(&instance).await,
+                Err(err) => Err(err.into()),
+            };
+            if let Err(err) = lifted {
+                tracing::warn!(
+                    instance_id = %attachment.instance_id,
+                    security_group_id = %id,
+                    error = %err,
+                    "could not lift the rules of the deleted security group"
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Lists the rules of a security group, oldest first.
+    pub async fn list_rules<P: Principal + Sync>(
+        &mut self,
+        principal: &P,
+        id: Uuid,
+    ) -> Result<Vec<SecurityGroupRule>, Error> {
+        self.auth
+            .can(principal)
+            .perform(Permission::Get)
+            .over::<SecurityGroup>(&id)
+            .await?;
+
+        self.rules(id).await
+    }
+
+    /// Adds a rule to a security group, applying it to the instances the
+    /// group is attached to.
+    pub async fn add_rule<P: Principal + Sync>(
+        &mut self,
+        principal: &P,
+        request: SecurityGroupRuleCreateRequest,
+    ) -> Result<SecurityGroupRule, Error> {
+        self.auth
+            .can(principal)
+            .perform(Permission::Update)
+            .over::<SecurityGroup>(&request.security_group_id)
+            .await?;
+
+        request.validate()?;
+
+        let rule = SecurityGroupRule {
+            id: Uuid::new_v4(),
+            security_group_id: request.security_group_id,
+            direction: request.direction,
+            protocol: request.protocol,
+            port_range: request.port_range,
+            cidr: request.cidr,
+            created_at: Utc::now(),
+        }
+        .create(&self.db)
+        .await?;
+
+        self.apply_attached(rule.security_group_id).await?;
+
+        Ok(rule)
+    }
+
+    /// Removes a rule from a security group, lifting it from the instances
+    /// the group is attached to.
+    pub async fn remove_rule<P: Principal + Sync>(
+        &mut self,
+        principal: &P,
+        id: Uuid,
+        rule_id: Uuid,
+    ) -> Result<(), Error> {
+        self.auth
+            .can(principal)
+            .perform(Permission::Update)
+            .over::<SecurityGroup>(&id)
+            .await?;
+
+        let rule = SecurityGroupRule::query()
+            .select()
+            .r#where(SecurityGroupRule::ID, "=", rule_id)
+            .r#where(SecurityGroupRule::SECURITY_GROUP_ID, "=", id)
+            .first(&self.db)
+            .await?
+            .ok_or(Error::SecurityGroupRuleNotFound(rule_id))?;
+
+        SecurityGroupRule::destroy(&self.db, rule.id).await?;
+
+        self.apply_attached(id).await
+    }
+
+    /// Attaches a security group to an instance of its project.
+    pub async fn attach<P: Principal + Sync>(
+        &mut self,
+        principal: &P,
+        id: Uuid,
+        instance_id: Uuid,
+    ) -> Result<(), Error> {
+        let (security_group, instance) = self
+            .authorize_attachment(principal, Permission::Attach, id, instance_id)
+            .await?;
+
+        if self.find_attachment(id, instance_id).await?.is_some() {
+            return Err(Error::SecurityGroupAlreadyAttached { id, instance_id });
+        }
+
+        InstanceSecurityGroup {
+            id: Uuid::new_v4(),
+            instance_id: instance.id,
+            security_group_id: security_group.id,
+            created_at: Utc::now(),
+        }
+        .create(&self.db)
+        .await?;
+
+        self.apply(&instance).await
+    }
+
+    /// Detaches a security group from an instance.
+    ///
+    /// Detaching the last group of an instance disables its firewall.
+    pub async fn detach<P: Principal + Sync>(
+        &mut self,
+        principal: &P,
+        id: Uuid,
+        instance_id: Uuid,
+    ) -> Result<(), Error> {
+        let (_, instance) = self
+            .authorize_attachment(principal, Permission::Detach, id, instance_id)
+            .await?;
+
+        let attachment = self
+            .find_attachment(id, instance_id)
+            .await?
+            .ok_or(Error::SecurityGroupNotAttached { id, instance_id })?;
+        attachment.delete(&self.db).await?;
+
+        self.apply(&instance).await
+    }
+
+    /// Re-applies the security groups of an instance when the firewall of the
+    /// hypervisor drifted from them, returning whether it did.
+    ///
+    /// Instances without security groups are left untouched, so that the
+    /// firewall of the hypervisor can still be managed by hand for them.
+    pub async fn enforce(&self, instance: &Instance) -> Result<bool, Error> {
+        let Some(desired) = self.firewall(instance.id).await? else {
+            return Ok(false);
+        };
+
+        let connector = self.connector(instance).await?;
+        if connector.firewall(&instance.distant_id).await? == Some(desired.clone()) {
+            return Ok(false);
+        }
+
+        connector
+            .apply_firewall(&instance.distant_id, Some(&desired))
+            .await?;
+
+        Ok(true)
+    }
+
+    /// Checks a principal can attach or detach a security group to an
+    /// instance, both belonging to the same project.
+    async fn authorize_attachment<P: Principal + Sync>(
+        &mut self,
+        principal: &P,
+        permission: Permission,
+        id: Uuid,
+        instance_id: Uuid,
+    ) -> Result<(SecurityGroup, Instance), Error> {
+        self.auth
+            .can(principal)
+            .perform(permission)
+            .over::<SecurityGroup>(&id)
+            .await?;
+        self.auth
+            .can(principal)
+            .perform(Permission::Update)
+            .over::<Instance>(&instance_id)
+            .await?;
+
+        let security_group = SecurityGroup::find(&self.db, id).await?;
+        let instance = Instance::find(&self.db, instance_id).await?;
+        if security_group.project_slug != instance.project_slug {
+            return Err(Error::SecurityGroupProjectMismatch { id, instance_id });
+        }
+
+        Ok((security_group, instance))
+    }
+
+    /// Applies the security groups of an instance to the firewall of the
+    /// hypervisor.
+    async fn apply(&self, instance: &Instance) -> Result<(), Error> {
+        let firewall = self.firewall(instance.id).await?;
+
+        self.connector(instance)
+            .await?
+            .apply_firewall(&instance.distant_id, firewall.as_ref())
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Applies the security groups of the instances a group is attached to.
+    async fn apply_attached(&self, id: Uuid) -> Result<(), Error> {
+        for attachment in self.attachments(id).await? {
+            let instance = Instance::find(&self.db, attachment.instance_id).await?;
+            self.apply(&instance).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Gets the firewall enforcing the security groups of an instance, none
+    /// when it has none attached.
+    async fn firewall(&self, instance_id: Uuid) -> Result<Option<Firewall>, Error> {
+        let mut attachments = InstanceSecurityGroup::query()
+            .select()
+            .r#where(InstanceSecurityGroup::INSTANCE_ID, "=", instance_id)
+            .get(&self.db)
+            .await?;
+        if attachments.is_empty() {
+            return Ok(None);
+        }
+        attachments.sort_by_key(|attachment| (attachment.created_at, attachment.id));
+
+        let mut rules = Vec::new();
+        for attachment in attachments {
+            rules.extend(
+                self.rules(attachment.security_group_id)
+                    .await?
+                    .iter()
+                    .map(SecurityGroupRule::firewall_rule),
+            );
+        }
+
+        Ok(Some(Firewall { rules }))
+    }
+
+    /// Lists the rules of a security group, oldest first.
+    async fn rules(&self, id: Uuid) -> Result<Vec<SecurityGroupRule>, Error> {
+        let mut rules = SecurityGroupRule::query()
+            .select()
+            .r#where(SecurityGroupRule::SECURITY_GROUP_ID, "=", id)
+            .get(&self.db)
+            .await?;
+        rules.sort_by_key(|rule| (rule.created_at, rule.id));
+
+        Ok(rules)
+    }
+
+    /// Lists the instances a security group is attached to.
+    async fn attachments(&self, id: Uuid) -> Result<Vec<InstanceSecurityGroup>, Error> {
+        InstanceSecurityGroup::query()
+            .select()
+            .r#where(InstanceSecurityGroup::SECURITY_GROUP_ID, "=", id)
+            .get(&self.db)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Finds the attachment of a security group to an instance.
+    async fn find_attachment(
+        &self,
+        id: Uuid,
+        instance_id: Uuid,
+    ) -> Result<Option<InstanceSecurityGroup>, Error> {
+        InstanceSecurityGroup::query()
+            .select()
+            .r#where(InstanceSecurityGroup::SECURITY_GROUP_ID, "=", id)
+            .r#where(InstanceSecurityGroup::INSTANCE_ID, "=", instance_id)
+            .first(&self.db)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Resolves the hypervisor connector of an instance.
+    async fn connector(&self, instance: &Instance) -> Result<impl HypervisorInstancesTrait, Error> {
+        let hypervisor = Hypervisor::find(&self.db, instance.hypervisor_id).await?;
+
+        hypervisor.resolve(&self.resolver)
+    }
+}
@@ -0,0 +1,313 @@
+//! Data volume management for compute instances.
+//!
+//! Provides the Volume data model and Volumes service for creating, attaching,
+//! detaching, growing and deleting block volumes independently of the boot
+//! disk of their instance, with authorization checks.
+
+use crate::Error;
+use crate::authorization::{Authorize, Permission, Principal, Relation, Relationship, Resource};
+use crate::compute::{Hypervisor, Instance, InstanceFactory, InstanceIdColumn};
+use crate::resourcemanager::{Project, Usage, enforce_quotas};
+use chrono::{DateTime, Utc};
+use fabrique::{Delete, Factory, Model, Persist, Query};
+use hypervisor::Resolver;
+use hypervisor::instance::Instances as HypervisorInstancesTrait;
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+
+/// Volume sizes are allocated by whole GiB on the hypervisor.
+const GIB: u64 = 1024 * 1024 * 1024;
+
+#[derive(Clone, Debug, Default, Factory, Model, Resource)]
+pub struct Volume {
+    /// Unique identifier for the volume
+    #[fabrique(primary_key)]
+    pub id: Uuid,
+    /// The instance owning this volume
+    #[fabrique(belongs_to = Instance)]
+    pub instance_id: Uuid,
+    /// The project this volume belongs to
+    pub project_slug: String,
+    /// Human-readable name, defined on the volume
+    pub name: String,
+    /// ID used by the hypervisor to identify this volume remotely
+    pub distant_id: String,
+    /// Device the volume is attached to, none when detached
+    pub device: Option<String>,
+    /// Size of the volume (in bytes)
+    pub size_bytes: i64,
+    // Creation time of the volume
+    pub created_at: DateTime<Utc>,
+    // Time of the volume last update
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug)]
+pub struct VolumeCreateRequest {
+    /// The instance to create the volume for.
+    pub instance_id: Uuid,
+
+    /// The volume human-readable name.
+    pub name: String,
+
+    /// The volume size in bytes, rounded up to the next GiB.
+    pub size_bytes: u64,
+}
+
+/// Service for managing data volumes.
+#[derive(Clone)]
+pub struct Volumes<A: Authorize> {
+    auth: A,
+    db: Pool<Postgres>,
+    resolver: Resolver,
+}
+
+impl<A: Authorize> Volumes<A> {
+    /// Creates a new volumes service.
+    pub fn new(auth: A, db: Pool<Postgres>, resolver: Resolver) -> Self {
+        Self { auth, db, resolver }
+    }
+
+    /// Lists all volumes accessible to the principal.
+    pub async fn list<P: Principal + Sync>(&mut self, principal: &P) -> Result<Vec<Volume>, Error> {
+        self.auth
+            .lookup::<Volume>()
+            .on_behalf_of(principal)
+            .with(Permission::Get)
+            .against(&self.db)
+            .await
+    }
+
+    /// Creates a volume and attaches it to an instance.
+    ///
+    /// The volume belongs to the project of the instance.
+    pub async fn create<P: Principal + Sync>(
+        &mut self,
+        principal: &P,
+        request: VolumeCreateRequest,
+    ) -> Result<Volume, Error> {
+        let instance = Instance::find(&self.db, request.instance_id).await?;
+
+        self.auth
+            .can(principal)
+            .perform(Permission::CreateVolume)
+            .over::<Project>(&instance.project_slug)
+            .await?;
+
+        let size_bytes = request.size_bytes.div_ceil(GIB) * GIB;
+
+        // Check the quotas in the transaction recording the volume, which
+        // holds the lock of the organization until the volume is recorded.
+        let mut tx = self.db.begin().await?;
+        enforce_quotas(
+            &mut tx,
+            &instance.project_slug,
+            &Usage {
+                disk_bytes: size_bytes as i64,
+                ..Default::default()
+            },
+        )
+        .await?;
+
+        let attached = self
+            .connector(&instance)
+            .await?
+            .create_volume(&instance.distant_id, size_bytes)
+            .await?;
+
+        let volume = Volume {
+            id: Uuid::new_v4(),
+            instance_id: instance.id,
+            project_slug: instance.project_slug.clone(),
+            name: request.name,
+            distant_id: attached.distant_id,
+            device: Some(attached.device),
+            size_bytes: size_bytes as i64,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+        .create(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        // Write the relationship synchronously to SpiceDB
+        self.auth
+            .write_relationship(&Relationship::new(
+                &Project::some(instance.project_slug),
+                Relation::Parent,
+                &volume,
+            ))
+            .await?;
+
+        Ok(volume)
+    }
+
+    /// Attaches a detached volume back to its instance.
+    pub async fn attach<P: Principal + Sync>(
+        &mut self,
+        principal: &P,
+        id: Uuid,
+    ) -> Result<Volume, Error> {
+        self.auth
+            .can(principal)
+            .perform(Permission::Attach)
+            .over::<Volume>(&id)
+            .await?;
+
+        let volume = Volume::find(&self.db, id).await?;
+        if volume.device.is_some() {
+            return Err(Error::VolumeAlreadyAttached(id));
+        }
+
+        let instance = Instance::find(&self.db, volume.instance_id).await?;
+        let attached = self
+            .connector(&instance)
+            .await?
+            .attach_volume(&instance.distant_id, &volume.distant_id)
+            .await?;
+
+        self.set_device(volume, Some(attached.device)).await
+    }
+
+    /// Detaches a volume from its instance, keeping its data.
+    pub async fn detach<P: Principal + Sync>(
+        &mut self,
+        principal: &P,
+        id: Uuid,
+    ) -> Result<Volume, Error> {
+        self.auth
+            .can(principal)
+            .perform(Permission::Detach)
+            .over::<Volume>(&id)
+            .await?;
+
+        let volume = Volume::find(&self.db, id).await?;
+        if volume.device.is_none() {
+            return Err(Error::VolumeNotAttached(id));
+        }
+
+        let instance = Instance::find(&self.db, volume.instance_id).await?;
+        self.connector(&instance)
+            .await?
+            .detach_volume(&instance.distant_id, &volume.distant_id)
+            .await?;
+
+        self.set_device(volume, None).await
+    }
+
+    /// Grows a volume to the given size.
+    ///
+    /// The volume must be attached, and can only grow.
+    pub async fn resize<P: Principal + Sync>(
+        &mut self,
+        principal: &P,
+        id: Uuid,
+        size_bytes: u64,
+    ) -> Result<Volume, Error> {
+        self.auth
+            .can(principal)
+            .perform(Permission::Resize)
+            .over::<Volume>(&id)
+            .await?;
+
+        let mut volume = Volume::find(&self.db, id).await?;
+        if volume.device.is_none() {
+            return Err(Error::VolumeNotAttached(id));
+        }
+
+        let size_bytes = size_bytes.div_ceil(GIB) * GIB;
+        if size_bytes < volume.size_bytes as u64 {
+            return Err(Error::VolumeShrink {
+                id,
+                size_bytes: volume.size_bytes as u64,
+                requested_bytes: size_bytes,
+            });
+        }
+
+        // Check the growth against the quotas in the transaction recording
+        // the new size, which holds the lock of the organization until then.
+        let mut tx = self.db.begin().await?;
+        enforce_quotas(
+            &mut tx,
+            &volume.project_slug,
+            &Usage {
+                disk_bytes: size_bytes as i64 - volume.size_bytes,
+                ..Default::default()
+            },
+        )
+        .await?;
+
+        let instance = Instance::find(&self.db, volume.instance_id).await?;
+        self.connector(&instance)
+            .await?
+            .resize_volume(&instance.distant_id, &volume.distant_id, size_bytes)
+            .await?;
+
+        Volume::update()
+            .set(Volume::SIZE_BYTES, size_bytes as i64)
+            .set(Volume::UPDATED_AT, Utc::now())
+            .r#where(Volume::ID, "=", id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        volume.size_bytes = size_bytes as i64;
+        Ok(volume)
+    }
+
+    /// Deletes a volume and its data, detaching it first if needed.
+    pub async fn delete<P: Principal + Sync>(
+        &mut self,
+        principal: &P,
+        id: Uuid,
+    ) -> Result<(), Error> {
+        self.auth
+            .can(principal)
+            .perform(Permission::Delete)
+            .over::<Volume>(&id)
+            .await?;
+
+        let volume = Volume::find(&self.db, id).await?;
+        let instance = Instance::find(&self.db, volume.instance_id).await?;
+        self.connector(&instance)
+            .await?
+            .delete_volume(&instance.distant_id, &volume.distant_id)
+            .await?;
+
+        Volume::destroy(&self.db, volume.id).await?;
+
+        self.auth
+            .delete_relationship(&Relationship::new(
+                &Project::some(volume.project_slug.clone()),
+                Relation::Parent,
+                &volume,
+            ))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Resolves the hypervisor connector of an instance.
+    async fn connector(&self, instance: &Instance) -> Result<impl HypervisorInstancesTrait, Error> {
+        let hypervisor = Hypervisor::find(&self.db, instance.hypervisor_id).await?;
+
+        hypervisor.resolve(&self.resolver)
+    }
+
+    /// Records the device a volume is attached to.
+    async fn set_device(
+        &self,
+        mut volume: Volume,
+        device: Option<String>,
+    ) -> Result<Volume, Error> {
+        Volume::update()
+            .set(Volume::DEVICE, device.clone())
+            .set(Volume::UPDATED_AT, Utc::now())
+            .r#where(Volume::ID, "=", volume.id)
+            .execute(&self.db)
+            .await?;
+
+        volume.device = device;
+        Ok(volume)
+    }
+}
@@ -1,5 +1,9 @@
 use crate::Error;
-use crate::authorization::{Authorize, Principal, Resource};
+use crate::authorization::{
+    Authorize, Permission, Platform, Principal, Relation, Relationship, Resource,
+};
+use crate::compute::require_admin;
+use crate::identity::{ServiceAccount, User};
 use chrono::{DateTime, Utc};
 use fabrique::{Factory, Model, Query};
 use sqlx::{Pool, Postgres};
@@ -23,7 +27,7 @@ pub struct Zone {
 
 #[derive(Clone)]
 pub struct Zones<Auth: Authorize> {
-    _auth: Auth,
+    auth: Auth,
     db: Pool<Postgres>,
 }
 
@@ -34,30 +38,82 @@ pub struct ZoneCreateRequest {
 impl<Auth: Authorize> Zones<Auth> {
     /// Creates a new zones service.
     pub fn new(auth: Auth, db: Pool<Postgres>) -> Self {
-        Self { _auth: auth, db }
+        Self { auth, db }
     }
 
-    /// Lists all zones accessible to the principal
-    pub async fn list<P: Principal>(&mut self, _principal: &P) -> Result<Vec<Zone>, Error> {
-        // self.auth
-        //     .can(principal)
-        //     .perform(Permission::List)
-        //     .over(&Zone::any())
-        //     .await?;
+    /// Lists all zones accessible to the principal.
+    ///
+    /// Zones are shared by every tenant, so every principal lists them all.
+    pub async fn list<P: Principal>(&mut self, principal: &P) -> Result<Vec<Zone>, Error> {
+        if principal.is_platform_admin() {
+            return Zone::all(&self.db).await.map_err(Into::into);
+        }
 
-        Zone::all(&self.db).await.map_err(Into::into)
+        self.auth
+            .lookup::<Zone>()
+            .on_behalf_of(principal)
+            .with(Permission::List)
+            .against(&self.db)
+            .await
     }
 
+    /// Creates a new zone, visible to every principal. Restricted to platform
+    /// admins.
     pub async fn create<P: Principal>(
         &mut self,
-        _principal: &P,
+        principal: &P,
         request: ZoneCreateRequest,
     ) -> Result<Zone, Error> {
-        Zone::factory()
+        require_admin(principal)?;
+        self.auth
+            .can::<P>(principal)
+            .perform(Permission::CreateZone)
+            .over::<Platform>(&Platform::ID.to_owned())
+            .await?;
+
+        let zone = Zone::factory()
             .id(Uuid::new_v4())
             .name(request.name)
             .create(&self.db)
-            .await
-            .map_err(Into::into)
+            .await?;
+
+        self.relate(&zone).await?;
+
+        Ok(zone)
+    }
+
+    /// Relates the zones to the platform and shares them with every
+    /// principal.
+    ///
+    /// Zones created before they were related through the authorization
+    /// backend are listed by no tenant until related here. Relationships are
+    /// idempotent, so this is safe to run on every boot.
+    pub async fn relate_all(&mut self) -> Result<(), Error> {
+        for zone in Zone::all(&self.db).await? {
+            self.relate(&zone).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn relate(&mut self, zone: &Zone) -> Result<(), Error> {
+        self.auth
+            .write_relationship(&Relationship::new(
+                &Platform::default(),
+                Relation::Platform,
+                zone,
+            ))
+            .await?;
+        self.auth
+            .write_relationship(&Relationship::wildcard::<User, _>(Relation::Viewer, zone))
+            .await?;
+        self.auth
+            .write_relationship(&Relationship::wildcard::<ServiceAccount, _>(
+                Relation::Viewer,
+                zone,
+            ))
+            .await?;
+
+        Ok(())
     }
 }
@@ -5,14 +5,25 @@
 //! from environment, returning an error if required variables are missing.
 
 use std::env;
+use std::sync::Arc;
+
+use frn_crypto::Kek;
+use hypervisor::{KubeVirtConfig, Resolver, SnippetsConfig, StoragesConfig};
 
 use crate::Error;
 
+/// Deterministic hypervisor token key for tests only. **Not a secret**: tests
+/// run against an isolated database and a fixed value keeps runs reproducible.
+pub(crate) const TEST_HYPERVISOR_TOKEN_KEK: [u8; frn_crypto::KEK_SIZE] = [36u8; 32];
+
 #[derive(Clone)]
 pub struct Config {
     pub auth_server_url: String,
     pub auth_server_token: String,
     pub database_url: String,
+    /// Resolver of the hypervisor connectors, holding the Key Encryption Key
+    /// wrapping their API tokens and the snippet storage configuration.
+    pub hypervisor_resolver: Resolver,
     pub oidc_url: String,
     pub root_organization: RootOrganization,
 }
@@ -37,6 +48,15 @@ impl Config {
             auth_server_url: read_env_var("SPICEDB_URL")?,
             auth_server_token: read_env_var("SPICEDB_GRPC_PRESHARED_KEY")?,
             database_url: read_env_var("DATABASE_URL")?,
+            hypervisor_resolver: Resolver::new(
+                Arc::new(
+                    Kek::from_base64(&read_env_var("HYPERVISOR_TOKEN_ENCRYPTION_KEY")?)
+                        .map_err(|err| Error::Other(err.to_string()))?,
+                ),
+                SnippetsConfig::from_env(),
+                StoragesConfig::from_env(),
+                KubeVirtConfig::from_env().map_err(|err| Error::Other(err.to_string()))?,
+            ),
             oidc_url: read_env_var("OIDC_URL")?,
             root_organization: RootOrganization {
                 name: env::var("ROOT_ORGANIZATION_NAME").unwrap_or("acme".to_owned()),
@@ -53,6 +73,14 @@ impl Config {
             auth_server_url: "".to_owned(),
             auth_server_token: "".to_owned(),
             database_url: "".to_owned(),
+            // The snippets are stored in the temporary volume a test points
+            // `PROXMOX_VOLUME_ABSOLUTE_PATH` at.
+            hypervisor_resolver: Resolver::new(
+                Arc::new(Kek::from_bytes(TEST_HYPERVISOR_TOKEN_KEK)),
+                SnippetsConfig::from_env(),
+                StoragesConfig::from_env(),
+                KubeVirtConfig::default(),
+            ),
             oidc_url: "".to_owned(),
             root_organization: RootOrganization {
                 name: "".to_owned(),
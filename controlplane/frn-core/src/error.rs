@@ -62,6 +62,145 @@ pub enum Error {
     #[error("no available hypervisors")]
     NoHypervisorsAvailable,
 
+    /// None of the candidate hypervisors could be reached.
+    #[error("no reachable hypervisors")]
+    HypervisorsUnreachable,
+
+    /// No hypervisor has room for the requested instance shape.
+    #[error(
+        "no hypervisor{} can host {cores} cores, {memory_bytes} bytes of memory and {disk_bytes} bytes of disk",
+        zone_id.map(|id| format!(" in zone {id}")).unwrap_or_default()
+    )]
+    InsufficientCapacity {
+        zone_id: Option<uuid::Uuid>,
+        cores: u32,
+        memory_bytes: u64,
+        disk_bytes: u64,
+    },
+
+    /// The requested disk image does not exist on the hypervisor.
+    #[error("image not found: {0}")]
+    ImageNotFound(String),
+
+    /// The instance has no backup policy.
+    #[error("backup policy not found for instance: {0}")]
+    BackupPolicyNotFound(uuid::Uuid),
+
+    /// The metrics window ends before it starts.
+    #[error("metrics window ends at {end} before it starts at {start}")]
+    InvalidMetricsWindow {
+        start: chrono::DateTime<chrono::Utc>,
+        end: chrono::DateTime<chrono::Utc>,
+    },
+
+    /// The guest credentials cannot be set on an instance.
+    #[error("invalid guest credentials: {0}")]
+    InvalidGuestCredentials(String),
+
+    /// The security group rule cannot be enforced by the hypervisor firewall.
+    #[error("invalid security group rule: {0}")]
+    InvalidSecurityGroupRule(String),
+
+    /// The security group is already attached to the instance.
+    #[error("security group {id} already attached to instance {instance_id}")]
+    SecurityGroupAlreadyAttached {
+        id: uuid::Uuid,
+        instance_id: uuid::Uuid,
+    },
+
+    /// The security group is not attached to the instance.
+    #[error("security group {id} not attached to instance {instance_id}")]
+    SecurityGroupNotAttached {
+        id: uuid::Uuid,
+        instance_id: uuid::Uuid,
+    },
+
+    /// Security groups only attach to the instances of their project.
+    #[error("security group {id} belongs to another project than instance {instance_id}")]
+    SecurityGroupProjectMismatch {
+        id: uuid::Uuid,
+        instance_id: uuid::Uuid,
+    },
+
+    /// The requested security group rule does not exist.
+    #[error("security group rule not found: {0}")]
+    SecurityGroupRuleNotFound(uuid::Uuid),
+
+    /// The snapshot name is not a valid name of the hypervisor.
+    #[error("invalid snapshot name: {0:?}")]
+    InvalidSnapshotName(String),
+
+    /// The requested instance snapshot does not exist.
+    #[error("snapshot not found: {0}")]
+    SnapshotNotFound(String),
+
+    /// The volume is already attached to its instance.
+    #[error("volume already attached: {0}")]
+    VolumeAlreadyAttached(uuid::Uuid),
+
+    /// The volumes of an instance do not follow it to another hypervisor.
+    #[error("instance {0} holds volumes and cannot move to another hypervisor")]
+    InstanceHoldsVolumes(uuid::Uuid),
+
+    /// The new shape of an instance cannot be applied as is by its
+    /// hypervisor.
+    #[error("invalid instance shape: {0}")]
+    InvalidInstanceShape(String),
+
+    /// The volume is not attached to any instance.
+    #[error("volume not attached: {0}")]
+    VolumeNotAttached(uuid::Uuid),
+
+    /// Volumes can only grow.
+    #[error("volume {id} cannot shrink from {size_bytes} to {requested_bytes} bytes")]
+    VolumeShrink {
+        id: uuid::Uuid,
+        size_bytes: u64,
+        requested_bytes: u64,
+    },
+
+    /// The image id is not the volume id of an image.
+    #[error("invalid image id: {0:?}")]
+    InvalidImageId(String),
+
+    /// The image cannot be imported from the URL.
+    #[error("invalid image url: {0}")]
+    InvalidImageUrl(String),
+
+    /// The cloud-init snippet cannot be extended with the configuration of
+    /// the instance.
+    #[error("invalid snippet: {0}")]
+    InvalidSnippet(String),
+
+    /// The requested zero trust network does not exist in the organization.
+    #[error("zero trust network not found: {0}")]
+    ZeroTrustNetworkNotFound(uuid::Uuid),
+
+    /// Every address of the overlay of the zero trust network is allocated.
+    #[error("no address left in the overlay of zero trust network {0}")]
+    OverlayExhausted(uuid::Uuid),
+
+    /// The quota limits cannot be set.
+    #[error("invalid quota: {0}")]
+    InvalidQuota(String),
+
+    /// The resources requested do not fit in the quota of their organization
+    /// or project.
+    #[error(
+        "quota exceeded for {resource} of {scope}: {requested} requested with {used} used out of {limit}"
+    )]
+    QuotaExceeded {
+        scope: String,
+        resource: &'static str,
+        limit: i64,
+        used: i64,
+        requested: i64,
+    },
+
+    /// The requested project does not exist in the organization.
+    #[error("project not found: {0}")]
+    ProjectNotFound(String),
+
     /// Serialization error.
     #[error("serialization: {0}")]
     Serialization(#[from] serde_json::Error),
@@ -90,6 +229,71 @@ impl From<Error> for tonic::Status {
             Error::SubjectMismatch => tonic::Status::unauthenticated(value.to_string()),
             Error::Forbidden => tonic::Status::permission_denied(value.to_string()),
             Error::SlugAlreadyExists(_) => tonic::Status::already_exists(value.to_string()),
+            Error::SnapshotNotFound(_) => tonic::Status::not_found(value.to_string()),
+            Error::InvalidSnapshotName(_) => tonic::Status::invalid_argument(value.to_string()),
+            Error::ProjectNotFound(_) => tonic::Status::not_found(value.to_string()),
+            Error::QuotaExceeded { .. } => tonic::Status::resource_exhausted(value.to_string()),
+            Error::InvalidQuota(_) => tonic::Status::invalid_argument(value.to_string()),
+            Error::InvalidSnippet(_) => tonic::Status::invalid_argument(value.to_string()),
+            Error::InvalidImageId(_) => tonic::Status::invalid_argument(value.to_string()),
+            Error::InvalidImageUrl(_) => tonic::Status::invalid_argument(value.to_string()),
+            Error::ZeroTrustNetworkNotFound(_) => tonic::Status::not_found(value.to_string()),
+            Error::OverlayExhausted(_) => tonic::Status::resource_exhausted(value.to_string()),
+            Error::BackupPolicyNotFound(_) => tonic::Status::not_found(value.to_string()),
+            Error::Hypervisor(hypervisor::Error::DistantBackupNotFound(_)) => {
+                tonic::Status::not_found(value.to_string())
+            }
+            Error::ImageNotFound(_) => tonic::Status::invalid_argument(value.to_string()),
+            Error::Hypervisor(hypervisor::Error::DistantImageNotFound(_)) => {
+                tonic::Status::not_found(value.to_string())
+            }
+            Error::Hypervisor(hypervisor::Error::UnsupportedImageFormat(_)) => {
+                tonic::Status::invalid_argument(value.to_string())
+            }
+            Error::Hypervisor(hypervisor::Error::DistantStorageNotFound(_)) => {
+                tonic::Status::failed_precondition(value.to_string())
+            }
+            Error::NoHypervisorsAvailable => tonic::Status::failed_precondition(value.to_string()),
+            Error::HypervisorsUnreachable => tonic::Status::unavailable(value.to_string()),
+            Error::InsufficientCapacity { .. } => {
+                tonic::Status::resource_exhausted(value.to_string())
+            }
+            Error::VolumeAlreadyAttached(_)
+            | Error::VolumeNotAttached(_)
+            | Error::InstanceHoldsVolumes(_) => {
+                tonic::Status::failed_precondition(value.to_string())
+            }
+            Error::VolumeShrink { .. } | Error::InvalidInstanceShape(_) => {
+                tonic::Status::invalid_argument(value.to_string())
+            }
+            Error::SecurityGroupAlreadyAttached { .. }
+            | Error::SecurityGroupNotAttached { .. }
+            | Error::SecurityGroupProjectMismatch { .. } => {
+                tonic::Status::failed_precondition(value.to_string())
+            }
+            Error::InvalidSecurityGroupRule(_) => {
+                tonic::Status::invalid_argument(value.to_string())
+            }
+            Error::SecurityGroupRuleNotFound(_) => tonic::Status::not_found(value.to_string()),
+            Error::InvalidMetricsWindow { .. } => {
+                tonic::Status::invalid_argument(value.to_string())
+            }
+            Error::InvalidGuestCredentials(_) => tonic::Status::invalid_argument(value.to_string()),
+            Error::Hypervisor(
+                hypervisor::Error::GuestAgentUnavailable | hypervisor::Error::InstanceNotRunning(_),
+            ) => tonic::Status::failed_precondition(value.to_string()),
+            Error::Hypervisor(hypervisor::Error::Unsupported(_)) => {
+                tonic::Status::unimplemented(value.to_string())
+            }
+            Error::Hypervisor(hypervisor::Error::NoFreeDevice(_)) => {
+                tonic::Status::resource_exhausted(value.to_string())
+            }
+            Error::Hypervisor(hypervisor::Error::InsufficientCapacity(_)) => {
+                tonic::Status::resource_exhausted(value.to_string())
+            }
+            Error::Hypervisor(hypervisor::Error::DistantNodeNotFound(_)) => {
+                tonic::Status::not_found(value.to_string())
+            }
             err => {
                 tracing::error!("internal error: {}", err);
                 tonic::Status::internal("internal error")
@@ -1,5 +1,5 @@
 use crate::Error;
-use crate::authorization::{Authorize, Principal};
+use crate::authorization::{Authorize, Platform, Principal, Relation, Relationship};
 use crate::resourcemanager::Organization;
 use fabrique::{Factory, Model, Persist, Query};
 use frn_derive::Resource;
@@ -145,13 +145,13 @@ impl Principal for User {
 
 #[derive(Clone)]
 pub struct Users<Auth: Authorize> {
-    _auth: Auth,
+    auth: Auth,
     db: Pool<Postgres>,
 }
 
 impl<Auth: Authorize> Users<Auth> {
     pub fn new(auth: Auth, db: Pool<Postgres>) -> Self {
-        Self { _auth: auth, db }
+        Self { auth, db }
     }
 
     pub async fn find_or_create<P: Principal>(
@@ -193,6 +193,9 @@ impl<Auth: Authorize> Users<Auth> {
     /// - if the user exists but is not an admin, it is promoted;
     /// - if the user already is an admin, nothing changes.
     ///
+    /// The admin is also related to the [`Platform`] in the authorization
+    /// backend, which grants it the platform-level permissions.
+    ///
     /// Authentication itself stays with the external IdP: this only designates
     /// which email is the platform administrator. When someone signs in through
     /// the IdP with that email, they are matched to this row (by email) and
@@ -201,8 +204,8 @@ impl<Auth: Authorize> Users<Auth> {
     /// # Errors
     /// Returns [`Error`] on any database failure.
     pub async fn initialize_root_admin(&self, email: String) -> Result<User, Error> {
-        match User::find_one_by_email(&self.db, &email).await? {
-            Some(user) if user.is_admin => Ok(user),
+        let admin = match User::find_one_by_email(&self.db, &email).await? {
+            Some(user) if user.is_admin => user,
             Some(user) => {
                 User::query()
                     .update()
@@ -210,24 +213,62 @@ impl<Auth: Authorize> Users<Auth> {
                     .r#where(User::ID, "=", user.id)
                     .execute(&self.db)
                     .await?;
-                Ok(User {
+                User {
                     is_admin: true,
                     ..user
-                })
+                }
             }
-            None => User::factory()
-                .id(Uuid::new_v4())
-                .email(email)
-                // Unpinned (NULL) subject: the bootstrap admin has not authenticated
-                // yet, so their OIDC subject is pinned on first login. The factory's
-                // Faker would otherwise fill `sub` with a random value that never
-                // matches the real token → the admin could never authenticate
-                // (SubjectMismatch), exactly as for an invited user.
-                .sub(None)
-                .is_admin(true)
-                .create(&self.db)
-                .await
-                .map_err(Into::into),
+            None => {
+                User::factory()
+                    .id(Uuid::new_v4())
+                    .email(email)
+                    // Unpinned (NULL) subject: the bootstrap admin has not authenticated
+                    // yet, so their OIDC subject is pinned on first login. The factory's
+                    // Faker would otherwise fill `sub` with a random value that never
+                    // matches the real token → the admin could never authenticate
+                    // (SubjectMismatch), exactly as for an invited user.
+                    .sub(None)
+                    .is_admin(true)
+                    .create(&self.db)
+                    .await?
+            }
+        };
+
+        self.auth
+            .clone()
+            .write_relationship(&Relationship::new(
+                &admin,
+                Relation::Admin,
+                &Platform::default(),
+            ))
+            .await?;
+
+        Ok(admin)
+    }
+
+    /// Relates every admin to the [`Platform`] in the authorization backend.
+    ///
+    /// Admins promoted before they were related to the platform are denied the
+    /// platform-level permissions until related here. Relationships are
+    /// idempotent, so this is safe to run on every boot.
+    pub async fn relate_admins(&self) -> Result<(), Error> {
+        let admins = User::query()
+            .select()
+            .r#where(User::IS_ADMIN, "=", true)
+            .get(&self.db)
+            .await?;
+
+        for admin in admins {
+            self.auth
+                .clone()
+                .write_relationship(&Relationship::new(
+                    &admin,
+                    Relation::Admin,
+                    &Platform::default(),
+                ))
+                .await?;
         }
+
+        Ok(())
     }
 }
@@ -270,11 +270,17 @@ pub enum ManagedServiceError {
     PlanRequiresPayment(String),
     #[error("workflow scheduling error: {0}")]
     Workflow(String),
+    #[error("{0}")]
+    QuotaExceeded(crate::Error),
 }
 
 impl From<crate::Error> for ManagedServiceError {
     fn from(err: crate::Error) -> Self {
-        ManagedServiceError::Authorization(err)
+        match err {
+            crate::Error::QuotaExceeded { .. } => ManagedServiceError::QuotaExceeded(err),
+            crate::Error::Database(err) => ManagedServiceError::Database(err),
+            err => ManagedServiceError::Authorization(err),
+        }
     }
 }
 
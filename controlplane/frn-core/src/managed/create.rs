@@ -5,7 +5,7 @@ use crate::managed::{
     ManagedServiceError, ManagedServiceInstance, ManagedServices, build_instance_labels,
     generate_namespace, generate_release_name, merge_helm_values,
 };
-use crate::resourcemanager::Project;
+use crate::resourcemanager::{Project, Usage, enforce_quotas};
 use crate::workflow::WorkflowScheduler;
 use fabrique::Query;
 use serde::{Deserialize, Serialize};
@@ -71,6 +71,18 @@ impl<A: Authorize> ManagedServices<A> {
             return Err(ManagedServiceError::PlanRequiresPayment(plan.slug.clone()));
         }
 
+        // Checked in the transaction recording the instance, so concurrent
+        // creations cannot both fit in the last slot of the quota.
+        enforce_quotas(
+            conn,
+            &request.project_slug,
+            &Usage {
+                managed_service_instances: 1,
+                ..Default::default()
+            },
+        )
+        .await?;
+
         let workflow_principal = Some(WorkflowPrincipal {
             principal_type: principal.name().to_owned(),
             principal_id: principal.id().to_string(),
@@ -83,7 +95,8 @@ impl<A: Authorize> ManagedServices<A> {
     /// Creates a managed service instance without authorization checks.
     ///
     /// Used by the billing webhook handler where authorization was already
-    /// verified at checkout session creation time.
+    /// verified at checkout session creation time. Quotas are not enforced
+    /// either, the instance being already paid for.
     pub async fn create_instance_unchecked<S: WorkflowScheduler<DeployManagedServiceParams>>(
         &self,
         conn: &mut PgConnection,
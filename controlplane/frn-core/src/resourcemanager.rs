@@ -1,5 +1,7 @@
 mod organization;
 mod project;
+mod quota;
 
 pub use organization::*;
 pub use project::*;
+pub use quota::*;
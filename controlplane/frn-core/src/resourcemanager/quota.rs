@@ -0,0 +1,402 @@
+//! Resource quotas.
+//!
+//! Provides the Quota data model and Quotas service for capping the compute
+//! and managed service resources of an organization, or of a single project,
+//! and reporting their consumption against those limits.
+//!
+//! Quotas are enforced by the services creating resources, within the
+//! transaction recording them: the organization row is locked while its
+//! usage is computed, so concurrent creations in the same organization cannot
+//! both squeeze under a limit.
+
+use crate::Error;
+use crate::authorization::{Authorize, Permission, Principal};
+use crate::compute::require_admin;
+use crate::resourcemanager::{Organization, Project};
+use chrono::{DateTime, Utc};
+use fabrique::{Factory, Model, Persist, Query};
+use sqlx::{PgConnection, Pool, Postgres};
+use uuid::Uuid;
+
+#[derive(Clone, Debug, Default, Factory, Model)]
+#[fabrique(table = "quotas")]
+pub struct Quota {
+    /// Unique identifier for the quota
+    #[fabrique(primary_key)]
+    pub id: Uuid,
+    /// The organization capped by this quota
+    pub organization_slug: String,
+    /// The project capped by this quota, the whole organization when unset
+    pub project_slug: Option<String>,
+    /// Maximum number of vCPUs of the instances, no limit when unset
+    pub max_cpu_cores: Option<i64>,
+    /// Maximum memory of the instances (in bytes), no limit when unset
+    pub max_memory_bytes: Option<i64>,
+    /// Maximum disk of the instances and their volumes (in bytes), no limit
+    /// when unset
+    pub max_disk_bytes: Option<i64>,
+    /// Maximum number of instances, no limit when unset
+    pub max_instances: Option<i64>,
+    /// Maximum number of managed service instances, no limit when unset
+    pub max_managed_service_instances: Option<i64>,
+    // Creation time of the quota
+    pub created_at: DateTime<Utc>,
+    // Time of the quota last update
+    pub updated_at: DateTime<Utc>,
+}
+
+/// An amount of each of the resources capped by quotas, either consumed or
+/// requested.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, sqlx::FromRow)]
+pub struct Usage {
+    /// The vCPUs of the instances.
+    pub cpu_cores: i64,
+    /// The memory of the instances, in bytes.
+    pub memory_bytes: i64,
+    /// The disk of the instances and of their volumes, in bytes.
+    pub disk_bytes: i64,
+    /// The number of instances.
+    pub instances: i64,
+    /// The number of managed service instances.
+    pub managed_service_instances: i64,
+}
+
+impl Quota {
+    /// Lists each resource with its limit and its amount in `usage`.
+    fn limits(&self, usage: &Usage) -> [(&'static str, Option<i64>, i64); 5] {
+        [
+            ("cpu cores", self.max_cpu_cores, usage.cpu_cores),
+            ("memory bytes", self.max_memory_bytes, usage.memory_bytes),
+            ("disk bytes", self.max_disk_bytes, usage.disk_bytes),
+            ("instances", self.max_instances, usage.instances),
+            (
+                "managed service instances",
+                self.max_managed_service_instances,
+                usage.managed_service_instances,
+            ),
+        ]
+    }
+
+    /// Describes what this quota caps, for error messages.
+    fn scope(&self) -> String {
+        match &self.project_slug {
+            Some(project_slug) => format!("project {}", project_slug),
+            None => format!("organization {}", self.organization_slug),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct QuotaSetRequest {
+    /// The organization to cap.
+    pub organization_slug: String,
+
+    /// The project of the organization to cap, the whole organization when
+    /// unset.
+    pub project_slug: Option<String>,
+
+    /// The maximum number of vCPUs, no limit when unset.
+    pub max_cpu_cores: Option<i64>,
+
+    /// The maximum memory in bytes, no limit when unset.
+    pub max_memory_bytes: Option<i64>,
+
+    /// The maximum disk in bytes, no limit when unset.
+    pub max_disk_bytes: Option<i64>,
+
+    /// The maximum number of instances, no limit when unset.
+    pub max_instances: Option<i64>,
+
+    /// The maximum number of managed service instances, no limit when unset.
+    pub max_managed_service_instances: Option<i64>,
+}
+
+/// The consumption of an organization or a project against its quota.
+#[derive(Clone, Debug)]
+pub struct QuotaUsage {
+    /// The organization consuming the resources.
+    pub organization_slug: String,
+
+    /// The project consuming the resources, the whole organization when unset.
+    pub project_slug: Option<String>,
+
+    /// The quota capping the resources, if any.
+    pub quota: Option<Quota>,
+
+    /// The resources consumed.
+    pub usage: Usage,
+}
+
+/// Service for managing the quotas of organizations and projects.
+#[derive(Clone)]
+pub struct Quotas<A: Authorize> {
+    auth: A,
+    db: Pool<Postgres>,
+}
+
+impl<A: Authorize> Quotas<A> {
+    /// Creates a new quotas service.
+    pub fn new(auth: A, db: Pool<Postgres>) -> Self {
+        Self { auth, db }
+    }
+
+    /// Sets the quota of an organization or one of its projects, replacing
+    /// the previous one. Restricted to platform admins.
+    ///
+    /// Lowering a limit under the current consumption is allowed: existing
+    /// resources are kept, new ones are refused until it is back under.
+    pub async fn set<P: Principal + Sync>(
+        &mut self,
+        principal: &P,
+        request: QuotaSetRequest,
+    ) -> Result<Quota, Error> {
+        require_admin(principal)?;
+
+        let limits = [
+            request.max_cpu_cores,
+            request.max_memory_bytes,
+            request.max_disk_bytes,
+            request.max_instances,
+            request.max_managed_service_instances,
+        ];
+        if limits.iter().flatten().any(|limit| *limit < 0) {
+            return Err(Error::InvalidQuota("limits cannot be negative".to_owned()));
+        }
+
+        Organization::find(&self.db, request.organization_slug.clone()).await?;
+        if let Some(project_slug) = &request.project_slug {
+            find_project(&self.db, &request.organization_slug, project_slug).await?;
+        }
+
+        match find_quota(
+            &self.db,
+            &request.organization_slug,
+            request.project_slug.as_deref(),
+        )
+        .await?
+        {
+            Some(quota) => {
+                Quota::update()
+                    .set(Quota::MAX_CPU_CORES, request.max_cpu_cores)
+                    .set(Quota::MAX_MEMORY_BYTES, request.max_memory_bytes)
+                    .set(Quota::MAX_DISK_BYTES, request.max_disk_bytes)
+                    .set(Quota::MAX_INSTANCES, request.max_instances)
+                    .set(
+                        Quota::MAX_MANAGED_SERVICE_INSTANCES,
+                        request.max_managed_service_instances,
+                    )
+                    .set(Quota::UPDATED_AT, Utc::now())
+                    .r#where(Quota::ID, "=", quota.id)
+                    .execute(&self.db)
+                    .await?;
+
+                Quota::find(&self.db, quota.id).await.map_err(Into::into)
+            }
+            None => Quota {
+                id: Uuid::new_v4(),
+                organization_slug: request.organization_slug,
+                project_slug: request.project_slug,
+                max_cpu_cores: request.max_cpu_cores,
+                max_memory_bytes: request.max_memory_bytes,
+                max_disk_bytes: request.max_disk_bytes,
+                max_instances: request.max_instances,
+                max_managed_service_instances: request.max_managed_service_instances,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            }
+            .create(&self.db)
+            .await
+            .map_err(Into::into),
+        }
+    }
+
+    /// Gets the consumption of an organization, or of one of its projects,
+    /// against its quota.
+    pub async fn usage<P: Principal + Sync>(
+        &mut self,
+        principal: &P,
+        organization_slug: &str,
+        project_slug: Option<&str>,
+    ) -> Result<QuotaUsage, Error> {
+        match project_slug {
+            Some(project_slug) => {
+                self.auth
+                    .can(principal)
+                    .perform(Permission::Get)
+                    .over::<Project>(&project_slug.to_owned())
+                    .await?;
+                find_project(&self.db, organization_slug, project_slug).await?;
+            }
+            None => {
+                self.auth
+                    .can(principal)
+                    .perform(Permission::Get)
+                    .over::<Organization>(&organization_slug.to_owned())
+                    .await?;
+            }
+        }
+
+        let mut conn = self.db.acquire().await?;
+
+        Ok(QuotaUsage {
+            organization_slug: organization_slug.to_owned(),
+            project_slug: project_slug.map(ToOwned::to_owned),
+            quota: find_quota(&mut *conn, organization_slug, project_slug).await?,
+            usage: usage(&mut conn, organization_slug, project_slug).await?,
+        })
+    }
+}
+
+/// Checks the quotas of a project and of its organization leave room for
+/// `demand`, failing with `QuotaExceeded` otherwise.
+///
+/// Locks the organization until the end of the transaction `conn` runs, which
+/// must also record the resources demanded for the check to hold.
+pub(crate) async fn enforce_quotas(
+    conn: &mut PgConnection,
+    project_slug: &str,
+    demand: &Usage,
+) -> Result<(), Error> {
+    enforce_scoped_quotas(conn, project_slug, demand, demand).await
+}
+
+/// Checks the quota of a project leaves room for `project_demand`, and the
+/// quota of its organization for `organization_demand`, failing with
+/// `QuotaExceeded` otherwise.
+///
+/// Resources moved to another project of their organization are new to the
+/// project only. Locks the organization as [`enforce_quotas`] does.
+pub(crate) async fn enforce_scoped_quotas(
+    conn: &mut PgConnection,
+    project_slug: &str,
+    organization_demand: &Usage,
+    project_demand: &Usage,
+) -> Result<(), Error> {
+    // Raw SQL: row locks are not expressible with fabrique. NO KEY UPDATE
+    // serializes the creations without blocking the inserts referencing the
+    // organization.
+    let organization_slug = sqlx::query_scalar::<_, String>(
+        r#"SELECT o.slug::text
+           FROM organizations o
+           JOIN projects p ON p.organization_slug = o.slug
+           WHERE p.slug = $1
+           FOR NO KEY UPDATE OF o"#,
+    )
+    .bind(project_slug)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let scopes = [
+        (None, organization_demand),
+        (Some(project_slug), project_demand),
+    ];
+    for (project_slug, demand) in scopes {
+        let Some(quota) = find_quota(&mut *conn, &organization_slug, project_slug).await? else {
+            continue;
+        };
+
+        let used = usage(conn, &organization_slug, project_slug).await?;
+        for ((resource, limit, used), (_, _, requested)) in
+            quota.limits(&used).into_iter().zip(quota.limits(demand))
+        {
+            if let Some(limit) = limit
+                && requested > 0
+                && used + requested > limit
+            {
+                return Err(Error::QuotaExceeded {
+                    scope: quota.scope(),
+                    resource,
+                    limit,
+                    used,
+                    requested,
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Finds the quota of an organization, or of one of its projects, if any.
+async fn find_quota<'e, E: sqlx::PgExecutor<'e>>(
+    executor: E,
+    organization_slug: &str,
+    project_slug: Option<&str>,
+) -> Result<Option<Quota>, Error> {
+    let query = Quota::query().select().r#where(
+        Quota::ORGANIZATION_SLUG,
+        "=",
+        organization_slug.to_owned(),
+    );
+
+    match project_slug {
+        Some(project_slug) => {
+            query
+                .r#where(Quota::PROJECT_SLUG, "=", Some(project_slug.to_owned()))
+                .first(executor)
+                .await
+        }
+        None => query.where_null(Quota::PROJECT_SLUG).first(executor).await,
+    }
+    .map_err(Into::into)
+}
+
+/// Finds a project of an organization.
+async fn find_project(
+    db: &Pool<Postgres>,
+    organization_slug: &str,
+    project_slug: &str,
+) -> Result<Project, Error> {
+    Project::query()
+        .select()
+        .r#where(Project::SLUG, "=", project_slug.to_owned())
+        .r#where(
+            Project::ORGANIZATION_SLUG,
+            "=",
+            organization_slug.to_owned(),
+        )
+        .first(db)
+        .await?
+        .ok_or_else(|| Error::ProjectNotFound(project_slug.to_owned()))
+}
+
+/// Computes the resources consumed by an organization, or by one of its
+/// projects.
+///
+/// Instances consume the shape they were created with, whatever their status,
+/// volumes their size, and managed service instances count until they are
+/// deleted.
+async fn usage(
+    conn: &mut PgConnection,
+    organization_slug: &str,
+    project_slug: Option<&str>,
+) -> Result<Usage, Error> {
+    // Raw SQL: aggregates across the compute and managed schemas are not
+    // expressible with fabrique.
+    sqlx::query_as::<_, Usage>(
+        r#"SELECT
+               COALESCE(SUM(i.max_cpu_cores), 0)::int8 AS cpu_cores,
+               COALESCE(SUM(i.max_memory_bytes), 0)::int8 AS memory_bytes,
+               (COALESCE(SUM(i.max_disk_bytes), 0)
+                + (SELECT COALESCE(SUM(v.size_bytes), 0)
+                   FROM volumes v
+                   JOIN projects vp ON vp.slug = v.project_slug
+                   WHERE vp.organization_slug = $1
+                     AND ($2::citext IS NULL OR vp.slug = $2)))::int8 AS disk_bytes,
+               COUNT(i.id) AS instances,
+               (SELECT COUNT(*)
+                FROM managed.service_instance_view s
+                WHERE s.organization_slug = $1
+                  AND ($2::citext IS NULL OR s.project_slug = $2)
+                  AND s.status <> 'deleted') AS managed_service_instances
+           FROM instances i
+           JOIN projects p ON p.slug = i.project_slug
+           WHERE p.organization_slug = $1
+             AND ($2::citext IS NULL OR p.slug = $2)"#,
+    )
+    .bind(organization_slug)
+    .bind(project_slug)
+    .fetch_one(conn)
+    .await
+    .map_err(Into::into)
+}
@@ -1,11 +1,13 @@
 use std::future::Future;
 
 use sqlx::PgConnection;
+use uuid::Uuid;
 
+/// Schedules the workflow of `P`, returning the id of its execution.
 pub trait WorkflowScheduler<P>: Clone + Send + Sync {
     fn schedule<'a>(
         &'a self,
         conn: &'a mut PgConnection,
         params: P,
-    ) -> impl Future<Output = Result<(), String>> + Send + 'a;
+    ) -> impl Future<Output = Result<Uuid, String>> + Send + 'a;
 }
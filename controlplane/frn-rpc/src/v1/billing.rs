@@ -71,6 +71,7 @@ fn billing_error_to_status(err: BillingError) -> Status {
                 | ManagedServiceError::PlanRequiresPayment(_) => {
                     Status::failed_precondition(message)
                 }
+                ManagedServiceError::QuotaExceeded(_) => Status::resource_exhausted(message),
                 _ => {
                     tracing::error!(error = %message, "internal billing error");
                     Status::internal("internal error")
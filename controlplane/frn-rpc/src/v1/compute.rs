@@ -1,13 +1,19 @@
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use crate::error::Error;
+use crate::timestamp::{from_timestamp, to_timestamp};
 use frn_core::authorization::Authorize;
 use frn_core::compute::{
-    HypervisorCreateRequest, Hypervisors as Service, InstanceCreateRequest, InstanceUpdateRequest,
+    BackupPolicyUpdateRequest, BackupRestoreRequest, HypervisorCreateRequest,
+    Hypervisors as Service, ImageImportRequest, InstanceCreateRequest, InstanceMetricsRequest,
+    InstanceSnapshotCreateRequest, InstanceUpdateRequest, SecurityGroupCreateRequest,
+    SecurityGroupRuleCreateRequest, VolumeCreateRequest,
 };
 use frn_core::identity::IAM;
+use hypervisor::instance::FirewallDirection;
 use sqlx::{Pool, Postgres, types::Uuid};
 use tonic::{Request, Response, Status};
+use workflow::scheduler::ComputeWorkflowScheduler;
 
 tonic::include_proto!("francenuage.fr.v1.compute");
 
@@ -20,6 +26,48 @@ impl From<frn_core::compute::Hypervisor> for Hypervisor {
             organization_slug: value.organization_slug.clone(),
             storage_name: value.storage_name,
             url: value.url,
+            health: HypervisorHealth::from(value.health).into(),
+            kind: HypervisorKind::from(value.kind).into(),
+            snippets_transport: SnippetsTransport::from(value.snippets_transport).into(),
+            last_health_check_at: value.last_health_check_at.map(to_timestamp),
+            version: value.version,
+            node_count: value.node_count as u32,
+            cpu_total: value.cpu_total as u32,
+            cpu_free: value.cpu_free,
+            memory_total_bytes: value.memory_total_bytes as u64,
+            memory_free_bytes: value.memory_free_bytes as u64,
+            storage_total_bytes: value.storage_total_bytes as u64,
+            storage_free_bytes: value.storage_free_bytes as u64,
+        }
+    }
+}
+
+impl From<frn_core::compute::HypervisorHealth> for HypervisorHealth {
+    fn from(value: frn_core::compute::HypervisorHealth) -> Self {
+        match value {
+            frn_core::compute::HypervisorHealth::Healthy => HypervisorHealth::Healthy,
+            frn_core::compute::HypervisorHealth::Unreachable => HypervisorHealth::Unreachable,
+            frn_core::compute::HypervisorHealth::Unknown => {
+                HypervisorHealth::UndefinedHypervisorHealth
+            }
+        }
+    }
+}
+
+impl From<hypervisor::HypervisorKind> for HypervisorKind {
+    fn from(value: hypervisor::HypervisorKind) -> Self {
+        match value {
+            hypervisor::HypervisorKind::Proxmox => HypervisorKind::Proxmox,
+            hypervisor::HypervisorKind::KubeVirt => HypervisorKind::Kubevirt,
+        }
+    }
+}
+
+impl From<hypervisor::SnippetsTransport> for SnippetsTransport {
+    fn from(value: hypervisor::SnippetsTransport) -> Self {
+        match value {
+            hypervisor::SnippetsTransport::Volume => SnippetsTransport::Volume,
+            hypervisor::SnippetsTransport::Sftp => SnippetsTransport::Sftp,
         }
     }
 }
@@ -58,6 +106,24 @@ impl<Auth: Authorize + 'static> hypervisors_server::Hypervisors for Hypervisors<
         Ok(Response::new(DetachHypervisorResponse {}))
     }
 
+    async fn drain_node(
+        &self,
+        request: Request<DrainHypervisorNodeRequest>,
+    ) -> Result<Response<DrainHypervisorNodeResponse>, Status> {
+        let principal = self.iam.principal(&request).await?;
+        let inner = request.into_inner();
+        let id = inner
+            .id
+            .parse::<Uuid>()
+            .map_err(|_| Error::MalformedId(inner.id))?;
+
+        let migrations = self.service.drain(&principal, id, &inner.node).await?;
+
+        Ok(Response::new(DrainHypervisorNodeResponse {
+            migrations: migrations.into_iter().map(Into::into).collect(),
+        }))
+    }
+
     async fn list(
         &self,
         request: Request<ListHypervisorsRequest>,
@@ -76,13 +142,27 @@ impl<Auth: Authorize + 'static> hypervisors_server::Hypervisors for Hypervisors<
         request: Request<RegisterHypervisorRequest>,
     ) -> Result<Response<RegisterHypervisorResponse>, Status> {
         let principal = self.iam.principal(&request).await?;
+        let inner = request.into_inner();
+        let kind = match inner.kind() {
+            HypervisorKind::UndefinedHypervisorKind | HypervisorKind::Proxmox => {
+                hypervisor::HypervisorKind::Proxmox
+            }
+            HypervisorKind::Kubevirt => hypervisor::HypervisorKind::KubeVirt,
+        };
+        let snippets_transport = match inner.snippets_transport() {
+            SnippetsTransport::UndefinedSnippetsTransport | SnippetsTransport::Volume => {
+                hypervisor::SnippetsTransport::Volume
+            }
+            SnippetsTransport::Sftp => hypervisor::SnippetsTransport::Sftp,
+        };
         let RegisterHypervisorRequest {
             authorization_token,
             organization_slug,
             storage_name,
             url,
             zone_id,
-        } = request.into_inner();
+            ..
+        } = inner;
 
         let hypervisor = self
             .service
@@ -91,7 +171,9 @@ impl<Auth: Authorize + 'static> hypervisors_server::Hypervisors for Hypervisors<
                 &principal,
                 HypervisorCreateRequest {
                     authorization_token,
+                    kind,
                     storage_name,
+                    snippets_transport,
                     url,
                     organization_slug,
                     zone_id: zone_id
@@ -146,16 +228,65 @@ impl From<frn_core::compute::Instance> for Instance {
     }
 }
 
+impl From<frn_core::compute::InstanceMigration> for InstanceMigration {
+    fn from(value: frn_core::compute::InstanceMigration) -> Self {
+        Self {
+            instance_id: value
+                .instance_id
+                .map(|id| id.to_string())
+                .unwrap_or_default(),
+            distant_id: value.distant_id,
+            source_node: value.source_node,
+            target_node: value.target_node,
+            hypervisor_id: value.hypervisor_id.to_string(),
+        }
+    }
+}
+
 impl From<hypervisor::instance::Status> for InstanceStatus {
     fn from(value: hypervisor::instance::Status) -> Self {
         match value {
+            hypervisor::instance::Status::Failed => InstanceStatus::Failed,
+            hypervisor::instance::Status::Missing => InstanceStatus::Missing,
+            hypervisor::instance::Status::Paused => InstanceStatus::Paused,
+            hypervisor::instance::Status::Provisioning => InstanceStatus::Provisioning,
             hypervisor::instance::Status::Running => InstanceStatus::Running,
             hypervisor::instance::Status::Stopped => InstanceStatus::Stopped,
+            hypervisor::instance::Status::Suspended => InstanceStatus::Suspended,
             hypervisor::instance::Status::Unknown => InstanceStatus::UndefinedInstanceStatus,
         }
     }
 }
 
+impl From<hypervisor::instance::Snapshot> for Snapshot {
+    fn from(value: hypervisor::instance::Snapshot) -> Self {
+        Self {
+            name: value.name,
+            description: value.description,
+            parent: value.parent,
+            include_memory: value.include_memory,
+            created_at: value
+                .created_at
+                .map(|secs| (UNIX_EPOCH + Duration::from_secs(secs)).into()),
+        }
+    }
+}
+
+impl From<hypervisor::instance::MetricsSample> for InstanceMetricsSample {
+    fn from(value: hypervisor::instance::MetricsSample) -> Self {
+        Self {
+            time: Some((UNIX_EPOCH + Duration::from_secs(value.time)).into()),
+            cpu_usage_percent: value.cpu_usage_percent,
+            memory_usage_bytes: value.memory_usage_bytes,
+            max_memory_bytes: value.max_memory_bytes,
+            disk_read_bytes_per_second: value.disk_read_bytes_per_second,
+            disk_write_bytes_per_second: value.disk_write_bytes_per_second,
+            network_in_bytes_per_second: value.network_in_bytes_per_second,
+            network_out_bytes_per_second: value.network_out_bytes_per_second,
+        }
+    }
+}
+
 #[tonic::async_trait]
 impl<Auth: Authorize + 'static> instances_server::Instances for Instances<Auth> {
     /// CreateInstance provisions a new instance based on the specified configuration.
@@ -176,12 +307,26 @@ impl<Auth: Authorize + 'static> instances_server::Instances for Instances<Auth>
             memory: request.memory_bytes,
             name: request.name,
             snippet: request.snippet,
+            zone_id: request
+                .zone_id
+                .map(|id| Uuid::parse_str(&id).map_err(|_| Error::MalformedId(id)))
+                .transpose()?,
+            zero_trust_network_id: request
+                .zero_trust_network_id
+                .map(|id| Uuid::parse_str(&id).map_err(|_| Error::MalformedId(id)))
+                .transpose()?,
         };
 
-        let instance = self.service.clone().create(&principal, request).await?;
+        let scheduler = ComputeWorkflowScheduler;
+        let provisioning = self
+            .service
+            .clone()
+            .create(&principal, &scheduler, request)
+            .await?;
 
         Ok(Response::new(CreateInstanceResponse {
-            instance: Some(instance.into()),
+            instance: Some(provisioning.instance.into()),
+            execution_id: provisioning.execution_id.to_string(),
         }))
     }
 
@@ -260,6 +405,79 @@ impl<Auth: Authorize + 'static> instances_server::Instances for Instances<Auth>
         Ok(Response::new(StopInstanceResponse {}))
     }
 
+    /// ShutdownInstance gracefully shuts a specific instance down.
+    /// Returns a response indicating success or a ProblemDetails on failure.
+    async fn shutdown(
+        &self,
+        request: Request<ShutdownInstanceRequest>,
+    ) -> Result<Response<ShutdownInstanceResponse>, Status> {
+        let principal = self.iam.principal(&request).await?;
+        let id = request.into_inner().id;
+        let id = Uuid::parse_str(&id).map_err(|_| Error::MalformedId(id))?;
+
+        self.service.clone().shutdown(&principal, id).await?;
+        Ok(Response::new(ShutdownInstanceResponse {}))
+    }
+
+    /// RebootInstance gracefully reboots a specific instance.
+    /// Returns a response indicating success or a ProblemDetails on failure.
+    async fn reboot(
+        &self,
+        request: Request<RebootInstanceRequest>,
+    ) -> Result<Response<RebootInstanceResponse>, Status> {
+        let principal = self.iam.principal(&request).await?;
+        let id = request.into_inner().id;
+        let id = Uuid::parse_str(&id).map_err(|_| Error::MalformedId(id))?;
+
+        self.service.clone().reboot(&principal, id).await?;
+        Ok(Response::new(RebootInstanceResponse {}))
+    }
+
+    /// ResetInstance hard resets a specific instance.
+    /// Returns a response indicating success or a ProblemDetails on failure.
+    async fn reset(
+        &self,
+        request: Request<ResetInstanceRequest>,
+    ) -> Result<Response<ResetInstanceResponse>, Status> {
+        let principal = self.iam.principal(&request).await?;
+        let id = request.into_inner().id;
+        let id = Uuid::parse_str(&id).map_err(|_| Error::MalformedId(id))?;
+
+        self.service.clone().reset(&principal, id).await?;
+        Ok(Response::new(ResetInstanceResponse {}))
+    }
+
+    /// SuspendInstance pauses or hibernates a specific instance.
+    /// Returns a response indicating success or a ProblemDetails on failure.
+    async fn suspend(
+        &self,
+        request: Request<SuspendInstanceRequest>,
+    ) -> Result<Response<SuspendInstanceResponse>, Status> {
+        let principal = self.iam.principal(&request).await?;
+        let inner = request.into_inner();
+        let id = Uuid::parse_str(&inner.id).map_err(|_| Error::MalformedId(inner.id))?;
+
+        self.service
+            .clone()
+            .suspend(&principal, id, inner.to_disk)
+            .await?;
+        Ok(Response::new(SuspendInstanceResponse {}))
+    }
+
+    /// ResumeInstance resumes a paused or suspended instance.
+    /// Returns a response indicating success or a ProblemDetails on failure.
+    async fn resume(
+        &self,
+        request: Request<ResumeInstanceRequest>,
+    ) -> Result<Response<ResumeInstanceResponse>, Status> {
+        let principal = self.iam.principal(&request).await?;
+        let id = request.into_inner().id;
+        let id = Uuid::parse_str(&id).map_err(|_| Error::MalformedId(id))?;
+
+        self.service.clone().resume(&principal, id).await?;
+        Ok(Response::new(ResumeInstanceResponse {}))
+    }
+
     /// Update modifies an existing instance's properties.
     /// Returns the updated instance or a ProblemDetails on failure.
     async fn update(
@@ -275,6 +493,8 @@ impl<Auth: Authorize + 'static> instances_server::Instances for Instances<Auth>
             id,
             name: inner.name,
             project_slug: inner.project_slug,
+            cores: inner.cpu_cores.map(|cores| cores as u8),
+            memory_bytes: inner.memory_bytes,
         };
 
         let instance = self.service.clone().update(&principal, request).await?;
@@ -283,6 +503,816 @@ impl<Auth: Authorize + 'static> instances_server::Instances for Instances<Auth>
             instance: Some(instance.into()),
         }))
     }
+
+    /// CreateSnapshot takes a snapshot of a specific instance.
+    /// Returns the created snapshot or a ProblemDetails on failure.
+    async fn create_snapshot(
+        &self,
+        request: Request<CreateSnapshotRequest>,
+    ) -> Result<Response<CreateSnapshotResponse>, Status> {
+        let principal = self.iam.principal(&request).await?;
+        let inner = request.into_inner();
+        let id = Uuid::parse_str(&inner.instance_id)
+            .map_err(|_| Error::MalformedId(inner.instance_id))?;
+
+        let request = InstanceSnapshotCreateRequest {
+            id,
+            name: inner.name,
+            description: inner.description,
+            include_memory: inner.include_memory,
+        };
+
+        let snapshot = self
+            .service
+            .clone()
+            .create_snapshot(&principal, request)
+            .await?;
+
+        Ok(Response::new(CreateSnapshotResponse {
+            snapshot: Some(snapshot.into()),
+        }))
+    }
+
+    /// ListSnapshots retrieves the snapshots of a specific instance.
+    /// Returns a collection of snapshot details.
+    async fn list_snapshots(
+        &self,
+        request: Request<ListSnapshotsRequest>,
+    ) -> Result<Response<ListSnapshotsResponse>, Status> {
+        let principal = self.iam.principal(&request).await?;
+        let id = request.into_inner().instance_id;
+        let id = Uuid::parse_str(&id).map_err(|_| Error::MalformedId(id))?;
+
+        let snapshots = self.service.clone().list_snapshots(&principal, id).await?;
+
+        Ok(Response::new(ListSnapshotsResponse {
+            snapshots: snapshots.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    /// RollbackSnapshot restores a specific instance to one of its snapshots.
+    /// Returns a response indicating success or a ProblemDetails on failure.
+    async fn rollback_snapshot(
+        &self,
+        request: Request<RollbackSnapshotRequest>,
+    ) -> Result<Response<RollbackSnapshotResponse>, Status> {
+        let principal = self.iam.principal(&request).await?;
+        let inner = request.into_inner();
+        let id = Uuid::parse_str(&inner.instance_id)
+            .map_err(|_| Error::MalformedId(inner.instance_id))?;
+
+        self.service
+            .clone()
+            .rollback_snapshot(&principal, id, &inner.name)
+            .await?;
+        Ok(Response::new(RollbackSnapshotResponse {}))
+    }
+
+    /// DeleteSnapshot deletes a snapshot of a specific instance.
+    /// Returns a response indicating success or a ProblemDetails on failure.
+    async fn delete_snapshot(
+        &self,
+        request: Request<DeleteSnapshotRequest>,
+    ) -> Result<Response<DeleteSnapshotResponse>, Status> {
+        let principal = self.iam.principal(&request).await?;
+        let inner = request.into_inner();
+        let id = Uuid::parse_str(&inner.instance_id)
+            .map_err(|_| Error::MalformedId(inner.instance_id))?;
+
+        self.service
+            .clone()
+            .delete_snapshot(&principal, id, &inner.name)
+            .await?;
+        Ok(Response::new(DeleteSnapshotResponse {}))
+    }
+
+    /// GetInstanceMetrics retrieves the usage history of a specific instance.
+    /// Returns the samples of the window or a ProblemDetails on failure.
+    async fn get_metrics(
+        &self,
+        request: Request<GetInstanceMetricsRequest>,
+    ) -> Result<Response<GetInstanceMetricsResponse>, Status> {
+        let principal = self.iam.principal(&request).await?;
+        let inner = request.into_inner();
+        let id = Uuid::parse_str(&inner.instance_id)
+            .map_err(|_| Error::MalformedId(inner.instance_id))?;
+
+        let end = match inner.end {
+            Some(end) => from_timestamp(&end)?,
+            None => chrono::Utc::now(),
+        };
+        let start = match inner.start {
+            Some(start) => from_timestamp(&start)?,
+            None => end - chrono::Duration::hours(1),
+        };
+
+        let request = InstanceMetricsRequest {
+            id,
+            start,
+            end,
+            resolution_seconds: inner.resolution_seconds as u64,
+        };
+
+        let metrics = self.service.clone().metrics(&principal, request).await?;
+
+        Ok(Response::new(GetInstanceMetricsResponse {
+            resolution_seconds: metrics.resolution_seconds as u32,
+            samples: metrics.samples.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    /// ResetPassword sets the password of a user of a specific instance.
+    /// Returns a response indicating success or a ProblemDetails on failure.
+    async fn reset_password(
+        &self,
+        request: Request<ResetInstancePasswordRequest>,
+    ) -> Result<Response<ResetInstancePasswordResponse>, Status> {
+        let principal = self.iam.principal(&request).await?;
+        let inner = request.into_inner();
+        let id = Uuid::parse_str(&inner.instance_id)
+            .map_err(|_| Error::MalformedId(inner.instance_id))?;
+
+        self.service
+            .clone()
+            .reset_password(&principal, id, &inner.username, &inner.password)
+            .await?;
+        Ok(Response::new(ResetInstancePasswordResponse {}))
+    }
+
+    /// InjectSshKey authorizes an SSH public key to log in as a user of a
+    /// specific instance.
+    /// Returns a response indicating success or a ProblemDetails on failure.
+    async fn inject_ssh_key(
+        &self,
+        request: Request<InjectInstanceSshKeyRequest>,
+    ) -> Result<Response<InjectInstanceSshKeyResponse>, Status> {
+        let principal = self.iam.principal(&request).await?;
+        let inner = request.into_inner();
+        let id = Uuid::parse_str(&inner.instance_id)
+            .map_err(|_| Error::MalformedId(inner.instance_id))?;
+
+        self.service
+            .clone()
+            .inject_ssh_key(&principal, id, &inner.username, &inner.public_key)
+            .await?;
+        Ok(Response::new(InjectInstanceSshKeyResponse {}))
+    }
+
+    /// Migrate moves a specific instance to another node of its hypervisor,
+    /// or to another hypervisor.
+    /// Returns the migration or a ProblemDetails on failure.
+    async fn migrate(
+        &self,
+        request: Request<MigrateInstanceRequest>,
+    ) -> Result<Response<MigrateInstanceResponse>, Status> {
+        let principal = self.iam.principal(&request).await?;
+        let inner = request.into_inner();
+        let id = Uuid::parse_str(&inner.id).map_err(|_| Error::MalformedId(inner.id))?;
+        let hypervisor_id = inner
+            .hypervisor_id
+            .map(|hypervisor_id| {
+                Uuid::parse_str(&hypervisor_id).map_err(|_| Error::MalformedId(hypervisor_id))
+            })
+            .transpose()?;
+
+        let migration = self
+            .service
+            .clone()
+            .migrate(&principal, id, hypervisor_id, inner.node.as_deref())
+            .await?;
+        Ok(Response::new(MigrateInstanceResponse {
+            migration: Some(migration.into()),
+        }))
+    }
+}
+
+#[derive(Clone)]
+pub struct InstanceBackups<A: Authorize> {
+    iam: IAM,
+    service: frn_core::compute::InstanceBackups<A>,
+}
+
+impl<A: Authorize> InstanceBackups<A> {
+    pub fn new(iam: IAM, service: frn_core::compute::InstanceBackups<A>) -> Self {
+        Self { iam, service }
+    }
+}
+
+impl From<hypervisor::instance::Backup> for Backup {
+    fn from(value: hypervisor::instance::Backup) -> Self {
+        Self {
+            id: value.id,
+            size_bytes: value.size_bytes,
+            notes: value.notes,
+            created_at: value
+                .created_at
+                .map(|secs| (UNIX_EPOCH + Duration::from_secs(secs)).into()),
+        }
+    }
+}
+
+impl From<frn_core::compute::BackupPolicy> for BackupPolicy {
+    fn from(value: frn_core::compute::BackupPolicy) -> Self {
+        Self {
+            instance_id: value.instance_id.to_string(),
+            schedule: value.schedule,
+            keep_last: value.keep_last as u32,
+            keep_daily: value.keep_daily as u32,
+            keep_weekly: value.keep_weekly as u32,
+            keep_monthly: value.keep_monthly as u32,
+            created_at: Some(to_timestamp(value.created_at)),
+            updated_at: Some(to_timestamp(value.updated_at)),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl<Auth: Authorize + 'static> instance_backups_server::InstanceBackups for InstanceBackups<Auth> {
+    /// ListBackups retrieves the backups of a specific instance.
+    /// Returns a collection of backup details, newest first.
+    async fn list(
+        &self,
+        request: Request<ListBackupsRequest>,
+    ) -> Result<Response<ListBackupsResponse>, Status> {
+        let principal = self.iam.principal(&request).await?;
+        let id = request.into_inner().instance_id;
+        let id = Uuid::parse_str(&id).map_err(|_| Error::MalformedId(id))?;
+
+        let backups = self.service.clone().list(&principal, id).await?;
+
+        Ok(Response::new(ListBackupsResponse {
+            backups: backups.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    /// CreateBackup backs a specific instance up right away.
+    /// Returns the created backup or a ProblemDetails on failure.
+    async fn create(
+        &self,
+        request: Request<CreateBackupRequest>,
+    ) -> Result<Response<CreateBackupResponse>, Status> {
+        let principal = self.iam.principal(&request).await?;
+        let id = request.into_inner().instance_id;
+        let id = Uuid::parse_str(&id).map_err(|_| Error::MalformedId(id))?;
+
+        let backup = self.service.clone().create(&principal, id).await?;
+
+        Ok(Response::new(CreateBackupResponse {
+            backup: Some(backup.into()),
+        }))
+    }
+
+    /// RestoreBackup provisions a new instance from a backup.
+    /// Returns the new instance or a ProblemDetails on failure.
+    async fn restore(
+        &self,
+        request: Request<RestoreBackupRequest>,
+    ) -> Result<Response<Instance>, Status> {
+        let principal = self.iam.principal(&request).await?;
+        let inner = request.into_inner();
+        let instance_id = Uuid::parse_str(&inner.instance_id)
+            .map_err(|_| Error::MalformedId(inner.instance_id))?;
+
+        let request = BackupRestoreRequest {
+            instance_id,
+            backup_id: inner.backup_id,
+            name: inner.name,
+        };
+
+        let instance = self.service.clone().restore(&principal, request).await?;
+
+        Ok(Response::new(instance.into()))
+    }
+
+    /// DeleteBackup removes a backup of a specific instance.
+    /// Returns an empty message or a ProblemDetails on failure.
+    async fn delete(
+        &self,
+        request: Request<DeleteBackupRequest>,
+    ) -> Result<Response<DeleteBackupResponse>, Status> {
+        let principal = self.iam.principal(&request).await?;
+        let inner = request.into_inner();
+        let instance_id = Uuid::parse_str(&inner.instance_id)
+            .map_err(|_| Error::MalformedId(inner.instance_id))?;
+
+        self.service
+            .clone()
+            .delete(&principal, instance_id, &inner.backup_id)
+            .await?;
+        Ok(Response::new(DeleteBackupResponse {}))
+    }
+
+    /// GetBackupPolicy retrieves the backup policy of a specific instance.
+    /// Returns the policy or a ProblemDetails when the instance has none.
+    async fn get_policy(
+        &self,
+        request: Request<GetBackupPolicyRequest>,
+    ) -> Result<Response<BackupPolicy>, Status> {
+        let principal = self.iam.principal(&request).await?;
+        let id = request.into_inner().instance_id;
+        let id = Uuid::parse_str(&id).map_err(|_| Error::MalformedId(id))?;
+
+        let policy = self.service.clone().get_policy(&principal, id).await?;
+
+        Ok(Response::new(policy.into()))
+    }
+
+    /// UpdateBackupPolicy sets the schedule and retention of the backups of a
+    /// specific instance. Returns the policy or a ProblemDetails on failure.
+    async fn update_policy(
+        &self,
+        request: Request<UpdateBackupPolicyRequest>,
+    ) -> Result<Response<BackupPolicy>, Status> {
+        let principal = self.iam.principal(&request).await?;
+        let inner = request.into_inner();
+        let instance_id = Uuid::parse_str(&inner.instance_id)
+            .map_err(|_| Error::MalformedId(inner.instance_id))?;
+
+        let keep = |count: u32| (count > 0).then_some(count);
+        let request = BackupPolicyUpdateRequest {
+            instance_id,
+            schedule: inner.schedule,
+            retention: hypervisor::instance::BackupRetention {
+                keep_last: keep(inner.keep_last),
+                keep_daily: keep(inner.keep_daily),
+                keep_weekly: keep(inner.keep_weekly),
+                keep_monthly: keep(inner.keep_monthly),
+            },
+        };
+
+        let policy = self
+            .service
+            .clone()
+            .update_policy(&principal, request)
+            .await?;
+
+        Ok(Response::new(policy.into()))
+    }
+
+    /// DeleteBackupPolicy stops the scheduled backups of a specific instance.
+    /// Returns an empty message or a ProblemDetails on failure.
+    async fn delete_policy(
+        &self,
+        request: Request<DeleteBackupPolicyRequest>,
+    ) -> Result<Response<DeleteBackupPolicyResponse>, Status> {
+        let principal = self.iam.principal(&request).await?;
+        let id = request.into_inner().instance_id;
+        let id = Uuid::parse_str(&id).map_err(|_| Error::MalformedId(id))?;
+
+        self.service.clone().delete_policy(&principal, id).await?;
+        Ok(Response::new(DeleteBackupPolicyResponse {}))
+    }
+}
+
+#[derive(Clone)]
+pub struct Volumes<A: Authorize> {
+    iam: IAM,
+    _pool: Pool<Postgres>,
+    service: frn_core::compute::Volumes<A>,
+}
+
+impl<A: Authorize> Volumes<A> {
+    pub fn new(iam: IAM, pool: Pool<Postgres>, service: frn_core::compute::Volumes<A>) -> Self {
+        Self {
+            iam,
+            _pool: pool,
+            service,
+        }
+    }
+}
+
+impl From<frn_core::compute::Volume> for Volume {
+    fn from(value: frn_core::compute::Volume) -> Self {
+        Self {
+            id: value.id.to_string(),
+            name: value.name,
+            size_bytes: value.size_bytes as u64,
+            device: value.device,
+            instance_id: value.instance_id.to_string(),
+            project_slug: value.project_slug,
+            created_at: Some(SystemTime::from(value.created_at).into()),
+            updated_at: Some(SystemTime::from(value.updated_at).into()),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl<Auth: Authorize + 'static> volumes_server::Volumes for Volumes<Auth> {
+    /// ListVolumes retrieves information about all accessible volumes.
+    async fn list(
+        &self,
+        request: Request<ListVolumesRequest>,
+    ) -> Result<Response<ListVolumesResponse>, Status> {
+        let principal = self.iam.principal(&request).await?;
+        let volumes = self.service.clone().list(&principal).await?;
+
+        Ok(Response::new(ListVolumesResponse {
+            volumes: volumes.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    /// CreateVolume allocates a new volume and attaches it to an instance.
+    /// Returns the created volume or a ProblemDetails on failure.
+    async fn create(
+        &self,
+        request: Request<CreateVolumeRequest>,
+    ) -> Result<Response<CreateVolumeResponse>, Status> {
+        let principal = self.iam.principal(&request).await?;
+        let inner = request.into_inner();
+        let instance_id = Uuid::parse_str(&inner.instance_id)
+            .map_err(|_| Error::MalformedId(inner.instance_id))?;
+
+        let request = VolumeCreateRequest {
+            instance_id,
+            name: inner.name,
+            size_bytes: inner.size_bytes,
+        };
+
+        let volume = self.service.clone().create(&principal, request).await?;
+
+        Ok(Response::new(CreateVolumeResponse {
+            volume: Some(volume.into()),
+        }))
+    }
+
+    /// AttachVolume plugs a detached volume back into its instance.
+    /// Returns the attached volume or a ProblemDetails on failure.
+    async fn attach(
+        &self,
+        request: Request<AttachVolumeRequest>,
+    ) -> Result<Response<AttachVolumeResponse>, Status> {
+        let principal = self.iam.principal(&request).await?;
+        let id = request.into_inner().id;
+        let id = Uuid::parse_str(&id).map_err(|_| Error::MalformedId(id))?;
+
+        let volume = self.service.clone().attach(&principal, id).await?;
+
+        Ok(Response::new(AttachVolumeResponse {
+            volume: Some(volume.into()),
+        }))
+    }
+
+    /// DetachVolume unplugs a volume from its instance.
+    /// Returns the detached volume or a ProblemDetails on failure.
+    async fn detach(
+        &self,
+        request: Request<DetachVolumeRequest>,
+    ) -> Result<Response<DetachVolumeResponse>, Status> {
+        let principal = self.iam.principal(&request).await?;
+        let id = request.into_inner().id;
+        let id = Uuid::parse_str(&id).map_err(|_| Error::MalformedId(id))?;
+
+        let volume = self.service.clone().detach(&principal, id).await?;
+
+        Ok(Response::new(DetachVolumeResponse {
+            volume: Some(volume.into()),
+        }))
+    }
+
+    /// ResizeVolume grows a specific volume.
+    /// Returns the resized volume or a ProblemDetails on failure.
+    async fn resize(
+        &self,
+        request: Request<ResizeVolumeRequest>,
+    ) -> Result<Response<ResizeVolumeResponse>, Status> {
+        let principal = self.iam.principal(&request).await?;
+        let inner = request.into_inner();
+        let id = Uuid::parse_str(&inner.id).map_err(|_| Error::MalformedId(inner.id))?;
+
+        let volume = self
+            .service
+            .clone()
+            .resize(&principal, id, inner.size_bytes)
+            .await?;
+
+        Ok(Response::new(ResizeVolumeResponse {
+            volume: Some(volume.into()),
+        }))
+    }
+
+    /// DeleteVolume removes a specific volume and its data.
+    /// Returns an empty message or a ProblemDetails on failure.
+    async fn delete(
+        &self,
+        request: Request<DeleteVolumeRequest>,
+    ) -> Result<Response<DeleteVolumeResponse>, Status> {
+        let principal = self.iam.principal(&request).await?;
+        let id = request.into_inner().id;
+        let id = Uuid::parse_str(&id).map_err(|_| Error::MalformedId(id))?;
+
+        self.service.clone().delete(&principal, id).await?;
+        Ok(Response::new(DeleteVolumeResponse {}))
+    }
+}
+
+#[derive(Clone)]
+pub struct SecurityGroups<A: Authorize> {
+    iam: IAM,
+    service: frn_core::compute::SecurityGroups<A>,
+}
+
+impl<A: Authorize> SecurityGroups<A> {
+    pub fn new(iam: IAM, service: frn_core::compute::SecurityGroups<A>) -> Self {
+        Self { iam, service }
+    }
+}
+
+impl From<frn_core::compute::SecurityGroup> for SecurityGroup {
+    fn from(value: frn_core::compute::SecurityGroup) -> Self {
+        Self {
+            id: value.id.to_string(),
+            name: value.name,
+            description: value.description,
+            project_slug: value.project_slug,
+            created_at: Some(SystemTime::from(value.created_at).into()),
+            updated_at: Some(SystemTime::from(value.updated_at).into()),
+        }
+    }
+}
+
+impl From<FirewallDirection> for SecurityGroupRuleDirection {
+    fn from(value: FirewallDirection) -> Self {
+        match value {
+            FirewallDirection::Ingress => SecurityGroupRuleDirection::Ingress,
+            FirewallDirection::Egress => SecurityGroupRuleDirection::Egress,
+        }
+    }
+}
+
+impl From<frn_core::compute::SecurityGroupRule> for SecurityGroupRule {
+    fn from(value: frn_core::compute::SecurityGroupRule) -> Self {
+        Self {
+            id: value.id.to_string(),
+            direction: SecurityGroupRuleDirection::from(value.direction).into(),
+            protocol: value.protocol,
+            port_range: value.port_range,
+            cidr: value.cidr,
+            security_group_id: value.security_group_id.to_string(),
+            created_at: Some(SystemTime::from(value.created_at).into()),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl<Auth: Authorize + 'static> security_groups_server::SecurityGroups for SecurityGroups<Auth> {
+    /// ListSecurityGroups retrieves information about all accessible security
+    /// groups.
+    async fn list(
+        &self,
+        request: Request<ListSecurityGroupsRequest>,
+    ) -> Result<Response<ListSecurityGroupsResponse>, Status> {
+        let principal = self.iam.principal(&request).await?;
+        let security_groups = self.service.clone().list(&principal).await?;
+
+        Ok(Response::new(ListSecurityGroupsResponse {
+            security_groups: security_groups.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    /// CreateSecurityGroup provisions an empty security group in a project.
+    /// Returns the created security group or a ProblemDetails on failure.
+    async fn create(
+        &self,
+        request: Request<CreateSecurityGroupRequest>,
+    ) -> Result<Response<CreateSecurityGroupResponse>, Status> {
+        let principal = self.iam.principal(&request).await?;
+        let inner = request.into_inner();
+
+        let request = SecurityGroupCreateRequest {
+            project_slug: inner.project_slug,
+            name: inner.name,
+            description: inner.description,
+        };
+
+        let security_group = self.service.clone().create(&principal, request).await?;
+
+        Ok(Response::new(CreateSecurityGroupResponse {
+            security_group: Some(security_group.into()),
+        }))
+    }
+
+    /// DeleteSecurityGroup removes a specific security group.
+    /// Returns an empty message or a ProblemDetails on failure.
+    async fn delete(
+        &self,
+        request: Request<DeleteSecurityGroupRequest>,
+    ) -> Result<Response<DeleteSecurityGroupResponse>, Status> {
+        let principal = self.iam.principal(&request).await?;
+        let id = request.into_inner().id;
+        let id = Uuid::parse_str(&id).map_err(|_| Error::MalformedId(id))?;
+
+        self.service.clone().delete(&principal, id).await?;
+        Ok(Response::new(DeleteSecurityGroupResponse {}))
+    }
+
+    /// ListSecurityGroupRules retrieves the rules of a specific security
+    /// group.
+    async fn list_rules(
+        &self,
+        request: Request<ListSecurityGroupRulesRequest>,
+    ) -> Result<Response<ListSecurityGroupRulesResponse>, Status> {
+        let principal = self.iam.principal(&request).await?;
+        let id = request.into_inner().security_group_id;
+        let id = Uuid::parse_str(&id).map_err(|_| Error::MalformedId(id))?;
+
+        let rules = self.service.clone().list_rules(&principal, id).await?;
+
+        Ok(Response::new(ListSecurityGroupRulesResponse {
+            rules: rules.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    /// AddSecurityGroupRule adds a rule to a specific security group.
+    /// Returns the added rule or a ProblemDetails on failure.
+    async fn add_rule(
+        &self,
+        request: Request<AddSecurityGroupRuleRequest>,
+    ) -> Result<Response<AddSecurityGroupRuleResponse>, Status> {
+        let principal = self.iam.principal(&request).await?;
+        let inner = request.into_inner();
+        let security_group_id = Uuid::parse_str(&inner.security_group_id)
+            .map_err(|_| Error::MalformedId(inner.security_group_id.clone()))?;
+
+        let direction = match inner.direction() {
+            SecurityGroupRuleDirection::Ingress => FirewallDirection::Ingress,
+            SecurityGroupRuleDirection::Egress => FirewallDirection::Egress,
+            SecurityGroupRuleDirection::UndefinedSecurityGroupRuleDirection => {
+                return Err(Error::InvalidInput("rule direction is required".to_owned()).into());
+            }
+        };
+
+        let request = SecurityGroupRuleCreateRequest {
+            security_group_id,
+            direction,
+            protocol: inner.protocol,
+            port_range: inner.port_range,
+            cidr: inner.cidr,
+        };
+
+        let rule = self.service.clone().add_rule(&principal, request).await?;
+
+        Ok(Response::new(AddSecurityGroupRuleResponse {
+            rule: Some(rule.into()),
+        }))
+    }
+
+    /// RemoveSecurityGroupRule removes a rule from a specific security group.
+    /// Returns an empty message or a ProblemDetails on failure.
+    async fn remove_rule(
+        &self,
+        request: Request<RemoveSecurityGroupRuleRequest>,
+    ) -> Result<Response<RemoveSecurityGroupRuleResponse>, Status> {
+        let principal = self.iam.principal(&request).await?;
+        let inner = request.into_inner();
+        let id = Uuid::parse_str(&inner.security_group_id)
+            .map_err(|_| Error::MalformedId(inner.security_group_id))?;
+        let rule_id =
+            Uuid::parse_str(&inner.rule_id).map_err(|_| Error::MalformedId(inner.rule_id))?;
+
+        self.service
+            .clone()
+            .remove_rule(&principal, id, rule_id)
+            .await?;
+        Ok(Response::new(RemoveSecurityGroupRuleResponse {}))
+    }
+
+    /// AttachSecurityGroup applies a specific security group to an instance.
+    /// Returns an empty message or a ProblemDetails on failure.
+    async fn attach(
+        &self,
+        request: Request<AttachSecurityGroupRequest>,
+    ) -> Result<Response<AttachSecurityGroupResponse>, Status> {
+        let principal = self.iam.principal(&request).await?;
+        let inner = request.into_inner();
+        let id = Uuid::parse_str(&inner.security_group_id)
+            .map_err(|_| Error::MalformedId(inner.security_group_id))?;
+        let instance_id = Uuid::parse_str(&inner.instance_id)
+            .map_err(|_| Error::MalformedId(inner.instance_id))?;
+
+        self.service
+            .clone()
+            .attach(&principal, id, instance_id)
+            .await?;
+        Ok(Response::new(AttachSecurityGroupResponse {}))
+    }
+
+    /// DetachSecurityGroup lifts a specific security group from an instance.
+    /// Returns an empty message or a ProblemDetails on failure.
+    async fn detach(
+        &self,
+        request: Request<DetachSecurityGroupRequest>,
+    ) -> Result<Response<DetachSecurityGroupResponse>, Status> {
+        let principal = self.iam.principal(&request).await?;
+        let inner = request.into_inner();
+        let id = Uuid::parse_str(&inner.security_group_id)
+            .map_err(|_| Error::MalformedId(inner.security_group_id))?;
+        let instance_id = Uuid::parse_str(&inner.instance_id)
+            .map_err(|_| Error::MalformedId(inner.instance_id))?;
+
+        self.service
+            .clone()
+            .detach(&principal, id, instance_id)
+            .await?;
+        Ok(Response::new(DetachSecurityGroupResponse {}))
+    }
+}
+
+#[derive(Clone)]
+pub struct Images<A: Authorize> {
+    iam: IAM,
+    service: frn_core::compute::Images<A>,
+}
+
+impl<A: Authorize> Images<A> {
+    pub fn new(iam: IAM, service: frn_core::compute::Images<A>) -> Self {
+        Self { iam, service }
+    }
+}
+
+impl From<hypervisor::instance::ImageKind> for ImageKind {
+    fn from(value: hypervisor::instance::ImageKind) -> Self {
+        match value {
+            hypervisor::instance::ImageKind::Iso => ImageKind::Iso,
+            hypervisor::instance::ImageKind::Disk => ImageKind::Disk,
+        }
+    }
+}
+
+impl From<hypervisor::instance::Image> for Image {
+    fn from(value: hypervisor::instance::Image) -> Self {
+        Self {
+            id: value.id,
+            name: value.name,
+            kind: ImageKind::from(value.kind).into(),
+            format: value.format,
+            size_bytes: value.size_bytes,
+            created_at: value
+                .created_at
+                .map(|secs| (UNIX_EPOCH + Duration::from_secs(secs)).into()),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl<Auth: Authorize + 'static> images_server::Images for Images<Auth> {
+    /// ListImages retrieves the images available on a hypervisor.
+    /// Returns a collection of image details.
+    async fn list(
+        &self,
+        request: Request<ListImagesRequest>,
+    ) -> Result<Response<ListImagesResponse>, Status> {
+        let principal = self.iam.principal(&request).await?;
+        let id = request.into_inner().hypervisor_id;
+        let id = Uuid::parse_str(&id).map_err(|_| Error::MalformedId(id))?;
+
+        let images = self.service.clone().list(&principal, id).await?;
+
+        Ok(Response::new(ListImagesResponse {
+            images: images.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    /// ImportImage downloads an image into the storage of a hypervisor.
+    /// Returns the imported image or a ProblemDetails on failure.
+    async fn import(
+        &self,
+        request: Request<ImportImageRequest>,
+    ) -> Result<Response<ImportImageResponse>, Status> {
+        let principal = self.iam.principal(&request).await?;
+        let inner = request.into_inner();
+        let hypervisor_id = Uuid::parse_str(&inner.hypervisor_id)
+            .map_err(|_| Error::MalformedId(inner.hypervisor_id))?;
+
+        let request = ImageImportRequest {
+            hypervisor_id,
+            url: inner.url,
+            name: inner.name,
+        };
+
+        let image = self.service.clone().import(&principal, request).await?;
+
+        Ok(Response::new(ImportImageResponse {
+            image: Some(image.into()),
+        }))
+    }
+
+    /// DeleteImage removes an image from the storage of a hypervisor.
+    /// Returns an empty message or a ProblemDetails on failure.
+    async fn delete(
+        &self,
+        request: Request<DeleteImageRequest>,
+    ) -> Result<Response<DeleteImageResponse>, Status> {
+        let principal = self.iam.principal(&request).await?;
+        let inner = request.into_inner();
+        let hypervisor_id = Uuid::parse_str(&inner.hypervisor_id)
+            .map_err(|_| Error::MalformedId(inner.hypervisor_id))?;
+
+        self.service
+            .clone()
+            .delete(&principal, hypervisor_id, &inner.id)
+            .await?;
+        Ok(Response::new(DeleteImageResponse {}))
+    }
 }
 
 impl From<frn_core::compute::Zone> for Zone {
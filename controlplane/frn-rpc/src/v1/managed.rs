@@ -164,6 +164,7 @@ fn managed_error_to_status(err: ManagedServiceError) -> Status {
             Status::failed_precondition(message)
         }
         ManagedServiceError::PlanServiceMismatch { .. } => Status::invalid_argument(message),
+        ManagedServiceError::QuotaExceeded(_) => Status::resource_exhausted(message),
     }
 }
 
@@ -1,5 +1,6 @@
+use frn_core::authorization::Authorize;
 use frn_core::identity::IAM;
-use frn_core::{authorization::Authorize, resourcemanager::ProjectCreateRequest};
+use frn_core::resourcemanager::{ProjectCreateRequest, QuotaSetRequest};
 use sqlx::{Pool, Postgres};
 use std::time::SystemTime;
 use tonic::{Request, Response, Status};
@@ -146,3 +147,113 @@ impl<Auth: Authorize + 'static> projects_server::Projects for Projects<Auth> {
         }))
     }
 }
+
+/// Convert between model and protobuf types
+impl From<frn_core::resourcemanager::Quota> for Quota {
+    fn from(quota: frn_core::resourcemanager::Quota) -> Self {
+        Self {
+            organization_slug: quota.organization_slug,
+            project_slug: quota.project_slug,
+            max_cpu_cores: quota.max_cpu_cores,
+            max_memory_bytes: quota.max_memory_bytes,
+            max_disk_bytes: quota.max_disk_bytes,
+            max_instances: quota.max_instances,
+            max_managed_service_instances: quota.max_managed_service_instances,
+            created_at: Some(SystemTime::from(quota.created_at).into()),
+            updated_at: Some(SystemTime::from(quota.updated_at).into()),
+        }
+    }
+}
+
+/// Convert between model and protobuf types
+impl From<frn_core::resourcemanager::QuotaUsage> for QuotaUsage {
+    fn from(usage: frn_core::resourcemanager::QuotaUsage) -> Self {
+        let quota = usage.quota.unwrap_or_default();
+        let resource = |used, limit| Some(ResourceUsage { used, limit });
+
+        Self {
+            organization_slug: usage.organization_slug,
+            project_slug: usage.project_slug,
+            cpu_cores: resource(usage.usage.cpu_cores, quota.max_cpu_cores),
+            memory_bytes: resource(usage.usage.memory_bytes, quota.max_memory_bytes),
+            disk_bytes: resource(usage.usage.disk_bytes, quota.max_disk_bytes),
+            instances: resource(usage.usage.instances, quota.max_instances),
+            managed_service_instances: resource(
+                usage.usage.managed_service_instances,
+                quota.max_managed_service_instances,
+            ),
+        }
+    }
+}
+
+pub struct Quotas<A: Authorize> {
+    iam: IAM,
+    quotas: frn_core::resourcemanager::Quotas<A>,
+}
+
+impl<Auth: Authorize> Quotas<Auth> {
+    pub fn new(iam: IAM, quotas: frn_core::resourcemanager::Quotas<Auth>) -> Self {
+        Self { iam, quotas }
+    }
+}
+
+#[tonic::async_trait]
+impl<Auth: Authorize + 'static> quotas_server::Quotas for Quotas<Auth> {
+    async fn set(
+        &self,
+        request: Request<SetQuotaRequest>,
+    ) -> Result<Response<SetQuotaResponse>, Status> {
+        let principal = self.iam.principal(&request).await?;
+        let SetQuotaRequest {
+            organization_slug,
+            project_slug,
+            max_cpu_cores,
+            max_memory_bytes,
+            max_disk_bytes,
+            max_instances,
+            max_managed_service_instances,
+        } = request.into_inner();
+
+        let request = QuotaSetRequest {
+            organization_slug,
+            project_slug,
+            max_cpu_cores,
+            max_memory_bytes,
+            max_disk_bytes,
+            max_instances,
+            max_managed_service_instances,
+        };
+        let quota = self
+            .quotas
+            .clone()
+            .set(&principal, request)
+            .await
+            .map_err(Status::from)?;
+
+        Ok(Response::new(SetQuotaResponse {
+            quota: Some(quota.into()),
+        }))
+    }
+
+    async fn get_usage(
+        &self,
+        request: Request<GetQuotaUsageRequest>,
+    ) -> Result<Response<GetQuotaUsageResponse>, Status> {
+        let principal = self.iam.principal(&request).await?;
+        let GetQuotaUsageRequest {
+            organization_slug,
+            project_slug,
+        } = request.into_inner();
+
+        let usage = self
+            .quotas
+            .clone()
+            .usage(&principal, &organization_slug, project_slug.as_deref())
+            .await
+            .map_err(Status::from)?;
+
+        Ok(Response::new(GetQuotaUsageResponse {
+            usage: Some(usage.into()),
+        }))
+    }
+}
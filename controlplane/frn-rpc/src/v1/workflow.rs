@@ -1,6 +1,8 @@
 use crate::auth::authenticate_bearer;
 pub use crate::timestamp::{from_timestamp, to_timestamp};
 use chrono::Utc;
+use frn_core::authorization::Resource;
+use frn_core::identity::IAM;
 use sqlx::{Pool, Postgres};
 use std::fmt::Display;
 use tonic::{Request, Response, Status};
@@ -17,11 +19,16 @@ tonic::include_proto!("francenuage.fr.v1.workflow");
 pub struct WorkflowEngine {
     pool: Pool<Postgres>,
     worker_token: String,
+    iam: IAM,
 }
 
 impl WorkflowEngine {
-    pub fn new(pool: Pool<Postgres>, worker_token: String) -> Self {
-        Self { pool, worker_token }
+    pub fn new(pool: Pool<Postgres>, worker_token: String, iam: IAM) -> Self {
+        Self {
+            pool,
+            worker_token,
+            iam,
+        }
     }
 
     fn authenticate(&self, request: &Request<impl Sized>) -> Result<(), Status> {
@@ -272,7 +279,11 @@ impl workflow_engine_server::WorkflowEngine for WorkflowEngine {
         &self,
         request: Request<GetStatusRequest>,
     ) -> Result<Response<GetStatusResponse>, Status> {
-        self.authenticate(&request)?;
+        // Besides the workers, principals follow the executions they initiated.
+        let initiator = match self.authenticate(&request) {
+            Ok(()) => None,
+            Err(_) => Some(*self.iam.principal(&request).await?.id()),
+        };
 
         let execution_id: WorkflowExecutionId = request
             .into_inner()
@@ -290,6 +301,12 @@ impl workflow_engine_server::WorkflowEngine for WorkflowEngine {
             .await
             .map_err(|e| internal_status("fetch workflow status", e))?;
 
+        if initiator.is_some() && initiator != status.initiated_by_user {
+            return Err(Status::permission_denied(
+                "the execution was not initiated by the principal",
+            ));
+        }
+
         Ok(Response::new(GetStatusResponse {
             status: ExecutionStatus::from(status.status).into(),
             next_retry_at: Some(to_timestamp(status.next_retry_at)),
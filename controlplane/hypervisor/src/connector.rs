@@ -0,0 +1,284 @@
+use crate::Error;
+use crate::instance::{
+    Backup, BackupRetention, BackupSchedule, Console, ConsoleKind, Export, Firewall, Image,
+    ImageImportRequest, Instance, InstanceCreateRequest, InstanceResizeRequest, Instances, Metrics,
+    MetricsRequest, Migration, Snapshot, SnapshotCreateRequest, Status, Volume,
+};
+use crate::kubevirt::instance::KubeVirtInstanceService;
+use crate::placement::{NodeCapacity, PlacementRequest};
+use crate::proxmox::instance::ProxmoxInstanceService;
+use fake::Dummy;
+use std::net::Ipv4Addr;
+use std::str::FromStr;
+use strum_macros::{Display, EnumString};
+use uuid::Uuid;
+
+/// Kind of platform a hypervisor runs on, selecting its connector.
+#[derive(Clone, Copy, Debug, Default, Display, Dummy, EnumString, PartialEq)]
+#[strum(serialize_all = "UPPERCASE")]
+pub enum HypervisorKind {
+    /// A Proxmox VE cluster.
+    #[default]
+    Proxmox,
+
+    /// A Kubernetes cluster running KubeVirt.
+    #[dummy(skip)]
+    KubeVirt,
+}
+
+impl From<String> for HypervisorKind {
+    fn from(value: String) -> Self {
+        HypervisorKind::from_str(&value).expect("could not parse value to hypervisor kind")
+    }
+}
+
+impl From<HypervisorKind> for String {
+    fn from(value: HypervisorKind) -> Self {
+        value.to_string()
+    }
+}
+
+/// Connector of a hypervisor, forwarding the operations on its instances to
+/// the implementation of its kind.
+#[derive(Clone)]
+pub enum Connector {
+    Proxmox(ProxmoxInstanceService),
+    KubeVirt(KubeVirtInstanceService),
+}
+
+/// Forwards a call to the service of the connector.
+macro_rules! dispatch {
+    ($connector:expr, $service:ident => $call:expr) => {
+        match $connector {
+            Connector::Proxmox($service) => $call,
+            Connector::KubeVirt($service) => $call,
+        }
+    };
+}
+
+impl Instances for Connector {
+    async fn capacity(&self) -> Result<Vec<NodeCapacity>, Error> {
+        dispatch!(self, service => service.capacity().await)
+    }
+
+    async fn version(&self) -> Result<String, Error> {
+        dispatch!(self, service => service.version().await)
+    }
+
+    async fn list(&self) -> Result<Vec<Instance>, Error> {
+        dispatch!(self, service => service.list().await)
+    }
+
+    async fn next_id(&self) -> Result<String, Error> {
+        dispatch!(self, service => service.next_id().await)
+    }
+
+    async fn clone(&self, source_id: &str) -> Result<String, Error> {
+        dispatch!(self, service => Instances::clone(service, source_id).await)
+    }
+
+    async fn create(&self, options: InstanceCreateRequest) -> Result<Uuid, Error> {
+        dispatch!(self, service => service.create(options).await)
+    }
+
+    async fn get_ip_address(&self, id: &str) -> Result<Option<Ipv4Addr>, Error> {
+        dispatch!(self, service => service.get_ip_address(id).await)
+    }
+
+    async fn delete(&self, id: &str) -> Result<(), Error> {
+        dispatch!(self, service => service.delete(id).await)
+    }
+
+    async fn start(&self, id: &str) -> Result<(), Error> {
+        dispatch!(self, service => service.start(id).await)
+    }
+
+    async fn status(&self, id: &str) -> Result<Status, Error> {
+        dispatch!(self, service => service.status(id).await)
+    }
+
+    async fn stop(&self, id: &str) -> Result<(), Error> {
+        dispatch!(self, service => service.stop(id).await)
+    }
+
+    async fn shutdown(&self, id: &str) -> Result<(), Error> {
+        dispatch!(self, service => service.shutdown(id).await)
+    }
+
+    async fn reboot(&self, id: &str) -> Result<(), Error> {
+        dispatch!(self, service => service.reboot(id).await)
+    }
+
+    async fn reset(&self, id: &str) -> Result<(), Error> {
+        dispatch!(self, service => service.reset(id).await)
+    }
+
+    async fn suspend(&self, id: &str, to_disk: bool) -> Result<(), Error> {
+        dispatch!(self, service => service.suspend(id, to_disk).await)
+    }
+
+    async fn resume(&self, id: &str) -> Result<(), Error> {
+        dispatch!(self, service => service.resume(id).await)
+    }
+
+    async fn metrics(&self, id: &str, request: &MetricsRequest) -> Result<Metrics, Error> {
+        dispatch!(self, service => service.metrics(id, request).await)
+    }
+
+    async fn console(&self, id: &str, kind: ConsoleKind) -> Result<Console, Error> {
+        dispatch!(self, service => service.console(id, kind).await)
+    }
+
+    async fn resize(&self, id: &str, options: InstanceResizeRequest) -> Result<(), Error> {
+        dispatch!(self, service => service.resize(id, options).await)
+    }
+
+    async fn create_snapshot(&self, id: &str, options: SnapshotCreateRequest) -> Result<(), Error> {
+        dispatch!(self, service => service.create_snapshot(id, options).await)
+    }
+
+    async fn list_snapshots(&self, id: &str) -> Result<Vec<Snapshot>, Error> {
+        dispatch!(self, service => service.list_snapshots(id).await)
+    }
+
+    async fn rollback_snapshot(&self, id: &str, name: &str) -> Result<(), Error> {
+        dispatch!(self, service => service.rollback_snapshot(id, name).await)
+    }
+
+    async fn delete_snapshot(&self, id: &str, name: &str) -> Result<(), Error> {
+        dispatch!(self, service => service.delete_snapshot(id, name).await)
+    }
+
+    async fn create_volume(&self, id: &str, size_bytes: u64) -> Result<Volume, Error> {
+        dispatch!(self, service => service.create_volume(id, size_bytes).await)
+    }
+
+    async fn attach_volume(&self, id: &str, distant_id: &str) -> Result<Volume, Error> {
+        dispatch!(self, service => service.attach_volume(id, distant_id).await)
+    }
+
+    async fn detach_volume(&self, id: &str, distant_id: &str) -> Result<(), Error> {
+        dispatch!(self, service => service.detach_volume(id, distant_id).await)
+    }
+
+    async fn resize_volume(
+        &self,
+        id: &str,
+        distant_id: &str,
+        size_bytes: u64,
+    ) -> Result<(), Error> {
+        dispatch!(self, service => service.resize_volume(id, distant_id, size_bytes).await)
+    }
+
+    async fn delete_volume(&self, id: &str, distant_id: &str) -> Result<(), Error> {
+        dispatch!(self, service => service.delete_volume(id, distant_id).await)
+    }
+
+    async fn list_images(&self, storage: &str) -> Result<Vec<Image>, Error> {
+        dispatch!(self, service => service.list_images(storage).await)
+    }
+
+    async fn import_image(
+        &self,
+        storage: &str,
+        options: ImageImportRequest,
+    ) -> Result<Image, Error> {
+        dispatch!(self, service => service.import_image(storage, options).await)
+    }
+
+    async fn delete_image(&self, storage: &str, id: &str) -> Result<(), Error> {
+        dispatch!(self, service => service.delete_image(storage, id).await)
+    }
+
+    async fn create_backup(
+        &self,
+        id: &str,
+        owner: Uuid,
+        retention: &BackupRetention,
+    ) -> Result<Backup, Error> {
+        dispatch!(self, service => service.create_backup(id, owner, retention).await)
+    }
+
+    async fn list_backups(&self, id: &str, owner: Uuid) -> Result<Vec<Backup>, Error> {
+        dispatch!(self, service => service.list_backups(id, owner).await)
+    }
+
+    async fn restore_backup(
+        &self,
+        id: &str,
+        owner: Uuid,
+        backup_id: &str,
+        name: &str,
+    ) -> Result<String, Error> {
+        dispatch!(self, service => service.restore_backup(id, owner, backup_id, name).await)
+    }
+
+    async fn delete_backup(&self, id: &str, owner: Uuid, backup_id: &str) -> Result<(), Error> {
+        dispatch!(self, service => service.delete_backup(id, owner, backup_id).await)
+    }
+
+    async fn schedule_backups(
+        &self,
+        id: &str,
+        owner: Uuid,
+        schedule: Option<BackupSchedule>,
+    ) -> Result<(), Error> {
+        dispatch!(self, service => service.schedule_backups(id, owner, schedule).await)
+    }
+
+    async fn firewall(&self, id: &str) -> Result<Option<Firewall>, Error> {
+        dispatch!(self, service => service.firewall(id).await)
+    }
+
+    async fn apply_firewall(&self, id: &str, firewall: Option<&Firewall>) -> Result<(), Error> {
+        dispatch!(self, service => service.apply_firewall(id, firewall).await)
+    }
+
+    async fn set_user_password(
+        &self,
+        id: &str,
+        username: &str,
+        password: &str,
+    ) -> Result<(), Error> {
+        dispatch!(self, service => service.set_user_password(id, username, password).await)
+    }
+
+    async fn authorize_ssh_key(
+        &self,
+        id: &str,
+        username: &str,
+        public_key: &str,
+    ) -> Result<(), Error> {
+        dispatch!(self, service => service.authorize_ssh_key(id, username, public_key).await)
+    }
+
+    async fn configure_wireguard(
+        &self,
+        id: &str,
+        interface: &str,
+        configuration: Option<&str>,
+    ) -> Result<(), Error> {
+        dispatch!(self, service => service.configure_wireguard(id, interface, configuration).await)
+    }
+
+    async fn migrate(&self, id: &str, node: Option<&str>) -> Result<Migration, Error> {
+        dispatch!(self, service => service.migrate(id, node).await)
+    }
+
+    async fn drain(&self, node: &str) -> Result<Vec<Migration>, Error> {
+        dispatch!(self, service => service.drain(node).await)
+    }
+
+    async fn export(&self, id: &str, owner: Uuid) -> Result<Export, Error> {
+        dispatch!(self, service => service.export(id, owner).await)
+    }
+
+    async fn import(
+        &self,
+        export: &Export,
+        node: Option<&str>,
+        request: &PlacementRequest,
+    ) -> Result<Migration, Error> {
+        dispatch!(self, service => service.import(export, node, request).await)
+    }
+}
@@ -2,18 +2,66 @@ use thiserror::Error as ThisError;
 
 #[derive(Debug, ThisError)]
 pub enum Error {
+    #[error("Distant backup {0} not found.")]
+    DistantBackupNotFound(String),
+
+    #[error("Distant image {0} not found.")]
+    DistantImageNotFound(String),
+
     #[error("Distant instance #{0} not found.")]
     DistantInstanceNotFound(String),
 
+    #[error("Distant node {0} not found.")]
+    DistantNodeNotFound(String),
+
+    #[error("Distant storage {0} not found.")]
+    DistantStorageNotFound(String),
+
+    #[error("Distant volume {0} not found.")]
+    DistantVolumeNotFound(String),
+
     #[error("Distant instance #{0} not running.")]
     InstanceNotRunning(String),
 
+    #[error("The guest agent of the distant instance is not configured or not running.")]
+    GuestAgentUnavailable,
+
+    #[error("Guest command failed: {0}")]
+    GuestCommandFailed(String),
+
+    #[error("Insufficient capacity: {0}")]
+    InsufficientCapacity(String),
+
+    #[error("Kubernetes API error: {0}")]
+    Kubernetes(#[from] kube::Error),
+
+    #[error("The value {0} is not a valid snippet volume id.")]
+    MalformedSnippetVolume(String),
+
     #[error("The value {0} could not be parsed to a valid vm id.")]
     MalformedVmId(String),
 
+    #[error("Distant instance #{0} has no free device left to attach a volume to.")]
+    NoFreeDevice(String),
+
+    #[error("The {0} operation is not supported by this hypervisor.")]
+    Unsupported(&'static str),
+
+    #[error("The image {0} is not a qcow2, raw or iso image.")]
+    UnsupportedImageFormat(String),
+
     #[error("Other: {0}")]
     Other(Box<dyn std::error::Error + Send + Sync>),
 
+    #[error("Could not encrypt or decrypt the hypervisor token: {0}")]
+    TokenEncryption(#[from] frn_crypto::EncryptionError),
+
+    #[error("The hypervisor token is encrypted with the unsupported {0} algorithm")]
+    UnsupportedTokenAlgorithm(String),
+
+    #[error("SFTP error: {0}")]
+    Sftp(#[from] openssh_sftp_client::Error),
+
     #[error("The snippet file {0} already exists")]
     SnippetFileExists(String),
 
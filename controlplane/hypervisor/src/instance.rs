@@ -1,4 +1,5 @@
 use crate::Error;
+use crate::placement::{NodeCapacity, PlacementRequest};
 use fake::Dummy;
 use std::net::Ipv4Addr;
 use std::str::FromStr;
@@ -8,12 +9,28 @@ use uuid::Uuid;
 #[derive(Clone, Debug, Default, Display, Dummy, EnumString, IntoStaticStr)]
 #[strum(serialize_all = "UPPERCASE")]
 pub enum Status {
+    /// Instance failed to be provisioned, until its provisioning is retried or
+    /// it is deleted.
+    Failed,
+
+    /// Instance vanished from its hypervisor, until its removal is confirmed.
+    Missing,
+
+    /// Instance is paused, its state kept in memory.
+    Paused,
+
+    /// Instance is being provisioned, not created on its hypervisor yet.
+    Provisioning,
+
     /// Instance is active and operational.
     Running,
 
     /// Instance is inactive.
     Stopped,
 
+    /// Instance is hibernated, its state saved to disk.
+    Suspended,
+
     /// Instance status is unknown.
     #[default]
     Unknown,
@@ -82,12 +99,312 @@ pub struct InstanceCreateRequest {
 
     /// The Cloud-Init snippet.
     pub snippet: String,
+
+    /// Distant ids of instances that should not share a node with this one,
+    /// typically the other replicas of the same workload.
+    pub anti_affinity: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct InstanceResizeRequest {
+    /// The new number of cores per socket.
+    pub cores: Option<u8>,
+
+    /// The new memory size in bytes.
+    pub memory_bytes: Option<u64>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Migration {
+    /// The id of the migrated instance
+    pub id: String,
+
+    /// The node the instance was migrated from
+    pub source_node: String,
+
+    /// The node the instance was migrated to
+    pub target_node: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Export {
+    /// The id of the exported instance
+    pub id: String,
+
+    /// The node holding the exported instance
+    pub node: String,
+
+    /// The id of the backup the instance was exported to
+    pub archive: String,
+
+    /// Whether the instance was running before being stopped for the export
+    pub running: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    /// The snapshot name, unique for a given instance
+    pub name: String,
+
+    /// Free-form description of the snapshot
+    pub description: String,
+
+    /// The name of the snapshot this one was taken from, if any
+    pub parent: Option<String>,
+
+    /// Creation time of the snapshot, as a unix timestamp
+    pub created_at: Option<u64>,
+
+    /// Whether the snapshot includes the instance memory state
+    pub include_memory: bool,
+}
+
+pub struct SnapshotCreateRequest {
+    /// The snapshot name.
+    pub name: String,
+
+    /// An optional description of the snapshot.
+    pub description: Option<String>,
+
+    /// Whether to include the instance memory state.
+    pub include_memory: bool,
+}
+
+/// The kind of console opened on an instance.
+#[derive(Clone, Copy, Debug, Default, Display, EnumString, PartialEq)]
+#[strum(serialize_all = "lowercase")]
+pub enum ConsoleKind {
+    /// The graphical display of the instance, streamed over VNC.
+    #[default]
+    Vnc,
+
+    /// The serial console of the instance, streamed as a terminal.
+    Terminal,
+}
+
+#[derive(Clone, Debug)]
+pub struct Console {
+    /// The websocket URL streaming the console
+    pub url: String,
+
+    /// The authorization header value to open the websocket with
+    pub authorization: String,
+
+    /// The password the client authenticates to a VNC console with, if any
+    pub password: Option<String>,
+
+    /// The first message to send on the websocket to authenticate the stream,
+    /// if any
+    pub handshake: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Volume {
+    /// The volume id on the hypervisor storage
+    pub distant_id: String,
+
+    /// The device the volume is attached to (e.g. `scsi1`)
+    pub device: String,
+}
+
+/// The kind of image held by a storage.
+#[derive(Clone, Copy, Debug, Display, EnumString, PartialEq)]
+#[strum(serialize_all = "lowercase")]
+pub enum ImageKind {
+    /// An installation medium, booted from a CD-ROM drive.
+    Iso,
+
+    /// A cloud image, imported as the boot disk of new instances.
+    Disk,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Image {
+    /// The volume id of the image on the hypervisor storage (e.g.
+    /// `local:import/debian-12-genericcloud-amd64.qcow2`)
+    pub id: String,
+
+    /// The file name of the image
+    pub name: String,
+
+    /// The kind of image
+    pub kind: ImageKind,
+
+    /// The format of the image (e.g. `qcow2`, `raw`, `iso`)
+    pub format: String,
+
+    /// The size of the image in bytes
+    pub size_bytes: u64,
+
+    /// Creation time of the image, as a unix timestamp
+    pub created_at: Option<u64>,
+}
+
+pub struct ImageImportRequest {
+    /// The URL to download the image from.
+    pub url: String,
+
+    /// The file name of the image, whose extension gives its format.
+    pub name: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Backup {
+    /// The volume id of the backup on the backup storage (e.g.
+    /// `pbs:backup/vm/100/2025-03-08T18:00:00Z`)
+    pub id: String,
+
+    /// The size of the backup in bytes
+    pub size_bytes: u64,
+
+    /// The notes attached to the backup, if any
+    pub notes: Option<String>,
+
+    /// Creation time of the backup, as a unix timestamp
+    pub created_at: Option<u64>,
+}
+
+/// The number of backups kept per period, unset periods keeping them all.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BackupRetention {
+    /// The number of most recent backups to keep.
+    pub keep_last: Option<u32>,
+
+    /// The number of days to keep the last backup of.
+    pub keep_daily: Option<u32>,
+
+    /// The number of weeks to keep the last backup of.
+    pub keep_weekly: Option<u32>,
+
+    /// The number of months to keep the last backup of.
+    pub keep_monthly: Option<u32>,
+}
+
+#[derive(Clone, Debug)]
+pub struct BackupSchedule {
+    /// The systemd calendar event backups are taken on (e.g. `daily`).
+    pub schedule: String,
+
+    /// The retention applied to the backups after each run.
+    pub retention: BackupRetention,
+}
+
+pub struct MetricsRequest {
+    /// Start of the window, as a unix timestamp.
+    pub start: u64,
+
+    /// End of the window, as a unix timestamp.
+    pub end: u64,
+
+    /// The requested time between two samples in seconds, the finest
+    /// available when 0.
+    pub resolution_seconds: u64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MetricsSample {
+    /// Start time of the sample, as a unix timestamp
+    pub time: u64,
+
+    /// Average CPU utilization as a percentage (0.0-100.0)
+    pub cpu_usage_percent: f64,
+
+    /// Average memory utilization in bytes
+    pub memory_usage_bytes: u64,
+
+    /// Memory available to the instance in bytes
+    pub max_memory_bytes: u64,
+
+    /// Average disk read rate in bytes per second
+    pub disk_read_bytes_per_second: f64,
+
+    /// Average disk write rate in bytes per second
+    pub disk_write_bytes_per_second: f64,
+
+    /// Average network receive rate in bytes per second
+    pub network_in_bytes_per_second: f64,
+
+    /// Average network transmit rate in bytes per second
+    pub network_out_bytes_per_second: f64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Metrics {
+    /// The time between two samples in seconds
+    pub resolution_seconds: u64,
+
+    /// The samples of the window, oldest first, missing where the instance
+    /// was not running
+    pub samples: Vec<MetricsSample>,
+}
+
+/// The direction of the traffic a firewall rule matches.
+#[derive(Clone, Copy, Debug, Default, Display, Dummy, EnumString, PartialEq)]
+#[strum(serialize_all = "UPPERCASE")]
+pub enum FirewallDirection {
+    /// Traffic coming into the instance.
+    #[default]
+    Ingress,
+
+    /// Traffic going out of the instance.
+    Egress,
+}
+
+impl From<String> for FirewallDirection {
+    fn from(value: String) -> Self {
+        FirewallDirection::from_str(&value).expect("could not parse value to firewall direction")
+    }
+}
+
+impl From<FirewallDirection> for String {
+    fn from(value: FirewallDirection) -> Self {
+        value.to_string()
+    }
+}
+
+/// A rule letting traffic through the firewall of an instance, on behalf of a
+/// security group.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FirewallRule {
+    /// The direction of the traffic matched
+    pub direction: FirewallDirection,
+
+    /// The protocol matched (`tcp`, `udp` or `icmp`), any when unset
+    pub protocol: Option<String>,
+
+    /// The destination ports matched (e.g. `22` or `8000:8080`), any when unset
+    pub port_range: Option<String>,
+
+    /// The remote addresses matched in CIDR notation, any when unset
+    pub cidr: Option<String>,
+
+    /// The security group the rule is managed for
+    pub group: String,
+}
+
+/// The managed firewall of an instance.
+///
+/// The incoming traffic no rule lets through is dropped, and so is the
+/// outgoing traffic once an egress rule exists.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Firewall {
+    /// The rules letting traffic through, in evaluation order
+    pub rules: Vec<FirewallRule>,
 }
 
 pub trait Instances: Clone {
+    /// Gets the capacity left on each node of the hypervisor.
+    fn capacity(&self) -> impl Future<Output = Result<Vec<NodeCapacity>, Error>> + Send;
+
+    /// Gets the version of the hypervisor API.
+    fn version(&self) -> impl Future<Output = Result<String, Error>> + Send;
+
     /// Lists all instances.
     fn list(&self) -> impl Future<Output = Result<Vec<Instance>, Error>> + Send;
 
+    /// Gets the next free distant instance id.
+    fn next_id(&self) -> impl Future<Output = Result<String, Error>> + Send;
+
     /// Clone the instance.
     fn clone(&self, source_id: &str) -> impl Future<Output = Result<String, Error>> + Send;
 
@@ -114,4 +431,233 @@ pub trait Instances: Clone {
 
     /// Stops the instance.
     fn stop(&self, id: &str) -> impl Future<Output = Result<(), Error>> + Send;
+
+    /// Shuts the instance down gracefully.
+    fn shutdown(&self, id: &str) -> impl Future<Output = Result<(), Error>> + Send;
+
+    /// Reboots the instance gracefully.
+    fn reboot(&self, id: &str) -> impl Future<Output = Result<(), Error>> + Send;
+
+    /// Resets the instance, without notifying its guest.
+    fn reset(&self, id: &str) -> impl Future<Output = Result<(), Error>> + Send;
+
+    /// Suspends the instance, in memory or to disk.
+    fn suspend(&self, id: &str, to_disk: bool) -> impl Future<Output = Result<(), Error>> + Send;
+
+    /// Resumes a paused or suspended instance.
+    fn resume(&self, id: &str) -> impl Future<Output = Result<(), Error>> + Send;
+
+    /// Gets the CPU, memory, disk and network usage history of the instance
+    /// over a window.
+    fn metrics(
+        &self,
+        id: &str,
+        request: &MetricsRequest,
+    ) -> impl Future<Output = Result<Metrics, Error>> + Send;
+
+    /// Opens a console on the instance.
+    fn console(
+        &self,
+        id: &str,
+        kind: ConsoleKind,
+    ) -> impl Future<Output = Result<Console, Error>> + Send;
+
+    /// Resizes the CPU and memory of the instance.
+    ///
+    /// Running instances are resized live when the hypervisor supports it,
+    /// and restarted otherwise.
+    fn resize(
+        &self,
+        id: &str,
+        options: InstanceResizeRequest,
+    ) -> impl Future<Output = Result<(), Error>> + Send;
+
+    /// Takes a snapshot of the instance.
+    fn create_snapshot(
+        &self,
+        id: &str,
+        options: SnapshotCreateRequest,
+    ) -> impl Future<Output = Result<(), Error>> + Send;
+
+    /// Lists the snapshots of the instance.
+    fn list_snapshots(&self, id: &str)
+    -> impl Future<Output = Result<Vec<Snapshot>, Error>> + Send;
+
+    /// Rolls the instance back to the given snapshot.
+    fn rollback_snapshot(
+        &self,
+        id: &str,
+        name: &str,
+    ) -> impl Future<Output = Result<(), Error>> + Send;
+
+    /// Deletes the given snapshot of the instance.
+    fn delete_snapshot(
+        &self,
+        id: &str,
+        name: &str,
+    ) -> impl Future<Output = Result<(), Error>> + Send;
+
+    /// Creates a data volume and attaches it to the instance.
+    fn create_volume(
+        &self,
+        id: &str,
+        size_bytes: u64,
+    ) -> impl Future<Output = Result<Volume, Error>> + Send;
+
+    /// Attaches a detached volume back to the instance owning it.
+    fn attach_volume(
+        &self,
+        id: &str,
+        distant_id: &str,
+    ) -> impl Future<Output = Result<Volume, Error>> + Send;
+
+    /// Detaches a volume from the instance, keeping its data.
+    fn detach_volume(
+        &self,
+        id: &str,
+        distant_id: &str,
+    ) -> impl Future<Output = Result<(), Error>> + Send;
+
+    /// Grows a volume of the instance to the given size.
+    fn resize_volume(
+        &self,
+        id: &str,
+        distant_id: &str,
+        size_bytes: u64,
+    ) -> impl Future<Output = Result<(), Error>> + Send;
+
+    /// Deletes a volume of the instance, detaching it first if needed.
+    fn delete_volume(
+        &self,
+        id: &str,
+        distant_id: &str,
+    ) -> impl Future<Output = Result<(), Error>> + Send;
+
+    /// Lists the images held by the given storage.
+    fn list_images(&self, storage: &str) -> impl Future<Output = Result<Vec<Image>, Error>> + Send;
+
+    /// Imports an image from a URL into the given storage.
+    fn import_image(
+        &self,
+        storage: &str,
+        options: ImageImportRequest,
+    ) -> impl Future<Output = Result<Image, Error>> + Send;
+
+    /// Deletes an image from the given storage.
+    fn delete_image(
+        &self,
+        storage: &str,
+        id: &str,
+    ) -> impl Future<Output = Result<(), Error>> + Send;
+
+    /// Backs the instance up to the backup storage, then prunes its backups
+    /// according to the given retention.
+    ///
+    /// The backups of an instance are bound to `owner`, the id the control
+    /// plane knows the instance by, rather than to the distant id alone: the
+    /// hypervisor may hand the distant id of a deleted instance out again.
+    fn create_backup(
+        &self,
+        id: &str,
+        owner: Uuid,
+        retention: &BackupRetention,
+    ) -> impl Future<Output = Result<Backup, Error>> + Send;
+
+    /// Lists the backups of the instance bound to `owner`.
+    fn list_backups(
+        &self,
+        id: &str,
+        owner: Uuid,
+    ) -> impl Future<Output = Result<Vec<Backup>, Error>> + Send;
+
+    /// Restores a backup of the instance bound to `owner` into a new instance
+    /// with the given name, returning the id of the new instance.
+    fn restore_backup(
+        &self,
+        id: &str,
+        owner: Uuid,
+        backup_id: &str,
+        name: &str,
+    ) -> impl Future<Output = Result<String, Error>> + Send;
+
+    /// Deletes a backup of the instance bound to `owner`.
+    fn delete_backup(
+        &self,
+        id: &str,
+        owner: Uuid,
+        backup_id: &str,
+    ) -> impl Future<Output = Result<(), Error>> + Send;
+
+    /// Sets the schedule backups of the instance are taken on, bound to
+    /// `owner`, or stops scheduled backups when unset.
+    fn schedule_backups(
+        &self,
+        id: &str,
+        owner: Uuid,
+        schedule: Option<BackupSchedule>,
+    ) -> impl Future<Output = Result<(), Error>> + Send;
+
+    /// Gets the managed firewall of the instance, none when it is disabled or
+    /// was changed outside of the managed rules.
+    fn firewall(&self, id: &str) -> impl Future<Output = Result<Option<Firewall>, Error>> + Send;
+
+    /// Replaces the managed firewall of the instance, or disables it when
+    /// unset. Rules added by hand are kept.
+    fn apply_firewall(
+        &self,
+        id: &str,
+        firewall: Option<&Firewall>,
+    ) -> impl Future<Output = Result<(), Error>> + Send;
+
+    /// Sets the password of a user of the instance, through its guest agent.
+    fn set_user_password(
+        &self,
+        id: &str,
+        username: &str,
+        password: &str,
+    ) -> impl Future<Output = Result<(), Error>> + Send;
+
+    /// Authorizes an SSH public key to log in as a user of the instance,
+    /// through its guest agent. Keys already authorized are kept.
+    fn authorize_ssh_key(
+        &self,
+        id: &str,
+        username: &str,
+        public_key: &str,
+    ) -> impl Future<Output = Result<(), Error>> + Send;
+
+    /// Applies a WireGuard configuration to an interface of the instance,
+    /// through its guest agent, or tears the interface down when unset.
+    fn configure_wireguard(
+        &self,
+        id: &str,
+        interface: &str,
+        configuration: Option<&str>,
+    ) -> impl Future<Output = Result<(), Error>> + Send;
+
+    /// Migrates the instance to another node of the hypervisor, live when it
+    /// is running. The node with the most headroom is picked when unset.
+    fn migrate(
+        &self,
+        id: &str,
+        node: Option<&str>,
+    ) -> impl Future<Output = Result<Migration, Error>> + Send;
+
+    /// Migrates every instance off a node onto the other nodes of the
+    /// hypervisor, without starting when they cannot hold them all.
+    fn drain(&self, node: &str) -> impl Future<Output = Result<Vec<Migration>, Error>> + Send;
+
+    /// Stops the instance and exports it to the backup storage, so that
+    /// another hypervisor sharing the storage can import it.
+    fn export(&self, id: &str, owner: Uuid) -> impl Future<Output = Result<Export, Error>> + Send;
+
+    /// Imports an instance exported by another hypervisor onto a node, and
+    /// starts it when it was running. The node with the most headroom for the
+    /// request is picked when unset.
+    fn import(
+        &self,
+        export: &Export,
+        node: Option<&str>,
+        request: &PlacementRequest,
+    ) -> impl Future<Output = Result<Migration, Error>> + Send;
 }
@@ -0,0 +1,370 @@
+use crate::Error;
+use crate::instance::{
+    Backup, BackupRetention, BackupSchedule, Console, ConsoleKind, Export, Firewall, Image,
+    ImageImportRequest, Instance, InstanceCreateRequest, InstanceResizeRequest, Instances, Metrics,
+    MetricsRequest, Migration, Snapshot, SnapshotCreateRequest, Status, Volume,
+};
+use crate::kubevirt::resources::{
+    self, RUN_STRATEGY_ALWAYS, RUN_STRATEGY_HALTED, virtual_machine_instance_resource,
+    virtual_machine_resource,
+};
+use crate::placement::{NodeCapacity, PlacementRequest};
+use k8s_openapi::api::core::v1::Node;
+use kube::api::{Api, DeleteParams, DynamicObject, ListParams, Patch, PatchParams, PostParams};
+use serde_json::json;
+use std::net::Ipv4Addr;
+use uuid::Uuid;
+
+#[derive(Clone)]
+pub struct KubeVirtInstanceService {
+    pub client: kube::Client,
+    pub namespace: String,
+    pub storage_class: Option<String>,
+}
+
+impl KubeVirtInstanceService {
+    fn virtual_machines(&self) -> Api<DynamicObject> {
+        Api::namespaced_with(
+            self.client.clone(),
+            &self.namespace,
+            &virtual_machine_resource(),
+        )
+    }
+
+    fn virtual_machine_instances(&self) -> Api<DynamicObject> {
+        Api::namespaced_with(
+            self.client.clone(),
+            &self.namespace,
+            &virtual_machine_instance_resource(),
+        )
+    }
+
+    /// Sets the run strategy of a VM, the KubeVirt controller starting or
+    /// stopping it accordingly.
+    async fn set_run_strategy(&self, id: &str, run_strategy: &str) -> Result<(), Error> {
+        self.virtual_machines()
+            .patch(
+                id,
+                &PatchParams::default(),
+                &Patch::Merge(json!({ "spec": { "runStrategy": run_strategy } })),
+            )
+            .await
+            .map_err(instance_error(id))?;
+
+        Ok(())
+    }
+}
+
+/// Maps the errors of the requests on a VM, a missing resource meaning the VM
+/// does not exist.
+fn instance_error(id: &str) -> impl FnOnce(kube::Error) -> Error + '_ {
+    move |error| match error {
+        kube::Error::Api(response) if response.code == 404 => {
+            Error::DistantInstanceNotFound(id.to_owned())
+        }
+        error => Error::Kubernetes(error),
+    }
+}
+
+impl Instances for KubeVirtInstanceService {
+    /// Gets the capacity left on the ready nodes of the cluster.
+    async fn capacity(&self) -> Result<Vec<NodeCapacity>, Error> {
+        let nodes = Api::<Node>::all(self.client.clone())
+            .list(&ListParams::default())
+            .await?
+            .items;
+        let vmis = Api::<DynamicObject>::all_with(
+            self.client.clone(),
+            &virtual_machine_instance_resource(),
+        )
+        .list(&ListParams::default())
+        .await?
+        .items;
+
+        Ok(resources::node_capacities(&nodes, &vmis))
+    }
+
+    /// Gets the version of the Kubernetes API (e.g. `v1.32.1`).
+    async fn version(&self) -> Result<String, Error> {
+        let version = self.client.apiserver_version().await?;
+
+        Ok(version.git_version)
+    }
+
+    async fn list(&self) -> Result<Vec<Instance>, Error> {
+        let vms = self
+            .virtual_machines()
+            .list(&ListParams::default())
+            .await?
+            .items;
+
+        Ok(vms.iter().map(resources::instance).collect())
+    }
+
+    /// Generates a name for a new VM, unique without having to look the
+    /// existing ones up.
+    async fn next_id(&self) -> Result<String, Error> {
+        Ok(format!("vm-{}", Uuid::new_v4().simple()))
+    }
+
+    /// Clones the instance, its disks being cloned by CDI once the clone is
+    /// created.
+    async fn clone(&self, id: &str) -> Result<String, Error> {
+        let source = self
+            .virtual_machines()
+            .get(id)
+            .await
+            .map_err(instance_error(id))?;
+
+        let clone_id = Instances::next_id(self).await?;
+        let clone = resources::cloned_virtual_machine(&source, &clone_id, &self.namespace);
+        self.virtual_machines()
+            .create(&PostParams::default(), &clone)
+            .await?;
+
+        Ok(clone_id)
+    }
+
+    /// Creates the VM of the instance, halted. Its disk is imported by CDI
+    /// from the image in the meantime.
+    async fn create(&self, options: InstanceCreateRequest) -> Result<Uuid, Error> {
+        let instance_id = Uuid::new_v4();
+        let vm = resources::virtual_machine(
+            instance_id,
+            &options,
+            &self.namespace,
+            self.storage_class.as_deref(),
+        );
+
+        self.virtual_machines()
+            .create(&PostParams::default(), &vm)
+            .await?;
+
+        Ok(instance_id)
+    }
+
+    /// Gets the address the VM reports while it is running.
+    async fn get_ip_address(&self, id: &str) -> Result<Option<Ipv4Addr>, Error> {
+        let vmi = self
+            .virtual_machine_instances()
+            .get_opt(id)
+            .await
+            .map_err(instance_error(id))?;
+
+        Ok(vmi.as_ref().and_then(resources::ip_address))
+    }
+
+    /// Deletes the VM, its disks being garbage collected after it.
+    async fn delete(&self, id: &str) -> Result<(), Error> {
+        self.virtual_machines()
+            .delete(id, &DeleteParams::background())
+            .await
+            .map_err(instance_error(id))?;
+
+        Ok(())
+    }
+
+    async fn start(&self, id: &str) -> Result<(), Error> {
+        self.set_run_strategy(id, RUN_STRATEGY_ALWAYS).await
+    }
+
+    async fn status(&self, id: &str) -> Result<Status, Error> {
+        let vm = self
+            .virtual_machines()
+            .get(id)
+            .await
+            .map_err(instance_error(id))?;
+
+        Ok(resources::status(&vm))
+    }
+
+    /// Stops the instance, within the termination grace period of the VM.
+    async fn stop(&self, id: &str) -> Result<(), Error> {
+        self.set_run_strategy(id, RUN_STRATEGY_HALTED).await
+    }
+
+    async fn shutdown(&self, id: &str) -> Result<(), Error> {
+        self.set_run_strategy(id, RUN_STRATEGY_HALTED).await
+    }
+
+    async fn reboot(&self, _id: &str) -> Result<(), Error> {
+        Err(Error::Unsupported("reboot"))
+    }
+
+    async fn reset(&self, _id: &str) -> Result<(), Error> {
+        Err(Error::Unsupported("reset"))
+    }
+
+    async fn suspend(&self, _id: &str, _to_disk: bool) -> Result<(), Error> {
+        Err(Error::Unsupported("suspend"))
+    }
+
+    async fn resume(&self, _id: &str) -> Result<(), Error> {
+        Err(Error::Unsupported("resume"))
+    }
+
+    async fn metrics(&self, _id: &str, _request: &MetricsRequest) -> Result<Metrics, Error> {
+        Err(Error::Unsupported("metrics"))
+    }
+
+    async fn console(&self, _id: &str, _kind: ConsoleKind) -> Result<Console, Error> {
+        Err(Error::Unsupported("console"))
+    }
+
+    async fn resize(&self, _id: &str, _options: InstanceResizeRequest) -> Result<(), Error> {
+        Err(Error::Unsupported("resize"))
+    }
+
+    async fn create_snapshot(
+        &self,
+        _id: &str,
+        _options: SnapshotCreateRequest,
+    ) -> Result<(), Error> {
+        Err(Error::Unsupported("create snapshot"))
+    }
+
+    async fn list_snapshots(&self, _id: &str) -> Result<Vec<Snapshot>, Error> {
+        Err(Error::Unsupported("list snapshots"))
+    }
+
+    async fn rollback_snapshot(&self, _id: &str, _name: &str) -> Result<(), Error> {
+        Err(Error::Unsupported("rollback snapshot"))
+    }
+
+    async fn delete_snapshot(&self, _id: &str, _name: &str) -> Result<(), Error> {
+        Err(Error::Unsupported("delete snapshot"))
+    }
+
+    async fn create_volume(&self, _id: &str, _size_bytes: u64) -> Result<Volume, Error> {
+        Err(Error::Unsupported("create volume"))
+    }
+
+    async fn attach_volume(&self, _id: &str, _distant_id: &str) -> Result<Volume, Error> {
+        Err(Error::Unsupported("attach volume"))
+    }
+
+    async fn detach_volume(&self, _id: &str, _distant_id: &str) -> Result<(), Error> {
+        Err(Error::Unsupported("detach volume"))
+    }
+
+    async fn resize_volume(
+        &self,
+        _id: &str,
+        _distant_id: &str,
+        _size_bytes: u64,
+    ) -> Result<(), Error> {
+        Err(Error::Unsupported("resize volume"))
+    }
+
+    async fn delete_volume(&self, _id: &str, _distant_id: &str) -> Result<(), Error> {
+        Err(Error::Unsupported("delete volume"))
+    }
+
+    async fn list_images(&self, _storage: &str) -> Result<Vec<Image>, Error> {
+        Err(Error::Unsupported("list images"))
+    }
+
+    async fn import_image(
+        &self,
+        _storage: &str,
+        _options: ImageImportRequest,
+    ) -> Result<Image, Error> {
+        Err(Error::Unsupported("import image"))
+    }
+
+    async fn delete_image(&self, _storage: &str, _id: &str) -> Result<(), Error> {
+        Err(Error::Unsupported("delete image"))
+    }
+
+    async fn create_backup(
+        &self,
+        _id: &str,
+        _owner: Uuid,
+        _retention: &BackupRetention,
+    ) -> Result<Backup, Error> {
+        Err(Error::Unsupported("create backup"))
+    }
+
+    async fn list_backups(&self, _id: &str, _owner: Uuid) -> Result<Vec<Backup>, Error> {
+        Err(Error::Unsupported("list backups"))
+    }
+
+    async fn restore_backup(
+        &self,
+        _id: &str,
+        _owner: Uuid,
+        _backup_id: &str,
+        _name: &str,
+    ) -> Result<String, Error> {
+        Err(Error::Unsupported("restore backup"))
+    }
+
+    async fn delete_backup(&self, _id: &str, _owner: Uuid, _backup_id: &str) -> Result<(), Error> {
+        Err(Error::Unsupported("delete backup"))
+    }
+
+    async fn schedule_backups(
+        &self,
+        _id: &str,
+        _owner: Uuid,
+        _schedule: Option<BackupSchedule>,
+    ) -> Result<(), Error> {
+        Err(Error::Unsupported("schedule backups"))
+    }
+
+    async fn firewall(&self, _id: &str) -> Result<Option<Firewall>, Error> {
+        Err(Error::Unsupported("firewall"))
+    }
+
+    async fn apply_firewall(&self, _id: &str, _firewall: Option<&Firewall>) -> Result<(), Error> {
+        Err(Error::Unsupported("apply firewall"))
+    }
+
+    async fn set_user_password(
+        &self,
+        _id: &str,
+        _username: &str,
+        _password: &str,
+    ) -> Result<(), Error> {
+        Err(Error::Unsupported("set user password"))
+    }
+
+    async fn authorize_ssh_key(
+        &self,
+        _id: &str,
+        _username: &str,
+        _public_key: &str,
+    ) -> Result<(), Error> {
+        Err(Error::Unsupported("authorize ssh key"))
+    }
+
+    async fn configure_wireguard(
+        &self,
+        _id: &str,
+        _interface: &str,
+        _configuration: Option<&str>,
+    ) -> Result<(), Error> {
+        Err(Error::Unsupported("configure wireguard"))
+    }
+
+    async fn migrate(&self, _id: &str, _node: Option<&str>) -> Result<Migration, Error> {
+        Err(Error::Unsupported("migrate"))
+    }
+
+    async fn drain(&self, _node: &str) -> Result<Vec<Migration>, Error> {
+        Err(Error::Unsupported("drain"))
+    }
+
+    async fn export(&self, _id: &str, _owner: Uuid) -> Result<Export, Error> {
+        Err(Error::Unsupported("export"))
+    }
+
+    async fn import(
+        &self,
+        _export: &Export,
+        _node: Option<&str>,
+        _request: &PlacementRequest,
+    ) -> Result<Migration, Error> {
+        Err(Error::Unsupported("import"))
+    }
+}
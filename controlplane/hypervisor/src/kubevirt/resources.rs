@@ -0,0 +1,543 @@
+//! Builders and readers of the KubeVirt custom resources.
+//!
+//! The resources are handled as dynamic objects, the KubeVirt API not being
+//! part of the Kubernetes OpenAPI bindings.
+
+use crate::instance::{Instance, InstanceCreateRequest, Status};
+use crate::placement::NodeCapacity;
+use k8s_openapi::api::core::v1::Node;
+use kube::ResourceExt;
+use kube::api::{ApiResource, DynamicObject, GroupVersionKind};
+use serde_json::{Value, json};
+use std::collections::{BTreeMap, HashMap};
+use std::net::Ipv4Addr;
+use uuid::Uuid;
+
+/// Annotation holding the name of the instance, VM names being restricted to
+/// DNS labels.
+pub const DISPLAY_NAME_ANNOTATION: &str = "francenuage.fr/display-name";
+
+/// Label holding the id of the instance.
+pub const INSTANCE_ID_LABEL: &str = "francenuage.fr/instance-id";
+
+/// Label KubeVirt sets on the pod running a VM, holding the name of the VM.
+pub const VM_NAME_LABEL: &str = "vm.kubevirt.io/name";
+
+/// Run strategy of a VM that should be running.
+pub const RUN_STRATEGY_ALWAYS: &str = "Always";
+
+/// Run strategy of a VM that should be stopped.
+pub const RUN_STRATEGY_HALTED: &str = "Halted";
+
+/// Gets the API resource of the `VirtualMachine` kind.
+pub fn virtual_machine_resource() -> ApiResource {
+    ApiResource::from_gvk_with_plural(
+        &GroupVersionKind::gvk("kubevirt.io", "v1", "VirtualMachine"),
+        "virtualmachines",
+    )
+}
+
+/// Gets the API resource of the `VirtualMachineInstance` kind, the running
+/// counterpart of a `VirtualMachine`.
+pub fn virtual_machine_instance_resource() -> ApiResource {
+    ApiResource::from_gvk_with_plural(
+        &GroupVersionKind::gvk("kubevirt.io", "v1", "VirtualMachineInstance"),
+        "virtualmachineinstances",
+    )
+}
+
+/// Builds the halted VM of an instance, booting from a disk imported from
+/// its image and provisioned by its snippet through cloud-init.
+pub fn virtual_machine(
+    instance_id: Uuid,
+    options: &InstanceCreateRequest,
+    namespace: &str,
+    storage_class: Option<&str>,
+) -> DynamicObject {
+    let disk = disk_name(&options.id, 0);
+    let mut storage = json!({
+        "resources": { "requests": { "storage": options.disk_bytes.to_string() } },
+    });
+    if let Some(storage_class) = storage_class {
+        storage["storageClassName"] = json!(storage_class);
+    }
+
+    let mut vm = DynamicObject::new(&options.id, &virtual_machine_resource())
+        .within(namespace)
+        .data(json!({
+            "spec": {
+                "runStrategy": RUN_STRATEGY_HALTED,
+                "dataVolumeTemplates": [{
+                    "metadata": { "name": disk },
+                    "spec": {
+                        "source": image_source(&options.disk_image, namespace),
+                        "storage": storage,
+                    },
+                }],
+                "template": {
+                    "spec": {
+                        "domain": {
+                            "cpu": { "cores": options.cores },
+                            "memory": { "guest": options.memory_bytes.to_string() },
+                            "devices": {
+                                "disks": [
+                                    { "name": "root", "disk": { "bus": "virtio" } },
+                                    { "name": "cloudinit", "disk": { "bus": "virtio" } },
+                                ],
+                                "interfaces": [{ "name": "default", "masquerade": {} }],
+                            },
+                        },
+                        "networks": [{ "name": "default", "pod": {} }],
+                        "volumes": [
+                            { "name": "root", "dataVolume": { "name": disk } },
+                            {
+                                "name": "cloudinit",
+                                "cloudInitNoCloud": { "userData": options.snippet },
+                            },
+                        ],
+                    },
+                },
+            },
+        }));
+    if !options.anti_affinity.is_empty() {
+        vm.data["spec"]["template"]["spec"]["affinity"] = anti_affinity(&options.anti_affinity);
+    }
+    vm.metadata.labels = Some(BTreeMap::from([(
+        INSTANCE_ID_LABEL.to_owned(),
+        instance_id.to_string(),
+    )]));
+    vm.metadata.annotations = Some(BTreeMap::from([(
+        DISPLAY_NAME_ANNOTATION.to_owned(),
+        options.name.clone(),
+    )]));
+
+    vm
+}
+
+/// Builds the affinity keeping a VM off the nodes running the given VMs, as
+/// long as the cluster has other nodes to schedule it on.
+fn anti_affinity(vms: &[String]) -> Value {
+    json!({
+        "podAntiAffinity": {
+            "preferredDuringSchedulingIgnoredDuringExecution": [{
+                "weight": 100,
+                "podAffinityTerm": {
+                    "labelSelector": {
+                        "matchExpressions": [{
+                            "key": VM_NAME_LABEL,
+                            "operator": "In",
+                            "values": vms,
+                        }],
+                    },
+                    "topologyKey": "kubernetes.io/hostname",
+                },
+            }],
+        },
+    })
+}
+
+/// Builds a halted copy of a VM, whose disks are cloned from the disks of the
+/// source VM.
+pub fn cloned_virtual_machine(source: &DynamicObject, id: &str, namespace: &str) -> DynamicObject {
+    let mut spec = source.data["spec"].clone();
+    spec["runStrategy"] = json!(RUN_STRATEGY_HALTED);
+    spec.as_object_mut().map(|spec| spec.remove("running"));
+
+    let mut renamed = HashMap::new();
+    if let Some(templates) = spec["dataVolumeTemplates"].as_array_mut() {
+        for (index, template) in templates.iter_mut().enumerate() {
+            let source_disk = template["metadata"]["name"]
+                .as_str()
+                .unwrap_or_default()
+                .to_owned();
+            let disk = disk_name(id, index);
+
+            template["metadata"] = json!({ "name": disk });
+            template["spec"]["source"] = json!({
+                "pvc": { "namespace": namespace, "name": source_disk },
+            });
+            renamed.insert(source_disk, disk);
+        }
+    }
+    if let Some(volumes) = spec["template"]["spec"]["volumes"].as_array_mut() {
+        for volume in volumes {
+            let name = &mut volume["dataVolume"]["name"];
+            if let Some(disk) = name.as_str().and_then(|source| renamed.get(source)) {
+                *name = json!(disk);
+            }
+        }
+    }
+
+    let mut vm = DynamicObject::new(id, &virtual_machine_resource())
+        .within(namespace)
+        .data(json!({ "spec": spec }));
+    vm.metadata.annotations = source
+        .annotations()
+        .get(DISPLAY_NAME_ANNOTATION)
+        .map(|name| BTreeMap::from([(DISPLAY_NAME_ANNOTATION.to_owned(), name.clone())]));
+
+    vm
+}
+
+/// Gets the status of a VM, from the status the KubeVirt controller reports.
+pub fn status(vm: &DynamicObject) -> Status {
+    match vm.data["status"]["printableStatus"].as_str() {
+        Some("Running" | "Migrating") => Status::Running,
+        Some("Paused") => Status::Paused,
+        Some("Stopped") => Status::Stopped,
+        _ => Status::Unknown,
+    }
+}
+
+/// Converts a VM to an instance.
+///
+/// The usage of the instance is not reported by the VM, and left to zero.
+pub fn instance(vm: &DynamicObject) -> Instance {
+    let spec = &vm.data["spec"];
+    let domain = &spec["template"]["spec"]["domain"];
+    let id = vm.name_any();
+
+    Instance {
+        name: vm
+            .annotations()
+            .get(DISPLAY_NAME_ANNOTATION)
+            .cloned()
+            .unwrap_or_else(|| id.clone()),
+        max_cpu_cores: domain["cpu"]["cores"].as_u64().unwrap_or(1) as u32,
+        max_memory_bytes: quantity(&domain["memory"]["guest"]).unwrap_or_default() as u64,
+        max_disk_bytes: spec["dataVolumeTemplates"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|template| {
+                quantity(&template["spec"]["storage"]["resources"]["requests"]["storage"])
+            })
+            .sum::<f64>() as u64,
+        cpu_usage_percent: 0.0,
+        disk_usage_bytes: 0,
+        memory_usage_bytes: 0,
+        status: status(vm),
+        id,
+    }
+}
+
+/// Gets the first IPv4 address a running VM reports on its interfaces, other
+/// than its loopback addresses.
+pub fn ip_address(vmi: &DynamicObject) -> Option<Ipv4Addr> {
+    vmi.data["status"]["interfaces"]
+        .as_array()?
+        .iter()
+        .flat_map(|interface| {
+            std::iter::once(&interface["ipAddress"])
+                .chain(interface["ipAddresses"].as_array().into_iter().flatten())
+        })
+        .filter_map(|address| address.as_str()?.parse::<Ipv4Addr>().ok())
+        .find(|address| !address.is_loopback())
+}
+
+/// Gets the capacity left on the ready nodes of the cluster, once the CPU and
+/// memory of the running VMs are reserved.
+pub fn node_capacities(nodes: &[Node], vmis: &[DynamicObject]) -> Vec<NodeCapacity> {
+    nodes
+        .iter()
+        .filter(|node| is_ready(node))
+        .map(|node| {
+            let name = node.name_any();
+            let allocatable = node
+                .status
+                .as_ref()
+                .and_then(|status| status.allocatable.as_ref());
+            let allocatable = |resource: &str| {
+                allocatable
+                    .and_then(|allocatable| allocatable.get(resource))
+                    .and_then(|quantity| parse_quantity(&quantity.0))
+                    .unwrap_or_default()
+            };
+
+            let hosted = vmis
+                .iter()
+                .filter(|vmi| vmi.data["status"]["nodeName"].as_str() == Some(name.as_str()));
+            let (used_cpu, used_memory) = hosted.fold((0.0, 0.0), |(cpu, memory), vmi| {
+                let domain = &vmi.data["spec"]["domain"];
+                (
+                    cpu + domain["cpu"]["cores"].as_f64().unwrap_or(1.0),
+                    memory + quantity(&domain["memory"]["guest"]).unwrap_or_default(),
+                )
+            });
+
+            let max_cpu = allocatable("cpu");
+            let max_memory = allocatable("memory");
+
+            NodeCapacity {
+                node: name,
+                max_cpu: max_cpu as u32,
+                free_cpu: (max_cpu - used_cpu).max(0.0),
+                max_memory_bytes: max_memory as u64,
+                free_memory_bytes: (max_memory - used_memory).max(0.0) as u64,
+                max_disk_bytes: None,
+                free_disk_bytes: None,
+            }
+        })
+        .collect()
+}
+
+/// Parses a Kubernetes quantity (e.g. `2Gi`, `500m`, `1.5`).
+pub fn parse_quantity(quantity: &str) -> Option<f64> {
+    const SUFFIXES: [(&str, f64); 13] = [
+        ("Ki", 1024.0),
+        ("Mi", 1024.0 * 1024.0),
+        ("Gi", 1024.0 * 1024.0 * 1024.0),
+        ("Ti", 1024.0 * 1024.0 * 1024.0 * 1024.0),
+        ("Pi", 1024.0 * 1024.0 * 1024.0 * 1024.0 * 1024.0),
+        ("n", 1e-9),
+        ("u", 1e-6),
+        ("m", 1e-3),
+        ("k", 1e3),
+        ("M", 1e6),
+        ("G", 1e9),
+        ("T", 1e12),
+        ("P", 1e15),
+    ];
+
+    let quantity = quantity.trim();
+    let (number, multiplier) = SUFFIXES
+        .iter()
+        .find_map(|(suffix, multiplier)| {
+            quantity
+                .strip_suffix(suffix)
+                .map(|number| (number, *multiplier))
+        })
+        .unwrap_or((quantity, 1.0));
+
+    number.parse::<f64>().ok().map(|number| number * multiplier)
+}
+
+/// Parses a quantity written either as a string or as a number.
+fn quantity(value: &Value) -> Option<f64> {
+    value
+        .as_str()
+        .and_then(parse_quantity)
+        .or_else(|| value.as_f64())
+}
+
+/// Gets the name of a disk of a VM.
+fn disk_name(id: &str, index: usize) -> String {
+    match index {
+        0 => format!("{id}-disk"),
+        index => format!("{id}-disk-{index}"),
+    }
+}
+
+/// Gets the source a disk is imported from: a container disk for `docker://`
+/// images, a download for `http(s)://` ones, and a clone of a persistent
+/// volume claim of the namespace otherwise.
+fn image_source(image: &str, namespace: &str) -> Value {
+    if image.starts_with("docker://") {
+        json!({ "registry": { "url": image } })
+    } else if image.starts_with("http://") || image.starts_with("https://") {
+        json!({ "http": { "url": image } })
+    } else {
+        json!({ "pvc": { "namespace": namespace, "name": image } })
+    }
+}
+
+/// Checks whether a node reports itself ready to run workloads.
+fn is_ready(node: &Node) -> bool {
+    let schedulable = !node
+        .spec
+        .as_ref()
+        .and_then(|spec| spec.unschedulable)
+        .unwrap_or(false);
+    let ready = node
+        .status
+        .as_ref()
+        .and_then(|status| status.conditions.as_ref())
+        .into_iter()
+        .flatten()
+        .any(|condition| condition.type_ == "Ready" && condition.status == "True");
+
+    schedulable && ready
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::core::v1::{NodeCondition, NodeStatus};
+    use k8s_openapi::apimachinery::pkg::api::resource::Quantity;
+
+    const GIB: u64 = 1024 * 1024 * 1024;
+
+    fn create_request() -> InstanceCreateRequest {
+        InstanceCreateRequest {
+            id: "vm-1".to_owned(),
+            cores: 2,
+            disk_bytes: 20 * GIB,
+            disk_image: "docker://quay.io/containerdisks/debian:12".to_owned(),
+            memory_bytes: 2 * GIB,
+            name: "web".to_owned(),
+            snippet: "#cloud-config\n".to_owned(),
+            anti_affinity: vec![],
+        }
+    }
+
+    fn node(name: &str, ready: &str, cpu: &str, memory: &str) -> Node {
+        let mut node = Node::default();
+        node.metadata.name = Some(name.to_owned());
+        node.status = Some(NodeStatus {
+            allocatable: Some(BTreeMap::from([
+                ("cpu".to_owned(), Quantity(cpu.to_owned())),
+                ("memory".to_owned(), Quantity(memory.to_owned())),
+            ])),
+            conditions: Some(vec![NodeCondition {
+                type_: "Ready".to_owned(),
+                status: ready.to_owned(),
+                ..Default::default()
+            }]),
+            ..Default::default()
+        });
+        node
+    }
+
+    #[test]
+    fn test_virtual_machine_reads_back_as_its_instance() {
+        let vm = virtual_machine(Uuid::nil(), &create_request(), "tenants", Some("ceph-rbd"));
+
+        let instance = instance(&vm);
+
+        assert_eq!(instance.id, "vm-1");
+        assert_eq!(instance.name, "web");
+        assert_eq!(instance.max_cpu_cores, 2);
+        assert_eq!(instance.max_memory_bytes, 2 * GIB);
+        assert_eq!(instance.max_disk_bytes, 20 * GIB);
+        assert!(matches!(instance.status, Status::Unknown));
+        assert_eq!(vm.data["spec"]["runStrategy"], RUN_STRATEGY_HALTED);
+        assert_eq!(
+            vm.data["spec"]["dataVolumeTemplates"][0]["spec"]["source"]["registry"]["url"],
+            "docker://quay.io/containerdisks/debian:12"
+        );
+        assert_eq!(
+            vm.data["spec"]["dataVolumeTemplates"][0]["spec"]["storage"]["storageClassName"],
+            "ceph-rbd"
+        );
+    }
+
+    #[test]
+    fn test_virtual_machine_clones_a_named_image() {
+        let mut options = create_request();
+        options.disk_image = "debian-12".to_owned();
+
+        let vm = virtual_machine(Uuid::nil(), &options, "tenants", None);
+
+        assert_eq!(
+            vm.data["spec"]["dataVolumeTemplates"][0]["spec"]["source"],
+            json!({ "pvc": { "namespace": "tenants", "name": "debian-12" } })
+        );
+    }
+
+    #[test]
+    fn test_virtual_machine_keeps_apart_from_its_anti_affinity() {
+        let mut options = create_request();
+        options.anti_affinity = vec!["vm-0".to_owned()];
+
+        let vm = virtual_machine(Uuid::nil(), &options, "tenants", None);
+        let without = virtual_machine(Uuid::nil(), &create_request(), "tenants", None);
+
+        let term = &vm.data["spec"]["template"]["spec"]["affinity"]["podAntiAffinity"]["preferredDuringSchedulingIgnoredDuringExecution"]
+            [0]["podAffinityTerm"];
+        assert_eq!(
+            term["labelSelector"]["matchExpressions"][0],
+            json!({ "key": VM_NAME_LABEL, "operator": "In", "values": ["vm-0"] })
+        );
+        assert_eq!(term["topologyKey"], "kubernetes.io/hostname");
+        assert!(without.data["spec"]["template"]["spec"]["affinity"].is_null());
+    }
+
+    #[test]
+    fn test_cloned_virtual_machine_clones_the_disks() {
+        let mut source = virtual_machine(Uuid::nil(), &create_request(), "tenants", None);
+        source.data["spec"]["runStrategy"] = json!(RUN_STRATEGY_ALWAYS);
+
+        let clone = cloned_virtual_machine(&source, "vm-2", "tenants");
+
+        let spec = &clone.data["spec"];
+        assert_eq!(clone.name_any(), "vm-2");
+        assert_eq!(spec["runStrategy"], RUN_STRATEGY_HALTED);
+        assert_eq!(
+            spec["dataVolumeTemplates"][0]["metadata"]["name"],
+            "vm-2-disk"
+        );
+        assert_eq!(
+            spec["dataVolumeTemplates"][0]["spec"]["source"],
+            json!({ "pvc": { "namespace": "tenants", "name": "vm-1-disk" } })
+        );
+        assert_eq!(
+            spec["template"]["spec"]["volumes"][0]["dataVolume"]["name"],
+            "vm-2-disk"
+        );
+        assert_eq!(instance(&clone).name, "web");
+    }
+
+    #[test]
+    fn test_status() {
+        let mut vm = virtual_machine(Uuid::nil(), &create_request(), "tenants", None);
+
+        for (printable, expected) in [
+            ("Running", Status::Running),
+            ("Migrating", Status::Running),
+            ("Paused", Status::Paused),
+            ("Stopped", Status::Stopped),
+            ("Provisioning", Status::Unknown),
+        ] {
+            vm.data["status"] = json!({ "printableStatus": printable });
+            assert_eq!(status(&vm).to_string(), expected.to_string());
+        }
+    }
+
+    #[test]
+    fn test_ip_address() {
+        let vmi = DynamicObject::new("vm-1", &virtual_machine_instance_resource()).data(json!({
+            "status": {
+                "interfaces": [
+                    { "name": "lo", "ipAddress": "127.0.0.1" },
+                    {
+                        "name": "default",
+                        "ipAddress": "fe80::1",
+                        "ipAddresses": ["fe80::1", "10.244.1.17"],
+                    },
+                ],
+            },
+        }));
+
+        assert_eq!(ip_address(&vmi), Some(Ipv4Addr::new(10, 244, 1, 17)));
+    }
+
+    #[test]
+    fn test_node_capacities() {
+        let nodes = [
+            node("node-1", "True", "16", "64Gi"),
+            node("node-2", "False", "16", "64Gi"),
+        ];
+        let vmi = DynamicObject::new("vm-1", &virtual_machine_instance_resource()).data(json!({
+            "spec": { "domain": { "cpu": { "cores": 2 }, "memory": { "guest": "4Gi" } } },
+            "status": { "nodeName": "node-1" },
+        }));
+
+        let capacities = node_capacities(&nodes, &[vmi]);
+
+        assert_eq!(capacities.len(), 1);
+        assert_eq!(capacities[0].node, "node-1");
+        assert_eq!(capacities[0].max_cpu, 16);
+        assert_eq!(capacities[0].free_cpu, 14.0);
+        assert_eq!(capacities[0].max_memory_bytes, 64 * GIB);
+        assert_eq!(capacities[0].free_memory_bytes, 60 * GIB);
+    }
+
+    #[test]
+    fn test_parse_quantity() {
+        assert_eq!(parse_quantity("2Gi"), Some(2.0 * GIB as f64));
+        assert_eq!(parse_quantity("500m"), Some(0.5));
+        assert_eq!(parse_quantity("1.5"), Some(1.5));
+        assert_eq!(parse_quantity("2G"), Some(2e9));
+        assert_eq!(parse_quantity("lots"), None);
+    }
+}
@@ -1,10 +1,18 @@
+mod connector;
 mod error;
 pub mod instance;
+pub mod kubevirt;
+pub mod placement;
 pub mod proxmox;
 mod resolver;
+pub mod token;
 
 #[cfg(feature = "mock")]
 pub mod mock;
 
+pub use connector::{Connector, HypervisorKind};
 pub use error::*;
-pub use resolver::resolve;
+pub use kubevirt::KubeVirtConfig;
+pub use proxmox::StoragesConfig;
+pub use proxmox::snippet::{SnippetsConfig, SnippetsTransport};
+pub use resolver::{ConnectorConfig, Resolver};
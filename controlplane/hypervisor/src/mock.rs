@@ -1,14 +1,53 @@
 pub use crate::proxmox::api::api_response::mock::WithApiInternalResponseError;
+pub use crate::proxmox::api::backup_job_create::mock::WithBackupJobCreateMock;
+pub use crate::proxmox::api::backup_job_delete::mock::WithBackupJobDeleteMock;
+pub use crate::proxmox::api::backup_job_list::mock::WithBackupJobListMock;
+pub use crate::proxmox::api::backup_job_update::mock::WithBackupJobUpdateMock;
 pub use crate::proxmox::api::cluster_next_id::mock::WithClusterNextId;
 pub use crate::proxmox::api::cluster_resources_list::mock::WithClusterResourceList;
+pub use crate::proxmox::api::cluster_status::mock::WithClusterStatusMock;
+pub use crate::proxmox::api::storage_content_create::mock::WithStorageContentCreateMock;
+pub use crate::proxmox::api::storage_content_delete::mock::WithStorageContentDeleteMock;
+pub use crate::proxmox::api::storage_content_list::mock::{
+    BACKUP_OWNER, WithStorageContentListMock,
+};
+pub use crate::proxmox::api::storage_download_url::mock::WithStorageDownloadUrlMock;
+pub use crate::proxmox::api::storage_read::mock::WithStorageReadMock;
 pub use crate::proxmox::api::task_status_read::mock::WithTaskStatusReadMock;
+pub use crate::proxmox::api::version_read::mock::WithVersionReadMock;
+pub use crate::proxmox::api::vm_agent_exec::mock::WithVMAgentExecMock;
+pub use crate::proxmox::api::vm_agent_exec_status::mock::WithVMAgentExecStatusMock;
+pub use crate::proxmox::api::vm_agent_file_write::mock::WithVMAgentFileWriteMock;
+pub use crate::proxmox::api::vm_agent_set_user_password::mock::WithVMAgentSetUserPasswordMock;
 pub use crate::proxmox::api::vm_clone::mock::WithVMCloneMock;
 pub use crate::proxmox::api::vm_config_read::mock::WithVMConfigMock;
+pub use crate::proxmox::api::vm_config_update::mock::WithVMConfigUpdateMock;
 pub use crate::proxmox::api::vm_create::mock::WithVMCreateMock;
 pub use crate::proxmox::api::vm_delete::mock::WithVMDeleteMock;
 pub use crate::proxmox::api::vm_disk_resize::mock::WithVMDiskResizeMock;
+pub use crate::proxmox::api::vm_firewall_options_read::mock::WithVMFirewallOptionsReadMock;
+pub use crate::proxmox::api::vm_firewall_options_update::mock::WithVMFirewallOptionsUpdateMock;
+pub use crate::proxmox::api::vm_firewall_rule_create::mock::WithVMFirewallRuleCreateMock;
+pub use crate::proxmox::api::vm_firewall_rule_delete::mock::WithVMFirewallRuleDeleteMock;
+pub use crate::proxmox::api::vm_firewall_rule_list::mock::WithVMFirewallRuleListMock;
 pub use crate::proxmox::api::vm_list::mock::WithVMListMock;
+pub use crate::proxmox::api::vm_migrate::mock::WithVMMigrateMock;
 pub use crate::proxmox::api::vm_network_interfaces::mock::WithVMNetworkInterfaces;
+pub use crate::proxmox::api::vm_pending_read::mock::WithVMPendingReadMock;
+pub use crate::proxmox::api::vm_restore::mock::WithVMRestoreMock;
+pub use crate::proxmox::api::vm_rrddata_read::mock::WithVMRrdDataReadMock;
+pub use crate::proxmox::api::vm_snapshot_create::mock::WithVMSnapshotCreateMock;
+pub use crate::proxmox::api::vm_snapshot_delete::mock::WithVMSnapshotDeleteMock;
+pub use crate::proxmox::api::vm_snapshot_list::mock::WithVMSnapshotListMock;
+pub use crate::proxmox::api::vm_snapshot_rollback::mock::WithVMSnapshotRollbackMock;
 pub use crate::proxmox::api::vm_status_read::mock::WithVMStatusReadMock;
+pub use crate::proxmox::api::vm_status_reboot::mock::WithVMStatusRebootMock;
+pub use crate::proxmox::api::vm_status_reset::mock::WithVMStatusResetMock;
+pub use crate::proxmox::api::vm_status_resume::mock::WithVMStatusResumeMock;
+pub use crate::proxmox::api::vm_status_shutdown::mock::WithVMStatusShutdownMock;
 pub use crate::proxmox::api::vm_status_start::mock::WithVMStatusStartMock;
 pub use crate::proxmox::api::vm_status_stop::mock::WithVMStatusStopMock;
+pub use crate::proxmox::api::vm_status_suspend::mock::WithVMStatusSuspendMock;
+pub use crate::proxmox::api::vm_termproxy::mock::WithVMTermProxyMock;
+pub use crate::proxmox::api::vm_vncproxy::mock::WithVMVncProxyMock;
+pub use crate::proxmox::api::vzdump_create::mock::WithVzdumpCreateMock;
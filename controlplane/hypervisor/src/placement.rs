@@ -0,0 +1,81 @@
+//! Capacity of the nodes of a hypervisor.
+//!
+//! Each kind of hypervisor reports the capacity left on its nodes in these
+//! terms, so instances are scheduled the same way whatever runs them.
+
+/// The resources an instance needs from the node it is placed on.
+#[derive(Clone, Debug, Default)]
+pub struct PlacementRequest {
+    /// The number of cores of the instance.
+    pub cores: u32,
+
+    /// The memory of the instance in bytes.
+    pub memory_bytes: u64,
+
+    /// The disk size of the instance in bytes.
+    pub disk_bytes: u64,
+}
+
+/// The capacity left on a node.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NodeCapacity {
+    /// The node name.
+    pub node: String,
+
+    /// Number of CPUs of the node.
+    pub max_cpu: u32,
+
+    /// Number of CPUs left idle on the node.
+    pub free_cpu: f64,
+
+    /// Total memory of the node in bytes.
+    pub max_memory_bytes: u64,
+
+    /// Memory left on the node in bytes.
+    pub free_memory_bytes: u64,
+
+    /// Total size of the image storage on the node in bytes, if known.
+    pub max_disk_bytes: Option<u64>,
+
+    /// Space left on the image storage of the node in bytes, if known.
+    pub free_disk_bytes: Option<u64>,
+}
+
+impl NodeCapacity {
+    /// Whether the node has room for the requested instance.
+    ///
+    /// CPUs may be overcommitted, but an instance can never get more cores
+    /// than the node has. Memory and storage are never overcommitted.
+    pub fn fits(&self, request: &PlacementRequest) -> bool {
+        self.max_cpu >= request.cores
+            && self.free_memory_bytes >= request.memory_bytes
+            && self
+                .free_disk_bytes
+                .is_none_or(|free| free >= request.disk_bytes)
+    }
+
+    /// Scores the headroom left on the node once the instance is placed.
+    ///
+    /// Each resource contributes its remaining share of the node total, so a
+    /// higher score means a less loaded node.
+    pub fn score(&self, request: &PlacementRequest) -> f64 {
+        let ratio = |free: f64, max: f64| if max > 0.0 { free / max } else { 0.0 };
+
+        let memory = ratio(
+            self.free_memory_bytes.saturating_sub(request.memory_bytes) as f64,
+            self.max_memory_bytes as f64,
+        );
+        let cpu = ratio(
+            (self.free_cpu - request.cores as f64).max(0.0),
+            self.max_cpu as f64,
+        );
+        let disk = match (self.free_disk_bytes, self.max_disk_bytes) {
+            (Some(free), Some(max)) => {
+                ratio(free.saturating_sub(request.disk_bytes) as f64, max as f64)
+            }
+            _ => 0.0,
+        };
+
+        memory + cpu + disk
+    }
+}
@@ -1,5 +1,45 @@
 pub mod api;
+#[cfg(feature = "mock")]
+pub mod fake;
+pub mod firewall;
+pub mod guest;
 pub mod instance;
+pub mod metrics;
+pub mod placement;
+pub mod snippet;
 
 pub const VOLUME_ABSOLUTE_PATH: &str = "/mnt/pve/nfs-snippets";
-pub const VOLUME_NAMED_PATH: &str = "user=nfs-snippets";
+
+/// Storages of the Proxmox hypervisors, shared by all of them.
+#[derive(Clone, Debug)]
+pub struct StoragesConfig {
+    /// The storage the disks of the instances and their volumes are created
+    /// on.
+    pub image: String,
+    /// The storage the backups of the instances are stored on, a Proxmox
+    /// Backup Server storage.
+    pub backup: String,
+}
+
+impl Default for StoragesConfig {
+    fn default() -> Self {
+        Self {
+            image: String::from("local-lvm"),
+            backup: String::from("pbs"),
+        }
+    }
+}
+
+impl StoragesConfig {
+    /// Reads the configuration from the environment (`PROXMOX_IMAGE_STORAGE`
+    /// and `PROXMOX_BACKUP_STORAGE`), falling back on the defaults for the
+    /// unset variables.
+    pub fn from_env() -> Self {
+        let default = Self::default();
+
+        Self {
+            image: std::env::var("PROXMOX_IMAGE_STORAGE").unwrap_or(default.image),
+            backup: std::env::var("PROXMOX_BACKUP_STORAGE").unwrap_or(default.backup),
+        }
+    }
+}
@@ -42,7 +42,10 @@ impl ApiResponseExt for Result<reqwest::Response, reqwest::Error> {
             }
             reqwest::StatusCode::INTERNAL_SERVER_ERROR => {
                 // Regex matching `Problem::MissingAgent` errors
-                let missing_agent_rx = Regex::new(r"^No QEMU guest agent configured\n$").unwrap();
+                let missing_agent_rx = Regex::new(
+                    r"^(No QEMU guest agent configured|QEMU guest agent is not running)\n$",
+                )
+                .unwrap();
                 // Regex matching `Problem::VMNotFound` errors
                 let vm_not_found_rx = Regex::new(
                     r"^Configuration file 'nodes/.*?/qemu-server/(\d+)\.conf' does not exist\n$",
@@ -54,7 +57,7 @@ impl ApiResponseExt for Result<reqwest::Response, reqwest::Error> {
                 let response = response.json::<ApiInternalErrorResponse>().await?;
 
                 match &response.message {
-                    // Handle "No QEMU guest agent configured" error
+                    // Handle "No QEMU guest agent configured" and "not running" errors
                     message if missing_agent_rx.is_match(message) => Err(Error::MissingAgent),
                     // Handle "VM Not Found" error
                     message if vm_not_found_rx.is_match(message) => {
@@ -106,6 +109,7 @@ pub mod mock {
         fn with_vm_not_found_error(self) -> Self;
         fn with_vm_not_running_error(self) -> Self;
         fn with_no_agent_configured_error(self) -> Self;
+        fn with_agent_not_running_error(self) -> Self;
     }
 
     impl WithApiInternalResponseError for MockServer {
@@ -150,6 +154,20 @@ pub mod mock {
 
             self
         }
+
+        fn with_agent_not_running_error(mut self) -> Self {
+            for method in ["DELETE", "GET", "POST", "PATCH", "PUT"].into_iter() {
+                let mock = self
+                    .server
+                    .mock(method, mockito::Matcher::Any)
+                    .with_body(r#"{"data":null,"message":"QEMU guest agent is not running\n"}"#)
+                    .with_status(500)
+                    .create();
+                self.mocks.push(mock);
+            }
+
+            self
+        }
     }
 }
 
@@ -187,6 +205,19 @@ mod tests {
         assert!(matches!(result.unwrap_err(), Error::MissingAgent));
     }
 
+    #[tokio::test]
+    async fn test_an_agent_not_running_error_is_detected_as_a_missing_agent() {
+        // Arrange a client responding with a "QEMU guest agent is not running"
+        let client = reqwest::Client::new();
+        let server = MockServer::new().await.with_agent_not_running_error();
+
+        // Act the call to the function
+        let result = cluster_next_id(&server.url(), &client, "").await;
+
+        assert!(result.is_err());
+        assert!(matches!(result.unwrap_err(), Error::MissingAgent));
+    }
+
     #[tokio::test]
     async fn test_a_vm_not_running_error_is_properly_detected() {
         // Arrange a client responding with a "vm not running error"
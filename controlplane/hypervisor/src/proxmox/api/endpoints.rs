@@ -1,27 +1,101 @@
+pub mod backup_job_create;
+pub mod backup_job_delete;
+pub mod backup_job_list;
+pub mod backup_job_update;
 pub mod cluster_next_id;
 pub mod cluster_resources_list;
+pub mod cluster_status;
+pub mod storage_content_create;
+pub mod storage_content_delete;
+pub mod storage_content_list;
+pub mod storage_download_url;
+pub mod storage_read;
 pub mod task_status_read;
+pub mod version_read;
+pub mod vm_agent_exec;
+pub mod vm_agent_exec_status;
+pub mod vm_agent_file_write;
+pub mod vm_agent_set_user_password;
 pub mod vm_clone;
 pub mod vm_config_read;
+pub mod vm_config_update;
 pub mod vm_create;
 pub mod vm_delete;
 pub mod vm_disk_resize;
+pub mod vm_firewall_options_read;
+pub mod vm_firewall_options_update;
+pub mod vm_firewall_rule_create;
+pub mod vm_firewall_rule_delete;
+pub mod vm_firewall_rule_list;
 pub mod vm_list;
+pub mod vm_migrate;
 pub mod vm_network_interfaces;
+pub mod vm_pending_read;
+pub mod vm_restore;
+pub mod vm_rrddata_read;
+pub mod vm_snapshot_create;
+pub mod vm_snapshot_delete;
+pub mod vm_snapshot_list;
+pub mod vm_snapshot_rollback;
 pub mod vm_status_read;
+pub mod vm_status_reboot;
+pub mod vm_status_reset;
+pub mod vm_status_resume;
+pub mod vm_status_shutdown;
 pub mod vm_status_start;
 pub mod vm_status_stop;
+pub mod vm_status_suspend;
+pub mod vm_termproxy;
+pub mod vm_vncproxy;
+pub mod vzdump_create;
 
+pub use backup_job_create::backup_job_create;
+pub use backup_job_delete::backup_job_delete;
+pub use backup_job_list::backup_job_list;
+pub use backup_job_update::backup_job_update;
 pub use cluster_next_id::cluster_next_id;
 pub use cluster_resources_list::cluster_resources_list;
+pub use cluster_status::cluster_status;
+pub use storage_content_create::storage_content_create;
+pub use storage_content_delete::storage_content_delete;
+pub use storage_content_list::storage_content_list;
+pub use storage_download_url::storage_download_url;
+pub use storage_read::storage_read;
 pub use task_status_read::task_status_read;
+pub use version_read::version_read;
+pub use vm_agent_exec::vm_agent_exec;
+pub use vm_agent_exec_status::vm_agent_exec_status;
+pub use vm_agent_file_write::vm_agent_file_write;
+pub use vm_agent_set_user_password::vm_agent_set_user_password;
 pub use vm_clone::vm_clone;
 pub use vm_config_read::vm_config_read;
+pub use vm_config_update::vm_config_update;
 pub use vm_create::vm_create;
 pub use vm_delete::vm_delete;
 pub use vm_disk_resize::vm_disk_resize;
+pub use vm_firewall_options_read::vm_firewall_options_read;
+pub use vm_firewall_options_update::vm_firewall_options_update;
+pub use vm_firewall_rule_create::vm_firewall_rule_create;
+pub use vm_firewall_rule_delete::vm_firewall_rule_delete;
+pub use vm_firewall_rule_list::vm_firewall_rule_list;
 pub use vm_list::vm_list;
+pub use vm_migrate::vm_migrate;
 pub use vm_network_interfaces::vm_network_interfaces;
+pub use vm_pending_read::vm_pending_read;
+pub use vm_restore::vm_restore;
+pub use vm_rrddata_read::vm_rrddata_read;
+pub use vm_snapshot_create::vm_snapshot_create;
+pub use vm_snapshot_delete::vm_snapshot_delete;
+pub use vm_snapshot_list::vm_snapshot_list;
+pub use vm_snapshot_rollback::vm_snapshot_rollback;
 pub use vm_status_read::vm_status_read;
+pub use vm_status_reboot::vm_status_reboot;
+pub use vm_status_reset::vm_status_reset;
+pub use vm_status_resume::vm_status_resume;
+pub use vm_status_shutdown::vm_status_shutdown;
 pub use vm_status_start::vm_status_start;
 pub use vm_status_stop::vm_status_stop;
+pub use vm_status_suspend::vm_status_suspend;
+pub use vm_termproxy::vm_termproxy;
+pub use vm_vncproxy::vm_vncproxy;
+pub use vzdump_create::vzdump_create;
@@ -0,0 +1,104 @@
+use crate::proxmox::api::Error;
+use crate::proxmox::api::api_response::{ApiResponse, ApiResponseExt};
+use serde::Serialize;
+use serde_with::skip_serializing_none;
+
+/// Creates a scheduled backup job on the cluster.
+///
+/// Calls `POST /cluster/backup`.
+pub async fn backup_job_create(
+    api_url: &str,
+    client: &reqwest::Client,
+    authorization: &str,
+    options: &BackupJobOptions,
+) -> Result<ApiResponse<Option<String>>, Error> {
+    client
+        .post(format!("{}/api2/json/cluster/backup", api_url))
+        .header(reqwest::header::AUTHORIZATION, authorization)
+        .json(options)
+        .send()
+        .await
+        .to_api_response()
+        .await
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Serialize)]
+pub struct BackupJobOptions {
+    /// The job identifier, only set on creation.
+    pub id: Option<String>,
+
+    /// The systemd calendar event the job runs on (e.g. `daily`).
+    pub schedule: String,
+
+    /// The comma-separated ids of the VMs to back up.
+    pub vmid: String,
+
+    /// The storage to store the backups on.
+    pub storage: String,
+
+    /// The backup mode (`snapshot`, `suspend` or `stop`).
+    pub mode: String,
+
+    /// Whether the job runs.
+    pub enabled: bool,
+
+    /// The retention applied to the backups of the VMs after each run (e.g.
+    /// `keep-last=3,keep-daily=7`), the one of the storage when unset.
+    #[serde(rename = "prune-backups")]
+    pub prune_backups: Option<String>,
+
+    /// The notes attached to the backups (e.g. `{{guestname}}`).
+    #[serde(rename = "notes-template")]
+    pub notes_template: Option<String>,
+}
+
+#[cfg(feature = "mock")]
+pub mod mock {
+    use mock_server::MockServer;
+
+    pub trait WithBackupJobCreateMock {
+        fn with_backup_job_create(self) -> Self;
+    }
+
+    impl WithBackupJobCreateMock for MockServer {
+        fn with_backup_job_create(mut self) -> Self {
+            let mock = self
+                .server
+                .mock(
+                    "POST",
+                    mockito::Matcher::Regex(r"^/api2/json/cluster/backup$".to_string()),
+                )
+                .with_body(r#"{"data":null}"#)
+                .create();
+            self.mocks.push(mock);
+            self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::WithBackupJobCreateMock;
+    use super::*;
+    use mock_server::MockServer;
+
+    #[tokio::test]
+    async fn test_backup_job_create() {
+        let client = reqwest::Client::new();
+        let server = MockServer::new().await.with_backup_job_create();
+        let options = BackupJobOptions {
+            id: Some("frn-vm-100".to_owned()),
+            schedule: "daily".to_owned(),
+            vmid: "100".to_owned(),
+            storage: "pbs".to_owned(),
+            mode: "snapshot".to_owned(),
+            enabled: true,
+            prune_backups: Some("keep-daily=7".to_owned()),
+            notes_template: None,
+        };
+        let result = backup_job_create(&server.url(), &client, "", &options).await;
+
+        assert!(result.is_ok());
+    }
+}
@@ -0,0 +1,60 @@
+use crate::proxmox::api::Error;
+use crate::proxmox::api::api_response::{ApiResponse, ApiResponseExt};
+
+/// Deletes a scheduled backup job of the cluster, keeping its backups.
+///
+/// Calls `DELETE /cluster/backup/{id}`.
+pub async fn backup_job_delete(
+    api_url: &str,
+    client: &reqwest::Client,
+    authorization: &str,
+    id: &str,
+) -> Result<ApiResponse<Option<String>>, Error> {
+    client
+        .delete(format!("{}/api2/json/cluster/backup/{}", api_url, id))
+        .header(reqwest::header::AUTHORIZATION, authorization)
+        .send()
+        .await
+        .to_api_response()
+        .await
+}
+
+#[cfg(feature = "mock")]
+pub mod mock {
+    use mock_server::MockServer;
+
+    pub trait WithBackupJobDeleteMock {
+        fn with_backup_job_delete(self) -> Self;
+    }
+
+    impl WithBackupJobDeleteMock for MockServer {
+        fn with_backup_job_delete(mut self) -> Self {
+            let mock = self
+                .server
+                .mock(
+                    "DELETE",
+                    mockito::Matcher::Regex(r"^/api2/json/cluster/backup/[^/]+$".to_string()),
+                )
+                .with_body(r#"{"data":null}"#)
+                .create();
+            self.mocks.push(mock);
+            self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::WithBackupJobDeleteMock;
+    use super::*;
+    use mock_server::MockServer;
+
+    #[tokio::test]
+    async fn test_backup_job_delete() {
+        let client = reqwest::Client::new();
+        let server = MockServer::new().await.with_backup_job_delete();
+        let result = backup_job_delete(&server.url(), &client, "", "frn-vm-100").await;
+
+        assert!(result.is_ok());
+    }
+}
@@ -0,0 +1,84 @@
+use crate::proxmox::api::Error;
+use crate::proxmox::api::api_response::{ApiResponse, ApiResponseExt};
+use serde::Deserialize;
+
+/// Lists the scheduled backup jobs of the cluster.
+///
+/// Calls `GET /cluster/backup`.
+pub async fn backup_job_list(
+    api_url: &str,
+    client: &reqwest::Client,
+    authorization: &str,
+) -> Result<ApiResponse<Vec<BackupJob>>, Error> {
+    client
+        .get(format!("{}/api2/json/cluster/backup", api_url))
+        .header(reqwest::header::AUTHORIZATION, authorization)
+        .send()
+        .await
+        .to_api_response()
+        .await
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct BackupJob {
+    /// The job identifier.
+    pub id: String,
+
+    /// The systemd calendar event the job runs on (e.g. `daily`).
+    pub schedule: String,
+
+    /// The comma-separated ids of the VMs backed up by the job, if it does not
+    /// back up all of them.
+    pub vmid: Option<String>,
+
+    /// The storage the backups are stored on.
+    pub storage: Option<String>,
+}
+
+#[cfg(feature = "mock")]
+pub mod mock {
+    use mock_server::MockServer;
+
+    pub trait WithBackupJobListMock {
+        fn with_backup_job_list(self) -> Self;
+    }
+
+    impl WithBackupJobListMock for MockServer {
+        fn with_backup_job_list(mut self) -> Self {
+            let mock = self
+                .server
+                .mock(
+                    "GET",
+                    mockito::Matcher::Regex(r"^/api2/json/cluster/backup$".to_string()),
+                )
+                .with_body(r#"{"data":[{"id":"frn-vm-100","type":"vzdump","schedule":"daily","vmid":"100","storage":"pbs","mode":"snapshot","enabled":1,"next-run":1741478400}]}"#)
+                .create();
+            self.mocks.push(mock);
+            self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::WithBackupJobListMock;
+    use super::*;
+    use mock_server::MockServer;
+
+    #[tokio::test]
+    async fn test_backup_job_list() {
+        let client = reqwest::Client::new();
+        let server = MockServer::new().await.with_backup_job_list();
+        let result = backup_job_list(&server.url(), &client, "").await;
+
+        assert_eq!(
+            result.unwrap().data,
+            vec![BackupJob {
+                id: "frn-vm-100".to_owned(),
+                schedule: "daily".to_owned(),
+                vmid: Some("100".to_owned()),
+                storage: Some("pbs".to_owned()),
+            }]
+        );
+    }
+}
@@ -0,0 +1,73 @@
+use crate::proxmox::api::Error;
+use crate::proxmox::api::api_response::{ApiResponse, ApiResponseExt};
+use crate::proxmox::api::backup_job_create::BackupJobOptions;
+
+/// Updates a scheduled backup job of the cluster.
+///
+/// Calls `PUT /cluster/backup/{id}`.
+pub async fn backup_job_update(
+    api_url: &str,
+    client: &reqwest::Client,
+    authorization: &str,
+    id: &str,
+    options: &BackupJobOptions,
+) -> Result<ApiResponse<Option<String>>, Error> {
+    client
+        .put(format!("{}/api2/json/cluster/backup/{}", api_url, id))
+        .header(reqwest::header::AUTHORIZATION, authorization)
+        .json(options)
+        .send()
+        .await
+        .to_api_response()
+        .await
+}
+
+#[cfg(feature = "mock")]
+pub mod mock {
+    use mock_server::MockServer;
+
+    pub trait WithBackupJobUpdateMock {
+        fn with_backup_job_update(self) -> Self;
+    }
+
+    impl WithBackupJobUpdateMock for MockServer {
+        fn with_backup_job_update(mut self) -> Self {
+            let mock = self
+                .server
+                .mock(
+                    "PUT",
+                    mockito::Matcher::Regex(r"^/api2/json/cluster/backup/[^/]+$".to_string()),
+                )
+                .with_body(r#"{"data":null}"#)
+                .create();
+            self.mocks.push(mock);
+            self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::WithBackupJobUpdateMock;
+    use super::*;
+    use mock_server::MockServer;
+
+    #[tokio::test]
+    async fn test_backup_job_update() {
+        let client = reqwest::Client::new();
+        let server = MockServer::new().await.with_backup_job_update();
+        let options = BackupJobOptions {
+            id: None,
+            schedule: "sun 02:00".to_owned(),
+            vmid: "100".to_owned(),
+            storage: "pbs".to_owned(),
+            mode: "snapshot".to_owned(),
+            enabled: true,
+            prune_backups: None,
+            notes_template: None,
+        };
+        let result = backup_job_update(&server.url(), &client, "", "frn-vm-100", &options).await;
+
+        assert!(result.is_ok());
+    }
+}
@@ -1,7 +1,7 @@
 use crate::instance::Instance;
 use crate::proxmox::api::Error;
-use crate::proxmox::api::ResourceStatus;
 use crate::proxmox::api::api_response::{ApiResponse, ApiResponseExt};
+use crate::proxmox::api::{ResourceStatus, instance_status};
 use serde::Deserialize;
 use std::fmt::Display;
 
@@ -40,6 +40,9 @@ pub struct Resource {
     /// Number of available memory in bytes (for types 'node', 'qemu' and 'lxc').
     pub maxmem: Option<u64>,
 
+    /// The lock held on the resource, if any (for types 'qemu' and 'lxc').
+    pub lock: Option<String>,
+
     /// Used memory in bytes (for types 'node', 'qemu' and 'lxc').
     pub mem: Option<u64>,
 
@@ -53,9 +56,15 @@ pub struct Resource {
     #[serde(rename = "type")]
     pub resource_type: ResourceType,
 
+    /// Whether the storage is shared between nodes (for type 'storage').
+    pub shared: Option<u8>,
+
     /// Resource type dependent status.
     pub status: ResourceStatus,
 
+    /// The storage identifier (for type 'storage').
+    pub storage: Option<String>,
+
     /// The numerical vmid (for types 'qemu' and 'lxc').
     pub vmid: Option<u32>,
 }
@@ -96,14 +105,14 @@ impl TryFrom<Resource> for Instance {
             disk_usage_bytes: value.disk.unwrap_or_default(),
             id: value
                 .vmid
-                .ok_or(Error::NotAnInstance(value.clone()))?
+                .ok_or_else(|| Error::NotAnInstance(Box::new(value.clone())))?
                 .to_string(),
             max_cpu_cores: value.maxcpu.unwrap_or_default(),
             max_disk_bytes: value.maxdisk.unwrap_or_default(),
             max_memory_bytes: value.maxmem.unwrap_or_default(),
             memory_usage_bytes: value.mem.unwrap_or_default(),
             name: value.name.unwrap_or_default(),
-            status: value.status.into(),
+            status: instance_status(value.status, value.lock.as_deref()),
         };
 
         Ok(info)
@@ -142,6 +151,17 @@ pub mod mock {
                 .create();
             self.mocks.push(node_mock);
 
+            // Mock for type=storage - returns only Storage resources
+            let storage_mock = self
+                .server
+                .mock(
+                    "GET",
+                    "/api2/json/cluster/resources?type=storage",
+                )
+                .with_body(r#"{"data":[{"status":"available","storage":"local-lvm","plugintype":"lvmthin","shared":0,"content":"rootdir,images","node":"pve-node1","type":"storage","id":"storage/pve-node1/local-lvm","disk":21474836480,"maxdisk":429496729600},{"status":"available","storage":"local","plugintype":"dir","shared":0,"content":"iso,vztmpl,import,snippets","node":"pve-node1","type":"storage","id":"storage/pve-node1/local","disk":8589934592,"maxdisk":107374182400}]}"#)
+                .create();
+            self.mocks.push(storage_mock);
+
             self
         }
     }
@@ -169,11 +189,14 @@ mod tests {
                 maxcpu: Some(1),
                 maxdisk: Some(53687091200),
                 maxmem: Some(4294967296),
+                lock: None,
                 mem: Some(1395277824),
                 name: Some(String::from("proxmox-dev")),
                 node: Some(String::from("pve-node1")),
                 resource_type: ResourceType::Qemu,
+                shared: None,
                 status: ResourceStatus::Running,
+                storage: None,
                 vmid: Some(100),
             }]
         );
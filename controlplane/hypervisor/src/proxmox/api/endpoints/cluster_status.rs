@@ -0,0 +1,80 @@
+use crate::proxmox::api::Error;
+use crate::proxmox::api::api_response::{ApiResponse, ApiResponseExt};
+use serde::Deserialize;
+
+/// Reads the status of the cluster and of its nodes.
+///
+/// Calls `GET /cluster/status`.
+pub async fn cluster_status(
+    api_url: &str,
+    client: &reqwest::Client,
+    authorization: &str,
+) -> Result<ApiResponse<Vec<ClusterStatus>>, Error> {
+    client
+        .get(format!("{}/api2/json/cluster/status", api_url))
+        .header(reqwest::header::AUTHORIZATION, authorization)
+        .send()
+        .await
+        .to_api_response()
+        .await
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct ClusterStatus {
+    /// The kind of entry, `cluster` or `node`.
+    #[serde(rename = "type")]
+    pub kind: String,
+
+    /// The name of the cluster or of the node.
+    pub name: String,
+
+    /// The address of the node on the cluster network.
+    pub ip: Option<String>,
+}
+
+#[cfg(feature = "mock")]
+pub mod mock {
+    use mock_server::MockServer;
+
+    pub trait WithClusterStatusMock {
+        fn with_cluster_status(self) -> Self;
+    }
+
+    impl WithClusterStatusMock for MockServer {
+        fn with_cluster_status(mut self) -> Self {
+            let mock = self
+                .server
+                .mock(
+                    "GET",
+                    mockito::Matcher::Regex(r"^/api2/json/cluster/status$".to_string()),
+                )
+                .with_body(r#"{"data":[{"type":"cluster","id":"cluster","name":"pve","nodes":2,"quorate":1,"version":4},{"type":"node","id":"node/pve-node1","name":"pve-node1","ip":"10.0.0.11","online":1,"local":1,"nodeid":1},{"type":"node","id":"node/pve-node2","name":"pve-node2","ip":"10.0.0.12","online":1,"local":0,"nodeid":2}]}"#)
+                .create();
+            self.mocks.push(mock);
+            self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::WithClusterStatusMock;
+    use super::*;
+    use mock_server::MockServer;
+
+    #[tokio::test]
+    async fn test_cluster_status() {
+        let client = reqwest::Client::new();
+        let server = MockServer::new().await.with_cluster_status();
+        let result = cluster_status(&server.url(), &client, "").await;
+
+        assert_eq!(
+            result.unwrap().data[1],
+            ClusterStatus {
+                kind: "node".to_owned(),
+                name: "pve-node1".to_owned(),
+                ip: Some("10.0.0.11".to_owned()),
+            }
+        );
+    }
+}
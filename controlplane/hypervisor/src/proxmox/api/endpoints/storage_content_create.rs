@@ -0,0 +1,102 @@
+use crate::proxmox::api::Error;
+use crate::proxmox::api::api_response::{ApiResponse, ApiResponseExt};
+use serde::Serialize;
+
+/// Request body for the Proxmox storage content allocation endpoint.
+#[derive(Debug, Serialize)]
+struct StorageContentCreateRequest<'a> {
+    /// The VM owning the volume.
+    vmid: u32,
+    /// The volume name, generated by Proxmox when empty.
+    filename: &'a str,
+    /// The volume size in Proxmox format (e.g. `10G`).
+    size: &'a str,
+}
+
+/// Allocates a new volume on a storage, owned by the given VM.
+///
+/// The volume is not attached to the VM. Returns the volume id (e.g.
+/// `local-lvm:vm-100-disk-1`).
+///
+/// Calls `POST /nodes/{node}/storage/{storage}/content`.
+pub async fn storage_content_create(
+    api_url: &str,
+    client: &reqwest::Client,
+    authorization: &str,
+    node_id: &str,
+    storage: &str,
+    vm_id: u32,
+    size_bytes: u64,
+) -> Result<ApiResponse<String>, Error> {
+    let size_gb = size_bytes.div_ceil(1024 * 1024 * 1024);
+    let size = &format!("{}G", size_gb);
+    let body = StorageContentCreateRequest {
+        vmid: vm_id,
+        filename: "",
+        size,
+    };
+
+    client
+        .post(format!(
+            "{}/api2/json/nodes/{}/storage/{}/content",
+            api_url, node_id, storage
+        ))
+        .header(reqwest::header::AUTHORIZATION, authorization)
+        .json(&body)
+        .send()
+        .await
+        .to_api_response()
+        .await
+}
+
+#[cfg(feature = "mock")]
+pub mod mock {
+    use mock_server::MockServer;
+
+    pub trait WithStorageContentCreateMock {
+        fn with_storage_content_create(self) -> Self;
+    }
+
+    impl WithStorageContentCreateMock for MockServer {
+        fn with_storage_content_create(mut self) -> Self {
+            let mock = self
+                .server
+                .mock(
+                    "POST",
+                    mockito::Matcher::Regex(
+                        r"^/api2/json/nodes/.*/storage/.*/content$".to_string(),
+                    ),
+                )
+                .with_body(r#"{"data":"local-lvm:vm-100-disk-3"}"#)
+                .create();
+            self.mocks.push(mock);
+            self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::WithStorageContentCreateMock;
+    use super::*;
+    use mock_server::MockServer;
+
+    #[tokio::test]
+    async fn test_storage_content_create() {
+        let client = reqwest::Client::new();
+        let server = MockServer::new().await.with_storage_content_create();
+        let result = storage_content_create(
+            &server.url(),
+            &client,
+            "",
+            "pve-node1",
+            "local-lvm",
+            100,
+            10737418240,
+        )
+        .await;
+
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap().data, "local-lvm:vm-100-disk-3");
+    }
+}
@@ -0,0 +1,81 @@
+use crate::proxmox::api::Error;
+use crate::proxmox::api::api_response::{ApiResponse, ApiResponseExt};
+
+/// Deletes a volume from a storage of a node.
+///
+/// `volume` is the volume id (e.g. `nfs-snippets:snippets/vm-100-user.yaml`).
+/// Returns the task id of the deletion, if the storage runs it as a task.
+///
+/// Calls `DELETE /nodes/{node}/storage/{storage}/content/{volume}`.
+pub async fn storage_content_delete(
+    api_url: &str,
+    client: &reqwest::Client,
+    authorization: &str,
+    node_id: &str,
+    storage: &str,
+    volume: &str,
+) -> Result<ApiResponse<Option<String>>, Error> {
+    client
+        .delete(format!(
+            "{}/api2/json/nodes/{}/storage/{}/content/{}",
+            api_url,
+            node_id,
+            storage,
+            url::form_urlencoded::byte_serialize(volume.as_bytes()).collect::<String>()
+        ))
+        .header(reqwest::header::AUTHORIZATION, authorization)
+        .send()
+        .await
+        .to_api_response()
+        .await
+}
+
+#[cfg(feature = "mock")]
+pub mod mock {
+    use mock_server::MockServer;
+
+    pub trait WithStorageContentDeleteMock {
+        fn with_storage_content_delete(self) -> Self;
+    }
+
+    impl WithStorageContentDeleteMock for MockServer {
+        fn with_storage_content_delete(mut self) -> Self {
+            let mock = self
+                .server
+                .mock(
+                    "DELETE",
+                    mockito::Matcher::Regex(
+                        r"^/api2/json/nodes/.*/storage/.*/content/.*$".to_string(),
+                    ),
+                )
+                .with_body(r#"{"data":null}"#)
+                .create();
+            self.mocks.push(mock);
+            self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::WithStorageContentDeleteMock;
+    use super::*;
+    use mock_server::MockServer;
+
+    #[tokio::test]
+    async fn test_storage_content_delete() {
+        let client = reqwest::Client::new();
+        let server = MockServer::new().await.with_storage_content_delete();
+        let result = storage_content_delete(
+            &server.url(),
+            &client,
+            "",
+            "pve-node1",
+            "nfs-snippets",
+            "nfs-snippets:snippets/vm-100-user.yaml",
+        )
+        .await;
+
+        assert_eq!(result.unwrap().data, None);
+    }
+}
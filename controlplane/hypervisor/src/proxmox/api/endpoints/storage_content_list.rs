@@ -0,0 +1,179 @@
+use crate::instance::{Backup, Image, ImageKind};
+use crate::proxmox::api::Error;
+use crate::proxmox::api::api_response::{ApiResponse, ApiResponseExt};
+use serde::Deserialize;
+
+/// Lists the volumes held by a storage of a node.
+///
+/// `content` restricts the listing to a content type (e.g. `iso`).
+///
+/// Calls `GET /nodes/{node}/storage/{storage}/content`.
+pub async fn storage_content_list(
+    api_url: &str,
+    client: &reqwest::Client,
+    authorization: &str,
+    node_id: &str,
+    storage: &str,
+    content: Option<&str>,
+) -> Result<ApiResponse<Vec<StorageContent>>, Error> {
+    let mut request = client
+        .get(format!(
+            "{}/api2/json/nodes/{}/storage/{}/content",
+            api_url, node_id, storage
+        ))
+        .header(reqwest::header::AUTHORIZATION, authorization);
+    if let Some(content) = content {
+        request = request.query(&[("content", content)]);
+    }
+
+    request.send().await.to_api_response().await
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct StorageContent {
+    /// Volume identifier (e.g. `local:iso/debian-12.iso`).
+    pub volid: String,
+
+    /// Content type of the volume (e.g. `iso`, `import`, `backup`).
+    pub content: String,
+
+    /// Format of the volume (e.g. `iso`, `qcow2`, `raw`).
+    pub format: String,
+
+    /// Volume size in bytes.
+    pub size: u64,
+
+    /// Creation time of the volume.
+    pub ctime: Option<u64>,
+
+    /// The id of the VM owning the volume, if any.
+    pub vmid: Option<u32>,
+
+    /// The notes attached to a backup.
+    pub notes: Option<String>,
+}
+
+impl StorageContent {
+    /// Gets the kind of image the volume holds, if any.
+    pub fn image_kind(&self) -> Option<ImageKind> {
+        match self.content.as_str() {
+            "iso" => Some(ImageKind::Iso),
+            "import" => Some(ImageKind::Disk),
+            _ => None,
+        }
+    }
+}
+
+impl TryFrom<StorageContent> for Image {
+    type Error = Error;
+
+    fn try_from(value: StorageContent) -> Result<Self, Self::Error> {
+        let kind = value
+            .image_kind()
+            .ok_or_else(|| Error::NotAnImage(value.volid.clone()))?;
+        let name = value
+            .volid
+            .rsplit_once('/')
+            .map(|(_, name)| name.to_owned())
+            .unwrap_or_else(|| value.volid.clone());
+
+        Ok(Image {
+            id: value.volid,
+            name,
+            kind,
+            format: value.format,
+            size_bytes: value.size,
+            created_at: value.ctime,
+        })
+    }
+}
+
+impl TryFrom<StorageContent> for Backup {
+    type Error = Error;
+
+    fn try_from(value: StorageContent) -> Result<Self, Self::Error> {
+        if value.content != "backup" {
+            return Err(Error::NotABackup(value.volid));
+        }
+
+        Ok(Backup {
+            id: value.volid,
+            size_bytes: value.size,
+            notes: value.notes,
+            created_at: value.ctime,
+        })
+    }
+}
+
+#[cfg(feature = "mock")]
+pub mod mock {
+    use mock_server::MockServer;
+    use uuid::Uuid;
+
+    /// The instance the backup listed by the mock is bound to.
+    pub const BACKUP_OWNER: Uuid = Uuid::from_u128(0x5c0ffee0_0000_4000_8000_000000000100);
+
+    pub trait WithStorageContentListMock {
+        fn with_storage_content_list(self) -> Self;
+    }
+
+    impl WithStorageContentListMock for MockServer {
+        fn with_storage_content_list(mut self) -> Self {
+            let mock = self
+                .server
+                .mock(
+                    "GET",
+                    mockito::Matcher::Regex(
+                        r"^/api2/json/nodes/.*/storage/.*/content(\?.*)?$".to_string(),
+                    ),
+                )
+                .with_body(r#"{"data":[{"volid":"local:iso/debian-12.7.0-amd64-netinst.iso","content":"iso","format":"iso","size":661651456,"ctime":1725635720},{"volid":"local:import/debian-12-genericcloud-amd64.qcow2","content":"import","format":"qcow2","size":333774848,"ctime":1733073600},{"volid":"local:snippets/vm-100-user.yaml","content":"snippets","format":"snippets","size":120,"ctime":1741455296},{"volid":"pbs:backup/vm/100/2025-03-08T18:00:00Z","content":"backup","format":"pbs-vm","size":2147483648,"ctime":1741456800,"vmid":100,"notes":"frn-instance=5c0ffee0-0000-4000-8000-000000000100"}]}"#)
+                .create();
+            self.mocks.push(mock);
+            self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::WithStorageContentListMock;
+    use super::*;
+    use mock_server::MockServer;
+
+    #[tokio::test]
+    async fn test_storage_content_list() {
+        let client = reqwest::Client::new();
+        let server = MockServer::new().await.with_storage_content_list();
+        let result =
+            storage_content_list(&server.url(), &client, "", "pve-node1", "local", None).await;
+
+        let images: Vec<Image> = result
+            .unwrap()
+            .data
+            .into_iter()
+            .filter_map(|content| content.try_into().ok())
+            .collect();
+        assert_eq!(
+            images,
+            vec![
+                Image {
+                    id: "local:iso/debian-12.7.0-amd64-netinst.iso".to_owned(),
+                    name: "debian-12.7.0-amd64-netinst.iso".to_owned(),
+                    kind: ImageKind::Iso,
+                    format: "iso".to_owned(),
+                    size_bytes: 661651456,
+                    created_at: Some(1725635720),
+                },
+                Image {
+                    id: "local:import/debian-12-genericcloud-amd64.qcow2".to_owned(),
+                    name: "debian-12-genericcloud-amd64.qcow2".to_owned(),
+                    kind: ImageKind::Disk,
+                    format: "qcow2".to_owned(),
+                    size_bytes: 333774848,
+                    created_at: Some(1733073600),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,89 @@
+use crate::proxmox::api::Error;
+use crate::proxmox::api::api_response::{ApiResponse, ApiResponseExt};
+use serde::Serialize;
+
+/// Downloads a file from a URL to a storage of a node.
+///
+/// Returns the task id of the download.
+///
+/// Calls `POST /nodes/{node}/storage/{storage}/download-url`.
+pub async fn storage_download_url(
+    api_url: &str,
+    client: &reqwest::Client,
+    authorization: &str,
+    node_id: &str,
+    storage: &str,
+    options: &StorageDownloadUrlOptions,
+) -> Result<ApiResponse<String>, Error> {
+    client
+        .post(format!(
+            "{}/api2/json/nodes/{}/storage/{}/download-url",
+            api_url, node_id, storage
+        ))
+        .header(reqwest::header::AUTHORIZATION, authorization)
+        .json(options)
+        .send()
+        .await
+        .to_api_response()
+        .await
+}
+
+#[derive(Debug, Serialize)]
+pub struct StorageDownloadUrlOptions {
+    /// The URL to download the file from.
+    pub url: String,
+
+    /// The content type the file is stored as (`iso`, `vztmpl` or `import`).
+    pub content: String,
+
+    /// The name of the file on the storage.
+    pub filename: String,
+}
+
+#[cfg(feature = "mock")]
+pub mod mock {
+    use mock_server::MockServer;
+
+    pub trait WithStorageDownloadUrlMock {
+        fn with_storage_download_url(self) -> Self;
+    }
+
+    impl WithStorageDownloadUrlMock for MockServer {
+        fn with_storage_download_url(mut self) -> Self {
+            let mock = self
+                .server
+                .mock(
+                    "POST",
+                    mockito::Matcher::Regex(
+                        r"^/api2/json/nodes/.*/storage/.*/download-url$".to_string(),
+                    ),
+                )
+                .with_body(r#"{"data":"UPID:pve-node1:0021C9B2:0233D8E4:67CC8043:download:debian-12-genericcloud-amd64.qcow2:root@pam!api:"}"#)
+                .create();
+            self.mocks.push(mock);
+            self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::WithStorageDownloadUrlMock;
+    use super::*;
+    use mock_server::MockServer;
+
+    #[tokio::test]
+    async fn test_storage_download_url() {
+        let client = reqwest::Client::new();
+        let server = MockServer::new().await.with_storage_download_url();
+        let options = StorageDownloadUrlOptions {
+            url: "https://cloud.debian.org/images/cloud/bookworm/latest/debian-12-genericcloud-amd64.qcow2".to_owned(),
+            content: "import".to_owned(),
+            filename: "debian-12-genericcloud-amd64.qcow2".to_owned(),
+        };
+        let result =
+            storage_download_url(&server.url(), &client, "", "pve-node1", "local", &options).await;
+
+        assert!(result.is_ok());
+    }
+}
@@ -0,0 +1,82 @@
+use crate::proxmox::api::Error;
+use crate::proxmox::api::api_response::{ApiResponse, ApiResponseExt};
+use serde::Deserialize;
+
+/// Reads the configuration of a storage.
+///
+/// Calls `GET /storage/{storage}`.
+pub async fn storage_read(
+    api_url: &str,
+    client: &reqwest::Client,
+    authorization: &str,
+    storage: &str,
+) -> Result<ApiResponse<StorageConfig>, Error> {
+    client
+        .get(format!("{}/api2/json/storage/{}", api_url, storage))
+        .header(reqwest::header::AUTHORIZATION, authorization)
+        .send()
+        .await
+        .to_api_response()
+        .await
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct StorageConfig {
+    /// The storage identifier.
+    pub storage: String,
+
+    /// The type of the storage (e.g. `dir`, `nfs`).
+    #[serde(rename = "type")]
+    pub kind: String,
+
+    /// The path the storage is mounted at on the nodes, for file based
+    /// storages.
+    pub path: Option<String>,
+}
+
+#[cfg(feature = "mock")]
+pub mod mock {
+    use mock_server::MockServer;
+
+    pub trait WithStorageReadMock {
+        fn with_storage_read(self) -> Self;
+    }
+
+    impl WithStorageReadMock for MockServer {
+        fn with_storage_read(mut self) -> Self {
+            let mock = self
+                .server
+                .mock(
+                    "GET",
+                    mockito::Matcher::Regex(r"^/api2/json/storage/[^/]+$".to_string()),
+                )
+                .with_body(r#"{"data":{"storage":"nfs-snippets","type":"nfs","path":"/mnt/pve/nfs-snippets","content":"snippets","server":"10.0.0.2","export":"/srv/snippets","digest":"7b3b0c2e"}}"#)
+                .create();
+            self.mocks.push(mock);
+            self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::WithStorageReadMock;
+    use super::*;
+    use mock_server::MockServer;
+
+    #[tokio::test]
+    async fn test_storage_read() {
+        let client = reqwest::Client::new();
+        let server = MockServer::new().await.with_storage_read();
+        let result = storage_read(&server.url(), &client, "", "nfs-snippets").await;
+
+        assert_eq!(
+            result.unwrap().data,
+            StorageConfig {
+                storage: "nfs-snippets".to_owned(),
+                kind: "nfs".to_owned(),
+                path: Some("/mnt/pve/nfs-snippets".to_owned()),
+            }
+        );
+    }
+}
@@ -33,14 +33,11 @@ pub struct TaskStatusResponse {
     pub task_type: String,
     pub upid: String,
     pub user: String,
-    #[serde(rename = "exitStatus")]
-    pub exit_status: Option<TaskExitStatus>,
-}
 
-#[derive(Debug, Deserialize)]
-pub enum TaskExitStatus {
-    #[serde(alias = "OK")]
-    Ok,
+    /// The exit status once the task stopped, `OK` when it succeeded and
+    /// the error message otherwise.
+    #[serde(rename = "exitstatus")]
+    pub exit_status: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -86,6 +83,6 @@ mod tests {
         let server = MockServer::new().await.with_task_status_read();
         let result = task_status_read(&server.url(), &client, "", "pve-node1", "foobar").await;
 
-        assert!(result.is_ok());
+        assert_eq!(result.unwrap().data.exit_status.as_deref(), Some("OK"));
     }
 }
@@ -0,0 +1,81 @@
+use crate::proxmox::api::Error;
+use crate::proxmox::api::api_response::{ApiResponse, ApiResponseExt};
+use serde::Deserialize;
+
+/// Reads the version of the Proxmox VE API.
+///
+/// Calls `GET /version`.
+pub async fn version_read(
+    api_url: &str,
+    client: &reqwest::Client,
+    authorization: &str,
+) -> Result<ApiResponse<Version>, Error> {
+    client
+        .get(format!("{}/api2/json/version", api_url))
+        .header(reqwest::header::AUTHORIZATION, authorization)
+        .send()
+        .await
+        .to_api_response()
+        .await
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct Version {
+    /// The full version of the API (e.g. `8.2.4`).
+    pub version: String,
+
+    /// The release of the API (e.g. `8.2`).
+    pub release: String,
+
+    /// The repository id of the build.
+    pub repoid: String,
+}
+
+#[cfg(feature = "mock")]
+pub mod mock {
+    use mock_server::MockServer;
+
+    pub trait WithVersionReadMock {
+        fn with_version_read(self) -> Self;
+    }
+
+    impl WithVersionReadMock for MockServer {
+        fn with_version_read(mut self) -> Self {
+            let mock = self
+                .server
+                .mock(
+                    "GET",
+                    mockito::Matcher::Regex(r"^/api2/json/version$".to_string()),
+                )
+                .with_body(
+                    r#"{"data":{"release":"8.2","repoid":"faa83925c9641325","version":"8.2.4"}}"#,
+                )
+                .create();
+            self.mocks.push(mock);
+            self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::WithVersionReadMock;
+    use super::*;
+    use mock_server::MockServer;
+
+    #[tokio::test]
+    async fn test_version_read() {
+        let client = reqwest::Client::new();
+        let server = MockServer::new().await.with_version_read();
+        let result = version_read(&server.url(), &client, "").await;
+
+        assert_eq!(
+            result.unwrap().data,
+            Version {
+                version: "8.2.4".to_owned(),
+                release: "8.2".to_owned(),
+                repoid: "faa83925c9641325".to_owned(),
+            }
+        );
+    }
+}
@@ -0,0 +1,103 @@
+use crate::proxmox::api::Error;
+use crate::proxmox::api::api_response::{ApiResponse, ApiResponseExt};
+use serde::{Deserialize, Serialize};
+use serde_with::skip_serializing_none;
+
+/// Runs a command in a VM through the QEMU guest agent, without waiting for
+/// it to exit.
+///
+/// Calls `POST /nodes/{node}/qemu/{vmid}/agent/exec`. The returned pid is
+/// polled with `vm_agent_exec_status`.
+///
+/// # Errors
+/// May return `Error::MissingAgent` if the QEMU guest agent is not configured
+/// or not running, or `Error::VMNotRunning` if the VM is not running.
+pub async fn vm_agent_exec(
+    api_url: &str,
+    client: &reqwest::Client,
+    authorization: &str,
+    node_id: &str,
+    vm_id: u32,
+    options: &VMAgentExecOptions,
+) -> Result<ApiResponse<VMAgentExec>, Error> {
+    client
+        .post(format!(
+            "{}/api2/json/nodes/{}/qemu/{}/agent/exec",
+            api_url, node_id, vm_id
+        ))
+        .header(reqwest::header::AUTHORIZATION, authorization)
+        .json(options)
+        .send()
+        .await
+        .to_api_response()
+        .await
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Default, Serialize)]
+pub struct VMAgentExecOptions {
+    /// The program to run followed by its arguments.
+    pub command: Vec<String>,
+
+    /// Data passed to the standard input of the command.
+    #[serde(rename = "input-data")]
+    pub input_data: Option<String>,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct VMAgentExec {
+    /// The pid of the command in the VM.
+    pub pid: u64,
+}
+
+#[cfg(feature = "mock")]
+pub mod mock {
+    use mock_server::MockServer;
+
+    pub trait WithVMAgentExecMock {
+        fn with_vm_agent_exec(self) -> Self;
+    }
+
+    impl WithVMAgentExecMock for MockServer {
+        fn with_vm_agent_exec(mut self) -> Self {
+            let mock = self
+                .server
+                .mock(
+                    "POST",
+                    mockito::Matcher::Regex(
+                        r"^/api2/json/nodes/.*/qemu/\d+/agent/exec$".to_string(),
+                    ),
+                )
+                .with_body(r#"{"data":{"pid":4242}}"#)
+                .create();
+            self.mocks.push(mock);
+            self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::WithVMAgentExecMock;
+    use super::*;
+    use mock_server::MockServer;
+
+    #[tokio::test]
+    async fn test_vm_agent_exec() {
+        let client = reqwest::Client::new();
+        let server = MockServer::new().await.with_vm_agent_exec();
+        let options = VMAgentExecOptions {
+            command: vec!["cat".to_owned()],
+            input_data: Some("hello".to_owned()),
+        };
+
+        let result = vm_agent_exec(&server.url(), &client, "", "pve-node1", 100, &options).await;
+
+        assert_eq!(
+            result.unwrap(),
+            ApiResponse {
+                data: VMAgentExec { pid: 4242 }
+            }
+        );
+    }
+}
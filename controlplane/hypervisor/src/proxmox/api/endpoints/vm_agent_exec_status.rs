@@ -0,0 +1,100 @@
+use crate::proxmox::api::Error;
+use crate::proxmox::api::api_response::{ApiResponse, ApiResponseExt};
+use serde::Deserialize;
+
+/// Reads the status of a command run in a VM through the QEMU guest agent.
+///
+/// Calls `GET /nodes/{node}/qemu/{vmid}/agent/exec-status`.
+pub async fn vm_agent_exec_status(
+    api_url: &str,
+    client: &reqwest::Client,
+    authorization: &str,
+    node_id: &str,
+    vm_id: u32,
+    pid: u64,
+) -> Result<ApiResponse<VMAgentExecStatus>, Error> {
+    client
+        .get(format!(
+            "{}/api2/json/nodes/{}/qemu/{}/agent/exec-status",
+            api_url, node_id, vm_id
+        ))
+        .header(reqwest::header::AUTHORIZATION, authorization)
+        .query(&[("pid", pid)])
+        .send()
+        .await
+        .to_api_response()
+        .await
+}
+
+#[derive(Debug, Default, Deserialize, PartialEq)]
+pub struct VMAgentExecStatus {
+    /// Whether the command exited.
+    pub exited: u8,
+
+    /// The exit code of the command, once it exited normally.
+    pub exitcode: Option<i32>,
+
+    /// The signal that killed the command, if any.
+    pub signal: Option<i32>,
+
+    /// The standard output of the command.
+    #[serde(rename = "out-data")]
+    pub out_data: Option<String>,
+
+    /// The standard error of the command.
+    #[serde(rename = "err-data")]
+    pub err_data: Option<String>,
+}
+
+#[cfg(feature = "mock")]
+pub mod mock {
+    use mock_server::MockServer;
+
+    pub trait WithVMAgentExecStatusMock {
+        fn with_vm_agent_exec_status(self) -> Self;
+    }
+
+    impl WithVMAgentExecStatusMock for MockServer {
+        fn with_vm_agent_exec_status(mut self) -> Self {
+            let mock = self
+                .server
+                .mock(
+                    "GET",
+                    mockito::Matcher::Regex(
+                        r"^/api2/json/nodes/.*/qemu/\d+/agent/exec-status(\?.*)?$".to_string(),
+                    ),
+                )
+                .with_body(r#"{"data":{"exited":1,"exitcode":0,"out-data":"hello"}}"#)
+                .create();
+            self.mocks.push(mock);
+            self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::WithVMAgentExecStatusMock;
+    use super::*;
+    use mock_server::MockServer;
+
+    #[tokio::test]
+    async fn test_vm_agent_exec_status() {
+        let client = reqwest::Client::new();
+        let server = MockServer::new().await.with_vm_agent_exec_status();
+
+        let result = vm_agent_exec_status(&server.url(), &client, "", "pve-node1", 100, 4242).await;
+
+        assert_eq!(
+            result.unwrap(),
+            ApiResponse {
+                data: VMAgentExecStatus {
+                    exited: 1,
+                    exitcode: Some(0),
+                    out_data: Some("hello".to_owned()),
+                    ..Default::default()
+                }
+            }
+        );
+    }
+}
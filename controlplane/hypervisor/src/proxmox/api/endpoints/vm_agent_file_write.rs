@@ -0,0 +1,96 @@
+use crate::proxmox::api::Error;
+use crate::proxmox::api::api_response::{ApiResponse, ApiResponseExt};
+use serde::Serialize;
+use serde_with::skip_serializing_none;
+
+/// Writes a file in a VM through the QEMU guest agent, replacing its content
+/// when it exists.
+///
+/// Calls `POST /nodes/{node}/qemu/{vmid}/agent/file-write`.
+///
+/// # Errors
+/// May return `Error::MissingAgent` if the QEMU guest agent is not configured
+/// or not running, or `Error::VMNotRunning` if the VM is not running.
+pub async fn vm_agent_file_write(
+    api_url: &str,
+    client: &reqwest::Client,
+    authorization: &str,
+    node_id: &str,
+    vm_id: u32,
+    options: &VMAgentFileWriteOptions,
+) -> Result<ApiResponse<Option<String>>, Error> {
+    client
+        .post(format!(
+            "{}/api2/json/nodes/{}/qemu/{}/agent/file-write",
+            api_url, node_id, vm_id
+        ))
+        .header(reqwest::header::AUTHORIZATION, authorization)
+        .json(options)
+        .send()
+        .await
+        .to_api_response()
+        .await
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Default, Serialize)]
+pub struct VMAgentFileWriteOptions {
+    /// The path of the file in the VM.
+    pub file: String,
+
+    /// The content of the file, at most 60 KiB.
+    pub content: String,
+
+    /// Whether the content is base64 encoded by the API before being sent to
+    /// the agent, the default. Content already encoded is sent as is.
+    pub encode: Option<bool>,
+}
+
+#[cfg(feature = "mock")]
+pub mod mock {
+    use mock_server::MockServer;
+
+    pub trait WithVMAgentFileWriteMock {
+        fn with_vm_agent_file_write(self) -> Self;
+    }
+
+    impl WithVMAgentFileWriteMock for MockServer {
+        fn with_vm_agent_file_write(mut self) -> Self {
+            let mock = self
+                .server
+                .mock(
+                    "POST",
+                    mockito::Matcher::Regex(
+                        r"^/api2/json/nodes/.*/qemu/\d+/agent/file-write$".to_string(),
+                    ),
+                )
+                .with_body(r#"{"data":null}"#)
+                .create();
+            self.mocks.push(mock);
+            self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::WithVMAgentFileWriteMock;
+    use super::*;
+    use mock_server::MockServer;
+
+    #[tokio::test]
+    async fn test_vm_agent_file_write() {
+        let client = reqwest::Client::new();
+        let server = MockServer::new().await.with_vm_agent_file_write();
+        let options = VMAgentFileWriteOptions {
+            file: "/etc/motd".to_owned(),
+            content: "Welcome\n".to_owned(),
+            ..Default::default()
+        };
+
+        let result =
+            vm_agent_file_write(&server.url(), &client, "", "pve-node1", 100, &options).await;
+
+        assert!(result.is_ok());
+    }
+}
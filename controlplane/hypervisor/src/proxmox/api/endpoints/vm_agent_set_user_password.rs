@@ -0,0 +1,95 @@
+use crate::proxmox::api::Error;
+use crate::proxmox::api::api_response::{ApiResponse, ApiResponseExt};
+use serde::{Serialize, de::IgnoredAny};
+use serde_with::skip_serializing_none;
+
+/// Sets the password of a user of a VM through the QEMU guest agent.
+///
+/// Calls `POST /nodes/{node}/qemu/{vmid}/agent/set-user-password`.
+///
+/// # Errors
+/// May return `Error::MissingAgent` if the QEMU guest agent is not configured
+/// or not running, or `Error::VMNotRunning` if the VM is not running.
+pub async fn vm_agent_set_user_password(
+    api_url: &str,
+    client: &reqwest::Client,
+    authorization: &str,
+    node_id: &str,
+    vm_id: u32,
+    options: &VMAgentSetUserPasswordOptions,
+) -> Result<ApiResponse<IgnoredAny>, Error> {
+    client
+        .post(format!(
+            "{}/api2/json/nodes/{}/qemu/{}/agent/set-user-password",
+            api_url, node_id, vm_id
+        ))
+        .header(reqwest::header::AUTHORIZATION, authorization)
+        .json(options)
+        .send()
+        .await
+        .to_api_response()
+        .await
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Default, Serialize)]
+pub struct VMAgentSetUserPasswordOptions {
+    /// The user to set the password of.
+    pub username: String,
+
+    /// The new password, at least 5 characters long.
+    pub password: String,
+
+    /// Whether the password is already crypted, as passed to `chpasswd -e`.
+    pub crypted: Option<bool>,
+}
+
+#[cfg(feature = "mock")]
+pub mod mock {
+    use mock_server::MockServer;
+
+    pub trait WithVMAgentSetUserPasswordMock {
+        fn with_vm_agent_set_user_password(self) -> Self;
+    }
+
+    impl WithVMAgentSetUserPasswordMock for MockServer {
+        fn with_vm_agent_set_user_password(mut self) -> Self {
+            let mock = self
+                .server
+                .mock(
+                    "POST",
+                    mockito::Matcher::Regex(
+                        r"^/api2/json/nodes/.*/qemu/\d+/agent/set-user-password$".to_string(),
+                    ),
+                )
+                .with_body(r#"{"data":{"result":{}}}"#)
+                .create();
+            self.mocks.push(mock);
+            self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::WithVMAgentSetUserPasswordMock;
+    use super::*;
+    use mock_server::MockServer;
+
+    #[tokio::test]
+    async fn test_vm_agent_set_user_password() {
+        let client = reqwest::Client::new();
+        let server = MockServer::new().await.with_vm_agent_set_user_password();
+        let options = VMAgentSetUserPasswordOptions {
+            username: "debian".to_owned(),
+            password: "correct horse battery staple".to_owned(),
+            ..Default::default()
+        };
+
+        let result =
+            vm_agent_set_user_password(&server.url(), &client, "", "pve-node1", 100, &options)
+                .await;
+
+        assert!(result.is_ok());
+    }
+}
@@ -1,7 +1,10 @@
 use crate::proxmox::api::Error;
 use crate::proxmox::api::api_response::{ApiResponse, ApiResponseExt};
-use serde::{Deserialize, Deserializer};
-use std::{net::Ipv4Addr, str};
+use serde::{Deserialize, Deserializer, de::IgnoredAny};
+use std::{collections::BTreeMap, net::Ipv4Addr, str};
+
+/// The highest SCSI device index Proxmox accepts.
+pub const MAX_SCSI_DEVICE: u8 = 30;
 
 pub async fn vm_config_read(
     api_url: &str,
@@ -24,8 +27,74 @@ pub async fn vm_config_read(
 
 #[derive(Debug, Deserialize, PartialEq)]
 pub struct VMConfig {
+    /// The custom cloud-init snippets of the VM (e.g.
+    /// `user=nfs-snippets:snippets/vm-100-user.yaml`).
+    pub cicustom: Option<String>,
+
     #[serde(deserialize_with = "deserialize_ipconfig", default)]
     pub ipconfig0: Option<IpConfig>,
+
+    /// The first network device of the VM (e.g.
+    /// `virtio=BC:24:11:20:4E:3F,bridge=vmbr0,firewall=1`).
+    pub net0: Option<String>,
+
+    /// The SCSI disks and unused volumes of the VM, keyed by device name
+    /// (e.g. `scsi1`, `unused0`).
+    #[serde(flatten, deserialize_with = "deserialize_disks")]
+    pub disks: BTreeMap<String, String>,
+}
+
+impl VMConfig {
+    /// Gets the volume id behind a device (e.g. `local-lvm:vm-100-disk-1`).
+    pub fn volume(&self, device: &str) -> Option<&str> {
+        self.disks
+            .get(device)
+            .and_then(|value| value.split(',').next())
+    }
+
+    /// Gets the device a volume is plugged in, attached or unused.
+    pub fn device(&self, volume: &str) -> Option<&str> {
+        self.disks
+            .keys()
+            .find(|device| self.volume(device) == Some(volume))
+            .map(String::as_str)
+    }
+
+    /// Gets the first SCSI device free to attach a volume to.
+    ///
+    /// `scsi0` is reserved for the boot disk.
+    pub fn free_scsi_device(&self) -> Option<String> {
+        (1..=MAX_SCSI_DEVICE)
+            .map(|index| format!("scsi{}", index))
+            .find(|device| !self.disks.contains_key(device))
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ConfigValue {
+    Text(String),
+    Other(IgnoredAny),
+}
+
+fn deserialize_disks<'de, D>(deserializer: D) -> Result<BTreeMap<String, String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let is_disk = |key: &str| {
+        ["scsi", "unused"].iter().any(|prefix| {
+            key.strip_prefix(prefix)
+                .is_some_and(|index| !index.is_empty() && index.bytes().all(|b| b.is_ascii_digit()))
+        })
+    };
+
+    Ok(BTreeMap::<String, ConfigValue>::deserialize(deserializer)?
+        .into_iter()
+        .filter_map(|(key, value)| match value {
+            ConfigValue::Text(value) if is_disk(&key) => Some((key, value)),
+            _ => None,
+        })
+        .collect())
 }
 
 #[derive(Debug, Deserialize, PartialEq)]
@@ -41,18 +110,24 @@ where
 {
     let opt = Option::<String>::deserialize(deserializer)?;
     match opt {
-        Some(s) => parse_ip_config(&s)
-            .map(Some)
-            .map_err(serde::de::Error::custom),
+        Some(s) => parse_ip_config(&s).map_err(serde::de::Error::custom),
         None => Ok(None),
     }
 }
 
-fn parse_ip_config(s: &str) -> Result<IpConfig, String> {
-    let parts: Vec<&str> = s.split(',').collect();
+/// Parses a static IPv4 configuration (e.g. `ip=10.2.16.80/21,gw=10.2.16.1`),
+/// none when the address is leased over DHCP or left to the guest.
+fn parse_ip_config(s: &str) -> Result<Option<IpConfig>, String> {
+    let option = |key: &str| {
+        s.split(',')
+            .find_map(|part| part.strip_prefix(key)?.strip_prefix('='))
+    };
 
-    let ip_part = parts[0].strip_prefix("ip=").ok_or("Missing 'ip=' prefix")?;
-    let gw_part = parts[1].strip_prefix("gw=").ok_or("Missing 'gw=' prefix")?;
+    let ip_part = match option("ip") {
+        None | Some("dhcp" | "manual") => return Ok(None),
+        Some(ip_part) => ip_part,
+    };
+    let gw_part = option("gw").ok_or("Missing 'gw=' option")?;
 
     let (ip_str, cidr_str) = ip_part.split_once('/').ok_or("Missing CIDR notation")?;
 
@@ -66,7 +141,7 @@ fn parse_ip_config(s: &str) -> Result<IpConfig, String> {
         .parse::<Ipv4Addr>()
         .map_err(|e| format!("Invalid gateway: {}", e))?;
 
-    Ok(IpConfig { ip, cidr, gateway })
+    Ok(Some(IpConfig { ip, cidr, gateway }))
 }
 
 #[cfg(feature = "mock")]
@@ -85,7 +160,7 @@ pub mod mock {
                     "GET",
                     mockito::Matcher::Regex(r"^/api2/json/nodes/.*/qemu/.*/config$".to_string()),
                 )
-                .with_body(r#"{"data":{"scsihw":"virtio-scsi-pci","cores":1,"meta":"creation-qemu=9.2.0,ctime=1752413379","net0":"virtio=BC:24:11:20:4E:3F,bridge=vmbr0","cpu":"x86-64-v2-AES","memory":"1024","name":"empty","vga":"serial0","bootdisk":"scsi0","agent":"enabled=1","scsi0":"ceph-pool-nvme-01:vm-555-disk-0,discard=on,size=50G,ssd=1","ide2":"ceph-pool-nvme-01:vm-555-cloudinit,media=cdrom","onboot":1,"numa":0,"ipconfig0":"ip=10.2.16.80/21,gw=10.2.16.1","nameserver":"8.8.8.8","cicustom":"user=nfs-snippets:snippets/ci-custom-empty-snippet.yaml","boot":"c","digest":"0d7b4aefa97d9a0dacfcfb0016fa1e614bc56cc6","smbios1":"uuid=6b03e691-fe59-451d-9007-b13629771ad8","vmgenid":"3c8a4d6f-7cd0-423d-903f-e1493b8d4a3a","sockets":1,"serial0":"socket"}}"#)
+                .with_body(r#"{"data":{"scsihw":"virtio-scsi-pci","cores":1,"meta":"creation-qemu=9.2.0,ctime=1752413379","net0":"virtio=BC:24:11:20:4E:3F,bridge=vmbr0,firewall=1","cpu":"x86-64-v2-AES","memory":"1024","name":"empty","vga":"serial0","bootdisk":"scsi0","agent":"enabled=1","scsi0":"ceph-pool-nvme-01:vm-555-disk-0,discard=on,size=50G,ssd=1","scsi1":"ceph-pool-nvme-01:vm-555-disk-1,size=10G","unused0":"ceph-pool-nvme-01:vm-555-disk-2","ide2":"ceph-pool-nvme-01:vm-555-cloudinit,media=cdrom","onboot":1,"numa":0,"ipconfig0":"ip=10.2.16.80/21,gw=10.2.16.1","nameserver":"8.8.8.8","cicustom":"user=nfs-snippets:snippets/ci-custom-empty-snippet.yaml","boot":"c","digest":"0d7b4aefa97d9a0dacfcfb0016fa1e614bc56cc6","smbios1":"uuid=6b03e691-fe59-451d-9007-b13629771ad8","vmgenid":"3c8a4d6f-7cd0-423d-903f-e1493b8d4a3a","sockets":1,"serial0":"socket"}}"#)
                 .create();
             self.mocks.push(mock);
             self
@@ -115,15 +190,70 @@ mod tests {
         assert_eq!(
             result.unwrap().data,
             VMConfig {
+                cicustom: Some(
+                    "user=nfs-snippets:snippets/ci-custom-empty-snippet.yaml".to_owned()
+                ),
                 ipconfig0: Some(IpConfig {
                     ip: Ipv4Addr::from_str("10.2.16.80").unwrap(),
                     cidr: 21,
                     gateway: Ipv4Addr::from_str("10.2.16.1").unwrap()
-                })
+                }),
+                net0: Some("virtio=BC:24:11:20:4E:3F,bridge=vmbr0,firewall=1".to_owned()),
+                disks: BTreeMap::from([
+                    (
+                        "scsi0".to_owned(),
+                        "ceph-pool-nvme-01:vm-555-disk-0,discard=on,size=50G,ssd=1".to_owned()
+                    ),
+                    (
+                        "scsi1".to_owned(),
+                        "ceph-pool-nvme-01:vm-555-disk-1,size=10G".to_owned()
+                    ),
+                    (
+                        "unused0".to_owned(),
+                        "ceph-pool-nvme-01:vm-555-disk-2".to_owned()
+                    ),
+                ]),
             }
         )
     }
 
-    #[tokio::test]
-    async fn test_vm_config_handles_none_ipconfig0() {}
+    #[test]
+    fn test_vm_config_free_scsi_device() {
+        let config = VMConfig {
+            cicustom: None,
+            ipconfig0: None,
+            net0: None,
+            disks: BTreeMap::from([
+                (
+                    "scsi0".to_owned(),
+                    "local-lvm:vm-100-disk-0,size=20G".to_owned(),
+                ),
+                (
+                    "scsi1".to_owned(),
+                    "local-lvm:vm-100-disk-1,size=10G".to_owned(),
+                ),
+                ("unused0".to_owned(), "local-lvm:vm-100-disk-2".to_owned()),
+            ]),
+        };
+
+        assert_eq!(config.free_scsi_device().as_deref(), Some("scsi2"));
+        assert_eq!(config.volume("scsi1"), Some("local-lvm:vm-100-disk-1"));
+        assert_eq!(config.device("local-lvm:vm-100-disk-2"), Some("unused0"));
+    }
+
+    #[test]
+    fn test_vm_config_handles_dynamic_ipconfig0() {
+        assert_eq!(parse_ip_config("ip=dhcp"), Ok(None));
+        assert_eq!(parse_ip_config("ip=manual,ip6=auto"), Ok(None));
+        assert_eq!(parse_ip_config("ip6=auto"), Ok(None));
+        assert_eq!(
+            parse_ip_config("gw=10.2.16.1,ip=10.2.16.80/21"),
+            Ok(Some(IpConfig {
+                ip: Ipv4Addr::new(10, 2, 16, 80),
+                cidr: 21,
+                gateway: Ipv4Addr::new(10, 2, 16, 1),
+            }))
+        );
+        assert!(parse_ip_config("ip=10.2.16.80").is_err());
+    }
 }
@@ -0,0 +1,103 @@
+use crate::proxmox::api::Error;
+use crate::proxmox::api::api_response::{ApiResponse, ApiResponseExt};
+use serde::Serialize;
+use serde_with::skip_serializing_none;
+use std::collections::BTreeMap;
+
+/// Updates the configuration of a VM.
+///
+/// Changes that can be hotplugged are applied to the running VM right away,
+/// the other ones are kept pending until the next VM start.
+///
+/// Calls `POST /nodes/{node}/qemu/{vmid}/config`.
+pub async fn vm_config_update(
+    api_url: &str,
+    client: &reqwest::Client,
+    authorization: &str,
+    node_id: &str,
+    vm_id: u32,
+    options: &VMConfigUpdateOptions,
+) -> Result<ApiResponse<String>, Error> {
+    client
+        .post(format!(
+            "{}/api2/json/nodes/{}/qemu/{}/config",
+            api_url, node_id, vm_id
+        ))
+        .header(reqwest::header::AUTHORIZATION, authorization)
+        .json(options)
+        .send()
+        .await
+        .to_api_response()
+        .await
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Default, Serialize)]
+pub struct VMConfigUpdateOptions {
+    /// The name of the VM.
+    pub name: Option<String>,
+
+    /// The number of cores per socket.
+    pub cores: Option<u8>,
+
+    /// Memory properties, in MiB.
+    pub memory: Option<u32>,
+
+    /// The first network device of the VM.
+    pub net0: Option<String>,
+
+    /// Disks to set, keyed by device name (e.g. `scsi1`). A value of
+    /// `{storage}:{size_gib}` allocates a new volume, while a volume id
+    /// attaches an existing one.
+    #[serde(flatten)]
+    pub disks: BTreeMap<String, String>,
+
+    /// A comma-separated list of settings to delete. Deleting a disk device
+    /// detaches its volume, and deleting an `unused` entry destroys it.
+    pub delete: Option<String>,
+}
+
+#[cfg(feature = "mock")]
+pub mod mock {
+    use mock_server::MockServer;
+
+    pub trait WithVMConfigUpdateMock {
+        fn with_vm_config_update(self) -> Self;
+    }
+
+    impl WithVMConfigUpdateMock for MockServer {
+        fn with_vm_config_update(mut self) -> Self {
+            let mock = self
+                .server
+                .mock(
+                    "POST",
+                    mockito::Matcher::Regex(r"^/api2/json/nodes/.*/qemu/\d+/config$".to_string()),
+                )
+                .with_body(r#"{"data":"UPID:pve-node1:0021C7D2:0233F1A0:67CC7EE2:qmconfig:100:root@pam!api:"}"#)
+                .create();
+            self.mocks.push(mock);
+            self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::WithVMConfigUpdateMock;
+    use super::*;
+    use mock_server::MockServer;
+
+    #[tokio::test]
+    async fn test_vm_config_update() {
+        let client = reqwest::Client::new();
+        let server = MockServer::new().await.with_vm_config_update();
+        let options = VMConfigUpdateOptions {
+            cores: Some(4),
+            memory: Some(8192),
+            ..Default::default()
+        };
+        let result = vm_config_update(&server.url(), &client, "", "pve-node1", 100, &options).await;
+
+        assert!(result.is_ok());
+    }
+}
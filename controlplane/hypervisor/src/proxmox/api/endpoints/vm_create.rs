@@ -95,7 +95,7 @@ impl Default for VMConfig {
             memory: Some(1024),
             name: None,
             nameserver: Some(String::from("1.1.1.1")),
-            net0: Some(String::from("virtio,bridge=vmbr0")),
+            net0: Some(String::from("virtio,bridge=vmbr0,firewall=1")),
             scsi0: Some(format!(
                 "{}:0,import-from=/var/lib/vz/images/0/debian-12-genericcloud-amd64-20241201-1948.qcow2,discard=on,ssd=1",
                 image_storage,
@@ -111,27 +111,28 @@ impl Default for VMConfig {
 }
 
 impl VMConfig {
+    /// Builds the configuration of a VM, provisioned by the cloud-init
+    /// snippet stored at `snippet_volume`, its disk created on
+    /// `image_storage`.
     pub fn from_instance_config(
         value: InstanceCreateRequest,
         vmid: u32,
-        snippet_filename: String,
+        snippet_volume: &str,
+        image_storage: &str,
     ) -> Self {
-        let image_storage =
-            std::env::var("PROXMOX_IMAGE_STORAGE").unwrap_or_else(|_| String::from("local-lvm"));
-
         let memory_mb = (value.memory_bytes / (1024 * 1024)) as u32;
 
-        let volume = format!(
-            "{}:0,import-from=local:0/{},discard=on,ssd=1",
-            image_storage, value.disk_image
-        );
+        // Images from the catalogue are referenced by their volume id, other
+        // ones by their name among the base images of the `local` storage.
+        let image = if value.disk_image.contains(':') {
+            value.disk_image
+        } else {
+            format!("local:0/{}", value.disk_image)
+        };
+        let volume = format!("{}:0,import-from={},discard=on,ssd=1", image_storage, image);
 
         VMConfig {
-            cicustom: Some(format!(
-                "{}:{}",
-                crate::proxmox::VOLUME_NAMED_PATH,
-                snippet_filename
-            )),
+            cicustom: Some(format!("user={}", snippet_volume)),
             cores: Some(value.cores),
             ipconfig0: Some("ip=dhcp".to_owned()),
             memory: Some(memory_mb),
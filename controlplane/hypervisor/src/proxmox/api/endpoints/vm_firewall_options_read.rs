@@ -0,0 +1,88 @@
+use crate::proxmox::api::Error;
+use crate::proxmox::api::api_response::{ApiResponse, ApiResponseExt};
+use serde::Deserialize;
+
+/// Reads the firewall options of a VM.
+///
+/// Calls `GET /nodes/{node}/qemu/{vmid}/firewall/options`.
+pub async fn vm_firewall_options_read(
+    api_url: &str,
+    client: &reqwest::Client,
+    authorization: &str,
+    node_id: &str,
+    vm_id: u32,
+) -> Result<ApiResponse<VMFirewallOptions>, Error> {
+    client
+        .get(format!(
+            "{}/api2/json/nodes/{}/qemu/{}/firewall/options",
+            api_url, node_id, vm_id
+        ))
+        .header(reqwest::header::AUTHORIZATION, authorization)
+        .send()
+        .await
+        .to_api_response()
+        .await
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+pub struct VMFirewallOptions {
+    /// Whether the firewall of the VM is enabled.
+    pub enable: Option<u8>,
+
+    /// The action applied to the incoming traffic no rule matched, `DROP`
+    /// when unset.
+    pub policy_in: Option<String>,
+
+    /// The action applied to the outgoing traffic no rule matched, `ACCEPT`
+    /// when unset.
+    pub policy_out: Option<String>,
+}
+
+#[cfg(feature = "mock")]
+pub mod mock {
+    use mock_server::MockServer;
+
+    pub trait WithVMFirewallOptionsReadMock {
+        fn with_vm_firewall_options_read(self) -> Self;
+    }
+
+    impl WithVMFirewallOptionsReadMock for MockServer {
+        fn with_vm_firewall_options_read(mut self) -> Self {
+            let mock = self
+                .server
+                .mock(
+                    "GET",
+                    mockito::Matcher::Regex(
+                        r"^/api2/json/nodes/.*/qemu/\d+/firewall/options$".to_string(),
+                    ),
+                )
+                .with_body(r#"{"data":{"enable":1,"policy_in":"DROP","policy_out":"ACCEPT","digest":"9f1c2b7a"}}"#)
+                .create();
+            self.mocks.push(mock);
+            self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::WithVMFirewallOptionsReadMock;
+    use super::*;
+    use mock_server::MockServer;
+
+    #[tokio::test]
+    async fn test_vm_firewall_options_read() {
+        let client = reqwest::Client::new();
+        let server = MockServer::new().await.with_vm_firewall_options_read();
+        let result = vm_firewall_options_read(&server.url(), &client, "", "pve-node1", 100).await;
+
+        assert_eq!(
+            result.unwrap().data,
+            VMFirewallOptions {
+                enable: Some(1),
+                policy_in: Some("DROP".to_owned()),
+                policy_out: Some("ACCEPT".to_owned()),
+            }
+        );
+    }
+}
@@ -0,0 +1,90 @@
+use crate::proxmox::api::Error;
+use crate::proxmox::api::api_response::{ApiResponse, ApiResponseExt};
+use serde::Serialize;
+use serde_with::skip_serializing_none;
+
+/// Updates the firewall options of a VM.
+///
+/// Calls `PUT /nodes/{node}/qemu/{vmid}/firewall/options`.
+pub async fn vm_firewall_options_update(
+    api_url: &str,
+    client: &reqwest::Client,
+    authorization: &str,
+    node_id: &str,
+    vm_id: u32,
+    options: &VMFirewallOptionsUpdate,
+) -> Result<ApiResponse<Option<String>>, Error> {
+    client
+        .put(format!(
+            "{}/api2/json/nodes/{}/qemu/{}/firewall/options",
+            api_url, node_id, vm_id
+        ))
+        .header(reqwest::header::AUTHORIZATION, authorization)
+        .json(options)
+        .send()
+        .await
+        .to_api_response()
+        .await
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Default, Serialize)]
+pub struct VMFirewallOptionsUpdate {
+    /// Whether the firewall of the VM is enabled.
+    pub enable: Option<bool>,
+
+    /// The action applied to the incoming traffic no rule matched.
+    pub policy_in: Option<String>,
+
+    /// The action applied to the outgoing traffic no rule matched.
+    pub policy_out: Option<String>,
+}
+
+#[cfg(feature = "mock")]
+pub mod mock {
+    use mock_server::MockServer;
+
+    pub trait WithVMFirewallOptionsUpdateMock {
+        fn with_vm_firewall_options_update(self) -> Self;
+    }
+
+    impl WithVMFirewallOptionsUpdateMock for MockServer {
+        fn with_vm_firewall_options_update(mut self) -> Self {
+            let mock = self
+                .server
+                .mock(
+                    "PUT",
+                    mockito::Matcher::Regex(
+                        r"^/api2/json/nodes/.*/qemu/\d+/firewall/options$".to_string(),
+                    ),
+                )
+                .with_body(r#"{"data":null}"#)
+                .create();
+            self.mocks.push(mock);
+            self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::WithVMFirewallOptionsUpdateMock;
+    use super::*;
+    use mock_server::MockServer;
+
+    #[tokio::test]
+    async fn test_vm_firewall_options_update() {
+        let client = reqwest::Client::new();
+        let server = MockServer::new().await.with_vm_firewall_options_update();
+        let options = VMFirewallOptionsUpdate {
+            enable: Some(true),
+            policy_in: Some("DROP".to_owned()),
+            policy_out: Some("ACCEPT".to_owned()),
+        };
+        let result =
+            vm_firewall_options_update(&server.url(), &client, "", "pve-node1", 100, &options)
+                .await;
+
+        assert!(result.is_ok());
+    }
+}
@@ -0,0 +1,114 @@
+use crate::proxmox::api::Error;
+use crate::proxmox::api::api_response::{ApiResponse, ApiResponseExt};
+use serde::Serialize;
+use serde_with::skip_serializing_none;
+
+/// Creates a firewall rule on a VM.
+///
+/// Calls `POST /nodes/{node}/qemu/{vmid}/firewall/rules`.
+pub async fn vm_firewall_rule_create(
+    api_url: &str,
+    client: &reqwest::Client,
+    authorization: &str,
+    node_id: &str,
+    vm_id: u32,
+    options: &VMFirewallRuleOptions,
+) -> Result<ApiResponse<Option<String>>, Error> {
+    client
+        .post(format!(
+            "{}/api2/json/nodes/{}/qemu/{}/firewall/rules",
+            api_url, node_id, vm_id
+        ))
+        .header(reqwest::header::AUTHORIZATION, authorization)
+        .json(options)
+        .send()
+        .await
+        .to_api_response()
+        .await
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Default, Serialize)]
+pub struct VMFirewallRuleOptions {
+    /// The direction of the traffic matched (`in` or `out`).
+    #[serde(rename = "type")]
+    pub direction: String,
+
+    /// The action applied to the traffic matched (`ACCEPT`, `DROP` or
+    /// `REJECT`).
+    pub action: String,
+
+    /// The position to insert the rule at, the first one when unset.
+    pub pos: Option<u32>,
+
+    /// The protocol matched (e.g. `tcp`), any when unset.
+    pub proto: Option<String>,
+
+    /// The destination ports matched (e.g. `22` or `8000:8080`), any when
+    /// unset.
+    pub dport: Option<String>,
+
+    /// The source addresses matched, any when unset.
+    pub source: Option<String>,
+
+    /// The destination addresses matched, any when unset.
+    pub dest: Option<String>,
+
+    /// Free-form comment of the rule.
+    pub comment: Option<String>,
+
+    /// Whether the rule is enabled.
+    pub enable: Option<bool>,
+}
+
+#[cfg(feature = "mock")]
+pub mod mock {
+    use mock_server::MockServer;
+
+    pub trait WithVMFirewallRuleCreateMock {
+        fn with_vm_firewall_rule_create(self) -> Self;
+    }
+
+    impl WithVMFirewallRuleCreateMock for MockServer {
+        fn with_vm_firewall_rule_create(mut self) -> Self {
+            let mock = self
+                .server
+                .mock(
+                    "POST",
+                    mockito::Matcher::Regex(
+                        r"^/api2/json/nodes/.*/qemu/\d+/firewall/rules$".to_string(),
+                    ),
+                )
+                .with_body(r#"{"data":null}"#)
+                .create();
+            self.mocks.push(mock);
+            self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::WithVMFirewallRuleCreateMock;
+    use super::*;
+    use mock_server::MockServer;
+
+    #[tokio::test]
+    async fn test_vm_firewall_rule_create() {
+        let client = reqwest::Client::new();
+        let server = MockServer::new().await.with_vm_firewall_rule_create();
+        let options = VMFirewallRuleOptions {
+            direction: "in".to_owned(),
+            action: "ACCEPT".to_owned(),
+            pos: Some(0),
+            proto: Some("tcp".to_owned()),
+            dport: Some("443".to_owned()),
+            enable: Some(true),
+            ..Default::default()
+        };
+        let result =
+            vm_firewall_rule_create(&server.url(), &client, "", "pve-node1", 100, &options).await;
+
+        assert!(result.is_ok());
+    }
+}
@@ -0,0 +1,69 @@
+use crate::proxmox::api::Error;
+use crate::proxmox::api::api_response::{ApiResponse, ApiResponseExt};
+
+/// Deletes the firewall rule at the given position of a VM.
+///
+/// The rules after it move up one position.
+///
+/// Calls `DELETE /nodes/{node}/qemu/{vmid}/firewall/rules/{pos}`.
+pub async fn vm_firewall_rule_delete(
+    api_url: &str,
+    client: &reqwest::Client,
+    authorization: &str,
+    node_id: &str,
+    vm_id: u32,
+    pos: u32,
+) -> Result<ApiResponse<Option<String>>, Error> {
+    client
+        .delete(format!(
+            "{}/api2/json/nodes/{}/qemu/{}/firewall/rules/{}",
+            api_url, node_id, vm_id, pos
+        ))
+        .header(reqwest::header::AUTHORIZATION, authorization)
+        .send()
+        .await
+        .to_api_response()
+        .await
+}
+
+#[cfg(feature = "mock")]
+pub mod mock {
+    use mock_server::MockServer;
+
+    pub trait WithVMFirewallRuleDeleteMock {
+        fn with_vm_firewall_rule_delete(self) -> Self;
+    }
+
+    impl WithVMFirewallRuleDeleteMock for MockServer {
+        fn with_vm_firewall_rule_delete(mut self) -> Self {
+            let mock = self
+                .server
+                .mock(
+                    "DELETE",
+                    mockito::Matcher::Regex(
+                        r"^/api2/json/nodes/.*/qemu/\d+/firewall/rules/\d+$".to_string(),
+                    ),
+                )
+                .with_body(r#"{"data":null}"#)
+                .create();
+            self.mocks.push(mock);
+            self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::WithVMFirewallRuleDeleteMock;
+    use super::*;
+    use mock_server::MockServer;
+
+    #[tokio::test]
+    async fn test_vm_firewall_rule_delete() {
+        let client = reqwest::Client::new();
+        let server = MockServer::new().await.with_vm_firewall_rule_delete();
+        let result = vm_firewall_rule_delete(&server.url(), &client, "", "pve-node1", 100, 0).await;
+
+        assert!(result.is_ok());
+    }
+}
@@ -0,0 +1,115 @@
+use crate::proxmox::api::Error;
+use crate::proxmox::api::api_response::{ApiResponse, ApiResponseExt};
+use serde::Deserialize;
+
+/// Lists the firewall rules of a VM, in the order they are evaluated.
+///
+/// Calls `GET /nodes/{node}/qemu/{vmid}/firewall/rules`.
+pub async fn vm_firewall_rule_list(
+    api_url: &str,
+    client: &reqwest::Client,
+    authorization: &str,
+    node_id: &str,
+    vm_id: u32,
+) -> Result<ApiResponse<Vec<VMFirewallRule>>, Error> {
+    client
+        .get(format!(
+            "{}/api2/json/nodes/{}/qemu/{}/firewall/rules",
+            api_url, node_id, vm_id
+        ))
+        .header(reqwest::header::AUTHORIZATION, authorization)
+        .send()
+        .await
+        .to_api_response()
+        .await
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct VMFirewallRule {
+    /// The position of the rule, which identifies it.
+    pub pos: u32,
+
+    /// The direction of the traffic matched (`in` or `out`).
+    #[serde(rename = "type")]
+    pub direction: String,
+
+    /// The action applied to the traffic matched (`ACCEPT`, `DROP` or
+    /// `REJECT`).
+    pub action: String,
+
+    /// The protocol matched (e.g. `tcp`), any when unset.
+    pub proto: Option<String>,
+
+    /// The destination ports matched (e.g. `22` or `8000:8080`), any when
+    /// unset.
+    pub dport: Option<String>,
+
+    /// The source addresses matched, any when unset.
+    pub source: Option<String>,
+
+    /// The destination addresses matched, any when unset.
+    pub dest: Option<String>,
+
+    /// Free-form comment of the rule.
+    pub comment: Option<String>,
+
+    /// Whether the rule is enabled.
+    pub enable: Option<u8>,
+}
+
+#[cfg(feature = "mock")]
+pub mod mock {
+    use mock_server::MockServer;
+
+    pub trait WithVMFirewallRuleListMock {
+        fn with_vm_firewall_rule_list(self) -> Self;
+    }
+
+    impl WithVMFirewallRuleListMock for MockServer {
+        fn with_vm_firewall_rule_list(mut self) -> Self {
+            let mock = self
+                .server
+                .mock(
+                    "GET",
+                    mockito::Matcher::Regex(
+                        r"^/api2/json/nodes/.*/qemu/\d+/firewall/rules$".to_string(),
+                    ),
+                )
+                .with_body(r#"{"data":[{"pos":0,"type":"in","action":"ACCEPT","proto":"tcp","dport":"22","source":"10.0.0.0/8","comment":"frn:0b5a3c5e-9a7e-4a0e-8a55-5d4b0c2b1f6e","enable":1,"ipversion":4,"digest":"4e5d0c0e"},{"pos":1,"type":"in","action":"ACCEPT","proto":"icmp","comment":"added by hand","enable":1,"digest":"4e5d0c0e"}]}"#)
+                .create();
+            self.mocks.push(mock);
+            self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::WithVMFirewallRuleListMock;
+    use super::*;
+    use mock_server::MockServer;
+
+    #[tokio::test]
+    async fn test_vm_firewall_rule_list() {
+        let client = reqwest::Client::new();
+        let server = MockServer::new().await.with_vm_firewall_rule_list();
+        let result = vm_firewall_rule_list(&server.url(), &client, "", "pve-node1", 100).await;
+
+        let rules = result.unwrap().data;
+        assert_eq!(rules.len(), 2);
+        assert_eq!(
+            rules[0],
+            VMFirewallRule {
+                pos: 0,
+                direction: "in".to_owned(),
+                action: "ACCEPT".to_owned(),
+                proto: Some("tcp".to_owned()),
+                dport: Some("22".to_owned()),
+                source: Some("10.0.0.0/8".to_owned()),
+                dest: None,
+                comment: Some("frn:0b5a3c5e-9a7e-4a0e-8a55-5d4b0c2b1f6e".to_owned()),
+                enable: Some(1),
+            }
+        );
+    }
+}
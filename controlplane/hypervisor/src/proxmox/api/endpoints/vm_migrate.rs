@@ -0,0 +1,95 @@
+use crate::proxmox::api::Error;
+use crate::proxmox::api::api_response::{ApiResponse, ApiResponseExt};
+use serde::Serialize;
+use serde_with::skip_serializing_none;
+
+/// Migrates a VM to another node of the cluster.
+///
+/// Calls `POST /nodes/{node}/qemu/{vmid}/migrate`.
+pub async fn vm_migrate(
+    api_url: &str,
+    client: &reqwest::Client,
+    authorization: &str,
+    node_id: &str,
+    vm_id: u32,
+    options: &VMMigrateOptions,
+) -> Result<ApiResponse<String>, Error> {
+    client
+        .post(format!(
+            "{}/api2/json/nodes/{}/qemu/{}/migrate",
+            api_url, node_id, vm_id
+        ))
+        .header(reqwest::header::AUTHORIZATION, authorization)
+        .json(options)
+        .send()
+        .await
+        .to_api_response()
+        .await
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Serialize)]
+pub struct VMMigrateOptions {
+    /// The node to migrate the VM to.
+    pub target: String,
+
+    /// Migrate a running VM without stopping it.
+    pub online: Option<bool>,
+
+    /// Copy the disks held by storages local to the node along with the VM.
+    #[serde(rename = "with-local-disks")]
+    pub with_local_disks: Option<bool>,
+}
+
+#[cfg(feature = "mock")]
+pub mod mock {
+    use mock_server::MockServer;
+
+    pub trait WithVMMigrateMock {
+        fn with_vm_migrate(self) -> Self;
+    }
+
+    impl WithVMMigrateMock for MockServer {
+        fn with_vm_migrate(mut self) -> Self {
+            let mock = self
+                .server
+                .mock(
+                    "POST",
+                    mockito::Matcher::Regex(r"^/api2/json/nodes/.*/qemu/\d+/migrate$".to_string()),
+                )
+                .with_body(r#"{"data":"UPID:pve-node1:0021B19E:02328820:67CC7B42:qmigrate:100:root@pam!api:"}"#)
+                .create();
+            self.mocks.push(mock);
+            self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::WithVMMigrateMock;
+    use super::*;
+    use mock_server::MockServer;
+
+    #[tokio::test]
+    async fn test_vm_migrate() {
+        // Arrange a client and the mock server
+        let client = reqwest::Client::new();
+        let server = MockServer::new().await.with_vm_migrate();
+        let options = VMMigrateOptions {
+            target: "pve-node2".to_owned(),
+            online: Some(true),
+            with_local_disks: Some(true),
+        };
+
+        // Act the call to the function
+        let result = vm_migrate(&server.url(), &client, "", "pve-node1", 100, &options).await;
+
+        // Assert the result
+        assert!(result.is_ok());
+        assert_eq!(
+            result.unwrap().data,
+            "UPID:pve-node1:0021B19E:02328820:67CC7B42:qmigrate:100:root@pam!api:"
+        );
+    }
+}
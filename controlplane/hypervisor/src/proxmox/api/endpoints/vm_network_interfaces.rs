@@ -1,6 +1,7 @@
 use crate::proxmox::api::Error;
 use crate::proxmox::api::api_response::{ApiResponse, ApiResponseExt};
 use serde::Deserialize;
+use std::net::Ipv4Addr;
 
 /// Retrieves network interface information for a Proxmox VM via the QEMU guest agent.
 ///
@@ -41,6 +42,19 @@ pub struct NetworkInterfaces {
     pub result: Vec<NetworkInterface>,
 }
 
+impl NetworkInterfaces {
+    /// Gets the first IPv4 address of the guest, other than its loopback
+    /// addresses.
+    pub fn ipv4_address(&self) -> Option<Ipv4Addr> {
+        self.result
+            .iter()
+            .flat_map(|interface| interface.ip_addresses.iter().flatten())
+            .filter(|address| address.ip_address_type == IpAddressType::Ipv4)
+            .filter_map(|address| address.ip_address.parse::<Ipv4Addr>().ok())
+            .find(|address| !address.is_loopback())
+    }
+}
+
 #[derive(Debug, Deserialize, PartialEq)]
 pub struct NetworkInterface {
     pub name: String,
@@ -172,4 +186,17 @@ mod tests {
             },
         );
     }
+
+    #[tokio::test]
+    async fn test_vm_network_interfaces_ipv4_address() {
+        let client = reqwest::Client::new();
+        let server = MockServer::new().await.test_vm_network_interfaces();
+
+        let result = vm_network_interfaces(&server.url(), &client, "", "pve-node1", 100).await;
+
+        assert_eq!(
+            result.unwrap().data.ipv4_address(),
+            Some(Ipv4Addr::new(10, 2, 16, 69))
+        );
+    }
 }
@@ -0,0 +1,93 @@
+use crate::proxmox::api::Error;
+use crate::proxmox::api::api_response::{ApiResponse, ApiResponseExt};
+use serde::Deserialize;
+use serde::de::IgnoredAny;
+
+/// Reads the VM configuration, including the changes awaiting a VM restart.
+///
+/// Calls `GET /nodes/{node}/qemu/{vmid}/pending`.
+pub async fn vm_pending_read(
+    api_url: &str,
+    client: &reqwest::Client,
+    authorization: &str,
+    node_id: &str,
+    vm_id: u32,
+) -> Result<ApiResponse<Vec<VMPendingConfig>>, Error> {
+    client
+        .get(format!(
+            "{}/api2/json/nodes/{}/qemu/{}/pending",
+            api_url, node_id, vm_id
+        ))
+        .header(reqwest::header::AUTHORIZATION, authorization)
+        .send()
+        .await
+        .to_api_response()
+        .await
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct VMPendingConfig {
+    /// Configuration option name.
+    pub key: String,
+
+    /// Pending value, applied on the next VM start.
+    pub pending: Option<IgnoredAny>,
+
+    /// Indicates a pending delete request if present and not 0.
+    pub delete: Option<u8>,
+}
+
+impl VMPendingConfig {
+    /// Whether a change to the option awaits a VM restart.
+    pub fn is_pending(&self) -> bool {
+        self.pending.is_some() || self.delete.unwrap_or_default() != 0
+    }
+}
+
+#[cfg(feature = "mock")]
+pub mod mock {
+    use mock_server::MockServer;
+
+    pub trait WithVMPendingReadMock {
+        fn with_vm_pending_read(self) -> Self;
+    }
+
+    impl WithVMPendingReadMock for MockServer {
+        fn with_vm_pending_read(mut self) -> Self {
+            let mock = self
+                .server
+                .mock(
+                    "GET",
+                    mockito::Matcher::Regex(r"^/api2/json/nodes/.*/qemu/\d+/pending$".to_string()),
+                )
+                .with_body(r#"{"data":[{"key":"cores","value":1,"pending":4},{"key":"memory","value":"1024"},{"key":"name","value":"proxmox-dev"}]}"#)
+                .create();
+            self.mocks.push(mock);
+            self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::WithVMPendingReadMock;
+    use super::*;
+    use mock_server::MockServer;
+
+    #[tokio::test]
+    async fn test_vm_pending_read() {
+        let client = reqwest::Client::new();
+        let server = MockServer::new().await.with_vm_pending_read();
+        let result = vm_pending_read(&server.url(), &client, "", "pve-node1", 100).await;
+
+        assert!(result.is_ok());
+        let pending = result
+            .unwrap()
+            .data
+            .into_iter()
+            .filter(VMPendingConfig::is_pending)
+            .map(|config| config.key)
+            .collect::<Vec<_>>();
+        assert_eq!(pending, vec![String::from("cores")]);
+    }
+}
@@ -0,0 +1,91 @@
+use crate::proxmox::api::Error;
+use crate::proxmox::api::api_response::{ApiResponse, ApiResponseExt};
+use serde::Serialize;
+use serde_with::skip_serializing_none;
+
+/// Restores a backup into a new VM.
+///
+/// Returns the task id of the restore.
+///
+/// Calls `POST /nodes/{node}/qemu` with an `archive`.
+pub async fn vm_restore(
+    api_url: &str,
+    client: &reqwest::Client,
+    authorization: &str,
+    node_id: &str,
+    options: &VMRestoreOptions,
+) -> Result<ApiResponse<String>, Error> {
+    client
+        .post(format!("{}/api2/json/nodes/{}/qemu", api_url, node_id))
+        .header(reqwest::header::AUTHORIZATION, authorization)
+        .json(options)
+        .send()
+        .await
+        .to_api_response()
+        .await
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Serialize)]
+pub struct VMRestoreOptions {
+    /// The id of the new VM.
+    pub vmid: u32,
+
+    /// The volume id of the backup to restore.
+    pub archive: String,
+
+    /// The storage to restore the disks of the VM on, the ones they were
+    /// backed up from when unset.
+    pub storage: Option<String>,
+
+    /// Whether to assign new unique MAC addresses to the network interfaces,
+    /// so the new VM does not clash with the one it was backed up from.
+    pub unique: Option<bool>,
+}
+
+#[cfg(feature = "mock")]
+pub mod mock {
+    use mock_server::MockServer;
+
+    pub trait WithVMRestoreMock {
+        fn with_vm_restore(self) -> Self;
+    }
+
+    impl WithVMRestoreMock for MockServer {
+        fn with_vm_restore(mut self) -> Self {
+            let mock = self
+                .server
+                .mock(
+                    "POST",
+                    mockito::Matcher::Regex(r"^/api2/json/nodes/.*/qemu$".to_string()),
+                )
+                .match_body(mockito::Matcher::Regex(r#""archive""#.to_string()))
+                .with_body(r#"{"data":"UPID:pve-node1:0021D3B1:0234B2D4:67CC8B20:qmrestore:101:root@pam!api:"}"#)
+                .create();
+            self.mocks.push(mock);
+            self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::WithVMRestoreMock;
+    use super::*;
+    use mock_server::MockServer;
+
+    #[tokio::test]
+    async fn test_vm_restore() {
+        let client = reqwest::Client::new();
+        let server = MockServer::new().await.with_vm_restore();
+        let options = VMRestoreOptions {
+            vmid: 101,
+            archive: "pbs:backup/vm/100/2025-03-08T18:00:00Z".to_owned(),
+            storage: Some("local-lvm".to_owned()),
+            unique: Some(true),
+        };
+        let result = vm_restore(&server.url(), &client, "", "pve-node1", &options).await;
+
+        assert!(result.is_ok());
+    }
+}
@@ -0,0 +1,146 @@
+use crate::instance::MetricsSample;
+use crate::proxmox::api::Error;
+use crate::proxmox::api::api_response::{ApiResponse, ApiResponseExt};
+use serde::Deserialize;
+
+/// Reads the usage history of a VM from the RRD database of its node.
+///
+/// `timeframe` selects the archive read (`hour`, `day`, `week`, `month` or
+/// `year`), each one holding points further apart than the previous one.
+/// Points are averaged over their step.
+///
+/// Calls `GET /nodes/{node}/qemu/{vmid}/rrddata`.
+pub async fn vm_rrddata_read(
+    api_url: &str,
+    client: &reqwest::Client,
+    authorization: &str,
+    node_id: &str,
+    vm_id: u32,
+    timeframe: &str,
+) -> Result<ApiResponse<Vec<VMRrdData>>, Error> {
+    client
+        .get(format!(
+            "{}/api2/json/nodes/{}/qemu/{}/rrddata",
+            api_url, node_id, vm_id
+        ))
+        .header(reqwest::header::AUTHORIZATION, authorization)
+        .query(&[("timeframe", timeframe), ("cf", "AVERAGE")])
+        .send()
+        .await
+        .to_api_response()
+        .await
+}
+
+/// A point of the usage history of a VM.
+///
+/// Usage fields are missing from the points where the VM was not running.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct VMRrdData {
+    /// Start time of the point, as a unix timestamp.
+    pub time: u64,
+
+    /// CPU utilization, as a fraction of the CPUs of the VM (0.0-1.0).
+    pub cpu: Option<f64>,
+
+    /// Used memory in bytes.
+    pub mem: Option<f64>,
+
+    /// Memory of the VM in bytes.
+    pub maxmem: Option<f64>,
+
+    /// Disk read rate in bytes per second.
+    pub diskread: Option<f64>,
+
+    /// Disk write rate in bytes per second.
+    pub diskwrite: Option<f64>,
+
+    /// Network receive rate in bytes per second.
+    pub netin: Option<f64>,
+
+    /// Network transmit rate in bytes per second.
+    pub netout: Option<f64>,
+}
+
+impl VMRrdData {
+    /// Whether the point holds usage values, the VM running at that time.
+    pub fn has_usage(&self) -> bool {
+        self.cpu.is_some()
+    }
+}
+
+impl From<VMRrdData> for MetricsSample {
+    fn from(value: VMRrdData) -> Self {
+        MetricsSample {
+            time: value.time,
+            cpu_usage_percent: value.cpu.unwrap_or_default() * 100.0,
+            memory_usage_bytes: value.mem.unwrap_or_default() as u64,
+            max_memory_bytes: value.maxmem.unwrap_or_default() as u64,
+            disk_read_bytes_per_second: value.diskread.unwrap_or_default(),
+            disk_write_bytes_per_second: value.diskwrite.unwrap_or_default(),
+            network_in_bytes_per_second: value.netin.unwrap_or_default(),
+            network_out_bytes_per_second: value.netout.unwrap_or_default(),
+        }
+    }
+}
+
+#[cfg(feature = "mock")]
+pub mod mock {
+    use mock_server::MockServer;
+
+    pub trait WithVMRrdDataReadMock {
+        fn with_vm_rrddata_read(self) -> Self;
+    }
+
+    impl WithVMRrdDataReadMock for MockServer {
+        fn with_vm_rrddata_read(mut self) -> Self {
+            let mock = self
+                .server
+                .mock(
+                    "GET",
+                    mockito::Matcher::Regex(
+                        r"^/api2/json/nodes/.*/qemu/\d+/rrddata(\?.*)?$".to_string(),
+                    ),
+                )
+                .with_body(r#"{"data":[{"time":1741456740},{"time":1741456800,"cpu":0.25,"maxcpu":2,"mem":1073741824,"maxmem":4294967296,"disk":0,"maxdisk":34359738368,"diskread":4096,"diskwrite":8192,"netin":1024,"netout":512},{"time":1741456860,"cpu":0.75,"maxcpu":2,"mem":2147483648,"maxmem":4294967296,"disk":0,"maxdisk":34359738368,"diskread":2048,"diskwrite":4096,"netin":3072,"netout":1536}]}"#)
+                .create();
+            self.mocks.push(mock);
+            self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::WithVMRrdDataReadMock;
+    use super::*;
+    use mock_server::MockServer;
+
+    #[tokio::test]
+    async fn test_vm_rrddata_read() {
+        let client = reqwest::Client::new();
+        let server = MockServer::new().await.with_vm_rrddata_read();
+        let result = vm_rrddata_read(&server.url(), &client, "", "pve-node1", 100, "hour").await;
+
+        let samples: Vec<MetricsSample> = result
+            .unwrap()
+            .data
+            .into_iter()
+            .filter(VMRrdData::has_usage)
+            .map(Into::into)
+            .collect();
+        assert_eq!(samples.len(), 2);
+        assert_eq!(
+            samples[0],
+            MetricsSample {
+                time: 1741456800,
+                cpu_usage_percent: 25.0,
+                memory_usage_bytes: 1073741824,
+                max_memory_bytes: 4294967296,
+                disk_read_bytes_per_second: 4096.0,
+                disk_write_bytes_per_second: 8192.0,
+                network_in_bytes_per_second: 1024.0,
+                network_out_bytes_per_second: 512.0,
+            }
+        );
+    }
+}
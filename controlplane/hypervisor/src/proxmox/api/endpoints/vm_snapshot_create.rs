@@ -0,0 +1,87 @@
+use crate::proxmox::api::Error;
+use crate::proxmox::api::api_response::{ApiResponse, ApiResponseExt};
+use serde::Serialize;
+use serde_with::skip_serializing_none;
+
+/// Takes a snapshot of a VM.
+///
+/// Calls `POST /nodes/{node}/qemu/{vmid}/snapshot`.
+pub async fn vm_snapshot_create(
+    api_url: &str,
+    client: &reqwest::Client,
+    authorization: &str,
+    node_id: &str,
+    vm_id: u32,
+    options: &VMSnapshotCreateOptions,
+) -> Result<ApiResponse<String>, Error> {
+    client
+        .post(format!(
+            "{}/api2/json/nodes/{}/qemu/{}/snapshot",
+            api_url, node_id, vm_id
+        ))
+        .header(reqwest::header::AUTHORIZATION, authorization)
+        .json(options)
+        .send()
+        .await
+        .to_api_response()
+        .await
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Serialize)]
+pub struct VMSnapshotCreateOptions {
+    /// The name of the snapshot.
+    pub snapname: String,
+
+    /// A textual description or comment.
+    pub description: Option<String>,
+
+    /// Save the vmstate.
+    pub vmstate: Option<bool>,
+}
+
+#[cfg(feature = "mock")]
+pub mod mock {
+    use mock_server::MockServer;
+
+    pub trait WithVMSnapshotCreateMock {
+        fn with_vm_snapshot_create(self) -> Self;
+    }
+
+    impl WithVMSnapshotCreateMock for MockServer {
+        fn with_vm_snapshot_create(mut self) -> Self {
+            let mock = self
+                .server
+                .mock(
+                    "POST",
+                    mockito::Matcher::Regex(r"^/api2/json/nodes/.*/qemu/\d+/snapshot$".to_string()),
+                )
+                .with_body(r#"{"data":"UPID:pve-node1:0021C4A2:0233C2F0:67CC7E6A:qmsnapshot:100:root@pam!api:"}"#)
+                .create();
+            self.mocks.push(mock);
+            self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::WithVMSnapshotCreateMock;
+    use super::*;
+    use mock_server::MockServer;
+
+    #[tokio::test]
+    async fn test_vm_snapshot_create() {
+        let client = reqwest::Client::new();
+        let server = MockServer::new().await.with_vm_snapshot_create();
+        let options = VMSnapshotCreateOptions {
+            snapname: String::from("before-upgrade"),
+            description: None,
+            vmstate: Some(false),
+        };
+        let result =
+            vm_snapshot_create(&server.url(), &client, "", "pve-node1", 100, &options).await;
+
+        assert!(result.is_ok());
+    }
+}
@@ -0,0 +1,78 @@
+use crate::proxmox::api::Error;
+use crate::proxmox::api::api_response::{ApiResponse, ApiResponseExt};
+
+/// Deletes a VM snapshot.
+///
+/// Calls `DELETE /nodes/{node}/qemu/{vmid}/snapshot/{snapname}`.
+pub async fn vm_snapshot_delete(
+    api_url: &str,
+    client: &reqwest::Client,
+    authorization: &str,
+    node_id: &str,
+    vm_id: u32,
+    snapname: &str,
+) -> Result<ApiResponse<String>, Error> {
+    client
+        .delete(format!(
+            "{}/api2/json/nodes/{}/qemu/{}/snapshot/{}",
+            api_url,
+            node_id,
+            vm_id,
+            url::form_urlencoded::byte_serialize(snapname.as_bytes()).collect::<String>()
+        ))
+        .header(reqwest::header::AUTHORIZATION, authorization)
+        .send()
+        .await
+        .to_api_response()
+        .await
+}
+
+#[cfg(feature = "mock")]
+pub mod mock {
+    use mock_server::MockServer;
+
+    pub trait WithVMSnapshotDeleteMock {
+        fn with_vm_snapshot_delete(self) -> Self;
+    }
+
+    impl WithVMSnapshotDeleteMock for MockServer {
+        fn with_vm_snapshot_delete(mut self) -> Self {
+            let mock = self
+                .server
+                .mock(
+                    "DELETE",
+                    mockito::Matcher::Regex(
+                        r"^/api2/json/nodes/.*/qemu/\d+/snapshot/[^/]+$".to_string(),
+                    ),
+                )
+                .with_body(r#"{"data":"UPID:pve-node1:0021C6C4:0233E0B8:67CC7EB8:qmdelsnapshot:100:root@pam!api:"}"#)
+                .create();
+            self.mocks.push(mock);
+            self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::WithVMSnapshotDeleteMock;
+    use super::*;
+    use mock_server::MockServer;
+
+    #[tokio::test]
+    async fn test_vm_snapshot_delete() {
+        let client = reqwest::Client::new();
+        let server = MockServer::new().await.with_vm_snapshot_delete();
+        let result = vm_snapshot_delete(
+            &server.url(),
+            &client,
+            "",
+            "pve-node1",
+            100,
+            "before-upgrade",
+        )
+        .await;
+
+        assert!(result.is_ok());
+    }
+}
@@ -0,0 +1,123 @@
+use crate::instance::Snapshot;
+use crate::proxmox::api::Error;
+use crate::proxmox::api::api_response::{ApiResponse, ApiResponseExt};
+use serde::Deserialize;
+
+/// The name Proxmox gives to the pseudo-snapshot representing the live state.
+pub const CURRENT_SNAPSHOT_NAME: &str = "current";
+
+/// Lists the snapshots of a VM.
+///
+/// The response includes a `current` pseudo-snapshot pointing at the live
+/// state of the VM, which callers usually want to filter out.
+///
+/// Calls `GET /nodes/{node}/qemu/{vmid}/snapshot`.
+pub async fn vm_snapshot_list(
+    api_url: &str,
+    client: &reqwest::Client,
+    authorization: &str,
+    node_id: &str,
+    vm_id: u32,
+) -> Result<ApiResponse<Vec<VMSnapshot>>, Error> {
+    client
+        .get(format!(
+            "{}/api2/json/nodes/{}/qemu/{}/snapshot",
+            api_url, node_id, vm_id
+        ))
+        .header(reqwest::header::AUTHORIZATION, authorization)
+        .send()
+        .await
+        .to_api_response()
+        .await
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct VMSnapshot {
+    /// Snapshot description.
+    #[serde(default)]
+    pub description: String,
+
+    /// Snapshot identifier. Value `current` identifies the current VM.
+    pub name: String,
+
+    /// Parent snapshot identifier.
+    pub parent: Option<String>,
+
+    /// Snapshot creation time.
+    pub snaptime: Option<u64>,
+
+    /// Snapshot includes RAM.
+    #[serde(default)]
+    pub vmstate: Option<u8>,
+}
+
+impl From<VMSnapshot> for Snapshot {
+    fn from(value: VMSnapshot) -> Self {
+        Snapshot {
+            name: value.name,
+            description: value.description,
+            parent: value.parent,
+            created_at: value.snaptime,
+            include_memory: value.vmstate.unwrap_or_default() == 1,
+        }
+    }
+}
+
+#[cfg(feature = "mock")]
+pub mod mock {
+    use mock_server::MockServer;
+
+    pub trait WithVMSnapshotListMock {
+        fn with_vm_snapshot_list(self) -> Self;
+    }
+
+    impl WithVMSnapshotListMock for MockServer {
+        fn with_vm_snapshot_list(mut self) -> Self {
+            let mock = self
+                .server
+                .mock(
+                    "GET",
+                    mockito::Matcher::Regex(r"^/api2/json/nodes/.*/qemu/\d+/snapshot$".to_string()),
+                )
+                .with_body(r#"{"data":[{"name":"before-upgrade","description":"pre apt upgrade","snaptime":1741454419,"vmstate":0},{"name":"current","description":"You are here!","parent":"before-upgrade","running":1,"digest":"0d7b4aefa97d9a0dacfcfb0016fa1e614bc56cc6"}]}"#)
+                .create();
+            self.mocks.push(mock);
+            self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::WithVMSnapshotListMock;
+    use super::*;
+    use mock_server::MockServer;
+
+    #[tokio::test]
+    async fn test_vm_snapshot_list() {
+        let client = reqwest::Client::new();
+        let server = MockServer::new().await.with_vm_snapshot_list();
+        let result = vm_snapshot_list(&server.url(), &client, "", "pve-node1", 100).await;
+
+        assert!(result.is_ok());
+        assert_eq!(
+            result.unwrap().data,
+            vec![
+                VMSnapshot {
+                    description: String::from("pre apt upgrade"),
+                    name: String::from("before-upgrade"),
+                    parent: None,
+                    snaptime: Some(1741454419),
+                    vmstate: Some(0),
+                },
+                VMSnapshot {
+                    description: String::from("You are here!"),
+                    name: String::from(CURRENT_SNAPSHOT_NAME),
+                    parent: Some(String::from("before-upgrade")),
+                    snaptime: None,
+                    vmstate: None,
+                },
+            ]
+        );
+    }
+}
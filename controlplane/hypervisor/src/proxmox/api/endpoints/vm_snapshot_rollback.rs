@@ -0,0 +1,78 @@
+use crate::proxmox::api::Error;
+use crate::proxmox::api::api_response::{ApiResponse, ApiResponseExt};
+
+/// Rolls a VM back to a given snapshot.
+///
+/// Calls `POST /nodes/{node}/qemu/{vmid}/snapshot/{snapname}/rollback`.
+pub async fn vm_snapshot_rollback(
+    api_url: &str,
+    client: &reqwest::Client,
+    authorization: &str,
+    node_id: &str,
+    vm_id: u32,
+    snapname: &str,
+) -> Result<ApiResponse<String>, Error> {
+    client
+        .post(format!(
+            "{}/api2/json/nodes/{}/qemu/{}/snapshot/{}/rollback",
+            api_url,
+            node_id,
+            vm_id,
+            url::form_urlencoded::byte_serialize(snapname.as_bytes()).collect::<String>()
+        ))
+        .header(reqwest::header::AUTHORIZATION, authorization)
+        .send()
+        .await
+        .to_api_response()
+        .await
+}
+
+#[cfg(feature = "mock")]
+pub mod mock {
+    use mock_server::MockServer;
+
+    pub trait WithVMSnapshotRollbackMock {
+        fn with_vm_snapshot_rollback(self) -> Self;
+    }
+
+    impl WithVMSnapshotRollbackMock for MockServer {
+        fn with_vm_snapshot_rollback(mut self) -> Self {
+            let mock = self
+                .server
+                .mock(
+                    "POST",
+                    mockito::Matcher::Regex(
+                        r"^/api2/json/nodes/.*/qemu/\d+/snapshot/[^/]+/rollback$".to_string(),
+                    ),
+                )
+                .with_body(r#"{"data":"UPID:pve-node1:0021C5B0:0233D1A4:67CC7E90:qmrollback:100:root@pam!api:"}"#)
+                .create();
+            self.mocks.push(mock);
+            self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::WithVMSnapshotRollbackMock;
+    use super::*;
+    use mock_server::MockServer;
+
+    #[tokio::test]
+    async fn test_vm_snapshot_rollback() {
+        let client = reqwest::Client::new();
+        let server = MockServer::new().await.with_vm_snapshot_rollback();
+        let result = vm_snapshot_rollback(
+            &server.url(),
+            &client,
+            "",
+            "pve-node1",
+            100,
+            "before-upgrade",
+        )
+        .await;
+
+        assert!(result.is_ok());
+    }
+}
@@ -25,6 +25,12 @@ pub async fn vm_status_read(
 #[derive(Debug, Deserialize, Eq, PartialEq)]
 pub struct VMStatusResponse {
     pub status: ResourceStatus,
+
+    /// The QEMU state of the VM, distinguishing paused from running VMs.
+    pub qmpstatus: Option<ResourceStatus>,
+
+    /// The lock held on the VM, if any (e.g. `suspended` once hibernated).
+    pub lock: Option<String>,
 }
 
 #[cfg(feature = "mock")]
@@ -71,7 +77,9 @@ mod tests {
             result.unwrap(),
             ApiResponse {
                 data: VMStatusResponse {
-                    status: ResourceStatus::Running
+                    status: ResourceStatus::Running,
+                    qmpstatus: Some(ResourceStatus::Running),
+                    lock: None,
                 }
             }
         );
@@ -0,0 +1,66 @@
+use crate::proxmox::api::Error;
+use crate::proxmox::api::api_response::{ApiResponse, ApiResponseExt};
+
+/// Reboots a VM gracefully, through an ACPI event or the guest agent.
+///
+/// Calls `POST /nodes/{node}/qemu/{vmid}/status/reboot`.
+pub async fn vm_status_reboot(
+    api_url: &str,
+    client: &reqwest::Client,
+    authorization: &str,
+    node_id: &str,
+    vm_id: u32,
+) -> Result<ApiResponse<String>, Error> {
+    client
+        .post(format!(
+            "{}/api2/json/nodes/{}/qemu/{}/status/reboot",
+            api_url, node_id, vm_id
+        ))
+        .header(reqwest::header::AUTHORIZATION, authorization)
+        .send()
+        .await
+        .to_api_response()
+        .await
+}
+
+#[cfg(feature = "mock")]
+pub mod mock {
+    use mock_server::MockServer;
+
+    pub trait WithVMStatusRebootMock {
+        fn with_vm_status_reboot(self) -> Self;
+    }
+
+    impl WithVMStatusRebootMock for MockServer {
+        fn with_vm_status_reboot(mut self) -> Self {
+            let mock = self
+                .server
+                .mock(
+                    "POST",
+                    mockito::Matcher::Regex(
+                        r"^/api2/json/nodes/.*/qemu/\d+/status/reboot$".to_string(),
+                    ),
+                )
+                .with_body(r#"{"data":"UPID:pve-node1:0021BBE8:02333375:67CC7CF9:qmreboot:105:root@pam!api:"}"#)
+                .create();
+            self.mocks.push(mock);
+            self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::WithVMStatusRebootMock;
+    use super::*;
+    use mock_server::MockServer;
+
+    #[tokio::test]
+    async fn test_vm_status_reboot() {
+        let client = reqwest::Client::new();
+        let server = MockServer::new().await.with_vm_status_reboot();
+        let result = vm_status_reboot(&server.url(), &client, "", "pve-node1", 100).await;
+
+        assert!(result.is_ok());
+    }
+}
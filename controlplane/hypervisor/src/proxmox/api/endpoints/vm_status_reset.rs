@@ -0,0 +1,66 @@
+use crate::proxmox::api::Error;
+use crate::proxmox::api::api_response::{ApiResponse, ApiResponseExt};
+
+/// Resets a VM, as a hard reset button would.
+///
+/// Calls `POST /nodes/{node}/qemu/{vmid}/status/reset`.
+pub async fn vm_status_reset(
+    api_url: &str,
+    client: &reqwest::Client,
+    authorization: &str,
+    node_id: &str,
+    vm_id: u32,
+) -> Result<ApiResponse<String>, Error> {
+    client
+        .post(format!(
+            "{}/api2/json/nodes/{}/qemu/{}/status/reset",
+            api_url, node_id, vm_id
+        ))
+        .header(reqwest::header::AUTHORIZATION, authorization)
+        .send()
+        .await
+        .to_api_response()
+        .await
+}
+
+#[cfg(feature = "mock")]
+pub mod mock {
+    use mock_server::MockServer;
+
+    pub trait WithVMStatusResetMock {
+        fn with_vm_status_reset(self) -> Self;
+    }
+
+    impl WithVMStatusResetMock for MockServer {
+        fn with_vm_status_reset(mut self) -> Self {
+            let mock = self
+                .server
+                .mock(
+                    "POST",
+                    mockito::Matcher::Regex(
+                        r"^/api2/json/nodes/.*/qemu/\d+/status/reset$".to_string(),
+                    ),
+                )
+                .with_body(r#"{"data":"UPID:pve-node1:0021BBE8:02333375:67CC7CF9:qmreset:105:root@pam!api:"}"#)
+                .create();
+            self.mocks.push(mock);
+            self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::WithVMStatusResetMock;
+    use super::*;
+    use mock_server::MockServer;
+
+    #[tokio::test]
+    async fn test_vm_status_reset() {
+        let client = reqwest::Client::new();
+        let server = MockServer::new().await.with_vm_status_reset();
+        let result = vm_status_reset(&server.url(), &client, "", "pve-node1", 100).await;
+
+        assert!(result.is_ok());
+    }
+}
@@ -0,0 +1,66 @@
+use crate::proxmox::api::Error;
+use crate::proxmox::api::api_response::{ApiResponse, ApiResponseExt};
+
+/// Resumes a paused or suspended VM.
+///
+/// Calls `POST /nodes/{node}/qemu/{vmid}/status/resume`.
+pub async fn vm_status_resume(
+    api_url: &str,
+    client: &reqwest::Client,
+    authorization: &str,
+    node_id: &str,
+    vm_id: u32,
+) -> Result<ApiResponse<String>, Error> {
+    client
+        .post(format!(
+            "{}/api2/json/nodes/{}/qemu/{}/status/resume",
+            api_url, node_id, vm_id
+        ))
+        .header(reqwest::header::AUTHORIZATION, authorization)
+        .send()
+        .await
+        .to_api_response()
+        .await
+}
+
+#[cfg(feature = "mock")]
+pub mod mock {
+    use mock_server::MockServer;
+
+    pub trait WithVMStatusResumeMock {
+        fn with_vm_status_resume(self) -> Self;
+    }
+
+    impl WithVMStatusResumeMock for MockServer {
+        fn with_vm_status_resume(mut self) -> Self {
+            let mock = self
+                .server
+                .mock(
+                    "POST",
+                    mockito::Matcher::Regex(
+                        r"^/api2/json/nodes/.*/qemu/\d+/status/resume$".to_string(),
+                    ),
+                )
+                .with_body(r#"{"data":"UPID:pve-node1:0021BBE8:02333375:67CC7CF9:qmresume:105:root@pam!api:"}"#)
+                .create();
+            self.mocks.push(mock);
+            self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::WithVMStatusResumeMock;
+    use super::*;
+    use mock_server::MockServer;
+
+    #[tokio::test]
+    async fn test_vm_status_resume() {
+        let client = reqwest::Client::new();
+        let server = MockServer::new().await.with_vm_status_resume();
+        let result = vm_status_resume(&server.url(), &client, "", "pve-node1", 100).await;
+
+        assert!(result.is_ok());
+    }
+}
@@ -0,0 +1,86 @@
+use crate::proxmox::api::Error;
+use crate::proxmox::api::api_response::{ApiResponse, ApiResponseExt};
+use serde::Serialize;
+use serde_with::skip_serializing_none;
+
+/// Shuts a VM down gracefully, through an ACPI event or the guest agent.
+///
+/// Calls `POST /nodes/{node}/qemu/{vmid}/status/shutdown`.
+pub async fn vm_status_shutdown(
+    api_url: &str,
+    client: &reqwest::Client,
+    authorization: &str,
+    node_id: &str,
+    vm_id: u32,
+    options: &VMShutdownOptions,
+) -> Result<ApiResponse<String>, Error> {
+    client
+        .post(format!(
+            "{}/api2/json/nodes/{}/qemu/{}/status/shutdown",
+            api_url, node_id, vm_id
+        ))
+        .header(reqwest::header::AUTHORIZATION, authorization)
+        .json(options)
+        .send()
+        .await
+        .to_api_response()
+        .await
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Default, Serialize)]
+pub struct VMShutdownOptions {
+    /// Maximum time to wait for the guest to power off, in seconds. The task
+    /// fails once it elapses, leaving the VM running.
+    pub timeout: Option<u32>,
+}
+
+#[cfg(feature = "mock")]
+pub mod mock {
+    use mock_server::MockServer;
+
+    pub trait WithVMStatusShutdownMock {
+        fn with_vm_status_shutdown(self) -> Self;
+    }
+
+    impl WithVMStatusShutdownMock for MockServer {
+        fn with_vm_status_shutdown(mut self) -> Self {
+            let mock = self
+                .server
+                .mock(
+                    "POST",
+                    mockito::Matcher::Regex(
+                        r"^/api2/json/nodes/.*/qemu/\d+/status/shutdown$".to_string(),
+                    ),
+                )
+                .with_body(r#"{"data":"UPID:pve-node1:0021BBE8:02333375:67CC7CF9:qmshutdown:105:root@pam!api:"}"#)
+                .create();
+            self.mocks.push(mock);
+            self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::WithVMStatusShutdownMock;
+    use super::*;
+    use mock_server::MockServer;
+
+    #[tokio::test]
+    async fn test_vm_status_shutdown() {
+        let client = reqwest::Client::new();
+        let server = MockServer::new().await.with_vm_status_shutdown();
+        let result = vm_status_shutdown(
+            &server.url(),
+            &client,
+            "",
+            "pve-node1",
+            100,
+            &VMShutdownOptions::default(),
+        )
+        .await;
+
+        assert!(result.is_ok());
+    }
+}
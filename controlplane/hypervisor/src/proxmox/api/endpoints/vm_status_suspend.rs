@@ -0,0 +1,79 @@
+use crate::proxmox::api::Error;
+use crate::proxmox::api::api_response::{ApiResponse, ApiResponseExt};
+use serde::Serialize;
+
+/// Request body for the Proxmox suspend endpoint.
+#[derive(Debug, Serialize)]
+struct VMStatusSuspendRequest {
+    /// Whether to hibernate the VM to disk instead of pausing it in memory.
+    todisk: bool,
+}
+
+/// Suspends a VM.
+///
+/// The VM is paused in memory, or hibernated to disk and stopped when
+/// `to_disk` is set.
+///
+/// Calls `POST /nodes/{node}/qemu/{vmid}/status/suspend`.
+pub async fn vm_status_suspend(
+    api_url: &str,
+    client: &reqwest::Client,
+    authorization: &str,
+    node_id: &str,
+    vm_id: u32,
+    to_disk: bool,
+) -> Result<ApiResponse<String>, Error> {
+    client
+        .post(format!(
+            "{}/api2/json/nodes/{}/qemu/{}/status/suspend",
+            api_url, node_id, vm_id
+        ))
+        .header(reqwest::header::AUTHORIZATION, authorization)
+        .json(&VMStatusSuspendRequest { todisk: to_disk })
+        .send()
+        .await
+        .to_api_response()
+        .await
+}
+
+#[cfg(feature = "mock")]
+pub mod mock {
+    use mock_server::MockServer;
+
+    pub trait WithVMStatusSuspendMock {
+        fn with_vm_status_suspend(self) -> Self;
+    }
+
+    impl WithVMStatusSuspendMock for MockServer {
+        fn with_vm_status_suspend(mut self) -> Self {
+            let mock = self
+                .server
+                .mock(
+                    "POST",
+                    mockito::Matcher::Regex(
+                        r"^/api2/json/nodes/.*/qemu/\d+/status/suspend$".to_string(),
+                    ),
+                )
+                .with_body(r#"{"data":"UPID:pve-node1:0021BBE8:02333375:67CC7CF9:qmsuspend:105:root@pam!api:"}"#)
+                .create();
+            self.mocks.push(mock);
+            self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::WithVMStatusSuspendMock;
+    use super::*;
+    use mock_server::MockServer;
+
+    #[tokio::test]
+    async fn test_vm_status_suspend() {
+        let client = reqwest::Client::new();
+        let server = MockServer::new().await.with_vm_status_suspend();
+        let result = vm_status_suspend(&server.url(), &client, "", "pve-node1", 100, false).await;
+
+        assert!(result.is_ok());
+    }
+}
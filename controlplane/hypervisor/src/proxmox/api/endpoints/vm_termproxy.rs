@@ -0,0 +1,68 @@
+use crate::proxmox::api::Error;
+use crate::proxmox::api::api_response::{ApiResponse, ApiResponseExt};
+use crate::proxmox::api::vm_vncproxy::ProxyTicket;
+
+/// Opens a terminal proxy to the serial console of a VM.
+///
+/// The proxy client must send `<user>:<ticket>\n` as its first message on the
+/// `vncwebsocket` endpoint streaming it.
+///
+/// Calls `POST /nodes/{node}/qemu/{vmid}/termproxy`.
+pub async fn vm_termproxy(
+    api_url: &str,
+    client: &reqwest::Client,
+    authorization: &str,
+    node_id: &str,
+    vm_id: u32,
+) -> Result<ApiResponse<ProxyTicket>, Error> {
+    client
+        .post(format!(
+            "{}/api2/json/nodes/{}/qemu/{}/termproxy",
+            api_url, node_id, vm_id
+        ))
+        .header(reqwest::header::AUTHORIZATION, authorization)
+        .send()
+        .await
+        .to_api_response()
+        .await
+}
+
+#[cfg(feature = "mock")]
+pub mod mock {
+    use mock_server::MockServer;
+
+    pub trait WithVMTermProxyMock {
+        fn with_vm_termproxy(self) -> Self;
+    }
+
+    impl WithVMTermProxyMock for MockServer {
+        fn with_vm_termproxy(mut self) -> Self {
+            let mock = self
+                .server
+                .mock(
+                    "POST",
+                    mockito::Matcher::Regex(r"^/api2/json/nodes/.*/qemu/\d+/termproxy$".to_string()),
+                )
+                .with_body(r#"{"data":{"port":5901,"ticket":"PVEVNC:67CC7F3A::ZG9sb3Igc2l0IGFtZXQ=","upid":"UPID:pve-node1:0021C7E1:0233D2B6:67CC7F3A:vncproxy:100:root@pam!api:","user":"root@pam!api"}}"#)
+                .create();
+            self.mocks.push(mock);
+            self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::WithVMTermProxyMock;
+    use super::*;
+    use mock_server::MockServer;
+
+    #[tokio::test]
+    async fn test_vm_termproxy() {
+        let client = reqwest::Client::new();
+        let server = MockServer::new().await.with_vm_termproxy();
+        let result = vm_termproxy(&server.url(), &client, "", "pve-node1", 100).await;
+
+        assert_eq!(result.unwrap().data.port, 5901);
+    }
+}
@@ -0,0 +1,104 @@
+use crate::proxmox::api::Error;
+use crate::proxmox::api::api_response::{ApiResponse, ApiResponseExt};
+use serde::{Deserialize, Serialize};
+use serde_with::{DisplayFromStr, PickFirst, serde_as};
+
+/// Request body for the Proxmox VNC proxy endpoint.
+#[derive(Debug, Serialize)]
+struct VMVncProxyRequest {
+    /// Prepares the proxy for a websocket client.
+    websocket: bool,
+}
+
+/// Opens a VNC proxy to the display of a VM.
+///
+/// The returned ticket is both the password of the VNC session and the
+/// credential of the `vncwebsocket` endpoint streaming it.
+///
+/// Calls `POST /nodes/{node}/qemu/{vmid}/vncproxy`.
+pub async fn vm_vncproxy(
+    api_url: &str,
+    client: &reqwest::Client,
+    authorization: &str,
+    node_id: &str,
+    vm_id: u32,
+) -> Result<ApiResponse<ProxyTicket>, Error> {
+    client
+        .post(format!(
+            "{}/api2/json/nodes/{}/qemu/{}/vncproxy",
+            api_url, node_id, vm_id
+        ))
+        .header(reqwest::header::AUTHORIZATION, authorization)
+        .json(&VMVncProxyRequest { websocket: true })
+        .send()
+        .await
+        .to_api_response()
+        .await
+}
+
+/// A console proxy opened on a node, awaiting its client.
+#[serde_as]
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct ProxyTicket {
+    /// The port the proxy listens on, on the node.
+    #[serde_as(as = "PickFirst<(_, DisplayFromStr)>")]
+    pub port: u16,
+
+    /// The ticket authenticating the proxy client.
+    pub ticket: String,
+
+    /// The user the ticket was issued to.
+    pub user: String,
+
+    /// The task running the proxy.
+    pub upid: String,
+}
+
+#[cfg(feature = "mock")]
+pub mod mock {
+    use mock_server::MockServer;
+
+    pub trait WithVMVncProxyMock {
+        fn with_vm_vncproxy(self) -> Self;
+    }
+
+    impl WithVMVncProxyMock for MockServer {
+        fn with_vm_vncproxy(mut self) -> Self {
+            let mock = self
+                .server
+                .mock(
+                    "POST",
+                    mockito::Matcher::Regex(r"^/api2/json/nodes/.*/qemu/\d+/vncproxy$".to_string()),
+                )
+                .with_body(r#"{"data":{"cert":"-----BEGIN CERTIFICATE-----\n-----END CERTIFICATE-----\n","port":"5900","ticket":"PVEVNC:67CC7F02::bG9yZW0gaXBzdW0=","upid":"UPID:pve-node1:0021C7A5:0233D1E4:67CC7F02:vncproxy:100:root@pam!api:","user":"root@pam!api"}}"#)
+                .create();
+            self.mocks.push(mock);
+            self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::WithVMVncProxyMock;
+    use super::*;
+    use mock_server::MockServer;
+
+    #[tokio::test]
+    async fn test_vm_vncproxy() {
+        let client = reqwest::Client::new();
+        let server = MockServer::new().await.with_vm_vncproxy();
+        let result = vm_vncproxy(&server.url(), &client, "", "pve-node1", 100).await;
+
+        assert_eq!(
+            result.unwrap().data,
+            ProxyTicket {
+                port: 5900,
+                ticket: "PVEVNC:67CC7F02::bG9yZW0gaXBzdW0=".to_owned(),
+                user: "root@pam!api".to_owned(),
+                upid: "UPID:pve-node1:0021C7A5:0233D1E4:67CC7F02:vncproxy:100:root@pam!api:"
+                    .to_owned(),
+            }
+        );
+    }
+}
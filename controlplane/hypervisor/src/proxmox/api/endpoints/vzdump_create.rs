@@ -0,0 +1,103 @@
+use crate::proxmox::api::Error;
+use crate::proxmox::api::api_response::{ApiResponse, ApiResponseExt};
+use serde::Serialize;
+use serde_with::skip_serializing_none;
+
+/// Backs a VM up with vzdump.
+///
+/// Returns the task id of the backup.
+///
+/// Calls `POST /nodes/{node}/vzdump`.
+pub async fn vzdump_create(
+    api_url: &str,
+    client: &reqwest::Client,
+    authorization: &str,
+    node_id: &str,
+    options: &VzdumpOptions,
+) -> Result<ApiResponse<String>, Error> {
+    client
+        .post(format!("{}/api2/json/nodes/{}/vzdump", api_url, node_id))
+        .header(reqwest::header::AUTHORIZATION, authorization)
+        .json(options)
+        .send()
+        .await
+        .to_api_response()
+        .await
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Serialize)]
+pub struct VzdumpOptions {
+    /// The id of the VM to back up.
+    pub vmid: String,
+
+    /// The storage to store the backup on.
+    pub storage: String,
+
+    /// The backup mode (`snapshot`, `suspend` or `stop`).
+    pub mode: String,
+
+    /// The compression of the backup (e.g. `zstd`), ignored by Proxmox Backup
+    /// Server storages which always compress.
+    pub compress: Option<String>,
+
+    /// The retention applied to the backups of the VM once done (e.g.
+    /// `keep-last=3,keep-daily=7`), the one of the storage when unset.
+    #[serde(rename = "prune-backups")]
+    pub prune_backups: Option<String>,
+
+    /// The notes attached to the backups (e.g. `{{guestname}}`).
+    #[serde(rename = "notes-template")]
+    pub notes_template: Option<String>,
+}
+
+#[cfg(feature = "mock")]
+pub mod mock {
+    use mock_server::MockServer;
+
+    pub trait WithVzdumpCreateMock {
+        fn with_vzdump_create(self) -> Self;
+    }
+
+    impl WithVzdumpCreateMock for MockServer {
+        fn with_vzdump_create(mut self) -> Self {
+            let mock = self
+                .server
+                .mock(
+                    "POST",
+                    mockito::Matcher::Regex(r"^/api2/json/nodes/.*/vzdump$".to_string()),
+                )
+                .with_body(r#"{"data":"UPID:pve-node1:0021D2A4:0234A1C3:67CC8A10:vzdump:100:root@pam!api:"}"#)
+                .create();
+            self.mocks.push(mock);
+            self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::WithVzdumpCreateMock;
+    use super::*;
+    use mock_server::MockServer;
+
+    #[tokio::test]
+    async fn test_vzdump_create() {
+        let client = reqwest::Client::new();
+        let server = MockServer::new().await.with_vzdump_create();
+        let options = VzdumpOptions {
+            vmid: "100".to_owned(),
+            storage: "pbs".to_owned(),
+            mode: "snapshot".to_owned(),
+            compress: None,
+            prune_backups: Some("keep-last=3".to_owned()),
+            notes_template: None,
+        };
+        let result = vzdump_create(&server.url(), &client, "", "pve-node1", &options).await;
+
+        assert_eq!(
+            result.unwrap().data,
+            "UPID:pve-node1:0021D2A4:0234A1C3:67CC8A10:vzdump:100:root@pam!api:"
+        );
+    }
+}
@@ -15,6 +15,15 @@ pub enum Error {
     #[error("The resource is guarded by Cloudflare")]
     GuardedByCloudflare,
 
+    #[error(
+        "No node can fit {cores} cores, {memory_bytes} bytes of memory and {disk_bytes} bytes of disk"
+    )]
+    InsufficientCapacity {
+        cores: u32,
+        memory_bytes: u64,
+        disk_bytes: u64,
+    },
+
     #[error("Proxmox Internal Server Error: {}", .response.message)]
     Internal { response: ApiInternalErrorResponse },
 
@@ -27,8 +36,14 @@ pub enum Error {
     #[error("No nodes are available on the cluster.")]
     NoNodesAvailable,
 
+    #[error("The volume {0} is not a backup")]
+    NotABackup(String),
+
+    #[error("The volume {0} is not an image")]
+    NotAnImage(String),
+
     #[error("The resource {0:?} is not an instance")]
-    NotAnInstance(Resource),
+    NotAnInstance(Box<Resource>),
 
     #[error("Proxmox resource #{id} of type {resource_type} is missing field {field}")]
     ResourceMissingField {
@@ -37,9 +52,15 @@ pub enum Error {
         field: String,
     },
 
+    #[error("Proxmox Task #{task} failed: {message}")]
+    TaskFailed { task: String, message: String },
+
     #[error("Proxmox Task #{0} has not completed")]
     TaskNotCompleted(String),
 
+    #[error("Proxmox Agent command #{0} has not completed")]
+    AgentCommandNotCompleted(u64),
+
     #[error("Attempted to run a VM action on a VM template")]
     IsTemplate,
 
@@ -64,6 +85,10 @@ impl From<Error> for crate::Error {
         match &value {
             Error::VMNotFound(id) => crate::Error::DistantInstanceNotFound(id.to_string()),
             Error::VMNotRunning(id) => crate::Error::InstanceNotRunning(id.to_string()),
+            Error::InsufficientCapacity { .. } | Error::NoNodesAvailable => {
+                crate::Error::InsufficientCapacity(value.to_string())
+            }
+            Error::MissingAgent => crate::Error::GuestAgentUnavailable,
             Error::IsTemplate => crate::Error::InstanceNotRunning("template".to_owned()),
             _ => crate::Error::Other(Box::new(value)),
         }
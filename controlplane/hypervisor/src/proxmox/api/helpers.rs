@@ -1,7 +1,7 @@
 use std::time::Duration;
 
 use tokio_retry::{
-    Retry,
+    Retry, RetryIf,
     strategy::{ExponentialBackoff, jitter},
 };
 
@@ -9,6 +9,7 @@ use super::{
     Error,
     cluster_resources_list::ResourceType,
     task_status_read::{TaskStatus, TaskStatusResponse},
+    vm_agent_exec_status::VMAgentExecStatus,
 };
 
 pub async fn wait_for_task_completion(
@@ -24,16 +25,59 @@ pub async fn wait_for_task_completion(
         .map(jitter)
         .take(10);
 
+    // A failed task is final, so only running tasks and request failures
+    // are retried.
+    RetryIf::spawn(
+        strategy,
+        || async {
+            let response =
+                crate::proxmox::api::task_status_read(api_url, client, authorization, node, task)
+                    .await?
+                    .data;
+
+            match (&response.status, response.exit_status.as_deref()) {
+                (TaskStatus::Running, _) => Err(Error::TaskNotCompleted(task.to_owned())),
+                (TaskStatus::Stopped, None | Some("OK")) => Ok(response),
+                (TaskStatus::Stopped, Some(message)) => Err(Error::TaskFailed {
+                    task: task.to_owned(),
+                    message: message.to_owned(),
+                }),
+            }
+        },
+        |error: &Error| !matches!(error, Error::TaskFailed { .. }),
+    )
+    .await
+}
+
+pub async fn wait_for_agent_command(
+    api_url: &str,
+    client: &reqwest::Client,
+    authorization: &str,
+    node: &str,
+    vm_id: u32,
+    pid: u64,
+) -> Result<VMAgentExecStatus, Error> {
+    let strategy = ExponentialBackoff::from_millis(2)
+        .factor(250)
+        .max_delay(Duration::from_secs(10))
+        .map(jitter)
+        .take(10);
+
     Retry::spawn(strategy, || async {
-        let result =
-            crate::proxmox::api::task_status_read(api_url, client, authorization, node, task).await;
+        let status = crate::proxmox::api::vm_agent_exec_status(
+            api_url,
+            client,
+            authorization,
+            node,
+            vm_id,
+            pid,
+        )
+        .await?
+        .data;
 
-        match result {
-            Ok(response) => match response.data.status {
-                TaskStatus::Running => Err(Error::TaskNotCompleted(task.to_owned())),
-                TaskStatus::Stopped => Ok(response.data),
-            },
-            Err(err) => Err(err),
+        match status.exited {
+            0 => Err(Error::AgentCommandNotCompleted(pid)),
+            _ => Ok(status),
         }
     })
     .await
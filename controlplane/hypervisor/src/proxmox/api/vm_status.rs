@@ -3,29 +3,72 @@ use serde::Deserialize;
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
 pub enum ResourceStatus {
+    #[serde(rename = "available")]
+    Available,
+
     #[serde(rename = "offline")]
     Offline,
 
     #[serde(rename = "online")]
     Online,
 
+    #[serde(rename = "paused")]
+    Paused,
+
     #[serde(rename = "running")]
     Running,
 
     #[serde(rename = "stopped")]
     Stopped,
 
-    #[serde(rename = "unknown")]
+    #[serde(rename = "suspended")]
+    Suspended,
+
+    #[serde(rename = "unknown", other)]
     Unknown,
 }
 
 impl From<ResourceStatus> for Status {
     fn from(value: ResourceStatus) -> Self {
         match value {
+            ResourceStatus::Paused => Status::Paused,
             ResourceStatus::Running => Status::Running,
             ResourceStatus::Stopped => Status::Stopped,
+            ResourceStatus::Suspended => Status::Suspended,
             ResourceStatus::Unknown => Status::Unknown,
             other => panic!("attempting to cast {:?} as an instance status", other),
         }
     }
 }
+
+/// Resolves the status of an instance from the state Proxmox reports for it.
+///
+/// Proxmox reports the QEMU state of paused VMs, while VMs hibernated to disk
+/// are reported as stopped with a `suspended` lock.
+pub fn instance_status(status: ResourceStatus, lock: Option<&str>) -> Status {
+    match lock {
+        Some("suspended" | "suspending") => Status::Suspended,
+        _ => status.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_instance_status() {
+        assert!(matches!(
+            instance_status(ResourceStatus::Paused, None),
+            Status::Paused
+        ));
+        assert!(matches!(
+            instance_status(ResourceStatus::Stopped, Some("suspended")),
+            Status::Suspended
+        ));
+        assert!(matches!(
+            instance_status(ResourceStatus::Running, Some("backup")),
+            Status::Running
+        ));
+    }
+}
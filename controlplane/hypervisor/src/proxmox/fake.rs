@@ -0,0 +1,1748 @@
+//! Stateful emulator of the Proxmox VE API.
+//!
+//! The mocks of the endpoints answer each request with a canned response,
+//! whatever happened before. The emulator instead keeps the nodes, VMs,
+//! storage contents and tasks of a cluster, so that sequences of calls behave
+//! as they would against a real cluster: a created VM is listed once its
+//! task completed, a started VM reports its address through the guest agent,
+//! and a deleted VM is not found anymore.
+//!
+//! Tasks complete after a configurable latency, and tasks of a given type can
+//! be configured to fail, in which case their effect is rolled back.
+//!
+//! ```
+//! use hypervisor::proxmox::fake::{FakeProxmox, WithFakeProxmox};
+//! use mock_server::MockServer;
+//!
+//! # async fn test() {
+//! let proxmox = FakeProxmox::new().with_node("pve-node1", 16, 64 * 1024 * 1024 * 1024);
+//! let server = MockServer::new().await.with_fake_proxmox(&proxmox);
+//! # }
+//! ```
+
+use crate::instance::Status;
+use mock_server::MockServer;
+use serde_json::{Map, Value, json};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::net::Ipv4Addr;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// The storage the disks of the VMs are allocated on.
+const IMAGE_STORAGE: &str = "local-lvm";
+
+/// The storage snippets are stored on.
+const SNIPPETS_STORAGE: &str = "nfs-snippets";
+
+/// The storage backups are stored on.
+const BACKUP_STORAGE: &str = "pbs";
+
+/// The lowest id handed out to VMs.
+const FIRST_VM_ID: u32 = 100;
+
+const GIB: u64 = 1024 * 1024 * 1024;
+
+/// A VM of the emulated cluster.
+#[derive(Clone, Debug)]
+pub struct FakeVm {
+    pub vmid: u32,
+    pub node: String,
+    pub name: String,
+    pub status: Status,
+
+    /// The lock held on the VM (e.g. `create` while it is created).
+    pub lock: Option<String>,
+
+    /// The configuration of the VM, as returned by the config endpoint.
+    pub config: BTreeMap<String, String>,
+
+    /// The changes to the configuration applied on the next start of the VM.
+    pub pending: BTreeMap<String, String>,
+
+    /// The address reported by the guest agent once the VM started.
+    pub ip_address: Option<Ipv4Addr>,
+}
+
+impl FakeVm {
+    fn cores(&self) -> u32 {
+        self.config
+            .get("cores")
+            .and_then(|cores| cores.parse().ok())
+            .unwrap_or(1)
+    }
+
+    fn memory_bytes(&self) -> u64 {
+        self.config
+            .get("memory")
+            .and_then(|memory| memory.parse::<u64>().ok())
+            .unwrap_or(512)
+            * 1024
+            * 1024
+    }
+
+    fn disk_bytes(&self) -> u64 {
+        self.config
+            .get("scsi0")
+            .and_then(|disk| {
+                disk.split(',')
+                    .find_map(|option| option.strip_prefix("size="))
+            })
+            .and_then(parse_size)
+            .unwrap_or_default()
+    }
+
+    /// Whether the QEMU guest agent is enabled.
+    fn has_agent(&self) -> bool {
+        self.config
+            .get("agent")
+            .is_some_and(|agent| agent == "1" || agent.split(',').any(|o| o == "enabled=1"))
+    }
+
+    /// The static address of the VM, none when it is configured over DHCP.
+    fn static_address(&self) -> Option<Ipv4Addr> {
+        self.config
+            .get("ipconfig0")?
+            .split(',')
+            .find_map(|option| option.strip_prefix("ip="))?
+            .split_once('/')?
+            .0
+            .parse()
+            .ok()
+    }
+
+    /// Whether the VM runs, even paused.
+    fn is_running(&self) -> bool {
+        matches!(self.status, Status::Running | Status::Paused)
+    }
+
+    /// The status of the VM, and the status reported by QEMU.
+    fn resource_status(&self) -> (&'static str, &'static str) {
+        match self.status {
+            Status::Running => ("running", "running"),
+            Status::Paused => ("running", "paused"),
+            _ => ("stopped", "stopped"),
+        }
+    }
+}
+
+struct FakeNode {
+    name: String,
+    cores: u32,
+    memory_bytes: u64,
+    disk_bytes: u64,
+}
+
+struct FakeVolume {
+    node: String,
+    storage: String,
+    volid: String,
+    content: String,
+    size: u64,
+    vmid: Option<u32>,
+    notes: Option<String>,
+
+    /// The configuration of the VM a backup was taken of.
+    config: BTreeMap<String, String>,
+}
+
+/// A scheduled backup job of the emulated cluster.
+struct FakeBackupJob {
+    id: String,
+    vmid: u32,
+}
+
+type Effect = Box<dyn FnOnce(&mut State) + Send>;
+
+struct FakeTask {
+    upid: String,
+    node: String,
+    task_type: String,
+    id: String,
+    pid: u32,
+    starttime: u64,
+    ready_at: Instant,
+
+    /// The exit status once the task stopped.
+    exit_status: Option<String>,
+
+    on_success: Option<Effect>,
+    on_failure: Option<Effect>,
+}
+
+#[derive(Default)]
+struct State {
+    nodes: Vec<FakeNode>,
+    vms: BTreeMap<u32, FakeVm>,
+    volumes: Vec<FakeVolume>,
+
+    /// The backup storage, which clusters may share.
+    backups: Arc<Mutex<Vec<FakeVolume>>>,
+    backup_jobs: Vec<FakeBackupJob>,
+    tasks: Vec<FakeTask>,
+    latency: Duration,
+    failures: HashMap<String, String>,
+    next_pid: u32,
+}
+
+impl State {
+    /// Stops the tasks whose latency elapsed, applying their effects.
+    fn settle(&mut self) {
+        let now = Instant::now();
+        let mut effects = vec![];
+        for task in self
+            .tasks
+            .iter_mut()
+            .filter(|task| task.exit_status.is_none() && task.ready_at <= now)
+        {
+            match self.failures.get(&task.task_type) {
+                Some(message) => {
+                    task.exit_status = Some(message.clone());
+                    effects.extend(task.on_failure.take());
+                }
+                None => {
+                    task.exit_status = Some("OK".to_owned());
+                    effects.extend(task.on_success.take());
+                }
+            }
+        }
+
+        for effect in effects {
+            effect(self);
+        }
+    }
+
+    /// Starts a task, returning its UPID.
+    fn spawn(
+        &mut self,
+        node: &str,
+        task_type: &str,
+        id: &str,
+        on_success: impl FnOnce(&mut State) + Send + 'static,
+        on_failure: impl FnOnce(&mut State) + Send + 'static,
+    ) -> String {
+        self.next_pid += 1;
+        let pid = self.next_pid;
+        let starttime = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let upid = format!(
+            "UPID:{}:{:08X}:{:08X}:{:08X}:{}:{}:root@pam:",
+            node, pid, pid, starttime, task_type, id
+        );
+
+        self.tasks.push(FakeTask {
+            upid: upid.clone(),
+            node: node.to_owned(),
+            task_type: task_type.to_owned(),
+            id: id.to_owned(),
+            pid,
+            starttime,
+            ready_at: Instant::now() + self.latency,
+            exit_status: None,
+            on_success: Some(Box::new(on_success)),
+            on_failure: Some(Box::new(on_failure)),
+        });
+        self.settle();
+
+        upid
+    }
+
+    fn backups(&self) -> MutexGuard<'_, Vec<FakeVolume>> {
+        self.backups.lock().expect("fake proxmox backups poisoned")
+    }
+
+    fn next_id(&self) -> u32 {
+        (FIRST_VM_ID..)
+            .find(|vmid| !self.vms.contains_key(vmid))
+            .expect("vm ids should not be exhausted")
+    }
+}
+
+/// A route of the Proxmox API served by the emulator.
+enum Route {
+    Version,
+    ClusterResources(String),
+    ClusterNextId,
+    TaskStatus {
+        upid: String,
+    },
+    StorageContentList {
+        node: String,
+        storage: String,
+    },
+    StorageContentDelete {
+        storage: String,
+        volume: String,
+    },
+    Vzdump {
+        node: String,
+    },
+    BackupJobList,
+    BackupJobDelete {
+        id: String,
+    },
+    VmCreate {
+        node: String,
+    },
+    VmList {
+        node: String,
+    },
+    Vm {
+        node: String,
+        vmid: u32,
+        action: VmAction,
+    },
+}
+
+enum VmAction {
+    Delete,
+    Clone,
+    Migrate,
+    ConfigRead,
+    ConfigUpdate,
+    PendingRead,
+    Resize,
+    StatusRead,
+    StatusChange(String),
+    AgentNetworkInterfaces,
+    AgentExec,
+    AgentExecStatus,
+    AgentFileWrite,
+    AgentSetUserPassword,
+}
+
+impl Route {
+    fn parse(request: &mockito::Request) -> Option<Route> {
+        let path = request.path().strip_prefix("/api2/json/")?;
+        let segments = path.split('/').map(percent_decode).collect::<Vec<_>>();
+        let segments = segments.iter().map(String::as_str).collect::<Vec<_>>();
+        let query = query(request.path_and_query());
+
+        let route = match (request.method(), segments.as_slice()) {
+            ("GET", ["version"]) => Route::Version,
+            ("GET", ["cluster", "resources"]) => {
+                Route::ClusterResources(query.get("type").cloned().unwrap_or_default())
+            }
+            ("GET", ["cluster", "nextid"]) => Route::ClusterNextId,
+            ("GET", ["cluster", "backup"]) => Route::BackupJobList,
+            ("DELETE", ["cluster", "backup", id]) => Route::BackupJobDelete {
+                id: (*id).to_owned(),
+            },
+            ("GET", ["nodes", _, "tasks", upid, "status"]) => Route::TaskStatus {
+                upid: (*upid).to_owned(),
+            },
+            ("GET", ["nodes", node, "storage", storage, "content"]) => Route::StorageContentList {
+                node: (*node).to_owned(),
+                storage: (*storage).to_owned(),
+            },
+            ("DELETE", ["nodes", _, "storage", storage, "content", volume]) => {
+                Route::StorageContentDelete {
+                    storage: (*storage).to_owned(),
+                    volume: (*volume).to_owned(),
+                }
+            }
+            ("POST", ["nodes", node, "vzdump"]) => Route::Vzdump {
+                node: (*node).to_owned(),
+            },
+            ("POST", ["nodes", node, "qemu"]) => Route::VmCreate {
+                node: (*node).to_owned(),
+            },
+            ("GET", ["nodes", node, "qemu"]) => Route::VmList {
+                node: (*node).to_owned(),
+            },
+            (method, ["nodes", node, "qemu", vmid, rest @ ..]) => {
+                let action = match (method, rest) {
+                    ("DELETE", []) => VmAction::Delete,
+                    ("POST", ["clone"]) => VmAction::Clone,
+                    ("POST", ["migrate"]) => VmAction::Migrate,
+                    ("GET", ["config"]) => VmAction::ConfigRead,
+                    ("POST" | "PUT", ["config"]) => VmAction::ConfigUpdate,
+                    ("GET", ["pending"]) => VmAction::PendingRead,
+                    ("PUT", ["resize"]) => VmAction::Resize,
+                    ("GET", ["status", "current"]) => VmAction::StatusRead,
+                    ("POST", ["status", action]) => VmAction::StatusChange((*action).to_owned()),
+                    ("GET", ["agent", "network-get-interfaces"]) => {
+                        VmAction::AgentNetworkInterfaces
+                    }
+                    ("POST", ["agent", "exec"]) => VmAction::AgentExec,
+                    ("GET", ["agent", "exec-status"]) => VmAction::AgentExecStatus,
+                    ("POST", ["agent", "file-write"]) => VmAction::AgentFileWrite,
+                    ("POST", ["agent", "set-user-password"]) => VmAction::AgentSetUserPassword,
+                    _ => return None,
+                };
+
+                Route::Vm {
+                    node: (*node).to_owned(),
+                    vmid: vmid.parse().ok()?,
+                    action,
+                }
+            }
+            _ => return None,
+        };
+
+        Some(route)
+    }
+}
+
+/// A stateful emulator of the Proxmox VE API, mounted on a mock server with
+/// [`WithFakeProxmox`].
+///
+/// Clones share the same cluster, so the emulator can be inspected and
+/// reconfigured after it was mounted.
+#[derive(Clone, Default)]
+pub struct FakeProxmox {
+    state: Arc<Mutex<State>>,
+}
+
+impl FakeProxmox {
+    /// Creates an empty cluster, without nodes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a node with a 1 TiB image storage.
+    pub fn with_node(self, name: &str, cores: u32, memory_bytes: u64) -> Self {
+        self.state().nodes.push(FakeNode {
+            name: name.to_owned(),
+            cores,
+            memory_bytes,
+            disk_bytes: 1024 * GIB,
+        });
+        self
+    }
+
+    /// Adds a VM to a node.
+    pub fn with_vm(self, node: &str, vmid: u32, name: &str, status: Status) -> Self {
+        let vm = FakeVm {
+            vmid,
+            node: node.to_owned(),
+            name: name.to_owned(),
+            status,
+            lock: None,
+            config: BTreeMap::from([
+                ("agent".to_owned(), "enabled=1".to_owned()),
+                ("cores".to_owned(), "1".to_owned()),
+                ("ipconfig0".to_owned(), "ip=dhcp".to_owned()),
+                ("memory".to_owned(), "1024".to_owned()),
+                ("name".to_owned(), name.to_owned()),
+                (
+                    "net0".to_owned(),
+                    "virtio=BC:24:11:20:4E:3F,bridge=vmbr0,firewall=1".to_owned(),
+                ),
+                (
+                    "scsi0".to_owned(),
+                    format!("{}:vm-{}-disk-0,size=10G", IMAGE_STORAGE, vmid),
+                ),
+            ]),
+            pending: BTreeMap::new(),
+            ip_address: None,
+        };
+        let ip_address = vm
+            .is_running()
+            .then(|| vm.static_address().unwrap_or(dhcp_address(vmid)));
+        self.state().vms.insert(vmid, FakeVm { ip_address, ..vm });
+        self
+    }
+
+    /// Adds a backup of a VM to the backup storage of a node, with notes.
+    pub fn with_backup(self, node: &str, vmid: u32, notes: &str) -> Self {
+        let state = self.state();
+        let mut backups = state.backups();
+        let volid = format!(
+            "{}:backup/vm/{}/2025-03-08T18:00:{:02}Z",
+            BACKUP_STORAGE,
+            vmid,
+            backups.len()
+        );
+        let config = state
+            .vms
+            .get(&vmid)
+            .map(|vm| vm.config.clone())
+            .unwrap_or_default();
+        backups.push(FakeVolume {
+            node: node.to_owned(),
+            storage: BACKUP_STORAGE.to_owned(),
+            volid,
+            content: "backup".to_owned(),
+            size: GIB,
+            vmid: Some(vmid),
+            notes: Some(notes.to_owned()),
+            config,
+        });
+        drop(backups);
+        drop(state);
+        self
+    }
+
+    /// Shares the backup storage of another cluster, so that the VMs backed
+    /// up by one can be restored by the other.
+    pub fn with_backup_storage_of(self, other: &FakeProxmox) -> Self {
+        let backups = other.state().backups.clone();
+        self.state().backups = backups;
+        self
+    }
+
+    /// Adds a job scheduling the backups of a VM.
+    pub fn with_backup_job(self, id: &str, vmid: u32) -> Self {
+        self.state().backup_jobs.push(FakeBackupJob {
+            id: id.to_owned(),
+            vmid,
+        });
+        self
+    }
+
+    /// Sets the time tasks run for before they stop.
+    pub fn with_task_latency(self, latency: Duration) -> Self {
+        self.state().latency = latency;
+        self
+    }
+
+    /// Makes the tasks of a type (e.g. `qmstart`) fail with a message.
+    pub fn with_task_failure(self, task_type: &str, message: &str) -> Self {
+        self.fail_tasks(task_type, message);
+        self
+    }
+
+    /// Makes the tasks of a type started from now on fail with a message.
+    pub fn fail_tasks(&self, task_type: &str, message: &str) {
+        self.state()
+            .failures
+            .insert(task_type.to_owned(), message.to_owned());
+    }
+
+    /// Lets the tasks of a type succeed again.
+    pub fn heal_tasks(&self, task_type: &str) {
+        self.state().failures.remove(task_type);
+    }
+
+    /// Gets a VM of the cluster.
+    pub fn vm(&self, vmid: u32) -> Option<FakeVm> {
+        let mut state = self.state();
+        state.settle();
+        state.vms.get(&vmid).cloned()
+    }
+
+    /// Lists the VMs of the cluster.
+    pub fn vms(&self) -> Vec<FakeVm> {
+        let mut state = self.state();
+        state.settle();
+        state.vms.values().cloned().collect()
+    }
+
+    /// Lists the volume ids stored on a storage.
+    pub fn volumes(&self, storage: &str) -> Vec<String> {
+        let mut state = self.state();
+        state.settle();
+        let backups = state.backups();
+        state
+            .volumes
+            .iter()
+            .chain(backups.iter())
+            .filter(|volume| volume.storage == storage)
+            .map(|volume| volume.volid.clone())
+            .collect()
+    }
+
+    /// Lists the ids of the scheduled backup jobs.
+    pub fn backup_jobs(&self) -> Vec<String> {
+        self.state()
+            .backup_jobs
+            .iter()
+            .map(|job| job.id.clone())
+            .collect()
+    }
+
+    /// Lists the types of the tasks started so far, oldest first.
+    pub fn task_types(&self) -> Vec<String> {
+        self.state()
+            .tasks
+            .iter()
+            .map(|task| task.task_type.clone())
+            .collect()
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().expect("fake proxmox state poisoned")
+    }
+
+    /// Gets the error the API responds to a request with, if any.
+    fn error(&self, request: &mockito::Request) -> Option<String> {
+        let route = Route::parse(request)?;
+        let mut state = self.state();
+        state.settle();
+
+        match route {
+            Route::TaskStatus { upid } => (!state.tasks.iter().any(|task| task.upid == upid))
+                .then(|| format!("unable to parse worker upid '{}'\n", upid)),
+            Route::Vzdump { .. } => {
+                let vmid = body(request).get("vmid").cloned().unwrap_or_default();
+                (!vmid
+                    .parse::<u32>()
+                    .is_ok_and(|vmid| state.vms.contains_key(&vmid)))
+                .then(|| format!("unable to find VM {}\n", vmid))
+            }
+            Route::VmCreate { .. } => {
+                let archive = body(request).get("archive").cloned()?;
+                (!state.backups().iter().any(|backup| backup.volid == archive))
+                    .then(|| format!("volume '{}' does not exist\n", archive))
+            }
+            Route::Vm { node, vmid, action } => {
+                let Some(vm) = state.vms.get(&vmid) else {
+                    return Some(format!(
+                        "Configuration file 'nodes/{}/qemu-server/{}.conf' does not exist\n",
+                        node, vmid
+                    ));
+                };
+                let running = vm.is_running();
+
+                match action {
+                    VmAction::Delete if running => {
+                        Some(format!("VM {} is running - destroy failed\n", vmid))
+                    }
+                    VmAction::Migrate => {
+                        let body = body(request);
+                        let target = body.get("target").map(String::as_str).unwrap_or_default();
+                        if target == vm.node {
+                            Some("target is local node.\n".to_owned())
+                        } else if !state.nodes.iter().any(|node| node.name == target) {
+                            Some(format!("no such cluster node '{}'\n", target))
+                        } else if running && body.get("online").is_none_or(|online| online != "1") {
+                            Some("can't migrate running VM without --online\n".to_owned())
+                        } else {
+                            None
+                        }
+                    }
+                    VmAction::StatusChange(action) => match action.as_str() {
+                        "start" if running => Some(format!("VM {} already running\n", vmid)),
+                        "resume" if matches!(vm.status, Status::Paused | Status::Suspended) => None,
+                        "start" => None,
+                        _ if !running => Some(format!("VM {} is not running\n", vmid)),
+                        _ => None,
+                    },
+                    VmAction::AgentNetworkInterfaces
+                    | VmAction::AgentExec
+                    | VmAction::AgentExecStatus
+                    | VmAction::AgentFileWrite
+                    | VmAction::AgentSetUserPassword => {
+                        if !vm.has_agent() {
+                            Some("No QEMU guest agent configured\n".to_owned())
+                        } else if !matches!(vm.status, Status::Running) {
+                            Some(format!("VM {} is not running\n", vmid))
+                        } else {
+                            None
+                        }
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Serves a request, updating the cluster.
+    fn respond(&self, request: &mockito::Request) -> Value {
+        let Some(route) = Route::parse(request) else {
+            return Value::Null;
+        };
+        let body = body(request);
+
+        let mut state = self.state();
+        state.settle();
+
+        match route {
+            Route::Version => json!({"version": "8.2.4", "release": "8.2", "repoid": "faa83925"}),
+            Route::ClusterResources(resource_type) => cluster_resources(&state, &resource_type),
+            Route::ClusterNextId => json!(state.next_id().to_string()),
+            Route::TaskStatus { upid } => {
+                let task = state
+                    .tasks
+                    .iter()
+                    .find(|task| task.upid == upid)
+                    .expect("the task should exist");
+                let mut status = json!({
+                    "upid": task.upid,
+                    "node": task.node,
+                    "pid": task.pid,
+                    "pstart": task.pid,
+                    "starttime": task.starttime,
+                    "type": task.task_type,
+                    "id": task.id,
+                    "user": "root@pam",
+                    "status": if task.exit_status.is_some() { "stopped" } else { "running" },
+                });
+                if let Some(exit_status) = &task.exit_status {
+                    status["exitstatus"] = json!(exit_status);
+                }
+                status
+            }
+            Route::StorageContentList { node, storage } => {
+                let content = query(request.path_and_query()).remove("content");
+                let backups = state.backups();
+                let volumes = state
+                    .volumes
+                    .iter()
+                    .chain(backups.iter())
+                    .filter(|volume| volume.node == node && volume.storage == storage)
+                    .filter(|volume| content.as_ref().is_none_or(|c| *c == volume.content))
+                    .map(|volume| {
+                        json!({
+                            "volid": volume.volid,
+                            "content": volume.content,
+                            "format": volume.volid.rsplit('.').next().unwrap_or("raw"),
+                            "size": volume.size,
+                            "vmid": volume.vmid,
+                            "notes": volume.notes,
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                json!(volumes)
+            }
+            Route::StorageContentDelete { storage, volume } => {
+                let volid = if volume.contains(':') {
+                    volume
+                } else {
+                    format!("{}:{}", storage, volume)
+                };
+                state.volumes.retain(|candidate| candidate.volid != volid);
+                state.backups().retain(|candidate| candidate.volid != volid);
+                Value::Null
+            }
+            Route::Vzdump { node } => {
+                let vmid = body
+                    .get("vmid")
+                    .and_then(|vmid| vmid.parse::<u32>().ok())
+                    .expect("the vm should exist");
+                let storage = body
+                    .get("storage")
+                    .cloned()
+                    .unwrap_or_else(|| BACKUP_STORAGE.to_owned());
+                let notes = body.get("notes-template").cloned();
+                let task_node = node.clone();
+                json!(state.spawn(
+                    &task_node,
+                    "vzdump",
+                    &vmid.to_string(),
+                    move |state| {
+                        let Some(vm) = state.vms.get(&vmid) else {
+                            return;
+                        };
+                        let (size, config) = (vm.disk_bytes(), vm.config.clone());
+                        let mut backups = state.backups();
+                        let volid = format!(
+                            "{}:backup/vm/{}/2025-03-08T18:00:{:02}Z",
+                            storage,
+                            vmid,
+                            backups.len()
+                        );
+                        backups.push(FakeVolume {
+                            node,
+                            storage,
+                            volid,
+                            content: "backup".to_owned(),
+                            size,
+                            vmid: Some(vmid),
+                            notes,
+                            config,
+                        });
+                    },
+                    |_| {},
+                ))
+            }
+            Route::BackupJobList => {
+                let jobs = state
+                    .backup_jobs
+                    .iter()
+                    .map(|job| {
+                        json!({
+                            "id": job.id,
+                            "type": "vzdump",
+                            "schedule": "daily",
+                            "vmid": job.vmid.to_string(),
+                            "storage": BACKUP_STORAGE,
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                json!(jobs)
+            }
+            Route::BackupJobDelete { id } => {
+                state.backup_jobs.retain(|job| job.id != id);
+                Value::Null
+            }
+            Route::VmCreate { node } if body.contains_key("archive") => {
+                restore_vm(&mut state, &node, body)
+            }
+            Route::VmCreate { node } => {
+                let vmid = body
+                    .get("vmid")
+                    .and_then(|vmid| vmid.parse::<u32>().ok())
+                    .unwrap_or_else(|| state.next_id());
+                let mut config = body.clone();
+                config.remove("vmid");
+                // The image is imported as the first disk of the VM
+                if let Some(disk) = config.get_mut("scsi0") {
+                    let storage = disk.split(':').next().unwrap_or(IMAGE_STORAGE);
+                    *disk = format!("{}:vm-{}-disk-0,discard=on,ssd=1,size=2G", storage, vmid);
+                }
+                state.vms.insert(
+                    vmid,
+                    FakeVm {
+                        vmid,
+                        node: node.clone(),
+                        name: body.get("name").cloned().unwrap_or_default(),
+                        status: Status::Stopped,
+                        lock: Some("create".to_owned()),
+                        config,
+                        pending: BTreeMap::new(),
+                        ip_address: None,
+                    },
+                );
+                json!(state.spawn(
+                    &node,
+                    "qmcreate",
+                    &vmid.to_string(),
+                    move |state| unlock(state, vmid),
+                    move |state| {
+                        state.vms.remove(&vmid);
+                    },
+                ))
+            }
+            Route::VmList { node } => {
+                let vms = state
+                    .vms
+                    .values()
+                    .filter(|vm| vm.node == node)
+                    .map(|vm| {
+                        json!({
+                            "vmid": vm.vmid,
+                            "name": vm.name,
+                            "status": vm.resource_status().0,
+                            "cpus": vm.cores(),
+                            "maxmem": vm.memory_bytes(),
+                            "maxdisk": vm.disk_bytes(),
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                json!(vms)
+            }
+            Route::Vm { node, vmid, action } => respond_vm(&mut state, &node, vmid, action, body),
+        }
+    }
+}
+
+/// Serves a request on a VM, known to exist.
+fn respond_vm(
+    state: &mut State,
+    node: &str,
+    vmid: u32,
+    action: VmAction,
+    body: BTreeMap<String, String>,
+) -> Value {
+    let id = vmid.to_string();
+    let vm = state.vms.get(&vmid).cloned().expect("the vm should exist");
+
+    match action {
+        VmAction::Delete => {
+            state.vms.get_mut(&vmid).expect("the vm should exist").lock = Some("destroyed".into());
+            json!(state.spawn(
+                node,
+                "qmdestroy",
+                &id,
+                move |state| {
+                    state.vms.remove(&vmid);
+                },
+                move |state| unlock(state, vmid),
+            ))
+        }
+        VmAction::Clone => {
+            let newid = body
+                .get("newid")
+                .and_then(|newid| newid.parse::<u32>().ok())
+                .unwrap_or_else(|| state.next_id());
+            let name = body
+                .get("name")
+                .cloned()
+                .unwrap_or_else(|| format!("Copy-of-VM-{}", vm.name));
+            let mut config = vm.config.clone();
+            config.insert("name".to_owned(), name.clone());
+            for disk in config.values_mut() {
+                *disk = disk.replace(&format!("vm-{}-", vmid), &format!("vm-{}-", newid));
+            }
+            state.vms.insert(
+                newid,
+                FakeVm {
+                    vmid: newid,
+                    node: node.to_owned(),
+                    name,
+                    status: Status::Stopped,
+                    lock: Some("clone".to_owned()),
+                    config,
+                    pending: BTreeMap::new(),
+                    ip_address: None,
+                },
+            );
+            json!(state.spawn(
+                node,
+                "qmclone",
+                &id,
+                move |state| unlock(state, newid),
+                move |state| {
+                    state.vms.remove(&newid);
+                },
+            ))
+        }
+        VmAction::Migrate => {
+            let target = body.get("target").cloned().unwrap_or_default();
+            state.vms.get_mut(&vmid).expect("the vm should exist").lock = Some("migrate".into());
+            json!(state.spawn(
+                node,
+                "qmigrate",
+                &id,
+                move |state| {
+                    if let Some(vm) = state.vms.get_mut(&vmid) {
+                        vm.node = target;
+                        vm.lock = None;
+                    }
+                },
+                move |state| unlock(state, vmid),
+            ))
+        }
+        VmAction::ConfigRead => {
+            let mut config = vm
+                .config
+                .iter()
+                .map(|(key, value)| (key.clone(), json!(value)))
+                .collect::<Map<_, _>>();
+            config.insert("digest".to_owned(), json!(format!("{:040x}", vmid)));
+            if let Some(lock) = &vm.lock {
+                config.insert("lock".to_owned(), json!(lock));
+            }
+            Value::Object(config)
+        }
+        VmAction::ConfigUpdate => {
+            let vm = state.vms.get_mut(&vmid).expect("the vm should exist");
+            for key in body
+                .get("delete")
+                .into_iter()
+                .flat_map(|keys| keys.split(','))
+            {
+                vm.config.remove(key.trim());
+            }
+            // Like without hotplug, the CPU and memory of a running VM only
+            // change once it restarts
+            let running = vm.is_running();
+            for (key, value) in body.iter().filter(|(key, _)| !is_update_option(key)) {
+                if running && matches!(key.as_str(), "cores" | "memory") {
+                    vm.pending.insert(key.clone(), value.clone());
+                } else {
+                    vm.config.insert(key.clone(), value.clone());
+                }
+            }
+            if let Some(name) = body.get("name") {
+                vm.name = name.clone();
+            }
+            json!(state.spawn(node, "qmconfig", &id, |_| {}, |_| {}))
+        }
+        VmAction::PendingRead => {
+            let keys = vm
+                .config
+                .keys()
+                .chain(vm.pending.keys())
+                .collect::<BTreeSet<_>>();
+            Value::Array(
+                keys.into_iter()
+                    .map(|key| {
+                        let mut config = json!({ "key": key });
+                        if let Some(value) = vm.config.get(key) {
+                            config["value"] = json!(value);
+                        }
+                        if let Some(pending) = vm.pending.get(key) {
+                            config["pending"] = json!(pending);
+                        }
+                        config
+                    })
+                    .collect(),
+            )
+        }
+        VmAction::Resize => {
+            let disk = body.get("disk").cloned().unwrap_or_default();
+            let size = body.get("size").cloned().unwrap_or_default();
+            json!(state.spawn(
+                node,
+                "resize",
+                &id,
+                move |state| {
+                    if let Some(config) = state
+                        .vms
+                        .get_mut(&vmid)
+                        .and_then(|vm| vm.config.get_mut(&disk))
+                    {
+                        *config = config
+                            .split(',')
+                            .filter(|option| !option.starts_with("size="))
+                            .chain([format!("size={}", size).as_str()])
+                            .collect::<Vec<_>>()
+                            .join(",");
+                    }
+                },
+                |_| {},
+            ))
+        }
+        VmAction::StatusRead => {
+            let (status, qmpstatus) = vm.resource_status();
+            json!({
+                "vmid": vmid,
+                "name": vm.name,
+                "status": status,
+                "qmpstatus": qmpstatus,
+                "lock": vm.lock,
+                "agent": u8::from(vm.has_agent()),
+                "cpus": vm.cores(),
+                "maxmem": vm.memory_bytes(),
+                "maxdisk": vm.disk_bytes(),
+            })
+        }
+        VmAction::StatusChange(action) => {
+            let (task_type, status, lock) = match action.as_str() {
+                "start" => ("qmstart", Status::Running, None),
+                "stop" => ("qmstop", Status::Stopped, None),
+                "shutdown" => ("qmshutdown", Status::Stopped, None),
+                "reboot" => ("qmreboot", Status::Running, None),
+                "reset" => ("qmreset", Status::Running, None),
+                "resume" => ("qmresume", Status::Running, None),
+                "suspend" if body.get("todisk").is_some_and(|todisk| todisk == "1") => {
+                    ("qmsuspend", Status::Suspended, Some("suspended".to_owned()))
+                }
+                "suspend" => ("qmpause", Status::Paused, None),
+                _ => return Value::Null,
+            };
+            let starts = action == "start";
+            json!(state.spawn(
+                node,
+                task_type,
+                &id,
+                move |state| {
+                    if let Some(vm) = state.vms.get_mut(&vmid) {
+                        if starts {
+                            let pending = std::mem::take(&mut vm.pending);
+                            vm.config.extend(pending);
+                        }
+                        vm.status = status;
+                        vm.lock = lock;
+                        vm.ip_address = vm
+                            .is_running()
+                            .then(|| vm.static_address().unwrap_or(dhcp_address(vmid)));
+                    }
+                },
+                |_| {},
+            ))
+        }
+        VmAction::AgentNetworkInterfaces => {
+            let mut interfaces = vec![json!({
+                "name": "lo",
+                "hardware-address": "00:00:00:00:00:00",
+                "ip-addresses": [{"ip-address": "127.0.0.1", "ip-address-type": "ipv4", "prefix": 8}],
+            })];
+            if let Some(ip_address) = vm.ip_address {
+                interfaces.push(json!({
+                    "name": "eth0",
+                    "hardware-address": "bc:24:11:20:4e:3f",
+                    "ip-addresses": [{"ip-address": ip_address.to_string(), "ip-address-type": "ipv4", "prefix": 24}],
+                }));
+            }
+            json!({ "result": interfaces })
+        }
+        VmAction::AgentExec => {
+            state.next_pid += 1;
+            json!({ "pid": state.next_pid })
+        }
+        VmAction::AgentExecStatus => json!({ "exited": 1, "exitcode": 0 }),
+        VmAction::AgentFileWrite => Value::Null,
+        VmAction::AgentSetUserPassword => json!({ "result": {} }),
+    }
+}
+
+/// Restores a backup into a new VM, known to exist on the backup storage.
+///
+/// The disks are restored on the requested storage, the ones they were backed
+/// up from when unset.
+fn restore_vm(state: &mut State, node: &str, body: BTreeMap<String, String>) -> Value {
+    let vmid = body
+        .get("vmid")
+        .and_then(|vmid| vmid.parse::<u32>().ok())
+        .unwrap_or_else(|| state.next_id());
+    let archive = body.get("archive").cloned().unwrap_or_default();
+    let (source, mut config) = state
+        .backups()
+        .iter()
+        .find(|backup| backup.volid == archive)
+        .map(|backup| (backup.vmid.unwrap_or_default(), backup.config.clone()))
+        .expect("the backup should exist");
+    for value in config.values_mut() {
+        *value = value.replace(&format!("vm-{}-", source), &format!("vm-{}-", vmid));
+        if let Some(storage) = body.get("storage")
+            && let Some((_, volume)) = value
+                .split_once(':')
+                .filter(|(_, volume)| volume.starts_with(&format!("vm-{}-", vmid)))
+        {
+            *value = format!("{}:{}", storage, volume);
+        }
+    }
+    state.vms.insert(
+        vmid,
+        FakeVm {
+            vmid,
+            node: node.to_owned(),
+            name: config.get("name").cloned().unwrap_or_default(),
+            status: Status::Stopped,
+            lock: Some("create".to_owned()),
+            config,
+            pending: BTreeMap::new(),
+            ip_address: None,
+        },
+    );
+    json!(state.spawn(
+        node,
+        "qmrestore",
+        &vmid.to_string(),
+        move |state| unlock(state, vmid),
+        move |state| {
+            state.vms.remove(&vmid);
+        },
+    ))
+}
+
+/// Lists the resources of a type of the cluster.
+fn cluster_resources(state: &State, resource_type: &str) -> Value {
+    let nodes = state.nodes.iter().map(|node| {
+        let used_memory = state
+            .vms
+            .values()
+            .filter(|vm| vm.node == node.name && vm.is_running())
+            .map(FakeVm::memory_bytes)
+            .sum::<u64>();
+        json!({
+            "id": format!("node/{}", node.name),
+            "type": "node",
+            "node": node.name,
+            "status": "online",
+            "cpu": 0.0,
+            "maxcpu": node.cores,
+            "mem": used_memory,
+            "maxmem": node.memory_bytes,
+        })
+    });
+    let storages = state.nodes.iter().flat_map(|node| {
+        let used_disk = state
+            .vms
+            .values()
+            .filter(|vm| vm.node == node.name)
+            .map(FakeVm::disk_bytes)
+            .sum::<u64>();
+        [
+            json!({
+                "id": format!("storage/{}/{}", node.name, IMAGE_STORAGE),
+                "type": "storage",
+                "node": node.name,
+                "storage": IMAGE_STORAGE,
+                "status": "available",
+                "shared": 0,
+                "disk": used_disk,
+                "maxdisk": node.disk_bytes,
+            }),
+            json!({
+                "id": format!("storage/{}/{}", node.name, SNIPPETS_STORAGE),
+                "type": "storage",
+                "node": node.name,
+                "storage": SNIPPETS_STORAGE,
+                "status": "available",
+                "shared": 1,
+                "disk": 0,
+                "maxdisk": 100 * GIB,
+            }),
+        ]
+    });
+    let vms = state.vms.values().map(|vm| {
+        let (status, _) = vm.resource_status();
+        json!({
+            "id": format!("qemu/{}", vm.vmid),
+            "type": "qemu",
+            "vmid": vm.vmid,
+            "node": vm.node,
+            "name": vm.name,
+            "status": status,
+            "lock": vm.lock,
+            "cpu": 0.0,
+            "maxcpu": vm.cores(),
+            "mem": 0,
+            "maxmem": vm.memory_bytes(),
+            "disk": 0,
+            "maxdisk": vm.disk_bytes(),
+        })
+    });
+
+    let resources = match resource_type {
+        "node" => nodes.collect::<Vec<_>>(),
+        "storage" => storages.collect(),
+        "vm" => vms.collect(),
+        _ => nodes.chain(storages).chain(vms).collect(),
+    };
+    json!(resources)
+}
+
+/// Releases the lock held on a VM.
+fn unlock(state: &mut State, vmid: u32) {
+    if let Some(vm) = state.vms.get_mut(&vmid) {
+        vm.lock = None;
+    }
+}
+
+/// Whether a parameter of a configuration update changes how it is applied
+/// rather than the configuration itself.
+fn is_update_option(key: &str) -> bool {
+    matches!(
+        key,
+        "delete" | "digest" | "revert" | "skiplock" | "background_delay"
+    )
+}
+
+/// Gets the address the DHCP server of the cluster leases to a VM.
+fn dhcp_address(vmid: u32) -> Ipv4Addr {
+    Ipv4Addr::new(10, 0, (vmid / 250) as u8, (vmid % 250) as u8 + 2)
+}
+
+/// Parses the JSON body of a request to the parameters it sets.
+fn body(request: &mockito::Request) -> BTreeMap<String, String> {
+    request
+        .body()
+        .ok()
+        .and_then(|body| serde_json::from_slice::<Map<String, Value>>(body).ok())
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(key, value)| (key, config_value(value)))
+        .collect()
+}
+
+/// Formats a JSON value as the string Proxmox stores in configurations.
+fn config_value(value: Value) -> String {
+    match value {
+        Value::String(value) => value,
+        Value::Bool(value) => u8::from(value).to_string(),
+        value => value.to_string(),
+    }
+}
+
+/// Parses a disk size (e.g. `20G`) to bytes.
+fn parse_size(size: &str) -> Option<u64> {
+    let (value, unit) = size.split_at(size.find(|c: char| !c.is_ascii_digit())?);
+    let multiplier = match unit {
+        "K" => 1024,
+        "M" => 1024 * 1024,
+        "G" => GIB,
+        "T" => 1024 * GIB,
+        _ => return None,
+    };
+    Some(value.parse::<u64>().ok()? * multiplier)
+}
+
+/// Parses the query string of a request.
+fn query(path_and_query: &str) -> HashMap<String, String> {
+    path_and_query
+        .split_once('?')
+        .map(|(_, query)| query)
+        .unwrap_or_default()
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(key, value)| (percent_decode(key), percent_decode(value)))
+        .collect()
+}
+
+/// Decodes a percent-encoded component of a URL.
+fn percent_decode(component: &str) -> String {
+    let bytes = component.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let escaped = (bytes[index] == b'%')
+            .then(|| component.get(index + 1..index + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+pub trait WithFakeProxmox {
+    fn with_fake_proxmox(self, proxmox: &FakeProxmox) -> Self;
+}
+
+impl WithFakeProxmox for MockServer {
+    /// Serves the Proxmox API from the emulated cluster. Requests the
+    /// emulator does not know are left to the other mocks.
+    fn with_fake_proxmox(mut self, proxmox: &FakeProxmox) -> Self {
+        for method in ["DELETE", "GET", "POST", "PUT"] {
+            let (matcher, responder) = (proxmox.clone(), proxmox.clone());
+            let mock = self
+                .server
+                .mock(method, mockito::Matcher::Regex(r"^/api2/json/".to_string()))
+                .match_request(move |request| Route::parse(request).is_some())
+                .expect_at_least(0)
+                .with_body_from_request(move |request| {
+                    json!({ "data": responder.respond(request) })
+                        .to_string()
+                        .into_bytes()
+                })
+                .create();
+            self.mocks.push(mock);
+
+            // Among the mocks matching a request, mockito picks the last one
+            // once none expects more hits: registered last, the error wins
+            let responder = proxmox.clone();
+            let mock = self
+                .server
+                .mock(method, mockito::Matcher::Regex(r"^/api2/json/".to_string()))
+                .match_request(move |request| matcher.error(request).is_some())
+                .expect_at_least(0)
+                .with_status(500)
+                .with_body_from_request(move |request| {
+                    json!({ "data": null, "message": responder.error(request) })
+                        .to_string()
+                        .into_bytes()
+                })
+                .create();
+            self.mocks.push(mock);
+        }
+
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instance::{Export, InstanceCreateRequest, InstanceResizeRequest, Instances};
+    use crate::placement::PlacementRequest;
+    use crate::proxmox::api::{self, task_status_read::TaskStatus};
+    use crate::proxmox::instance::ProxmoxInstanceService;
+    use crate::proxmox::snippet::{Snippets, VolumeSnippetStorage};
+
+    /// Creates the service of the emulated cluster, storing its snippets in a
+    /// fresh directory standing for the shared volume.
+    fn service(server: &MockServer) -> ProxmoxInstanceService {
+        let path = std::env::temp_dir().join(format!("snippets-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(path.join("snippets")).unwrap();
+        ProxmoxInstanceService {
+            api_url: server.url(),
+            client: reqwest::Client::new(),
+            authorization: String::new(),
+            storage: IMAGE_STORAGE.to_owned(),
+            image_storage: IMAGE_STORAGE.to_owned(),
+            backup_storage: BACKUP_STORAGE.to_owned(),
+            snippets: Snippets::Volume(VolumeSnippetStorage {
+                path: path.to_string_lossy().into_owned(),
+                storage: SNIPPETS_STORAGE.to_owned(),
+            }),
+        }
+    }
+
+    /// Lists the snippets stored by the service.
+    fn snippets(service: &ProxmoxInstanceService) -> Vec<std::fs::DirEntry> {
+        let Snippets::Volume(storage) = &service.snippets else {
+            unreachable!("the emulated cluster stores snippets on a volume")
+        };
+        std::fs::read_dir(format!("{}/snippets", storage.path))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    fn create_request() -> InstanceCreateRequest {
+        InstanceCreateRequest {
+            id: "100".to_owned(),
+            cores: 2,
+            disk_bytes: 20 * GIB,
+            disk_image: "debian-12-genericcloud-amd64-20241201-1948.qcow2".to_owned(),
+            memory_bytes: 2 * GIB,
+            name: "web".to_owned(),
+            snippet: "#cloud-config\n".to_owned(),
+            anti_affinity: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_an_instance_goes_through_its_lifecycle() {
+        // Arrange a cluster of a single node
+        let proxmox = FakeProxmox::new().with_node("pve-node1", 16, 64 * GIB);
+        let server = MockServer::new().await.with_fake_proxmox(&proxmox);
+        let service = service(&server);
+
+        // Act the creation of an instance
+        service.create(create_request()).await.unwrap();
+
+        // Assert the VM was created stopped, with its disk resized
+        let vm = proxmox.vm(100).expect("the vm should be created");
+        assert_eq!(vm.node, "pve-node1");
+        assert!(matches!(vm.status, Status::Stopped));
+        assert_eq!(vm.lock, None);
+        assert!(vm.config["scsi0"].ends_with("size=20G"));
+        assert_eq!(snippets(&service).len(), 1);
+        assert_eq!(service.next_id().await.unwrap(), "101");
+
+        // Act the start of the instance
+        service.start("100").await.unwrap();
+
+        // Assert the instance runs, and reports its address through its agent
+        assert!(matches!(
+            service.status("100").await.unwrap(),
+            Status::Running
+        ));
+        assert_eq!(
+            service.get_ip_address("100").await.unwrap(),
+            Some(Ipv4Addr::new(10, 0, 0, 102))
+        );
+
+        // Act the deletion of the running instance, then of the stopped one
+        let running = service.delete("100").await;
+        service.stop("100").await.unwrap();
+        service.delete("100").await.unwrap();
+
+        // Assert the VM and its snippet are gone
+        assert!(running.is_err());
+        assert!(proxmox.vms().is_empty());
+        assert!(snippets(&service).is_empty());
+        assert!(matches!(
+            service.status("100").await,
+            Err(crate::Error::DistantInstanceNotFound(_))
+        ));
+        assert_eq!(
+            proxmox.task_types(),
+            ["qmcreate", "resize", "qmstart", "qmstop", "qmdestroy"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_an_instance_is_kept_apart_from_its_anti_affinity() {
+        // Arrange a cluster of two nodes, the emptier one holding a replica
+        let proxmox = FakeProxmox::new()
+            .with_node("pve-node1", 16, 64 * GIB)
+            .with_node("pve-node2", 16, 32 * GIB)
+            .with_vm("pve-node1", 100, "web-1", Status::Running);
+        let server = MockServer::new().await.with_fake_proxmox(&proxmox);
+        let service = service(&server);
+
+        // Act the creation of another replica
+        service
+            .create(InstanceCreateRequest {
+                id: "101".to_owned(),
+                anti_affinity: vec!["100".to_owned()],
+                ..create_request()
+            })
+            .await
+            .unwrap();
+
+        // Assert it was placed on the other node
+        assert_eq!(proxmox.vm(101).unwrap().node, "pve-node2");
+    }
+
+    #[tokio::test]
+    async fn test_a_failed_task_is_reported_and_rolled_back() {
+        // Arrange a cluster failing to create VMs
+        let proxmox = FakeProxmox::new()
+            .with_node("pve-node1", 16, 64 * GIB)
+            .with_task_failure("qmcreate", "unable to create VM 100 - no space left");
+        let server = MockServer::new().await.with_fake_proxmox(&proxmox);
+        let service = service(&server);
+
+        // Act the creation of an instance
+        let result = service.create(create_request()).await;
+
+        // Assert nothing was left behind
+        assert!(result.is_err());
+        assert!(proxmox.vms().is_empty());
+        assert!(snippets(&service).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_a_running_instance_is_restarted_gracefully_to_apply_its_new_shape() {
+        // Arrange a cluster holding a running VM, then another whose guest
+        // never powers off
+        let proxmox = FakeProxmox::new()
+            .with_node("pve-node1", 16, 64 * GIB)
+            .with_vm("pve-node1", 100, "web", Status::Running)
+            .with_vm("pve-node1", 101, "db", Status::Running);
+        let server = MockServer::new().await.with_fake_proxmox(&proxmox);
+        let service = service(&server);
+        let resize = InstanceResizeRequest {
+            cores: Some(4),
+            memory_bytes: Some(4 * GIB),
+        };
+
+        // Act the resize of the VMs
+        service.resize("100", resize.clone()).await.unwrap();
+        proxmox.fail_tasks("qmshutdown", "VM quit/powerdown failed");
+        service.resize("101", resize).await.unwrap();
+
+        // Assert both VMs run their new shape, the second one once stopped
+        for vmid in [100, 101] {
+            let vm = proxmox.vm(vmid).unwrap();
+            assert!(matches!(vm.status, Status::Running));
+            assert_eq!(vm.config["cores"], "4");
+            assert_eq!(vm.config["memory"], "4096");
+            assert!(vm.pending.is_empty());
+        }
+        assert_eq!(
+            proxmox.task_types(),
+            [
+                "qmconfig",
+                "qmshutdown",
+                "qmstart",
+                "qmconfig",
+                "qmshutdown",
+                "qmstop",
+                "qmstart"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_paused_instances_are_listed_as_paused() {
+        // Arrange a cluster holding a running VM and a paused one
+        let proxmox = FakeProxmox::new()
+            .with_node("pve-node1", 16, 64 * GIB)
+            .with_vm("pve-node1", 100, "web", Status::Running)
+            .with_vm("pve-node1", 101, "db", Status::Paused);
+        let server = MockServer::new().await.with_fake_proxmox(&proxmox);
+        let service = service(&server);
+
+        // Act the listing of the instances
+        let instances = service.list().await.unwrap();
+
+        // Assert the status of each one
+        let status = |id: &str| {
+            instances
+                .iter()
+                .find(|instance| instance.id == id)
+                .map(|instance| instance.status.clone())
+        };
+        assert!(matches!(status("100"), Some(Status::Running)));
+        assert!(matches!(status("101"), Some(Status::Paused)));
+    }
+
+    #[tokio::test]
+    async fn test_tasks_run_for_the_configured_latency() {
+        // Arrange a cluster holding a stopped VM, whose tasks are slow
+        let proxmox = FakeProxmox::new()
+            .with_node("pve-node1", 16, 64 * GIB)
+            .with_vm("pve-node1", 100, "web", Status::Stopped)
+            .with_task_latency(Duration::from_millis(200));
+        let server = MockServer::new().await.with_fake_proxmox(&proxmox);
+        let client = reqwest::Client::new();
+
+        // Act the start of the VM
+        let task = api::vm_status_start(&server.url(), &client, "", "pve-node1", 100)
+            .await
+            .unwrap()
+            .data;
+        let before = api::task_status_read(&server.url(), &client, "", "pve-node1", &task)
+            .await
+            .unwrap()
+            .data;
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        let after = api::task_status_read(&server.url(), &client, "", "pve-node1", &task)
+            .await
+            .unwrap()
+            .data;
+
+        // Assert the VM only started once the task stopped
+        assert!(matches!(before.status, TaskStatus::Running));
+        assert!(matches!(after.status, TaskStatus::Stopped));
+        assert_eq!(after.exit_status.as_deref(), Some("OK"));
+        assert!(matches!(proxmox.vm(100).unwrap().status, Status::Running));
+    }
+
+    #[tokio::test]
+    async fn test_the_agent_of_stopped_vms_is_not_running() {
+        // Arrange a cluster holding a stopped VM
+        let proxmox = FakeProxmox::new()
+            .with_node("pve-node1", 16, 64 * GIB)
+            .with_vm("pve-node1", 100, "web", Status::Stopped);
+        let server = MockServer::new().await.with_fake_proxmox(&proxmox);
+        let client = reqwest::Client::new();
+
+        // Act the calls to the guest agent of the VM, and of a missing one
+        let stopped =
+            api::vm_network_interfaces(&server.url(), &client, "", "pve-node1", 100).await;
+        let missing =
+            api::vm_network_interfaces(&server.url(), &client, "", "pve-node1", 101).await;
+
+        // Assert the result
+        assert!(matches!(stopped, Err(api::Error::VMNotRunning(100))));
+        assert!(matches!(missing, Err(api::Error::VMNotFound(101))));
+    }
+
+    #[tokio::test]
+    async fn test_instances_are_migrated_off_a_drained_node() {
+        // Arrange a cluster of three nodes, the first one holding two VMs
+        let proxmox = FakeProxmox::new()
+            .with_node("pve-node1", 16, 64 * GIB)
+            .with_node("pve-node2", 16, 64 * GIB)
+            .with_node("pve-node3", 16, 64 * GIB)
+            .with_vm("pve-node1", 100, "web", Status::Running)
+            .with_vm("pve-node1", 101, "db", Status::Stopped)
+            .with_vm("pve-node2", 102, "cache", Status::Running);
+        let server = MockServer::new().await.with_fake_proxmox(&proxmox);
+        let service = service(&server);
+
+        // Act the migration of a VM to a given node, then to a missing one
+        let migration = service.migrate("102", Some("pve-node3")).await.unwrap();
+        let missing = service.migrate("102", Some("pve-node9")).await;
+
+        // Assert the VM moved
+        assert_eq!(migration.source_node, "pve-node2");
+        assert_eq!(migration.target_node, "pve-node3");
+        assert_eq!(proxmox.vm(102).unwrap().node, "pve-node3");
+        assert!(matches!(
+            missing,
+            Err(crate::Error::DistantNodeNotFound(node)) if node == "pve-node9"
+        ));
+
+        // Act the drain of the first node
+        let migrations = service.drain("pve-node1").await.unwrap();
+
+        // Assert its VMs moved to the other nodes, the running one staying up
+        assert_eq!(migrations.len(), 2);
+        assert!(proxmox.vms().iter().all(|vm| vm.node != "pve-node1"));
+        assert!(matches!(proxmox.vm(100).unwrap().status, Status::Running));
+        assert_eq!(proxmox.vm(100).unwrap().lock, None);
+        assert_eq!(proxmox.task_types(), ["qmigrate", "qmigrate", "qmigrate"]);
+    }
+
+    #[tokio::test]
+    async fn test_a_drain_the_cluster_cannot_hold_moves_nothing() {
+        // Arrange a cluster whose second node only fits one of the VMs
+        let proxmox = FakeProxmox::new()
+            .with_node("pve-node1", 16, 64 * GIB)
+            .with_node("pve-node2", 16, 3 * GIB / 2)
+            .with_vm("pve-node1", 100, "web", Status::Running)
+            .with_vm("pve-node1", 101, "db", Status::Running);
+        let server = MockServer::new().await.with_fake_proxmox(&proxmox);
+        let service = service(&server);
+
+        // Act the drain of the first node
+        let result = service.drain("pve-node1").await;
+
+        // Assert no VM was moved
+        assert!(matches!(result, Err(crate::Error::InsufficientCapacity(_))));
+        assert!(proxmox.vms().iter().all(|vm| vm.node == "pve-node1"));
+        assert!(proxmox.task_types().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_an_instance_is_exported_to_another_cluster() {
+        // Arrange two clusters sharing their backup storage, the first one
+        // running a VM
+        let owner = uuid::Uuid::new_v4();
+        let source = FakeProxmox::new()
+            .with_node("pve-node1", 16, 64 * GIB)
+            .with_vm("pve-node1", 100, "web", Status::Running);
+        let target = FakeProxmox::new()
+            .with_node("pve-node1", 16, 64 * GIB)
+            .with_node("pve-node2", 16, 64 * GIB)
+            .with_vm("pve-node1", 100, "db", Status::Running)
+            .with_backup_storage_of(&source);
+        let source_server = MockServer::new().await.with_fake_proxmox(&source);
+        let target_server = MockServer::new().await.with_fake_proxmox(&target);
+        let (source_service, target_service) = (service(&source_server), service(&target_server));
+        let request = PlacementRequest {
+            cores: 1,
+            memory_bytes: GIB,
+            disk_bytes: 10 * GIB,
+        };
+
+        // Act the export of the VM, then its import on the second cluster
+        let export = source_service.export("100", owner).await.unwrap();
+        let migration = target_service
+            .import(&export, Some("pve-node2"), &request)
+            .await
+            .unwrap();
+
+        // Assert the VM was stopped and restored on the second cluster, where
+        // it runs again
+        assert!(export.running);
+        assert!(matches!(source.vm(100).unwrap().status, Status::Stopped));
+        assert_eq!(migration.id, "101");
+        assert_eq!(migration.source_node, "pve-node1");
+        assert_eq!(migration.target_node, "pve-node2");
+        let vm = target.vm(101).unwrap();
+        assert_eq!(vm.name, "web");
+        assert!(matches!(vm.status, Status::Running));
+        assert_eq!(
+            vm.config.get("scsi0").map(String::as_str),
+            Some("local-lvm:vm-101-disk-0,size=10G")
+        );
+        assert_eq!(target.task_types(), ["qmrestore", "qmconfig", "qmstart"]);
+
+        // Act the import of an export missing from the backup storage
+        let missing = Export {
+            archive: "pbs:backup/vm/100/missing".to_owned(),
+            ..export
+        };
+        let result = target_service.import(&missing, None, &request).await;
+
+        // Assert nothing was imported
+        assert!(result.is_err());
+        assert_eq!(target.vms().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_the_backups_of_a_deleted_instance_are_not_reachable_by_the_next_one() {
+        // Arrange a VM backed up on a schedule, then deleted
+        let (owner, next_owner) = (uuid::Uuid::new_v4(), uuid::Uuid::new_v4());
+        let proxmox = FakeProxmox::new()
+            .with_node("pve-node1", 16, 64 * GIB)
+            .with_vm("pve-node1", 100, "web", Status::Stopped)
+            .with_backup("pve-node1", 100, &format!("frn-instance={}", owner))
+            .with_backup_job(&format!("frn-{}", owner), 100)
+            .with_backup_job("frn-other", 101);
+        let server = MockServer::new().await.with_fake_proxmox(&proxmox);
+        let service = service(&server);
+        let backups = service.list_backups("100", owner).await.unwrap();
+
+        // Act the deletion of the VM
+        service.delete("100").await.unwrap();
+
+        // Assert its backups and their schedule were deleted with it
+        assert_eq!(backups.len(), 1);
+        assert!(proxmox.volumes(BACKUP_STORAGE).is_empty());
+        assert_eq!(proxmox.backup_jobs(), ["frn-other"]);
+
+        // Arrange a backup left behind, and a VM taking the id of the deleted
+        // one for another instance
+        let proxmox = proxmox
+            .with_backup("pve-node1", 100, &format!("frn-instance={}", owner))
+            .with_vm("pve-node1", 100, "db", Status::Stopped);
+        let backup_id = proxmox.volumes(BACKUP_STORAGE).remove(0);
+
+        // Act the listing and restoration of the backups of the new instance
+        let backups = service.list_backups("100", next_owner).await.unwrap();
+        let restored = service
+            .restore_backup("100", next_owner, &backup_id, "db")
+            .await;
+
+        // Assert the backup of the deleted instance is out of reach
+        assert!(backups.is_empty());
+        assert!(matches!(
+            restored,
+            Err(crate::Error::DistantBackupNotFound(id)) if id == backup_id
+        ));
+    }
+}
@@ -0,0 +1,203 @@
+//! Firewall of instances.
+//!
+//! Security groups are enforced through the firewall of each VM. The rules
+//! managed on behalf of a group are tagged with a comment naming it, so that
+//! rules added by hand on the hypervisor are left untouched. Managed rules
+//! only let traffic through: the incoming traffic they do not match is
+//! dropped, and so is the outgoing traffic once a group restricts it.
+//!
+//! The VM firewall only filters traffic when the firewall of the cluster is
+//! enabled, and on network devices with the `firewall` flag set.
+
+use crate::instance::{Firewall, FirewallDirection, FirewallRule};
+use crate::proxmox::api::{
+    vm_firewall_options_read::VMFirewallOptions, vm_firewall_rule_create::VMFirewallRuleOptions,
+    vm_firewall_rule_list::VMFirewallRule,
+};
+
+/// Prefix of the comment of the rules managed on behalf of a security group.
+const MANAGED_PREFIX: &str = "frn:";
+
+/// The action letting traffic through.
+const ACCEPT: &str = "ACCEPT";
+
+/// The action silently discarding traffic.
+const DROP: &str = "DROP";
+
+/// Gets the rule managed on behalf of a security group behind a rule of the
+/// VM firewall, none for the rules added by hand.
+pub fn managed_rule(rule: &VMFirewallRule) -> Option<FirewallRule> {
+    let group = rule.comment.as_deref()?.strip_prefix(MANAGED_PREFIX)?;
+    let direction = match rule.direction.as_str() {
+        "in" => FirewallDirection::Ingress,
+        "out" => FirewallDirection::Egress,
+        _ => return None,
+    };
+    let cidr = match direction {
+        FirewallDirection::Ingress => rule.source.clone(),
+        FirewallDirection::Egress => rule.dest.clone(),
+    };
+
+    Some(FirewallRule {
+        direction,
+        protocol: rule.proto.clone(),
+        port_range: rule.dport.clone(),
+        cidr,
+        group: group.to_owned(),
+    })
+}
+
+/// Builds the rule of the VM firewall enforcing a managed rule.
+pub fn rule_options(rule: &FirewallRule) -> VMFirewallRuleOptions {
+    let (direction, source, dest) = match rule.direction {
+        FirewallDirection::Ingress => ("in", rule.cidr.clone(), None),
+        FirewallDirection::Egress => ("out", None, rule.cidr.clone()),
+    };
+
+    VMFirewallRuleOptions {
+        direction: direction.to_owned(),
+        action: ACCEPT.to_owned(),
+        pos: Some(0),
+        proto: rule.protocol.clone(),
+        dport: rule.port_range.clone(),
+        source,
+        dest,
+        comment: Some(format!("{}{}", MANAGED_PREFIX, rule.group)),
+        enable: Some(true),
+    }
+}
+
+/// Gets the policies applied to the incoming and outgoing traffic no rule
+/// lets through. Outgoing traffic is only restricted once an egress rule
+/// exists.
+pub fn policies(firewall: &Firewall) -> (&'static str, &'static str) {
+    let restricted = firewall
+        .rules
+        .iter()
+        .any(|rule| rule.direction == FirewallDirection::Egress);
+
+    (DROP, if restricted { DROP } else { ACCEPT })
+}
+
+/// Whether the options of the VM firewall enforce a managed firewall, which
+/// is enabled with its policies. Unset policies stand for the defaults of
+/// Proxmox.
+pub fn enforces(options: &VMFirewallOptions, firewall: &Firewall) -> bool {
+    let (policy_in, policy_out) = policies(firewall);
+
+    options.enable == Some(1)
+        && options.policy_in.as_deref().unwrap_or(DROP) == policy_in
+        && options.policy_out.as_deref().unwrap_or(ACCEPT) == policy_out
+}
+
+/// Whether the firewall flag is set on a network device (e.g.
+/// `virtio=BC:24:11:20:4E:3F,bridge=vmbr0,firewall=1`).
+pub fn has_firewall_flag(net: &str) -> bool {
+    net.split(',').any(|option| option == "firewall=1")
+}
+
+/// Sets the firewall flag on a network device.
+pub fn with_firewall_flag(net: &str) -> String {
+    net.split(',')
+        .filter(|option| !option.starts_with("firewall="))
+        .chain(["firewall=1"])
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ssh_rule() -> FirewallRule {
+        FirewallRule {
+            direction: FirewallDirection::Ingress,
+            protocol: Some("tcp".to_owned()),
+            port_range: Some("22".to_owned()),
+            cidr: Some("10.0.0.0/8".to_owned()),
+            group: "0b5a3c5e-9a7e-4a0e-8a55-5d4b0c2b1f6e".to_owned(),
+        }
+    }
+
+    #[test]
+    fn test_managed_rules_round_trip_through_the_vm_firewall() {
+        let options = rule_options(&ssh_rule());
+        let listed = VMFirewallRule {
+            pos: 0,
+            direction: options.direction,
+            action: options.action,
+            proto: options.proto,
+            dport: options.dport,
+            source: options.source,
+            dest: options.dest,
+            comment: options.comment,
+            enable: Some(1),
+        };
+
+        assert_eq!(managed_rule(&listed), Some(ssh_rule()));
+    }
+
+    #[test]
+    fn test_rules_added_by_hand_are_not_managed() {
+        let listed = VMFirewallRule {
+            pos: 1,
+            direction: "in".to_owned(),
+            action: ACCEPT.to_owned(),
+            proto: Some("icmp".to_owned()),
+            dport: None,
+            source: None,
+            dest: None,
+            comment: Some("added by hand".to_owned()),
+            enable: Some(1),
+        };
+
+        assert_eq!(managed_rule(&listed), None);
+    }
+
+    #[test]
+    fn test_egress_is_only_restricted_by_egress_rules() {
+        let mut firewall = Firewall {
+            rules: vec![ssh_rule()],
+        };
+        assert_eq!(policies(&firewall), (DROP, ACCEPT));
+
+        firewall.rules.push(FirewallRule {
+            direction: FirewallDirection::Egress,
+            protocol: None,
+            port_range: None,
+            cidr: Some("10.0.0.0/8".to_owned()),
+            group: "0b5a3c5e-9a7e-4a0e-8a55-5d4b0c2b1f6e".to_owned(),
+        });
+        assert_eq!(policies(&firewall), (DROP, DROP));
+    }
+
+    #[test]
+    fn test_policies_changed_by_hand_are_not_enforced() {
+        let firewall = Firewall {
+            rules: vec![ssh_rule()],
+        };
+        let mut options = VMFirewallOptions {
+            enable: Some(1),
+            policy_in: None,
+            policy_out: None,
+        };
+        assert!(enforces(&options, &firewall));
+
+        options.policy_in = Some(ACCEPT.to_owned());
+        assert!(!enforces(&options, &firewall));
+    }
+
+    #[test]
+    fn test_with_firewall_flag() {
+        assert_eq!(
+            with_firewall_flag("virtio=BC:24:11:20:4E:3F,bridge=vmbr0"),
+            "virtio=BC:24:11:20:4E:3F,bridge=vmbr0,firewall=1"
+        );
+        assert_eq!(
+            with_firewall_flag("virtio,bridge=vmbr0,firewall=0"),
+            "virtio,bridge=vmbr0,firewall=1"
+        );
+        assert!(has_firewall_flag("virtio,bridge=vmbr0,firewall=1"));
+        assert!(!has_firewall_flag("virtio,bridge=vmbr0"));
+    }
+}
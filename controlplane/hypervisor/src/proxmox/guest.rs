@@ -0,0 +1,178 @@
+//! Commands run in instances through the QEMU guest agent.
+//!
+//! The agent runs commands without a shell, so the commands below are POSIX
+//! shell scripts given their arguments positionally and their secrets on the
+//! standard input, never interpolated into the script itself.
+
+use crate::Error;
+use crate::proxmox::api::{
+    vm_agent_exec::VMAgentExecOptions, vm_agent_exec_status::VMAgentExecStatus,
+};
+
+/// Appends the public key read on the standard input to the authorized keys
+/// of the user given as first argument, unless it is already authorized.
+const AUTHORIZE_SSH_KEY_SCRIPT: &str = r#"set -eu
+home=$(getent passwd "$1" | cut -d: -f6)
+[ -n "$home" ] || { echo "unknown user $1" >&2; exit 1; }
+key=$(cat)
+mkdir -p "$home/.ssh"
+keys="$home/.ssh/authorized_keys"
+touch "$keys"
+if ! grep -qxF "$key" "$keys"; then
+    if [ -s "$keys" ] && [ -n "$(tail -c 1 "$keys")" ]; then echo >> "$keys"; fi
+    printf '%s\n' "$key" >> "$keys"
+fi
+chmod 700 "$home/.ssh"
+chmod 600 "$keys"
+chown -R "$1:$(id -gn "$1")" "$home/.ssh"
+"#;
+
+/// Builds the command authorizing an SSH public key to log in as a user.
+pub fn authorize_ssh_key(username: &str, public_key: &str) -> VMAgentExecOptions {
+    VMAgentExecOptions {
+        command: vec![
+            "sh".to_owned(),
+            "-c".to_owned(),
+            AUTHORIZE_SSH_KEY_SCRIPT.to_owned(),
+            "sh".to_owned(),
+            username.to_owned(),
+        ],
+        input_data: Some(public_key.trim().to_owned()),
+    }
+}
+
+/// Writes the WireGuard configuration read on the standard input for the
+/// interface given as first argument, and brings the interface up, or
+/// synchronizes its peers in place when it already is.
+const WIREGUARD_UP_SCRIPT: &str = r#"set -eu
+conf="/etc/wireguard/$1.conf"
+mkdir -p /etc/wireguard
+(umask 077 && cat > "$conf")
+if ip link show "$1" > /dev/null 2>&1; then
+    stripped=$(mktemp)
+    trap 'rm -f "$stripped"' EXIT
+    wg-quick strip "$1" > "$stripped"
+    wg syncconf "$1" "$stripped"
+else
+    systemctl enable --now "wg-quick@$1"
+fi
+"#;
+
+/// Brings the WireGuard interface given as first argument down, and removes
+/// its configuration.
+const WIREGUARD_DOWN_SCRIPT: &str = r#"set -eu
+systemctl disable --now "wg-quick@$1" 2> /dev/null || true
+if ip link show "$1" > /dev/null 2>&1; then
+    ip link delete "$1"
+fi
+rm -f "/etc/wireguard/$1.conf"
+"#;
+
+/// Builds the command applying a WireGuard configuration to an interface, or
+/// tearing the interface down when unset.
+pub fn configure_wireguard(interface: &str, configuration: Option<&str>) -> VMAgentExecOptions {
+    let script = match configuration {
+        Some(_) => WIREGUARD_UP_SCRIPT,
+        None => WIREGUARD_DOWN_SCRIPT,
+    };
+
+    VMAgentExecOptions {
+        command: vec![
+            "sh".to_owned(),
+            "-c".to_owned(),
+            script.to_owned(),
+            "sh".to_owned(),
+            interface.to_owned(),
+        ],
+        input_data: configuration.map(ToOwned::to_owned),
+    }
+}
+
+/// Checks a command exited successfully, reporting its standard error
+/// otherwise.
+pub fn succeeded(status: &VMAgentExecStatus) -> Result<(), Error> {
+    match (status.exitcode, status.signal) {
+        (Some(0), None) => Ok(()),
+        (_, Some(signal)) => Err(Error::GuestCommandFailed(format!(
+            "killed by signal {}",
+            signal
+        ))),
+        (code, None) => Err(Error::GuestCommandFailed(format!(
+            "exited with code {}: {}",
+            code.unwrap_or(-1),
+            status.err_data.as_deref().unwrap_or_default().trim()
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secrets_are_not_passed_as_arguments() {
+        let key = "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIAABAgMEBQYHCAkKCwwNDg8QERITFBUWFxgZGhscHR4f alice@laptop";
+        let options = authorize_ssh_key("debian", &format!("{}\n", key));
+
+        assert_eq!(options.command[0], "sh");
+        assert_eq!(options.command.last().map(String::as_str), Some("debian"));
+        assert!(
+            !options
+                .command
+                .iter()
+                .any(|argument| argument.contains(key))
+        );
+        assert_eq!(options.input_data.as_deref(), Some(key));
+    }
+
+    #[test]
+    fn test_configure_wireguard() {
+        let configuration = "[Interface]\nPrivateKey = cGFzc3dvcmQ=\n";
+        let options = configure_wireguard("wg-frn", Some(configuration));
+
+        assert_eq!(options.command[2], WIREGUARD_UP_SCRIPT);
+        assert_eq!(options.command.last().map(String::as_str), Some("wg-frn"));
+        assert!(
+            !options
+                .command
+                .iter()
+                .any(|argument| argument.contains("PrivateKey"))
+        );
+        assert_eq!(options.input_data.as_deref(), Some(configuration));
+
+        let options = configure_wireguard("wg-frn", None);
+        assert_eq!(options.command[2], WIREGUARD_DOWN_SCRIPT);
+        assert_eq!(options.input_data, None);
+    }
+
+    #[test]
+    fn test_succeeded() {
+        let status = VMAgentExecStatus {
+            exited: 1,
+            exitcode: Some(0),
+            ..Default::default()
+        };
+        assert!(succeeded(&status).is_ok());
+
+        let status = VMAgentExecStatus {
+            exited: 1,
+            exitcode: Some(1),
+            err_data: Some("unknown user root2\n".to_owned()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            succeeded(&status),
+            Err(Error::GuestCommandFailed(message)) if message == "exited with code 1: unknown user root2"
+        ));
+
+        let status = VMAgentExecStatus {
+            exited: 1,
+            signal: Some(9),
+            ..Default::default()
+        };
+        assert!(matches!(
+            succeeded(&status),
+            Err(Error::GuestCommandFailed(_))
+        ));
+    }
+}
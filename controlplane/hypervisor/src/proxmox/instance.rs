@@ -1,34 +1,512 @@
 use uuid::Uuid;
 
 use crate::Error;
-use crate::instance::{Instance, InstanceCreateRequest, Instances, Status};
-use crate::proxmox::api::cluster_resources_list::Resource;
-use crate::proxmox::api::{self, ResourceStatus};
+use crate::instance::{
+    Backup, BackupRetention, BackupSchedule, Console, ConsoleKind, Export, Firewall, Image,
+    ImageImportRequest, Instance, InstanceCreateRequest, InstanceResizeRequest, Instances, Metrics,
+    MetricsRequest, Migration, Snapshot, SnapshotCreateRequest, Status, Volume,
+};
+use crate::placement::{NodeCapacity, PlacementRequest};
+use crate::proxmox::api;
 use crate::proxmox::api::{
-    cluster_resources_list::ResourceType, helpers, vm_clone::VMCloneOptions, vm_create::VMConfig,
+    ResourceStatus,
+    backup_job_create::BackupJobOptions,
+    cluster_resources_list::{Resource, ResourceType},
+    helpers,
+    storage_download_url::StorageDownloadUrlOptions,
+    vm_agent_set_user_password::VMAgentSetUserPasswordOptions,
+    vm_clone::VMCloneOptions,
+    vm_config_read,
+    vm_config_update::VMConfigUpdateOptions,
+    vm_create::VMConfig,
+    vm_firewall_options_update::VMFirewallOptionsUpdate,
+    vm_migrate::VMMigrateOptions,
+    vm_restore::VMRestoreOptions,
+    vm_rrddata_read::VMRrdData,
+    vm_snapshot_create::VMSnapshotCreateOptions,
+    vm_snapshot_list::CURRENT_SNAPSHOT_NAME,
+    vm_status_shutdown::VMShutdownOptions,
+    vzdump_create::VzdumpOptions,
+};
+use crate::proxmox::firewall::{
+    enforces, has_firewall_flag, managed_rule, policies, rule_options, with_firewall_flag,
 };
+use crate::proxmox::guest;
+use crate::proxmox::metrics;
+use crate::proxmox::placement;
+use crate::proxmox::snippet::{self, SnippetStorage, Snippets};
+use futures::{StreamExt, stream};
+use std::collections::BTreeMap;
 use std::net::Ipv4Addr;
-use tokio::fs::File;
-use tokio::io::AsyncWriteExt;
+use std::time::{SystemTime, UNIX_EPOCH};
+use url::Url;
 
 #[derive(Clone)]
 pub struct ProxmoxInstanceService {
     pub api_url: String,
     pub client: reqwest::Client,
     pub authorization: String,
+    /// The storage the capacity of the nodes is accounted on.
+    pub storage: String,
+    /// The storage the disks and volumes are created on.
+    pub image_storage: String,
+    /// The storage the backups are stored on.
+    pub backup_storage: String,
+    pub snippets: Snippets,
+}
+
+/// Time the guest of an instance restarted to apply its new shape is given to
+/// shut down, in seconds, before it is stopped.
+const RESTART_SHUTDOWN_TIMEOUT: u32 = 120;
+
+/// Number of VM statuses read at once while listing the instances.
+const STATUS_READ_CONCURRENCY: usize = 8;
+
+/// Gets the content type an image is stored as, from its file name.
+fn image_content(name: &str) -> Result<&'static str, Error> {
+    match name.rsplit_once('.').map(|(_, extension)| extension) {
+        Some("qcow2" | "raw") => Ok("import"),
+        Some("iso") => Ok("iso"),
+        _ => Err(Error::UnsupportedImageFormat(name.to_owned())),
+    }
+}
+
+/// Gets the prefix naming the snippets owned by a VM.
+fn snippet_prefix(vm_id: u32) -> String {
+    format!("vm-{}-", vm_id)
+}
+
+/// Gets the id of the job scheduling the backups of an instance.
+fn backup_job_id(owner: Uuid) -> String {
+    format!("frn-{}", owner)
+}
+
+/// Gets the notes binding the backups of a VM to the instance owning it,
+/// since Proxmox hands the ids of deleted VMs out again.
+fn backup_notes(owner: Uuid) -> String {
+    format!("frn-instance={}", owner)
+}
+
+/// Formats a retention as the `prune-backups` option of a backup.
+fn prune_backups(retention: &BackupRetention) -> String {
+    let keep = [
+        ("keep-last", retention.keep_last),
+        ("keep-daily", retention.keep_daily),
+        ("keep-weekly", retention.keep_weekly),
+        ("keep-monthly", retention.keep_monthly),
+    ]
+    .into_iter()
+    .filter_map(|(option, count)| count.map(|count| format!("{}={}", option, count)))
+    .collect::<Vec<_>>();
+
+    if keep.is_empty() {
+        String::from("keep-all=1")
+    } else {
+        keep.join(",")
+    }
+}
+
+impl ProxmoxInstanceService {
+    /// Selects the node with the most headroom able to fit the instance.
+    async fn select_node(&self, options: &InstanceCreateRequest) -> Result<String, Error> {
+        let capacities = self.capacity().await?;
+        let occupied = if options.anti_affinity.is_empty() {
+            Default::default()
+        } else {
+            let vms =
+                api::cluster_resources_list(&self.api_url, &self.client, &self.authorization, "vm")
+                    .await?
+                    .data;
+            placement::occupied_nodes(&vms, &options.anti_affinity)
+        };
+
+        let request = PlacementRequest {
+            cores: options.cores as u32,
+            memory_bytes: options.memory_bytes,
+            disk_bytes: options.disk_bytes,
+        };
+
+        placement::select_node(&capacities, &occupied, &request).map_err(Into::into)
+    }
+
+    /// Shuts the instance down gracefully, stopping it when its guest does not
+    /// power off within [`RESTART_SHUTDOWN_TIMEOUT`] seconds.
+    async fn shutdown_or_stop(&self, id: &str, vm_id: u32, node_id: &str) -> Result<(), Error> {
+        let task = api::vm_status_shutdown(
+            &self.api_url,
+            &self.client,
+            &self.authorization,
+            node_id,
+            vm_id,
+            &VMShutdownOptions {
+                timeout: Some(RESTART_SHUTDOWN_TIMEOUT),
+            },
+        )
+        .await?
+        .data;
+
+        let shutdown = api::helpers::wait_for_task_completion(
+            &self.api_url,
+            &self.client,
+            &self.authorization,
+            node_id,
+            &task,
+        )
+        .await;
+
+        if let Err(err) = shutdown {
+            tracing::warn!("instance {} did not shut down, stopping it: {}", id, err);
+            self.stop(id).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the status of a running VM with the one QEMU reports, the
+    /// cluster resources reporting paused VMs as running.
+    ///
+    /// The status of the cluster resources is kept when QEMU cannot be asked.
+    async fn with_qmp_status(&self, mut resource: Resource) -> Resource {
+        let (Some(node_id), Some(vm_id)) = (resource.node.as_deref(), resource.vmid) else {
+            return resource;
+        };
+        if resource.status != ResourceStatus::Running {
+            return resource;
+        }
+
+        match api::vm_status_read(
+            &self.api_url,
+            &self.client,
+            &self.authorization,
+            node_id,
+            vm_id,
+        )
+        .await
+        {
+            Ok(response) => {
+                if let Some(qmpstatus) = response.data.qmpstatus {
+                    resource.status = qmpstatus;
+                }
+            }
+            Err(err) => tracing::warn!("could not read the status of VM {}: {}", vm_id, err),
+        }
+
+        resource
+    }
+
+    /// Parses the instance id and finds the node running it.
+    async fn locate(&self, id: &str) -> Result<(u32, String), Error> {
+        let vm_id = id
+            .parse::<u32>()
+            .map_err(|_| Error::MalformedVmId(id.to_owned()))?;
+
+        let node_id =
+            helpers::get_vm_execution_node(&self.api_url, &self.client, &self.authorization, vm_id)
+                .await?;
+
+        Ok((vm_id, node_id))
+    }
+
+    /// Reads the current configuration of the instance.
+    async fn read_config(
+        &self,
+        node_id: &str,
+        vm_id: u32,
+    ) -> Result<vm_config_read::VMConfig, Error> {
+        Ok(api::vm_config_read(
+            &self.api_url,
+            &self.client,
+            &self.authorization,
+            node_id,
+            vm_id,
+        )
+        .await?
+        .data)
+    }
+
+    /// Gets the nodes a storage is available on.
+    ///
+    /// A shared storage holds the same content on every node, so only one of
+    /// them is returned.
+    async fn storage_nodes(&self, storage: &str) -> Result<Vec<String>, Error> {
+        let storages = api::cluster_resources_list(
+            &self.api_url,
+            &self.client,
+            &self.authorization,
+            "storage",
+        )
+        .await?
+        .data
+        .into_iter()
+        .filter(|resource| {
+            resource.storage.as_deref() == Some(storage)
+                && resource.status == ResourceStatus::Available
+        })
+        .collect::<Vec<_>>();
+
+        let shared = storages.iter().any(|resource| resource.shared == Some(1));
+        let mut nodes = storages
+            .into_iter()
+            .filter_map(|resource| resource.node)
+            .collect::<Vec<_>>();
+        nodes.dedup();
+        if shared {
+            nodes.truncate(1);
+        }
+
+        if nodes.is_empty() {
+            return Err(Error::DistantStorageNotFound(storage.to_owned()));
+        }
+
+        Ok(nodes)
+    }
+
+    /// Lists the images held by a storage of a node.
+    async fn node_images(&self, node_id: &str, storage: &str) -> Result<Vec<Image>, Error> {
+        Ok(api::storage_content_list(
+            &self.api_url,
+            &self.client,
+            &self.authorization,
+            node_id,
+            storage,
+            None,
+        )
+        .await?
+        .data
+        .into_iter()
+        .filter_map(|content| content.try_into().ok())
+        .collect())
+    }
+
+    /// Lists the backups of a VM held by the backup storage, whoever owned
+    /// the VM.
+    async fn vm_archives(&self, node_id: &str, vm_id: u32) -> Result<Vec<Backup>, Error> {
+        Ok(api::storage_content_list(
+            &self.api_url,
+            &self.client,
+            &self.authorization,
+            node_id,
+            &self.backup_storage,
+            Some("backup"),
+        )
+        .await?
+        .data
+        .into_iter()
+        .filter(|content| content.vmid == Some(vm_id))
+        .filter_map(|content| content.try_into().ok())
+        .collect())
+    }
+
+    /// Lists the backups of a VM taken while `owner` owned it.
+    async fn vm_backups(
+        &self,
+        node_id: &str,
+        vm_id: u32,
+        owner: Uuid,
+    ) -> Result<Vec<Backup>, Error> {
+        let notes = backup_notes(owner);
+
+        Ok(self
+            .vm_archives(node_id, vm_id)
+            .await?
+            .into_iter()
+            .filter(|backup| backup.notes.as_deref() == Some(notes.as_str()))
+            .collect())
+    }
+
+    /// Ensures a backup belongs to a VM and to the instance owning it, so that
+    /// a VM cannot reach the backups of another one, nor of a deleted VM it
+    /// took the id of.
+    async fn ensure_vm_backup(
+        &self,
+        node_id: &str,
+        vm_id: u32,
+        owner: Uuid,
+        backup_id: &str,
+    ) -> Result<(), Error> {
+        let owned = self
+            .vm_backups(node_id, vm_id, owner)
+            .await?
+            .iter()
+            .any(|backup| backup.id == backup_id);
+        if !owned {
+            return Err(Error::DistantBackupNotFound(backup_id.to_owned()));
+        }
+
+        Ok(())
+    }
+
+    /// Deletes the backup jobs of a VM, so that the VM taking its id does not
+    /// inherit its schedule.
+    async fn delete_vm_backup_jobs(&self, vm_id: u32) -> Result<(), Error> {
+        let jobs = api::backup_job_list(&self.api_url, &self.client, &self.authorization)
+            .await?
+            .data
+            .into_iter()
+            .filter(|job| job.id.starts_with("frn-") && job.vmid == Some(vm_id.to_string()));
+        for job in jobs {
+            api::backup_job_delete(&self.api_url, &self.client, &self.authorization, &job.id)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Deletes the backups of a VM from the backup storage, so that the VM
+    /// taking its id cannot reach them.
+    async fn delete_vm_backups(&self, node_id: &str, vm_id: u32) -> Result<(), Error> {
+        for backup in self.vm_archives(node_id, vm_id).await? {
+            let task = api::storage_content_delete(
+                &self.api_url,
+                &self.client,
+                &self.authorization,
+                node_id,
+                &self.backup_storage,
+                &backup.id,
+            )
+            .await?
+            .data;
+
+            if let Some(task) = task {
+                api::helpers::wait_for_task_completion(
+                    &self.api_url,
+                    &self.client,
+                    &self.authorization,
+                    node_id,
+                    &task,
+                )
+                .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Updates the configuration of the instance and waits for the change.
+    async fn update_config(
+        &self,
+        node_id: &str,
+        vm_id: u32,
+        options: &VMConfigUpdateOptions,
+    ) -> Result<(), Error> {
+        let task = api::vm_config_update(
+            &self.api_url,
+            &self.client,
+            &self.authorization,
+            node_id,
+            vm_id,
+            options,
+        )
+        .await?
+        .data;
+
+        api::helpers::wait_for_task_completion(
+            &self.api_url,
+            &self.client,
+            &self.authorization,
+            node_id,
+            &task,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Migrates a VM between nodes and waits for the migration.
+    ///
+    /// Running VMs are migrated live, and the disks held by storages local to
+    /// the source node are copied along with them.
+    async fn migrate_vm(
+        &self,
+        vm_id: u32,
+        source_node: &str,
+        target_node: &str,
+        online: bool,
+    ) -> Result<(), Error> {
+        let task = api::vm_migrate(
+            &self.api_url,
+            &self.client,
+            &self.authorization,
+            source_node,
+            vm_id,
+            &VMMigrateOptions {
+                target: target_node.to_owned(),
+                online: online.then_some(true),
+                with_local_disks: Some(true),
+            },
+        )
+        .await?
+        .data;
+
+        api::helpers::wait_for_task_completion(
+            &self.api_url,
+            &self.client,
+            &self.authorization,
+            source_node,
+            &task,
+        )
+        .await?;
+
+        Ok(())
+    }
 }
 
 impl Instances for ProxmoxInstanceService {
+    async fn capacity(&self) -> Result<Vec<NodeCapacity>, Error> {
+        let nodes =
+            api::cluster_resources_list(&self.api_url, &self.client, &self.authorization, "node")
+                .await?
+                .data;
+        let storages = api::cluster_resources_list(
+            &self.api_url,
+            &self.client,
+            &self.authorization,
+            "storage",
+        )
+        .await?
+        .data;
+
+        Ok(placement::node_capacities(&nodes, &storages, &self.storage))
+    }
+
+    /// Gets the version of the Proxmox VE API (e.g. `8.2.4`).
+    async fn version(&self) -> Result<String, Error> {
+        let version = api::version_read(&self.api_url, &self.client, &self.authorization)
+            .await?
+            .data;
+
+        Ok(version.version)
+    }
+
+    /// Lists the VMs of the cluster, with the status QEMU reports for the
+    /// running ones.
     async fn list(&self) -> Result<Vec<Instance>, Error> {
         let response =
             api::cluster_resources_list(&self.api_url, &self.client, &self.authorization, "vm")
                 .await?
                 .data;
-        response
-            .into_iter()
-            .filter(|resource| resource.resource_type == ResourceType::Qemu)
-            .map(|resource| resource.try_into().map_err(Into::into))
-            .collect()
+
+        stream::iter(
+            response
+                .into_iter()
+                .filter(|resource| resource.resource_type == ResourceType::Qemu),
+        )
+        .map(|resource| self.with_qmp_status(resource))
+        .buffered(STATUS_READ_CONCURRENCY)
+        .collect::<Vec<_>>()
+        .await
+        .into_iter()
+        .map(|resource| resource.try_into().map_err(Into::into))
+        .collect()
+    }
+
+    /// Gets the next free VM id of the cluster.
+    async fn next_id(&self) -> Result<String, Error> {
+        let next_id = api::cluster_next_id(&self.api_url, &self.client, &self.authorization)
+            .await?
+            .data;
+
+        Ok(next_id.to_string())
     }
 
     /// Clones the instance.
@@ -74,66 +552,62 @@ impl Instances for ProxmoxInstanceService {
     }
 
     /// Creates the instance.
+    ///
+    /// The cloud-init snippet of the instance is stored on the node it is
+    /// provisioned on, and named after the VM so it can be deleted with it.
     async fn create(&self, options: InstanceCreateRequest) -> Result<Uuid, Error> {
-        // Write the value of options.snippet to a shared file
         let instance_id = Uuid::new_v4();
-        let snippet_filename = format!("snippets/{}.yaml", &instance_id);
-        let volume_path = std::env::var("PROXMOX_VOLUME_ABSOLUTE_PATH")
-            .unwrap_or_else(|_| crate::proxmox::VOLUME_ABSOLUTE_PATH.to_owned());
-        let mut snippet_file = File::create_new(format!("{}/{}", volume_path, &snippet_filename))
-            .await
-            .map_err(|err| {
-                tracing::error!("oopsie: {:?}", err);
-                Error::SnippetFileExists(snippet_filename.clone())
-            })?;
-        snippet_file.write_all(options.snippet.as_bytes()).await?;
-
-        tracing::info!("snippet written to file: {:?}", snippet_file);
 
         // Get the next id to use
         let next_id = api::cluster_next_id(&self.api_url, &self.client, &self.authorization)
             .await?
             .data;
 
-        tracing::info!("before node fetch");
-
         // Get the node id on which provision the instance
-        let node_id =
-            api::cluster_resources_list(&self.api_url, &self.client, &self.authorization, "node")
-                .await?
-                .data
-                .into_iter()
-                .filter(|resource| resource.status == ResourceStatus::Online)
-                .collect::<Vec<Resource>>()
-                .first()
-                .ok_or_else(|| api::Error::NoNodesAvailable)?
-                .node
-                .clone()
-                .expect("node should be defined for resource of type node");
+        let node_id = self.select_node(&options).await?;
+
+        // Store the snippet provisioning the instance
+        let snippet_filename = format!("{}{}.yaml", snippet_prefix(next_id), instance_id);
+        let snippet_volume = self
+            .snippets
+            .store(&node_id, &snippet_filename, &options.snippet)
+            .await?;
+
+        tracing::info!(snippet_volume, "snippet stored");
 
         let disk_bytes = options.disk_bytes;
-        let vm_config = VMConfig::from_instance_config(options, next_id, snippet_filename);
+        let vm_config =
+            VMConfig::from_instance_config(options, next_id, &snippet_volume, &self.image_storage);
 
         // Create the VM and wait for the task to complete
-        let task_id = api::vm_create(
-            &self.api_url,
-            &self.client,
-            &self.authorization,
-            &node_id,
-            &vm_config,
-        )
-        .await?
-        .data;
+        let created = async {
+            let task_id = api::vm_create(
+                &self.api_url,
+                &self.client,
+                &self.authorization,
+                &node_id,
+                &vm_config,
+            )
+            .await?
+            .data;
 
-        api::helpers::wait_for_task_completion(
-            &self.api_url,
-            &self.client,
-            &self.authorization,
-            &node_id,
-            &task_id,
-        )
-        .await
-        .map(|result| result.id)?;
+            api::helpers::wait_for_task_completion(
+                &self.api_url,
+                &self.client,
+                &self.authorization,
+                &node_id,
+                &task_id,
+            )
+            .await
+        }
+        .await;
+
+        if let Err(e) = created {
+            if let Err(e) = self.snippets.delete(&node_id, &snippet_volume).await {
+                tracing::warn!(snippet_volume, error = %e, "failed to delete orphan snippet");
+            }
+            return Err(e.into());
+        }
 
         // Resize the disk to the requested size. The `import-from` directive
         // used during creation ignores the `size` parameter and creates the
@@ -164,7 +638,7 @@ impl Instances for ProxmoxInstanceService {
             helpers::get_vm_execution_node(&self.api_url, &self.client, &self.authorization, id)
                 .await?;
 
-        // Get the VM network interfaces
+        // Use the static address of the VM if it has one
         let ip = api::vm_config_read(
             &self.api_url,
             &self.client,
@@ -176,28 +650,51 @@ impl Instances for ProxmoxInstanceService {
         .data
         .ipconfig0
         .map(|config| config.ip);
+        if ip.is_some() {
+            return Ok(ip);
+        }
 
-        Ok(ip)
-    }
-
-    /// Deletes the instance.
-    async fn delete(&self, id: &str) -> Result<(), Error> {
-        let node_id = helpers::get_vm_execution_node(
+        // Otherwise ask the guest agent for the address it was leased, unknown
+        // until the agent runs
+        match api::vm_network_interfaces(
             &self.api_url,
             &self.client,
             &self.authorization,
-            id.parse::<u32>()
-                .map_err(|_| Error::MalformedVmId(id.to_owned()))?,
+            &node_id,
+            id,
         )
-        .await?;
+        .await
+        {
+            Ok(response) => Ok(response.data.ipv4_address()),
+            Err(api::Error::MissingAgent) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Deletes the instance, its backups and their schedule, and the
+    /// cloud-init snippet it was created with.
+    async fn delete(&self, id: &str) -> Result<(), Error> {
+        let (vm_id, node_id) = self.locate(id).await?;
+        self.delete_vm_backup_jobs(vm_id).await?;
+
+        let snippet_volume = self
+            .read_config(&node_id, vm_id)
+            .await?
+            .cicustom
+            .as_deref()
+            .and_then(snippet::user_volume)
+            .filter(|volume| {
+                snippet::filename(volume)
+                    .is_some_and(|filename| filename.starts_with(&snippet_prefix(vm_id)))
+            })
+            .map(ToOwned::to_owned);
 
         let task = api::vm_delete(
             &self.api_url,
             &self.client,
             &self.authorization,
             &node_id,
-            id.parse::<u32>()
-                .map_err(|_| Error::MalformedVmId(id.to_owned()))?,
+            vm_id,
         )
         .await?
         .data;
@@ -211,6 +708,20 @@ impl Instances for ProxmoxInstanceService {
         )
         .await?;
 
+        // The snippet is only deleted when owned by the VM, as snippets
+        // shared by several VMs may be referenced as well.
+        if let Some(snippet_volume) = snippet_volume
+            && let Err(e) = self.snippets.delete(&node_id, &snippet_volume).await
+        {
+            tracing::warn!(snippet_volume, error = %e, "failed to delete snippet");
+        }
+
+        // Backups left behind cannot be reached by the VM taking the id, as
+        // they are bound to the deleted instance.
+        if let Err(e) = self.delete_vm_backups(&node_id, vm_id).await {
+            tracing::warn!(vm_id, error = %e, "failed to delete backups");
+        }
+
         Ok(())
     }
 
@@ -250,25 +761,22 @@ impl Instances for ProxmoxInstanceService {
 
     /// Gets the instance status.
     async fn status(&self, id: &str) -> Result<Status, Error> {
-        let node_id = helpers::get_vm_execution_node(
-            &self.api_url,
-            &self.client,
-            &self.authorization,
-            id.parse::<u32>()
-                .map_err(|_| Error::MalformedVmId(id.to_owned()))?,
-        )
-        .await?;
+        let (vm_id, node_id) = self.locate(id).await?;
 
         let result = api::vm_status_read(
             &self.api_url,
             &self.client,
             &self.authorization,
             &node_id,
-            id.parse::<u32>()
-                .map_err(|_| Error::MalformedVmId(id.to_owned()))?,
+            vm_id,
         )
-        .await?;
-        Ok(result.data.status.into())
+        .await?
+        .data;
+
+        Ok(api::instance_status(
+            result.qmpstatus.unwrap_or(result.status),
+            result.lock.as_deref(),
+        ))
     }
 
     /// Stops the instance.
@@ -304,4 +812,1284 @@ impl Instances for ProxmoxInstanceService {
 
         Ok(())
     }
+
+    /// Shuts the instance down gracefully.
+    ///
+    /// The guest is notified through an ACPI event, or its agent when enabled.
+    async fn shutdown(&self, id: &str) -> Result<(), Error> {
+        let (vm_id, node_id) = self.locate(id).await?;
+
+        let task = api::vm_status_shutdown(
+            &self.api_url,
+            &self.client,
+            &self.authorization,
+            &node_id,
+            vm_id,
+            &VMShutdownOptions::default(),
+        )
+        .await?
+        .data;
+
+        api::helpers::wait_for_task_completion(
+            &self.api_url,
+            &self.client,
+            &self.authorization,
+            &node_id,
+            &task,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Reboots the instance gracefully.
+    ///
+    /// The guest is notified through an ACPI event, or its agent when enabled.
+    async fn reboot(&self, id: &str) -> Result<(), Error> {
+        let (vm_id, node_id) = self.locate(id).await?;
+
+        let task = api::vm_status_reboot(
+            &self.api_url,
+            &self.client,
+            &self.authorization,
+            &node_id,
+            vm_id,
+        )
+        .await?
+        .data;
+
+        api::helpers::wait_for_task_completion(
+            &self.api_url,
+            &self.client,
+            &self.authorization,
+            &node_id,
+            &task,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Resets the instance, as a hard reset button would.
+    async fn reset(&self, id: &str) -> Result<(), Error> {
+        let (vm_id, node_id) = self.locate(id).await?;
+
+        let task = api::vm_status_reset(
+            &self.api_url,
+            &self.client,
+            &self.authorization,
+            &node_id,
+            vm_id,
+        )
+        .await?
+        .data;
+
+        api::helpers::wait_for_task_completion(
+            &self.api_url,
+            &self.client,
+            &self.authorization,
+            &node_id,
+            &task,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Suspends the instance.
+    ///
+    /// The instance is paused in memory, or hibernated to disk when `to_disk`
+    /// is set.
+    async fn suspend(&self, id: &str, to_disk: bool) -> Result<(), Error> {
+        let (vm_id, node_id) = self.locate(id).await?;
+
+        let task = api::vm_status_suspend(
+            &self.api_url,
+            &self.client,
+            &self.authorization,
+            &node_id,
+            vm_id,
+            to_disk,
+        )
+        .await?
+        .data;
+
+        api::helpers::wait_for_task_completion(
+            &self.api_url,
+            &self.client,
+            &self.authorization,
+            &node_id,
+            &task,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Resumes a paused or suspended instance.
+    async fn resume(&self, id: &str) -> Result<(), Error> {
+        let (vm_id, node_id) = self.locate(id).await?;
+
+        let task = api::vm_status_resume(
+            &self.api_url,
+            &self.client,
+            &self.authorization,
+            &node_id,
+            vm_id,
+        )
+        .await?
+        .data;
+
+        api::helpers::wait_for_task_completion(
+            &self.api_url,
+            &self.client,
+            &self.authorization,
+            &node_id,
+            &task,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Gets the usage history of the instance from the RRD archives of its
+    /// node.
+    ///
+    /// The archive read is the finest one going back to the start of the
+    /// window, so the samples of long windows are further apart.
+    async fn metrics(&self, id: &str, request: &MetricsRequest) -> Result<Metrics, Error> {
+        let (vm_id, node_id) = self.locate(id).await?;
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let (timeframe, step) = metrics::timeframe(now, request.start);
+
+        let points = api::vm_rrddata_read(
+            &self.api_url,
+            &self.client,
+            &self.authorization,
+            &node_id,
+            vm_id,
+            timeframe,
+        )
+        .await?
+        .data
+        .into_iter()
+        .filter(VMRrdData::has_usage)
+        .map(Into::into)
+        .collect();
+
+        Ok(metrics::downsample(points, request, step))
+    }
+
+    /// Opens a console on the instance.
+    ///
+    /// Proxmox streams both kinds of console through the `vncwebsocket`
+    /// endpoint of the node, authenticated by the ticket of the proxy. A VNC
+    /// client uses that ticket as its password, while a terminal stream is
+    /// authenticated by its first message.
+    async fn console(&self, id: &str, kind: ConsoleKind) -> Result<Console, Error> {
+        let (vm_id, node_id) = self.locate(id).await?;
+
+        let ticket = match kind {
+            ConsoleKind::Vnc => {
+                api::vm_vncproxy(
+                    &self.api_url,
+                    &self.client,
+                    &self.authorization,
+                    &node_id,
+                    vm_id,
+                )
+                .await?
+                .data
+            }
+            ConsoleKind::Terminal => {
+                api::vm_termproxy(
+                    &self.api_url,
+                    &self.client,
+                    &self.authorization,
+                    &node_id,
+                    vm_id,
+                )
+                .await?
+                .data
+            }
+        };
+
+        let mut url = Url::parse(&format!(
+            "{}/api2/json/nodes/{}/qemu/{}/vncwebsocket",
+            self.api_url, node_id, vm_id
+        ))
+        .map_err(|err| Error::Other(Box::new(err)))?;
+        let scheme = if url.scheme() == "https" { "wss" } else { "ws" };
+        url.set_scheme(scheme)
+            .map_err(|_| Error::Other(format!("unsupported url {}", self.api_url).into()))?;
+        url.query_pairs_mut()
+            .append_pair("port", &ticket.port.to_string())
+            .append_pair("vncticket", &ticket.ticket);
+
+        let (password, handshake) = match kind {
+            ConsoleKind::Vnc => (Some(ticket.ticket), None),
+            ConsoleKind::Terminal => (None, Some(format!("{}:{}\n", ticket.user, ticket.ticket))),
+        };
+
+        Ok(Console {
+            url: url.into(),
+            authorization: self.authorization.clone(),
+            password,
+            handshake,
+        })
+    }
+
+    /// Resizes the CPU and memory of the instance.
+    ///
+    /// The new configuration is hotplugged into a running instance whenever
+    /// possible. Changes Proxmox leaves pending are applied by restarting the
+    /// instance.
+    async fn resize(&self, id: &str, options: InstanceResizeRequest) -> Result<(), Error> {
+        let vm_id = id
+            .parse::<u32>()
+            .map_err(|_| Error::MalformedVmId(id.to_owned()))?;
+
+        let node_id =
+            helpers::get_vm_execution_node(&self.api_url, &self.client, &self.authorization, vm_id)
+                .await?;
+
+        let update = VMConfigUpdateOptions {
+            cores: options.cores,
+            memory: options
+                .memory_bytes
+                .map(|bytes| (bytes / (1024 * 1024)) as u32),
+            ..Default::default()
+        };
+
+        let task = api::vm_config_update(
+            &self.api_url,
+            &self.client,
+            &self.authorization,
+            &node_id,
+            vm_id,
+            &update,
+        )
+        .await?
+        .data;
+
+        api::helpers::wait_for_task_completion(
+            &self.api_url,
+            &self.client,
+            &self.authorization,
+            &node_id,
+            &task,
+        )
+        .await?;
+
+        if !matches!(self.status(id).await?, Status::Running) {
+            return Ok(());
+        }
+
+        let requires_restart = api::vm_pending_read(
+            &self.api_url,
+            &self.client,
+            &self.authorization,
+            &node_id,
+            vm_id,
+        )
+        .await?
+        .data
+        .iter()
+        .any(|config| matches!(config.key.as_str(), "cores" | "memory") && config.is_pending());
+
+        if requires_restart {
+            tracing::info!("restarting instance {} to apply its new shape", id);
+            self.shutdown_or_stop(id, vm_id, &node_id).await?;
+            self.start(id).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Takes a snapshot of the instance.
+    async fn create_snapshot(&self, id: &str, options: SnapshotCreateRequest) -> Result<(), Error> {
+        let id = id
+            .parse::<u32>()
+            .map_err(|_| Error::MalformedVmId(id.to_owned()))?;
+
+        let node_id =
+            helpers::get_vm_execution_node(&self.api_url, &self.client, &self.authorization, id)
+                .await?;
+
+        let options = VMSnapshotCreateOptions {
+            snapname: options.name,
+            description: options.description,
+            vmstate: Some(options.include_memory),
+        };
+
+        let task = api::vm_snapshot_create(
+            &self.api_url,
+            &self.client,
+            &self.authorization,
+            &node_id,
+            id,
+            &options,
+        )
+        .await?
+        .data;
+
+        api::helpers::wait_for_task_completion(
+            &self.api_url,
+            &self.client,
+            &self.authorization,
+            &node_id,
+            &task,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Lists the snapshots of the instance.
+    async fn list_snapshots(&self, id: &str) -> Result<Vec<Snapshot>, Error> {
+        let id = id
+            .parse::<u32>()
+            .map_err(|_| Error::MalformedVmId(id.to_owned()))?;
+
+        let node_id =
+            helpers::get_vm_execution_node(&self.api_url, &self.client, &self.authorization, id)
+                .await?;
+
+        let snapshots = api::vm_snapshot_list(
+            &self.api_url,
+            &self.client,
+            &self.authorization,
+            &node_id,
+            id,
+        )
+        .await?
+        .data
+        .into_iter()
+        .filter(|snapshot| snapshot.name != CURRENT_SNAPSHOT_NAME)
+        .map(Into::into)
+        .collect();
+
+        Ok(snapshots)
+    }
+
+    /// Rolls the instance back to the given snapshot.
+    async fn rollback_snapshot(&self, id: &str, name: &str) -> Result<(), Error> {
+        let id = id
+            .parse::<u32>()
+            .map_err(|_| Error::MalformedVmId(id.to_owned()))?;
+
+        let node_id =
+            helpers::get_vm_execution_node(&self.api_url, &self.client, &self.authorization, id)
+                .await?;
+
+        let task = api::vm_snapshot_rollback(
+            &self.api_url,
+            &self.client,
+            &self.authorization,
+            &node_id,
+            id,
+            name,
+        )
+        .await?
+        .data;
+
+        api::helpers::wait_for_task_completion(
+            &self.api_url,
+            &self.client,
+            &self.authorization,
+            &node_id,
+            &task,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Deletes the given snapshot of the instance.
+    async fn delete_snapshot(&self, id: &str, name: &str) -> Result<(), Error> {
+        let id = id
+            .parse::<u32>()
+            .map_err(|_| Error::MalformedVmId(id.to_owned()))?;
+
+        let node_id =
+            helpers::get_vm_execution_node(&self.api_url, &self.client, &self.authorization, id)
+                .await?;
+
+        let task = api::vm_snapshot_delete(
+            &self.api_url,
+            &self.client,
+            &self.authorization,
+            &node_id,
+            id,
+            name,
+        )
+        .await?
+        .data;
+
+        api::helpers::wait_for_task_completion(
+            &self.api_url,
+            &self.client,
+            &self.authorization,
+            &node_id,
+            &task,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Creates a data volume and attaches it to the instance.
+    ///
+    /// The volume is allocated on the image storage and attached to the first
+    /// free SCSI device of the instance. Its size is rounded up to the next
+    /// GiB.
+    async fn create_volume(&self, id: &str, size_bytes: u64) -> Result<Volume, Error> {
+        let (vm_id, node_id) = self.locate(id).await?;
+
+        let device = self
+            .read_config(&node_id, vm_id)
+            .await?
+            .free_scsi_device()
+            .ok_or_else(|| Error::NoFreeDevice(id.to_owned()))?;
+
+        let distant_id = api::storage_content_create(
+            &self.api_url,
+            &self.client,
+            &self.authorization,
+            &node_id,
+            &self.image_storage,
+            vm_id,
+            size_bytes,
+        )
+        .await?
+        .data;
+
+        let update = VMConfigUpdateOptions {
+            disks: BTreeMap::from([(device.clone(), distant_id.clone())]),
+            ..Default::default()
+        };
+        self.update_config(&node_id, vm_id, &update).await?;
+
+        Ok(Volume { distant_id, device })
+    }
+
+    /// Attaches a detached volume back to the instance owning it.
+    ///
+    /// Proxmox volumes belong to the VM they were created for, so only the
+    /// unused volumes of the instance can be attached.
+    async fn attach_volume(&self, id: &str, distant_id: &str) -> Result<Volume, Error> {
+        let (vm_id, node_id) = self.locate(id).await?;
+        let config = self.read_config(&node_id, vm_id).await?;
+
+        match config.device(distant_id) {
+            Some(device) if device.starts_with("scsi") => {
+                return Ok(Volume {
+                    distant_id: distant_id.to_owned(),
+                    device: device.to_owned(),
+                });
+            }
+            Some(_) => {}
+            None => return Err(Error::DistantVolumeNotFound(distant_id.to_owned())),
+        }
+
+        let device = config
+            .free_scsi_device()
+            .ok_or_else(|| Error::NoFreeDevice(id.to_owned()))?;
+
+        let update = VMConfigUpdateOptions {
+            disks: BTreeMap::from([(device.clone(), distant_id.to_owned())]),
+            ..Default::default()
+        };
+        self.update_config(&node_id, vm_id, &update).await?;
+
+        Ok(Volume {
+            distant_id: distant_id.to_owned(),
+            device,
+        })
+    }
+
+    /// Detaches a volume from the instance.
+    ///
+    /// The volume is kept as an unused disk of the instance.
+    async fn detach_volume(&self, id: &str, distant_id: &str) -> Result<(), Error> {
+        let (vm_id, node_id) = self.locate(id).await?;
+        let config = self.read_config(&node_id, vm_id).await?;
+
+        let device = config
+            .device(distant_id)
+            .ok_or_else(|| Error::DistantVolumeNotFound(distant_id.to_owned()))?;
+
+        if !device.starts_with("scsi") {
+            return Ok(());
+        }
+
+        let update = VMConfigUpdateOptions {
+            delete: Some(device.to_owned()),
+            ..Default::default()
+        };
+        self.update_config(&node_id, vm_id, &update).await
+    }
+
+    /// Grows a volume of the instance.
+    ///
+    /// Proxmox only resizes disks attached to the instance.
+    async fn resize_volume(
+        &self,
+        id: &str,
+        distant_id: &str,
+        size_bytes: u64,
+    ) -> Result<(), Error> {
+        let (vm_id, node_id) = self.locate(id).await?;
+        let config = self.read_config(&node_id, vm_id).await?;
+
+        let device = config
+            .device(distant_id)
+            .filter(|device| device.starts_with("scsi"))
+            .ok_or_else(|| Error::DistantVolumeNotFound(distant_id.to_owned()))?;
+
+        api::vm_disk_resize(
+            &self.api_url,
+            &self.client,
+            &self.authorization,
+            &node_id,
+            vm_id,
+            device,
+            size_bytes,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Deletes a volume of the instance.
+    ///
+    /// Attached volumes are detached first, as Proxmox only destroys the
+    /// unused disks of a VM.
+    async fn delete_volume(&self, id: &str, distant_id: &str) -> Result<(), Error> {
+        self.detach_volume(id, distant_id).await?;
+
+        let (vm_id, node_id) = self.locate(id).await?;
+        let device = self
+            .read_config(&node_id, vm_id)
+            .await?
+            .device(distant_id)
+            .map(ToOwned::to_owned)
+            .ok_or_else(|| Error::DistantVolumeNotFound(distant_id.to_owned()))?;
+
+        let update = VMConfigUpdateOptions {
+            delete: Some(device),
+            ..Default::default()
+        };
+        self.update_config(&node_id, vm_id, &update).await
+    }
+
+    /// Lists the ISO and cloud images held by the storage.
+    ///
+    /// The images of a local storage are listed on every node, the ones
+    /// missing from some nodes included.
+    async fn list_images(&self, storage: &str) -> Result<Vec<Image>, Error> {
+        let mut images = BTreeMap::new();
+        for node_id in self.storage_nodes(storage).await? {
+            for image in self.node_images(&node_id, storage).await? {
+                images.entry(image.id.clone()).or_insert(image);
+            }
+        }
+
+        Ok(images.into_values().collect())
+    }
+
+    /// Imports an image from a URL into the storage.
+    ///
+    /// The image is downloaded by the hypervisor itself, on every node of a
+    /// local storage.
+    async fn import_image(
+        &self,
+        storage: &str,
+        options: ImageImportRequest,
+    ) -> Result<Image, Error> {
+        let content = image_content(&options.name)?;
+        let nodes = self.storage_nodes(storage).await?;
+        let download = StorageDownloadUrlOptions {
+            url: options.url,
+            content: content.to_owned(),
+            filename: options.name.clone(),
+        };
+
+        for node_id in &nodes {
+            let task = api::storage_download_url(
+                &self.api_url,
+                &self.client,
+                &self.authorization,
+                node_id,
+                storage,
+                &download,
+            )
+            .await?
+            .data;
+
+            api::helpers::wait_for_task_completion(
+                &self.api_url,
+                &self.client,
+                &self.authorization,
+                node_id,
+                &task,
+            )
+            .await?;
+        }
+
+        let id = format!("{}:{}/{}", storage, content, options.name);
+        self.node_images(&nodes[0], storage)
+            .await?
+            .into_iter()
+            .find(|image| image.id == id)
+            .ok_or(Error::DistantImageNotFound(id))
+    }
+
+    /// Deletes an image from the storage, on every node of a local storage.
+    ///
+    /// Only ISO and cloud images can be deleted, not the disks of instances.
+    async fn delete_image(&self, storage: &str, id: &str) -> Result<(), Error> {
+        let is_image = id
+            .strip_prefix(storage)
+            .and_then(|path| path.strip_prefix(':'))
+            .and_then(|path| path.split_once('/'))
+            .is_some_and(|(content, _)| matches!(content, "import" | "iso"));
+        if !is_image {
+            return Err(Error::DistantImageNotFound(id.to_owned()));
+        }
+
+        for node_id in self.storage_nodes(storage).await? {
+            let task = api::storage_content_delete(
+                &self.api_url,
+                &self.client,
+                &self.authorization,
+                &node_id,
+                storage,
+                id,
+            )
+            .await?
+            .data;
+
+            if let Some(task) = task {
+                api::helpers::wait_for_task_completion(
+                    &self.api_url,
+                    &self.client,
+                    &self.authorization,
+                    &node_id,
+                    &task,
+                )
+                .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Backs the instance up with vzdump to the backup storage.
+    ///
+    /// Running instances are backed up live from a snapshot. The backups of
+    /// the instance are pruned once done, all of them being kept when the
+    /// retention is unset.
+    async fn create_backup(
+        &self,
+        id: &str,
+        owner: Uuid,
+        retention: &BackupRetention,
+    ) -> Result<Backup, Error> {
+        let (vm_id, node_id) = self.locate(id).await?;
+        let options = VzdumpOptions {
+            vmid: vm_id.to_string(),
+            storage: self.backup_storage.clone(),
+            mode: String::from("snapshot"),
+            compress: None,
+            prune_backups: Some(prune_backups(retention)),
+            notes_template: Some(backup_notes(owner)),
+        };
+
+        let task = api::vzdump_create(
+            &self.api_url,
+            &self.client,
+            &self.authorization,
+            &node_id,
+            &options,
+        )
+        .await?
+        .data;
+
+        api::helpers::wait_for_task_completion(
+            &self.api_url,
+            &self.client,
+            &self.authorization,
+            &node_id,
+            &task,
+        )
+        .await?;
+
+        self.vm_backups(&node_id, vm_id, owner)
+            .await?
+            .into_iter()
+            .max_by_key(|backup| backup.created_at)
+            .ok_or(Error::DistantBackupNotFound(id.to_owned()))
+    }
+
+    /// Lists the backups of the instance held by the backup storage.
+    async fn list_backups(&self, id: &str, owner: Uuid) -> Result<Vec<Backup>, Error> {
+        let (vm_id, node_id) = self.locate(id).await?;
+
+        self.vm_backups(&node_id, vm_id, owner).await
+    }
+
+    /// Restores a backup of the instance into a new VM, next to the instance.
+    ///
+    /// The disks of the new VM are restored on the image storage, and its
+    /// network interfaces get new MAC addresses.
+    async fn restore_backup(
+        &self,
+        id: &str,
+        owner: Uuid,
+        backup_id: &str,
+        name: &str,
+    ) -> Result<String, Error> {
+        let (vm_id, node_id) = self.locate(id).await?;
+        self.ensure_vm_backup(&node_id, vm_id, owner, backup_id)
+            .await?;
+
+        let next_id = api::cluster_next_id(&self.api_url, &self.client, &self.authorization)
+            .await?
+            .data;
+
+        let options = VMRestoreOptions {
+            vmid: next_id,
+            archive: backup_id.to_owned(),
+            storage: Some(self.image_storage.clone()),
+            unique: Some(true),
+        };
+
+        let task = api::vm_restore(
+            &self.api_url,
+            &self.client,
+            &self.authorization,
+            &node_id,
+            &options,
+        )
+        .await?
+        .data;
+
+        api::helpers::wait_for_task_completion(
+            &self.api_url,
+            &self.client,
+            &self.authorization,
+            &node_id,
+            &task,
+        )
+        .await?;
+
+        let update = VMConfigUpdateOptions {
+            name: Some(name.to_owned()),
+            ..Default::default()
+        };
+        self.update_config(&node_id, next_id, &update).await?;
+
+        Ok(next_id.to_string())
+    }
+
+    /// Deletes a backup of the instance from the backup storage.
+    async fn delete_backup(&self, id: &str, owner: Uuid, backup_id: &str) -> Result<(), Error> {
+        let (vm_id, node_id) = self.locate(id).await?;
+        self.ensure_vm_backup(&node_id, vm_id, owner, backup_id)
+            .await?;
+
+        let task = api::storage_content_delete(
+            &self.api_url,
+            &self.client,
+            &self.authorization,
+            &node_id,
+            &self.backup_storage,
+            backup_id,
+        )
+        .await?
+        .data;
+
+        if let Some(task) = task {
+            api::helpers::wait_for_task_completion(
+                &self.api_url,
+                &self.client,
+                &self.authorization,
+                &node_id,
+                &task,
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Sets the schedule of the backups of the instance.
+    ///
+    /// Each instance is backed up by its own cluster backup job, created on
+    /// the first schedule and deleted once unset. The job is named after the
+    /// instance rather than the VM, so that the VM taking the id of a deleted
+    /// one never takes its job over.
+    async fn schedule_backups(
+        &self,
+        id: &str,
+        owner: Uuid,
+        schedule: Option<BackupSchedule>,
+    ) -> Result<(), Error> {
+        let (vm_id, _) = self.locate(id).await?;
+        let job_id = backup_job_id(owner);
+
+        let exists = api::backup_job_list(&self.api_url, &self.client, &self.authorization)
+            .await?
+            .data
+            .iter()
+            .any(|job| job.id == job_id);
+
+        match (schedule, exists) {
+            (Some(schedule), exists) => {
+                let options = BackupJobOptions {
+                    id: (!exists).then(|| job_id.clone()),
+                    schedule: schedule.schedule,
+                    vmid: vm_id.to_string(),
+                    storage: self.backup_storage.clone(),
+                    mode: String::from("snapshot"),
+                    enabled: true,
+                    prune_backups: Some(prune_backups(&schedule.retention)),
+                    notes_template: Some(backup_notes(owner)),
+                };
+
+                if exists {
+                    api::backup_job_update(
+                        &self.api_url,
+                        &self.client,
+                        &self.authorization,
+                        &job_id,
+                        &options,
+                    )
+                    .await?;
+                } else {
+                    api::backup_job_create(
+                        &self.api_url,
+                        &self.client,
+                        &self.authorization,
+                        &options,
+                    )
+                    .await?;
+                }
+            }
+            (None, true) => {
+                api::backup_job_delete(&self.api_url, &self.client, &self.authorization, &job_id)
+                    .await?;
+            }
+            (None, false) => {}
+        }
+
+        Ok(())
+    }
+
+    async fn firewall(&self, id: &str) -> Result<Option<Firewall>, Error> {
+        let (vm_id, node_id) = self.locate(id).await?;
+
+        let options = api::vm_firewall_options_read(
+            &self.api_url,
+            &self.client,
+            &self.authorization,
+            &node_id,
+            vm_id,
+        )
+        .await?
+        .data;
+        let mut rules = api::vm_firewall_rule_list(
+            &self.api_url,
+            &self.client,
+            &self.authorization,
+            &node_id,
+            vm_id,
+        )
+        .await?
+        .data;
+        rules.sort_by_key(|rule| rule.pos);
+        let firewall = Firewall {
+            rules: rules.iter().filter_map(managed_rule).collect(),
+        };
+
+        let flagged = self
+            .read_config(&node_id, vm_id)
+            .await?
+            .net0
+            .is_some_and(|net| has_firewall_flag(&net));
+        if !flagged || !enforces(&options, &firewall) {
+            return Ok(None);
+        }
+
+        Ok(Some(firewall))
+    }
+
+    async fn apply_firewall(&self, id: &str, firewall: Option<&Firewall>) -> Result<(), Error> {
+        let (vm_id, node_id) = self.locate(id).await?;
+
+        // Delete the managed rules from the last one, so that the positions
+        // of the others hold
+        let mut positions = api::vm_firewall_rule_list(
+            &self.api_url,
+            &self.client,
+            &self.authorization,
+            &node_id,
+            vm_id,
+        )
+        .await?
+        .data
+        .into_iter()
+        .filter(|rule| managed_rule(rule).is_some())
+        .map(|rule| rule.pos)
+        .collect::<Vec<_>>();
+        positions.sort_unstable_by(|a, b| b.cmp(a));
+        for pos in positions {
+            api::vm_firewall_rule_delete(
+                &self.api_url,
+                &self.client,
+                &self.authorization,
+                &node_id,
+                vm_id,
+                pos,
+            )
+            .await?;
+        }
+
+        let Some(firewall) = firewall else {
+            let options = VMFirewallOptionsUpdate {
+                enable: Some(false),
+                ..Default::default()
+            };
+            api::vm_firewall_options_update(
+                &self.api_url,
+                &self.client,
+                &self.authorization,
+                &node_id,
+                vm_id,
+                &options,
+            )
+            .await?;
+
+            return Ok(());
+        };
+
+        // The firewall only filters the network devices flagged for it
+        if let Some(net0) = self.read_config(&node_id, vm_id).await?.net0
+            && !has_firewall_flag(&net0)
+        {
+            let update = VMConfigUpdateOptions {
+                net0: Some(with_firewall_flag(&net0)),
+                ..Default::default()
+            };
+            self.update_config(&node_id, vm_id, &update).await?;
+        }
+
+        // Rules are inserted on top of the others, the last one first
+        for rule in firewall.rules.iter().rev() {
+            api::vm_firewall_rule_create(
+                &self.api_url,
+                &self.client,
+                &self.authorization,
+                &node_id,
+                vm_id,
+                &rule_options(rule),
+            )
+            .await?;
+        }
+
+        let (policy_in, policy_out) = policies(firewall);
+        let options = VMFirewallOptionsUpdate {
+            enable: Some(true),
+            policy_in: Some(policy_in.to_owned()),
+            policy_out: Some(policy_out.to_owned()),
+        };
+        api::vm_firewall_options_update(
+            &self.api_url,
+            &self.client,
+            &self.authorization,
+            &node_id,
+            vm_id,
+            &options,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn set_user_password(
+        &self,
+        id: &str,
+        username: &str,
+        password: &str,
+    ) -> Result<(), Error> {
+        let (vm_id, node_id) = self.locate(id).await?;
+
+        let options = VMAgentSetUserPasswordOptions {
+            username: username.to_owned(),
+            password: password.to_owned(),
+            ..Default::default()
+        };
+        api::vm_agent_set_user_password(
+            &self.api_url,
+            &self.client,
+            &self.authorization,
+            &node_id,
+            vm_id,
+            &options,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn authorize_ssh_key(
+        &self,
+        id: &str,
+        username: &str,
+        public_key: &str,
+    ) -> Result<(), Error> {
+        let (vm_id, node_id) = self.locate(id).await?;
+
+        let pid = api::vm_agent_exec(
+            &self.api_url,
+            &self.client,
+            &self.authorization,
+            &node_id,
+            vm_id,
+            &guest::authorize_ssh_key(username, public_key),
+        )
+        .await?
+        .data
+        .pid;
+        let status = helpers::wait_for_agent_command(
+            &self.api_url,
+            &self.client,
+            &self.authorization,
+            &node_id,
+            vm_id,
+            pid,
+        )
+        .await?;
+
+        guest::succeeded(&status)
+    }
+
+    async fn configure_wireguard(
+        &self,
+        id: &str,
+        interface: &str,
+        configuration: Option<&str>,
+    ) -> Result<(), Error> {
+        let (vm_id, node_id) = self.locate(id).await?;
+
+        let pid = api::vm_agent_exec(
+            &self.api_url,
+            &self.client,
+            &self.authorization,
+            &node_id,
+            vm_id,
+            &guest::configure_wireguard(interface, configuration),
+        )
+        .await?
+        .data
+        .pid;
+        let status = helpers::wait_for_agent_command(
+            &self.api_url,
+            &self.client,
+            &self.authorization,
+            &node_id,
+            vm_id,
+            pid,
+        )
+        .await?;
+
+        guest::succeeded(&status)
+    }
+
+    /// Migrates the instance to another node of the cluster.
+    ///
+    /// An explicit target node must be online and have room for the instance.
+    /// Migrating an instance to the node holding it does nothing.
+    async fn migrate(&self, id: &str, node: Option<&str>) -> Result<Migration, Error> {
+        let (vm_id, source_node) = self.locate(id).await?;
+        let vm =
+            api::cluster_resources_list(&self.api_url, &self.client, &self.authorization, "vm")
+                .await?
+                .data
+                .into_iter()
+                .find(|resource| resource.vmid == Some(vm_id))
+                .ok_or_else(|| Error::DistantInstanceNotFound(id.to_owned()))?;
+
+        let capacities = self.capacity().await?;
+        let candidates = match node {
+            Some(node) if node == source_node => {
+                return Ok(Migration {
+                    id: id.to_owned(),
+                    target_node: source_node.clone(),
+                    source_node,
+                });
+            }
+            Some(node) => capacities
+                .into_iter()
+                .filter(|capacity| capacity.node == node)
+                .collect::<Vec<_>>(),
+            None => capacities
+                .into_iter()
+                .filter(|capacity| capacity.node != source_node)
+                .collect(),
+        };
+        if let (Some(node), true) = (node, candidates.is_empty()) {
+            return Err(Error::DistantNodeNotFound(node.to_owned()));
+        }
+
+        let target_node = placement::select_node(
+            &candidates,
+            &Default::default(),
+            &placement::migration_request(&vm),
+        )?;
+        self.migrate_vm(
+            vm_id,
+            &source_node,
+            &target_node,
+            vm.status == ResourceStatus::Running,
+        )
+        .await?;
+
+        Ok(Migration {
+            id: id.to_owned(),
+            source_node,
+            target_node,
+        })
+    }
+
+    /// Migrates every VM off a node, one at a time.
+    ///
+    /// The target of each VM is planned upfront, so that nothing moves when
+    /// the other nodes cannot hold them all. A failed migration stops the
+    /// drain, leaving the VMs already migrated on their new node.
+    async fn drain(&self, node: &str) -> Result<Vec<Migration>, Error> {
+        let capacities = self.capacity().await?;
+        if !capacities.iter().any(|capacity| capacity.node == node) {
+            return Err(Error::DistantNodeNotFound(node.to_owned()));
+        }
+        let vms =
+            api::cluster_resources_list(&self.api_url, &self.client, &self.authorization, "vm")
+                .await?
+                .data;
+
+        let mut migrations = vec![];
+        for (vm_id, target_node) in placement::plan_drain(&capacities, &vms, node)? {
+            let online = vms
+                .iter()
+                .any(|vm| vm.vmid == Some(vm_id) && vm.status == ResourceStatus::Running);
+            self.migrate_vm(vm_id, node, &target_node, online).await?;
+
+            migrations.push(Migration {
+                id: vm_id.to_string(),
+                source_node: node.to_owned(),
+                target_node,
+            });
+        }
+
+        Ok(migrations)
+    }
+
+    /// Stops the VM and backs it up to the backup storage.
+    ///
+    /// The VM is stopped first so that the backup holds its latest state, and
+    /// nothing is written to it once exported. The backup is kept whatever the
+    /// retention of the backups of the VM.
+    async fn export(&self, id: &str, owner: Uuid) -> Result<Export, Error> {
+        let (_, node) = self.locate(id).await?;
+        let running = matches!(self.status(id).await?, Status::Running);
+        if running {
+            self.stop(id).await?;
+        }
+
+        let backup = self
+            .create_backup(id, owner, &BackupRetention::default())
+            .await?;
+
+        Ok(Export {
+            id: id.to_owned(),
+            node,
+            archive: backup.id,
+            running,
+        })
+    }
+
+    /// Restores a VM exported by another cluster into a new VM.
+    ///
+    /// The backup storage must be shared by both clusters, under the same
+    /// name. The disks are restored on the storage the capacity is accounted
+    /// on, and the network interfaces keep their MAC addresses. The cloud-init
+    /// snippet stays on the source cluster, so it is detached from the VM.
+    async fn import(
+        &self,
+        export: &Export,
+        node: Option<&str>,
+        request: &PlacementRequest,
+    ) -> Result<Migration, Error> {
+        let candidates = self
+            .capacity()
+            .await?
+            .into_iter()
+            .filter(|capacity| node.is_none_or(|node| capacity.node == node))
+            .collect::<Vec<_>>();
+        if let (Some(node), true) = (node, candidates.is_empty()) {
+            return Err(Error::DistantNodeNotFound(node.to_owned()));
+        }
+        let target_node = placement::select_node(&candidates, &Default::default(), request)?;
+
+        let vm_id = api::cluster_next_id(&self.api_url, &self.client, &self.authorization)
+            .await?
+            .data;
+
+        let options = VMRestoreOptions {
+            vmid: vm_id,
+            archive: export.archive.clone(),
+            storage: Some(self.storage.clone()),
+            unique: None,
+        };
+
+        let task = api::vm_restore(
+            &self.api_url,
+            &self.client,
+            &self.authorization,
+            &target_node,
+            &options,
+        )
+        .await?
+        .data;
+
+        api::helpers::wait_for_task_completion(
+            &self.api_url,
+            &self.client,
+            &self.authorization,
+            &target_node,
+            &task,
+        )
+        .await?;
+
+        let update = VMConfigUpdateOptions {
+            delete: Some(String::from("cicustom")),
+            ..Default::default()
+        };
+        self.update_config(&target_node, vm_id, &update).await?;
+
+        if export.running {
+            self.start(&vm_id.to_string()).await?;
+        }
+
+        Ok(Migration {
+            id: vm_id.to_string(),
+            source_node: export.node.clone(),
+            target_node,
+        })
+    }
 }
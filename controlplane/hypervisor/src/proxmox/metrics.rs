@@ -0,0 +1,148 @@
+//! Usage history of instances.
+//!
+//! Nodes keep the usage history of their VMs in RRD archives of 70 points
+//! each, from one point a minute over the last hour to one point a week over
+//! the last year. The finest archive covering a requested window is read,
+//! and its points are averaged into samples of the requested resolution.
+
+use std::collections::BTreeMap;
+
+use crate::instance::{Metrics, MetricsRequest, MetricsSample};
+
+/// The number of points held by each RRD archive.
+const ARCHIVE_POINTS: u64 = 70;
+
+/// The RRD archives of a node, from the finest to the coarsest, with the
+/// time between two of their points in seconds.
+const TIMEFRAMES: [(&str, u64); 5] = [
+    ("hour", 60),
+    ("day", 30 * 60),
+    ("week", 3 * 60 * 60),
+    ("month", 12 * 60 * 60),
+    ("year", 7 * 24 * 60 * 60),
+];
+
+/// Selects the finest archive going back to the start of the window,
+/// returning its name and the time between two of its points.
+///
+/// Windows starting more than a year ago are served from the yearly archive,
+/// which misses their oldest part.
+pub fn timeframe(now: u64, start: u64) -> (&'static str, u64) {
+    let age = now.saturating_sub(start);
+
+    TIMEFRAMES
+        .into_iter()
+        .find(|(_, step)| age <= step * ARCHIVE_POINTS)
+        .unwrap_or(TIMEFRAMES[TIMEFRAMES.len() - 1])
+}
+
+/// Averages the points of an archive falling in the window into samples of
+/// the requested resolution, aligned on multiples of the resolution.
+///
+/// The resolution cannot be finer than the time between two points of the
+/// archive.
+pub fn downsample(points: Vec<MetricsSample>, request: &MetricsRequest, step: u64) -> Metrics {
+    let resolution = request.resolution_seconds.max(step);
+
+    let mut buckets = BTreeMap::<u64, Vec<MetricsSample>>::new();
+    for point in points
+        .into_iter()
+        .filter(|point| (request.start..=request.end).contains(&point.time))
+    {
+        buckets
+            .entry(point.time - point.time % resolution)
+            .or_default()
+            .push(point);
+    }
+
+    let samples = buckets
+        .into_iter()
+        .map(|(time, points)| average(time, &points))
+        .collect();
+
+    Metrics {
+        resolution_seconds: resolution,
+        samples,
+    }
+}
+
+/// Averages points into a single sample starting at the given time.
+fn average(time: u64, points: &[MetricsSample]) -> MetricsSample {
+    let count = points.len() as f64;
+    let mean = |value: fn(&MetricsSample) -> f64| points.iter().map(value).sum::<f64>() / count;
+
+    MetricsSample {
+        time,
+        cpu_usage_percent: mean(|point| point.cpu_usage_percent),
+        memory_usage_bytes: mean(|point| point.memory_usage_bytes as f64) as u64,
+        max_memory_bytes: points
+            .iter()
+            .map(|point| point.max_memory_bytes)
+            .max()
+            .unwrap_or_default(),
+        disk_read_bytes_per_second: mean(|point| point.disk_read_bytes_per_second),
+        disk_write_bytes_per_second: mean(|point| point.disk_write_bytes_per_second),
+        network_in_bytes_per_second: mean(|point| point.network_in_bytes_per_second),
+        network_out_bytes_per_second: mean(|point| point.network_out_bytes_per_second),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(time: u64, cpu_usage_percent: f64) -> MetricsSample {
+        MetricsSample {
+            time,
+            cpu_usage_percent,
+            memory_usage_bytes: 1024,
+            max_memory_bytes: 4096,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_timeframe_selects_the_finest_archive_covering_the_window() {
+        let now = 1_741_500_000;
+
+        assert_eq!(timeframe(now, now - 3600), ("hour", 60));
+        assert_eq!(timeframe(now, now - 24 * 3600), ("day", 1800));
+        assert_eq!(timeframe(now, now - 7 * 24 * 3600), ("week", 10800));
+        assert_eq!(timeframe(now, now - 1000 * 24 * 3600), ("year", 604800));
+    }
+
+    #[test]
+    fn test_downsample_averages_points_into_buckets() {
+        let points = vec![point(0, 10.0), point(60, 30.0), point(120, 50.0)];
+        let request = MetricsRequest {
+            start: 0,
+            end: 180,
+            resolution_seconds: 120,
+        };
+
+        let metrics = downsample(points, &request, 60);
+
+        assert_eq!(metrics.resolution_seconds, 120);
+        assert_eq!(metrics.samples.len(), 2);
+        assert_eq!(metrics.samples[0].time, 0);
+        assert_eq!(metrics.samples[0].cpu_usage_percent, 20.0);
+        assert_eq!(metrics.samples[0].memory_usage_bytes, 1024);
+        assert_eq!(metrics.samples[1].time, 120);
+        assert_eq!(metrics.samples[1].cpu_usage_percent, 50.0);
+    }
+
+    #[test]
+    fn test_downsample_keeps_the_archive_step_as_finest_resolution() {
+        let points = vec![point(0, 10.0), point(1800, 30.0), point(7200, 50.0)];
+        let request = MetricsRequest {
+            start: 1000,
+            end: 5000,
+            resolution_seconds: 0,
+        };
+
+        let metrics = downsample(points, &request, 1800);
+
+        assert_eq!(metrics.resolution_seconds, 1800);
+        assert_eq!(metrics.samples, vec![point(1800, 30.0)]);
+    }
+}
@@ -0,0 +1,278 @@
+//! Node placement for new and migrated instances.
+//!
+//! Ranks the online nodes of a cluster by their remaining memory, CPU and
+//! storage, discards the ones that cannot fit the requested instance and
+//! steers replicas of a workload away from each other.
+
+use std::collections::HashSet;
+
+use crate::placement::{NodeCapacity, PlacementRequest};
+use crate::proxmox::api::{
+    Error, ResourceStatus,
+    cluster_resources_list::{Resource, ResourceType},
+};
+
+/// Computes the capacity of the online nodes of a cluster.
+///
+/// `storages` holds the storage resources of the cluster, of which only
+/// the one named `storage_name` is accounted for on each node.
+pub fn node_capacities(
+    nodes: &[Resource],
+    storages: &[Resource],
+    storage_name: &str,
+) -> Vec<NodeCapacity> {
+    nodes
+        .iter()
+        .filter(|resource| resource.resource_type == ResourceType::Node)
+        .filter(|resource| resource.status == ResourceStatus::Online)
+        .filter_map(|resource| {
+            let node = resource.node.clone()?;
+            let max_cpu = resource.maxcpu.unwrap_or_default();
+            let max_memory_bytes = resource.maxmem.unwrap_or_default();
+            let storage = storages.iter().find(|storage| {
+                storage.node.as_deref() == Some(node.as_str())
+                    && storage.storage.as_deref() == Some(storage_name)
+            });
+
+            Some(NodeCapacity {
+                max_cpu,
+                free_cpu: max_cpu as f64 * (1.0 - resource.cpu.unwrap_or_default() as f64),
+                max_memory_bytes,
+                free_memory_bytes: max_memory_bytes
+                    .saturating_sub(resource.mem.unwrap_or_default()),
+                max_disk_bytes: storage.and_then(|storage| storage.maxdisk),
+                free_disk_bytes: storage.and_then(|storage| {
+                    Some(
+                        storage
+                            .maxdisk?
+                            .saturating_sub(storage.disk.unwrap_or_default()),
+                    )
+                }),
+                node,
+            })
+        })
+        .collect()
+}
+
+/// Selects the node to place the requested instance on.
+///
+/// Nodes that cannot fit the instance are discarded, and the remaining ones
+/// are ranked by their headroom. Nodes in `occupied` host an instance the new
+/// one should be kept apart from: they are only picked when no other node can
+/// fit the instance.
+pub fn select_node(
+    nodes: &[NodeCapacity],
+    occupied: &HashSet<String>,
+    request: &PlacementRequest,
+) -> Result<String, Error> {
+    if nodes.is_empty() {
+        return Err(Error::NoNodesAvailable);
+    }
+
+    let mut candidates = nodes
+        .iter()
+        .filter(|node| node.fits(request))
+        .map(|node| (occupied.contains(&node.node), node.score(request), node))
+        .collect::<Vec<_>>();
+
+    candidates.sort_by(|(a_occupied, a_score, _), (b_occupied, b_score, _)| {
+        a_occupied
+            .cmp(b_occupied)
+            .then_with(|| b_score.total_cmp(a_score))
+    });
+
+    let (is_occupied, _, node) = candidates
+        .first()
+        .ok_or_else(|| Error::InsufficientCapacity {
+            cores: request.cores,
+            memory_bytes: request.memory_bytes,
+            disk_bytes: request.disk_bytes,
+        })?;
+
+    if *is_occupied {
+        tracing::warn!(
+            "no node satisfies the anti-affinity hints, placing instance on {}",
+            node.node
+        );
+    }
+
+    Ok(node.node.clone())
+}
+
+/// Lists the nodes hosting one of the given instances.
+pub fn occupied_nodes(vms: &[Resource], anti_affinity: &[String]) -> HashSet<String> {
+    vms.iter()
+        .filter(|resource| {
+            resource
+                .vmid
+                .is_some_and(|vmid| anti_affinity.contains(&vmid.to_string()))
+        })
+        .filter_map(|resource| resource.node.clone())
+        .collect()
+}
+
+/// Gets the resources a VM needs on the node it migrates to.
+///
+/// Disks are either on shared storage or copied along with the VM, so only
+/// its CPU and memory are requested.
+pub fn migration_request(vm: &Resource) -> PlacementRequest {
+    PlacementRequest {
+        cores: vm.maxcpu.unwrap_or_default(),
+        memory_bytes: vm.maxmem.unwrap_or_default(),
+        disk_bytes: 0,
+    }
+}
+
+/// Plans the migration of the VMs held by a node onto the other nodes,
+/// returning the target node of each VM.
+///
+/// The VMs needing the most memory are placed first, each one reserving its
+/// CPU and memory on its target node. The whole plan fails when a VM fits
+/// on no other node, so a drain never starts without being able to finish.
+pub fn plan_drain(
+    nodes: &[NodeCapacity],
+    vms: &[Resource],
+    node: &str,
+) -> Result<Vec<(u32, String)>, Error> {
+    let mut targets = nodes
+        .iter()
+        .filter(|candidate| candidate.node != node)
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let mut drained = vms
+        .iter()
+        .filter(|vm| vm.resource_type == ResourceType::Qemu)
+        .filter(|vm| vm.node.as_deref() == Some(node))
+        .filter_map(|vm| Some((vm.vmid?, migration_request(vm))))
+        .collect::<Vec<_>>();
+    drained.sort_by_key(|(_, request)| std::cmp::Reverse(request.memory_bytes));
+
+    drained
+        .into_iter()
+        .map(|(vm_id, request)| {
+            let target = select_node(&targets, &HashSet::new(), &request)?;
+            if let Some(capacity) = targets.iter_mut().find(|capacity| capacity.node == target) {
+                capacity.free_cpu = (capacity.free_cpu - request.cores as f64).max(0.0);
+                capacity.free_memory_bytes = capacity
+                    .free_memory_bytes
+                    .saturating_sub(request.memory_bytes);
+            }
+
+            Ok((vm_id, target))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GIB: u64 = 1024 * 1024 * 1024;
+
+    fn node(name: &str, free_cpu: f64, free_memory_gib: u64) -> NodeCapacity {
+        NodeCapacity {
+            node: name.to_owned(),
+            max_cpu: 16,
+            free_cpu,
+            max_memory_bytes: 64 * GIB,
+            free_memory_bytes: free_memory_gib * GIB,
+            max_disk_bytes: Some(1024 * GIB),
+            free_disk_bytes: Some(512 * GIB),
+        }
+    }
+
+    fn request(cores: u32, memory_gib: u64) -> PlacementRequest {
+        PlacementRequest {
+            cores,
+            memory_bytes: memory_gib * GIB,
+            disk_bytes: 20 * GIB,
+        }
+    }
+
+    fn vm(vmid: u32, node: &str, memory_gib: u64) -> Resource {
+        serde_json::from_value(serde_json::json!({
+            "type": "qemu",
+            "vmid": vmid,
+            "node": node,
+            "status": "running",
+            "maxcpu": 2,
+            "maxmem": memory_gib * GIB,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_select_node_prefers_the_least_loaded_node() {
+        let nodes = vec![node("pve-node1", 2.0, 8), node("pve-node2", 12.0, 48)];
+
+        let selected = select_node(&nodes, &HashSet::new(), &request(2, 4));
+
+        assert_eq!(selected.unwrap(), "pve-node2");
+    }
+
+    #[test]
+    fn test_select_node_refuses_nodes_that_cannot_fit() {
+        let nodes = vec![node("pve-node1", 12.0, 2), node("pve-node2", 1.0, 8)];
+
+        let selected = select_node(&nodes, &HashSet::new(), &request(2, 4));
+
+        assert_eq!(selected.unwrap(), "pve-node2");
+        assert!(matches!(
+            select_node(&nodes, &HashSet::new(), &request(32, 4)),
+            Err(Error::InsufficientCapacity { cores: 32, .. })
+        ));
+    }
+
+    #[test]
+    fn test_select_node_honours_anti_affinity_hints() {
+        let nodes = vec![node("pve-node1", 12.0, 48), node("pve-node2", 2.0, 8)];
+        let occupied = HashSet::from(["pve-node1".to_owned()]);
+
+        let selected = select_node(&nodes, &occupied, &request(2, 4));
+        assert_eq!(selected.unwrap(), "pve-node2");
+
+        // The hint is relaxed when no other node can fit the instance.
+        let selected = select_node(&nodes, &occupied, &request(2, 16));
+        assert_eq!(selected.unwrap(), "pve-node1");
+    }
+
+    #[test]
+    fn test_select_node_without_nodes() {
+        assert!(matches!(
+            select_node(&[], &HashSet::new(), &request(1, 1)),
+            Err(Error::NoNodesAvailable)
+        ));
+    }
+
+    #[test]
+    fn test_plan_drain_spreads_the_vms_over_the_other_nodes() {
+        let nodes = vec![
+            node("pve-node1", 12.0, 48),
+            node("pve-node2", 12.0, 20),
+            node("pve-node3", 12.0, 16),
+        ];
+        let vms = vec![
+            vm(100, "pve-node1", 8),
+            vm(101, "pve-node1", 16),
+            vm(102, "pve-node2", 4),
+        ];
+
+        let plan = plan_drain(&nodes, &vms, "pve-node1").unwrap();
+
+        assert_eq!(
+            plan,
+            [(101, "pve-node2".to_owned()), (100, "pve-node3".to_owned())]
+        );
+    }
+
+    #[test]
+    fn test_plan_drain_fails_when_a_vm_fits_nowhere() {
+        let nodes = vec![node("pve-node1", 12.0, 48), node("pve-node2", 12.0, 20)];
+        let vms = vec![vm(100, "pve-node1", 16), vm(101, "pve-node1", 16)];
+
+        let plan = plan_drain(&nodes, &vms, "pve-node1");
+
+        assert!(matches!(plan, Err(Error::InsufficientCapacity { .. })));
+    }
+}
@@ -0,0 +1,407 @@
+//! Storage of the cloud-init snippets referenced by instances.
+//!
+//! A snippet is stored on a Proxmox storage holding the `snippets` content
+//! type, and referenced by its volume id (e.g.
+//! `nfs-snippets:snippets/vm-100-user.yaml`) in the `cicustom` option of the
+//! VM. The Proxmox API cannot upload snippets, so they are written to the
+//! storage by one of two transports, selected per hypervisor:
+//!
+//! - `volume` writes snippets to a volume the control plane shares with the
+//!   hypervisor, mounted at `PROXMOX_VOLUME_ABSOLUTE_PATH`.
+//! - `sftp` writes snippets over SFTP to the node of the instance, at the path
+//!   the storage is mounted at on the node. The control plane logs in as
+//!   `PROXMOX_SSH_USER` (`root` by default) with the `PROXMOX_SSH_KEY` private
+//!   key, and only trusts the host keys of its known hosts.
+//!
+//! The storage holding the snippets is selected by `PROXMOX_SNIPPETS_STORAGE`.
+//! The environment is read once, into the [`SnippetsConfig`] the hypervisors
+//! are resolved with.
+
+use std::str::FromStr;
+
+use fake::Dummy;
+use openssh::{KnownHosts, SessionBuilder};
+use openssh_sftp_client::error::SftpErrorKind;
+use openssh_sftp_client::{Sftp, SftpOptions};
+use strum_macros::{Display, EnumString};
+use tokio::fs::File;
+use tokio::io::AsyncWriteExt;
+
+use crate::Error;
+use crate::proxmox::api;
+
+/// The storage holding the snippets when none is configured.
+pub const DEFAULT_STORAGE: &str = "nfs-snippets";
+
+/// The content type snippets are stored as.
+const CONTENT: &str = "snippets";
+
+/// The user the nodes are logged in as when none is configured.
+const DEFAULT_SSH_USER: &str = "root";
+
+/// Transport the snippets of a hypervisor are written to its storage with.
+#[derive(Clone, Copy, Debug, Default, Display, Dummy, EnumString, PartialEq)]
+#[strum(serialize_all = "UPPERCASE")]
+pub enum SnippetsTransport {
+    /// A volume the control plane shares with the hypervisor.
+    #[default]
+    Volume,
+
+    /// SFTP to the nodes of the hypervisor.
+    #[dummy(skip)]
+    Sftp,
+}
+
+impl From<String> for SnippetsTransport {
+    fn from(value: String) -> Self {
+        SnippetsTransport::from_str(&value).expect("could not parse value to snippets transport")
+    }
+}
+
+impl From<SnippetsTransport> for String {
+    fn from(value: SnippetsTransport) -> Self {
+        value.to_string()
+    }
+}
+
+pub trait SnippetStorage {
+    /// Stores a snippet on a node, returning its volume id.
+    fn store(
+        &self,
+        node_id: &str,
+        filename: &str,
+        content: &str,
+    ) -> impl Future<Output = Result<String, Error>> + Send;
+
+    /// Deletes a snippet from a node by its volume id.
+    fn delete(&self, node_id: &str, volume: &str)
+    -> impl Future<Output = Result<(), Error>> + Send;
+}
+
+/// Stores snippets over SFTP on the nodes of the hypervisor.
+#[derive(Clone)]
+pub struct SftpSnippetStorage {
+    pub api_url: String,
+    pub client: reqwest::Client,
+    pub authorization: String,
+    pub storage: String,
+    /// The user the nodes are logged in as.
+    pub user: String,
+    /// The path of the private key the nodes are logged in with, the default
+    /// keys of the user when unset.
+    pub key: Option<String>,
+}
+
+impl SftpSnippetStorage {
+    /// Gets the address of a node on the cluster network.
+    async fn node_address(&self, node_id: &str) -> Result<String, Error> {
+        api::cluster_status(&self.api_url, &self.client, &self.authorization)
+            .await?
+            .data
+            .into_iter()
+            .find(|status| status.kind == "node" && status.name == node_id)
+            .and_then(|status| status.ip)
+            .ok_or_else(|| Error::DistantNodeNotFound(node_id.to_owned()))
+    }
+
+    /// Gets the path of a snippet on the nodes, under the path the storage is
+    /// mounted at.
+    async fn path(&self, storage: &str, relative_path: &str) -> Result<String, Error> {
+        let mount = api::storage_read(&self.api_url, &self.client, &self.authorization, storage)
+            .await?
+            .data
+            .path
+            .ok_or_else(|| Error::DistantStorageNotFound(storage.to_owned()))?;
+
+        Ok(format!("{}/{}", mount, relative_path))
+    }
+
+    /// Opens an SFTP session on a node.
+    async fn connect(&self, node_id: &str) -> Result<Sftp, Error> {
+        let address = self.node_address(node_id).await?;
+
+        let mut builder = SessionBuilder::default();
+        builder
+            .user(self.user.clone())
+            .known_hosts_check(KnownHosts::Strict);
+        if let Some(key) = &self.key {
+            builder.keyfile(key);
+        }
+        let session = builder
+            .connect(&address)
+            .await
+            .map_err(openssh_sftp_client::Error::from)?;
+
+        Ok(Sftp::from_session(session, SftpOptions::default()).await?)
+    }
+}
+
+impl SnippetStorage for SftpSnippetStorage {
+    async fn store(&self, node_id: &str, filename: &str, content: &str) -> Result<String, Error> {
+        let path = self
+            .path(&self.storage, &format!("{}/{}", CONTENT, filename))
+            .await?;
+        let sftp = self.connect(node_id).await?;
+
+        let mut file = sftp
+            .options()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .await
+            .map_err(|e| {
+                tracing::error!(path, error = %e, "could not create snippet file");
+                Error::SnippetFileExists(filename.to_owned())
+            })?;
+        file.write_all(content.as_bytes()).await?;
+        file.close().await?;
+        sftp.close().await?;
+
+        Ok(volume_id(&self.storage, filename))
+    }
+
+    async fn delete(&self, node_id: &str, volume: &str) -> Result<(), Error> {
+        let (storage, relative_path) = volume
+            .split_once(':')
+            .ok_or_else(|| Error::MalformedSnippetVolume(volume.to_owned()))?;
+        let path = self.path(storage, relative_path).await?;
+        let sftp = self.connect(node_id).await?;
+
+        let result = sftp.fs().remove_file(&path).await;
+        sftp.close().await?;
+        match result {
+            Err(openssh_sftp_client::Error::SftpError(SftpErrorKind::NoSuchFile, _)) | Ok(()) => {
+                Ok(())
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Stores snippets on a volume shared with the hypervisor.
+#[derive(Clone)]
+pub struct VolumeSnippetStorage {
+    /// The path the storage is mounted at.
+    pub path: String,
+    pub storage: String,
+}
+
+impl SnippetStorage for VolumeSnippetStorage {
+    async fn store(&self, _node_id: &str, filename: &str, content: &str) -> Result<String, Error> {
+        let path = format!("{}/{}/{}", self.path, CONTENT, filename);
+        let mut file = File::create_new(&path).await.map_err(|e| {
+            tracing::error!(path, error = %e, "could not create snippet file");
+            Error::SnippetFileExists(filename.to_owned())
+        })?;
+        file.write_all(content.as_bytes()).await?;
+
+        Ok(volume_id(&self.storage, filename))
+    }
+
+    async fn delete(&self, _node_id: &str, volume: &str) -> Result<(), Error> {
+        let relative_path = volume
+            .strip_prefix(&self.storage)
+            .and_then(|path| path.strip_prefix(':'))
+            .ok_or_else(|| Error::MalformedSnippetVolume(volume.to_owned()))?;
+
+        match tokio::fs::remove_file(format!("{}/{}", self.path, relative_path)).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Configuration of the snippet storage, shared by the hypervisors.
+#[derive(Clone, Debug)]
+pub struct SnippetsConfig {
+    /// The storage holding the snippets.
+    pub storage: String,
+    /// The path the storage is mounted at on the control plane, for the
+    /// `volume` transport.
+    pub volume_path: String,
+    /// The user the nodes are logged in as, for the `sftp` transport.
+    pub ssh_user: String,
+    /// The path of the private key the nodes are logged in with, for the
+    /// `sftp` transport.
+    pub ssh_key: Option<String>,
+}
+
+impl Default for SnippetsConfig {
+    fn default() -> Self {
+        Self {
+            storage: DEFAULT_STORAGE.to_owned(),
+            volume_path: crate::proxmox::VOLUME_ABSOLUTE_PATH.to_owned(),
+            ssh_user: DEFAULT_SSH_USER.to_owned(),
+            ssh_key: None,
+        }
+    }
+}
+
+impl SnippetsConfig {
+    /// Reads the configuration from the environment, falling back on the
+    /// defaults for the unset variables.
+    pub fn from_env() -> Self {
+        let default = Self::default();
+
+        Self {
+            storage: std::env::var("PROXMOX_SNIPPETS_STORAGE").unwrap_or(default.storage),
+            volume_path: std::env::var("PROXMOX_VOLUME_ABSOLUTE_PATH")
+                .unwrap_or(default.volume_path),
+            ssh_user: std::env::var("PROXMOX_SSH_USER").unwrap_or(default.ssh_user),
+            ssh_key: std::env::var("PROXMOX_SSH_KEY").ok(),
+        }
+    }
+}
+
+/// The snippet storage backends.
+#[derive(Clone)]
+pub enum Snippets {
+    Sftp(SftpSnippetStorage),
+    Volume(VolumeSnippetStorage),
+}
+
+impl Snippets {
+    /// Selects the backend of the transport of a hypervisor.
+    pub fn new(
+        config: &SnippetsConfig,
+        transport: SnippetsTransport,
+        api_url: &str,
+        client: &reqwest::Client,
+        authorization: &str,
+    ) -> Self {
+        match transport {
+            SnippetsTransport::Sftp => Snippets::Sftp(SftpSnippetStorage {
+                api_url: api_url.to_owned(),
+                client: client.clone(),
+                authorization: authorization.to_owned(),
+                storage: config.storage.clone(),
+                user: config.ssh_user.clone(),
+                key: config.ssh_key.clone(),
+            }),
+            SnippetsTransport::Volume => Snippets::Volume(VolumeSnippetStorage {
+                path: config.volume_path.clone(),
+                storage: config.storage.clone(),
+            }),
+        }
+    }
+}
+
+impl SnippetStorage for Snippets {
+    async fn store(&self, node_id: &str, filename: &str, content: &str) -> Result<String, Error> {
+        match self {
+            Snippets::Sftp(storage) => storage.store(node_id, filename, content).await,
+            Snippets::Volume(storage) => storage.store(node_id, filename, content).await,
+        }
+    }
+
+    async fn delete(&self, node_id: &str, volume: &str) -> Result<(), Error> {
+        match self {
+            Snippets::Sftp(storage) => storage.delete(node_id, volume).await,
+            Snippets::Volume(storage) => storage.delete(node_id, volume).await,
+        }
+    }
+}
+
+/// Gets the volume id of a snippet stored on a storage.
+fn volume_id(storage: &str, filename: &str) -> String {
+    format!("{}:{}/{}", storage, CONTENT, filename)
+}
+
+/// Gets the volume id of the user snippet referenced by a `cicustom` option
+/// (e.g. `user=nfs-snippets:snippets/vm-100-user.yaml,meta=...`).
+pub fn user_volume(cicustom: &str) -> Option<&str> {
+    cicustom
+        .split(',')
+        .find_map(|part| part.strip_prefix("user="))
+}
+
+/// Gets the name of the file behind a snippet volume id.
+pub fn filename(volume: &str) -> Option<&str> {
+    volume.rsplit_once('/').map(|(_, filename)| filename)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{WithClusterStatusMock, WithStorageReadMock};
+    use mock_server::MockServer;
+
+    #[test]
+    fn test_user_volume() {
+        assert_eq!(
+            user_volume("user=nfs-snippets:snippets/vm-100-user.yaml"),
+            Some("nfs-snippets:snippets/vm-100-user.yaml")
+        );
+        assert_eq!(
+            user_volume("meta=local:snippets/meta.yaml,user=local:snippets/user.yaml"),
+            Some("local:snippets/user.yaml")
+        );
+        assert_eq!(user_volume("meta=local:snippets/meta.yaml"), None);
+    }
+
+    #[test]
+    fn test_filename() {
+        assert_eq!(
+            filename("nfs-snippets:snippets/vm-100-user.yaml"),
+            Some("vm-100-user.yaml")
+        );
+        assert_eq!(filename("nfs-snippets"), None);
+    }
+
+    #[tokio::test]
+    async fn test_sftp_snippet_storage_locates_the_snippet_on_the_node() {
+        let server = MockServer::new()
+            .await
+            .with_cluster_status()
+            .with_storage_read();
+        let storage = SftpSnippetStorage {
+            api_url: server.url(),
+            client: reqwest::Client::new(),
+            authorization: String::new(),
+            storage: "nfs-snippets".to_owned(),
+            user: DEFAULT_SSH_USER.to_owned(),
+            key: None,
+        };
+
+        let address = storage.node_address("pve-node2").await.unwrap();
+        let path = storage
+            .path("nfs-snippets", "snippets/vm-100-user.yaml")
+            .await
+            .unwrap();
+        let unknown = storage.node_address("pve-node3").await;
+
+        assert_eq!(address, "10.0.0.12");
+        assert_eq!(path, "/mnt/pve/nfs-snippets/snippets/vm-100-user.yaml");
+        assert!(matches!(unknown, Err(Error::DistantNodeNotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_volume_snippet_storage_roundtrip() {
+        let path = std::env::temp_dir().join(format!("snippets-{}", uuid::Uuid::new_v4()));
+        tokio::fs::create_dir_all(path.join(CONTENT)).await.unwrap();
+        let storage = VolumeSnippetStorage {
+            path: path.to_string_lossy().into_owned(),
+            storage: "nfs-snippets".to_owned(),
+        };
+
+        let volume = storage
+            .store("pve-node1", "vm-100-user.yaml", "#cloud-config\n")
+            .await
+            .unwrap();
+        let written = tokio::fs::read_to_string(path.join("snippets/vm-100-user.yaml"))
+            .await
+            .unwrap();
+        let duplicate = storage
+            .store("pve-node1", "vm-100-user.yaml", "#cloud-config\n")
+            .await;
+        storage.delete("pve-node1", &volume).await.unwrap();
+
+        assert_eq!(volume, "nfs-snippets:snippets/vm-100-user.yaml");
+        assert_eq!(written, "#cloud-config\n");
+        assert!(matches!(duplicate, Err(Error::SnippetFileExists(_))));
+        assert!(!path.join("snippets/vm-100-user.yaml").exists());
+        assert!(storage.delete("pve-node1", &volume).await.is_ok());
+
+        tokio::fs::remove_dir_all(path).await.unwrap();
+    }
+}
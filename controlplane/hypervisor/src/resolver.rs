@@ -1,10 +1,121 @@
-pub fn resolve(url: String, token: String) -> impl crate::instance::Instances {
-    crate::proxmox::instance::ProxmoxInstanceService {
-        api_url: url,
-        client: reqwest::Client::builder()
-            .redirect(reqwest::redirect::Policy::none())
-            .build()
-            .unwrap(),
-        authorization: token,
+use std::sync::Arc;
+
+use frn_crypto::{EnvelopeCiphertext, Kek};
+use reqwest::header::{AUTHORIZATION, HeaderValue};
+use uuid::Uuid;
+
+use crate::connector::{Connector, HypervisorKind};
+use crate::kubevirt::KubeVirtConfig;
+use crate::kubevirt::instance::KubeVirtInstanceService;
+use crate::proxmox::StoragesConfig;
+use crate::proxmox::snippet::{Snippets, SnippetsConfig, SnippetsTransport};
+
+/// Configuration of the connector of a hypervisor, derived from its record.
+pub struct ConnectorConfig {
+    /// The id of the hypervisor, its API token is bound to.
+    pub id: Uuid,
+    /// The url of the hypervisor API.
+    pub url: String,
+    /// The kind of platform the hypervisor runs on.
+    pub kind: HypervisorKind,
+    /// The API token, envelope-encrypted.
+    pub token: EnvelopeCiphertext,
+    /// The algorithm the API token is encrypted with.
+    pub token_algorithm: String,
+    /// The storage the capacity of a Proxmox hypervisor is accounted on.
+    pub storage: String,
+    /// The transport the snippets of a Proxmox hypervisor are written with.
+    pub snippets_transport: SnippetsTransport,
+}
+
+/// Resolves the connectors of the hypervisors, with the key their API tokens
+/// are encrypted with and the configurations of the platforms they run on, all
+/// loaded once at startup.
+#[derive(Clone)]
+pub struct Resolver {
+    kek: Arc<Kek>,
+    snippets: Arc<SnippetsConfig>,
+    storages: Arc<StoragesConfig>,
+    kubevirt: Arc<KubeVirtConfig>,
+}
+
+impl Resolver {
+    /// Creates a new resolver.
+    pub fn new(
+        kek: Arc<Kek>,
+        snippets: SnippetsConfig,
+        storages: StoragesConfig,
+        kubevirt: KubeVirtConfig,
+    ) -> Self {
+        Self {
+            kek,
+            snippets: Arc::new(snippets),
+            storages: Arc::new(storages),
+            kubevirt: Arc::new(kubevirt),
+        }
+    }
+
+    /// Gets the key the API tokens of the hypervisors are encrypted with.
+    pub fn kek(&self) -> &Arc<Kek> {
+        &self.kek
+    }
+
+    /// Resolves the connector of a hypervisor, decrypting its API token.
+    ///
+    /// The token of a Proxmox hypervisor is the whole value of its
+    /// authorization header, while the token of a KubeVirt one is the bearer
+    /// token of a service account of its cluster.
+    pub fn resolve(
+        &self,
+        config: ConnectorConfig,
+    ) -> Result<impl crate::instance::Instances + use<>, crate::Error> {
+        let token =
+            crate::token::decrypt(&self.kek, config.id, &config.token, &config.token_algorithm)?;
+
+        match config.kind {
+            HypervisorKind::Proxmox => {
+                let client = reqwest::Client::builder()
+                    .redirect(reqwest::redirect::Policy::none())
+                    .build()
+                    .unwrap();
+
+                Ok(Connector::Proxmox(
+                    crate::proxmox::instance::ProxmoxInstanceService {
+                        snippets: Snippets::new(
+                            &self.snippets,
+                            config.snippets_transport,
+                            &config.url,
+                            &client,
+                            &token,
+                        ),
+                        api_url: config.url,
+                        client,
+                        authorization: token,
+                        storage: config.storage,
+                        image_storage: self.storages.image.clone(),
+                        backup_storage: self.storages.backup.clone(),
+                    },
+                ))
+            }
+            HypervisorKind::KubeVirt => {
+                let mut kube_config = kube::Config::new(
+                    config
+                        .url
+                        .parse()
+                        .map_err(|err| crate::Error::Other(Box::new(err)))?,
+                );
+                kube_config.root_cert = self.kubevirt.root_certificates.clone();
+                let mut authorization = HeaderValue::from_str(&format!("Bearer {}", token))
+                    .map_err(|err| crate::Error::Other(Box::new(err)))?;
+                authorization.set_sensitive(true);
+                kube_config.headers.push((AUTHORIZATION, authorization));
+
+                Ok(Connector::KubeVirt(KubeVirtInstanceService {
+                    client: kube::Client::try_from(kube_config)?,
+                    namespace: self.kubevirt.namespace.clone(),
+                    storage_class: self.kubevirt.storage_class.clone(),
+                }))
+            }
+        }
     }
 }
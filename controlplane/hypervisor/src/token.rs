@@ -0,0 +1,87 @@
+//! Encryption at rest of the API tokens authenticating against hypervisors.
+//!
+//! A token grants root access to a cluster, so it is only ever stored
+//! envelope-encrypted with [`frn_crypto`], under additional data binding it to
+//! the hypervisor it belongs to: a ciphertext copied to another hypervisor row
+//! fails to decrypt. Tokens are decrypted by [`crate::Resolver`] only.
+
+use frn_crypto::{CURRENT_KEY_VERSION, EncryptionError, EnvelopeCiphertext, Kek};
+use uuid::Uuid;
+
+use crate::Error;
+
+/// Encrypts the API token of a hypervisor.
+pub fn encrypt(kek: &Kek, hypervisor_id: Uuid, token: &str) -> Result<EnvelopeCiphertext, Error> {
+    let aad = aad(hypervisor_id, CURRENT_KEY_VERSION);
+    frn_crypto::encrypt(kek, token.as_bytes(), &aad, CURRENT_KEY_VERSION).map_err(Into::into)
+}
+
+/// Decrypts the API token of a hypervisor, encrypted with `algorithm`.
+pub(crate) fn decrypt(
+    kek: &Kek,
+    hypervisor_id: Uuid,
+    token: &EnvelopeCiphertext,
+    algorithm: &str,
+) -> Result<String, Error> {
+    if algorithm != frn_crypto::ALGORITHM {
+        return Err(Error::UnsupportedTokenAlgorithm(algorithm.to_owned()));
+    }
+
+    let aad = aad(hypervisor_id, token.key_version);
+    let plaintext = frn_crypto::decrypt(kek, token, &aad)?;
+    String::from_utf8(plaintext).map_err(|_| EncryptionError::DecryptFailed.into())
+}
+
+/// Builds the additional data bound to a token: the hypervisor id followed by
+/// the version of the key wrapping it.
+fn aad(hypervisor_id: Uuid, key_version: i32) -> Vec<u8> {
+    let mut aad = hypervisor_id.as_bytes().to_vec();
+    aad.extend_from_slice(&key_version.to_be_bytes());
+    aad
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_roundtrip() {
+        let kek = Kek::from_bytes([7u8; 32]);
+        let id = Uuid::new_v4();
+
+        let token = encrypt(&kek, id, "PVEAPIToken=root@pam!api=secret").unwrap();
+
+        assert!(!token.ciphertext.windows(6).any(|w| w == b"secret"));
+        assert_eq!(
+            decrypt(&kek, id, &token, frn_crypto::ALGORITHM).unwrap(),
+            "PVEAPIToken=root@pam!api=secret"
+        );
+    }
+
+    #[test]
+    fn test_token_is_bound_to_its_hypervisor() {
+        let kek = Kek::from_bytes([7u8; 32]);
+        let token = encrypt(&kek, Uuid::new_v4(), "PVEAPIToken=root@pam!api=secret").unwrap();
+
+        let result = decrypt(&kek, Uuid::new_v4(), &token, frn_crypto::ALGORITHM);
+
+        assert!(matches!(
+            result,
+            Err(Error::TokenEncryption(EncryptionError::DecryptFailed))
+        ));
+    }
+
+    #[test]
+    fn test_token_is_bound_to_its_algorithm() {
+        let kek = Kek::from_bytes([7u8; 32]);
+        let id = Uuid::new_v4();
+        let token = encrypt(&kek, id, "PVEAPIToken=root@pam!api=secret").unwrap();
+
+        let result = decrypt(&kek, id, &token, "aes-256-gcm");
+
+        assert!(matches!(
+            result,
+            Err(Error::UnsupportedTokenAlgorithm(algorithm)) if algorithm == "aes-256-gcm"
+        ));
+    }
+}
@@ -0,0 +1,285 @@
+//! End-to-end tests for the KubeVirt connector against a real API server.
+//!
+//! The connector only manages `VirtualMachine` resources, so neither KubeVirt
+//! nor a node is needed: the tests install permissive definitions of the
+//! KubeVirt resources on a bare `kube-apiserver` + `etcd` (the envtest
+//! assets), and play the part of the KubeVirt controller by writing the
+//! status of the VMs themselves.
+//!
+//! They are opt-in: `#[ignore]`d by default and gated on `E2E_KUBECONFIG`
+//! pointing at a ready cluster. The `scripts/run-with-envtest.sh` helper spins
+//! one up and sets `E2E_KUBECONFIG` for the wrapped command:
+//!
+//! ```text
+//! scripts/run-with-envtest.sh cargo test -p hypervisor --test kubevirt_instances -- --ignored
+//! ```
+//!
+//! Set `E2E_SUFFIX` (e.g. to `$CI_JOB_ID`) to make the created namespace
+//! unique, so runs against a shared cluster do not collide.
+
+use std::net::Ipv4Addr;
+use std::time::Duration;
+
+use hypervisor::instance::{InstanceCreateRequest, Instances, Status};
+use hypervisor::kubevirt::instance::KubeVirtInstanceService;
+use hypervisor::kubevirt::resources::{
+    RUN_STRATEGY_ALWAYS, RUN_STRATEGY_HALTED, virtual_machine_instance_resource,
+    virtual_machine_resource,
+};
+use k8s_openapi::api::core::v1::Namespace;
+use k8s_openapi::apiextensions_apiserver::pkg::apis::apiextensions::v1::CustomResourceDefinition;
+use kube::Api;
+use kube::api::{DynamicObject, Patch, PatchParams, PostParams};
+use serde_json::json;
+
+const GIB: u64 = 1024 * 1024 * 1024;
+
+/// Appends the optional `E2E_SUFFIX` so parallel runs or a shared cluster do
+/// not race on a fixed name.
+fn res_name(base: &str) -> String {
+    match std::env::var("E2E_SUFFIX") {
+        Ok(suffix) if !suffix.is_empty() => format!("{base}-{suffix}"),
+        _ => base.to_owned(),
+    }
+}
+
+async fn kube_client() -> kube::Client {
+    let path = std::env::var("E2E_KUBECONFIG").expect("E2E_KUBECONFIG must point at a kubeconfig");
+    let kubeconfig = kube::config::Kubeconfig::read_from(path).expect("could not read kubeconfig");
+    let config = kube::Config::from_custom_kubeconfig(
+        kubeconfig,
+        &kube::config::KubeConfigOptions::default(),
+    )
+    .await
+    .expect("could not build kube config");
+    kube::Client::try_from(config).expect("could not build kube client")
+}
+
+/// Installs a definition of a KubeVirt resource accepting any content, and
+/// waits for it to be served.
+async fn install_definition(client: &kube::Client, kind: &str, plural: &str) {
+    let name = format!("{plural}.kubevirt.io");
+    let definition: CustomResourceDefinition = serde_json::from_value(json!({
+        "apiVersion": "apiextensions.k8s.io/v1",
+        "kind": "CustomResourceDefinition",
+        "metadata": { "name": name },
+        "spec": {
+            "group": "kubevirt.io",
+            "names": { "kind": kind, "plural": plural },
+            "scope": "Namespaced",
+            "versions": [{
+                "name": "v1",
+                "served": true,
+                "storage": true,
+                "schema": {
+                    "openAPIV3Schema": {
+                        "type": "object",
+                        "x-kubernetes-preserve-unknown-fields": true,
+                    },
+                },
+            }],
+        },
+    }))
+    .expect("the definition should be valid");
+
+    let definitions = Api::<CustomResourceDefinition>::all(client.clone());
+    definitions
+        .patch(
+            &name,
+            &PatchParams::apply("frn-e2e").force(),
+            &Patch::Apply(definition),
+        )
+        .await
+        .expect("definition installation must succeed");
+
+    for _ in 0..50 {
+        let established = definitions
+            .get(&name)
+            .await
+            .expect("definition lookup must succeed")
+            .status
+            .and_then(|status| status.conditions)
+            .into_iter()
+            .flatten()
+            .any(|condition| condition.type_ == "Established" && condition.status == "True");
+        if established {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("the definition of {kind} was not established");
+}
+
+/// Installs the KubeVirt resources, and creates a namespace holding the VMs
+/// of the test.
+async fn service(base: &str) -> KubeVirtInstanceService {
+    let client = kube_client().await;
+    install_definition(&client, "VirtualMachine", "virtualmachines").await;
+    install_definition(&client, "VirtualMachineInstance", "virtualmachineinstances").await;
+
+    let namespace = res_name(base);
+    let namespaces = Api::<Namespace>::all(client.clone());
+    if namespaces
+        .get_opt(&namespace)
+        .await
+        .expect("namespace lookup must succeed")
+        .is_none()
+    {
+        let mut object = Namespace::default();
+        object.metadata.name = Some(namespace.clone());
+        namespaces
+            .create(&PostParams::default(), &object)
+            .await
+            .expect("namespace creation must succeed");
+    }
+
+    KubeVirtInstanceService {
+        client,
+        namespace,
+        storage_class: None,
+    }
+}
+
+fn create_request(id: &str) -> InstanceCreateRequest {
+    InstanceCreateRequest {
+        id: id.to_owned(),
+        cores: 2,
+        disk_bytes: 20 * GIB,
+        disk_image: "docker://quay.io/containerdisks/debian:12".to_owned(),
+        memory_bytes: 2 * GIB,
+        name: "web".to_owned(),
+        snippet: "#cloud-config\n".to_owned(),
+        anti_affinity: vec![],
+    }
+}
+
+#[tokio::test]
+#[ignore = "requires a Kubernetes cluster via E2E_KUBECONFIG"]
+async fn instance_goes_through_its_lifecycle() {
+    let service = service("frn-e2e-kubevirt-lifecycle").await;
+    let vms = Api::<DynamicObject>::namespaced_with(
+        service.client.clone(),
+        &service.namespace,
+        &virtual_machine_resource(),
+    );
+    let vmis = Api::<DynamicObject>::namespaced_with(
+        service.client.clone(),
+        &service.namespace,
+        &virtual_machine_instance_resource(),
+    );
+    let id = service.next_id().await.expect("next id must succeed");
+
+    service
+        .create(create_request(&id))
+        .await
+        .expect("instance creation must succeed");
+    let instance = service
+        .list()
+        .await
+        .expect("instance listing must succeed")
+        .into_iter()
+        .find(|instance| instance.id == id)
+        .expect("the instance should be listed");
+    assert_eq!(instance.name, "web");
+    assert_eq!(instance.max_cpu_cores, 2);
+    assert_eq!(instance.max_memory_bytes, 2 * GIB);
+
+    service
+        .start(&id)
+        .await
+        .expect("instance start must succeed");
+    let vm = vms.get(&id).await.expect("vm lookup must succeed");
+    assert_eq!(vm.data["spec"]["runStrategy"], RUN_STRATEGY_ALWAYS);
+
+    // Play the KubeVirt controller, running the VM
+    vms.patch(
+        &id,
+        &PatchParams::default(),
+        &Patch::Merge(json!({ "status": { "printableStatus": "Running" } })),
+    )
+    .await
+    .expect("vm status update must succeed");
+    let vmi = DynamicObject::new(&id, &virtual_machine_instance_resource()).data(json!({
+        "spec": { "domain": { "cpu": { "cores": 2 } } },
+        "status": { "interfaces": [{ "name": "default", "ipAddress": "10.244.1.17" }] },
+    }));
+    vmis.create(&PostParams::default(), &vmi)
+        .await
+        .expect("vmi creation must succeed");
+
+    assert!(matches!(
+        service.status(&id).await.expect("status must succeed"),
+        Status::Running
+    ));
+    assert_eq!(
+        service
+            .get_ip_address(&id)
+            .await
+            .expect("ip lookup must succeed"),
+        Some(Ipv4Addr::new(10, 244, 1, 17))
+    );
+
+    service.stop(&id).await.expect("instance stop must succeed");
+    let vm = vms.get(&id).await.expect("vm lookup must succeed");
+    assert_eq!(vm.data["spec"]["runStrategy"], RUN_STRATEGY_HALTED);
+
+    service
+        .delete(&id)
+        .await
+        .expect("instance deletion must succeed");
+    assert!(matches!(
+        service.status(&id).await,
+        Err(hypervisor::Error::DistantInstanceNotFound(_))
+    ));
+}
+
+#[tokio::test]
+#[ignore = "requires a Kubernetes cluster via E2E_KUBECONFIG"]
+async fn clone_copies_the_instance_with_cloned_disks() {
+    let service = service("frn-e2e-kubevirt-clone").await;
+    let vms = Api::<DynamicObject>::namespaced_with(
+        service.client.clone(),
+        &service.namespace,
+        &virtual_machine_resource(),
+    );
+    let id = service.next_id().await.expect("next id must succeed");
+    service
+        .create(create_request(&id))
+        .await
+        .expect("instance creation must succeed");
+
+    let clone_id = Instances::clone(&service, &id)
+        .await
+        .expect("instance clone must succeed");
+
+    let clone = vms.get(&clone_id).await.expect("clone lookup must succeed");
+    assert_eq!(clone.data["spec"]["runStrategy"], RUN_STRATEGY_HALTED);
+    assert_eq!(
+        clone.data["spec"]["dataVolumeTemplates"][0]["spec"]["source"]["pvc"]["name"],
+        format!("{id}-disk")
+    );
+    assert_eq!(
+        clone.data["spec"]["template"]["spec"]["volumes"][0]["dataVolume"]["name"],
+        format!("{clone_id}-disk")
+    );
+}
+
+#[tokio::test]
+#[ignore = "requires a Kubernetes cluster via E2E_KUBECONFIG"]
+async fn missing_instance_is_reported() {
+    let service = service("frn-e2e-kubevirt-missing").await;
+
+    let started = service.start("vm-missing").await;
+    let cloned = Instances::clone(&service, "vm-missing").await;
+    let address = service.get_ip_address("vm-missing").await;
+
+    assert!(matches!(
+        started,
+        Err(hypervisor::Error::DistantInstanceNotFound(_))
+    ));
+    assert!(matches!(
+        cloned,
+        Err(hypervisor::Error::DistantInstanceNotFound(_))
+    ));
+    assert_eq!(address.expect("ip lookup must succeed"), None);
+}
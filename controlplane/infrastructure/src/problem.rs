@@ -1,7 +1,40 @@
 use thiserror::Error;
+use uuid::Uuid;
 
 #[derive(Debug, Error)]
 pub enum Problem {
+    /// The request does not hold valid values.
+    #[error("invalid argument: {0}")]
+    InvalidArgument(String),
+
+    /// An identifier of the request is not a uuid.
+    #[error("malformed id {0}, expected uuid")]
+    MalformedId(String),
+
+    /// The requested zero trust network does not exist.
+    #[error("zero trust network not found: {0}")]
+    ZeroTrustNetworkNotFound(Uuid),
+
+    /// The instance is already attached to a zero trust network.
+    #[error("instance {instance_id} already attached to zero trust network {id}")]
+    InstanceAlreadyAttached { id: Uuid, instance_id: Uuid },
+
+    /// The instance is not attached to the zero trust network.
+    #[error("instance {instance_id} not attached to zero trust network {id}")]
+    InstanceNotAttached { id: Uuid, instance_id: Uuid },
+
+    /// The instance is still provisioning.
+    #[error("instance {0} is still provisioning")]
+    InstanceProvisioning(Uuid),
+
+    /// Zero trust networks only attach the instances of their organization.
+    #[error("zero trust network {id} belongs to another organization than instance {instance_id}")]
+    OrganizationMismatch { id: Uuid, instance_id: Uuid },
+
+    /// A failure of the core services, authorization included.
+    #[error("{0}")]
+    Core(#[from] frn_core::Error),
+
     #[error("Other")]
     Other(Box<dyn std::error::Error + Send + Sync>),
 }
@@ -23,6 +56,40 @@ impl From<fabrique::Error> for Problem {
 /// Converts a `infrastructure::Problem` into a `tonic::Status`.
 impl From<Problem> for tonic::Status {
     fn from(value: Problem) -> Self {
-        tonic::Status::from_error(Box::new(value))
+        match value {
+            Problem::InvalidArgument(_) | Problem::MalformedId(_) => {
+                tonic::Status::invalid_argument(value.to_string())
+            }
+            Problem::ZeroTrustNetworkNotFound(_) => tonic::Status::not_found(value.to_string()),
+            Problem::InstanceAlreadyAttached { .. }
+            | Problem::InstanceNotAttached { .. }
+            | Problem::InstanceProvisioning(_)
+            | Problem::OrganizationMismatch { .. } => {
+                tonic::Status::failed_precondition(value.to_string())
+            }
+            Problem::Core(error) => error.into(),
+            Problem::Other(_) => tonic::Status::from_error(Box::new(value)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tonic::Code;
+
+    #[test]
+    fn test_validation_failures_map_to_invalid_argument() {
+        let status = tonic::Status::from(Problem::InvalidArgument("empty name".to_owned()));
+        assert_eq!(status.code(), Code::InvalidArgument);
+
+        let status = tonic::Status::from(Problem::MalformedId("network".to_owned()));
+        assert_eq!(status.code(), Code::InvalidArgument);
+    }
+
+    #[test]
+    fn test_forbidden_maps_to_permission_denied() {
+        let status = tonic::Status::from(Problem::from(frn_core::Error::Forbidden));
+        assert_eq!(status.code(), Code::PermissionDenied);
     }
 }
@@ -4,4 +4,4 @@ mod service;
 
 pub use model::{ZeroTrustNetwork, ZeroTrustNetworkFactory};
 pub use rpc::ZeroTrustNetworkRpcService;
-pub use service::ZeroTrustNetworkService;
+pub use service::{ZeroTrustNetworkCreateRequest, ZeroTrustNetworkService};
@@ -1,15 +1,17 @@
 use crate::{ZeroTrustNetworkType, ZeroTrustNetworkTypeFactory, ZeroTrustNetworkTypeIdColumn};
 use chrono::{DateTime, Utc};
 use fabrique::{Factory, Model};
+use frn_core::authorization::Resource;
 use uuid::Uuid;
 
-#[derive(Debug, Default, Factory, Model, PartialEq)]
+#[derive(Clone, Debug, Default, Factory, Model, PartialEq, Resource)]
 #[fabrique(table = "zero_trust_networks")]
 pub struct ZeroTrustNetwork {
     /// Unique identifier for the zero trust network
     #[fabrique(primary_key)]
     pub id: Uuid,
 
+    /// The organization this zero trust network belongs to
     pub organization_slug: String,
 
     #[fabrique(belongs_to = ZeroTrustNetworkType)]
@@ -1,60 +1,159 @@
-use super::ZeroTrustNetworkService;
+use super::{ZeroTrustNetworkCreateRequest, ZeroTrustNetworkService};
+use crate::Problem;
 use crate::v1::{
-    ListZeroTrustNetworksRequest, ListZeroTrustNetworksResponse,
+    AttachZeroTrustNetworkInstanceRequest, AttachZeroTrustNetworkInstanceResponse,
+    CreateZeroTrustNetworkRequest, CreateZeroTrustNetworkResponse, DeleteZeroTrustNetworkRequest,
+    DeleteZeroTrustNetworkResponse, DetachZeroTrustNetworkInstanceRequest,
+    DetachZeroTrustNetworkInstanceResponse, GetZeroTrustNetworkRequest,
+    GetZeroTrustNetworkResponse, ListZeroTrustNetworksRequest, ListZeroTrustNetworksResponse,
+    UpdateZeroTrustNetworkRequest, UpdateZeroTrustNetworkResponse,
     zero_trust_networks_server::ZeroTrustNetworks,
 };
+use frn_core::authorization::Authorize;
+use frn_core::identity::IAM;
 use tonic::{Request, Response, Status};
+use uuid::Uuid;
 
-pub struct ZeroTrustNetworkRpcService {
-    service: ZeroTrustNetworkService,
+pub struct ZeroTrustNetworkRpcService<A: Authorize> {
+    iam: IAM,
+    service: ZeroTrustNetworkService<A>,
 }
 
-impl ZeroTrustNetworkRpcService {
+impl<A: Authorize> ZeroTrustNetworkRpcService<A> {
     /// Create a new instance of the ZeroTrustNetworks gRPC service.
-    pub fn new(pool: sqlx::PgPool) -> Self {
-        Self {
-            service: ZeroTrustNetworkService::new(pool),
-        }
+    pub fn new(iam: IAM, service: ZeroTrustNetworkService<A>) -> Self {
+        Self { iam, service }
     }
 }
 
+/// Parses an identifier of a request.
+fn parse_id(id: String) -> Result<Uuid, Problem> {
+    id.parse::<Uuid>().map_err(|_| Problem::MalformedId(id))
+}
+
 #[tonic::async_trait]
-impl ZeroTrustNetworks for ZeroTrustNetworkRpcService {
+impl<A: Authorize + 'static> ZeroTrustNetworks for ZeroTrustNetworkRpcService<A> {
     async fn list(
         &self,
-        _: Request<ListZeroTrustNetworksRequest>,
+        request: Request<ListZeroTrustNetworksRequest>,
     ) -> Result<Response<ListZeroTrustNetworksResponse>, Status> {
-        let models = self.service.list().await?;
+        let principal = self.iam.principal(&request).await?;
+
+        let models = self.service.list(&principal).await?;
 
         Ok(Response::new(ListZeroTrustNetworksResponse {
             zero_trust_networks: models.into_iter().map(Into::into).collect(),
         }))
     }
+
+    async fn create(
+        &self,
+        request: Request<CreateZeroTrustNetworkRequest>,
+    ) -> Result<Response<CreateZeroTrustNetworkResponse>, Status> {
+        let principal = self.iam.principal(&request).await?;
+        let inner = request.into_inner();
+
+        let model = self
+            .service
+            .clone()
+            .create(
+                &principal,
+                ZeroTrustNetworkCreateRequest {
+                    organization_slug: inner.organization_slug,
+                    zero_trust_network_type_id: parse_id(inner.zero_trust_network_type_id)?,
+                    name: inner.name,
+                },
+            )
+            .await?;
+
+        Ok(Response::new(CreateZeroTrustNetworkResponse {
+            zero_trust_network: Some(model.into()),
+        }))
+    }
+
+    async fn get(
+        &self,
+        request: Request<GetZeroTrustNetworkRequest>,
+    ) -> Result<Response<GetZeroTrustNetworkResponse>, Status> {
+        let principal = self.iam.principal(&request).await?;
+        let id = parse_id(request.into_inner().id)?;
+
+        let model = self.service.get(&principal, id).await?;
+
+        Ok(Response::new(GetZeroTrustNetworkResponse {
+            zero_trust_network: Some(model.into()),
+        }))
+    }
+
+    async fn update(
+        &self,
+        request: Request<UpdateZeroTrustNetworkRequest>,
+    ) -> Result<Response<UpdateZeroTrustNetworkResponse>, Status> {
+        let principal = self.iam.principal(&request).await?;
+        let inner = request.into_inner();
+        let id = parse_id(inner.id)?;
+
+        let model = self.service.update(&principal, id, inner.name).await?;
+
+        Ok(Response::new(UpdateZeroTrustNetworkResponse {
+            zero_trust_network: Some(model.into()),
+        }))
+    }
+
+    async fn delete(
+        &self,
+        request: Request<DeleteZeroTrustNetworkRequest>,
+    ) -> Result<Response<DeleteZeroTrustNetworkResponse>, Status> {
+        let principal = self.iam.principal(&request).await?;
+        let id = parse_id(request.into_inner().id)?;
+
+        self.service.clone().delete(&principal, id).await?;
+
+        Ok(Response::new(DeleteZeroTrustNetworkResponse {}))
+    }
+
+    async fn attach_instance(
+        &self,
+        request: Request<AttachZeroTrustNetworkInstanceRequest>,
+    ) -> Result<Response<AttachZeroTrustNetworkInstanceResponse>, Status> {
+        let principal = self.iam.principal(&request).await?;
+        let inner = request.into_inner();
+        let id = parse_id(inner.id)?;
+        let instance_id = parse_id(inner.instance_id)?;
+
+        self.service.attach(&principal, id, instance_id).await?;
+
+        Ok(Response::new(AttachZeroTrustNetworkInstanceResponse {}))
+    }
+
+    async fn detach_instance(
+        &self,
+        request: Request<DetachZeroTrustNetworkInstanceRequest>,
+    ) -> Result<Response<DetachZeroTrustNetworkInstanceResponse>, Status> {
+        let principal = self.iam.principal(&request).await?;
+        let inner = request.into_inner();
+        let id = parse_id(inner.id)?;
+        let instance_id = parse_id(inner.instance_id)?;
+
+        self.service.detach(&principal, id, instance_id).await?;
+
+        Ok(Response::new(DetachZeroTrustNetworkInstanceResponse {}))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{ZeroTrustNetwork, ZeroTrustNetworkType};
-    use fabrique::Factory;
-    use frn_core::resourcemanager::Organization;
+    use frn_core::App;
 
     #[sqlx::test(migrations = "../migrations")]
-    async fn test_list_organizations_works(pool: sqlx::PgPool) {
+    async fn test_list_without_credentials_fails(pool: sqlx::PgPool) {
         // Arrange the test
-        let org = Organization::factory()
-            .slug("test-org".to_owned())
-            .parent_slug(None)
-            .create(&pool)
-            .await
-            .unwrap();
-        let model = ZeroTrustNetwork::factory()
-            .organization_slug(org.slug)
-            .for_zero_trust_network_type(ZeroTrustNetworkType::factory())
-            .create(&pool)
-            .await
-            .unwrap();
-        let service = ZeroTrustNetworkRpcService::new(pool);
+        let app = App::test(pool.clone()).await.unwrap();
+        let service = ZeroTrustNetworkRpcService::new(
+            app.iam,
+            ZeroTrustNetworkService::new(app.auth, pool, app.overlays),
+        );
 
         // Act the call to the list procedure
         let result = service
@@ -62,12 +161,6 @@ mod tests {
             .await;
 
         // Assert the procedure result
-        assert!(result.is_ok());
-        assert_eq!(
-            result.unwrap().into_inner(),
-            ListZeroTrustNetworksResponse {
-                zero_trust_networks: vec![model.into()],
-            }
-        )
+        assert_eq!(result.unwrap_err().code(), tonic::Code::Unauthenticated);
     }
 }
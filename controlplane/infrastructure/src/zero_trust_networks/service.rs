@@ -1,57 +1,633 @@
-use fabrique::Query;
+use chrono::Utc;
+use fabrique::{Delete, Persist, Query};
+use frn_core::authorization::{Authorize, Permission, Principal, Relation, Relationship, Resource};
+use frn_core::compute::{Instance, Overlays};
+use frn_core::resourcemanager::{Organization, Project};
+use hypervisor::instance::Status;
+use uuid::Uuid;
 
-use crate::{Problem, ZeroTrustNetwork};
+use crate::{Problem, ZeroTrustNetwork, ZeroTrustNetworkType};
+
+/// The longest name of a zero trust network.
+const MAX_NAME_LENGTH: usize = 64;
+
+/// The request to create a zero trust network in an organization.
+#[derive(Clone, Debug)]
+pub struct ZeroTrustNetworkCreateRequest {
+    /// The organization to create the zero trust network in.
+    pub organization_slug: String,
+
+    /// The zero trust network type the network is based on.
+    pub zero_trust_network_type_id: Uuid,
+
+    /// The zero trust network human-readable name.
+    pub name: String,
+}
 
 /// Expose functions for interacting with zero trust networks.
-pub struct ZeroTrustNetworkService {
+///
+/// The instances attached to a zero trust network are the peers of its
+/// WireGuard overlay, whose configuration is pushed to every peer when one
+/// joins or leaves it.
+#[derive(Clone)]
+pub struct ZeroTrustNetworkService<A: Authorize> {
+    auth: A,
     pool: sqlx::PgPool,
+    overlays: Overlays,
 }
 
-impl ZeroTrustNetworkService {
-    /// List all zero trust networks.
-    pub async fn list(&self) -> Result<Vec<ZeroTrustNetwork>, Problem> {
-        ZeroTrustNetwork::query()
-            .select()
-            .get(&self.pool)
+impl<A: Authorize> ZeroTrustNetworkService<A> {
+    /// List the zero trust networks accessible to the principal.
+    pub async fn list<P: Principal + Sync>(
+        &self,
+        principal: &P,
+    ) -> Result<Vec<ZeroTrustNetwork>, Problem> {
+        self.auth
+            .lookup::<ZeroTrustNetwork>()
+            .on_behalf_of(principal)
+            .with(Permission::Get)
+            .against(&self.pool)
             .await
             .map_err(Into::into)
     }
 
+    /// Get a zero trust network.
+    pub async fn get<P: Principal + Sync>(
+        &self,
+        principal: &P,
+        id: Uuid,
+    ) -> Result<ZeroTrustNetwork, Problem> {
+        self.auth
+            .can(principal)
+            .perform(Permission::Get)
+            .over::<ZeroTrustNetwork>(&id)
+            .await?;
+
+        self.find(id).await
+    }
+
+    /// Create a zero trust network in an organization.
+    pub async fn create<P: Principal + Sync>(
+        &mut self,
+        principal: &P,
+        request: ZeroTrustNetworkCreateRequest,
+    ) -> Result<ZeroTrustNetwork, Problem> {
+        self.auth
+            .can(principal)
+            .perform(Permission::CreateZeroTrustNetwork)
+            .over::<Organization>(&request.organization_slug)
+            .await?;
+
+        validate_name(&request.name)?;
+        ZeroTrustNetworkType::query()
+            .select()
+            .r#where(
+                ZeroTrustNetworkType::ID,
+                "=",
+                request.zero_trust_network_type_id,
+            )
+            .first(&self.pool)
+            .await?
+            .ok_or_else(|| {
+                Problem::InvalidArgument(format!(
+                    "unknown zero trust network type {}",
+                    request.zero_trust_network_type_id
+                ))
+            })?;
+
+        let zero_trust_network = ZeroTrustNetwork {
+            id: Uuid::new_v4(),
+            organization_slug: request.organization_slug.clone(),
+            zero_trust_network_type_id: request.zero_trust_network_type_id,
+            name: request.name,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+        .create(&self.pool)
+        .await?;
+
+        // Write the relationship synchronously to SpiceDB
+        self.auth
+            .write_relationship(&Relationship::new(
+                &Organization::some(request.organization_slug),
+                Relation::Parent,
+                &zero_trust_network,
+            ))
+            .await?;
+
+        Ok(zero_trust_network)
+    }
+
+    /// Rename a zero trust network.
+    pub async fn update<P: Principal + Sync>(
+        &self,
+        principal: &P,
+        id: Uuid,
+        name: String,
+    ) -> Result<ZeroTrustNetwork, Problem> {
+        self.auth
+            .can(principal)
+            .perform(Permission::Update)
+            .over::<ZeroTrustNetwork>(&id)
+            .await?;
+
+        validate_name(&name)?;
+        self.find(id).await?;
+
+        ZeroTrustNetwork::update()
+            .set(ZeroTrustNetwork::NAME, name)
+            .set(ZeroTrustNetwork::UPDATED_AT, Utc::now())
+            .r#where(ZeroTrustNetwork::ID, "=", id)
+            .execute(&self.pool)
+            .await?;
+
+        self.find(id).await
+    }
+
+    /// Delete a zero trust network, detaching its instances.
+    pub async fn delete<P: Principal + Sync>(
+        &mut self,
+        principal: &P,
+        id: Uuid,
+    ) -> Result<(), Problem> {
+        self.auth
+            .can(principal)
+            .perform(Permission::Delete)
+            .over::<ZeroTrustNetwork>(&id)
+            .await?;
+
+        let zero_trust_network = self.find(id).await?;
+
+        // Instances are detached along with the network, their overlay
+        // interface torn down
+        let instances = Instance::query()
+            .select()
+            .r#where(Instance::ZERO_TRUST_NETWORK_ID, "=", Some(id))
+            .get(&self.pool)
+            .await?;
+        for instance in &instances {
+            self.tear_down(instance).await;
+        }
+        ZeroTrustNetwork::destroy(&self.pool, id).await?;
+
+        self.auth
+            .delete_relationship(&Relationship::new(
+                &Organization::some(zero_trust_network.organization_slug.clone()),
+                Relation::Parent,
+                &zero_trust_network,
+            ))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Attach an instance of its organization to a zero trust network,
+    /// making it a peer of the overlay of the network.
+    pub async fn attach<P: Principal + Sync>(
+        &self,
+        principal: &P,
+        id: Uuid,
+        instance_id: Uuid,
+    ) -> Result<(), Problem> {
+        let instance = self
+            .authorize_attachment(principal, Permission::Attach, id, instance_id)
+            .await?;
+
+        if let Some(attached_id) = instance.zero_trust_network_id {
+            return Err(Problem::InstanceAlreadyAttached {
+                id: attached_id,
+                instance_id,
+            });
+        }
+        // The overlay configuration of an instance still provisioning, or
+        // whose provisioning is retried, is only injected into its snippet
+        // when it was created in the network.
+        if matches!(instance.status, Status::Provisioning | Status::Failed) {
+            return Err(Problem::InstanceProvisioning(instance_id));
+        }
+
+        // The instance is attached only once it is a peer of the overlay
+        let mut tx = self.pool.begin().await?;
+        self.overlays.join_on(id, instance_id, &mut tx).await?;
+        Self::set_zero_trust_network(&mut *tx, instance_id, Some(id)).await?;
+        tx.commit().await?;
+        self.overlays.push(id).await?;
+
+        Ok(())
+    }
+
+    /// Detach an instance from a zero trust network, tearing its overlay
+    /// interface down.
+    pub async fn detach<P: Principal + Sync>(
+        &self,
+        principal: &P,
+        id: Uuid,
+        instance_id: Uuid,
+    ) -> Result<(), Problem> {
+        let instance = self
+            .authorize_attachment(principal, Permission::Detach, id, instance_id)
+            .await?;
+
+        if instance.zero_trust_network_id != Some(id) {
+            return Err(Problem::InstanceNotAttached { id, instance_id });
+        }
+
+        Self::set_zero_trust_network(&self.pool, instance_id, None).await?;
+        self.overlays.leave(instance_id).await?;
+        self.tear_down(&instance).await;
+        self.overlays.push(id).await?;
+
+        Ok(())
+    }
+
     /// Create a new zero trust network service.
-    pub fn new(pool: sqlx::PgPool) -> ZeroTrustNetworkService {
-        ZeroTrustNetworkService { pool }
+    pub fn new(auth: A, pool: sqlx::PgPool, overlays: Overlays) -> ZeroTrustNetworkService<A> {
+        ZeroTrustNetworkService {
+            auth,
+            pool,
+            overlays,
+        }
+    }
+
+    /// Check a principal can attach or detach an instance to a zero trust
+    /// network, both belonging to the same organization.
+    async fn authorize_attachment<P: Principal + Sync>(
+        &self,
+        principal: &P,
+        permission: Permission,
+        id: Uuid,
+        instance_id: Uuid,
+    ) -> Result<Instance, Problem> {
+        self.auth
+            .can(principal)
+            .perform(permission)
+            .over::<ZeroTrustNetwork>(&id)
+            .await?;
+        self.auth
+            .can(principal)
+            .perform(Permission::Update)
+            .over::<Instance>(&instance_id)
+            .await?;
+
+        let zero_trust_network = self.find(id).await?;
+        let instance = Instance::find(&self.pool, instance_id).await?;
+        let project = Project::query()
+            .select()
+            .r#where(Project::SLUG, "=", instance.project_slug.clone())
+            .first(&self.pool)
+            .await?;
+        if project
+            .is_none_or(|project| project.organization_slug != zero_trust_network.organization_slug)
+        {
+            return Err(Problem::OrganizationMismatch { id, instance_id });
+        }
+
+        Ok(instance)
+    }
+
+    /// Find a zero trust network.
+    async fn find(&self, id: Uuid) -> Result<ZeroTrustNetwork, Problem> {
+        ZeroTrustNetwork::query()
+            .select()
+            .r#where(ZeroTrustNetwork::ID, "=", id)
+            .first(&self.pool)
+            .await?
+            .ok_or(Problem::ZeroTrustNetworkNotFound(id))
+    }
+
+    /// Tear the overlay interface of an instance down.
+    ///
+    /// Best effort - the instance may not be running, failures are logged.
+    async fn tear_down(&self, instance: &Instance) {
+        if let Err(err) = self.overlays.tear_down(instance).await {
+            tracing::warn!(
+                instance_id = %instance.id,
+                error = %err,
+                "could not tear the overlay interface down"
+            );
+        }
     }
+
+    /// Set the zero trust network an instance belongs to.
+    async fn set_zero_trust_network<'e, E: sqlx::Executor<'e, Database = sqlx::Postgres>>(
+        executor: E,
+        instance_id: Uuid,
+        id: Option<Uuid>,
+    ) -> Result<(), Problem> {
+        Instance::update()
+            .set(Instance::ZERO_TRUST_NETWORK_ID, id)
+            .set(Instance::UPDATED_AT, Utc::now())
+            .r#where(Instance::ID, "=", instance_id)
+            .execute(executor)
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Check the name of a zero trust network is not empty, nor too long, and
+/// only holds alphanumeric characters, spaces, underscores and hyphens.
+fn validate_name(name: &str) -> Result<(), Problem> {
+    if name.trim().is_empty() {
+        return Err(Problem::InvalidArgument("name cannot be empty".to_owned()));
+    }
+    if name.chars().count() > MAX_NAME_LENGTH {
+        return Err(Problem::InvalidArgument(format!(
+            "name cannot exceed {} characters",
+            MAX_NAME_LENGTH
+        )));
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, ' ' | '_' | '-'))
+    {
+        return Err(Problem::InvalidArgument(format!(
+            "name {} holds characters other than alphanumerics, spaces, underscores and hyphens",
+            name
+        )));
+    }
+
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::ZeroTrustNetworkType;
     use fabrique::Factory;
-    use frn_core::resourcemanager::Organization;
+    use frn_core::App;
+    use frn_core::compute::{Hypervisor, Zone};
+    use frn_core::identity::{ServiceAccount, User};
+    use spicedb::SpiceDB;
+
+    /// Seeds an organization and a network type to create networks from.
+    async fn seed(pool: &sqlx::PgPool) -> (Organization, ZeroTrustNetworkType) {
+        let organization = Organization::factory()
+            .slug("test-org".to_owned())
+            .parent_slug(None)
+            .create(pool)
+            .await
+            .unwrap();
+        let zero_trust_network_type = ZeroTrustNetworkType::factory().create(pool).await.unwrap();
+
+        (organization, zero_trust_network_type)
+    }
 
     #[sqlx::test(migrations = "../migrations")]
     async fn test_list(pool: sqlx::PgPool) {
         // Arrange the service
-        let service = ZeroTrustNetworkService::new(pool.clone());
-        let org = Organization::factory()
-            .slug("test-org".to_owned())
-            .parent_slug(None)
+        let app = App::test(pool.clone()).await.unwrap();
+        let service = ZeroTrustNetworkService::new(app.auth, pool.clone(), app.overlays);
+        let (organization, zero_trust_network_type) = seed(&pool).await;
+        ZeroTrustNetwork::factory()
+            .organization_slug(organization.slug)
+            .zero_trust_network_type_id(zero_trust_network_type.id)
             .create(&pool)
             .await
             .unwrap();
-        let model = ZeroTrustNetwork::factory()
-            .organization_slug(org.slug)
-            .for_zero_trust_network_type(ZeroTrustNetworkType::factory())
+
+        // Act the call to the list method
+        let result = service.list(&ServiceAccount::default()).await;
+
+        // Assert only the networks the principal can access are listed
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap(), vec![]);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_list_the_networks_of_the_organizations_of_a_member(pool: sqlx::PgPool) {
+        // Arrange a network in two organizations, and a member of the first
+        let app = App::test(pool.clone()).await.unwrap();
+        let (mut spicedb, _) = SpiceDB::recording().await;
+        let service = ZeroTrustNetworkService::new(spicedb.clone(), pool.clone(), app.overlays);
+        let (organization, zero_trust_network_type) = seed(&pool).await;
+        let other = Organization::factory()
+            .slug("other-org".to_owned())
+            .parent_slug(None)
             .create(&pool)
             .await
             .unwrap();
+        let member = User::factory().is_admin(false).create(&pool).await.unwrap();
+        let mut networks = Vec::new();
+        for organization in [&organization, &other] {
+            let zero_trust_network = ZeroTrustNetwork::factory()
+                .organization_slug(organization.slug.clone())
+                .zero_trust_network_type_id(zero_trust_network_type.id)
+                .create(&pool)
+                .await
+                .unwrap();
+            spicedb
+                .write_relationship(&Relationship::new(
+                    organization,
+                    Relation::Parent,
+                    &zero_trust_network,
+                ))
+                .await
+                .unwrap();
+            networks.push(zero_trust_network);
+        }
+        spicedb
+            .write_relationship(&Relationship::new(&member, Relation::Member, &organization))
+            .await
+            .unwrap();
 
-        // Act the call to the list method
-        let result = service.list().await;
+        // Act the call to the list method on behalf of the member
+        let result = service.list(&member).await.unwrap();
+
+        // Assert only the network of their organization is listed
+        let listed: Vec<Uuid> = result.iter().map(|network| network.id).collect();
+        assert_eq!(listed, vec![networks[0].id]);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_create_update_and_delete(pool: sqlx::PgPool) {
+        // Arrange the service
+        let app = App::test(pool.clone()).await.unwrap();
+        let mut service = ZeroTrustNetworkService::new(app.auth, pool.clone(), app.overlays);
+        let principal = ServiceAccount::default();
+        let (organization, zero_trust_network_type) = seed(&pool).await;
+
+        // Act the creation, renaming and deletion of a network
+        let created = service
+            .create(
+                &principal,
+                ZeroTrustNetworkCreateRequest {
+                    organization_slug: organization.slug.clone(),
+                    zero_trust_network_type_id: zero_trust_network_type.id,
+                    name: "office".to_owned(),
+                },
+            )
+            .await
+            .unwrap();
+        let updated = service
+            .update(&principal, created.id, "head office".to_owned())
+            .await
+            .unwrap();
+        let deleted = service.delete(&principal, created.id).await;
 
         // Assert the result
-        assert!(result.is_ok());
-        assert_eq!(result.unwrap(), vec![model]);
+        assert_eq!(created.organization_slug, organization.slug);
+        assert_eq!(updated.name, "head office");
+        assert!(deleted.is_ok());
+        assert!(matches!(
+            service.get(&principal, created.id).await,
+            Err(Problem::ZeroTrustNetworkNotFound(_))
+        ));
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_create_rejects_invalid_requests(pool: sqlx::PgPool) {
+        // Arrange the service
+        let app = App::test(pool.clone()).await.unwrap();
+        let mut service = ZeroTrustNetworkService::new(app.auth, pool.clone(), app.overlays);
+        let principal = ServiceAccount::default();
+        let (organization, zero_trust_network_type) = seed(&pool).await;
+
+        // Act the creation of networks with an invalid name or type
+        let invalid_name = service
+            .create(
+                &principal,
+                ZeroTrustNetworkCreateRequest {
+                    organization_slug: organization.slug.clone(),
+                    zero_trust_network_type_id: zero_trust_network_type.id,
+                    name: "office/paris".to_owned(),
+                },
+            )
+            .await;
+        let unknown_type = service
+            .create(
+                &principal,
+                ZeroTrustNetworkCreateRequest {
+                    organization_slug: organization.slug.clone(),
+                    zero_trust_network_type_id: Uuid::new_v4(),
+                    name: "office".to_owned(),
+                },
+            )
+            .await;
+
+        // Assert both were rejected
+        assert!(matches!(invalid_name, Err(Problem::InvalidArgument(_))));
+        assert!(matches!(unknown_type, Err(Problem::InvalidArgument(_))));
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_attach_and_detach(pool: sqlx::PgPool) {
+        // Arrange a network and an instance of its organization
+        let app = App::test(pool.clone()).await.unwrap();
+        let service = ZeroTrustNetworkService::new(app.auth, pool.clone(), app.overlays);
+        let principal = ServiceAccount::default();
+        let (organization, zero_trust_network_type) = seed(&pool).await;
+        let zero_trust_network = ZeroTrustNetwork::factory()
+            .organization_slug(organization.slug.clone())
+            .zero_trust_network_type_id(zero_trust_network_type.id)
+            .create(&pool)
+            .await
+            .unwrap();
+        let project = Project::factory()
+            .slug("test-project".to_owned())
+            .organization_slug(organization.slug.clone())
+            .create(&pool)
+            .await
+            .unwrap();
+        let hypervisor = Hypervisor::factory()
+            .for_zone(Zone::factory())
+            .organization_slug(organization.slug.clone())
+            .create(&pool)
+            .await
+            .unwrap();
+        let instance = Instance::factory()
+            .hypervisor_id(hypervisor.id)
+            .project_slug(project.slug)
+            .distant_id(String::new())
+            .status(Status::Running)
+            .zero_trust_network_id(None)
+            .create(&pool)
+            .await
+            .unwrap();
+
+        // Act the attachment, a second one, then the detachment
+        let attached = service
+            .attach(&principal, zero_trust_network.id, instance.id)
+            .await;
+        let attached_id = Instance::find(&pool, instance.id)
+            .await
+            .unwrap()
+            .zero_trust_network_id;
+        let configuration = service.overlays.configuration(instance.id).await.unwrap();
+        let reattached = service
+            .attach(&principal, zero_trust_network.id, instance.id)
+            .await;
+        let detached = service
+            .detach(&principal, zero_trust_network.id, instance.id)
+            .await;
+        let detached_id = Instance::find(&pool, instance.id)
+            .await
+            .unwrap()
+            .zero_trust_network_id;
+
+        // Assert the instance joined, then left the network and its overlay
+        assert!(attached.is_ok());
+        assert_eq!(attached_id, Some(zero_trust_network.id));
+        assert!(configuration.is_some_and(|c| c.contains("Address = 100.64.0.1/16")));
+        assert!(matches!(
+            reattached,
+            Err(Problem::InstanceAlreadyAttached { .. })
+        ));
+        assert!(detached.is_ok());
+        assert_eq!(detached_id, None);
+        assert_eq!(
+            service.overlays.configuration(instance.id).await.unwrap(),
+            None
+        );
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_attach_rejects_instances_of_other_organizations(pool: sqlx::PgPool) {
+        // Arrange a network and an instance of another organization
+        let app = App::test(pool.clone()).await.unwrap();
+        let service = ZeroTrustNetworkService::new(app.auth, pool.clone(), app.overlays);
+        let (organization, zero_trust_network_type) = seed(&pool).await;
+        let other = Organization::factory()
+            .slug("other-org".to_owned())
+            .parent_slug(None)
+            .create(&pool)
+            .await
+            .unwrap();
+        let zero_trust_network = ZeroTrustNetwork::factory()
+            .organization_slug(organization.slug)
+            .zero_trust_network_type_id(zero_trust_network_type.id)
+            .create(&pool)
+            .await
+            .unwrap();
+        let project = Project::factory()
+            .slug("other-project".to_owned())
+            .organization_slug(other.slug.clone())
+            .create(&pool)
+            .await
+            .unwrap();
+        let hypervisor = Hypervisor::factory()
+            .for_zone(Zone::factory())
+            .organization_slug(other.slug)
+            .create(&pool)
+            .await
+            .unwrap();
+        let instance = Instance::factory()
+            .hypervisor_id(hypervisor.id)
+            .project_slug(project.slug)
+            .zero_trust_network_id(None)
+            .create(&pool)
+            .await
+            .unwrap();
+
+        // Act the attachment
+        let result = service
+            .attach(
+                &ServiceAccount::default(),
+                zero_trust_network.id,
+                instance.id,
+            )
+            .await;
+
+        // Assert it was rejected
+        assert!(matches!(result, Err(Problem::OrganizationMismatch { .. })));
     }
 }
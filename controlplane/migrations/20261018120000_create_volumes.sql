@@ -0,0 +1,17 @@
+-- Create "volumes" table
+CREATE TABLE "public"."volumes" (
+  "id" uuid NOT NULL DEFAULT gen_random_uuid(),
+  "instance_id" uuid NOT NULL,
+  "project_slug" citext NOT NULL,
+  "name" character varying(255) NOT NULL DEFAULT '',
+  "distant_id" text NOT NULL,
+  "device" character varying(50) NULL,
+  "size_bytes" bigint NOT NULL,
+  "created_at" timestamptz NOT NULL DEFAULT now(),
+  "updated_at" timestamptz NOT NULL DEFAULT now(),
+  PRIMARY KEY ("id"),
+  CONSTRAINT "volumes_instance_id_fkey" FOREIGN KEY ("instance_id") REFERENCES "public"."instances" ("id") ON UPDATE NO ACTION ON DELETE CASCADE,
+  CONSTRAINT "volumes_project_slug_fkey" FOREIGN KEY ("project_slug") REFERENCES "public"."projects" ("slug") ON UPDATE NO ACTION ON DELETE CASCADE
+);
+-- Create index "idx_volumes_instance_id" to table: "volumes"
+CREATE INDEX "idx_volumes_instance_id" ON "public"."volumes" ("instance_id");
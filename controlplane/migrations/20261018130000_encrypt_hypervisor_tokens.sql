@@ -0,0 +1,32 @@
+-- Envelope-encrypt the authorization tokens of the hypervisors.
+--
+-- Adds the columns needed by frn_crypto envelope encryption (ciphertext,
+-- nonce, wrapped DEK, DEK nonce, key version, algorithm). The KEK never
+-- touches the database, so the existing tokens cannot be encrypted here: the
+-- legacy authorization_token column becomes nullable, and the control plane
+-- re-encrypts every token it still holds at startup, clearing it in the same
+-- statement (Hypervisors::encrypt_plaintext_tokens). New hypervisors only
+-- store the encrypted token.
+--
+-- Risk: SAFE - new nullable columns on a small table.
+
+-- Modify "hypervisors" table
+ALTER TABLE "public"."hypervisors"
+  ALTER COLUMN "authorization_token" DROP NOT NULL,
+  ADD COLUMN "token_ciphertext" bytea NULL,
+  ADD COLUMN "token_nonce" bytea NULL,
+  ADD COLUMN "token_dek_ciphertext" bytea NULL,
+  ADD COLUMN "token_dek_nonce" bytea NULL,
+  ADD COLUMN "token_key_version" integer NULL,
+  ADD COLUMN "token_algorithm" character varying(50) NULL,
+  ADD CONSTRAINT "hypervisors_token_check" CHECK (
+    "authorization_token" IS NOT NULL
+    OR (
+      "token_ciphertext" IS NOT NULL
+      AND "token_nonce" IS NOT NULL
+      AND "token_dek_ciphertext" IS NOT NULL
+      AND "token_dek_nonce" IS NOT NULL
+      AND "token_key_version" IS NOT NULL
+      AND "token_algorithm" IS NOT NULL
+    )
+  );
@@ -0,0 +1,21 @@
+-- Record the result of the periodic hypervisor probe.
+--
+-- The synchronizer probes every hypervisor on each pass and stores whether it
+-- answered, the version of its API and the capacity aggregated over its
+-- online nodes. Hypervisors never probed yet keep the UNKNOWN health and no
+-- capacity; the scheduler skips the UNREACHABLE ones.
+--
+-- Risk: SAFE - new columns with defaults on a small table.
+
+-- Modify "hypervisors" table
+ALTER TABLE "public"."hypervisors"
+  ADD COLUMN "health" character varying(50) NOT NULL DEFAULT 'UNKNOWN',
+  ADD COLUMN "last_health_check_at" timestamptz NULL,
+  ADD COLUMN "version" character varying(50) NULL,
+  ADD COLUMN "node_count" integer NOT NULL DEFAULT 0,
+  ADD COLUMN "cpu_total" integer NOT NULL DEFAULT 0,
+  ADD COLUMN "cpu_free" double precision NOT NULL DEFAULT 0,
+  ADD COLUMN "memory_total_bytes" bigint NOT NULL DEFAULT 0,
+  ADD COLUMN "memory_free_bytes" bigint NOT NULL DEFAULT 0,
+  ADD COLUMN "storage_total_bytes" bigint NOT NULL DEFAULT 0,
+  ADD COLUMN "storage_free_bytes" bigint NOT NULL DEFAULT 0;
@@ -0,0 +1,20 @@
+-- Create "backup_policies" table
+--
+-- Records the schedule and retention of the backups of an instance. The
+-- schedule itself runs as a backup job on the hypervisor; the keep_* columns
+-- hold the number of backups kept per period, 0 standing for no limit.
+CREATE TABLE "public"."backup_policies" (
+  "id" uuid NOT NULL DEFAULT gen_random_uuid(),
+  "instance_id" uuid NOT NULL,
+  "schedule" character varying(255) NOT NULL,
+  "keep_last" integer NOT NULL DEFAULT 0,
+  "keep_daily" integer NOT NULL DEFAULT 0,
+  "keep_weekly" integer NOT NULL DEFAULT 0,
+  "keep_monthly" integer NOT NULL DEFAULT 0,
+  "created_at" timestamptz NOT NULL DEFAULT now(),
+  "updated_at" timestamptz NOT NULL DEFAULT now(),
+  PRIMARY KEY ("id"),
+  CONSTRAINT "backup_policies_instance_id_key" UNIQUE ("instance_id"),
+  CONSTRAINT "backup_policies_instance_id_fkey" FOREIGN KEY ("instance_id") REFERENCES "public"."instances" ("id") ON UPDATE NO ACTION ON DELETE CASCADE,
+  CONSTRAINT "backup_policies_keep_check" CHECK ("keep_last" >= 0 AND "keep_daily" >= 0 AND "keep_weekly" >= 0 AND "keep_monthly" >= 0)
+);
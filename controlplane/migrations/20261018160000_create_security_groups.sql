@@ -0,0 +1,45 @@
+-- Create "security_groups", "security_group_rules" and "instance_security_groups" tables
+--
+-- Security groups hold the rules letting traffic through to and from the
+-- instances of a project they are attached to. Rules with a NULL protocol,
+-- port range or CIDR match any of them. Rules and attachments are deleted
+-- along with their group or instance.
+--
+-- Risk: SAFE - only new tables are created.
+CREATE TABLE "public"."security_groups" (
+  "id" uuid NOT NULL DEFAULT gen_random_uuid(),
+  "project_slug" citext NOT NULL,
+  "name" character varying(255) NOT NULL,
+  "description" character varying(255) NOT NULL DEFAULT '',
+  "created_at" timestamptz NOT NULL DEFAULT now(),
+  "updated_at" timestamptz NOT NULL DEFAULT now(),
+  PRIMARY KEY ("id"),
+  CONSTRAINT "security_groups_project_slug_name_key" UNIQUE ("project_slug", "name"),
+  CONSTRAINT "security_groups_project_slug_fkey" FOREIGN KEY ("project_slug") REFERENCES "public"."projects" ("slug") ON UPDATE NO ACTION ON DELETE CASCADE
+);
+CREATE TABLE "public"."security_group_rules" (
+  "id" uuid NOT NULL DEFAULT gen_random_uuid(),
+  "security_group_id" uuid NOT NULL,
+  "direction" character varying(50) NOT NULL,
+  "protocol" character varying(50) NULL,
+  "port_range" character varying(50) NULL,
+  "cidr" character varying(50) NULL,
+  "created_at" timestamptz NOT NULL DEFAULT now(),
+  PRIMARY KEY ("id"),
+  CONSTRAINT "security_group_rules_security_group_id_fkey" FOREIGN KEY ("security_group_id") REFERENCES "public"."security_groups" ("id") ON UPDATE NO ACTION ON DELETE CASCADE,
+  CONSTRAINT "security_group_rules_direction_check" CHECK ("direction" IN ('INGRESS', 'EGRESS'))
+);
+-- Create index "idx_security_group_rules_security_group_id" to table: "security_group_rules"
+CREATE INDEX "idx_security_group_rules_security_group_id" ON "public"."security_group_rules" ("security_group_id");
+CREATE TABLE "public"."instance_security_groups" (
+  "id" uuid NOT NULL DEFAULT gen_random_uuid(),
+  "instance_id" uuid NOT NULL,
+  "security_group_id" uuid NOT NULL,
+  "created_at" timestamptz NOT NULL DEFAULT now(),
+  PRIMARY KEY ("id"),
+  CONSTRAINT "instance_security_groups_instance_id_security_group_id_key" UNIQUE ("instance_id", "security_group_id"),
+  CONSTRAINT "instance_security_groups_instance_id_fkey" FOREIGN KEY ("instance_id") REFERENCES "public"."instances" ("id") ON UPDATE NO ACTION ON DELETE CASCADE,
+  CONSTRAINT "instance_security_groups_security_group_id_fkey" FOREIGN KEY ("security_group_id") REFERENCES "public"."security_groups" ("id") ON UPDATE NO ACTION ON DELETE CASCADE
+);
+-- Create index "idx_instance_security_groups_security_group_id" to table: "instance_security_groups"
+CREATE INDEX "idx_instance_security_groups_security_group_id" ON "public"."instance_security_groups" ("security_group_id");
@@ -0,0 +1,11 @@
+-- Record the kind of platform each hypervisor runs on.
+--
+-- The kind selects the connector the hypervisor is driven through: the
+-- Proxmox VE API, or the KubeVirt resources of a Kubernetes cluster. The
+-- hypervisors registered so far are all Proxmox clusters.
+--
+-- Risk: SAFE - new column with a default on a small table.
+
+-- Modify "hypervisors" table
+ALTER TABLE "public"."hypervisors"
+  ADD COLUMN "kind" character varying(50) NOT NULL DEFAULT 'PROXMOX';
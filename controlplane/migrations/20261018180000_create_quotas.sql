@@ -0,0 +1,30 @@
+-- Create "quotas" table
+--
+-- Caps the compute and managed service resources of an organization, or of a
+-- single project of it when project_slug is set. NULL limits stand for no
+-- limit. An organization holds at most one quota of its own and one per
+-- project.
+--
+-- Risk: SAFE - only a new table is created.
+CREATE TABLE "public"."quotas" (
+  "id" uuid NOT NULL DEFAULT gen_random_uuid(),
+  "organization_slug" citext NOT NULL,
+  "project_slug" citext NULL,
+  "max_cpu_cores" bigint NULL,
+  "max_memory_bytes" bigint NULL,
+  "max_disk_bytes" bigint NULL,
+  "max_instances" bigint NULL,
+  "max_managed_service_instances" bigint NULL,
+  "created_at" timestamptz NOT NULL DEFAULT now(),
+  "updated_at" timestamptz NOT NULL DEFAULT now(),
+  PRIMARY KEY ("id"),
+  CONSTRAINT "quotas_project_slug_key" UNIQUE ("project_slug"),
+  CONSTRAINT "quotas_organization_slug_fkey" FOREIGN KEY ("organization_slug") REFERENCES "public"."organizations" ("slug") ON UPDATE NO ACTION ON DELETE CASCADE,
+  CONSTRAINT "quotas_project_slug_fkey" FOREIGN KEY ("project_slug") REFERENCES "public"."projects" ("slug") ON UPDATE NO ACTION ON DELETE CASCADE,
+  CONSTRAINT "quotas_limits_check" CHECK ("max_cpu_cores" >= 0 AND "max_memory_bytes" >= 0 AND "max_disk_bytes" >= 0 AND "max_instances" >= 0 AND "max_managed_service_instances" >= 0)
+);
+-- Create index "idx_quotas_organization_slug" to table: "quotas"
+--
+-- One quota per organization for the organization itself; the quotas of its
+-- projects are deduplicated by the project_slug unique constraint.
+CREATE UNIQUE INDEX "idx_quotas_organization_slug" ON "public"."quotas" ("organization_slug") WHERE "project_slug" IS NULL;
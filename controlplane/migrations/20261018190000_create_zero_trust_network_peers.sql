@@ -0,0 +1,22 @@
+-- Create "zero_trust_network_peers" table
+--
+-- The instances attached to a zero trust network are the peers of its
+-- WireGuard overlay, each holding a keypair whose private key is sealed at
+-- rest, and an address of the overlay network unique within it. An instance
+-- joins at most one overlay.
+--
+-- Risk: SAFE - only a new table is created.
+CREATE TABLE "public"."zero_trust_network_peers" (
+  "id" uuid NOT NULL DEFAULT gen_random_uuid(),
+  "zero_trust_network_id" uuid NOT NULL,
+  "instance_id" uuid NOT NULL,
+  "public_key" text NOT NULL,
+  "sealed_private_key" text NOT NULL,
+  "overlay_ip" text NOT NULL,
+  "created_at" timestamptz NOT NULL DEFAULT now(),
+  PRIMARY KEY ("id"),
+  CONSTRAINT "zero_trust_network_peers_instance_id_key" UNIQUE ("instance_id"),
+  CONSTRAINT "zero_trust_network_peers_overlay_ip_key" UNIQUE ("zero_trust_network_id", "overlay_ip"),
+  CONSTRAINT "zero_trust_network_peers_zero_trust_network_id_fkey" FOREIGN KEY ("zero_trust_network_id") REFERENCES "public"."zero_trust_networks" ("id") ON UPDATE NO ACTION ON DELETE CASCADE,
+  CONSTRAINT "zero_trust_network_peers_instance_id_fkey" FOREIGN KEY ("instance_id") REFERENCES "public"."instances" ("id") ON UPDATE NO ACTION ON DELETE CASCADE
+);
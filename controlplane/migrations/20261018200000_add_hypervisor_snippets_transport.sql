@@ -0,0 +1,12 @@
+-- Record the transport the snippets of each hypervisor are written with.
+--
+-- The Proxmox API cannot upload cloud-init snippets, so they are written to
+-- the storage of the hypervisor either through a volume shared with the
+-- control plane, or over SFTP to its nodes. The hypervisors registered so far
+-- all share a volume with the control plane.
+--
+-- Risk: SAFE - new column with a default on a small table.
+
+-- Modify "hypervisors" table
+ALTER TABLE "public"."hypervisors"
+  ADD COLUMN "snippets_transport" character varying(50) NOT NULL DEFAULT 'VOLUME';
@@ -0,0 +1,14 @@
+-- Record when an instance went missing from its hypervisor.
+--
+-- The synchronizer deletes the instances missing for longer than a grace
+-- period, which was measured from their last update, reset by the refresh of
+-- their metrics and status.
+--
+-- Risk: SAFE - new nullable column.
+
+-- Modify "instances" table
+ALTER TABLE "public"."instances"
+  ADD COLUMN "missing_since" timestamptz NULL;
+
+-- Instances already missing start their grace period from their last update
+UPDATE "public"."instances" SET "missing_since" = "updated_at" WHERE "status" = 'MISSING';
@@ -19,6 +19,7 @@ use tower_http::cors::CorsLayer;
 use frn_core::billing::Billing;
 use frn_core::billing::stripe::HttpStripeClient;
 use frn_core::managed::ManagedServices;
+use infrastructure::ZeroTrustNetworkService;
 
 use crate::config::Config;
 use crate::error::Error;
@@ -189,8 +190,12 @@ impl<L> Application<L> {
     /// # Registered Services
     ///
     /// The following gRPC services are registered:
+    /// - **Images**: Disk image catalogue of hypervisors
     /// - **Instances**: Instance management service for virtual machine
     ///   lifecycle operations
+    /// - **InstanceBackups**: Backups of instances and their schedules
+    /// - **SecurityGroups**: Rules filtering the traffic of instances
+    /// - **Volumes**: Data volume management service for instances
     ///
     /// # Example
     ///
@@ -218,11 +223,17 @@ impl<L> Application<L> {
         let iam = self.config.app.iam.clone();
         let pool = self.config.pool.clone();
         let hypervisors = self.config.app.hypervisors.clone();
+        let images = self.config.app.images.clone();
+        let instance_backups = self.config.app.instance_backups.clone();
         let instances = self.config.app.instances.clone();
         let invitations = self.config.app.invitations.clone();
         let organizations = self.config.app.organizations.clone();
+        let overlays = self.config.app.overlays.clone();
         let projects = self.config.app.projects.clone();
+        let quotas = self.config.app.quotas.clone();
+        let security_groups = self.config.app.security_groups.clone();
         let users = self.config.app.users.clone();
+        let volumes = self.config.app.volumes.clone();
         let zones = self.config.app.zones.clone();
         let auth = self.config.app.auth.clone();
         let worker_token = self.config.worker_token.clone();
@@ -234,7 +245,10 @@ impl<L> Application<L> {
             .router
             .health()
             .hypervisors(iam.clone(), pool.clone(), hypervisors.clone())
+            .images(iam.clone(), images.clone())
             .instances(iam.clone(), pool.clone(), instances.clone())
+            .instance_backups(iam.clone(), instance_backups)
+            .security_groups(iam.clone(), security_groups)
             .invitations(iam.clone(), invitations.clone(), users.clone())
             .profile(iam.clone())
             .managed_services(
@@ -247,11 +261,26 @@ impl<L> Application<L> {
             )
             .kubernetes_clusters(iam.clone(), pool.clone(), kubeconfig_encryption_kek.clone())
             .reflection()
-            .resources(iam.clone(), organizations, pool.clone(), projects.clone())
-            .zero_trust_networks(pool.clone())
+            .resources(
+                iam.clone(),
+                organizations,
+                pool.clone(),
+                projects.clone(),
+                quotas,
+            )
+            .zero_trust_networks(
+                iam.clone(),
+                ZeroTrustNetworkService::new(auth.clone(), pool.clone(), overlays),
+            )
             .zero_trust_network_types(pool.clone())
-            .workflow_engine(pool.clone(), worker_token)
-            .zones(iam.clone(), zones.clone());
+            .workflow_engine(iam.clone(), pool.clone(), worker_token)
+            .volumes(iam.clone(), pool.clone(), volumes.clone())
+            .zones(iam.clone(), zones.clone())
+            .consoles(
+                iam.clone(),
+                instances.clone(),
+                self.config.console_origin.clone(),
+            );
 
         if let (Some(stripe_key), Some(webhook_secret), Some(success_url), Some(cancel_url)) = (
             self.config.stripe_secret_key.clone(),
@@ -360,13 +389,13 @@ impl Application<Middleware<Identity>> {
         stream: TcpListenerStream,
     ) -> Result<(), Error> {
         if let Some(http_routes) = self.router.http_routes {
-            let http_listener = tokio::net::TcpListener::bind("0.0.0.0:8081")
+            let http_listener = tokio::net::TcpListener::bind(self.config.http_addr)
                 .await
                 .map_err(Error::IO)?;
-            tracing::info!("webhook HTTP server listening on 0.0.0.0:8081");
+            tracing::info!("HTTP server listening on {}", self.config.http_addr);
             tokio::spawn(async move {
                 if let Err(e) = axum::serve(http_listener, http_routes).await {
-                    tracing::error!(error = %e, "webhook HTTP server exited with error");
+                    tracing::error!(error = %e, "HTTP server exited with error");
                 }
             });
         }
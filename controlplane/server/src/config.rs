@@ -51,6 +51,11 @@ pub struct Config {
     /// will use. The default value binds to all available interfaces (`[::]`) on port 8080.
     pub addr: SocketAddr,
 
+    /// The socket address the plain HTTP routes (webhooks, consoles) listen on.
+    ///
+    /// Defaults to port 8081 on all interfaces, next to the gRPC server.
+    pub http_addr: SocketAddr,
+
     /// CORS configuration specifying which headers are allowed in cross-origin requests.
     ///
     /// This field controls the `Access-Control-Allow-Headers` header in HTTP responses.
@@ -129,6 +134,11 @@ pub struct Config {
     /// all and cannot authenticate — an absent secret is a deployment
     /// misconfiguration to avoid, not a graceful fallback to a legacy flow.
     pub bff: Option<crate::bff::Bff>,
+
+    /// Origin of the console (`CONSOLE_URL`), the only one allowed to open
+    /// instance consoles with the session cookie. `None` refuses every cookie
+    /// authenticated console.
+    pub console_origin: Option<http::HeaderValue>,
 }
 
 /// Deterministic KEK used only by [`Config::test`]. Not a secret: tests run
@@ -136,6 +146,11 @@ pub struct Config {
 #[cfg(feature = "mock")]
 const TEST_KUBECONFIG_ENCRYPTION_KEK: [u8; frn_crypto::KEK_SIZE] = [42u8; 32];
 
+/// Console origin used only by [`Config::test`], the one cookie authenticated
+/// console requests of the tests must come from.
+#[cfg(feature = "mock")]
+pub const TEST_CONSOLE_ORIGIN: &str = "https://console.test";
+
 impl Config {
     /// Creates a test configuration with a dynamically allocated port and mock OIDC server.
     ///
@@ -169,6 +184,7 @@ impl Config {
     #[cfg(feature = "mock")]
     pub async fn test(pool: &Pool<Postgres>, _mock_server: &MockServer) -> Result<Self, Error> {
         let addr = Config::reserve_socket_addr(None).await?;
+        let http_addr = Config::reserve_socket_addr(None).await?;
 
         let app = App::test(pool.to_owned())
             .await
@@ -177,6 +193,7 @@ impl Config {
         Ok(Config {
             app,
             addr,
+            http_addr,
             allow_headers: AllowHeaders::any(),
             allow_methods: AllowMethods::any(),
             allow_origin: AllowOrigin::any(),
@@ -198,6 +215,7 @@ impl Config {
             charts_registry_credentials: None,
             allow_credentials: false,
             bff: None,
+            console_origin: Some(http::HeaderValue::from_static(TEST_CONSOLE_ORIGIN)),
         })
     }
 
@@ -267,6 +285,10 @@ impl Config {
         Ok(Config {
             app,
             addr: Config::reserve_socket_addr(env::var("CONTROLPLANE_ADDR").ok()).await?,
+            http_addr: Config::reserve_socket_addr(Some(
+                env::var("CONTROLPLANE_HTTP_ADDR").unwrap_or_else(|_| "0.0.0.0:8081".to_owned()),
+            ))
+            .await?,
             allow_headers,
             allow_methods,
             allow_origin,
@@ -300,6 +322,10 @@ impl Config {
             },
             allow_credentials,
             bff,
+            console_origin: env::var("CONSOLE_URL")
+                .ok()
+                .map(|console_url| console_cors_origin(&console_url))
+                .transpose()?,
         })
     }
 
@@ -0,0 +1,213 @@
+//! Instance console websocket relay.
+//!
+//! Exposes `GET /instances/{id}/console` outside the gRPC service layer. The
+//! request is authenticated like any gRPC call, by bearer token or by the BFF
+//! session cookie, and checked against the `console` permission of the
+//! instance. A console proxy is then opened on the hypervisor, and its stream
+//! relayed over the upgraded websocket.
+//!
+//! The `kind` query parameter selects the console: `vnc` (the default)
+//! streams the display of the instance to a noVNC client, `terminal` its
+//! serial console to a terminal emulator. The relay authenticates the
+//! terminal stream itself, whereas the VNC password is sent to the client as
+//! a first text message, ahead of the relayed stream.
+//!
+//! Browsers attach cookies to cross-site websocket upgrades, so an upgrade
+//! authenticated by the session cookie must come from the console origin.
+
+use std::str::FromStr;
+use std::time::Duration;
+
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path, Query, State};
+use axum::http::{HeaderMap, HeaderValue, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use futures::{SinkExt, StreamExt};
+use serde::Deserialize;
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::{self, client::IntoClientRequest};
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+use tonic::metadata::MetadataMap;
+use uuid::Uuid;
+
+use frn_core::authorization::Authorize;
+use frn_core::compute::Instances;
+use frn_core::identity::IAM;
+use hypervisor::instance::{Console, ConsoleKind};
+
+/// Bound on opening the websocket to the hypervisor.
+const UPSTREAM_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+type Upstream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+#[derive(Clone)]
+pub struct ConsoleState<A: Authorize> {
+    pub iam: IAM,
+    pub instances: Instances<A>,
+    /// The origin cookie authenticated upgrades must come from.
+    pub console_origin: Option<HeaderValue>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConsoleParams {
+    kind: Option<String>,
+}
+
+pub async fn console_handler<A: Authorize + Clone + Send + Sync + 'static>(
+    State(state): State<ConsoleState<A>>,
+    Path(id): Path<Uuid>,
+    Query(params): Query<ConsoleParams>,
+    headers: HeaderMap,
+    upgrade: WebSocketUpgrade,
+) -> Response {
+    let kind = match params.kind.as_deref().map(ConsoleKind::from_str) {
+        None => ConsoleKind::default(),
+        Some(Ok(kind)) => kind,
+        Some(Err(_)) => return StatusCode::BAD_REQUEST.into_response(),
+    };
+
+    // Without a bearer token the IAM falls back to the session cookie.
+    let allowed_origin = is_bearer(&headers) || is_from(&headers, state.console_origin.as_ref());
+
+    // The IAM resolves principals from gRPC metadata, which carries the same
+    // `authorization` and `cookie` headers as the upgrade request.
+    let mut request = tonic::Request::new(());
+    *request.metadata_mut() = MetadataMap::from_headers(headers);
+    let principal = match state.iam.principal(&request).await {
+        Ok(principal) => principal,
+        Err(e) => {
+            tracing::debug!(error = %e, "console request is not authenticated");
+            return StatusCode::UNAUTHORIZED.into_response();
+        }
+    };
+    if !allowed_origin {
+        tracing::debug!(instance_id = %id, "console request from a foreign origin");
+        return StatusCode::FORBIDDEN.into_response();
+    }
+
+    let console = match state.instances.clone().console(&principal, id, kind).await {
+        Ok(console) => console,
+        Err(e) => {
+            let status = tonic::Status::from(e);
+            return status_code(status.code()).into_response();
+        }
+    };
+
+    // The hypervisor is reached before upgrading, so a failure surfaces as a
+    // plain HTTP error instead of a websocket closed right after opening.
+    let upstream = match connect(&console).await {
+        Ok(upstream) => upstream,
+        Err(e) => {
+            tracing::warn!(instance_id = %id, error = %e, "failed to open console on hypervisor");
+            return StatusCode::BAD_GATEWAY.into_response();
+        }
+    };
+
+    upgrade.on_upgrade(move |mut socket| async move {
+        if let Some(password) = console.password
+            && socket.send(Message::Text(password.into())).await.is_err()
+        {
+            return;
+        }
+
+        relay(socket, upstream).await;
+    })
+}
+
+/// Whether the request authenticates with a bearer token, which the IAM
+/// prefers over the session cookie.
+fn is_bearer(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|token| !token.is_empty())
+}
+
+/// Whether the request comes from `origin`, failing closed when no origin is
+/// configured.
+fn is_from(headers: &HeaderMap, origin: Option<&HeaderValue>) -> bool {
+    origin.is_some_and(|origin| headers.get(header::ORIGIN) == Some(origin))
+}
+
+/// Opens the console websocket on the hypervisor, authenticating its stream.
+async fn connect(console: &Console) -> Result<Upstream, tungstenite::Error> {
+    let mut request = console.url.as_str().into_client_request()?;
+    let authorization = HeaderValue::from_str(&console.authorization)
+        .map_err(|e| tungstenite::Error::HttpFormat(e.into()))?;
+    request
+        .headers_mut()
+        .insert(header::AUTHORIZATION, authorization);
+
+    let (mut upstream, _) = tokio::time::timeout(
+        UPSTREAM_CONNECT_TIMEOUT,
+        tokio_tungstenite::connect_async(request),
+    )
+    .await
+    .map_err(|_| tungstenite::Error::Io(std::io::ErrorKind::TimedOut.into()))??;
+
+    if let Some(handshake) = &console.handshake {
+        upstream
+            .send(tungstenite::Message::text(handshake.as_str()))
+            .await?;
+    }
+
+    Ok(upstream)
+}
+
+/// Relays messages both ways until either side closes.
+async fn relay(socket: WebSocket, upstream: Upstream) {
+    let (mut client_tx, mut client_rx) = socket.split();
+    let (mut upstream_tx, mut upstream_rx) = upstream.split();
+
+    let client_to_upstream = async {
+        while let Some(Ok(message)) = client_rx.next().await {
+            let message = match message {
+                Message::Text(text) => tungstenite::Message::text(text.as_str()),
+                Message::Binary(data) => tungstenite::Message::Binary(data),
+                Message::Ping(data) => tungstenite::Message::Ping(data),
+                Message::Pong(data) => tungstenite::Message::Pong(data),
+                Message::Close(_) => break,
+            };
+            if upstream_tx.send(message).await.is_err() {
+                break;
+            }
+        }
+        let _ = upstream_tx.close().await;
+    };
+
+    let upstream_to_client = async {
+        while let Some(Ok(message)) = upstream_rx.next().await {
+            let message = match message {
+                tungstenite::Message::Text(text) => Message::Text(text.as_str().into()),
+                tungstenite::Message::Binary(data) => Message::Binary(data),
+                tungstenite::Message::Ping(data) => Message::Ping(data),
+                tungstenite::Message::Pong(data) => Message::Pong(data),
+                tungstenite::Message::Close(_) => break,
+                tungstenite::Message::Frame(_) => continue,
+            };
+            if client_tx.send(message).await.is_err() {
+                break;
+            }
+        }
+        let _ = client_tx.close().await;
+    };
+
+    tokio::select! {
+        _ = client_to_upstream => {}
+        _ = upstream_to_client => {}
+    }
+}
+
+/// Maps the gRPC code of a service error to its HTTP counterpart.
+fn status_code(code: tonic::Code) -> StatusCode {
+    match code {
+        tonic::Code::InvalidArgument => StatusCode::BAD_REQUEST,
+        tonic::Code::Unauthenticated => StatusCode::UNAUTHORIZED,
+        tonic::Code::PermissionDenied => StatusCode::FORBIDDEN,
+        tonic::Code::NotFound => StatusCode::NOT_FOUND,
+        tonic::Code::FailedPrecondition => StatusCode::CONFLICT,
+        tonic::Code::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
@@ -27,6 +27,7 @@ pub mod application;
 pub mod bff;
 pub mod catalog;
 pub mod config;
+pub mod console;
 pub mod error;
 pub mod metrics;
 pub mod router;
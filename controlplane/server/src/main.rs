@@ -1,12 +1,16 @@
 //! Main executable for the control plane.
 //!
 //! Runs the gRPC server by default, or a `catalog` subcommand that reconciles
-//! or checks the declarative catalogue and exits. Command parsing uses clap.
+//! or checks the declarative catalogue and exits, or a `hypervisors`
+//! subcommand migrating their data and exits. Command parsing uses clap.
 
+use std::env;
 use std::path::PathBuf;
 
 use clap::{Parser, Subcommand};
+use frn_crypto::Kek;
 use server::catalog::{self, DEFAULT_CATALOG_PATH};
+use server::error::Error;
 use server::{Config, serve, shutdown_signal};
 
 /// Control plane binary: gRPC server, plus catalogue management subcommands.
@@ -22,6 +26,16 @@ enum Command {
     /// Manage the declarative service catalogue.
     #[command(subcommand)]
     Catalog(CatalogCommand),
+    /// Migrate the data of the hypervisors.
+    #[command(subcommand)]
+    Hypervisors(HypervisorsCommand),
+}
+
+#[derive(Subcommand)]
+enum HypervisorsCommand {
+    /// Encrypt the authentication tokens still stored in plaintext (needs
+    /// `DATABASE_URL` and `HYPERVISOR_TOKEN_ENCRYPTION_KEY` only).
+    EncryptTokens,
 }
 
 #[derive(Subcommand)]
@@ -53,11 +67,11 @@ async fn main() -> Result<(), server::error::Error> {
 
     let cli = Cli::parse();
 
-    if let Some(Command::Catalog(command)) = cli.command {
-        return run_catalog_command(command).await;
+    match cli.command {
+        Some(Command::Catalog(command)) => run_catalog_command(command).await,
+        Some(Command::Hypervisors(command)) => run_hypervisors_command(command).await,
+        None => run_server().await,
     }
-
-    run_server().await
 }
 
 /// Runs a one-shot `catalog` subcommand and exits.
@@ -76,6 +90,35 @@ async fn run_catalog_command(command: CatalogCommand) -> Result<(), server::erro
     }
 }
 
+/// Runs a one-shot `hypervisors` subcommand and exits.
+///
+/// Run by the deployment after its migrations, before the control plane is
+/// configured: no Config, only the database and the hypervisor token KEK. A
+/// missing or malformed variable fails the command with a configuration error.
+async fn run_hypervisors_command(command: HypervisorsCommand) -> Result<(), Error> {
+    match command {
+        HypervisorsCommand::EncryptTokens => {
+            let database_url = env::var("DATABASE_URL")
+                .map_err(|_| Error::Config("DATABASE_URL must be set".to_owned()))?;
+            let pool = sqlx::PgPool::connect(&database_url)
+                .await
+                .map_err(frn_core::Error::from)?;
+            let encoded_kek = env::var("HYPERVISOR_TOKEN_ENCRYPTION_KEY").map_err(|_| {
+                Error::Config("HYPERVISOR_TOKEN_ENCRYPTION_KEY must be set".to_owned())
+            })?;
+            let kek = Kek::from_base64(&encoded_kek).map_err(|err| {
+                Error::Config(format!(
+                    "HYPERVISOR_TOKEN_ENCRYPTION_KEY must be base64-encoded 32 bytes: {}",
+                    err
+                ))
+            })?;
+
+            frn_core::compute::encrypt_plaintext_tokens(&pool, &kek).await?;
+            Ok(())
+        }
+    }
+}
+
 /// Boots the control plane: self-initializes its baseline state, then serves.
 async fn run_server() -> Result<(), server::error::Error> {
     let config = Config::from_env().await?;
@@ -126,6 +169,22 @@ async fn run_server() -> Result<(), server::error::Error> {
             .await?;
     }
 
+    // Admins, hypervisors and zones created before they were related through
+    // the authorization backend are denied or listed by none until related
+    // here.
+    config.app.users.clone().relate_admins().await?;
+    config.app.hypervisors.clone().relate_all().await?;
+    config.app.zones.clone().relate_all().await?;
+
+    // Hypervisors registered before their tokens were encrypted at rest still
+    // hold them in plaintext until encrypted here, when the deployment did not
+    // run `hypervisors encrypt-tokens` after its migrations.
+    frn_core::compute::encrypt_plaintext_tokens(
+        &config.app.db,
+        config.app.config.hypervisor_resolver.kek(),
+    )
+    .await?;
+
     catalog::sync_at_boot(&config).await?;
     catalog::spawn_version_discovery(&config);
 
@@ -7,15 +7,23 @@
 
 use std::sync::Arc;
 
-use axum::routing::post;
+use axum::routing::{get, post};
 use frn_core::billing::StripeClient;
 use frn_core::identity::IAM;
 use frn_crypto::Kek;
 use frn_rpc::v1::compute::Hypervisors;
+use frn_rpc::v1::compute::Images;
+use frn_rpc::v1::compute::InstanceBackups;
 use frn_rpc::v1::compute::Instances;
+use frn_rpc::v1::compute::SecurityGroups;
+use frn_rpc::v1::compute::Volumes;
 use frn_rpc::v1::compute::Zones;
 use frn_rpc::v1::compute::hypervisors_server::HypervisorsServer;
+use frn_rpc::v1::compute::images_server::ImagesServer;
+use frn_rpc::v1::compute::instance_backups_server::InstanceBackupsServer;
 use frn_rpc::v1::compute::instances_server::InstancesServer;
+use frn_rpc::v1::compute::security_groups_server::SecurityGroupsServer;
+use frn_rpc::v1::compute::volumes_server::VolumesServer;
 use frn_rpc::v1::compute::zones_server::ZonesServer;
 use frn_rpc::v1::iam::Invitations;
 use frn_rpc::v1::iam::Profile;
@@ -27,11 +35,14 @@ use frn_rpc::v1::managed::ManagedServicesRpc;
 use frn_rpc::v1::managed::managed_services_server::ManagedServicesServer;
 use frn_rpc::v1::resourcemanager::Organizations;
 use frn_rpc::v1::resourcemanager::Projects;
+use frn_rpc::v1::resourcemanager::Quotas;
 use frn_rpc::v1::resourcemanager::organizations_server::OrganizationsServer;
 use frn_rpc::v1::resourcemanager::projects_server::ProjectsServer;
+use frn_rpc::v1::resourcemanager::quotas_server::QuotasServer;
 use frn_rpc::v1::workflow::WorkflowEngine;
 use frn_rpc::v1::workflow::workflow_engine_server::WorkflowEngineServer;
 use infrastructure::ZeroTrustNetworkRpcService;
+use infrastructure::ZeroTrustNetworkService;
 use infrastructure::ZeroTrustNetworkTypeRpcService;
 use infrastructure::v1::zero_trust_network_types_server::ZeroTrustNetworkTypesServer;
 use infrastructure::v1::zero_trust_networks_server::ZeroTrustNetworksServer;
@@ -101,15 +112,20 @@ impl Router {
         tokio::spawn(async move {
             tokio::join!(
                 health_reporter.set_serving::<HypervisorsServer<Hypervisors<SpiceDB>>>(),
+                health_reporter.set_serving::<ImagesServer<Images<SpiceDB>>>(),
                 health_reporter.set_serving::<InstancesServer<Instances<SpiceDB>>>(),
+                health_reporter.set_serving::<InstanceBackupsServer<InstanceBackups<SpiceDB>>>(),
+                health_reporter.set_serving::<SecurityGroupsServer<SecurityGroups<SpiceDB>>>(),
+                health_reporter.set_serving::<VolumesServer<Volumes<SpiceDB>>>(),
                 health_reporter.set_serving::<InvitationsServer<Invitations<SpiceDB>>>(),
                 health_reporter.set_serving::<ProfileServer<Profile>>(),
                 health_reporter.set_serving::<OrganizationsServer<Organizations<SpiceDB>>>(),
                 health_reporter.set_serving::<ProjectsServer<Projects<SpiceDB>>>(),
+                health_reporter.set_serving::<QuotasServer<Quotas<SpiceDB>>>(),
                 health_reporter
                     .set_serving::<ZeroTrustNetworkTypesServer<ZeroTrustNetworkTypeRpcService>>(),
                 health_reporter
-                    .set_serving::<ZeroTrustNetworksServer<ZeroTrustNetworkRpcService>>(),
+                    .set_serving::<ZeroTrustNetworksServer<ZeroTrustNetworkRpcService<SpiceDB>>>(),
                 health_reporter.set_serving::<ManagedServicesServer<ManagedServicesRpc<SpiceDB>>>(),
                 health_reporter.set_serving::<KubernetesClustersServer<KubernetesClustersRpc>>(),
                 health_reporter.set_serving::<WorkflowEngineServer<WorkflowEngine>>(),
@@ -148,6 +164,21 @@ impl Router {
         }
     }
 
+    /// Registers the images management service with the router.
+    ///
+    /// This method adds the images gRPC service to the router, providing
+    /// endpoints to list, import and delete the disk images held by the
+    /// storage of hypervisors.
+    pub fn images(self, iam: IAM, images: frn_core::compute::Images<SpiceDB>) -> Self {
+        Self {
+            routes: self
+                .routes
+                .add_service(ImagesServer::new(Images::new(iam, images))),
+            http_routes: self.http_routes,
+            health_reporter: self.health_reporter,
+        }
+    }
+
     /// Registers the instances management service with the router.
     ///
     /// This method adds the instances gRPC service to the router, providing
@@ -173,6 +204,27 @@ impl Router {
         }
     }
 
+    /// Registers the instance backups service with the router.
+    ///
+    /// This method adds the instance backups gRPC service to the router,
+    /// providing endpoints to back instances up, restore their backups into
+    /// new instances and schedule their backups.
+    pub fn instance_backups(
+        self,
+        iam: IAM,
+        backups: frn_core::compute::InstanceBackups<SpiceDB>,
+    ) -> Self {
+        Self {
+            routes: self
+                .routes
+                .add_service(InstanceBackupsServer::new(InstanceBackups::new(
+                    iam, backups,
+                ))),
+            http_routes: self.http_routes,
+            health_reporter: self.health_reporter,
+        }
+    }
+
     pub fn invitations(
         self,
         iam: IAM,
@@ -220,6 +272,7 @@ impl Router {
         organizations: frn_core::resourcemanager::Organizations<SpiceDB>,
         pool: Pool<Postgres>,
         projects: frn_core::resourcemanager::Projects<SpiceDB>,
+        quotas: frn_core::resourcemanager::Quotas<SpiceDB>,
     ) -> Self {
         Self {
             routes: self
@@ -229,7 +282,11 @@ impl Router {
                     organizations.clone(),
                     pool.clone(),
                 )))
-                .add_service(ProjectsServer::new(Projects::<SpiceDB>::new(iam, projects))),
+                .add_service(ProjectsServer::new(Projects::<SpiceDB>::new(
+                    iam.clone(),
+                    projects,
+                )))
+                .add_service(QuotasServer::new(Quotas::<SpiceDB>::new(iam, quotas))),
             http_routes: self.http_routes,
             health_reporter: self.health_reporter,
         }
@@ -258,17 +315,21 @@ impl Router {
     /// Registers the zero trust networks service with the router.
     ///
     /// This method adds the zero trust networks gRPC service to the router,
-    /// providing endpoints for zero trust network creation, configuration, and
-    /// management operations. The service is configured with the provided
-    /// database pool for persistent storage operations.
+    /// providing endpoints for zero trust network creation, configuration,
+    /// deletion and the attachment of instances, authorized by organization.
     ///
     /// # Parameters
     ///
-    /// * `pool` - PostgreSQL database connection pool for database operations
-    pub fn zero_trust_networks(self, pool: Pool<Postgres>) -> Self {
+    /// * `iam` - Identity and access management for authentication
+    /// * `zero_trust_networks` - Zero trust networks service
+    pub fn zero_trust_networks(
+        self,
+        iam: IAM,
+        zero_trust_networks: ZeroTrustNetworkService<SpiceDB>,
+    ) -> Self {
         Self {
             routes: self.routes.add_service(ZeroTrustNetworksServer::new(
-                ZeroTrustNetworkRpcService::new(pool),
+                ZeroTrustNetworkRpcService::new(iam, zero_trust_networks),
             )),
             http_routes: self.http_routes,
             health_reporter: self.health_reporter,
@@ -315,19 +376,66 @@ impl Router {
         }
     }
 
-    pub fn workflow_engine(self, pool: Pool<Postgres>, worker_token: String) -> Self {
+    pub fn workflow_engine(self, iam: IAM, pool: Pool<Postgres>, worker_token: String) -> Self {
         Self {
             routes: self
                 .routes
                 .add_service(WorkflowEngineServer::new(WorkflowEngine::new(
                     pool,
                     worker_token,
+                    iam,
                 ))),
             http_routes: self.http_routes,
             health_reporter: self.health_reporter,
         }
     }
 
+    /// Registers the security groups service with the router.
+    ///
+    /// This method adds the security groups gRPC service to the router,
+    /// providing endpoints to manage the rules filtering the traffic of
+    /// instances and to attach them to instances.
+    pub fn security_groups(
+        self,
+        iam: IAM,
+        security_groups: frn_core::compute::SecurityGroups<SpiceDB>,
+    ) -> Self {
+        Self {
+            routes: self
+                .routes
+                .add_service(SecurityGroupsServer::new(SecurityGroups::new(
+                    iam,
+                    security_groups,
+                ))),
+            http_routes: self.http_routes,
+            health_reporter: self.health_reporter,
+        }
+    }
+
+    /// Registers the volumes management service with the router.
+    ///
+    /// This method adds the volumes gRPC service to the router, providing
+    /// endpoints to create, attach, detach, grow and delete the data volumes
+    /// of instances.
+    ///
+    /// # Parameters
+    ///
+    /// * `pool` - PostgreSQL database connection pool for database operations
+    pub fn volumes(
+        self,
+        iam: IAM,
+        pool: Pool<Postgres>,
+        volumes: frn_core::compute::Volumes<SpiceDB>,
+    ) -> Self {
+        Self {
+            routes: self
+                .routes
+                .add_service(VolumesServer::new(Volumes::new(iam, pool, volumes))),
+            http_routes: self.http_routes,
+            health_reporter: self.health_reporter,
+        }
+    }
+
     pub fn zones(self, iam: IAM, zones: frn_core::compute::Zones<SpiceDB>) -> Self {
         Self {
             routes: self
@@ -375,6 +483,39 @@ impl Router {
         }
     }
 
+    /// Registers the instance console websocket HTTP endpoint.
+    ///
+    /// The endpoint is a plain HTTP GET at `/instances/{id}/console`, upgraded
+    /// to a websocket relaying the console stream of the instance. Cookie
+    /// authenticated upgrades must come from `console_origin`.
+    pub fn consoles(
+        self,
+        iam: IAM,
+        instances: frn_core::compute::Instances<SpiceDB>,
+        console_origin: Option<http::HeaderValue>,
+    ) -> Self {
+        use crate::console::{ConsoleState, console_handler};
+
+        let console_route: axum::Router = axum::Router::new()
+            .route("/instances/{id}/console", get(console_handler::<SpiceDB>))
+            .with_state(ConsoleState {
+                iam,
+                instances,
+                console_origin,
+            });
+
+        let merged_http = match self.http_routes {
+            Some(existing) => existing.merge(console_route),
+            None => console_route,
+        };
+
+        Self {
+            routes: self.routes,
+            http_routes: Some(merged_http),
+            health_reporter: self.health_reporter,
+        }
+    }
+
     /// Registers the billing gRPC service and Stripe webhook HTTP endpoint.
     ///
     /// The gRPC service handles checkout sessions, subscriptions, and cancellations.
@@ -22,20 +22,39 @@ use frn_core::kubernetes::{
 };
 use frn_core::resourcemanager::{Organization, Project};
 use frn_crypto::Kek;
+use frn_rpc::v1::compute::images_client::ImagesClient;
+use frn_rpc::v1::compute::instance_backups_client::InstanceBackupsClient;
 use frn_rpc::v1::compute::instances_client::InstancesClient;
+use frn_rpc::v1::compute::security_groups_client::SecurityGroupsClient;
+use frn_rpc::v1::compute::volumes_client::VolumesClient;
+use frn_rpc::v1::compute::zones_client::ZonesClient;
 use frn_rpc::v1::iam::profile_client::ProfileClient;
 use frn_rpc::v1::kubernetes::kubernetes_clusters_client::KubernetesClustersClient;
 use frn_rpc::v1::managed::managed_services_client::ManagedServicesClient;
 use frn_rpc::v1::workflow::workflow_engine_client::WorkflowEngineClient;
 use frn_rpc::v1::{
     compute::hypervisors_client::HypervisorsClient,
-    resourcemanager::{organizations_client::OrganizationsClient, projects_client::ProjectsClient},
+    resourcemanager::{
+        organizations_client::OrganizationsClient, projects_client::ProjectsClient,
+        quotas_client::QuotasClient,
+    },
 };
 use hypervisor::mock::{
-    WithClusterNextId, WithClusterResourceList, WithTaskStatusReadMock, WithVMCloneMock,
-    WithVMCreateMock, WithVMDeleteMock, WithVMDiskResizeMock, WithVMStatusReadMock,
-    WithVMStatusStartMock, WithVMStatusStopMock,
+    WithBackupJobCreateMock, WithBackupJobDeleteMock, WithBackupJobListMock,
+    WithBackupJobUpdateMock, WithClusterNextId, WithClusterResourceList,
+    WithStorageContentCreateMock, WithStorageContentDeleteMock, WithStorageContentListMock,
+    WithStorageDownloadUrlMock, WithTaskStatusReadMock, WithVMAgentExecMock,
+    WithVMAgentExecStatusMock, WithVMAgentFileWriteMock, WithVMAgentSetUserPasswordMock,
+    WithVMCloneMock, WithVMConfigMock, WithVMConfigUpdateMock, WithVMCreateMock, WithVMDeleteMock,
+    WithVMDiskResizeMock, WithVMFirewallOptionsReadMock, WithVMFirewallOptionsUpdateMock,
+    WithVMFirewallRuleCreateMock, WithVMFirewallRuleDeleteMock, WithVMFirewallRuleListMock,
+    WithVMPendingReadMock, WithVMRestoreMock, WithVMRrdDataReadMock, WithVMSnapshotCreateMock,
+    WithVMSnapshotDeleteMock, WithVMSnapshotListMock, WithVMSnapshotRollbackMock,
+    WithVMStatusReadMock, WithVMStatusRebootMock, WithVMStatusResetMock, WithVMStatusResumeMock,
+    WithVMStatusShutdownMock, WithVMStatusStartMock, WithVMStatusStopMock, WithVMStatusSuspendMock,
+    WithVMTermProxyMock, WithVMVncProxyMock, WithVersionReadMock, WithVzdumpCreateMock,
 };
+use infrastructure::v1::zero_trust_networks_client::ZeroTrustNetworksClient;
 use mock_server::MockServer;
 use server::{Config, error::Error};
 use sqlx::types::chrono::Utc;
@@ -44,6 +63,9 @@ use std::str::FromStr;
 use tokio::sync::oneshot;
 use tonic::{Request, metadata::MetadataValue, transport::Channel};
 use uuid::Uuid;
+use workflow::execution::WorkflowExecutionId;
+use workflow::operations::{Operation, Operations};
+use workflow::workflows::{WorkflowDefinition, WorkflowDefinitions};
 
 #[derive(Clone)]
 struct NoopWorkflowScheduler;
@@ -53,8 +75,8 @@ impl WorkflowScheduler<DeployManagedServiceParams> for NoopWorkflowScheduler {
         &self,
         _conn: &mut PgConnection,
         _params: DeployManagedServiceParams,
-    ) -> Result<(), String> {
-        Ok(())
+    ) -> Result<Uuid, String> {
+        Ok(Uuid::nil())
     }
 }
 
@@ -62,17 +84,32 @@ impl WorkflowScheduler<DeployManagedServiceParams> for NoopWorkflowScheduler {
 #[allow(dead_code)]
 pub struct Compute {
     pub hypervisors: HypervisorsClient<Channel>,
+    pub backups: InstanceBackupsClient<Channel>,
+    pub images: ImagesClient<Channel>,
     pub instances: InstancesClient<Channel>,
+    pub security_groups: SecurityGroupsClient<Channel>,
+    pub volumes: VolumesClient<Channel>,
+    pub zones: ZonesClient<Channel>,
 }
 
 impl Compute {
     pub async fn create(dst: &str) -> Result<Self, Error> {
+        let backups = InstanceBackupsClient::connect(dst.to_owned()).await?;
         let hypervisors = HypervisorsClient::connect(dst.to_owned()).await?;
+        let images = ImagesClient::connect(dst.to_owned()).await?;
         let instances = InstancesClient::connect(dst.to_owned()).await?;
+        let security_groups = SecurityGroupsClient::connect(dst.to_owned()).await?;
+        let volumes = VolumesClient::connect(dst.to_owned()).await?;
+        let zones = ZonesClient::connect(dst.to_owned()).await?;
 
         Ok(Self {
+            backups,
             hypervisors,
+            images,
             instances,
+            security_groups,
+            volumes,
+            zones,
         })
     }
 }
@@ -81,16 +118,33 @@ impl Compute {
 pub struct ResourceManager {
     pub organizations: OrganizationsClient<Channel>,
     pub projects: ProjectsClient<Channel>,
+    pub quotas: QuotasClient<Channel>,
 }
 
 impl ResourceManager {
     pub async fn create(dst: &str) -> Result<Self, Error> {
         let organizations = OrganizationsClient::connect(dst.to_owned()).await?;
         let projects = ProjectsClient::connect(dst.to_owned()).await?;
+        let quotas = QuotasClient::connect(dst.to_owned()).await?;
 
         Ok(Self {
             organizations,
             projects,
+            quotas,
+        })
+    }
+}
+
+/// gRPC clients for infrastructure services.
+pub struct Infrastructure {
+    pub zero_trust_networks: ZeroTrustNetworksClient<Channel>,
+}
+
+impl Infrastructure {
+    pub async fn create(dst: &str) -> Result<Self, Error> {
+        let zero_trust_networks = ZeroTrustNetworksClient::connect(dst.to_owned()).await?;
+        Ok(Self {
+            zero_trust_networks,
         })
     }
 }
@@ -135,11 +189,14 @@ pub const TEST_CI_TOKEN: &str = "test-ci-token";
 #[allow(dead_code)]
 pub struct Api {
     pub compute: Compute,
+    pub infrastructure: Infrastructure,
     pub managed: Managed,
     pub kubernetes: Kubernetes,
     pub resourcemanager: ResourceManager,
     pub workflow: Workflow,
     pub profile: ProfileClient<Channel>,
+    /// Base URL of the plain HTTP routes (webhooks, consoles).
+    pub http_url: String,
     pub mock_server: MockServer,
     pub service_account: ServiceAccount,
     shutdown: Option<oneshot::Sender<()>>,
@@ -150,19 +207,55 @@ impl Api {
     pub async fn start(pool: &Pool<Postgres>) -> Result<Self, Error> {
         let mock_server = MockServer::new()
             .await
+            .with_backup_job_create()
+            .with_backup_job_delete()
+            .with_backup_job_list()
+            .with_backup_job_update()
             .with_cluster_next_id()
             .with_cluster_resource_list()
+            .with_storage_content_create()
+            .with_storage_content_delete()
+            .with_storage_content_list()
+            .with_storage_download_url()
             .with_task_status_read()
+            .with_version_read()
+            .with_vm_agent_exec()
+            .with_vm_agent_exec_status()
+            .with_vm_agent_file_write()
+            .with_vm_agent_set_user_password()
             .with_vm_clone()
+            .with_vm_config()
+            .with_vm_config_update()
             .with_vm_create()
             .with_vm_delete()
             .with_vm_disk_resize()
+            .with_vm_firewall_options_read()
+            .with_vm_firewall_options_update()
+            .with_vm_firewall_rule_create()
+            .with_vm_firewall_rule_delete()
+            .with_vm_firewall_rule_list()
+            .with_vm_pending_read()
+            .with_vm_restore()
+            .with_vm_rrddata_read()
+            .with_vm_snapshot_create()
+            .with_vm_snapshot_delete()
+            .with_vm_snapshot_list()
+            .with_vm_snapshot_rollback()
             .with_vm_status_read()
+            .with_vm_status_reboot()
+            .with_vm_status_reset()
+            .with_vm_status_resume()
+            .with_vm_status_shutdown()
             .with_vm_status_start()
             .with_vm_status_stop()
+            .with_vm_status_suspend()
+            .with_vm_termproxy()
+            .with_vm_vncproxy()
+            .with_vzdump_create()
             .with_well_known();
         let config = Config::test(pool, &mock_server).await?;
         let server_url = format!("http://{}", config.addr);
+        let http_url = format!("http://{}", config.http_addr);
         let shutdown = server::serve(config).await?;
 
         let service_account = ServiceAccount::factory()
@@ -173,11 +266,13 @@ impl Api {
 
         Ok(Self {
             compute: Compute::create(&server_url).await?,
+            infrastructure: Infrastructure::create(&server_url).await?,
             managed: Managed::create(&server_url).await?,
             kubernetes: Kubernetes::create(&server_url).await?,
             resourcemanager: ResourceManager::create(&server_url).await?,
             workflow: Workflow::create(&server_url).await?,
             profile: ProfileClient::connect(server_url.clone()).await?,
+            http_url,
             mock_server,
             service_account,
             shutdown: Some(shutdown),
@@ -532,6 +627,14 @@ pub async fn deactivate_managed_service(pool: &Pool<Postgres>, service_id: Uuid)
         .expect("could not deactivate managed service");
 }
 
+/// Creates a directory standing for the volume the control plane shares with
+/// the hypervisors, holding the snippets the instances are created with.
+pub fn snippets_volume() -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!("snippets-{}", Uuid::new_v4()));
+    std::fs::create_dir_all(path.join("snippets")).expect("could not create snippets volume");
+    path
+}
+
 /// Builds a [`workflow::WorkerContext`] for exercising workflow operations
 /// directly against the test database.
 ///
@@ -555,6 +658,57 @@ pub async fn worker_context(pool: &Pool<Postgres>) -> workflow::WorkerContext {
             deployment_annotations: std::collections::BTreeMap::new(),
         },
         kek: Arc::new(Kek::from_bytes([42u8; 32])),
+        hypervisor_resolver: frn_core::Config::test().hypervisor_resolver,
         kubeconfig_path: None,
     }
 }
+
+/// Runs a scheduled workflow execution in process, the way the worker does:
+/// the operations of each round are executed, and the successful ones rolled
+/// back in reverse order once one of them fails.
+pub async fn run_workflow(pool: &Pool<Postgres>, execution_id: &str) -> Result<(), String> {
+    let execution_id = Uuid::parse_str(execution_id).expect("invalid execution id");
+    // Raw SQL: workflow.execution has no fabrique model.
+    let definition: serde_json::Value =
+        sqlx::query_scalar("SELECT definition FROM workflow.execution WHERE execution_id = $1")
+            .bind(execution_id)
+            .fetch_one(pool)
+            .await
+            .expect("could not find workflow execution");
+    let mut definition: WorkflowDefinitions =
+        serde_json::from_value(definition).expect("could not parse workflow definition");
+    let execution_id = WorkflowExecutionId::from_uuid(execution_id);
+    let ctx = worker_context(pool).await;
+
+    let mut executed: Vec<Operations> = Vec::new();
+    let failure = loop {
+        let operations = match definition.next_operations(ctx.clone()).await {
+            Ok(operations) if operations.is_empty() => return Ok(()),
+            Ok(operations) => operations,
+            Err(e) => break e.to_string(),
+        };
+
+        let mut round = Vec::new();
+        let mut failure = None;
+        for operation in operations {
+            match operation.execute(ctx.clone(), execution_id).await {
+                Ok(operation) => round.push(operation),
+                Err(e) => failure = Some(e.to_string()),
+            }
+        }
+        definition.record_operations(&round);
+        executed.extend(round);
+        if let Some(failure) = failure {
+            break failure;
+        }
+    };
+
+    for operation in executed.into_iter().rev() {
+        operation
+            .rollback(ctx.clone(), execution_id)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    Err(failure)
+}
@@ -0,0 +1,113 @@
+use crate::common::{Api, OnBehalfOf};
+use fabrique::Factory;
+use frn_core::{
+    compute::{Hypervisor, Instance, InstanceSecurityGroup, SecurityGroup, Zone},
+    resourcemanager::{Organization, Project},
+};
+use frn_rpc::v1::compute::{AddSecurityGroupRuleRequest, SecurityGroupRuleDirection};
+use tonic::{Code, Request};
+
+mod common;
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_add_security_group_rule_procedure_works(pool: sqlx::PgPool) {
+    // Arrange the grpc server and a client
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let mock_url = api.mock_server.url();
+
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await
+        .expect("could not create organization");
+    let hypervisor = Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
+        .for_zone(Zone::factory())
+        .organization_slug(organization.slug.clone())
+        .url(mock_url)
+        .create(&pool)
+        .await
+        .expect("could not create hypervisor");
+    let project = Project::factory()
+        .slug("test-project".to_owned())
+        .organization_slug(organization.slug.clone())
+        .create(&pool)
+        .await
+        .expect("could not create project");
+    let instance = Instance::factory()
+        .hypervisor_id(hypervisor.id)
+        .project_slug(project.slug.clone())
+        .distant_id("100".into())
+        .zero_trust_network_id(None)
+        .create(&pool)
+        .await
+        .expect("could not create instance");
+    let security_group = SecurityGroup::factory()
+        .project_slug(project.slug.clone())
+        .name("web".to_owned())
+        .create(&pool)
+        .await
+        .expect("could not create security group");
+    InstanceSecurityGroup::factory()
+        .instance_id(instance.id)
+        .security_group_id(security_group.id)
+        .create(&pool)
+        .await
+        .expect("could not attach security group");
+
+    // Act the request to the test_the_add_security_group_rule_procedure_works
+    let request = Request::new(AddSecurityGroupRuleRequest {
+        security_group_id: security_group.id.to_string(),
+        direction: SecurityGroupRuleDirection::Ingress.into(),
+        protocol: Some("tcp".to_owned()),
+        port_range: Some("8000:8080".to_owned()),
+        cidr: Some("0.0.0.0/0".to_owned()),
+    })
+    .on_behalf_of(&api.service_account);
+    let response = api.compute.security_groups.add_rule(request).await;
+
+    // Assert the rule was added and applied to the attached instance
+    let rule = response.unwrap().into_inner().rule.unwrap();
+    assert_eq!(rule.security_group_id, security_group.id.to_string());
+    assert_eq!(rule.port_range.as_deref(), Some("8000:8080"));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_add_security_group_rule_procedure_rejects_invalid_rules(pool: sqlx::PgPool) {
+    // Arrange the grpc server and a client
+    let mut api = Api::start(&pool).await.expect("could not start api");
+
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await
+        .expect("could not create organization");
+    let project = Project::factory()
+        .slug("test-project".to_owned())
+        .organization_slug(organization.slug.clone())
+        .create(&pool)
+        .await
+        .expect("could not create project");
+    let security_group = SecurityGroup::factory()
+        .project_slug(project.slug.clone())
+        .name("web".to_owned())
+        .create(&pool)
+        .await
+        .expect("could not create security group");
+
+    // Act the request with ports on a protocol without any
+    let request = Request::new(AddSecurityGroupRuleRequest {
+        security_group_id: security_group.id.to_string(),
+        direction: SecurityGroupRuleDirection::Ingress.into(),
+        protocol: Some("icmp".to_owned()),
+        port_range: Some("22".to_owned()),
+        cidr: None,
+    })
+    .on_behalf_of(&api.service_account);
+    let response = api.compute.security_groups.add_rule(request).await;
+
+    // Assert the rule was rejected
+    assert_eq!(response.unwrap_err().code(), Code::InvalidArgument);
+}
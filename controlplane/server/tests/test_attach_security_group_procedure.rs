@@ -0,0 +1,122 @@
+use crate::common::{Api, OnBehalfOf};
+use fabrique::Factory;
+use frn_core::{
+    compute::{Hypervisor, Instance, SecurityGroup, Zone},
+    resourcemanager::{Organization, Project},
+};
+use frn_rpc::v1::compute::AttachSecurityGroupRequest;
+use tonic::{Code, Request};
+
+mod common;
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_attach_security_group_procedure_works(pool: sqlx::PgPool) {
+    // Arrange the grpc server and a client
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let mock_url = api.mock_server.url();
+
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await
+        .expect("could not create organization");
+    let hypervisor = Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
+        .for_zone(Zone::factory())
+        .organization_slug(organization.slug.clone())
+        .url(mock_url)
+        .create(&pool)
+        .await
+        .expect("could not create hypervisor");
+    let project = Project::factory()
+        .slug("test-project".to_owned())
+        .organization_slug(organization.slug.clone())
+        .create(&pool)
+        .await
+        .expect("could not create project");
+    let instance = Instance::factory()
+        .hypervisor_id(hypervisor.id)
+        .project_slug(project.slug.clone())
+        .distant_id("100".into())
+        .zero_trust_network_id(None)
+        .create(&pool)
+        .await
+        .expect("could not create instance");
+    let security_group = SecurityGroup::factory()
+        .project_slug(project.slug.clone())
+        .name("web".to_owned())
+        .create(&pool)
+        .await
+        .expect("could not create security group");
+
+    // Act the request to the test_the_attach_security_group_procedure_works
+    let request = Request::new(AttachSecurityGroupRequest {
+        security_group_id: security_group.id.to_string(),
+        instance_id: instance.id.to_string(),
+    })
+    .on_behalf_of(&api.service_account);
+    let response = api.compute.security_groups.attach(request).await;
+
+    // Assert the result
+    assert!(response.is_ok());
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_attach_security_group_procedure_fails_across_projects(pool: sqlx::PgPool) {
+    // Arrange the grpc server and a client
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let mock_url = api.mock_server.url();
+
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await
+        .expect("could not create organization");
+    let hypervisor = Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
+        .for_zone(Zone::factory())
+        .organization_slug(organization.slug.clone())
+        .url(mock_url)
+        .create(&pool)
+        .await
+        .expect("could not create hypervisor");
+    let project = Project::factory()
+        .slug("test-project".to_owned())
+        .organization_slug(organization.slug.clone())
+        .create(&pool)
+        .await
+        .expect("could not create project");
+    let instance = Instance::factory()
+        .hypervisor_id(hypervisor.id)
+        .project_slug(project.slug.clone())
+        .distant_id("100".into())
+        .zero_trust_network_id(None)
+        .create(&pool)
+        .await
+        .expect("could not create instance");
+    let other_project = Project::factory()
+        .slug("other-project".to_owned())
+        .organization_slug(organization.slug.clone())
+        .create(&pool)
+        .await
+        .expect("could not create project");
+    let security_group = SecurityGroup::factory()
+        .project_slug(other_project.slug.clone())
+        .name("web".to_owned())
+        .create(&pool)
+        .await
+        .expect("could not create security group");
+
+    // Act the request with a group of another project
+    let request = Request::new(AttachSecurityGroupRequest {
+        security_group_id: security_group.id.to_string(),
+        instance_id: instance.id.to_string(),
+    })
+    .on_behalf_of(&api.service_account);
+    let response = api.compute.security_groups.attach(request).await;
+
+    // Assert the attachment was rejected
+    assert_eq!(response.unwrap_err().code(), Code::FailedPrecondition);
+}
@@ -0,0 +1,225 @@
+use crate::common::{Api, OnBehalfOf};
+use fabrique::{Factory, Query};
+use frn_core::{
+    compute::{Hypervisor, Instance, ZeroTrustNetworkPeer, Zone},
+    resourcemanager::{Organization, Project},
+};
+use hypervisor::instance::Status;
+use infrastructure::v1::AttachZeroTrustNetworkInstanceRequest;
+use infrastructure::{ZeroTrustNetwork, ZeroTrustNetworkType};
+use tonic::{Code, Request};
+
+mod common;
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_attach_zero_trust_network_instance_procedure_works(pool: sqlx::PgPool) {
+    // Arrange the grpc server and a client
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let mock_url = api.mock_server.url();
+
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await
+        .expect("could not create organization");
+    let zero_trust_network = ZeroTrustNetwork::factory()
+        .organization_slug(organization.slug.clone())
+        .for_zero_trust_network_type(ZeroTrustNetworkType::factory())
+        .create(&pool)
+        .await
+        .expect("could not create zero trust network");
+    let hypervisor = Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
+        .for_zone(Zone::factory())
+        .organization_slug(organization.slug.clone())
+        .url(mock_url)
+        .create(&pool)
+        .await
+        .expect("could not create hypervisor");
+    let project = Project::factory()
+        .slug("test-project".to_owned())
+        .organization_slug(organization.slug.clone())
+        .create(&pool)
+        .await
+        .expect("could not create project");
+    let mut instances = Vec::new();
+    for _ in 0..2 {
+        let instance = Instance::factory()
+            .hypervisor_id(hypervisor.id)
+            .project_slug(project.slug.clone())
+            .distant_id("100".into())
+            .status(Status::Running)
+            .zero_trust_network_id(None)
+            .create(&pool)
+            .await
+            .expect("could not create instance");
+        instances.push(instance);
+    }
+
+    // Act the requests attaching both instances
+    for instance in &instances {
+        let request = Request::new(AttachZeroTrustNetworkInstanceRequest {
+            id: zero_trust_network.id.to_string(),
+            instance_id: instance.id.to_string(),
+        })
+        .on_behalf_of(&api.service_account);
+        let response = api
+            .infrastructure
+            .zero_trust_networks
+            .attach_instance(request)
+            .await;
+        assert!(response.is_ok());
+    }
+
+    // Assert the instances joined the network, each a peer of its overlay
+    let mut overlay_ips = Vec::new();
+    for instance in &instances {
+        let found = Instance::find(&pool, instance.id)
+            .await
+            .expect("could not find instance");
+        assert_eq!(found.zero_trust_network_id, Some(zero_trust_network.id));
+
+        let peer = ZeroTrustNetworkPeer::query()
+            .select()
+            .r#where(ZeroTrustNetworkPeer::INSTANCE_ID, "=", instance.id)
+            .first(&pool)
+            .await
+            .expect("could not query peer")
+            .expect("could not find peer");
+        assert_eq!(peer.zero_trust_network_id, zero_trust_network.id);
+        assert_eq!(peer.public_key.len(), 44);
+        assert!(!peer.sealed_private_key.contains(&peer.public_key));
+        overlay_ips.push(peer.overlay_ip);
+    }
+    assert_eq!(overlay_ips, vec!["100.64.0.1", "100.64.0.2"]);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_attach_zero_trust_network_instance_procedure_fails_while_provisioning(
+    pool: sqlx::PgPool,
+) {
+    // Arrange the grpc server and a client
+    let mut api = Api::start(&pool).await.expect("could not start api");
+
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await
+        .expect("could not create organization");
+    let zero_trust_network = ZeroTrustNetwork::factory()
+        .organization_slug(organization.slug.clone())
+        .for_zero_trust_network_type(ZeroTrustNetworkType::factory())
+        .create(&pool)
+        .await
+        .expect("could not create zero trust network");
+    let hypervisor = Hypervisor::factory()
+        .for_zone(Zone::factory())
+        .organization_slug(organization.slug.clone())
+        .create(&pool)
+        .await
+        .expect("could not create hypervisor");
+    let project = Project::factory()
+        .slug("test-project".to_owned())
+        .organization_slug(organization.slug.clone())
+        .create(&pool)
+        .await
+        .expect("could not create project");
+    let instance = Instance::factory()
+        .hypervisor_id(hypervisor.id)
+        .project_slug(project.slug)
+        .distant_id(String::new())
+        .status(Status::Provisioning)
+        .zero_trust_network_id(None)
+        .create(&pool)
+        .await
+        .expect("could not create instance");
+
+    // Act the request to the test_the_attach_zero_trust_network_instance_procedure_fails_while_provisioning
+    let request = Request::new(AttachZeroTrustNetworkInstanceRequest {
+        id: zero_trust_network.id.to_string(),
+        instance_id: instance.id.to_string(),
+    })
+    .on_behalf_of(&api.service_account);
+    let response = api
+        .infrastructure
+        .zero_trust_networks
+        .attach_instance(request)
+        .await;
+
+    // Assert the result
+    assert_eq!(response.unwrap_err().code(), Code::FailedPrecondition);
+    let peers = ZeroTrustNetworkPeer::query()
+        .select()
+        .r#where(ZeroTrustNetworkPeer::INSTANCE_ID, "=", instance.id)
+        .get(&pool)
+        .await
+        .expect("could not query peers");
+    assert!(peers.is_empty());
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_attach_zero_trust_network_instance_procedure_fails_across_organizations(
+    pool: sqlx::PgPool,
+) {
+    // Arrange the grpc server and a client
+    let mut api = Api::start(&pool).await.expect("could not start api");
+
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await
+        .expect("could not create organization");
+    let other = Organization::factory()
+        .slug("other-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await
+        .expect("could not create organization");
+    let zero_trust_network = ZeroTrustNetwork::factory()
+        .organization_slug(organization.slug.clone())
+        .for_zero_trust_network_type(ZeroTrustNetworkType::factory())
+        .create(&pool)
+        .await
+        .expect("could not create zero trust network");
+    let hypervisor = Hypervisor::factory()
+        .for_zone(Zone::factory())
+        .organization_slug(other.slug.clone())
+        .create(&pool)
+        .await
+        .expect("could not create hypervisor");
+    let project = Project::factory()
+        .slug("test-project".to_owned())
+        .organization_slug(other.slug.clone())
+        .create(&pool)
+        .await
+        .expect("could not create project");
+    let instance = Instance::factory()
+        .hypervisor_id(hypervisor.id)
+        .project_slug(project.slug)
+        .zero_trust_network_id(None)
+        .create(&pool)
+        .await
+        .expect("could not create instance");
+
+    // Act the request to the test_the_attach_zero_trust_network_instance_procedure_fails_across_organizations
+    let request = Request::new(AttachZeroTrustNetworkInstanceRequest {
+        id: zero_trust_network.id.to_string(),
+        instance_id: instance.id.to_string(),
+    })
+    .on_behalf_of(&api.service_account);
+    let response = api
+        .infrastructure
+        .zero_trust_networks
+        .attach_instance(request)
+        .await;
+
+    // Assert the result
+    assert_eq!(response.unwrap_err().code(), Code::FailedPrecondition);
+    let instance = Instance::find(&pool, instance.id)
+        .await
+        .expect("could not find instance");
+    assert_eq!(instance.zero_trust_network_id, None);
+}
@@ -1,9 +1,10 @@
-use fabrique::Factory;
+use fabrique::{Factory, Query};
 use frn_core::{
     compute::{Hypervisor, Instance, Zone},
     resourcemanager::{Organization, Project},
 };
 use frn_rpc::v1::compute::CloneInstanceRequest;
+use infrastructure::{ZeroTrustNetwork, ZeroTrustNetworkType};
 use tonic::Request;
 
 use crate::common::{Api, OnBehalfOf};
@@ -22,6 +23,7 @@ async fn test_the_clone_instance_procedure_works(pool: sqlx::PgPool) {
         .await
         .expect("could not create organization");
     let hypervisor = Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
         .url(mock_url)
         .for_zone(Zone::factory())
         .organization_slug(organization.slug.clone())
@@ -34,11 +36,18 @@ async fn test_the_clone_instance_procedure_works(pool: sqlx::PgPool) {
         .create(&pool)
         .await
         .expect("could not create project");
+    let zero_trust_network = ZeroTrustNetwork::factory()
+        .organization_slug(organization.slug.clone())
+        .for_zero_trust_network_type(ZeroTrustNetworkType::factory())
+        .create(&pool)
+        .await
+        .expect("could not create zero trust network");
     let instance = Instance::factory()
         .distant_id("100".into())
         .hypervisor_id(hypervisor.id)
         .project_slug(project.slug.clone())
-        .zero_trust_network_id(None)
+        .zero_trust_network_id(Some(zero_trust_network.id))
+        .missing_since(Some(chrono::Utc::now()))
         .create(&pool)
         .await
         .expect("could not create instance");
@@ -53,6 +62,13 @@ async fn test_the_clone_instance_procedure_works(pool: sqlx::PgPool) {
     let instances = &mut api.compute.instances;
     let response = instances.clone(request).await;
 
-    // Assert the result
+    // Assert the result, the clone joins no zero trust network and is not
+    // missing
     assert!(response.is_ok());
+    let id = response.unwrap().into_inner().id.parse().unwrap();
+    let clone = Instance::find(&pool, id)
+        .await
+        .expect("could not find cloned instance");
+    assert_eq!(clone.zero_trust_network_id, None);
+    assert_eq!(clone.missing_since, None);
 }
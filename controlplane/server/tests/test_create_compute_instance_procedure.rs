@@ -0,0 +1,346 @@
+use crate::common::{Api, OnBehalfOf};
+use fabrique::Factory;
+use frn_core::{
+    compute::{Hypervisor, HypervisorHealth, Zone},
+    resourcemanager::{Organization, Project},
+};
+use frn_rpc::v1::compute::{CreateInstanceRequest, InstanceStatus};
+use frn_rpc::v1::workflow::{ExecutionStatus, GetStatusRequest};
+use infrastructure::{ZeroTrustNetwork, ZeroTrustNetworkType};
+use tonic::{Code, Request};
+
+mod common;
+
+/// An address nothing listens on, standing for an unreachable hypervisor.
+const UNREACHABLE_URL: &str = "http://127.0.0.1:1";
+
+const GIB: u64 = 1024 * 1024 * 1024;
+
+fn create_instance_request(zone: &Zone, memory_bytes: u64) -> CreateInstanceRequest {
+    CreateInstanceRequest {
+        image: "debian-12-genericcloud-amd64-20241201-1948.qcow2".to_owned(),
+        cpu_cores: 2,
+        disk_bytes: 20 * GIB,
+        memory_bytes,
+        name: "scheduled-instance".to_owned(),
+        snippet: String::new(),
+        project_slug: "test-project".to_owned(),
+        zone_id: Some(zone.id.to_string()),
+        zero_trust_network_id: None,
+    }
+}
+
+async fn seed_organization_and_project(pool: &sqlx::PgPool) -> Organization {
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(pool)
+        .await
+        .expect("could not create organization");
+    Project::factory()
+        .slug("test-project".to_owned())
+        .organization_slug(organization.slug.clone())
+        .create(pool)
+        .await
+        .expect("could not create project");
+
+    organization
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_create_instance_procedure_schedules_the_provisioning(pool: sqlx::PgPool) {
+    // Arrange a zone with a hypervisor
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let organization = seed_organization_and_project(&pool).await;
+    let zone = Zone::factory()
+        .create(&pool)
+        .await
+        .expect("could not create zone");
+    Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
+        .zone_id(zone.id)
+        .organization_slug(organization.slug.clone())
+        .url(api.mock_server.url())
+        .create(&pool)
+        .await
+        .expect("could not create hypervisor");
+
+    // Act the request to the create instance procedure, then the poll of the
+    // provisioning it returned
+    let request =
+        Request::new(create_instance_request(&zone, 2 * GIB)).on_behalf_of(&api.service_account);
+    let response = api
+        .compute
+        .instances
+        .create(request)
+        .await
+        .expect("could not create instance")
+        .into_inner();
+    let request = Request::new(GetStatusRequest {
+        execution_id: response.execution_id.clone(),
+    })
+    .on_behalf_of(&api.service_account);
+    let status = api
+        .workflow
+        .engine
+        .get_status(request)
+        .await
+        .expect("could not get the provisioning status")
+        .into_inner();
+
+    // Assert the instance awaits its provisioning
+    let instance = response
+        .instance
+        .expect("the response should hold the instance");
+    assert_eq!(instance.status, InstanceStatus::Provisioning as i32);
+    assert_eq!(status.status, ExecutionStatus::Pending as i32);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_create_instance_procedure_fails_without_hypervisors_in_zone(pool: sqlx::PgPool) {
+    // Arrange a zone without hypervisors, next to a zone with one
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let organization = seed_organization_and_project(&pool).await;
+    Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
+        .for_zone(Zone::factory())
+        .organization_slug(organization.slug.clone())
+        .url(api.mock_server.url())
+        .create(&pool)
+        .await
+        .expect("could not create hypervisor");
+    let empty_zone = Zone::factory()
+        .create(&pool)
+        .await
+        .expect("could not create zone");
+
+    // Act the request to the create instance procedure
+    let request = Request::new(create_instance_request(&empty_zone, 2 * GIB))
+        .on_behalf_of(&api.service_account);
+    let response = api.compute.instances.create(request).await;
+
+    // Assert the result
+    assert_eq!(response.unwrap_err().code(), Code::FailedPrecondition);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_create_instance_procedure_skips_unreachable_hypervisors(pool: sqlx::PgPool) {
+    // Arrange a zone with an unreachable hypervisor and a reachable one
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let organization = seed_organization_and_project(&pool).await;
+    let zone = Zone::factory()
+        .create(&pool)
+        .await
+        .expect("could not create zone");
+    for url in [UNREACHABLE_URL.to_owned(), api.mock_server.url()] {
+        Hypervisor::factory()
+            .authorization_token("PVEAPIToken=root@pam!api=secret")
+            .zone_id(zone.id)
+            .organization_slug(organization.slug.clone())
+            .url(url)
+            .create(&pool)
+            .await
+            .expect("could not create hypervisor");
+    }
+
+    // Act a request larger than the memory left on the reachable hypervisor
+    let request =
+        Request::new(create_instance_request(&zone, 64 * GIB)).on_behalf_of(&api.service_account);
+    let response = api.compute.instances.create(request).await;
+
+    // Assert the reachable hypervisor was probed and refused the shape
+    let status = response.unwrap_err();
+    assert_eq!(status.code(), Code::ResourceExhausted);
+    assert!(status.message().contains(&zone.id.to_string()));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_create_instance_procedure_skips_hung_hypervisors(pool: sqlx::PgPool) {
+    // Arrange a zone with a hypervisor accepting connections without ever
+    // answering, and a reachable one
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let organization = seed_organization_and_project(&pool).await;
+    let zone = Zone::factory()
+        .create(&pool)
+        .await
+        .expect("could not create zone");
+    let hung = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("could not bind listener");
+    let hung_url = format!("http://{}", hung.local_addr().unwrap());
+    for url in [hung_url, api.mock_server.url()] {
+        Hypervisor::factory()
+            .authorization_token("PVEAPIToken=root@pam!api=secret")
+            .zone_id(zone.id)
+            .organization_slug(organization.slug.clone())
+            .url(url)
+            .create(&pool)
+            .await
+            .expect("could not create hypervisor");
+    }
+
+    // Act a request larger than the memory left on the reachable hypervisor
+    let request =
+        Request::new(create_instance_request(&zone, 64 * GIB)).on_behalf_of(&api.service_account);
+    let response = api.compute.instances.create(request).await;
+
+    // Assert the hung hypervisor was given up on, and the reachable one
+    // refused the shape
+    assert_eq!(response.unwrap_err().code(), Code::ResourceExhausted);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_create_instance_procedure_fails_when_hypervisors_are_unreachable(
+    pool: sqlx::PgPool,
+) {
+    // Arrange a zone whose only hypervisor is unreachable
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let organization = seed_organization_and_project(&pool).await;
+    let zone = Zone::factory()
+        .create(&pool)
+        .await
+        .expect("could not create zone");
+    Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
+        .zone_id(zone.id)
+        .organization_slug(organization.slug.clone())
+        .url(UNREACHABLE_URL.to_owned())
+        .create(&pool)
+        .await
+        .expect("could not create hypervisor");
+
+    // Act the request to the create instance procedure
+    let request =
+        Request::new(create_instance_request(&zone, 2 * GIB)).on_behalf_of(&api.service_account);
+    let response = api.compute.instances.create(request).await;
+
+    // Assert the result
+    assert_eq!(response.unwrap_err().code(), Code::Unavailable);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_create_instance_procedure_skips_hypervisors_probed_unreachable(
+    pool: sqlx::PgPool,
+) {
+    // Arrange a zone whose only hypervisor answers, but failed its last probe
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let organization = seed_organization_and_project(&pool).await;
+    let zone = Zone::factory()
+        .create(&pool)
+        .await
+        .expect("could not create zone");
+    Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
+        .zone_id(zone.id)
+        .organization_slug(organization.slug.clone())
+        .url(api.mock_server.url())
+        .health(HypervisorHealth::Unreachable)
+        .create(&pool)
+        .await
+        .expect("could not create hypervisor");
+
+    // Act the request to the create instance procedure
+    let request =
+        Request::new(create_instance_request(&zone, 2 * GIB)).on_behalf_of(&api.service_account);
+    let response = api.compute.instances.create(request).await;
+
+    // Assert the hypervisor was not considered
+    assert_eq!(response.unwrap_err().code(), Code::Unavailable);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_create_instance_procedure_rejects_unknown_images(pool: sqlx::PgPool) {
+    // Arrange a zone with a hypervisor holding a catalogue of images
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let organization = seed_organization_and_project(&pool).await;
+    let zone = Zone::factory()
+        .create(&pool)
+        .await
+        .expect("could not create zone");
+    Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
+        .zone_id(zone.id)
+        .organization_slug(organization.slug.clone())
+        .url(api.mock_server.url())
+        .storage_name("local".to_owned())
+        .create(&pool)
+        .await
+        .expect("could not create hypervisor");
+
+    // Act a request for an image missing from the catalogue
+    let request = Request::new(CreateInstanceRequest {
+        image: "local:import/ubuntu-24.04-server-cloudimg-amd64.qcow2".to_owned(),
+        ..create_instance_request(&zone, 2 * GIB)
+    })
+    .on_behalf_of(&api.service_account);
+    let response = api.compute.instances.create(request).await;
+
+    // Assert the result
+    assert_eq!(response.unwrap_err().code(), Code::InvalidArgument);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_create_instance_procedure_attaches_zero_trust_networks_of_its_organization(
+    pool: sqlx::PgPool,
+) {
+    // Arrange a zone with a hypervisor, and networks of the organization of
+    // the project and of another one
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let organization = seed_organization_and_project(&pool).await;
+    let other = Organization::factory()
+        .slug("other-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await
+        .expect("could not create organization");
+    let zone = Zone::factory()
+        .create(&pool)
+        .await
+        .expect("could not create zone");
+    Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
+        .zone_id(zone.id)
+        .organization_slug(organization.slug.clone())
+        .url(api.mock_server.url())
+        .create(&pool)
+        .await
+        .expect("could not create hypervisor");
+    let zero_trust_network = ZeroTrustNetwork::factory()
+        .organization_slug(organization.slug.clone())
+        .for_zero_trust_network_type(ZeroTrustNetworkType::factory())
+        .create(&pool)
+        .await
+        .expect("could not create zero trust network");
+    let foreign_network = ZeroTrustNetwork::factory()
+        .organization_slug(other.slug.clone())
+        .for_zero_trust_network_type(ZeroTrustNetworkType::factory())
+        .create(&pool)
+        .await
+        .expect("could not create zero trust network");
+
+    // Act the requests creating an instance in each network
+    let request = Request::new(CreateInstanceRequest {
+        zero_trust_network_id: Some(zero_trust_network.id.to_string()),
+        ..create_instance_request(&zone, 2 * GIB)
+    })
+    .on_behalf_of(&api.service_account);
+    let attached = api.compute.instances.create(request).await;
+    let request = Request::new(CreateInstanceRequest {
+        zero_trust_network_id: Some(foreign_network.id.to_string()),
+        ..create_instance_request(&zone, 2 * GIB)
+    })
+    .on_behalf_of(&api.service_account);
+    let foreign = api.compute.instances.create(request).await;
+
+    // Assert only the network of the organization was accepted
+    let instance = attached
+        .expect("could not create instance")
+        .into_inner()
+        .instance
+        .expect("the response should hold the instance");
+    assert_eq!(
+        instance.zero_trust_network_id,
+        Some(zero_trust_network.id.to_string())
+    );
+    assert_eq!(foreign.unwrap_err().code(), Code::NotFound);
+}
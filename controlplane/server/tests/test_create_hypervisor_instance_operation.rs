@@ -0,0 +1,82 @@
+use crate::common::worker_context;
+use fabrique::Factory;
+use frn_core::compute::{Hypervisor, Instance, Zone};
+use frn_core::resourcemanager::{Organization, Project};
+use hypervisor::instance::Status;
+use hypervisor::proxmox::fake::{FakeProxmox, WithFakeProxmox};
+use mock_server::MockServer;
+use workflow::execution::WorkflowExecutionId;
+use workflow::operations::Operation;
+use workflow::operations::create_hypervisor_instance::CreateHypervisorInstanceOp;
+
+mod common;
+
+const GIB: u64 = 1024 * 1024 * 1024;
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_execute_keeps_the_vm_apart_from_the_instances_of_its_project(
+    pool: sqlx::PgPool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let snippets = common::snippets_volume();
+    // SAFETY: this is the only test of the binary, so no other thread reads
+    // the environment while it is modified.
+    unsafe { std::env::set_var("PROXMOX_VOLUME_ABSOLUTE_PATH", &snippets) };
+
+    // Arrange a cluster of two nodes, the emptier one running an instance of
+    // the project
+    let proxmox = FakeProxmox::new()
+        .with_node("pve-node1", 16, 64 * GIB)
+        .with_node("pve-node2", 16, 32 * GIB)
+        .with_vm("pve-node1", 100, "web-1", Status::Running);
+    let server = MockServer::new().await.with_fake_proxmox(&proxmox);
+    let ctx = worker_context(&pool).await;
+
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await?;
+    let hypervisor = Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
+        .for_zone(Zone::factory())
+        .organization_slug(organization.slug.clone())
+        .url(server.url())
+        .create(&pool)
+        .await?;
+    let project = Project::factory()
+        .slug("test-project".to_owned())
+        .organization_slug(organization.slug.clone())
+        .create(&pool)
+        .await?;
+    Instance::factory()
+        .hypervisor_id(hypervisor.id)
+        .project_slug(project.slug.clone())
+        .zero_trust_network_id(None)
+        .distant_id("100".to_owned())
+        .create(&pool)
+        .await?;
+
+    // Act the creation of the VM of another instance of the project
+    let operation = CreateHypervisorInstanceOp {
+        hypervisor_id: hypervisor.id,
+        project_slug: project.slug.clone(),
+        name: "web-2".to_owned(),
+        cores: 2,
+        disk_bytes: 20 * GIB,
+        disk_image: "debian-12-genericcloud-amd64-20241201-1948.qcow2".to_owned(),
+        memory_bytes: 2 * GIB,
+        snippet: String::new(),
+        distant_id: None,
+    }
+    .execute(ctx, WorkflowExecutionId::new())
+    .await?;
+
+    // Assert the VM was placed on the other node
+    let distant_id = operation
+        .distant_id
+        .expect("the vm should be created")
+        .parse::<u32>()?;
+    assert_eq!(proxmox.vm(distant_id).unwrap().node, "pve-node2");
+
+    Ok(())
+}
@@ -34,9 +34,9 @@ impl WorkflowScheduler<DeployManagedServiceParams> for CapturingScheduler {
         &self,
         _conn: &mut PgConnection,
         params: DeployManagedServiceParams,
-    ) -> Result<(), String> {
+    ) -> Result<Uuid, String> {
         *self.captured.lock().unwrap() = Some(params.cluster_id);
-        Ok(())
+        Ok(Uuid::nil())
     }
 }
 
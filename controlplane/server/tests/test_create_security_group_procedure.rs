@@ -0,0 +1,41 @@
+use crate::common::{Api, OnBehalfOf};
+use fabrique::Factory;
+use frn_core::resourcemanager::{Organization, Project};
+use frn_rpc::v1::compute::CreateSecurityGroupRequest;
+use tonic::Request;
+
+mod common;
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_create_security_group_procedure_works(pool: sqlx::PgPool) {
+    // Arrange the grpc server and a client
+    let mut api = Api::start(&pool).await.expect("could not start api");
+
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await
+        .expect("could not create organization");
+    let project = Project::factory()
+        .slug("test-project".to_owned())
+        .organization_slug(organization.slug.clone())
+        .create(&pool)
+        .await
+        .expect("could not create project");
+
+    // Act the request to the test_the_create_security_group_procedure_works
+    let request = Request::new(CreateSecurityGroupRequest {
+        name: "web".to_owned(),
+        description: "Public web servers".to_owned(),
+        project_slug: project.slug.clone(),
+    })
+    .on_behalf_of(&api.service_account);
+    let response = api.compute.security_groups.create(request).await;
+
+    // Assert the result
+    let security_group = response.unwrap().into_inner().security_group.unwrap();
+    assert_eq!(security_group.name, "web");
+    assert_eq!(security_group.description, "Public web servers");
+    assert_eq!(security_group.project_slug, "test-project");
+}
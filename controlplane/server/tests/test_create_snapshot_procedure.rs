@@ -0,0 +1,61 @@
+use crate::common::{Api, OnBehalfOf};
+use fabrique::Factory;
+use frn_core::{
+    compute::{Hypervisor, Instance, Zone},
+    resourcemanager::{Organization, Project},
+};
+use frn_rpc::v1::compute::CreateSnapshotRequest;
+use tonic::Request;
+
+mod common;
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_create_snapshot_procedure_works(pool: sqlx::PgPool) {
+    // Arrange the grpc server and a client
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let mock_url = api.mock_server.url();
+
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await
+        .expect("could not create organization");
+    let hypervisor = Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
+        .for_zone(Zone::factory())
+        .organization_slug(organization.slug.clone())
+        .url(mock_url)
+        .create(&pool)
+        .await
+        .expect("could not create hypervisor");
+    let project = Project::factory()
+        .slug("test-project".to_owned())
+        .organization_slug(organization.slug.clone())
+        .create(&pool)
+        .await
+        .expect("could not create project");
+    let instance = Instance::factory()
+        .hypervisor_id(hypervisor.id)
+        .project_slug(project.slug.clone())
+        .distant_id("100".into())
+        .zero_trust_network_id(None)
+        .create(&pool)
+        .await
+        .expect("could not create instance");
+
+    // Act the request to the test_the_create_snapshot_procedure_works
+    let request = Request::new(CreateSnapshotRequest {
+        instance_id: instance.id.to_string(),
+        name: "before-upgrade".to_owned(),
+        description: Some("pre apt upgrade".to_owned()),
+        include_memory: false,
+    })
+    .on_behalf_of(&api.service_account);
+    let response = api.compute.instances.create_snapshot(request).await;
+
+    // Assert the result
+    assert!(response.is_ok());
+    let snapshot = response.unwrap().into_inner().snapshot.unwrap();
+    assert_eq!(snapshot.name, "before-upgrade");
+}
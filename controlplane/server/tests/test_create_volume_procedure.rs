@@ -0,0 +1,64 @@
+use crate::common::{Api, OnBehalfOf};
+use fabrique::Factory;
+use frn_core::{
+    compute::{Hypervisor, Instance, Zone},
+    resourcemanager::{Organization, Project},
+};
+use frn_rpc::v1::compute::CreateVolumeRequest;
+use tonic::Request;
+
+mod common;
+
+const GIB: u64 = 1024 * 1024 * 1024;
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_create_volume_procedure_works(pool: sqlx::PgPool) {
+    // Arrange the grpc server and a client
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let mock_url = api.mock_server.url();
+
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await
+        .expect("could not create organization");
+    let hypervisor = Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
+        .for_zone(Zone::factory())
+        .organization_slug(organization.slug.clone())
+        .url(mock_url)
+        .create(&pool)
+        .await
+        .expect("could not create hypervisor");
+    let project = Project::factory()
+        .slug("test-project".to_owned())
+        .organization_slug(organization.slug.clone())
+        .create(&pool)
+        .await
+        .expect("could not create project");
+    let instance = Instance::factory()
+        .hypervisor_id(hypervisor.id)
+        .project_slug(project.slug.clone())
+        .distant_id("100".into())
+        .zero_trust_network_id(None)
+        .create(&pool)
+        .await
+        .expect("could not create instance");
+
+    // Act the request to the test_the_create_volume_procedure_works
+    let request = Request::new(CreateVolumeRequest {
+        instance_id: instance.id.to_string(),
+        name: "data".to_owned(),
+        size_bytes: 10 * GIB - 1,
+    })
+    .on_behalf_of(&api.service_account);
+    let response = api.compute.volumes.create(request).await;
+
+    // Assert the volume was attached to the first free device
+    let volume = response.unwrap().into_inner().volume.unwrap();
+    assert_eq!(volume.instance_id, instance.id.to_string());
+    assert_eq!(volume.project_slug, project.slug);
+    assert_eq!(volume.device.as_deref(), Some("scsi2"));
+    assert_eq!(volume.size_bytes, 10 * GIB);
+}
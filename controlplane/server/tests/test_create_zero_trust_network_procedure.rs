@@ -0,0 +1,72 @@
+use crate::common::{Api, OnBehalfOf};
+use fabrique::Factory;
+use frn_core::resourcemanager::Organization;
+use infrastructure::ZeroTrustNetworkType;
+use infrastructure::v1::CreateZeroTrustNetworkRequest;
+use tonic::{Code, Request};
+
+mod common;
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_create_zero_trust_network_procedure_works(pool: sqlx::PgPool) {
+    // Arrange the grpc server and a client
+    let mut api = Api::start(&pool).await.expect("could not start api");
+
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await
+        .expect("could not create organization");
+    let zero_trust_network_type = ZeroTrustNetworkType::factory()
+        .create(&pool)
+        .await
+        .expect("could not create zero trust network type");
+
+    // Act the request to the test_the_create_zero_trust_network_procedure_works
+    let request = Request::new(CreateZeroTrustNetworkRequest {
+        organization_slug: organization.slug.clone(),
+        zero_trust_network_type_id: zero_trust_network_type.id.to_string(),
+        name: "office".to_owned(),
+    })
+    .on_behalf_of(&api.service_account);
+    let response = api.infrastructure.zero_trust_networks.create(request).await;
+
+    // Assert the result
+    let zero_trust_network = response
+        .expect("could not create zero trust network")
+        .into_inner()
+        .zero_trust_network
+        .expect("missing zero trust network");
+    assert_eq!(zero_trust_network.name, "office");
+    assert_eq!(zero_trust_network.organization_slug, organization.slug);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_create_zero_trust_network_procedure_rejects_invalid_names(pool: sqlx::PgPool) {
+    // Arrange the grpc server and a client
+    let mut api = Api::start(&pool).await.expect("could not start api");
+
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await
+        .expect("could not create organization");
+    let zero_trust_network_type = ZeroTrustNetworkType::factory()
+        .create(&pool)
+        .await
+        .expect("could not create zero trust network type");
+
+    // Act the request to the test_the_create_zero_trust_network_procedure_rejects_invalid_names
+    let request = Request::new(CreateZeroTrustNetworkRequest {
+        organization_slug: organization.slug,
+        zero_trust_network_type_id: zero_trust_network_type.id.to_string(),
+        name: "office/paris".to_owned(),
+    })
+    .on_behalf_of(&api.service_account);
+    let response = api.infrastructure.zero_trust_networks.create(request).await;
+
+    // Assert the result
+    assert_eq!(response.unwrap_err().code(), Code::InvalidArgument);
+}
@@ -0,0 +1,49 @@
+use crate::common::{Api, WithUser, non_admin_token, seed_admin_token};
+use fabrique::Query;
+use frn_core::compute::Zone;
+use frn_rpc::v1::compute::CreateZoneRequest;
+use tonic::{Code, Request};
+
+mod common;
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_create_zone_procedure_works(pool: sqlx::PgPool) {
+    // Arrange the grpc server and a client
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let token = seed_admin_token(&pool, "admin@francenuage.fr").await;
+
+    // Act the request to the test_the_create_zone_procedure_works
+    let request = Request::new(CreateZoneRequest {
+        name: "paris-1".to_owned(),
+    })
+    .with_user(&token);
+    let zone = api
+        .compute
+        .zones
+        .create(request)
+        .await
+        .expect("could not create zone")
+        .into_inner()
+        .zone
+        .expect("the response should hold the zone");
+
+    // Assert the result
+    assert_eq!(zone.name, "paris-1");
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_create_zone_procedure_is_restricted_to_admins(pool: sqlx::PgPool) {
+    // Arrange the grpc server and a client
+    let mut api = Api::start(&pool).await.expect("could not start api");
+
+    // Act the request to the test_the_create_zone_procedure_is_restricted_to_admins
+    let request = Request::new(CreateZoneRequest {
+        name: "paris-1".to_owned(),
+    })
+    .with_user(&non_admin_token("member@francenuage.fr"));
+    let response = api.compute.zones.create(request).await;
+
+    // Assert the result
+    assert_eq!(response.unwrap_err().code(), Code::PermissionDenied);
+    assert!(Zone::all(&pool).await.unwrap().is_empty());
+}
@@ -0,0 +1,106 @@
+use crate::common::{Api, OnBehalfOf};
+use fabrique::Factory;
+use frn_core::{
+    compute::{Hypervisor, Instance, Zone},
+    resourcemanager::{Organization, Project},
+};
+use frn_rpc::v1::compute::DeleteBackupRequest;
+use hypervisor::mock::BACKUP_OWNER;
+use tonic::{Code, Request};
+
+mod common;
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_delete_backup_procedure_works(pool: sqlx::PgPool) {
+    // Arrange the grpc server and a client
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let mock_url = api.mock_server.url();
+
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await
+        .expect("could not create organization");
+    let hypervisor = Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
+        .for_zone(Zone::factory())
+        .organization_slug(organization.slug.clone())
+        .url(mock_url)
+        .create(&pool)
+        .await
+        .expect("could not create hypervisor");
+    let project = Project::factory()
+        .slug("test-project".to_owned())
+        .organization_slug(organization.slug.clone())
+        .create(&pool)
+        .await
+        .expect("could not create project");
+    let instance = Instance::factory()
+        .id(BACKUP_OWNER)
+        .hypervisor_id(hypervisor.id)
+        .project_slug(project.slug.clone())
+        .distant_id("100".into())
+        .zero_trust_network_id(None)
+        .create(&pool)
+        .await
+        .expect("could not create instance");
+
+    // Act the request to the test_the_delete_backup_procedure_works
+    let request = Request::new(DeleteBackupRequest {
+        instance_id: instance.id.to_string(),
+        backup_id: "pbs:backup/vm/100/2025-03-08T18:00:00Z".to_owned(),
+    })
+    .on_behalf_of(&api.service_account);
+    let response = api.compute.backups.delete(request).await;
+
+    // Assert the result
+    assert!(response.is_ok());
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_delete_backup_procedure_rejects_unknown_backups(pool: sqlx::PgPool) {
+    // Arrange the grpc server and a client
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let mock_url = api.mock_server.url();
+
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await
+        .expect("could not create organization");
+    let hypervisor = Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
+        .for_zone(Zone::factory())
+        .organization_slug(organization.slug.clone())
+        .url(mock_url)
+        .create(&pool)
+        .await
+        .expect("could not create hypervisor");
+    let project = Project::factory()
+        .slug("test-project".to_owned())
+        .organization_slug(organization.slug.clone())
+        .create(&pool)
+        .await
+        .expect("could not create project");
+    let instance = Instance::factory()
+        .hypervisor_id(hypervisor.id)
+        .project_slug(project.slug.clone())
+        .distant_id("100".into())
+        .zero_trust_network_id(None)
+        .create(&pool)
+        .await
+        .expect("could not create instance");
+
+    // Act the request to the test_the_delete_backup_procedure_rejects_unknown_backups
+    let request = Request::new(DeleteBackupRequest {
+        instance_id: instance.id.to_string(),
+        backup_id: "local:iso/debian-12.7.0-amd64-netinst.iso".to_owned(),
+    })
+    .on_behalf_of(&api.service_account);
+    let response = api.compute.backups.delete(request).await;
+
+    // Assert the result
+    assert_eq!(response.unwrap_err().code(), Code::NotFound);
+}
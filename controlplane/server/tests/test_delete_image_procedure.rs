@@ -0,0 +1,64 @@
+use crate::common::{Api, OnBehalfOf};
+use fabrique::Factory;
+use frn_core::{
+    compute::{Hypervisor, Zone},
+    resourcemanager::Organization,
+};
+use frn_rpc::v1::compute::DeleteImageRequest;
+use tonic::{Code, Request};
+
+mod common;
+
+async fn seed_hypervisor(pool: &sqlx::PgPool, url: String) -> Hypervisor {
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(pool)
+        .await
+        .expect("could not create organization");
+    Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
+        .for_zone(Zone::factory())
+        .organization_slug(organization.slug.clone())
+        .url(url)
+        .storage_name("local".to_owned())
+        .create(pool)
+        .await
+        .expect("could not create hypervisor")
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_delete_image_procedure_works(pool: sqlx::PgPool) {
+    // Arrange the grpc server and a hypervisor holding images
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let hypervisor = seed_hypervisor(&pool, api.mock_server.url()).await;
+
+    // Act the request to the delete image procedure
+    let request = Request::new(DeleteImageRequest {
+        hypervisor_id: hypervisor.id.to_string(),
+        id: "local:import/debian-12-genericcloud-amd64.qcow2".to_owned(),
+    })
+    .on_behalf_of(&api.service_account);
+    let response = api.compute.images.delete(request).await;
+
+    // Assert the result
+    assert!(response.is_ok());
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_delete_image_procedure_refuses_other_volumes(pool: sqlx::PgPool) {
+    // Arrange the grpc server and a hypervisor
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let hypervisor = seed_hypervisor(&pool, api.mock_server.url()).await;
+
+    // Act the request to delete the snippet of an instance
+    let request = Request::new(DeleteImageRequest {
+        hypervisor_id: hypervisor.id.to_string(),
+        id: "local:snippets/vm-100-user.yaml".to_owned(),
+    })
+    .on_behalf_of(&api.service_account);
+    let response = api.compute.images.delete(request).await;
+
+    // Assert the volume is not treated as an image
+    assert_eq!(response.unwrap_err().code(), Code::InvalidArgument);
+}
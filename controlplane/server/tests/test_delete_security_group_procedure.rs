@@ -0,0 +1,139 @@
+use crate::common::{Api, OnBehalfOf};
+use fabrique::Factory;
+use fabrique::Query;
+use frn_core::{
+    compute::{Hypervisor, Instance, InstanceSecurityGroup, SecurityGroup, Zone},
+    resourcemanager::{Organization, Project},
+};
+use frn_rpc::v1::compute::DeleteSecurityGroupRequest;
+use tonic::Request;
+
+mod common;
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_delete_security_group_procedure_works(pool: sqlx::PgPool) {
+    // Arrange the grpc server and a client
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let mock_url = api.mock_server.url();
+
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await
+        .expect("could not create organization");
+    let hypervisor = Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
+        .for_zone(Zone::factory())
+        .organization_slug(organization.slug.clone())
+        .url(mock_url)
+        .create(&pool)
+        .await
+        .expect("could not create hypervisor");
+    let project = Project::factory()
+        .slug("test-project".to_owned())
+        .organization_slug(organization.slug.clone())
+        .create(&pool)
+        .await
+        .expect("could not create project");
+    let instance = Instance::factory()
+        .hypervisor_id(hypervisor.id)
+        .project_slug(project.slug.clone())
+        .distant_id("100".into())
+        .zero_trust_network_id(None)
+        .create(&pool)
+        .await
+        .expect("could not create instance");
+    let security_group = SecurityGroup::factory()
+        .project_slug(project.slug.clone())
+        .name("web".to_owned())
+        .create(&pool)
+        .await
+        .expect("could not create security group");
+    InstanceSecurityGroup::factory()
+        .instance_id(instance.id)
+        .security_group_id(security_group.id)
+        .create(&pool)
+        .await
+        .expect("could not attach security group");
+
+    // Act the request to the test_the_delete_security_group_procedure_works
+    let request = Request::new(DeleteSecurityGroupRequest {
+        id: security_group.id.to_string(),
+    })
+    .on_behalf_of(&api.service_account);
+    let response = api.compute.security_groups.delete(request).await;
+
+    // Assert the group and its attachment were deleted
+    assert!(response.is_ok());
+    let attachments = InstanceSecurityGroup::query()
+        .select()
+        .get(&pool)
+        .await
+        .expect("could not list attachments");
+    assert!(attachments.is_empty());
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_delete_security_group_procedure_deletes_the_group_of_unreachable_instances(
+    pool: sqlx::PgPool,
+) {
+    // Arrange the grpc server and a group attached to the instance of a
+    // hypervisor which does not answer
+    let mut api = Api::start(&pool).await.expect("could not start api");
+
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await
+        .expect("could not create organization");
+    let hypervisor = Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
+        .for_zone(Zone::factory())
+        .organization_slug(organization.slug.clone())
+        .url("http://127.0.0.1:1".to_owned())
+        .create(&pool)
+        .await
+        .expect("could not create hypervisor");
+    let project = Project::factory()
+        .slug("test-project".to_owned())
+        .organization_slug(organization.slug.clone())
+        .create(&pool)
+        .await
+        .expect("could not create project");
+    let instance = Instance::factory()
+        .hypervisor_id(hypervisor.id)
+        .project_slug(project.slug.clone())
+        .distant_id("100".into())
+        .zero_trust_network_id(None)
+        .create(&pool)
+        .await
+        .expect("could not create instance");
+    let security_group = SecurityGroup::factory()
+        .project_slug(project.slug.clone())
+        .name("web".to_owned())
+        .create(&pool)
+        .await
+        .expect("could not create security group");
+    InstanceSecurityGroup::factory()
+        .instance_id(instance.id)
+        .security_group_id(security_group.id)
+        .create(&pool)
+        .await
+        .expect("could not attach security group");
+
+    // Act the request to delete the group
+    let request = Request::new(DeleteSecurityGroupRequest {
+        id: security_group.id.to_string(),
+    })
+    .on_behalf_of(&api.service_account);
+    let response = api.compute.security_groups.delete(request).await;
+
+    // Assert the group was deleted nonetheless
+    assert!(response.is_ok());
+    let groups = SecurityGroup::all(&pool)
+        .await
+        .expect("could not list security groups");
+    assert!(groups.is_empty());
+}
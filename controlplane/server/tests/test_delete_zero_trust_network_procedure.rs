@@ -0,0 +1,63 @@
+use crate::common::{Api, OnBehalfOf};
+use fabrique::{Factory, Query};
+use frn_core::{
+    compute::{Hypervisor, Instance, Zone},
+    resourcemanager::{Organization, Project},
+};
+use infrastructure::v1::DeleteZeroTrustNetworkRequest;
+use infrastructure::{ZeroTrustNetwork, ZeroTrustNetworkType};
+use tonic::Request;
+
+mod common;
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_delete_zero_trust_network_procedure_works(pool: sqlx::PgPool) {
+    // Arrange the grpc server and a client
+    let mut api = Api::start(&pool).await.expect("could not start api");
+
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await
+        .expect("could not create organization");
+    let zero_trust_network = ZeroTrustNetwork::factory()
+        .organization_slug(organization.slug.clone())
+        .for_zero_trust_network_type(ZeroTrustNetworkType::factory())
+        .create(&pool)
+        .await
+        .expect("could not create zero trust network");
+    let hypervisor = Hypervisor::factory()
+        .for_zone(Zone::factory())
+        .organization_slug(organization.slug.clone())
+        .create(&pool)
+        .await
+        .expect("could not create hypervisor");
+    let project = Project::factory()
+        .slug("test-project".to_owned())
+        .organization_slug(organization.slug)
+        .create(&pool)
+        .await
+        .expect("could not create project");
+    let instance = Instance::factory()
+        .hypervisor_id(hypervisor.id)
+        .project_slug(project.slug)
+        .zero_trust_network_id(Some(zero_trust_network.id))
+        .create(&pool)
+        .await
+        .expect("could not create instance");
+
+    // Act the request to the test_the_delete_zero_trust_network_procedure_works
+    let request = Request::new(DeleteZeroTrustNetworkRequest {
+        id: zero_trust_network.id.to_string(),
+    })
+    .on_behalf_of(&api.service_account);
+    let response = api.infrastructure.zero_trust_networks.delete(request).await;
+
+    // Assert the network was deleted and its instance detached
+    assert!(response.is_ok());
+    let instance = Instance::find(&pool, instance.id)
+        .await
+        .expect("could not find instance");
+    assert_eq!(instance.zero_trust_network_id, None);
+}
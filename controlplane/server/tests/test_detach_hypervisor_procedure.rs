@@ -1,11 +1,11 @@
-use crate::common::{Api, OnBehalfOf};
-use fabrique::Factory;
+use crate::common::{Api, WithUser, non_admin_token, seed_admin_token};
+use fabrique::{Factory, Query};
 use frn_core::{
     compute::{Hypervisor, Zone},
     resourcemanager::Organization,
 };
 use frn_rpc::v1::compute::DetachHypervisorRequest;
-use tonic::Request;
+use tonic::{Code, Request};
 
 mod common;
 
@@ -19,6 +19,7 @@ async fn test_the_detach_hypervisor_procedure_works(pool: sqlx::PgPool) {
         .await
         .expect("could not create organization");
     let hypervisor = Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
         .for_zone(Zone::factory())
         .organization_slug(organization.slug.clone())
         .create(&pool)
@@ -26,14 +27,46 @@ async fn test_the_detach_hypervisor_procedure_works(pool: sqlx::PgPool) {
         .expect("could not bootstrap data");
 
     let mut api = Api::start(&pool).await.expect("could not start api");
+    let token = seed_admin_token(&pool, "admin@francenuage.fr").await;
 
     // Act the request to the test_the_status_procedure_works
     let request = Request::new(DetachHypervisorRequest {
         id: hypervisor.id.to_string(),
     })
-    .on_behalf_of(&api.service_account);
+    .with_user(&token);
     let result = api.compute.hypervisors.detach(request).await;
 
     // Assert the result
     assert!(result.is_ok());
 }
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_detach_hypervisor_procedure_is_restricted_to_admins(pool: sqlx::PgPool) {
+    // Arrange the grpc server and a client
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await
+        .expect("could not create organization");
+    let hypervisor = Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
+        .for_zone(Zone::factory())
+        .organization_slug(organization.slug.clone())
+        .create(&pool)
+        .await
+        .expect("could not bootstrap data");
+
+    let mut api = Api::start(&pool).await.expect("could not start api");
+
+    // Act the request to the test_the_detach_hypervisor_procedure_is_restricted_to_admins
+    let request = Request::new(DetachHypervisorRequest {
+        id: hypervisor.id.to_string(),
+    })
+    .with_user(&non_admin_token("member@francenuage.fr"));
+    let result = api.compute.hypervisors.detach(request).await;
+
+    // Assert the result
+    assert_eq!(result.unwrap_err().code(), Code::PermissionDenied);
+    assert!(Hypervisor::find(&pool, hypervisor.id).await.is_ok());
+}
@@ -0,0 +1,141 @@
+use crate::common::{Api, OnBehalfOf};
+use fabrique::{Factory, Query};
+use frn_core::{
+    compute::{Hypervisor, Instance, ZeroTrustNetworkPeer, Zone},
+    resourcemanager::{Organization, Project},
+};
+use infrastructure::v1::DetachZeroTrustNetworkInstanceRequest;
+use infrastructure::{ZeroTrustNetwork, ZeroTrustNetworkType};
+use tonic::{Code, Request};
+
+mod common;
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_detach_zero_trust_network_instance_procedure_works(pool: sqlx::PgPool) {
+    // Arrange the grpc server and a client
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let mock_url = api.mock_server.url();
+
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await
+        .expect("could not create organization");
+    let zero_trust_network = ZeroTrustNetwork::factory()
+        .organization_slug(organization.slug.clone())
+        .for_zero_trust_network_type(ZeroTrustNetworkType::factory())
+        .create(&pool)
+        .await
+        .expect("could not create zero trust network");
+    let hypervisor = Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
+        .for_zone(Zone::factory())
+        .organization_slug(organization.slug.clone())
+        .url(mock_url)
+        .create(&pool)
+        .await
+        .expect("could not create hypervisor");
+    let project = Project::factory()
+        .slug("test-project".to_owned())
+        .organization_slug(organization.slug.clone())
+        .create(&pool)
+        .await
+        .expect("could not create project");
+    let instance = Instance::factory()
+        .hypervisor_id(hypervisor.id)
+        .project_slug(project.slug)
+        .distant_id("100".into())
+        .zero_trust_network_id(Some(zero_trust_network.id))
+        .create(&pool)
+        .await
+        .expect("could not create instance");
+    ZeroTrustNetworkPeer::factory()
+        .zero_trust_network_id(zero_trust_network.id)
+        .instance_id(instance.id)
+        .overlay_ip("100.64.0.1".to_owned())
+        .create(&pool)
+        .await
+        .expect("could not create peer");
+
+    // Act the request to the test_the_detach_zero_trust_network_instance_procedure_works
+    let request = Request::new(DetachZeroTrustNetworkInstanceRequest {
+        id: zero_trust_network.id.to_string(),
+        instance_id: instance.id.to_string(),
+    })
+    .on_behalf_of(&api.service_account);
+    let response = api
+        .infrastructure
+        .zero_trust_networks
+        .detach_instance(request)
+        .await;
+
+    // Assert the instance left the network
+    assert!(response.is_ok());
+    let instance = Instance::find(&pool, instance.id)
+        .await
+        .expect("could not find instance");
+    assert_eq!(instance.zero_trust_network_id, None);
+    let peers = ZeroTrustNetworkPeer::query()
+        .select()
+        .r#where(ZeroTrustNetworkPeer::INSTANCE_ID, "=", instance.id)
+        .get(&pool)
+        .await
+        .expect("could not query peers");
+    assert!(peers.is_empty());
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_detach_zero_trust_network_instance_procedure_fails_when_not_attached(
+    pool: sqlx::PgPool,
+) {
+    // Arrange the grpc server and a client
+    let mut api = Api::start(&pool).await.expect("could not start api");
+
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await
+        .expect("could not create organization");
+    let zero_trust_network = ZeroTrustNetwork::factory()
+        .organization_slug(organization.slug.clone())
+        .for_zero_trust_network_type(ZeroTrustNetworkType::factory())
+        .create(&pool)
+        .await
+        .expect("could not create zero trust network");
+    let hypervisor = Hypervisor::factory()
+        .for_zone(Zone::factory())
+        .organization_slug(organization.slug.clone())
+        .create(&pool)
+        .await
+        .expect("could not create hypervisor");
+    let project = Project::factory()
+        .slug("test-project".to_owned())
+        .organization_slug(organization.slug.clone())
+        .create(&pool)
+        .await
+        .expect("could not create project");
+    let instance = Instance::factory()
+        .hypervisor_id(hypervisor.id)
+        .project_slug(project.slug)
+        .zero_trust_network_id(None)
+        .create(&pool)
+        .await
+        .expect("could not create instance");
+
+    // Act the request to the test_the_detach_zero_trust_network_instance_procedure_fails_when_not_attached
+    let request = Request::new(DetachZeroTrustNetworkInstanceRequest {
+        id: zero_trust_network.id.to_string(),
+        instance_id: instance.id.to_string(),
+    })
+    .on_behalf_of(&api.service_account);
+    let response = api
+        .infrastructure
+        .zero_trust_networks
+        .detach_instance(request)
+        .await;
+
+    // Assert the result
+    assert_eq!(response.unwrap_err().code(), Code::FailedPrecondition);
+}
@@ -0,0 +1,144 @@
+use crate::common::{Api, OnBehalfOf, WithUser, seed_admin_token};
+use fabrique::Factory;
+use frn_core::{
+    compute::{Hypervisor, Instance, Zone},
+    resourcemanager::{Organization, Project},
+};
+use frn_rpc::v1::compute::DrainHypervisorNodeRequest;
+use hypervisor::instance::Status;
+use hypervisor::proxmox::fake::{FakeProxmox, WithFakeProxmox};
+use mock_server::MockServer;
+use tonic::{Code, Request};
+
+mod common;
+
+const GIB: u64 = 1024 * 1024 * 1024;
+
+/// Seeds a hypervisor served by the given server, recording the instance
+/// whose VM id is 100.
+async fn seed_hypervisor(pool: &sqlx::PgPool, url: String) -> (Hypervisor, Instance) {
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(pool)
+        .await
+        .expect("could not create organization");
+    let hypervisor = Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
+        .for_zone(Zone::factory())
+        .organization_slug(organization.slug.clone())
+        .url(url)
+        .create(pool)
+        .await
+        .expect("could not create hypervisor");
+    let project = Project::factory()
+        .slug("test-project".to_owned())
+        .organization_slug(organization.slug.clone())
+        .create(pool)
+        .await
+        .expect("could not create project");
+    let instance = Instance::factory()
+        .hypervisor_id(hypervisor.id)
+        .project_slug(project.slug.clone())
+        .distant_id("100".into())
+        .zero_trust_network_id(None)
+        .create(pool)
+        .await
+        .expect("could not create instance");
+
+    (hypervisor, instance)
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_drain_hypervisor_node_procedure_works(pool: sqlx::PgPool) {
+    // Arrange the grpc server, and a hypervisor whose first node holds two VMs
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let proxmox = FakeProxmox::new()
+        .with_node("pve-node1", 16, 64 * GIB)
+        .with_node("pve-node2", 16, 64 * GIB)
+        .with_node("pve-node3", 16, 64 * GIB)
+        .with_vm("pve-node1", 100, "web", Status::Running)
+        .with_vm("pve-node1", 101, "db", Status::Stopped);
+    let server = MockServer::new().await.with_fake_proxmox(&proxmox);
+    let (hypervisor, instance) = seed_hypervisor(&pool, server.url()).await;
+    let token = seed_admin_token(&pool, "admin@francenuage.fr").await;
+
+    // Act the request to the test_the_drain_hypervisor_node_procedure_works
+    let request = Request::new(DrainHypervisorNodeRequest {
+        id: hypervisor.id.to_string(),
+        node: "pve-node1".to_owned(),
+    })
+    .with_user(&token);
+    let response = api.compute.hypervisors.drain_node(request).await;
+
+    // Assert every VM left the node, the recorded one being matched
+    let migrations = response
+        .expect("the drain should succeed")
+        .into_inner()
+        .migrations;
+    assert_eq!(migrations.len(), 2);
+    let recorded = migrations
+        .iter()
+        .find(|migration| migration.distant_id == "100")
+        .expect("the recorded instance should be migrated");
+    assert_eq!(recorded.instance_id, instance.id.to_string());
+    assert!(
+        migrations
+            .iter()
+            .all(|migration| migration.source_node == "pve-node1")
+    );
+    assert!(proxmox.vms().iter().all(|vm| vm.node != "pve-node1"));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_drain_hypervisor_node_procedure_respects_capacity(pool: sqlx::PgPool) {
+    // Arrange the grpc server, and a hypervisor whose second node fits a single VM
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let proxmox = FakeProxmox::new()
+        .with_node("pve-node1", 16, 64 * GIB)
+        .with_node("pve-node2", 16, 3 * GIB / 2)
+        .with_vm("pve-node1", 100, "web", Status::Running)
+        .with_vm("pve-node1", 101, "db", Status::Running);
+    let server = MockServer::new().await.with_fake_proxmox(&proxmox);
+    let (hypervisor, _) = seed_hypervisor(&pool, server.url()).await;
+    let token = seed_admin_token(&pool, "admin@francenuage.fr").await;
+
+    // Act the request to the test_the_drain_hypervisor_node_procedure_respects_capacity
+    let request = Request::new(DrainHypervisorNodeRequest {
+        id: hypervisor.id.to_string(),
+        node: "pve-node1".to_owned(),
+    })
+    .with_user(&token);
+    let response = api.compute.hypervisors.drain_node(request).await;
+
+    // Assert nothing moved
+    assert_eq!(response.unwrap_err().code(), Code::ResourceExhausted);
+    assert!(proxmox.vms().iter().all(|vm| vm.node == "pve-node1"));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_drain_hypervisor_node_procedure_is_restricted_to_admins(pool: sqlx::PgPool) {
+    // Arrange the grpc server, and a hypervisor whose first node holds a VM
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let proxmox = FakeProxmox::new()
+        .with_node("pve-node1", 16, 64 * GIB)
+        .with_node("pve-node2", 16, 64 * GIB)
+        .with_vm("pve-node1", 100, "web", Status::Running);
+    let server = MockServer::new().await.with_fake_proxmox(&proxmox);
+    let (hypervisor, _) = seed_hypervisor(&pool, server.url()).await;
+
+    // Act the request to the test_the_drain_hypervisor_node_procedure_is_restricted_to_admins
+    let request = Request::new(DrainHypervisorNodeRequest {
+        id: hypervisor.id.to_string(),
+        node: "pve-node1".to_owned(),
+    })
+    .on_behalf_of(&api.service_account);
+    let response = api.compute.hypervisors.drain_node(request).await;
+
+    // Assert the result
+    assert_eq!(response.unwrap_err().code(), Code::PermissionDenied);
+    assert_eq!(
+        proxmox.vm(100).expect("the vm should exist").node,
+        "pve-node1"
+    );
+}
@@ -0,0 +1,138 @@
+//! Tests for the encryption of legacy hypervisor tokens run by
+//! `server hypervisors encrypt-tokens` during the migrations.
+//!
+//! Hypervisors registered before tokens were encrypted at rest keep them in
+//! plaintext until encrypted after the migrations, the plaintext column being
+//! dropped by a later release. Drives `encrypt_plaintext_tokens` against a
+//! real database.
+
+use fabrique::{Factory, Query};
+use frn_core::compute::{Hypervisor, Zone, encrypt_plaintext_tokens};
+use frn_core::resourcemanager::Organization;
+use frn_crypto::Kek;
+use hypervisor::{KubeVirtConfig, Resolver, SnippetsConfig, StoragesConfig};
+use std::sync::Arc;
+
+/// Inserts a hypervisor the way it was stored before tokens were encrypted.
+async fn insert_legacy_hypervisor(pool: &sqlx::PgPool) -> uuid::Uuid {
+    let zone = Zone::factory()
+        .create(pool)
+        .await
+        .expect("could not create zone");
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(pool)
+        .await
+        .expect("could not create organization");
+
+    sqlx::query_scalar(
+        r#"INSERT INTO hypervisors (url, authorization_token, storage_name, organization_slug, zone_id)
+           VALUES ('https://pve.test', 'PVEAPIToken=root@pam!api=secret', 'local-lvm', $1, $2)
+           RETURNING id"#,
+    )
+    .bind(&organization.slug)
+    .bind(zone.id)
+    .fetch_one(pool)
+    .await
+    .expect("could not insert legacy hypervisor")
+}
+
+fn kek() -> Kek {
+    Kek::from_bytes([7u8; 32])
+}
+
+/// Resolves the hypervisors with the tokens encrypted with `kek`.
+fn resolver(kek: Kek) -> Resolver {
+    Resolver::new(
+        Arc::new(kek),
+        SnippetsConfig::default(),
+        StoragesConfig::default(),
+        KubeVirtConfig::default(),
+    )
+}
+
+/// A plaintext token is encrypted, bound to its hypervisor, and cleared.
+#[sqlx::test(migrations = "../migrations")]
+async fn test_encrypts_a_plaintext_token(
+    pool: sqlx::PgPool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let id = insert_legacy_hypervisor(&pool).await;
+
+    let encrypted = encrypt_plaintext_tokens(&pool, &kek()).await?;
+
+    assert_eq!(encrypted, 1);
+    let plaintext: Option<String> =
+        sqlx::query_scalar("SELECT authorization_token FROM hypervisors WHERE id = $1")
+            .bind(id)
+            .fetch_one(&pool)
+            .await?;
+    assert_eq!(plaintext, None, "the plaintext token must be cleared");
+
+    let hypervisor = Hypervisor::find(&pool, id).await?;
+    assert!(
+        !hypervisor
+            .token_ciphertext
+            .windows(6)
+            .any(|window| window == b"secret"),
+        "the stored token must not hold the plaintext"
+    );
+    assert!(hypervisor.resolve(&resolver(kek())).is_ok());
+    assert!(
+        hypervisor
+            .resolve(&resolver(Kek::from_bytes([0u8; 32])))
+            .is_err(),
+        "the token must only decrypt with the configured key"
+    );
+    Ok(())
+}
+
+/// Re-running the encryption leaves encrypted tokens untouched.
+#[sqlx::test(migrations = "../migrations")]
+async fn test_is_a_no_op_once_encrypted(
+    pool: sqlx::PgPool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let id = insert_legacy_hypervisor(&pool).await;
+    encrypt_plaintext_tokens(&pool, &kek()).await?;
+    let before = Hypervisor::find(&pool, id).await?;
+
+    let encrypted = encrypt_plaintext_tokens(&pool, &kek()).await?;
+
+    assert_eq!(encrypted, 0);
+    let after = Hypervisor::find(&pool, id).await?;
+    assert_eq!(after.token_ciphertext, before.token_ciphertext);
+    Ok(())
+}
+
+/// The encryption is a no-op once the plaintext column is dropped.
+#[sqlx::test(migrations = "../migrations")]
+async fn test_is_a_no_op_once_the_plaintext_column_is_dropped(
+    pool: sqlx::PgPool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    sqlx::query("ALTER TABLE hypervisors DROP COLUMN authorization_token")
+        .execute(&pool)
+        .await?;
+
+    let encrypted = encrypt_plaintext_tokens(&pool, &kek()).await?;
+
+    assert_eq!(encrypted, 0);
+    Ok(())
+}
+
+/// A token copied to another hypervisor row does not decrypt.
+#[sqlx::test(migrations = "../migrations")]
+async fn test_binds_the_token_to_its_hypervisor(
+    pool: sqlx::PgPool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let id = insert_legacy_hypervisor(&pool).await;
+    encrypt_plaintext_tokens(&pool, &kek()).await?;
+    let hypervisor = Hypervisor::find(&pool, id).await?;
+
+    let copy = Hypervisor {
+        id: uuid::Uuid::new_v4(),
+        ..hypervisor
+    };
+
+    assert!(copy.resolve(&resolver(kek())).is_err());
+    Ok(())
+}
@@ -0,0 +1,399 @@
+//! Tests for the quotas enforced on the creation of instances, volumes and
+//! managed service instances, and on the growth of instances and volumes.
+
+use crate::common::{
+    Api, OnBehalfOf, attach_test_deploy_label, seed_kubernetes_cluster, seed_managed_service,
+    seed_managed_service_plan, seed_managed_service_version,
+};
+use fabrique::{Factory, Query};
+use frn_core::{
+    compute::{Hypervisor, Instance, Volume, Zone},
+    resourcemanager::{Organization, Project, Quota},
+};
+use frn_rpc::v1::compute::{
+    CloneInstanceRequest, CreateInstanceRequest, CreateVolumeRequest, ResizeVolumeRequest,
+    RestoreBackupRequest, UpdateInstanceRequest,
+};
+use tonic::{Code, Request};
+
+mod common;
+
+const GIB: u64 = 1024 * 1024 * 1024;
+
+/// Seeds an organization and its project, with a 2 cores instance running on
+/// a hypervisor of a new zone.
+async fn seed_instance(pool: &sqlx::PgPool, url: String) -> (Zone, Instance) {
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(pool)
+        .await
+        .expect("could not create organization");
+    let zone = Zone::factory()
+        .create(pool)
+        .await
+        .expect("could not create zone");
+    let hypervisor = Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
+        .zone_id(zone.id)
+        .organization_slug(organization.slug.clone())
+        .url(url)
+        .create(pool)
+        .await
+        .expect("could not create hypervisor");
+    let project = Project::factory()
+        .slug("test-project".to_owned())
+        .organization_slug(organization.slug.clone())
+        .create(pool)
+        .await
+        .expect("could not create project");
+    let instance = Instance::factory()
+        .distant_id("100".into())
+        .hypervisor_id(hypervisor.id)
+        .project_slug(project.slug.clone())
+        .zero_trust_network_id(None)
+        .max_cpu_cores(2)
+        .max_memory_bytes(2 * GIB as i64)
+        .max_disk_bytes(20 * GIB as i64)
+        .create(pool)
+        .await
+        .expect("could not create instance");
+
+    (zone, instance)
+}
+
+fn create_instance_request(zone: &Zone) -> CreateInstanceRequest {
+    CreateInstanceRequest {
+        image: "debian-12-genericcloud-amd64-20241201-1948.qcow2".to_owned(),
+        cpu_cores: 2,
+        disk_bytes: 20 * GIB,
+        memory_bytes: 2 * GIB,
+        name: "over-quota".to_owned(),
+        snippet: String::new(),
+        project_slug: "test-project".to_owned(),
+        zone_id: Some(zone.id.to_string()),
+        zero_trust_network_id: None,
+    }
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_instances_cannot_exceed_the_instance_quota_of_their_project(pool: sqlx::PgPool) {
+    // Arrange a project already holding as many instances as its quota allows
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let (zone, _) = seed_instance(&pool, api.mock_server.url()).await;
+    Quota::factory()
+        .organization_slug("test-org".to_owned())
+        .project_slug(Some("test-project".to_owned()))
+        .max_cpu_cores(None)
+        .max_memory_bytes(None)
+        .max_disk_bytes(None)
+        .max_instances(Some(1))
+        .max_managed_service_instances(None)
+        .create(&pool)
+        .await
+        .expect("could not create quota");
+
+    // Act the request to the create instance procedure
+    let request = Request::new(create_instance_request(&zone)).on_behalf_of(&api.service_account);
+    let response = api.compute.instances.create(request).await;
+
+    // Assert the instance was refused before reaching the hypervisor
+    let status = response.unwrap_err();
+    assert_eq!(status.code(), Code::ResourceExhausted);
+    assert!(status.message().contains("project test-project"));
+    assert_eq!(Instance::all(&pool).await.unwrap().len(), 1);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_instances_cannot_exceed_the_cpu_quota_of_their_organization(pool: sqlx::PgPool) {
+    // Arrange an organization with a single core left
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let (zone, _) = seed_instance(&pool, api.mock_server.url()).await;
+    Quota::factory()
+        .organization_slug("test-org".to_owned())
+        .project_slug(None)
+        .max_cpu_cores(Some(3))
+        .max_memory_bytes(None)
+        .max_disk_bytes(None)
+        .max_instances(None)
+        .max_managed_service_instances(None)
+        .create(&pool)
+        .await
+        .expect("could not create quota");
+
+    // Act the request to the create instance procedure
+    let request = Request::new(create_instance_request(&zone)).on_behalf_of(&api.service_account);
+    let response = api.compute.instances.create(request).await;
+
+    // Assert the instance was refused
+    let status = response.unwrap_err();
+    assert_eq!(status.code(), Code::ResourceExhausted);
+    assert!(status.message().contains("organization test-org"));
+    assert!(status.message().contains("cpu cores"));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_clones_count_against_the_instance_quota(pool: sqlx::PgPool) {
+    // Arrange a project with room for a single more instance
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let (_, instance) = seed_instance(&pool, api.mock_server.url()).await;
+    Quota::factory()
+        .organization_slug("test-org".to_owned())
+        .project_slug(Some("test-project".to_owned()))
+        .max_cpu_cores(None)
+        .max_memory_bytes(None)
+        .max_disk_bytes(None)
+        .max_instances(Some(2))
+        .max_managed_service_instances(None)
+        .create(&pool)
+        .await
+        .expect("could not create quota");
+
+    // Act two requests to the clone instance procedure
+    let instances = &mut api.compute.instances;
+    let mut responses = vec![];
+    for _ in 0..2 {
+        let request = Request::new(CloneInstanceRequest {
+            id: instance.id.to_string(),
+            name: None,
+        })
+        .on_behalf_of(&api.service_account);
+        responses.push(instances.clone(request).await);
+    }
+
+    // Assert only the first clone fit in the quota
+    assert!(responses[0].is_ok());
+    assert_eq!(
+        responses[1].as_ref().unwrap_err().code(),
+        Code::ResourceExhausted
+    );
+    assert_eq!(Instance::all(&pool).await.unwrap().len(), 2);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_restored_instances_count_against_the_instance_quota(pool: sqlx::PgPool) {
+    // Arrange a project already holding as many instances as its quota allows
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let (_, instance) = seed_instance(&pool, api.mock_server.url()).await;
+    Quota::factory()
+        .organization_slug("test-org".to_owned())
+        .project_slug(Some("test-project".to_owned()))
+        .max_cpu_cores(None)
+        .max_memory_bytes(None)
+        .max_disk_bytes(None)
+        .max_instances(Some(1))
+        .max_managed_service_instances(None)
+        .create(&pool)
+        .await
+        .expect("could not create quota");
+
+    // Act the request to the restore backup procedure
+    let request = Request::new(RestoreBackupRequest {
+        instance_id: instance.id.to_string(),
+        backup_id: "pbs:backup/vm/100/2025-03-08T18:00:00Z".to_owned(),
+        name: Some("restored".to_owned()),
+    })
+    .on_behalf_of(&api.service_account);
+    let response = api.compute.backups.restore(request).await;
+
+    // Assert the restore was refused before reaching the hypervisor
+    let status = response.unwrap_err();
+    assert_eq!(status.code(), Code::ResourceExhausted);
+    assert_eq!(Instance::all(&pool).await.unwrap().len(), 1);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_volumes_count_against_the_disk_quota(pool: sqlx::PgPool) {
+    // Arrange a project whose 20 GiB instance and 4 GiB volume leave room for
+    // a single more GiB of disk
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let (_, instance) = seed_instance(&pool, api.mock_server.url()).await;
+    let volume = Volume::factory()
+        .instance_id(instance.id)
+        .project_slug("test-project".to_owned())
+        .distant_id("local-lvm:vm-100-disk-1".to_owned())
+        .device(Some("scsi1".to_owned()))
+        .size_bytes((4 * GIB) as i64)
+        .create(&pool)
+        .await
+        .expect("could not create volume");
+    Quota::factory()
+        .organization_slug("test-org".to_owned())
+        .project_slug(Some("test-project".to_owned()))
+        .max_cpu_cores(None)
+        .max_memory_bytes(None)
+        .max_disk_bytes(Some((25 * GIB) as i64))
+        .max_instances(None)
+        .max_managed_service_instances(None)
+        .create(&pool)
+        .await
+        .expect("could not create quota");
+
+    // Act the requests creating a 2 GiB volume, and growing the volume by 2
+    // GiB
+    let create = Request::new(CreateVolumeRequest {
+        instance_id: instance.id.to_string(),
+        name: "data".to_owned(),
+        size_bytes: 2 * GIB,
+    })
+    .on_behalf_of(&api.service_account);
+    let created = api.compute.volumes.create(create).await;
+    let resize = Request::new(ResizeVolumeRequest {
+        id: volume.id.to_string(),
+        size_bytes: 6 * GIB,
+    })
+    .on_behalf_of(&api.service_account);
+    let resized = api.compute.volumes.resize(resize).await;
+
+    // Assert both were refused before reaching the hypervisor
+    let status = created.unwrap_err();
+    assert_eq!(status.code(), Code::ResourceExhausted);
+    assert!(status.message().contains("disk bytes"));
+    assert_eq!(resized.unwrap_err().code(), Code::ResourceExhausted);
+    let volumes = Volume::all(&pool).await.unwrap();
+    assert_eq!(volumes.len(), 1);
+    assert_eq!(volumes[0].size_bytes, (4 * GIB) as i64);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_resizes_cannot_exceed_the_cpu_quota_of_their_organization(pool: sqlx::PgPool) {
+    // Arrange an organization with a single core left
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let (_, instance) = seed_instance(&pool, api.mock_server.url()).await;
+    Quota::factory()
+        .organization_slug("test-org".to_owned())
+        .project_slug(None)
+        .max_cpu_cores(Some(3))
+        .max_memory_bytes(None)
+        .max_disk_bytes(None)
+        .max_instances(None)
+        .max_managed_service_instances(None)
+        .create(&pool)
+        .await
+        .expect("could not create quota");
+
+    // Act the request growing the instance by 2 cores
+    let request = Request::new(UpdateInstanceRequest {
+        id: instance.id.to_string(),
+        name: None,
+        project_slug: None,
+        cpu_cores: Some(4),
+        memory_bytes: None,
+    })
+    .on_behalf_of(&api.service_account);
+    let response = api.compute.instances.update(request).await;
+
+    // Assert the resize was refused and the instance kept its shape
+    let status = response.unwrap_err();
+    assert_eq!(status.code(), Code::ResourceExhausted);
+    assert!(status.message().contains("cpu cores"));
+    let instance = Instance::find(&pool, instance.id).await.unwrap();
+    assert_eq!(instance.max_cpu_cores, 2);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_moves_cannot_exceed_the_instance_quota_of_their_new_project(pool: sqlx::PgPool) {
+    // Arrange a second project of the organization not allowed any instance
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let (_, instance) = seed_instance(&pool, api.mock_server.url()).await;
+    Project::factory()
+        .slug("full-project".to_owned())
+        .organization_slug("test-org".to_owned())
+        .create(&pool)
+        .await
+        .expect("could not create project");
+    Quota::factory()
+        .organization_slug("test-org".to_owned())
+        .project_slug(Some("full-project".to_owned()))
+        .max_cpu_cores(None)
+        .max_memory_bytes(None)
+        .max_disk_bytes(None)
+        .max_instances(Some(0))
+        .max_managed_service_instances(None)
+        .create(&pool)
+        .await
+        .expect("could not create quota");
+
+    // Act the request moving the instance to the second project
+    let request = Request::new(UpdateInstanceRequest {
+        id: instance.id.to_string(),
+        name: None,
+        project_slug: Some("full-project".to_owned()),
+        cpu_cores: None,
+        memory_bytes: None,
+    })
+    .on_behalf_of(&api.service_account);
+    let response = api.compute.instances.update(request).await;
+
+    // Assert the move was refused and the instance stayed in its project
+    let status = response.unwrap_err();
+    assert_eq!(status.code(), Code::ResourceExhausted);
+    assert!(status.message().contains("project full-project"));
+    let instance = Instance::find(&pool, instance.id).await.unwrap();
+    assert_eq!(instance.project_slug, "test-project");
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_managed_service_instances_cannot_exceed_their_quota(pool: sqlx::PgPool) {
+    // Arrange an organization not allowed any managed service instance
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let organization = Organization::factory()
+        .slug("acme".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await
+        .expect("could not create organization");
+    let cluster = seed_kubernetes_cluster(&pool, "prod-eu").await;
+    attach_test_deploy_label(&pool, cluster.id).await;
+    let project = Project::factory()
+        .slug("test-project".to_owned())
+        .organization_slug(organization.slug.clone())
+        .create(&pool)
+        .await
+        .expect("could not create project");
+    let service_id = seed_managed_service(&pool, "vaultwarden", "Vaultwarden", "security").await;
+    let version_id = seed_managed_service_version(
+        &pool,
+        service_id,
+        "1.0.0",
+        Some("1.32.0"),
+        "oci://registry.example.com/charts/vaultwarden",
+    )
+    .await;
+    let plan_id =
+        seed_managed_service_plan(&pool, service_id, "vaultwarden-standard", "Standard").await;
+    Quota::factory()
+        .organization_slug(organization.slug.clone())
+        .project_slug(None)
+        .max_cpu_cores(None)
+        .max_memory_bytes(None)
+        .max_disk_bytes(None)
+        .max_instances(None)
+        .max_managed_service_instances(Some(0))
+        .create(&pool)
+        .await
+        .expect("could not create quota");
+
+    // Act the request to the create managed service instance procedure
+    let response = api
+        .managed
+        .services
+        .create_instance(
+            Request::new(frn_rpc::v1::managed::CreateInstanceRequest {
+                project_slug: project.slug.clone(),
+                organization_slug: organization.slug.clone(),
+                service_slug: "vaultwarden".to_owned(),
+                version_id: version_id.to_string(),
+                plan_id: plan_id.to_string(),
+                user_values: None,
+                secret_values: None,
+            })
+            .on_behalf_of(&api.service_account),
+        )
+        .await;
+
+    // Assert the instance was refused
+    let status = response.unwrap_err();
+    assert_eq!(status.code(), Code::ResourceExhausted);
+    assert!(status.message().contains("managed service instances"));
+}
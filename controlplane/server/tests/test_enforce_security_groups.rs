@@ -0,0 +1,123 @@
+//! Tests for the security group enforcement run on each synchronizer pass.
+//!
+//! Drives `SecurityGroups::enforce` against a mocked Proxmox API, whose VM
+//! firewall holds a single rule managed on behalf of a known group.
+
+use fabrique::Factory;
+use frn_core::App;
+use frn_core::compute::{
+    Hypervisor, Instance, InstanceSecurityGroup, SecurityGroup, SecurityGroupRule, Zone,
+};
+use frn_core::resourcemanager::{Organization, Project};
+use hypervisor::instance::FirewallDirection;
+use hypervisor::mock::{
+    WithClusterResourceList, WithVMConfigMock, WithVMFirewallOptionsReadMock,
+    WithVMFirewallOptionsUpdateMock, WithVMFirewallRuleCreateMock, WithVMFirewallRuleDeleteMock,
+    WithVMFirewallRuleListMock,
+};
+use mock_server::MockServer;
+use uuid::Uuid;
+
+/// The security group the rule of the mocked VM firewall is managed for.
+const MANAGED_GROUP_ID: &str = "0b5a3c5e-9a7e-4a0e-8a55-5d4b0c2b1f6e";
+
+async fn seed_instance(pool: &sqlx::PgPool, url: String, group_id: Uuid) -> Instance {
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(pool)
+        .await
+        .expect("could not create organization");
+    let hypervisor = Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
+        .for_zone(Zone::factory())
+        .organization_slug(organization.slug.clone())
+        .url(url)
+        .create(pool)
+        .await
+        .expect("could not create hypervisor");
+    let project = Project::factory()
+        .slug("test-project".to_owned())
+        .organization_slug(organization.slug.clone())
+        .create(pool)
+        .await
+        .expect("could not create project");
+    let instance = Instance::factory()
+        .hypervisor_id(hypervisor.id)
+        .project_slug(project.slug.clone())
+        .distant_id("100".into())
+        .zero_trust_network_id(None)
+        .create(pool)
+        .await
+        .expect("could not create instance");
+    let security_group = SecurityGroup::factory()
+        .id(group_id)
+        .project_slug(project.slug.clone())
+        .name("ssh".to_owned())
+        .create(pool)
+        .await
+        .expect("could not create security group");
+    SecurityGroupRule::factory()
+        .security_group_id(security_group.id)
+        .direction(FirewallDirection::Ingress)
+        .protocol(Some("tcp".to_owned()))
+        .port_range(Some("22".to_owned()))
+        .cidr(Some("10.0.0.0/8".to_owned()))
+        .create(pool)
+        .await
+        .expect("could not create rule");
+    InstanceSecurityGroup::factory()
+        .instance_id(instance.id)
+        .security_group_id(security_group.id)
+        .create(pool)
+        .await
+        .expect("could not attach security group");
+
+    instance
+}
+
+async fn mock_server() -> MockServer {
+    MockServer::new()
+        .await
+        .with_cluster_resource_list()
+        .with_vm_config()
+        .with_vm_firewall_options_read()
+        .with_vm_firewall_options_update()
+        .with_vm_firewall_rule_create()
+        .with_vm_firewall_rule_delete()
+        .with_vm_firewall_rule_list()
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_enforce_leaves_a_firewall_in_sync_untouched(
+    pool: sqlx::PgPool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Arrange an instance whose firewall holds the rule of its group
+    let server = mock_server().await;
+    let app = App::test(pool.clone()).await?;
+    let instance = seed_instance(&pool, server.url(), Uuid::parse_str(MANAGED_GROUP_ID)?).await;
+
+    // Act the enforcement
+    let drifted = app.security_groups.enforce(&instance).await?;
+
+    // Assert the firewall was left as is
+    assert!(!drifted);
+    Ok(())
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_enforce_reapplies_a_drifted_firewall(
+    pool: sqlx::PgPool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Arrange an instance whose firewall holds the rule of another group
+    let server = mock_server().await;
+    let app = App::test(pool.clone()).await?;
+    let instance = seed_instance(&pool, server.url(), Uuid::new_v4()).await;
+
+    // Act the enforcement
+    let drifted = app.security_groups.enforce(&instance).await?;
+
+    // Assert the firewall was re-applied
+    assert!(drifted);
+    Ok(())
+}
@@ -0,0 +1,118 @@
+use crate::common::{Api, OnBehalfOf};
+use fabrique::Factory;
+use frn_core::{
+    compute::{BackupPolicy, Hypervisor, Instance, Zone},
+    resourcemanager::{Organization, Project},
+};
+use frn_rpc::v1::compute::GetBackupPolicyRequest;
+use tonic::{Code, Request};
+
+mod common;
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_get_backup_policy_procedure_works(pool: sqlx::PgPool) {
+    // Arrange the grpc server and a client
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let mock_url = api.mock_server.url();
+
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await
+        .expect("could not create organization");
+    let hypervisor = Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
+        .for_zone(Zone::factory())
+        .organization_slug(organization.slug.clone())
+        .url(mock_url)
+        .create(&pool)
+        .await
+        .expect("could not create hypervisor");
+    let project = Project::factory()
+        .slug("test-project".to_owned())
+        .organization_slug(organization.slug.clone())
+        .create(&pool)
+        .await
+        .expect("could not create project");
+    let instance = Instance::factory()
+        .hypervisor_id(hypervisor.id)
+        .project_slug(project.slug.clone())
+        .distant_id("100".into())
+        .zero_trust_network_id(None)
+        .create(&pool)
+        .await
+        .expect("could not create instance");
+    let policy = BackupPolicy::factory()
+        .instance_id(instance.id)
+        .schedule("daily".to_owned())
+        .keep_last(3)
+        .keep_daily(7)
+        .keep_weekly(0)
+        .keep_monthly(0)
+        .create(&pool)
+        .await
+        .expect("could not create backup policy");
+
+    // Act the request to the test_the_get_backup_policy_procedure_works
+    let request = Request::new(GetBackupPolicyRequest {
+        instance_id: instance.id.to_string(),
+    })
+    .on_behalf_of(&api.service_account);
+    let response = api.compute.backups.get_policy(request).await;
+
+    // Assert the result
+    assert!(response.is_ok());
+    let response = response.unwrap().into_inner();
+    assert_eq!(response.instance_id, policy.instance_id.to_string());
+    assert_eq!(response.schedule, "daily");
+    assert_eq!(response.keep_last, 3);
+    assert_eq!(response.keep_daily, 7);
+    assert_eq!(response.keep_weekly, 0);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_get_backup_policy_procedure_fails_without_policy(pool: sqlx::PgPool) {
+    // Arrange the grpc server and a client
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let mock_url = api.mock_server.url();
+
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await
+        .expect("could not create organization");
+    let hypervisor = Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
+        .for_zone(Zone::factory())
+        .organization_slug(organization.slug.clone())
+        .url(mock_url)
+        .create(&pool)
+        .await
+        .expect("could not create hypervisor");
+    let project = Project::factory()
+        .slug("test-project".to_owned())
+        .organization_slug(organization.slug.clone())
+        .create(&pool)
+        .await
+        .expect("could not create project");
+    let instance = Instance::factory()
+        .hypervisor_id(hypervisor.id)
+        .project_slug(project.slug.clone())
+        .distant_id("100".into())
+        .zero_trust_network_id(None)
+        .create(&pool)
+        .await
+        .expect("could not create instance");
+
+    // Act the request to the test_the_get_backup_policy_procedure_fails_without_policy
+    let request = Request::new(GetBackupPolicyRequest {
+        instance_id: instance.id.to_string(),
+    })
+    .on_behalf_of(&api.service_account);
+    let response = api.compute.backups.get_policy(request).await;
+
+    // Assert the result
+    assert_eq!(response.unwrap_err().code(), Code::NotFound);
+}
@@ -0,0 +1,100 @@
+use crate::common::{Api, OnBehalfOf};
+use fabrique::Factory;
+use frn_core::{
+    compute::{Hypervisor, Instance, Zone},
+    resourcemanager::{Organization, Project},
+};
+use frn_rpc::v1::compute::GetInstanceMetricsRequest;
+use prost_types::Timestamp;
+use tonic::{Code, Request};
+use uuid::Uuid;
+
+mod common;
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_get_instance_metrics_procedure_works(pool: sqlx::PgPool) {
+    // Arrange the grpc server and a client
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let mock_url = api.mock_server.url();
+
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await
+        .expect("could not create organization");
+    let hypervisor = Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
+        .for_zone(Zone::factory())
+        .organization_slug(organization.slug.clone())
+        .url(mock_url)
+        .create(&pool)
+        .await
+        .expect("could not create hypervisor");
+    let project = Project::factory()
+        .slug("test-project".to_owned())
+        .organization_slug(organization.slug.clone())
+        .create(&pool)
+        .await
+        .expect("could not create project");
+    let instance = Instance::factory()
+        .hypervisor_id(hypervisor.id)
+        .project_slug(project.slug.clone())
+        .distant_id("100".into())
+        .zero_trust_network_id(None)
+        .create(&pool)
+        .await
+        .expect("could not create instance");
+
+    // Act the request to the test_the_get_instance_metrics_procedure_works
+    let request = Request::new(GetInstanceMetricsRequest {
+        instance_id: instance.id.to_string(),
+        start: Some(Timestamp {
+            seconds: 1741456000,
+            nanos: 0,
+        }),
+        end: Some(Timestamp {
+            seconds: 1741457000,
+            nanos: 0,
+        }),
+        resolution_seconds: 0,
+    })
+    .on_behalf_of(&api.service_account);
+    let response = api.compute.instances.get_metrics(request).await;
+
+    // Assert the result, the window being older than a year old is served at
+    // the weekly resolution, and the point without usage is skipped
+    assert!(response.is_ok());
+    let metrics = response.unwrap().into_inner();
+    assert_eq!(metrics.resolution_seconds, 604800);
+    assert_eq!(metrics.samples.len(), 1);
+    assert_eq!(metrics.samples[0].cpu_usage_percent, 50.0);
+    assert_eq!(metrics.samples[0].memory_usage_bytes, 1610612736);
+    assert_eq!(metrics.samples[0].max_memory_bytes, 4294967296);
+    assert_eq!(metrics.samples[0].network_in_bytes_per_second, 2048.0);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_get_instance_metrics_procedure_rejects_inverted_windows(pool: sqlx::PgPool) {
+    // Arrange the grpc server and a client
+    let mut api = Api::start(&pool).await.expect("could not start api");
+
+    // Act the request to the test_the_get_instance_metrics_procedure_rejects_inverted_windows
+    let request = Request::new(GetInstanceMetricsRequest {
+        instance_id: Uuid::new_v4().to_string(),
+        start: Some(Timestamp {
+            seconds: 1741457000,
+            nanos: 0,
+        }),
+        end: Some(Timestamp {
+            seconds: 1741456000,
+            nanos: 0,
+        }),
+        resolution_seconds: 60,
+    })
+    .on_behalf_of(&api.service_account);
+    let response = api.compute.instances.get_metrics(request).await;
+
+    // Assert the result
+    assert_eq!(response.unwrap_err().code(), Code::InvalidArgument);
+}
@@ -0,0 +1,106 @@
+use crate::common::{Api, OnBehalfOf};
+use fabrique::Factory;
+use frn_core::{
+    compute::{Hypervisor, Instance, Zone},
+    resourcemanager::{Organization, Project, Quota},
+};
+use frn_rpc::v1::resourcemanager::{GetQuotaUsageRequest, ResourceUsage};
+use tonic::Request;
+
+mod common;
+
+const GIB: i64 = 1024 * 1024 * 1024;
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_get_quota_usage_procedure_works(pool: sqlx::PgPool) {
+    // Arrange an organization with two projects holding an instance each,
+    // and a quota on one of them
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await
+        .expect("could not create organization");
+    let hypervisor = Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
+        .for_zone(Zone::factory())
+        .organization_slug(organization.slug.clone())
+        .url(api.mock_server.url())
+        .create(&pool)
+        .await
+        .expect("could not create hypervisor");
+    for (slug, cores) in [("web-project", 2), ("data-project", 4)] {
+        let project = Project::factory()
+            .slug(slug.to_owned())
+            .organization_slug(organization.slug.clone())
+            .create(&pool)
+            .await
+            .expect("could not create project");
+        Instance::factory()
+            .hypervisor_id(hypervisor.id)
+            .project_slug(project.slug.clone())
+            .zero_trust_network_id(None)
+            .max_cpu_cores(cores)
+            .max_memory_bytes(cores as i64 * GIB)
+            .max_disk_bytes(10 * GIB)
+            .create(&pool)
+            .await
+            .expect("could not create instance");
+    }
+    Quota::factory()
+        .organization_slug(organization.slug.clone())
+        .project_slug(Some("web-project".to_owned()))
+        .max_cpu_cores(Some(8))
+        .max_memory_bytes(None)
+        .max_disk_bytes(None)
+        .max_instances(Some(3))
+        .max_managed_service_instances(None)
+        .create(&pool)
+        .await
+        .expect("could not create quota");
+
+    // Act the requests for the usage of the organization and of the project
+    let quotas = &mut api.resourcemanager.quotas;
+    let organization_usage = quotas
+        .get_usage(
+            Request::new(GetQuotaUsageRequest {
+                organization_slug: "test-org".to_owned(),
+                project_slug: None,
+            })
+            .on_behalf_of(&api.service_account),
+        )
+        .await
+        .expect("could not get organization usage")
+        .into_inner()
+        .usage
+        .expect("the response should hold the usage");
+    let project_usage = quotas
+        .get_usage(
+            Request::new(GetQuotaUsageRequest {
+                organization_slug: "test-org".to_owned(),
+                project_slug: Some("web-project".to_owned()),
+            })
+            .on_behalf_of(&api.service_account),
+        )
+        .await
+        .expect("could not get project usage")
+        .into_inner()
+        .usage
+        .expect("the response should hold the usage");
+
+    // Assert the organization is consumed by both projects, without limits
+    let usage = |used, limit| Some(ResourceUsage { used, limit });
+    assert_eq!(organization_usage.project_slug, None);
+    assert_eq!(organization_usage.cpu_cores, usage(6, None));
+    assert_eq!(organization_usage.memory_bytes, usage(6 * GIB, None));
+    assert_eq!(organization_usage.disk_bytes, usage(20 * GIB, None));
+    assert_eq!(organization_usage.instances, usage(2, None));
+    assert_eq!(organization_usage.managed_service_instances, usage(0, None));
+
+    // Assert the project is consumed by its instance, against its quota
+    assert_eq!(project_usage.project_slug.as_deref(), Some("web-project"));
+    assert_eq!(project_usage.cpu_cores, usage(2, Some(8)));
+    assert_eq!(project_usage.instances, usage(1, Some(3)));
+    assert_eq!(project_usage.memory_bytes, usage(2 * GIB, None));
+}
@@ -1,4 +1,4 @@
-use crate::common::{Api, IntoWorker};
+use crate::common::{Api, IntoWorker, OnBehalfOf};
 use frn_rpc::v1::workflow::{
     ExecutionStatus, GetStatusRequest, Initiator, ScheduleRequest, initiator,
 };
@@ -85,3 +85,39 @@ async fn test_get_status_rejects_unauthenticated(
 
     Ok(())
 }
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_get_status_rejects_principals_which_did_not_initiate_the_execution(
+    pool: sqlx::PgPool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut api = Api::start(&pool).await.expect("could not start api");
+
+    let definition = json!({"WriteRelationships": {"relationships": [], "done": false}});
+
+    let schedule_request = Request::new(ScheduleRequest {
+        definition: definition.to_string(),
+        max_retry: 3,
+        initiated_by: Some(Initiator {
+            kind: Some(initiator::Kind::System(true)),
+        }),
+        schedule_at: None,
+    })
+    .into_worker();
+
+    let schedule_resp = api
+        .workflow
+        .engine
+        .schedule(schedule_request)
+        .await?
+        .into_inner();
+    let execution_id = schedule_resp.execution.unwrap().execution_id;
+
+    let request =
+        Request::new(GetStatusRequest { execution_id }).on_behalf_of(&api.service_account);
+
+    let response = api.workflow.engine.get_status(request).await;
+
+    assert_eq!(response.unwrap_err().code(), tonic::Code::PermissionDenied);
+
+    Ok(())
+}
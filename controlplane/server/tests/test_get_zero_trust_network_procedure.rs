@@ -0,0 +1,60 @@
+use crate::common::{Api, OnBehalfOf};
+use fabrique::Factory;
+use frn_core::resourcemanager::Organization;
+use infrastructure::v1::GetZeroTrustNetworkRequest;
+use infrastructure::{ZeroTrustNetwork, ZeroTrustNetworkType};
+use tonic::{Code, Request};
+use uuid::Uuid;
+
+mod common;
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_get_zero_trust_network_procedure_works(pool: sqlx::PgPool) {
+    // Arrange the grpc server and a client
+    let mut api = Api::start(&pool).await.expect("could not start api");
+
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await
+        .expect("could not create organization");
+    let zero_trust_network = ZeroTrustNetwork::factory()
+        .organization_slug(organization.slug)
+        .for_zero_trust_network_type(ZeroTrustNetworkType::factory())
+        .create(&pool)
+        .await
+        .expect("could not create zero trust network");
+
+    // Act the request to the test_the_get_zero_trust_network_procedure_works
+    let request = Request::new(GetZeroTrustNetworkRequest {
+        id: zero_trust_network.id.to_string(),
+    })
+    .on_behalf_of(&api.service_account);
+    let response = api.infrastructure.zero_trust_networks.get(request).await;
+
+    // Assert the result
+    assert_eq!(
+        response
+            .expect("could not get zero trust network")
+            .into_inner()
+            .zero_trust_network,
+        Some(zero_trust_network.into())
+    );
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_get_zero_trust_network_procedure_fails_for_unknown_networks(pool: sqlx::PgPool) {
+    // Arrange the grpc server and a client
+    let mut api = Api::start(&pool).await.expect("could not start api");
+
+    // Act the request to the test_the_get_zero_trust_network_procedure_fails_for_unknown_networks
+    let request = Request::new(GetZeroTrustNetworkRequest {
+        id: Uuid::new_v4().to_string(),
+    })
+    .on_behalf_of(&api.service_account);
+    let response = api.infrastructure.zero_trust_networks.get(request).await;
+
+    // Assert the result
+    assert_eq!(response.unwrap_err().code(), Code::NotFound);
+}
@@ -0,0 +1,98 @@
+use crate::common::{Api, OnBehalfOf};
+use fabrique::Factory;
+use frn_core::{
+    compute::{Hypervisor, Zone},
+    resourcemanager::Organization,
+};
+use frn_rpc::v1::compute::{ImageKind, ImportImageRequest};
+use tonic::{Code, Request};
+
+mod common;
+
+// A public address, so that the URL is checked without resolving a name.
+const IMAGE_URL: &str =
+    "https://151.101.2.132/images/cloud/bookworm/latest/debian-12-genericcloud-amd64.qcow2";
+
+async fn seed_hypervisor(pool: &sqlx::PgPool, url: String) -> Hypervisor {
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(pool)
+        .await
+        .expect("could not create organization");
+    Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
+        .for_zone(Zone::factory())
+        .organization_slug(organization.slug.clone())
+        .url(url)
+        .storage_name("local".to_owned())
+        .create(pool)
+        .await
+        .expect("could not create hypervisor")
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_import_image_procedure_works(pool: sqlx::PgPool) {
+    // Arrange the grpc server and a hypervisor
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let hypervisor = seed_hypervisor(&pool, api.mock_server.url()).await;
+
+    // Act the request to the import image procedure
+    let request = Request::new(ImportImageRequest {
+        hypervisor_id: hypervisor.id.to_string(),
+        url: IMAGE_URL.to_owned(),
+        name: "debian-12-genericcloud-amd64.qcow2".to_owned(),
+    })
+    .on_behalf_of(&api.service_account);
+    let response = api.compute.images.import(request).await;
+
+    // Assert the imported image is returned
+    let image = response
+        .expect("could not import image")
+        .into_inner()
+        .image
+        .expect("missing image");
+    assert_eq!(image.id, "local:import/debian-12-genericcloud-amd64.qcow2");
+    assert_eq!(image.kind(), ImageKind::Disk);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_import_image_procedure_rejects_unsupported_formats(pool: sqlx::PgPool) {
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let hypervisor = seed_hypervisor(&pool, api.mock_server.url()).await;
+
+    let request = Request::new(ImportImageRequest {
+        hypervisor_id: hypervisor.id.to_string(),
+        url: IMAGE_URL.to_owned(),
+        name: "debian-12-genericcloud-amd64.vmdk".to_owned(),
+    })
+    .on_behalf_of(&api.service_account);
+    let response = api.compute.images.import(request).await;
+
+    assert_eq!(response.unwrap_err().code(), Code::InvalidArgument);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_import_image_procedure_rejects_internal_urls(pool: sqlx::PgPool) {
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let hypervisor = seed_hypervisor(&pool, api.mock_server.url()).await;
+
+    for url in [
+        "http://151.101.2.132/debian-12-genericcloud-amd64.qcow2",
+        "https://localhost/debian-12-genericcloud-amd64.qcow2",
+        "https://10.0.0.1/debian-12-genericcloud-amd64.qcow2",
+        "https://169.254.169.254/latest/meta-data",
+        "https://[fe80::1]/debian-12-genericcloud-amd64.qcow2",
+        "https://[::ffff:192.168.1.1]/debian-12-genericcloud-amd64.qcow2",
+    ] {
+        let request = Request::new(ImportImageRequest {
+            hypervisor_id: hypervisor.id.to_string(),
+            url: url.to_owned(),
+            name: "debian-12-genericcloud-amd64.qcow2".to_owned(),
+        })
+        .on_behalf_of(&api.service_account);
+        let response = api.compute.images.import(request).await;
+
+        assert_eq!(response.unwrap_err().code(), Code::InvalidArgument, "{url}");
+    }
+}
@@ -90,3 +90,20 @@ async fn test_is_idempotent_for_an_existing_admin(
     assert_eq!(count, 1, "exactly one user must exist");
     Ok(())
 }
+
+/// The admin is related to the platform in the authorization backend.
+#[sqlx::test(migrations = "../migrations")]
+async fn test_relates_the_admin_to_the_platform(
+    pool: sqlx::PgPool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (auth, store) = SpiceDB::recording().await;
+    let users = Users::new(auth, pool.clone());
+
+    let admin = users
+        .initialize_root_admin("founder@france-nuage.fr".to_owned())
+        .await?;
+
+    let store = store.lock().expect("relationship store poisoned");
+    assert!(store.contains(&format!("platform:plateforme#admin@user:{}", admin.id)));
+    Ok(())
+}
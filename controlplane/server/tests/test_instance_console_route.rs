@@ -0,0 +1,273 @@
+use crate::common::Api;
+use axum::extract::Query;
+use axum::extract::ws::WebSocketUpgrade;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use fabrique::Factory;
+use frn_core::identity::{SessionKey, SessionPayload, TEST_SESSION_KEY};
+use frn_core::{
+    compute::{Hypervisor, Instance, Zone},
+    resourcemanager::{Organization, Project},
+};
+use futures::{SinkExt, StreamExt};
+use serde_json::json;
+use server::config::TEST_CONSOLE_ORIGIN;
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio_tungstenite::tungstenite::{
+    self, Message, client::IntoClientRequest, handshake::client::Request,
+};
+
+mod common;
+
+const TICKET: &str = "PVEVNC:67CC7F02::bG9yZW0gaXBzdW0=";
+const AUTHORIZATION_TOKEN: &str = "PVEAPIToken=root@pam!api=secret";
+
+/// Starts a fake hypervisor serving the Proxmox endpoints a console goes
+/// through, and echoing the console stream back.
+async fn start_hypervisor() -> String {
+    let proxy = || async {
+        Json(
+            json!({"data":{"port":"5900","ticket":TICKET,"upid":"UPID:pve-node1:0021C7A5:0233D1E4:67CC7F02:vncproxy:100:root@pam!api:","user":"root@pam!api"}}),
+        )
+    };
+    let router = Router::new()
+        .route(
+            "/api2/json/cluster/resources",
+            get(|| async {
+                Json(json!({"data":[{"type":"qemu","vmid":100,"node":"pve-node1","status":"running"}]}))
+            }),
+        )
+        .route("/api2/json/nodes/{node}/qemu/{vmid}/vncproxy", post(proxy))
+        .route("/api2/json/nodes/{node}/qemu/{vmid}/termproxy", post(proxy))
+        .route(
+            "/api2/json/nodes/{node}/qemu/{vmid}/vncwebsocket",
+            get(echo_websocket),
+        );
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("could not bind fake hypervisor");
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, router).await });
+
+    url
+}
+
+async fn echo_websocket(
+    Query(params): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+    upgrade: WebSocketUpgrade,
+) -> Response {
+    let authorized = headers
+        .get("authorization")
+        .is_some_and(|value| value == AUTHORIZATION_TOKEN);
+    if !authorized || params.get("vncticket").map(String::as_str) != Some(TICKET) {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+
+    upgrade.on_upgrade(|mut socket| async move {
+        while let Some(Ok(message)) = socket.recv().await {
+            if socket.send(message).await.is_err() {
+                break;
+            }
+        }
+    })
+}
+
+async fn seed_instance(pool: &sqlx::PgPool, hypervisor_url: String) -> Instance {
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(pool)
+        .await
+        .expect("could not create organization");
+    let hypervisor = Hypervisor::factory()
+        .authorization_token(AUTHORIZATION_TOKEN)
+        .for_zone(Zone::factory())
+        .organization_slug(organization.slug.clone())
+        .url(hypervisor_url)
+        .create(pool)
+        .await
+        .expect("could not create hypervisor");
+    let project = Project::factory()
+        .slug("test-project".to_owned())
+        .organization_slug(organization.slug.clone())
+        .create(pool)
+        .await
+        .expect("could not create project");
+    Instance::factory()
+        .hypervisor_id(hypervisor.id)
+        .project_slug(project.slug.clone())
+        .distant_id("100".into())
+        .zero_trust_network_id(None)
+        .create(pool)
+        .await
+        .expect("could not create instance")
+}
+
+fn console_request(api: &Api, instance: &Instance, kind: &str) -> Request {
+    format!(
+        "{}/instances/{}/console?kind={}",
+        api.http_url.replacen("http", "ws", 1),
+        instance.id,
+        kind
+    )
+    .into_client_request()
+    .expect("could not build console request")
+}
+
+fn on_behalf_of(mut request: Request, api: &Api) -> Request {
+    request.headers_mut().insert(
+        "authorization",
+        format!("Bearer {}", api.service_account.key)
+            .parse()
+            .expect("could not build authorization header"),
+    );
+    request
+}
+
+/// Authenticates the request with a session cookie, sent from `origin`.
+fn with_session_cookie(mut request: Request, origin: Option<&str>) -> Request {
+    let exp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("system clock before unix epoch")
+        .as_secs()
+        + 3600;
+    let cookie = SessionKey::from_bytes(TEST_SESSION_KEY)
+        .seal(&SessionPayload {
+            refresh_token: "rt".to_owned(),
+            sub: "subject-1".to_owned(),
+            email: "console-user@francenuage.fr".to_owned(),
+            exp,
+        })
+        .expect("could not seal session cookie");
+    request.headers_mut().insert(
+        "cookie",
+        format!("frn_session={cookie}")
+            .parse()
+            .expect("could not build cookie header"),
+    );
+    if let Some(origin) = origin {
+        request.headers_mut().insert(
+            "origin",
+            origin.parse().expect("could not build origin header"),
+        );
+    }
+    request
+}
+
+async fn rejection(request: Request) -> StatusCode {
+    match tokio_tungstenite::connect_async(request).await {
+        Err(tungstenite::Error::Http(response)) => response.status(),
+        other => panic!("expected an HTTP rejection, got {other:?}"),
+    }
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_console_route_relays_the_vnc_stream(pool: sqlx::PgPool) {
+    // Arrange the server and an instance on the fake hypervisor
+    let api = Api::start(&pool).await.expect("could not start api");
+    let instance = seed_instance(&pool, start_hypervisor().await).await;
+
+    // Act the connection to the console route
+    let request = on_behalf_of(console_request(&api, &instance, "vnc"), &api);
+    let (mut socket, _) = tokio_tungstenite::connect_async(request)
+        .await
+        .expect("could not open console");
+
+    // Assert the password is sent first, then the stream is relayed
+    let password = socket.next().await.unwrap().unwrap();
+    assert_eq!(password, Message::text(TICKET));
+    socket
+        .send(Message::binary(vec![0x52, 0x46, 0x42]))
+        .await
+        .unwrap();
+    let echoed = socket.next().await.unwrap().unwrap();
+    assert_eq!(echoed, Message::binary(vec![0x52, 0x46, 0x42]));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_console_route_authenticates_the_terminal_stream(pool: sqlx::PgPool) {
+    // Arrange the server and an instance on the fake hypervisor
+    let api = Api::start(&pool).await.expect("could not start api");
+    let instance = seed_instance(&pool, start_hypervisor().await).await;
+
+    // Act the connection to the console route
+    let request = on_behalf_of(console_request(&api, &instance, "terminal"), &api);
+    let (mut socket, _) = tokio_tungstenite::connect_async(request)
+        .await
+        .expect("could not open console");
+
+    // Assert the relay sent the login message, echoed by the fake hypervisor
+    let login = socket.next().await.unwrap().unwrap();
+    assert_eq!(login, Message::text(format!("root@pam!api:{TICKET}\n")));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_console_route_requires_authentication(pool: sqlx::PgPool) {
+    let api = Api::start(&pool).await.expect("could not start api");
+    let instance = seed_instance(&pool, start_hypervisor().await).await;
+
+    let status = rejection(console_request(&api, &instance, "vnc")).await;
+
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_console_route_rejects_unknown_kinds(pool: sqlx::PgPool) {
+    let api = Api::start(&pool).await.expect("could not start api");
+    let instance = seed_instance(&pool, start_hypervisor().await).await;
+
+    let status = rejection(on_behalf_of(
+        console_request(&api, &instance, "spice"),
+        &api,
+    ))
+    .await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_console_route_fails_when_the_hypervisor_refuses_the_stream(pool: sqlx::PgPool) {
+    // Arrange an instance on a hypervisor issuing tickets but not streaming
+    let api = Api::start(&pool).await.expect("could not start api");
+    let instance = seed_instance(&pool, api.mock_server.url()).await;
+
+    let status = rejection(on_behalf_of(console_request(&api, &instance, "vnc"), &api)).await;
+
+    assert_eq!(status, StatusCode::BAD_GATEWAY);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_console_route_accepts_cookies_from_the_console_origin(pool: sqlx::PgPool) {
+    let api = Api::start(&pool).await.expect("could not start api");
+    let instance = seed_instance(&pool, start_hypervisor().await).await;
+
+    let request = with_session_cookie(
+        console_request(&api, &instance, "vnc"),
+        Some(TEST_CONSOLE_ORIGIN),
+    );
+    let (mut socket, _) = tokio_tungstenite::connect_async(request)
+        .await
+        .expect("could not open console");
+
+    let password = socket.next().await.unwrap().unwrap();
+    assert_eq!(password, Message::text(TICKET));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_console_route_rejects_cookies_from_other_origins(pool: sqlx::PgPool) {
+    let api = Api::start(&pool).await.expect("could not start api");
+    let instance = seed_instance(&pool, start_hypervisor().await).await;
+
+    for origin in [Some("https://attacker.test"), None] {
+        let request = with_session_cookie(console_request(&api, &instance, "vnc"), origin);
+
+        let status = rejection(request).await;
+
+        assert_eq!(status, StatusCode::FORBIDDEN, "origin {origin:?}");
+    }
+}
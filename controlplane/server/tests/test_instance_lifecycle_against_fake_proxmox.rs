@@ -0,0 +1,165 @@
+//! Runs the lifecycle of an instance through the compute procedures, against
+//! the stateful emulator of the Proxmox API rather than canned responses.
+
+use crate::common::{Api, OnBehalfOf, run_workflow};
+use fabrique::{Factory, Query};
+use frn_core::{
+    compute::{Hypervisor, Instance, Zone},
+    resourcemanager::{Organization, Project},
+};
+use frn_rpc::v1::compute::{
+    CreateInstanceRequest, DeleteInstanceRequest, InstanceStatus, StartInstanceRequest,
+    StopInstanceRequest,
+};
+use hypervisor::instance::Status;
+use hypervisor::proxmox::fake::{FakeProxmox, WithFakeProxmox};
+use mock_server::MockServer;
+use std::net::Ipv4Addr;
+use tonic::{Code, Request};
+use uuid::Uuid;
+
+mod common;
+
+const GIB: u64 = 1024 * 1024 * 1024;
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_an_instance_goes_through_its_lifecycle(pool: sqlx::PgPool) {
+    let snippets = common::snippets_volume();
+    // SAFETY: this is the only test of the binary, so no other thread reads
+    // the environment while it is modified.
+    unsafe { std::env::set_var("PROXMOX_VOLUME_ABSOLUTE_PATH", &snippets) };
+
+    // Arrange the grpc server, and a hypervisor emulating a single node
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let proxmox = FakeProxmox::new().with_node("pve-node1", 16, 64 * GIB);
+    let server = MockServer::new().await.with_fake_proxmox(&proxmox);
+
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await
+        .expect("could not create organization");
+    let zone = Zone::factory()
+        .create(&pool)
+        .await
+        .expect("could not create zone");
+    Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
+        .zone_id(zone.id)
+        .organization_slug(organization.slug.clone())
+        .url(server.url())
+        .create(&pool)
+        .await
+        .expect("could not create hypervisor");
+    Project::factory()
+        .slug("test-project".to_owned())
+        .organization_slug(organization.slug.clone())
+        .create(&pool)
+        .await
+        .expect("could not create project");
+
+    // Act the creation of an instance
+    let request = Request::new(CreateInstanceRequest {
+        image: "debian-12-genericcloud-amd64-20241201-1948.qcow2".to_owned(),
+        cpu_cores: 2,
+        disk_bytes: 20 * GIB,
+        memory_bytes: 2 * GIB,
+        name: "web".to_owned(),
+        snippet: String::new(),
+        project_slug: "test-project".to_owned(),
+        zone_id: Some(zone.id.to_string()),
+        zero_trust_network_id: None,
+    })
+    .on_behalf_of(&api.service_account);
+    let response = api
+        .compute
+        .instances
+        .create(request)
+        .await
+        .expect("could not create instance")
+        .into_inner();
+    let instance = response
+        .instance
+        .expect("the response should hold the instance");
+    let id = instance.id.clone();
+
+    // Assert the instance awaits its provisioning
+    assert_eq!(instance.status, InstanceStatus::Provisioning as i32);
+    assert!(proxmox.vms().is_empty());
+
+    // Act the run of the provisioning workflow
+    run_workflow(&pool, &response.execution_id)
+        .await
+        .expect("could not provision instance");
+
+    // Assert the VM was created, and the instance recorded
+    let vm = proxmox.vm(100).expect("the vm should be created");
+    assert!(matches!(vm.status, Status::Stopped));
+    assert_eq!(vm.config["cores"], "2");
+    let recorded = Instance::find(&pool, Uuid::parse_str(&id).unwrap())
+        .await
+        .expect("the instance should be recorded");
+    assert_eq!(recorded.distant_id, "100");
+
+    // Act the start of the instance while the hypervisor fails to, then once
+    // it recovered
+    proxmox.fail_tasks("qmstart", "start failed: QEMU exited with code 1");
+    let failed = api
+        .compute
+        .instances
+        .start(
+            Request::new(StartInstanceRequest { id: id.clone() })
+                .on_behalf_of(&api.service_account),
+        )
+        .await;
+    proxmox.heal_tasks("qmstart");
+    let started = api
+        .compute
+        .instances
+        .start(
+            Request::new(StartInstanceRequest { id: id.clone() })
+                .on_behalf_of(&api.service_account),
+        )
+        .await;
+
+    // Assert the failure was reported, and the VM runs with an address
+    assert_eq!(failed.unwrap_err().code(), Code::Internal);
+    assert!(started.is_ok());
+    let vm = proxmox.vm(100).expect("the vm should exist");
+    assert!(matches!(vm.status, Status::Running));
+    assert_eq!(vm.ip_address, Some(Ipv4Addr::new(10, 0, 0, 102)));
+
+    // Act the stop and the deletion of the instance
+    let stopped = api
+        .compute
+        .instances
+        .stop(
+            Request::new(StopInstanceRequest { id: id.clone() }).on_behalf_of(&api.service_account),
+        )
+        .await;
+    let deleted = api
+        .compute
+        .instances
+        .delete(
+            Request::new(DeleteInstanceRequest { id: id.clone() })
+                .on_behalf_of(&api.service_account),
+        )
+        .await;
+
+    // Assert the VM, its snippet and the instance are gone
+    assert!(stopped.is_ok());
+    assert!(deleted.is_ok());
+    assert!(proxmox.vms().is_empty());
+    assert_eq!(
+        std::fs::read_dir(snippets.join("snippets"))
+            .unwrap()
+            .count(),
+        0
+    );
+    assert!(
+        Instance::find(&pool, Uuid::parse_str(&id).unwrap())
+            .await
+            .is_err()
+    );
+}
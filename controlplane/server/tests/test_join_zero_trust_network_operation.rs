@@ -0,0 +1,95 @@
+use common::worker_context;
+use fabrique::{Factory, Query};
+use frn_core::compute::{Hypervisor, Instance, Overlays, ZeroTrustNetworkPeer, Zone};
+use frn_core::resourcemanager::{Organization, Project};
+use infrastructure::{ZeroTrustNetwork, ZeroTrustNetworkType};
+use workflow::execution::WorkflowExecutionId;
+use workflow::operations::Operation;
+use workflow::operations::join_zero_trust_network::JoinZeroTrustNetworkOp;
+
+mod common;
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_execute_injects_the_overlay_configuration_then_rollback_leaves(
+    pool: sqlx::PgPool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Arrange a network already holding a peer, and an instance to join it
+    let ctx = worker_context(&pool).await;
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await?;
+    let zero_trust_network = ZeroTrustNetwork::factory()
+        .organization_slug(organization.slug.clone())
+        .for_zero_trust_network_type(ZeroTrustNetworkType::factory())
+        .create(&pool)
+        .await?;
+    let hypervisor = Hypervisor::factory()
+        .for_zone(Zone::factory())
+        .organization_slug(organization.slug.clone())
+        .create(&pool)
+        .await?;
+    let project = Project::factory()
+        .slug("test-project".to_owned())
+        .organization_slug(organization.slug.clone())
+        .create(&pool)
+        .await?;
+    let mut instances = Vec::new();
+    for _ in 0..2 {
+        let instance = Instance::factory()
+            .hypervisor_id(hypervisor.id)
+            .project_slug(project.slug.clone())
+            .ip_v4("192.0.2.10".to_owned())
+            .zero_trust_network_id(Some(zero_trust_network.id))
+            .create(&pool)
+            .await?;
+        instances.push(instance);
+    }
+    let overlays = Overlays::new(pool.clone(), ctx.hypervisor_resolver.clone());
+    let peer = overlays
+        .join(zero_trust_network.id, instances[0].id)
+        .await?;
+
+    // Act the operation joining the second instance
+    let operation = JoinZeroTrustNetworkOp {
+        instance_id: instances[1].id,
+        zero_trust_network_id: zero_trust_network.id,
+        snippet: "#cloud-config\npackages:\n  - curl\n".to_owned(),
+        prepared_snippet: None,
+    }
+    .execute(ctx.clone(), WorkflowExecutionId::new())
+    .await?;
+
+    // Assert the snippet configures the overlay, the existing peer included
+    let snippet = operation
+        .prepared_snippet
+        .clone()
+        .expect("the snippet should be prepared");
+    assert!(snippet.starts_with("#cloud-config\n"));
+    assert!(snippet.contains("- curl"));
+    assert!(snippet.contains("- wireguard-tools"));
+    assert!(snippet.contains("/etc/wireguard/wg-frn.conf"));
+    assert!(snippet.contains("Address = 100.64.0.2/16"));
+    assert!(snippet.contains(&format!("PublicKey = {}", peer.public_key)));
+    assert!(snippet.contains("Endpoint = 192.0.2.10:51820"));
+    assert!(snippet.contains("systemctl enable --now wg-quick@wg-frn"));
+
+    // Act the rollback of the operation
+    operation.rollback(ctx, WorkflowExecutionId::new()).await?;
+
+    // Assert the second instance left the overlay, the first one still in it
+    let peers = ZeroTrustNetworkPeer::query()
+        .select()
+        .r#where(
+            ZeroTrustNetworkPeer::ZERO_TRUST_NETWORK_ID,
+            "=",
+            zero_trust_network.id,
+        )
+        .get(&pool)
+        .await?;
+    assert_eq!(peers.len(), 1);
+    assert_eq!(peers[0].instance_id, instances[0].id);
+
+    Ok(())
+}
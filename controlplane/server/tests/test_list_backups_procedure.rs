@@ -0,0 +1,82 @@
+use crate::common::{Api, OnBehalfOf};
+use fabrique::Factory;
+use frn_core::{
+    compute::{Hypervisor, Instance, Zone},
+    resourcemanager::{Organization, Project},
+};
+use frn_rpc::v1::compute::ListBackupsRequest;
+use hypervisor::mock::BACKUP_OWNER;
+use tonic::{Code, Request};
+
+mod common;
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_list_backups_procedure_works(pool: sqlx::PgPool) {
+    // Arrange the grpc server and a client
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let mock_url = api.mock_server.url();
+
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await
+        .expect("could not create organization");
+    let hypervisor = Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
+        .for_zone(Zone::factory())
+        .organization_slug(organization.slug.clone())
+        .url(mock_url)
+        .create(&pool)
+        .await
+        .expect("could not create hypervisor");
+    let project = Project::factory()
+        .slug("test-project".to_owned())
+        .organization_slug(organization.slug.clone())
+        .create(&pool)
+        .await
+        .expect("could not create project");
+    let instance = Instance::factory()
+        .id(BACKUP_OWNER)
+        .hypervisor_id(hypervisor.id)
+        .project_slug(project.slug.clone())
+        .distant_id("100".into())
+        .zero_trust_network_id(None)
+        .create(&pool)
+        .await
+        .expect("could not create instance");
+
+    // Act the request to the test_the_list_backups_procedure_works
+    let request = Request::new(ListBackupsRequest {
+        instance_id: instance.id.to_string(),
+    })
+    .on_behalf_of(&api.service_account);
+    let response = api.compute.backups.list(request).await;
+
+    // Assert the result, only the backups of the instance are listed
+    assert!(response.is_ok());
+    let backups = response.unwrap().into_inner().backups;
+    assert_eq!(backups.len(), 1);
+    assert_eq!(backups[0].id, "pbs:backup/vm/100/2025-03-08T18:00:00Z");
+    assert_eq!(backups[0].size_bytes, 2147483648);
+    assert_eq!(
+        backups[0].notes.as_deref(),
+        Some(format!("frn-instance={}", BACKUP_OWNER).as_str())
+    );
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_list_backups_procedure_rejects_unknown_instances(pool: sqlx::PgPool) {
+    // Arrange the grpc server and a client
+    let mut api = Api::start(&pool).await.expect("could not start api");
+
+    // Act the request to the test_the_list_backups_procedure_rejects_unknown_instances
+    let request = Request::new(ListBackupsRequest {
+        instance_id: "not-an-id".to_owned(),
+    })
+    .on_behalf_of(&api.service_account);
+    let response = api.compute.backups.list(request).await;
+
+    // Assert the result
+    assert_eq!(response.unwrap_err().code(), Code::InvalidArgument);
+}
@@ -0,0 +1,81 @@
+use crate::common::{Api, WithUser, seed_admin_token};
+use fabrique::Factory;
+use frn_core::{
+    compute::{Hypervisor, HypervisorHealth, Zone},
+    resourcemanager::Organization,
+};
+use frn_rpc::v1::compute::{self, ListHypervisorsRequest};
+use tonic::Request;
+
+mod common;
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_list_hypervisors_procedure_reports_the_last_probe(pool: sqlx::PgPool) {
+    // Arrange a probed hypervisor and one never probed
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let token = seed_admin_token(&pool, "admin@francenuage.fr").await;
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await
+        .expect("could not create organization");
+    let probed = Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
+        .for_zone(Zone::factory())
+        .organization_slug(organization.slug.clone())
+        .health(HypervisorHealth::Healthy)
+        .last_health_check_at(Some(chrono::Utc::now()))
+        .version(Some("8.2.4".to_owned()))
+        .node_count(3)
+        .cpu_total(48)
+        .cpu_free(40.5)
+        .memory_total_bytes(192 * 1024 * 1024 * 1024)
+        .memory_free_bytes(64 * 1024 * 1024 * 1024)
+        .storage_total_bytes(3 * 1024 * 1024 * 1024 * 1024)
+        .storage_free_bytes(1024 * 1024 * 1024 * 1024)
+        .create(&pool)
+        .await
+        .expect("could not create hypervisor");
+    let unprobed = Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
+        .for_zone(Zone::factory())
+        .organization_slug(organization.slug.clone())
+        .create(&pool)
+        .await
+        .expect("could not create hypervisor");
+
+    // Act the request to the list hypervisors procedure
+    let request = Request::new(ListHypervisorsRequest {}).with_user(&token);
+    let hypervisors = api
+        .compute
+        .hypervisors
+        .list(request)
+        .await
+        .expect("could not list hypervisors")
+        .into_inner()
+        .hypervisors;
+
+    // Assert the probe results are exposed
+    let find = |id: uuid::Uuid| {
+        hypervisors
+            .iter()
+            .find(|hypervisor| hypervisor.id == id.to_string())
+            .expect("could not find hypervisor")
+    };
+    let probed = find(probed.id);
+    assert_eq!(probed.health(), compute::HypervisorHealth::Healthy);
+    assert!(probed.last_health_check_at.is_some());
+    assert_eq!(probed.version.as_deref(), Some("8.2.4"));
+    assert_eq!(probed.node_count, 3);
+    assert_eq!(probed.cpu_total, 48);
+    assert_eq!(probed.cpu_free, 40.5);
+    assert_eq!(probed.memory_free_bytes, 64 * 1024 * 1024 * 1024);
+    assert_eq!(probed.storage_free_bytes, 1024 * 1024 * 1024 * 1024);
+
+    let unprobed = find(unprobed.id);
+    assert_eq!(
+        unprobed.health(),
+        compute::HypervisorHealth::UndefinedHypervisorHealth
+    );
+}
@@ -0,0 +1,86 @@
+use crate::common::{Api, OnBehalfOf};
+use fabrique::Factory;
+use frn_core::{
+    compute::{Hypervisor, Zone},
+    resourcemanager::Organization,
+};
+use frn_rpc::v1::compute::{ImageKind, ListImagesRequest};
+use tonic::Request;
+
+mod common;
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_list_images_procedure_works(pool: sqlx::PgPool) {
+    // Arrange the grpc server and a hypervisor holding images
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await
+        .expect("could not create organization");
+    let hypervisor = Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
+        .for_zone(Zone::factory())
+        .organization_slug(organization.slug.clone())
+        .url(api.mock_server.url())
+        .storage_name("local".to_owned())
+        .create(&pool)
+        .await
+        .expect("could not create hypervisor");
+
+    // Act the request to the list images procedure
+    let request = Request::new(ListImagesRequest {
+        hypervisor_id: hypervisor.id.to_string(),
+    })
+    .on_behalf_of(&api.service_account);
+    let response = api.compute.images.list(request).await;
+
+    // Assert the ISO and cloud images are listed, not the other volumes
+    let images = response.expect("could not list images").into_inner().images;
+    let listed: Vec<_> = images
+        .iter()
+        .map(|image| (image.id.as_str(), image.kind()))
+        .collect();
+    assert_eq!(
+        listed,
+        vec![
+            (
+                "local:import/debian-12-genericcloud-amd64.qcow2",
+                ImageKind::Disk
+            ),
+            ("local:iso/debian-12.7.0-amd64-netinst.iso", ImageKind::Iso),
+        ]
+    );
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_list_images_procedure_fails_on_unknown_storages(pool: sqlx::PgPool) {
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await
+        .expect("could not create organization");
+    let hypervisor = Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
+        .for_zone(Zone::factory())
+        .organization_slug(organization.slug.clone())
+        .url(api.mock_server.url())
+        .storage_name("missing-storage".to_owned())
+        .create(&pool)
+        .await
+        .expect("could not create hypervisor");
+
+    let request = Request::new(ListImagesRequest {
+        hypervisor_id: hypervisor.id.to_string(),
+    })
+    .on_behalf_of(&api.service_account);
+    let response = api.compute.images.list(request).await;
+
+    assert_eq!(
+        response.unwrap_err().code(),
+        tonic::Code::FailedPrecondition
+    );
+}
@@ -1,4 +1,4 @@
-use crate::common::{Api, OnBehalfOf};
+use crate::common::{Api, WithUser, seed_admin_token};
 use fabrique::Factory;
 use frn_core::compute::{Hypervisor, Instance, Zone};
 use frn_core::resourcemanager::{DEFAULT_PROJECT_NAME, Organization, Project};
@@ -11,6 +11,7 @@ mod common;
 async fn test_the_list_instances_procedure_works(pool: sqlx::PgPool) {
     // Arrange the grpc server and a client
     let mut api = Api::start(&pool).await.expect("could not start api");
+    let token = seed_admin_token(&pool, "admin@francenuage.fr").await;
     let mock_url = api.mock_server.url();
 
     let organization = Organization::factory()
@@ -20,6 +21,7 @@ async fn test_the_list_instances_procedure_works(pool: sqlx::PgPool) {
         .await
         .expect("could not create organization");
     let hypervisor = Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
         .for_zone(Zone::factory())
         .organization_slug(organization.slug.clone())
         .url(mock_url)
@@ -42,7 +44,7 @@ async fn test_the_list_instances_procedure_works(pool: sqlx::PgPool) {
         .expect("could not create instance");
 
     // Act the request to the test_the_status_procedure_works
-    let request = Request::new(ListInstancesRequest::default()).on_behalf_of(&api.service_account);
+    let request = Request::new(ListInstancesRequest::default()).with_user(&token);
     let response = api.compute.instances.list(request).await;
 
     // Assert the result
@@ -0,0 +1,59 @@
+use crate::common::{Api, OnBehalfOf};
+use fabrique::Factory;
+use frn_core::{
+    compute::{SecurityGroup, SecurityGroupRule},
+    resourcemanager::{Organization, Project},
+};
+use frn_rpc::v1::compute::{ListSecurityGroupRulesRequest, SecurityGroupRuleDirection};
+use hypervisor::instance::FirewallDirection;
+use tonic::Request;
+
+mod common;
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_list_security_group_rules_procedure_works(pool: sqlx::PgPool) {
+    // Arrange the grpc server and a client
+    let mut api = Api::start(&pool).await.expect("could not start api");
+
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await
+        .expect("could not create organization");
+    let project = Project::factory()
+        .slug("test-project".to_owned())
+        .organization_slug(organization.slug.clone())
+        .create(&pool)
+        .await
+        .expect("could not create project");
+    let security_group = SecurityGroup::factory()
+        .project_slug(project.slug.clone())
+        .name("web".to_owned())
+        .create(&pool)
+        .await
+        .expect("could not create security group");
+    let rule = SecurityGroupRule::factory()
+        .security_group_id(security_group.id)
+        .direction(FirewallDirection::Ingress)
+        .protocol(Some("tcp".to_owned()))
+        .port_range(Some("22".to_owned()))
+        .cidr(Some("10.0.0.0/8".to_owned()))
+        .create(&pool)
+        .await
+        .expect("could not create rule");
+
+    // Act the request to the test_the_list_security_group_rules_procedure_works
+    let request = Request::new(ListSecurityGroupRulesRequest {
+        security_group_id: security_group.id.to_string(),
+    })
+    .on_behalf_of(&api.service_account);
+    let response = api.compute.security_groups.list_rules(request).await;
+
+    // Assert the result
+    let rules = response.unwrap().into_inner().rules;
+    assert_eq!(rules.len(), 1);
+    assert_eq!(rules[0].id, rule.id.to_string());
+    assert_eq!(rules[0].direction(), SecurityGroupRuleDirection::Ingress);
+    assert_eq!(rules[0].port_range.as_deref(), Some("22"));
+}
@@ -0,0 +1,19 @@
+use crate::common::{Api, OnBehalfOf};
+use frn_rpc::v1::compute::ListSecurityGroupsRequest;
+use tonic::Request;
+
+mod common;
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_list_security_groups_procedure_works(pool: sqlx::PgPool) {
+    // Arrange the grpc server and a client
+    let mut api = Api::start(&pool).await.expect("could not start api");
+
+    // Act the request to the test_the_list_security_groups_procedure_works
+    let request =
+        Request::new(ListSecurityGroupsRequest::default()).on_behalf_of(&api.service_account);
+    let response = api.compute.security_groups.list(request).await;
+
+    // Assert the result
+    assert!(response.is_ok());
+}
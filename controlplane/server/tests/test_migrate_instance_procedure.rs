@@ -0,0 +1,201 @@
+use crate::common::{Api, OnBehalfOf, WithUser, seed_admin_token};
+use fabrique::{Factory, Query};
+use frn_core::{
+    compute::{Hypervisor, Instance, Zone},
+    resourcemanager::{Organization, Project},
+};
+use frn_rpc::v1::compute::MigrateInstanceRequest;
+use hypervisor::instance::Status;
+use hypervisor::proxmox::fake::{FakeProxmox, WithFakeProxmox};
+use mock_server::MockServer;
+use tonic::{Code, Request};
+
+mod common;
+
+const GIB: u64 = 1024 * 1024 * 1024;
+
+/// Seeds an instance recorded on a hypervisor served by the given server.
+async fn seed_instance(pool: &sqlx::PgPool, url: String) -> Instance {
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(pool)
+        .await
+        .expect("could not create organization");
+    let hypervisor = Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
+        .for_zone(Zone::factory())
+        .organization_slug(organization.slug.clone())
+        .url(url)
+        .create(pool)
+        .await
+        .expect("could not create hypervisor");
+    let project = Project::factory()
+        .slug("test-project".to_owned())
+        .organization_slug(organization.slug.clone())
+        .create(pool)
+        .await
+        .expect("could not create project");
+
+    Instance::factory()
+        .hypervisor_id(hypervisor.id)
+        .project_slug(project.slug.clone())
+        .distant_id("100".into())
+        .max_cpu_cores(1)
+        .max_memory_bytes(GIB as i64)
+        .max_disk_bytes(10 * GIB as i64)
+        .zero_trust_network_id(None)
+        .create(pool)
+        .await
+        .expect("could not create instance")
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_migrate_instance_procedure_works(pool: sqlx::PgPool) {
+    // Arrange the grpc server, and a hypervisor of two nodes running the instance
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let proxmox = FakeProxmox::new()
+        .with_node("pve-node1", 16, 64 * GIB)
+        .with_node("pve-node2", 16, 64 * GIB)
+        .with_vm("pve-node1", 100, "web", Status::Running);
+    let server = MockServer::new().await.with_fake_proxmox(&proxmox);
+    let instance = seed_instance(&pool, server.url()).await;
+    let token = seed_admin_token(&pool, "admin@francenuage.fr").await;
+
+    // Act the request to the test_the_migrate_instance_procedure_works
+    let request = Request::new(MigrateInstanceRequest {
+        id: instance.id.to_string(),
+        node: None,
+        hypervisor_id: None,
+    })
+    .with_user(&token);
+    let response = api.compute.instances.migrate(request).await;
+
+    // Assert the instance was migrated live to the other node
+    let migration = response
+        .expect("the migration should succeed")
+        .into_inner()
+        .migration
+        .expect("the response should hold the migration");
+    assert_eq!(migration.instance_id, instance.id.to_string());
+    assert_eq!(migration.source_node, "pve-node1");
+    assert_eq!(migration.target_node, "pve-node2");
+    let vm = proxmox.vm(100).expect("the vm should exist");
+    assert_eq!(vm.node, "pve-node2");
+    assert!(matches!(vm.status, Status::Running));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_migrate_instance_procedure_moves_instances_to_another_hypervisor(
+    pool: sqlx::PgPool,
+) {
+    // Arrange the grpc server, and two hypervisors sharing their backup
+    // storage, the first one running the instance
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let source = FakeProxmox::new()
+        .with_node("pve-node1", 16, 64 * GIB)
+        .with_vm("pve-node1", 100, "web", Status::Running);
+    let target = FakeProxmox::new()
+        .with_node("pve-node1", 16, 64 * GIB)
+        .with_vm("pve-node1", 100, "db", Status::Running)
+        .with_backup_storage_of(&source);
+    let source_server = MockServer::new().await.with_fake_proxmox(&source);
+    let target_server = MockServer::new().await.with_fake_proxmox(&target);
+    let instance = seed_instance(&pool, source_server.url()).await;
+    let hypervisor = Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
+        .for_zone(Zone::factory())
+        .organization_slug("test-org".to_owned())
+        .url(target_server.url())
+        .storage_name("local-lvm".to_owned())
+        .create(&pool)
+        .await
+        .expect("could not create hypervisor");
+    let token = seed_admin_token(&pool, "admin@francenuage.fr").await;
+
+    // Act the request to the test_the_migrate_instance_procedure_moves_instances_to_another_hypervisor
+    let request = Request::new(MigrateInstanceRequest {
+        id: instance.id.to_string(),
+        node: None,
+        hypervisor_id: Some(hypervisor.id.to_string()),
+    })
+    .with_user(&token);
+    let response = api.compute.instances.migrate(request).await;
+
+    // Assert the instance runs on the other hypervisor, the source VM and its
+    // export being gone
+    let migration = response
+        .expect("the migration should succeed")
+        .into_inner()
+        .migration
+        .expect("the response should hold the migration");
+    assert_eq!(migration.instance_id, instance.id.to_string());
+    assert_eq!(migration.hypervisor_id, hypervisor.id.to_string());
+    assert_eq!(migration.distant_id, "101");
+    let vm = target.vm(101).expect("the vm should be imported");
+    assert!(matches!(vm.status, Status::Running));
+    assert!(source.vm(100).is_none());
+    assert!(source.volumes("pbs").is_empty());
+    let instance = Instance::find(&pool, instance.id)
+        .await
+        .expect("could not find instance");
+    assert_eq!(instance.hypervisor_id, hypervisor.id);
+    assert_eq!(instance.distant_id, "101");
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_migrate_instance_procedure_rejects_unknown_nodes(pool: sqlx::PgPool) {
+    // Arrange the grpc server, and a hypervisor of two nodes holding the instance
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let proxmox = FakeProxmox::new()
+        .with_node("pve-node1", 16, 64 * GIB)
+        .with_node("pve-node2", 16, 64 * GIB)
+        .with_vm("pve-node1", 100, "web", Status::Stopped);
+    let server = MockServer::new().await.with_fake_proxmox(&proxmox);
+    let instance = seed_instance(&pool, server.url()).await;
+    let token = seed_admin_token(&pool, "admin@francenuage.fr").await;
+
+    // Act the request to the test_the_migrate_instance_procedure_rejects_unknown_nodes
+    let request = Request::new(MigrateInstanceRequest {
+        id: instance.id.to_string(),
+        node: Some("pve-node9".to_owned()),
+        hypervisor_id: None,
+    })
+    .with_user(&token);
+    let response = api.compute.instances.migrate(request).await;
+
+    // Assert the result
+    assert_eq!(response.unwrap_err().code(), Code::NotFound);
+    assert_eq!(
+        proxmox.vm(100).expect("the vm should exist").node,
+        "pve-node1"
+    );
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_migrate_instance_procedure_is_restricted_to_admins(pool: sqlx::PgPool) {
+    // Arrange the grpc server, and a hypervisor of two nodes running the instance
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let proxmox = FakeProxmox::new()
+        .with_node("pve-node1", 16, 64 * GIB)
+        .with_node("pve-node2", 16, 64 * GIB)
+        .with_vm("pve-node1", 100, "web", Status::Running);
+    let server = MockServer::new().await.with_fake_proxmox(&proxmox);
+    let instance = seed_instance(&pool, server.url()).await;
+
+    // Act the request to the test_the_migrate_instance_procedure_is_restricted_to_admins
+    let request = Request::new(MigrateInstanceRequest {
+        id: instance.id.to_string(),
+        node: None,
+        hypervisor_id: None,
+    })
+    .on_behalf_of(&api.service_account);
+    let response = api.compute.instances.migrate(request).await;
+
+    // Assert the result
+    assert_eq!(response.unwrap_err().code(), Code::PermissionDenied);
+    assert_eq!(
+        proxmox.vm(100).expect("the vm should exist").node,
+        "pve-node1"
+    );
+}
@@ -0,0 +1,82 @@
+//! Tests for the hypervisor probe run on each synchronizer pass.
+//!
+//! Drives `Hypervisors::probe` against a mocked Proxmox API, checking the
+//! reachability, version and capacity recorded on the hypervisor.
+
+use fabrique::Factory;
+use frn_core::App;
+use frn_core::compute::{Hypervisor, HypervisorHealth, Zone};
+use frn_core::resourcemanager::Organization;
+use hypervisor::mock::{WithClusterResourceList, WithVersionReadMock};
+use mock_server::MockServer;
+
+/// An address nothing listens on, standing for an unreachable hypervisor.
+const UNREACHABLE_URL: &str = "http://127.0.0.1:1";
+
+async fn seed_hypervisor(pool: &sqlx::PgPool, url: String) -> Hypervisor {
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(pool)
+        .await
+        .expect("could not create organization");
+    Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
+        .for_zone(Zone::factory())
+        .organization_slug(organization.slug.clone())
+        .url(url)
+        .health(HypervisorHealth::Healthy)
+        .version(Some("8.1.0".to_owned()))
+        .node_count(2)
+        .create(pool)
+        .await
+        .expect("could not create hypervisor")
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_probe_records_the_health_version_and_capacity(
+    pool: sqlx::PgPool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Arrange a hypervisor answering the probe
+    let server = MockServer::new()
+        .await
+        .with_cluster_resource_list()
+        .with_version_read();
+    let app = App::test(pool.clone()).await?;
+    let hypervisor = seed_hypervisor(&pool, server.url()).await;
+
+    // Act the probe
+    let probed = app.hypervisors.probe(&hypervisor).await?;
+
+    // Assert the report of the single online node of the mock was recorded
+    assert_eq!(probed.health, HypervisorHealth::Healthy);
+    assert!(probed.last_health_check_at.is_some());
+    assert_eq!(probed.version.as_deref(), Some("8.2.4"));
+    assert_eq!(probed.node_count, 1);
+    assert_eq!(probed.cpu_total, 16);
+    assert!(probed.cpu_free > 15.0 && probed.cpu_free < 16.0);
+    assert_eq!(probed.memory_total_bytes, 67396141056);
+    assert_eq!(probed.memory_free_bytes, 67396141056 - 7849295872);
+    assert_eq!(probed.storage_total_bytes, 429496729600);
+    assert_eq!(probed.storage_free_bytes, 429496729600 - 21474836480);
+    Ok(())
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_probe_marks_unreachable_hypervisors(
+    pool: sqlx::PgPool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Arrange a hypervisor nothing answers for
+    let app = App::test(pool.clone()).await?;
+    let hypervisor = seed_hypervisor(&pool, UNREACHABLE_URL.to_owned()).await;
+
+    // Act the probe
+    let probed = app.hypervisors.probe(&hypervisor).await?;
+
+    // Assert the hypervisor was marked unreachable, keeping its last report
+    assert_eq!(probed.health, HypervisorHealth::Unreachable);
+    assert!(probed.last_health_check_at.is_some());
+    assert_eq!(probed.version.as_deref(), Some("8.1.0"));
+    assert_eq!(probed.node_count, 2);
+    Ok(())
+}
@@ -0,0 +1,137 @@
+//! Runs the workflow provisioning an instance against the stateful emulator
+//! of the Proxmox API, through its failures and retries.
+
+use crate::common::{Api, OnBehalfOf, run_workflow, worker_context};
+use fabrique::{Factory, Query};
+use frn_core::{
+    compute::{Hypervisor, Instance, Zone},
+    resourcemanager::{Organization, Project},
+};
+use frn_rpc::v1::compute::{CreateInstanceRequest, InstanceStatus};
+use hypervisor::instance::Status;
+use hypervisor::proxmox::fake::{FakeProxmox, WithFakeProxmox};
+use mock_server::MockServer;
+use tonic::Request;
+use workflow::execution::WorkflowExecutionId;
+use workflow::operations::Operation;
+use workflow::operations::create_hypervisor_instance::CreateHypervisorInstanceOp;
+
+mod common;
+
+const GIB: u64 = 1024 * 1024 * 1024;
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_provision_instance_workflow_rolls_back_its_operations(pool: sqlx::PgPool) {
+    let snippets = common::snippets_volume();
+    // SAFETY: this is the only test of the binary, so no other thread reads
+    // the environment while it is modified.
+    unsafe { std::env::set_var("PROXMOX_VOLUME_ABSOLUTE_PATH", &snippets) };
+
+    // Arrange the grpc server, and a hypervisor failing to create VMs
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let proxmox = FakeProxmox::new()
+        .with_node("pve-node1", 16, 64 * GIB)
+        .with_task_failure("qmcreate", "unable to create VM 100 - no space left");
+    let server = MockServer::new().await.with_fake_proxmox(&proxmox);
+
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await
+        .expect("could not create organization");
+    let zone = Zone::factory()
+        .create(&pool)
+        .await
+        .expect("could not create zone");
+    let hypervisor = Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
+        .zone_id(zone.id)
+        .organization_slug(organization.slug.clone())
+        .url(server.url())
+        .create(&pool)
+        .await
+        .expect("could not create hypervisor");
+    Project::factory()
+        .slug("test-project".to_owned())
+        .organization_slug(organization.slug.clone())
+        .create(&pool)
+        .await
+        .expect("could not create project");
+
+    // Act the creation of an instance, and the run of its provisioning
+    let request = Request::new(CreateInstanceRequest {
+        image: "debian-12-genericcloud-amd64-20241201-1948.qcow2".to_owned(),
+        cpu_cores: 2,
+        disk_bytes: 20 * GIB,
+        memory_bytes: 2 * GIB,
+        name: "web".to_owned(),
+        snippet: String::new(),
+        project_slug: "test-project".to_owned(),
+        zone_id: Some(zone.id.to_string()),
+        zero_trust_network_id: None,
+    })
+    .on_behalf_of(&api.service_account);
+    let response = api
+        .compute
+        .instances
+        .create(request)
+        .await
+        .expect("could not create instance")
+        .into_inner();
+    let instance = response
+        .instance
+        .expect("the response should hold the instance");
+    let provisioned = run_workflow(&pool, &response.execution_id).await;
+
+    // Assert the creation was accepted, then its provisioning undone, the
+    // instance kept as failed
+    assert_eq!(instance.status, InstanceStatus::Provisioning as i32);
+    assert!(provisioned.unwrap_err().contains("no space left"));
+    assert!(proxmox.vms().is_empty());
+    let id = instance.id.parse().unwrap();
+    let failed = Instance::find(&pool, id)
+        .await
+        .expect("the instance should be kept");
+    assert!(matches!(failed.status, Status::Failed));
+
+    // Act the creation of a VM once the hypervisor recovered, then its
+    // rollback
+    proxmox.heal_tasks("qmcreate");
+    let ctx = worker_context(&pool).await;
+    let operation = CreateHypervisorInstanceOp {
+        hypervisor_id: hypervisor.id,
+        project_slug: "test-project".to_owned(),
+        name: "web".to_owned(),
+        cores: 2,
+        disk_bytes: 20 * GIB,
+        disk_image: "debian-12-genericcloud-amd64-20241201-1948.qcow2".to_owned(),
+        memory_bytes: 2 * GIB,
+        snippet: String::new(),
+        distant_id: None,
+    }
+    .execute(ctx.clone(), WorkflowExecutionId::new())
+    .await
+    .expect("could not create the vm");
+    let created = proxmox.vms().len();
+    operation
+        .rollback(ctx, WorkflowExecutionId::new())
+        .await
+        .expect("could not roll the vm creation back");
+
+    // Assert the VM was created, then deleted
+    assert_eq!(created, 1);
+    assert!(proxmox.vms().is_empty());
+
+    // Act the retry of the provisioning
+    run_workflow(&pool, &response.execution_id)
+        .await
+        .expect("could not retry the provisioning");
+
+    // Assert the instance was provisioned on its retry
+    let provisioned = Instance::find(&pool, id)
+        .await
+        .expect("could not find the instance");
+    assert!(!provisioned.distant_id.is_empty());
+    assert_eq!(proxmox.vms().len(), 1);
+}
@@ -1,14 +1,19 @@
-use crate::common::{Api, OnBehalfOf};
-use fabrique::Factory;
-use frn_core::{compute::Zone, resourcemanager::Organization};
-use frn_rpc::v1::compute::RegisterHypervisorRequest;
-use tonic::Request;
+use crate::common::{Api, WithUser, non_admin_token, seed_admin_token};
+use fabrique::{Factory, Query};
+use frn_core::{
+    compute::{Hypervisor, Zone},
+    resourcemanager::Organization,
+};
+use frn_rpc::v1::compute::{HypervisorKind, RegisterHypervisorRequest, SnippetsTransport};
+use tonic::{Code, Request};
+use uuid::Uuid;
 
 mod common;
 
 #[sqlx::test(migrations = "../migrations")]
 async fn test_the_register_hypervisor_procedure_works(pool: sqlx::PgPool) {
     let mut api = Api::start(&pool).await.expect("count not start api");
+    let token = seed_admin_token(&pool, "admin@francenuage.fr").await;
     let zone = Zone::factory()
         .create(&pool)
         .await
@@ -26,9 +31,161 @@ async fn test_the_register_hypervisor_procedure_works(pool: sqlx::PgPool) {
         organization_slug: organization.slug.clone(),
         ..Default::default()
     })
-    .on_behalf_of(&api.service_account);
+    .with_user(&token);
     let result = api.compute.hypervisors.register(request).await;
 
     // Assert the result
     assert!(result.is_ok());
 }
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_register_hypervisor_procedure_encrypts_the_token(pool: sqlx::PgPool) {
+    let mut api = Api::start(&pool).await.expect("count not start api");
+    let token = seed_admin_token(&pool, "admin@francenuage.fr").await;
+    let zone = Zone::factory()
+        .create(&pool)
+        .await
+        .expect("could not create zone");
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await
+        .expect("could not create organization");
+
+    // Act the registration of a hypervisor
+    let request = Request::new(RegisterHypervisorRequest {
+        zone_id: zone.id.to_string(),
+        organization_slug: organization.slug.clone(),
+        authorization_token: "PVEAPIToken=root@pam!api=secret".to_owned(),
+        ..Default::default()
+    })
+    .with_user(&token);
+    api.compute
+        .hypervisors
+        .register(request)
+        .await
+        .expect("could not register hypervisor");
+
+    // Assert the token is only stored encrypted
+    let ciphertext: Vec<u8> = sqlx::query_scalar("SELECT token_ciphertext FROM hypervisors")
+        .fetch_one(&pool)
+        .await
+        .expect("could not read hypervisor");
+    assert!(!ciphertext.windows(6).any(|window| window == b"secret"));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_register_hypervisor_procedure_records_the_kind(pool: sqlx::PgPool) {
+    let mut api = Api::start(&pool).await.expect("count not start api");
+    let token = seed_admin_token(&pool, "admin@francenuage.fr").await;
+    let zone = Zone::factory()
+        .create(&pool)
+        .await
+        .expect("could not create zone");
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await
+        .expect("could not create organization");
+
+    // Act the registration of a KubeVirt hypervisor
+    let request = Request::new(RegisterHypervisorRequest {
+        zone_id: zone.id.to_string(),
+        organization_slug: organization.slug.clone(),
+        url: "https://kubernetes.example.com:6443".to_owned(),
+        kind: HypervisorKind::Kubevirt.into(),
+        ..Default::default()
+    })
+    .with_user(&token);
+    let hypervisor = api
+        .compute
+        .hypervisors
+        .register(request)
+        .await
+        .expect("could not register hypervisor")
+        .into_inner()
+        .hypervisor
+        .expect("the response should hold the hypervisor");
+
+    // Assert the kind is returned and recorded
+    assert_eq!(hypervisor.kind(), HypervisorKind::Kubevirt);
+    let recorded = Hypervisor::find(&pool, Uuid::parse_str(&hypervisor.id).unwrap())
+        .await
+        .expect("the hypervisor should be recorded");
+    assert_eq!(recorded.kind, hypervisor::HypervisorKind::KubeVirt);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_register_hypervisor_procedure_records_the_snippets_transport(pool: sqlx::PgPool) {
+    let mut api = Api::start(&pool).await.expect("count not start api");
+    let token = seed_admin_token(&pool, "admin@francenuage.fr").await;
+    let zone = Zone::factory()
+        .create(&pool)
+        .await
+        .expect("could not create zone");
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await
+        .expect("could not create organization");
+
+    // Act the registration of a hypervisor sharing no volume with the control
+    // plane
+    let request = Request::new(RegisterHypervisorRequest {
+        zone_id: zone.id.to_string(),
+        organization_slug: organization.slug.clone(),
+        snippets_transport: SnippetsTransport::Sftp.into(),
+        ..Default::default()
+    })
+    .with_user(&token);
+    let hypervisor = api
+        .compute
+        .hypervisors
+        .register(request)
+        .await
+        .expect("could not register hypervisor")
+        .into_inner()
+        .hypervisor
+        .expect("the response should hold the hypervisor");
+
+    // Assert the transport is returned and recorded
+    assert_eq!(hypervisor.snippets_transport(), SnippetsTransport::Sftp);
+    let recorded = Hypervisor::find(&pool, Uuid::parse_str(&hypervisor.id).unwrap())
+        .await
+        .expect("the hypervisor should be recorded");
+    assert_eq!(
+        recorded.snippets_transport,
+        hypervisor::SnippetsTransport::Sftp
+    );
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_register_hypervisor_procedure_is_restricted_to_admins(pool: sqlx::PgPool) {
+    let mut api = Api::start(&pool).await.expect("count not start api");
+    let zone = Zone::factory()
+        .create(&pool)
+        .await
+        .expect("could not create zone");
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await
+        .expect("could not create organization");
+
+    // Act the registration of a hypervisor by a member of the organization
+    let request = Request::new(RegisterHypervisorRequest {
+        zone_id: zone.id.to_string(),
+        organization_slug: organization.slug.clone(),
+        ..Default::default()
+    })
+    .with_user(&non_admin_token("member@francenuage.fr"));
+    let result = api.compute.hypervisors.register(request).await;
+
+    // Assert the result
+    assert_eq!(result.unwrap_err().code(), Code::PermissionDenied);
+    assert!(Hypervisor::all(&pool).await.unwrap().is_empty());
+}
@@ -0,0 +1,87 @@
+use crate::common::{Api, OnBehalfOf};
+use fabrique::{Factory, Query};
+use frn_core::{
+    compute::{
+        Hypervisor, Instance, InstanceSecurityGroup, SecurityGroup, SecurityGroupRule, Zone,
+    },
+    resourcemanager::{Organization, Project},
+};
+use frn_rpc::v1::compute::RemoveSecurityGroupRuleRequest;
+use hypervisor::instance::FirewallDirection;
+use tonic::Request;
+
+mod common;
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_remove_security_group_rule_procedure_works(pool: sqlx::PgPool) {
+    // Arrange the grpc server and a client
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let mock_url = api.mock_server.url();
+
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await
+        .expect("could not create organization");
+    let hypervisor = Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
+        .for_zone(Zone::factory())
+        .organization_slug(organization.slug.clone())
+        .url(mock_url)
+        .create(&pool)
+        .await
+        .expect("could not create hypervisor");
+    let project = Project::factory()
+        .slug("test-project".to_owned())
+        .organization_slug(organization.slug.clone())
+        .create(&pool)
+        .await
+        .expect("could not create project");
+    let instance = Instance::factory()
+        .hypervisor_id(hypervisor.id)
+        .project_slug(project.slug.clone())
+        .distant_id("100".into())
+        .zero_trust_network_id(None)
+        .create(&pool)
+        .await
+        .expect("could not create instance");
+    let security_group = SecurityGroup::factory()
+        .project_slug(project.slug.clone())
+        .name("web".to_owned())
+        .create(&pool)
+        .await
+        .expect("could not create security group");
+    let rule = SecurityGroupRule::factory()
+        .security_group_id(security_group.id)
+        .direction(FirewallDirection::Ingress)
+        .protocol(Some("tcp".to_owned()))
+        .port_range(Some("22".to_owned()))
+        .cidr(Some("10.0.0.0/8".to_owned()))
+        .create(&pool)
+        .await
+        .expect("could not create rule");
+    InstanceSecurityGroup::factory()
+        .instance_id(instance.id)
+        .security_group_id(security_group.id)
+        .create(&pool)
+        .await
+        .expect("could not attach security group");
+
+    // Act the request to the test_the_remove_security_group_rule_procedure_works
+    let request = Request::new(RemoveSecurityGroupRuleRequest {
+        security_group_id: security_group.id.to_string(),
+        rule_id: rule.id.to_string(),
+    })
+    .on_behalf_of(&api.service_account);
+    let response = api.compute.security_groups.remove_rule(request).await;
+
+    // Assert the rule was removed
+    assert!(response.is_ok());
+    let rules = SecurityGroupRule::query()
+        .select()
+        .get(&pool)
+        .await
+        .expect("could not list rules");
+    assert!(rules.is_empty());
+}
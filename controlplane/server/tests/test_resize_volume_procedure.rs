@@ -0,0 +1,125 @@
+use crate::common::{Api, OnBehalfOf};
+use fabrique::Factory;
+use frn_core::{
+    compute::{Hypervisor, Instance, Volume, Zone},
+    resourcemanager::{Organization, Project},
+};
+use frn_rpc::v1::compute::ResizeVolumeRequest;
+use tonic::{Code, Request};
+
+mod common;
+
+const GIB: u64 = 1024 * 1024 * 1024;
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_resize_volume_procedure_works(pool: sqlx::PgPool) {
+    // Arrange the grpc server and a client
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let mock_url = api.mock_server.url();
+
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await
+        .expect("could not create organization");
+    let hypervisor = Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
+        .for_zone(Zone::factory())
+        .organization_slug(organization.slug.clone())
+        .url(mock_url)
+        .create(&pool)
+        .await
+        .expect("could not create hypervisor");
+    let project = Project::factory()
+        .slug("test-project".to_owned())
+        .organization_slug(organization.slug.clone())
+        .create(&pool)
+        .await
+        .expect("could not create project");
+    let instance = Instance::factory()
+        .hypervisor_id(hypervisor.id)
+        .project_slug(project.slug.clone())
+        .distant_id("100".into())
+        .zero_trust_network_id(None)
+        .create(&pool)
+        .await
+        .expect("could not create instance");
+    let volume = Volume::factory()
+        .instance_id(instance.id)
+        .project_slug(project.slug.clone())
+        .distant_id("ceph-pool-nvme-01:vm-555-disk-1".to_owned())
+        .device(Some("scsi1".to_owned()))
+        .size_bytes((10 * GIB) as i64)
+        .create(&pool)
+        .await
+        .expect("could not create volume");
+
+    // Act the request to the test_the_resize_volume_procedure_works
+    let request = Request::new(ResizeVolumeRequest {
+        id: volume.id.to_string(),
+        size_bytes: 20 * GIB,
+    })
+    .on_behalf_of(&api.service_account);
+    let response = api.compute.volumes.resize(request).await;
+
+    // Assert the result
+    let volume = response.unwrap().into_inner().volume.unwrap();
+    assert_eq!(volume.size_bytes, 20 * GIB);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_resize_volume_procedure_refuses_to_shrink(pool: sqlx::PgPool) {
+    // Arrange the grpc server and a client
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let mock_url = api.mock_server.url();
+
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await
+        .expect("could not create organization");
+    let hypervisor = Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
+        .for_zone(Zone::factory())
+        .organization_slug(organization.slug.clone())
+        .url(mock_url)
+        .create(&pool)
+        .await
+        .expect("could not create hypervisor");
+    let project = Project::factory()
+        .slug("test-project".to_owned())
+        .organization_slug(organization.slug.clone())
+        .create(&pool)
+        .await
+        .expect("could not create project");
+    let instance = Instance::factory()
+        .hypervisor_id(hypervisor.id)
+        .project_slug(project.slug.clone())
+        .distant_id("100".into())
+        .zero_trust_network_id(None)
+        .create(&pool)
+        .await
+        .expect("could not create instance");
+    let volume = Volume::factory()
+        .instance_id(instance.id)
+        .project_slug(project.slug.clone())
+        .distant_id("ceph-pool-nvme-01:vm-555-disk-1".to_owned())
+        .device(Some("scsi1".to_owned()))
+        .size_bytes((10 * GIB) as i64)
+        .create(&pool)
+        .await
+        .expect("could not create volume");
+
+    // Act the request to the test_the_resize_volume_procedure_refuses_to_shrink
+    let request = Request::new(ResizeVolumeRequest {
+        id: volume.id.to_string(),
+        size_bytes: 5 * GIB,
+    })
+    .on_behalf_of(&api.service_account);
+    let response = api.compute.volumes.resize(request).await;
+
+    // Assert the result
+    assert_eq!(response.unwrap_err().code(), Code::InvalidArgument);
+}
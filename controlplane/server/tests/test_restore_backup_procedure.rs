@@ -0,0 +1,180 @@
+use crate::common::{Api, OnBehalfOf};
+use fabrique::Factory;
+use fabrique::Query;
+use frn_core::{
+    compute::{Hypervisor, Instance, Zone},
+    resourcemanager::{Organization, Project},
+};
+use frn_rpc::v1::compute::RestoreBackupRequest;
+use hypervisor::mock::BACKUP_OWNER;
+use infrastructure::{ZeroTrustNetwork, ZeroTrustNetworkType};
+use tonic::{Code, Request};
+
+mod common;
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_restore_backup_procedure_works(pool: sqlx::PgPool) {
+    // Arrange the grpc server and a client
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let mock_url = api.mock_server.url();
+
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await
+        .expect("could not create organization");
+    let hypervisor = Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
+        .for_zone(Zone::factory())
+        .organization_slug(organization.slug.clone())
+        .url(mock_url)
+        .create(&pool)
+        .await
+        .expect("could not create hypervisor");
+    let project = Project::factory()
+        .slug("test-project".to_owned())
+        .organization_slug(organization.slug.clone())
+        .create(&pool)
+        .await
+        .expect("could not create project");
+    let zero_trust_network = ZeroTrustNetwork::factory()
+        .organization_slug(organization.slug.clone())
+        .for_zero_trust_network_type(ZeroTrustNetworkType::factory())
+        .create(&pool)
+        .await
+        .expect("could not create zero trust network");
+    let instance = Instance::factory()
+        .id(BACKUP_OWNER)
+        .hypervisor_id(hypervisor.id)
+        .project_slug(project.slug.clone())
+        .distant_id("100".into())
+        .zero_trust_network_id(Some(zero_trust_network.id))
+        .missing_since(None)
+        .create(&pool)
+        .await
+        .expect("could not create instance");
+
+    // Act the request to the test_the_restore_backup_procedure_works
+    let request = Request::new(RestoreBackupRequest {
+        instance_id: instance.id.to_string(),
+        backup_id: "pbs:backup/vm/100/2025-03-08T18:00:00Z".to_owned(),
+        name: Some("restored".to_owned()),
+    })
+    .on_behalf_of(&api.service_account);
+    let response = api.compute.backups.restore(request).await;
+
+    // Assert the result, a new instance is recorded in the same project
+    assert!(response.is_ok());
+    let restored = response.unwrap().into_inner();
+    assert_ne!(restored.id, instance.id.to_string());
+    assert_eq!(restored.name, "restored");
+    assert_eq!(restored.project_slug, project.slug);
+
+    let id = restored.id.parse().unwrap();
+    let restored = Instance::find(&pool, id)
+        .await
+        .expect("could not find restored instance");
+    assert_eq!(restored.hypervisor_id, hypervisor.id);
+    assert_eq!(restored.project_slug, project.slug);
+    assert_eq!(restored.zero_trust_network_id, None);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_restore_backup_procedure_rejects_backups_of_other_instances(pool: sqlx::PgPool) {
+    // Arrange the grpc server and a client
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let mock_url = api.mock_server.url();
+
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await
+        .expect("could not create organization");
+    let hypervisor = Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
+        .for_zone(Zone::factory())
+        .organization_slug(organization.slug.clone())
+        .url(mock_url)
+        .create(&pool)
+        .await
+        .expect("could not create hypervisor");
+    let project = Project::factory()
+        .slug("test-project".to_owned())
+        .organization_slug(organization.slug.clone())
+        .create(&pool)
+        .await
+        .expect("could not create project");
+    let instance = Instance::factory()
+        .hypervisor_id(hypervisor.id)
+        .project_slug(project.slug.clone())
+        .distant_id("100".into())
+        .zero_trust_network_id(None)
+        .create(&pool)
+        .await
+        .expect("could not create instance");
+
+    // Act the request to the test_the_restore_backup_procedure_rejects_backups_of_other_instances
+    let request = Request::new(RestoreBackupRequest {
+        instance_id: instance.id.to_string(),
+        backup_id: "pbs:backup/vm/101/2025-03-08T18:00:00Z".to_owned(),
+        name: None,
+    })
+    .on_behalf_of(&api.service_account);
+    let response = api.compute.backups.restore(request).await;
+
+    // Assert the result, the instance reserved for the restore is removed
+    assert_eq!(response.unwrap_err().code(), Code::NotFound);
+    assert_eq!(Instance::all(&pool).await.unwrap().len(), 1);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_restore_backup_procedure_rejects_backups_of_the_previous_owner_of_the_vm(
+    pool: sqlx::PgPool,
+) {
+    // Arrange the grpc server and a client
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let mock_url = api.mock_server.url();
+
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await
+        .expect("could not create organization");
+    let hypervisor = Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
+        .for_zone(Zone::factory())
+        .organization_slug(organization.slug.clone())
+        .url(mock_url)
+        .create(&pool)
+        .await
+        .expect("could not create hypervisor");
+    let project = Project::factory()
+        .slug("test-project".to_owned())
+        .organization_slug(organization.slug.clone())
+        .create(&pool)
+        .await
+        .expect("could not create project");
+    let instance = Instance::factory()
+        .hypervisor_id(hypervisor.id)
+        .project_slug(project.slug.clone())
+        .distant_id("100".into())
+        .zero_trust_network_id(None)
+        .create(&pool)
+        .await
+        .expect("could not create instance");
+
+    // Act the request to the test_the_restore_backup_procedure_rejects_backups_of_the_previous_owner_of_the_vm
+    let request = Request::new(RestoreBackupRequest {
+        instance_id: instance.id.to_string(),
+        backup_id: "pbs:backup/vm/100/2025-03-08T18:00:00Z".to_owned(),
+        name: None,
+    })
+    .on_behalf_of(&api.service_account);
+    let response = api.compute.backups.restore(request).await;
+
+    // Assert the backup of the VM, taken for another instance, is out of reach
+    assert_eq!(response.unwrap_err().code(), Code::NotFound);
+}
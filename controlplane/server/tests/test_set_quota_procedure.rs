@@ -0,0 +1,106 @@
+use crate::common::{Api, WithUser, non_admin_token, seed_admin_token};
+use fabrique::{Factory, Query};
+use frn_core::resourcemanager::{Organization, Project, Quota};
+use frn_rpc::v1::resourcemanager::SetQuotaRequest;
+use tonic::{Code, Request};
+
+mod common;
+
+async fn seed_project(pool: &sqlx::PgPool) {
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(pool)
+        .await
+        .expect("could not create organization");
+    Project::factory()
+        .slug("test-project".to_owned())
+        .organization_slug(organization.slug.clone())
+        .create(pool)
+        .await
+        .expect("could not create project");
+}
+
+fn set_quota_request(max_instances: i64) -> SetQuotaRequest {
+    SetQuotaRequest {
+        organization_slug: "test-org".to_owned(),
+        project_slug: Some("test-project".to_owned()),
+        max_cpu_cores: Some(8),
+        max_memory_bytes: None,
+        max_disk_bytes: None,
+        max_instances: Some(max_instances),
+        max_managed_service_instances: None,
+    }
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_set_quota_procedure_works(pool: sqlx::PgPool) {
+    // Arrange the grpc server and a platform admin
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    seed_project(&pool).await;
+    let token = seed_admin_token(&pool, "admin@example.com").await;
+
+    // Act two requests to the set quota procedure
+    let quotas = &mut api.resourcemanager.quotas;
+    for max_instances in [4, 2] {
+        quotas
+            .set(Request::new(set_quota_request(max_instances)).with_user(&token))
+            .await
+            .expect("could not set quota");
+    }
+
+    // Assert the second request replaced the quota of the first
+    let quotas = Quota::all(&pool).await.unwrap();
+    assert_eq!(quotas.len(), 1);
+    assert_eq!(quotas[0].project_slug.as_deref(), Some("test-project"));
+    assert_eq!(quotas[0].max_cpu_cores, Some(8));
+    assert_eq!(quotas[0].max_instances, Some(2));
+    assert_eq!(quotas[0].max_memory_bytes, None);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_set_quota_procedure_is_restricted_to_admins(pool: sqlx::PgPool) {
+    // Arrange the grpc server and a regular user
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    seed_project(&pool).await;
+    let token = non_admin_token("user@example.com");
+
+    // Act the request to the set quota procedure
+    let response = api
+        .resourcemanager
+        .quotas
+        .set(Request::new(set_quota_request(100)).with_user(&token))
+        .await;
+
+    // Assert the result
+    assert_eq!(response.unwrap_err().code(), Code::PermissionDenied);
+    assert!(Quota::all(&pool).await.unwrap().is_empty());
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_set_quota_procedure_rejects_projects_of_other_organizations(pool: sqlx::PgPool) {
+    // Arrange the grpc server and a project of another organization
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    seed_project(&pool).await;
+    Organization::factory()
+        .slug("other-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await
+        .expect("could not create organization");
+    let token = seed_admin_token(&pool, "admin@example.com").await;
+
+    // Act the request to the set quota procedure
+    let request = SetQuotaRequest {
+        organization_slug: "other-org".to_owned(),
+        ..set_quota_request(1)
+    };
+    let response = api
+        .resourcemanager
+        .quotas
+        .set(Request::new(request).with_user(&token))
+        .await;
+
+    // Assert the result
+    assert_eq!(response.unwrap_err().code(), Code::NotFound);
+}
@@ -22,6 +22,7 @@ async fn test_the_start_instance_procedure_works(pool: sqlx::PgPool) {
         .await
         .expect("could not create organization");
     let hypervisor = Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
         .for_zone(Zone::factory())
         .organization_slug(organization.slug.clone())
         .url(mock_url)
@@ -99,8 +99,8 @@ impl WorkflowScheduler<DeployManagedServiceParams> for NoopWorkflowScheduler {
         &self,
         _conn: &mut PgConnection,
         _params: DeployManagedServiceParams,
-    ) -> Result<(), String> {
-        Ok(())
+    ) -> Result<Uuid, String> {
+        Ok(Uuid::nil())
     }
 }
 
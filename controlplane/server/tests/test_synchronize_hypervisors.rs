@@ -0,0 +1,106 @@
+//! Tests for the synchronization of the hypervisors, isolated from each
+//! other's failures and led by a single synchronizer replica.
+
+use chrono::Duration;
+use fabrique::Factory;
+use frn_core::App;
+use frn_core::compute::{Hypervisor, Zone};
+use frn_core::resourcemanager::{DEFAULT_PROJECT_NAME, Organization, Project};
+use hypervisor::instance::Status;
+use hypervisor::proxmox::fake::{FakeProxmox, WithFakeProxmox};
+use mock_server::MockServer;
+use synchronizer::leader::LeaderElection;
+use synchronizer::{Backoff, synchronize};
+
+const GIB: u64 = 1024 * 1024 * 1024;
+const UNREACHABLE_URL: &str = "http://127.0.0.1:1";
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_synchronize_isolates_and_backs_off_unreachable_hypervisors(
+    pool: sqlx::PgPool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Arrange an unreachable hypervisor next to one listing a VM
+    let proxmox = FakeProxmox::new()
+        .with_node("pve-node1", 16, 64 * GIB)
+        .with_vm("pve-node1", 100, "web", Status::Stopped);
+    let server = MockServer::new().await.with_fake_proxmox(&proxmox);
+    let mut app = App::test(pool.clone()).await?;
+
+    let organization = Organization::factory()
+        .slug("root-org".to_owned())
+        .name(app.config.root_organization.name.clone())
+        .parent_slug(None)
+        .create(&pool)
+        .await?;
+    Project::factory()
+        .slug("root-org-default".to_owned())
+        .name(DEFAULT_PROJECT_NAME.to_owned())
+        .organization_slug(organization.slug.clone())
+        .create(&pool)
+        .await?;
+    let unreachable = Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
+        .for_zone(Zone::factory())
+        .organization_slug(organization.slug.clone())
+        .url(UNREACHABLE_URL.to_owned())
+        .create(&pool)
+        .await?;
+    Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
+        .for_zone(Zone::factory())
+        .organization_slug(organization.slug.clone())
+        .url(server.url())
+        .create(&pool)
+        .await?;
+    let mut backoff = Backoff::new(
+        std::time::Duration::from_secs(60),
+        std::time::Duration::from_secs(600),
+    );
+
+    // Act a synchronization pass
+    let report = synchronize(&mut app, Duration::minutes(10), &mut backoff).await?;
+
+    // Assert the reachable hypervisor was synchronized despite the failure
+    assert_eq!(report.created.len(), 1);
+    assert_eq!(report.failed, vec![unreachable.id]);
+    assert!(!backoff.is_ready(unreachable.id));
+
+    // Act a pass while the unreachable hypervisor backs off
+    let report = synchronize(&mut app, Duration::minutes(10), &mut backoff).await?;
+
+    // Assert it was not attempted again
+    assert!(report.failed.is_empty());
+    assert_eq!(report.updated.len(), 1);
+
+    Ok(())
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_leader_election_elects_a_single_replica(
+    pool: sqlx::PgPool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Arrange two replicas sharing the database
+    let mut first = LeaderElection::new(pool.clone());
+    let mut second = LeaderElection::new(pool.clone());
+
+    // Act and assert only the first replica leads, and keeps leading while
+    // the second one retries
+    assert!(first.is_leader().await?);
+    assert!(!second.is_leader().await?);
+    assert!(!second.is_leader().await?);
+    assert!(first.is_leader().await?);
+
+    // Act and assert the second replica takes over once the first stops
+    drop(first);
+    let mut leads = false;
+    for _ in 0..50 {
+        if second.is_leader().await? {
+            leads = true;
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    assert!(leads);
+
+    Ok(())
+}
@@ -0,0 +1,192 @@
+//! Tests for the reconciliation of the recorded instances with the ones of
+//! their hypervisor, run on each synchronizer pass.
+
+use chrono::{Duration, Utc};
+use fabrique::{Factory, Query};
+use frn_core::App;
+use frn_core::authorization::{Authorize, Relation, Relationship, Resource};
+use frn_core::compute::{Hypervisor, Instance, Instances, Zone};
+use frn_core::resourcemanager::{DEFAULT_PROJECT_NAME, Organization, Project};
+use hypervisor::instance::Status;
+use hypervisor::proxmox::fake::{FakeProxmox, WithFakeProxmox};
+use mock_server::MockServer;
+use spicedb::SpiceDB;
+use synchronizer::{Backoff, synchronize};
+
+const GIB: u64 = 1024 * 1024 * 1024;
+
+/// Seeds an instance of `project` recorded on `hypervisor`.
+async fn seed_instance(
+    pool: &sqlx::PgPool,
+    hypervisor: &Hypervisor,
+    project: &Project,
+    distant_id: &str,
+    status: Status,
+) -> Instance {
+    Instance::factory()
+        .hypervisor_id(hypervisor.id)
+        .project_slug(project.slug.clone())
+        .distant_id(distant_id.to_owned())
+        .zero_trust_network_id(None)
+        .status(status)
+        .updated_at(Utc::now())
+        .missing_since(None)
+        .create(pool)
+        .await
+        .expect("could not create instance")
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_synchronize_reconciles_instances_missing_from_their_hypervisor(
+    pool: sqlx::PgPool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Arrange a hypervisor listing two VMs, only the first of them recorded
+    // and found missing earlier, next to instances it does not list anymore
+    let proxmox = FakeProxmox::new()
+        .with_node("pve-node1", 16, 64 * GIB)
+        .with_vm("pve-node1", 100, "web", Status::Stopped)
+        .with_vm("pve-node1", 101, "db", Status::Stopped);
+    let server = MockServer::new().await.with_fake_proxmox(&proxmox);
+    let mut app = App::test(pool.clone()).await?;
+
+    let organization = Organization::factory()
+        .slug("root-org".to_owned())
+        .name(app.config.root_organization.name.clone())
+        .parent_slug(None)
+        .create(&pool)
+        .await?;
+    let project = Project::factory()
+        .slug("root-org-default".to_owned())
+        .name(DEFAULT_PROJECT_NAME.to_owned())
+        .organization_slug(organization.slug.clone())
+        .create(&pool)
+        .await?;
+    let hypervisor = Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
+        .for_zone(Zone::factory())
+        .organization_slug(organization.slug.clone())
+        .url(server.url())
+        .create(&pool)
+        .await?;
+    let listed = Instance::factory()
+        .hypervisor_id(hypervisor.id)
+        .project_slug(project.slug.clone())
+        .distant_id("100".to_owned())
+        .zero_trust_network_id(None)
+        .status(Status::Missing)
+        .missing_since(Some(Utc::now() - Duration::minutes(5)))
+        .create(&pool)
+        .await?;
+    let gone = seed_instance(&pool, &hypervisor, &project, "102", Status::Running).await;
+    let provisioning = seed_instance(&pool, &hypervisor, &project, "", Status::Provisioning).await;
+    let vanished = Instance::factory()
+        .hypervisor_id(hypervisor.id)
+        .project_slug(project.slug.clone())
+        .distant_id("103".to_owned())
+        .zero_trust_network_id(None)
+        .status(Status::Missing)
+        .updated_at(Utc::now())
+        .missing_since(Some(Utc::now() - Duration::hours(1)))
+        .create(&pool)
+        .await?;
+
+    let mut backoff = Backoff::new(
+        std::time::Duration::from_secs(60),
+        std::time::Duration::from_secs(600),
+    );
+
+    // Act a synchronization pass
+    let report = synchronize(&mut app, Duration::minutes(10), &mut backoff).await?;
+
+    // Assert the discovered VM was recorded, the instance listed again no
+    // longer missing, the instance gone from the hypervisor marked missing,
+    // and the one missing for too long deleted despite its recent update
+    assert_eq!(report.created.len(), 1);
+    assert_eq!(report.updated, vec![listed.id]);
+    assert_eq!(report.missing, vec![gone.id]);
+    assert_eq!(report.vanished, vec![vanished.id]);
+    let listed = Instance::find(&pool, listed.id).await?;
+    assert!(matches!(listed.status, Status::Stopped));
+    assert!(listed.missing_since.is_none());
+    let gone = Instance::find(&pool, gone.id).await?;
+    assert!(matches!(gone.status, Status::Missing));
+    assert!(gone.missing_since.is_some());
+    let provisioning = Instance::find(&pool, provisioning.id).await?;
+    assert!(matches!(provisioning.status, Status::Provisioning));
+    assert!(Instance::find(&pool, vanished.id).await.is_err());
+
+    // Act a pass once the grace period elapsed
+    let report = synchronize(&mut app, Duration::zero(), &mut backoff).await?;
+
+    // Assert the missing instance was deleted, and nothing else
+    assert!(report.created.is_empty() && report.missing.is_empty());
+    assert_eq!(report.vanished, vec![gone.id]);
+    assert_eq!(Instance::all(&pool).await?.len(), 3);
+
+    Ok(())
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_provisioning_replaces_the_instance_recorded_by_the_synchronizer(
+    pool: sqlx::PgPool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Arrange an instance awaiting its provisioning, and the record of its VM
+    // the synchronizer made first in the default project
+    let (mut spicedb, store) = SpiceDB::recording().await;
+    let app = App::test(pool.clone()).await?;
+    let instances = Instances::new(
+        spicedb.clone(),
+        pool.clone(),
+        app.config.hypervisor_resolver.clone(),
+    );
+
+    let organization = Organization::factory()
+        .slug("root-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await?;
+    let default_project = Project::factory()
+        .slug("root-org-default".to_owned())
+        .name(DEFAULT_PROJECT_NAME.to_owned())
+        .organization_slug(organization.slug.clone())
+        .create(&pool)
+        .await?;
+    let project = Project::factory()
+        .slug("root-org-web".to_owned())
+        .organization_slug(organization.slug.clone())
+        .create(&pool)
+        .await?;
+    let hypervisor = Hypervisor::factory()
+        .for_zone(Zone::factory())
+        .organization_slug(organization.slug.clone())
+        .create(&pool)
+        .await?;
+    let provisioning = seed_instance(&pool, &hypervisor, &project, "", Status::Provisioning).await;
+    let synchronized =
+        seed_instance(&pool, &hypervisor, &default_project, "100", Status::Running).await;
+    let relationship = Relationship::new(
+        &Project::some(default_project.slug.clone()),
+        Relation::Parent,
+        &synchronized,
+    );
+    spicedb.write_relationship(&relationship).await?;
+
+    // Act the completion of the provisioning
+    instances
+        .complete_provisioning(provisioning.id, "100")
+        .await?;
+
+    // Assert the record of the synchronizer was replaced, along with its
+    // relationship to the default project
+    assert!(Instance::find(&pool, synchronized.id).await.is_err());
+    let provisioned = Instance::find(&pool, provisioning.id).await?;
+    assert_eq!(provisioned.distant_id, "100");
+    assert!(
+        !store
+            .lock()
+            .expect("store poisoned")
+            .contains(&relationship.to_string())
+    );
+
+    Ok(())
+}
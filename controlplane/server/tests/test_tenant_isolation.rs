@@ -0,0 +1,232 @@
+//! Tests for the isolation of the tenants of the platform.
+//!
+//! Each tenant is an organization whose members only list the instances and
+//! hypervisors the authorization backend relates them to, while zones are
+//! visible to everyone. Drives the compute services against a real database
+//! and a recording SpiceDB, whose lookups follow the relationships written by
+//! the services.
+
+use fabrique::{Factory, Query};
+use frn_core::authorization::{Relation, Relationship, Resource};
+use frn_core::compute::{
+    Hypervisor, HypervisorCreateRequest, Hypervisors, Instance, Instances, Zone, ZoneCreateRequest,
+    Zones,
+};
+use frn_core::identity::{User, Users};
+use frn_core::resourcemanager::{Organizations, Project};
+use frn_core::{Config, Error};
+use spicedb::SpiceDB;
+use sqlx::PgPool;
+
+/// The services under test, sharing a recording SpiceDB.
+struct Fixture {
+    auth: SpiceDB,
+    hypervisors: Hypervisors<SpiceDB>,
+    instances: Instances<SpiceDB>,
+    organizations: Organizations<SpiceDB>,
+    zones: Zones<SpiceDB>,
+    admin: User,
+}
+
+impl Fixture {
+    async fn new(pool: &PgPool) -> Self {
+        let (auth, _) = SpiceDB::recording().await;
+        let resolver = Config::test().hypervisor_resolver;
+        let admin = User::factory()
+            .is_admin(true)
+            .create(pool)
+            .await
+            .expect("could not create admin");
+        Users::new(auth.clone(), pool.clone())
+            .relate_admins()
+            .await
+            .expect("could not relate admins");
+
+        Self {
+            hypervisors: Hypervisors::new(auth.clone(), pool.clone(), resolver.clone()),
+            instances: Instances::new(auth.clone(), pool.clone(), resolver),
+            organizations: Organizations::new(auth.clone(), pool.clone()),
+            zones: Zones::new(auth.clone(), pool.clone()),
+            auth,
+            admin,
+        }
+    }
+
+    /// Creates an organization with a single member, and an instance in its
+    /// default project running on a hypervisor of its own.
+    async fn tenant(&mut self, pool: &PgPool, zone: &Zone, name: &str) -> (User, Instance) {
+        let organization = self
+            .organizations
+            .create_organization(pool, &self.admin, name.to_owned(), None)
+            .await
+            .expect("could not create organization");
+        let member = User::factory()
+            .is_admin(false)
+            .create(pool)
+            .await
+            .expect("could not create user");
+        self.organizations
+            .add_user(&organization, &member)
+            .await
+            .expect("could not add user");
+
+        let hypervisor = self
+            .hypervisors
+            .create(
+                &self.admin,
+                HypervisorCreateRequest {
+                    authorization_token: "PVEAPIToken=root@pam!api=secret".to_owned(),
+                    kind: hypervisor::HypervisorKind::Proxmox,
+                    organization_slug: organization.slug.clone(),
+                    storage_name: "local-lvm".to_owned(),
+                    snippets_transport: hypervisor::SnippetsTransport::Volume,
+                    url: "https://pve.test".to_owned(),
+                    zone_id: zone.id,
+                },
+            )
+            .await
+            .expect("could not create hypervisor");
+        let project = Project::query()
+            .select()
+            .r#where(Project::ORGANIZATION_SLUG, "=", organization.slug.clone())
+            .first_or_fail(pool)
+            .await
+            .expect("could not find default project");
+
+        // Instances are recorded by the synchronizer, which relates them to
+        // their project the same way.
+        let instance = Instance::factory()
+            .hypervisor_id(hypervisor.id)
+            .project_slug(project.slug.clone())
+            .zero_trust_network_id(None)
+            .create(pool)
+            .await
+            .expect("could not create instance");
+        self.auth
+            .write_relationship(
+                Relationship::new(
+                    &Project::some(project.slug.clone()),
+                    Relation::Parent,
+                    &instance,
+                )
+                .into(),
+            )
+            .await
+            .expect("could not relate instance");
+
+        (member, instance)
+    }
+}
+
+/// Members of an organization only list its instances.
+#[sqlx::test(migrations = "../migrations")]
+async fn test_tenants_only_list_their_instances(pool: PgPool) {
+    let mut fixture = Fixture::new(&pool).await;
+    let zone = Zone::factory()
+        .create(&pool)
+        .await
+        .expect("could not create zone");
+    let (alice, alice_instance) = fixture.tenant(&pool, &zone, "Acme").await;
+    let (bob, bob_instance) = fixture.tenant(&pool, &zone, "Globex").await;
+
+    let ids = |instances: Vec<Instance>| instances.iter().map(|i| i.id).collect::<Vec<_>>();
+    let listed = fixture.instances.list(&alice).await.unwrap();
+    assert_eq!(ids(listed), [alice_instance.id]);
+    let listed = fixture.instances.list(&bob).await.unwrap();
+    assert_eq!(ids(listed), [bob_instance.id]);
+
+    // Platform admins list the whole fleet
+    assert_eq!(
+        fixture.instances.list(&fixture.admin).await.unwrap().len(),
+        2
+    );
+}
+
+/// Members of an organization only list its hypervisors.
+#[sqlx::test(migrations = "../migrations")]
+async fn test_tenants_only_list_their_hypervisors(pool: PgPool) {
+    let mut fixture = Fixture::new(&pool).await;
+    let zone = Zone::factory()
+        .create(&pool)
+        .await
+        .expect("could not create zone");
+    let (alice, alice_instance) = fixture.tenant(&pool, &zone, "Acme").await;
+    let (bob, bob_instance) = fixture.tenant(&pool, &zone, "Globex").await;
+
+    let ids = |hypervisors: Vec<Hypervisor>| hypervisors.iter().map(|h| h.id).collect::<Vec<_>>();
+    let listed = fixture.hypervisors.list(&alice).await.unwrap();
+    assert_eq!(ids(listed), [alice_instance.hypervisor_id]);
+    let listed = fixture.hypervisors.list(&bob).await.unwrap();
+    assert_eq!(ids(listed), [bob_instance.hypervisor_id]);
+
+    assert_eq!(
+        fixture
+            .hypervisors
+            .list(&fixture.admin)
+            .await
+            .unwrap()
+            .len(),
+        2
+    );
+}
+
+/// Zones are listed by every tenant, but only platform admins create them.
+#[sqlx::test(migrations = "../migrations")]
+async fn test_zones_are_shared_and_created_by_admins(pool: PgPool) {
+    let mut fixture = Fixture::new(&pool).await;
+    let zone = fixture
+        .zones
+        .create(
+            &fixture.admin,
+            ZoneCreateRequest {
+                name: "paris-1".to_owned(),
+            },
+        )
+        .await
+        .expect("could not create zone");
+    let (alice, _) = fixture.tenant(&pool, &zone, "Acme").await;
+    let (bob, _) = fixture.tenant(&pool, &zone, "Globex").await;
+
+    assert_eq!(fixture.zones.list(&alice).await.unwrap(), [zone]);
+    assert_eq!(fixture.zones.list(&bob).await.unwrap().len(), 1);
+
+    let result = fixture
+        .zones
+        .create(
+            &alice,
+            ZoneCreateRequest {
+                name: "rogue".to_owned(),
+            },
+        )
+        .await;
+    assert!(matches!(result, Err(Error::Forbidden)));
+}
+
+/// Platform admins are recognized through the authorization backend, the
+/// admins it does not relate to the platform being denied its permissions.
+#[sqlx::test(migrations = "../migrations")]
+async fn test_platform_permissions_are_checked_against_the_authorization_backend(pool: PgPool) {
+    let mut fixture = Fixture::new(&pool).await;
+    let unrelated = User::factory()
+        .is_admin(true)
+        .create(&pool)
+        .await
+        .expect("could not create admin");
+    let request = || ZoneCreateRequest {
+        name: "paris-1".to_owned(),
+    };
+
+    let result = fixture.zones.create(&unrelated, request()).await;
+    assert!(matches!(result, Err(Error::Forbidden)));
+
+    Users::new(fixture.auth.clone(), pool.clone())
+        .relate_admins()
+        .await
+        .expect("could not relate admins");
+    let zone = fixture
+        .zones
+        .create(&unrelated, request())
+        .await
+        .expect("could not create zone");
+    assert_eq!(zone.name, "paris-1");
+}
@@ -0,0 +1,140 @@
+use crate::common::{Api, OnBehalfOf};
+use fabrique::Factory;
+use fabrique::Query;
+use frn_core::{
+    compute::{BackupPolicy, Hypervisor, Instance, Zone},
+    resourcemanager::{Organization, Project},
+};
+use frn_rpc::v1::compute::UpdateBackupPolicyRequest;
+use tonic::Request;
+
+mod common;
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_update_backup_policy_procedure_creates_the_policy(pool: sqlx::PgPool) {
+    // Arrange the grpc server and a client
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let mock_url = api.mock_server.url();
+
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await
+        .expect("could not create organization");
+    let hypervisor = Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
+        .for_zone(Zone::factory())
+        .organization_slug(organization.slug.clone())
+        .url(mock_url)
+        .create(&pool)
+        .await
+        .expect("could not create hypervisor");
+    let project = Project::factory()
+        .slug("test-project".to_owned())
+        .organization_slug(organization.slug.clone())
+        .create(&pool)
+        .await
+        .expect("could not create project");
+    let instance = Instance::factory()
+        .hypervisor_id(hypervisor.id)
+        .project_slug(project.slug.clone())
+        .distant_id("100".into())
+        .zero_trust_network_id(None)
+        .create(&pool)
+        .await
+        .expect("could not create instance");
+
+    // Act the request to the test_the_update_backup_policy_procedure_creates_the_policy
+    let request = Request::new(UpdateBackupPolicyRequest {
+        instance_id: instance.id.to_string(),
+        schedule: "sun 02:00".to_owned(),
+        keep_last: 0,
+        keep_daily: 7,
+        keep_weekly: 4,
+        keep_monthly: 0,
+    })
+    .on_behalf_of(&api.service_account);
+    let response = api.compute.backups.update_policy(request).await;
+
+    // Assert the result
+    assert!(response.is_ok());
+    let policies = BackupPolicy::query()
+        .select()
+        .r#where(BackupPolicy::INSTANCE_ID, "=", instance.id)
+        .get(&pool)
+        .await
+        .expect("could not list backup policies");
+    assert_eq!(policies.len(), 1);
+    assert_eq!(policies[0].schedule, "sun 02:00");
+    assert_eq!(policies[0].keep_daily, 7);
+    assert_eq!(policies[0].keep_weekly, 4);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_update_backup_policy_procedure_updates_the_policy(pool: sqlx::PgPool) {
+    // Arrange the grpc server and a client
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let mock_url = api.mock_server.url();
+
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await
+        .expect("could not create organization");
+    let hypervisor = Hypervisor::factory()
+        .authorization_token("PVEAPIToken=root@pam!api=secret")
+        .for_zone(Zone::factory())
+        .organization_slug(organization.slug.clone())
+        .url(mock_url)
+        .create(&pool)
+        .await
+        .expect("could not create hypervisor");
+    let project = Project::factory()
+        .slug("test-project".to_owned())
+        .organization_slug(organization.slug.clone())
+        .create(&pool)
+        .await
+        .expect("could not create project");
+    let instance = Instance::factory()
+        .hypervisor_id(hypervisor.id)
+        .project_slug(project.slug.clone())
+        .distant_id("100".into())
+        .zero_trust_network_id(None)
+        .create(&pool)
+        .await
+        .expect("could not create instance");
+    let policy = BackupPolicy::factory()
+        .instance_id(instance.id)
+        .schedule("daily".to_owned())
+        .keep_last(3)
+        .keep_daily(7)
+        .keep_weekly(0)
+        .keep_monthly(0)
+        .create(&pool)
+        .await
+        .expect("could not create backup policy");
+
+    // Act the request to the test_the_update_backup_policy_procedure_updates_the_policy
+    let request = Request::new(UpdateBackupPolicyRequest {
+        instance_id: instance.id.to_string(),
+        schedule: "weekly".to_owned(),
+        keep_last: 5,
+        keep_daily: 0,
+        keep_weekly: 0,
+        keep_monthly: 12,
+    })
+    .on_behalf_of(&api.service_account);
+    let response = api.compute.backups.update_policy(request).await;
+
+    // Assert the result, the existing policy is updated in place
+    assert!(response.is_ok());
+    let updated = BackupPolicy::find(&pool, policy.id)
+        .await
+        .expect("could not find backup policy");
+    assert_eq!(updated.schedule, "weekly");
+    assert_eq!(updated.keep_last, 5);
+    assert_eq!(updated.keep_daily, 0);
+    assert_eq!(updated.keep_monthly, 12);
+}
@@ -1,6 +1,6 @@
 use crate::common::{Api, OnBehalfOf};
 use fabrique::{Factory, Query};
-use frn_core::compute::{Hypervisor, Instance, Zone};
+use frn_core::compute::{Hypervisor, Instance, Volume, Zone};
 use frn_core::resourcemanager::{Organization, Project};
 use frn_rpc::v1::compute::UpdateInstanceRequest;
 use tonic::Request;
@@ -42,6 +42,7 @@ async fn test_the_update_instance_procedure_can_change_project(pool: sqlx::PgPoo
     let instance = Instance::factory()
         .for_hypervisor(
             Hypervisor::factory()
+                .authorization_token("PVEAPIToken=root@pam!api=secret")
                 .for_zone(Zone::factory())
                 .organization_slug(organization.slug.clone())
                 .url(mock_url),
@@ -53,6 +54,12 @@ async fn test_the_update_instance_procedure_can_change_project(pool: sqlx::PgPoo
         .create(&pool)
         .await
         .expect("could not create instance");
+    let volume = Volume::factory()
+        .instance_id(instance.id)
+        .project_slug(project_a.slug.clone())
+        .create(&pool)
+        .await
+        .expect("could not create volume");
 
     let original_project_slug = instance.project_slug.clone();
 
@@ -61,6 +68,8 @@ async fn test_the_update_instance_procedure_can_change_project(pool: sqlx::PgPoo
         id: instance.id.to_string(),
         name: None,
         project_slug: Some(project_b.slug.clone()),
+        cpu_cores: None,
+        memory_bytes: None,
     })
     .on_behalf_of(&api.service_account);
 
@@ -82,6 +91,10 @@ async fn test_the_update_instance_procedure_can_change_project(pool: sqlx::PgPoo
         .await
         .expect("could not find instance");
     assert_eq!(db_instance.project_slug, project_b.slug);
+    let db_volume = Volume::find(&pool, volume.id)
+        .await
+        .expect("could not find volume");
+    assert_eq!(db_volume.project_slug, project_b.slug);
 }
 
 #[sqlx::test(migrations = "../migrations")]
@@ -109,6 +122,7 @@ async fn test_the_update_instance_procedure_can_change_name(pool: sqlx::PgPool)
     let instance = Instance::factory()
         .for_hypervisor(
             Hypervisor::factory()
+                .authorization_token("PVEAPIToken=root@pam!api=secret")
                 .for_zone(Zone::factory())
                 .organization_slug(organization.slug.clone())
                 .url(mock_url),
@@ -128,6 +142,8 @@ async fn test_the_update_instance_procedure_can_change_name(pool: sqlx::PgPool)
         id: instance.id.to_string(),
         name: Some("new-name".to_string()),
         project_slug: None,
+        cpu_cores: None,
+        memory_bytes: None,
     })
     .on_behalf_of(&api.service_account);
 
@@ -148,3 +164,138 @@ async fn test_the_update_instance_procedure_can_change_name(pool: sqlx::PgPool)
         .expect("could not find instance");
     assert_eq!(db_instance.name, "new-name");
 }
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_update_instance_procedure_can_resize(pool: sqlx::PgPool) {
+    // Arrange a test api and the required data
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let mock_url = api.mock_server.url();
+
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await
+        .expect("could not create organization");
+
+    let project = Project::factory()
+        .slug("test-project".to_owned())
+        .organization_slug(organization.slug.clone())
+        .name("test-project".into())
+        .create(&pool)
+        .await
+        .expect("could not create project");
+
+    let instance = Instance::factory()
+        .for_hypervisor(
+            Hypervisor::factory()
+                .authorization_token("PVEAPIToken=root@pam!api=secret")
+                .for_zone(Zone::factory())
+                .organization_slug(organization.slug.clone())
+                .url(mock_url),
+        )
+        .project_slug(project.slug)
+        .name("test-instance".into())
+        .distant_id("100".into())
+        .max_cpu_cores(1)
+        .max_memory_bytes(1073741824)
+        .zero_trust_network_id(None)
+        .create(&pool)
+        .await
+        .expect("could not create instance");
+
+    // Act: Resize the instance
+    let request = Request::new(UpdateInstanceRequest {
+        id: instance.id.to_string(),
+        name: None,
+        project_slug: None,
+        cpu_cores: Some(4),
+        memory_bytes: Some(8589934592),
+    })
+    .on_behalf_of(&api.service_account);
+
+    let result = api.compute.instances.update(request).await;
+
+    // Assert the result
+    assert!(result.is_ok(), "Update should succeed: {:?}", result.err());
+
+    let response = result.unwrap().into_inner();
+    let updated_instance = response.instance.expect("Response should contain instance");
+
+    assert_eq!(updated_instance.max_cpu_cores, 4);
+    assert_eq!(updated_instance.max_memory_bytes, 8589934592);
+    assert_eq!(updated_instance.name, "test-instance");
+
+    // Verify in database
+    let db_instance = Instance::find(&pool, instance.id)
+        .await
+        .expect("could not find instance");
+    assert_eq!(db_instance.max_cpu_cores, 4);
+    assert_eq!(db_instance.max_memory_bytes, 8589934592);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_update_instance_procedure_rejects_memory_not_in_whole_mebibytes(
+    pool: sqlx::PgPool,
+) {
+    // Arrange a test api and the required data
+    let mut api = Api::start(&pool).await.expect("could not start api");
+    let mock_url = api.mock_server.url();
+
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await
+        .expect("could not create organization");
+
+    let project = Project::factory()
+        .slug("test-project".to_owned())
+        .organization_slug(organization.slug.clone())
+        .name("test-project".into())
+        .create(&pool)
+        .await
+        .expect("could not create project");
+
+    let instance = Instance::factory()
+        .for_hypervisor(
+            Hypervisor::factory()
+                .authorization_token("PVEAPIToken=root@pam!api=secret")
+                .for_zone(Zone::factory())
+                .organization_slug(organization.slug.clone())
+                .url(mock_url),
+        )
+        .project_slug(project.slug)
+        .name("test-instance".into())
+        .distant_id("100".into())
+        .max_cpu_cores(1)
+        .max_memory_bytes(1073741824)
+        .zero_trust_network_id(None)
+        .create(&pool)
+        .await
+        .expect("could not create instance");
+
+    // Act: Resize the instance to a memory size the hypervisor would round
+    let mut results = Vec::new();
+    for memory_bytes in [0, 1073741824 + 512] {
+        let request = Request::new(UpdateInstanceRequest {
+            id: instance.id.to_string(),
+            name: None,
+            project_slug: None,
+            cpu_cores: None,
+            memory_bytes: Some(memory_bytes),
+        })
+        .on_behalf_of(&api.service_account);
+        results.push(api.compute.instances.update(request).await);
+    }
+
+    // Assert both were rejected, and the instance kept its shape
+    for result in results {
+        let status = result.expect_err("the resize should be rejected");
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    }
+    let db_instance = Instance::find(&pool, instance.id)
+        .await
+        .expect("could not find instance");
+    assert_eq!(db_instance.max_memory_bytes, 1073741824);
+}
@@ -0,0 +1,43 @@
+use crate::common::{Api, OnBehalfOf};
+use fabrique::Factory;
+use frn_core::resourcemanager::Organization;
+use infrastructure::v1::UpdateZeroTrustNetworkRequest;
+use infrastructure::{ZeroTrustNetwork, ZeroTrustNetworkType};
+use tonic::Request;
+
+mod common;
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_the_update_zero_trust_network_procedure_works(pool: sqlx::PgPool) {
+    // Arrange the grpc server and a client
+    let mut api = Api::start(&pool).await.expect("could not start api");
+
+    let organization = Organization::factory()
+        .slug("test-org".to_owned())
+        .parent_slug(None)
+        .create(&pool)
+        .await
+        .expect("could not create organization");
+    let zero_trust_network = ZeroTrustNetwork::factory()
+        .organization_slug(organization.slug)
+        .for_zero_trust_network_type(ZeroTrustNetworkType::factory())
+        .create(&pool)
+        .await
+        .expect("could not create zero trust network");
+
+    // Act the request to the test_the_update_zero_trust_network_procedure_works
+    let request = Request::new(UpdateZeroTrustNetworkRequest {
+        id: zero_trust_network.id.to_string(),
+        name: "head office".to_owned(),
+    })
+    .on_behalf_of(&api.service_account);
+    let response = api.infrastructure.zero_trust_networks.update(request).await;
+
+    // Assert the result
+    let updated = response
+        .expect("could not update zero trust network")
+        .into_inner()
+        .zero_trust_network
+        .expect("missing zero trust network");
+    assert_eq!(updated.name, "head office");
+}
@@ -3,7 +3,7 @@ use crate::api::v1::{
     CheckBulkPermissionsRequest, CheckBulkPermissionsResponse, CheckPermissionRequest,
     CheckPermissionResponse, DeleteRelationshipsRequest, DeleteRelationshipsResponse,
     ExpandPermissionTreeRequest, ExpandPermissionTreeResponse, LookupResourcesRequest,
-    LookupResourcesResponse, LookupSubjectsRequest, LookupSubjectsResponse,
+    LookupResourcesResponse, LookupSubjectsRequest, LookupSubjectsResponse, ObjectReference,
     ReadRelationshipsRequest, ReadRelationshipsResponse, Relationship, WriteRelationshipsRequest,
     WriteRelationshipsResponse, ZedToken,
     check_permission_response::Permissionship,
@@ -198,15 +198,62 @@ fn relationship_key(rel: &Relationship) -> Option<String> {
     ))
 }
 
+/// Splits a relationship key into its object and its subject, both in the
+/// `type:id` form.
+fn split_key(key: &str) -> Option<(&str, &str)> {
+    let (resource, subject) = key.split_once('@')?;
+    let (object, _relation) = resource.split_once('#')?;
+    Some((object, subject))
+}
+
+/// Lists the ids of the objects of `resource_type` the subject reaches by
+/// following the stored relationships from it, whatever their relation.
+///
+/// Relationships to a wildcard subject (`user:*`) are reached by every subject
+/// of that type. Permissions are not evaluated: any path grants all of them,
+/// which is enough to tell the resources of distinct tenants apart.
+fn reachable(
+    store: &HashSet<String>,
+    subject: &ObjectReference,
+    resource_type: &str,
+) -> Vec<String> {
+    let mut reached = HashSet::from([
+        format!("{}:{}", subject.object_type, subject.object_id),
+        format!("{}:*", subject.object_type),
+    ]);
+
+    loop {
+        let newly = store
+            .iter()
+            .filter_map(|key| split_key(key))
+            .filter(|(object, subject)| reached.contains(*subject) && !reached.contains(*object))
+            .map(|(object, _)| object.to_owned())
+            .collect::<Vec<_>>();
+        if newly.is_empty() {
+            break;
+        }
+        reached.extend(newly);
+    }
+
+    reached
+        .iter()
+        .filter_map(|object| object.strip_prefix(resource_type)?.strip_prefix(':'))
+        .filter(|id| *id != "*")
+        .map(ToOwned::to_owned)
+        .collect()
+}
+
 /// An in-process SpiceDB test double that records relationship writes and
 /// deletes into a shared [`RelationshipStore`].
 ///
 /// The real SpiceDB client routes both writes and deletes through the
 /// `write_relationships` RPC (`Operation::Touch` vs `Operation::Delete`), so
 /// only that RPC is stateful here: it lets a test assert exactly which
-/// relationships an operation persisted or removed at the gRPC boundary. The
-/// read and permission RPCs are not implemented because the operations under
-/// test never call them.
+/// relationships an operation persisted or removed at the gRPC boundary.
+/// `LookupResources` and `CheckPermission` answer from the recorded
+/// relationships (see [`reachable`]), so tests can tell apart what each
+/// principal lists and may do. The other read and permission RPCs are not
+/// implemented because the operations under test never call them.
 pub struct RecordingSpiceDBServer {
     store: RelationshipStore,
 }
@@ -245,11 +292,35 @@ impl PermissionsService for RecordingSpiceDBServer {
         unimplemented!()
     }
 
+    /// Grants the permission when the subject reaches the resource through the
+    /// recorded relationships.
     async fn check_permission(
         &self,
-        _: Request<CheckPermissionRequest>,
+        request: Request<CheckPermissionRequest>,
     ) -> Result<Response<CheckPermissionResponse>, Status> {
-        unimplemented!()
+        let request = request.into_inner();
+        let subject = request
+            .subject
+            .and_then(|subject| subject.object)
+            .ok_or_else(|| Status::invalid_argument("missing subject"))?;
+        let resource = request
+            .resource
+            .ok_or_else(|| Status::invalid_argument("missing resource"))?;
+        let store = self.store.lock().expect("relationship store poisoned");
+
+        let permissionship =
+            if reachable(&store, &subject, &resource.object_type).contains(&resource.object_id) {
+                Permissionship::HasPermission
+            } else {
+                Permissionship::NoPermission
+            };
+
+        Ok(Response::new(CheckPermissionResponse {
+            checked_at: None,
+            debug_trace: None,
+            partial_caveat_info: None,
+            permissionship: permissionship as i32,
+        }))
     }
 
     async fn delete_relationships(
@@ -266,11 +337,33 @@ impl PermissionsService for RecordingSpiceDBServer {
         unimplemented!()
     }
 
+    /// Looks up the resources the subject reaches through the recorded
+    /// relationships.
     async fn lookup_resources(
         &self,
-        _: Request<LookupResourcesRequest>,
+        request: Request<LookupResourcesRequest>,
     ) -> Result<Response<Self::LookupResourcesStream>, Status> {
-        unimplemented!()
+        let request = request.into_inner();
+        let subject = request
+            .subject
+            .and_then(|subject| subject.object)
+            .ok_or_else(|| Status::invalid_argument("missing subject"))?;
+        let store = self.store.lock().expect("relationship store poisoned");
+
+        let responses = reachable(&store, &subject, &request.resource_object_type)
+            .into_iter()
+            .map(|id| {
+                Ok(LookupResourcesResponse {
+                    after_result_cursor: None,
+                    looked_up_at: None,
+                    partial_caveat_info: None,
+                    permissionship: Permissionship::HasPermission as i32,
+                    resource_object_id: id,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        Ok(Response::new(Box::pin(futures::stream::iter(responses))))
     }
 
     async fn lookup_subjects(
@@ -0,0 +1,95 @@
+use sqlx::types::Uuid;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Delays the synchronization of the hypervisors which failed their last
+/// ones, doubling the delay on each consecutive failure.
+#[derive(Debug)]
+pub struct Backoff {
+    base: Duration,
+    max: Duration,
+    failures: HashMap<Uuid, Failure>,
+}
+
+#[derive(Debug)]
+struct Failure {
+    count: u32,
+    retry_at: Instant,
+}
+
+impl Backoff {
+    /// Creates a backoff starting at `base`, capped at `max`.
+    pub fn new(base: Duration, max: Duration) -> Self {
+        Self {
+            base,
+            max,
+            failures: HashMap::new(),
+        }
+    }
+
+    /// Whether the hypervisor is due for a synchronization.
+    pub fn is_ready(&self, hypervisor_id: Uuid) -> bool {
+        self.failures
+            .get(&hypervisor_id)
+            .is_none_or(|failure| Instant::now() >= failure.retry_at)
+    }
+
+    /// Resets the backoff of a hypervisor which synchronized.
+    pub fn succeeded(&mut self, hypervisor_id: Uuid) {
+        self.failures.remove(&hypervisor_id);
+    }
+
+    /// Records the failure of a hypervisor, returning the delay before its
+    /// next synchronization.
+    pub fn failed(&mut self, hypervisor_id: Uuid) -> Duration {
+        let count = self
+            .failures
+            .get(&hypervisor_id)
+            .map_or(1, |failure| failure.count.saturating_add(1));
+        let delay = self
+            .base
+            .saturating_mul(2u32.saturating_pow(count - 1))
+            .min(self.max);
+
+        self.failures.insert(
+            hypervisor_id,
+            Failure {
+                count,
+                retry_at: Instant::now() + delay,
+            },
+        );
+
+        delay
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_failures_double_the_delay_up_to_max() {
+        let mut backoff = Backoff::new(Duration::from_secs(10), Duration::from_secs(30));
+        let id = Uuid::new_v4();
+
+        assert!(backoff.is_ready(id));
+        assert_eq!(backoff.failed(id), Duration::from_secs(10));
+        assert!(!backoff.is_ready(id));
+        assert_eq!(backoff.failed(id), Duration::from_secs(20));
+        assert_eq!(backoff.failed(id), Duration::from_secs(30));
+        assert!(backoff.is_ready(Uuid::new_v4()));
+    }
+
+    #[test]
+    fn test_success_resets_the_delay() {
+        let mut backoff = Backoff::new(Duration::from_secs(10), Duration::from_secs(30));
+        let id = Uuid::new_v4();
+
+        backoff.failed(id);
+        backoff.failed(id);
+        backoff.succeeded(id);
+
+        assert!(backoff.is_ready(id));
+        assert_eq!(backoff.failed(id), Duration::from_secs(10));
+    }
+}
@@ -13,4 +13,7 @@ pub enum Error {
 
     #[error("hypervisor error: {0}")]
     Hypervisor(#[from] hypervisor::Error),
+
+    #[error("hypervisor unreachable")]
+    Unreachable,
 }
@@ -0,0 +1,68 @@
+use crate::error::Error;
+use sqlx::{PgConnection, Pool, Postgres};
+
+/// The key of the advisory lock held by the leading synchronizer.
+const LEADER_LOCK_KEY: i64 = 0x7379_6e63_6872_6f6e;
+
+/// Elects a single leading synchronizer among its replicas through a Postgres
+/// session advisory lock.
+///
+/// The lock is tried on a single connection detached from the pool, kept
+/// across the ticks of the replica, and released by Postgres as soon as that
+/// connection closes, letting another replica take over.
+pub struct LeaderElection {
+    pool: Pool<Postgres>,
+    connection: Option<PgConnection>,
+    leading: bool,
+}
+
+impl LeaderElection {
+    pub fn new(pool: Pool<Postgres>) -> Self {
+        Self {
+            pool,
+            connection: None,
+            leading: false,
+        }
+    }
+
+    /// Whether this replica leads, trying to acquire the lock when it does
+    /// not hold it.
+    pub async fn is_leader(&mut self) -> Result<bool, Error> {
+        // A new connection does not hold the lock of the one it replaces
+        let mut connection = match self.connection.take() {
+            Some(connection) => connection,
+            None => {
+                self.leading = false;
+                self.pool.acquire().await?.detach()
+            }
+        };
+
+        let leading = if self.leading {
+            // Check the connection holding the lock is still alive.
+            // Raw SQL: fabrique has no statement-less queries
+            sqlx::query("SELECT 1")
+                .execute(&mut connection)
+                .await
+                .map(|_| true)
+        } else {
+            // Raw SQL: advisory locks are not modeled by fabrique
+            sqlx::query_scalar("SELECT pg_try_advisory_lock($1)")
+                .bind(LEADER_LOCK_KEY)
+                .fetch_one(&mut connection)
+                .await
+        };
+
+        match leading {
+            Ok(leading) => {
+                self.connection = Some(connection);
+                self.leading = leading;
+                Ok(leading)
+            }
+            Err(e) => {
+                tracing::warn!(error = %e, "Lost the connection of the leader election");
+                self.leading = false;
+                Err(e.into())
+            }
+        }
+    }
+}
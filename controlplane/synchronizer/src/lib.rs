@@ -1,126 +1,289 @@
-use fabrique::Query;
+use chrono::{Duration, Utc};
+use fabrique::{Delete, Query};
 use frn_core::authorization::{Relation, Relationship, Resource};
 use frn_core::resourcemanager::Project;
 use frn_core::{
-    App, authorization::Authorize, compute::Instance, identity::ServiceAccount,
+    App,
+    authorization::Authorize,
+    compute::{Hypervisor, HypervisorHealth, Instance},
+    identity::ServiceAccount,
     resourcemanager::Organization,
 };
 use futures::{StreamExt, TryStreamExt, stream};
-use hypervisor::instance::Instances;
+use hypervisor::instance::{Instances, Status};
 use sqlx::types::Uuid;
+use std::time::Instant;
 
+mod backoff;
 mod error;
+pub mod leader;
+pub mod metrics;
+mod report;
+pub use backoff::Backoff;
 use error::Error;
+pub use report::SynchronizationReport;
 
-pub async fn synchronize<Auth: Authorize>(app: &mut App<Auth>) -> Result<(), Error> {
+/// The number of hypervisors synchronized at once.
+const HYPERVISOR_CONCURRENCY: usize = 4;
+
+/// Synchronizes the recorded instances with the ones of each hypervisor.
+///
+/// Hypervisors are synchronized concurrently, the failure of one of them
+/// being recorded in the report and delaying its next synchronization through
+/// `backoff`, without holding the others back. Instances a hypervisor stopped
+/// listing are deleted once they stayed missing for `grace_period`.
+pub async fn synchronize<Auth: Authorize>(
+    app: &mut App<Auth>,
+    grace_period: Duration,
+    backoff: &mut Backoff,
+) -> Result<SynchronizationReport, Error> {
     let principal = ServiceAccount::default();
 
-    // Retrieve the connected hypervisors
-    let hypervisors = app.hypervisors.list(&principal).await?;
-
-    for hypervisor in hypervisors {
-        tracing::info!(hypervisor_id = %hypervisor.id, hypervisor_url = %hypervisor.url, "Synchronizing hypervisor");
-
-        let service = hypervisor::resolve(
-            hypervisor.url.clone(),
-            hypervisor.authorization_token.clone(),
-        );
-        let root_organization = Organization::query()
-            .select()
-            .r#where(
-                Organization::NAME,
-                "=",
-                app.config.root_organization.name.clone(),
-            )
-            .first_or_fail(&app.db)
-            .await?;
-        let default_project = app
-            .projects
-            .get_default_project(&principal, &root_organization.slug)
-            .await?;
-
-        let distant_instances = service.list().await?;
-        let instances = stream::iter(distant_instances)
-            .map(|distant| {
-                let pool = app.db.clone();
-                let service = Clone::clone(&service);
-                let default_slug = default_project.slug.clone();
-
-                async move {
-                    let mut existing = Instance::query()
-                        .select()
-                        .r#where(Instance::DISTANT_ID, "=", distant.id.clone())
-                        .r#where(Instance::HYPERVISOR_ID, "=", hypervisor.id)
-                        .first(&pool)
-                        .await?
-                        .unwrap_or(Instance {
-                            id: Uuid::new_v4(),
-                            project_slug: default_slug.clone(),
-                            ..Default::default()
-                        });
-
-                    // Try to retrieve the ip address if it is not known yet
-                    if existing.ip_v4.is_empty() {
-                        let ip = match service.get_ip_address(&distant.id).await {
-                            Ok(value) => Ok(value),
-                            Err(hypervisor::Error::InstanceNotRunning(_)) => Ok(None),
-                            Err(err) => Err(err),
-                        }?;
-
-                        if let Some(ip) = ip {
-                            existing.ip_v4 = ip.to_string();
-                        }
-                    }
+    // Resolve the project recording the instances discovered on the hypervisors
+    let root_organization = Organization::query()
+        .select()
+        .r#where(
+            Organization::NAME,
+            "=",
+            app.config.root_organization.name.clone(),
+        )
+        .first_or_fail(&app.db)
+        .await?;
+    let default_project = app
+        .projects
+        .get_default_project(&principal, &root_organization.slug)
+        .await?;
+
+    // Retrieve the connected hypervisors across every tenant, but the ones backing off
+    let hypervisors: Vec<Hypervisor> = Hypervisor::all(&app.db)
+        .await?
+        .into_iter()
+        .filter(|hypervisor| backoff.is_ready(hypervisor.id))
+        .collect();
+
+    let app = &*app;
+    let outcomes = stream::iter(hypervisors)
+        .map(|hypervisor| {
+            let default_slug = default_project.slug.clone();
+
+            async move {
+                let started = Instant::now();
+                let outcome =
+                    synchronize_hypervisor(app, &hypervisor, &default_slug, grace_period).await;
+
+                (hypervisor.id, started.elapsed(), outcome)
+            }
+        })
+        .buffer_unordered(HYPERVISOR_CONCURRENCY)
+        .collect::<Vec<_>>()
+        .await;
+
+    let mut report = SynchronizationReport::default();
+    for (hypervisor_id, elapsed, outcome) in outcomes {
+        match outcome {
+            Ok(synchronized) => {
+                backoff.succeeded(hypervisor_id);
+                metrics::synchronized(hypervisor_id, elapsed);
+                report.merge(synchronized);
+            }
+            Err(e) => {
+                let delay = backoff.failed(hypervisor_id);
+                metrics::failed(hypervisor_id, elapsed);
+                tracing::error!(hypervisor_id = %hypervisor_id, error = %e, retry_in = ?delay, "Failed to synchronize hypervisor");
+                report.failed.push(hypervisor_id);
+            }
+        }
+    }
 
-                    Ok::<Instance, Error>(Instance {
-                        id: existing.id,
-                        hypervisor_id: hypervisor.id,
-                        project_slug: existing.project_slug.clone(),
-                        zero_trust_network_id: existing.zero_trust_network_id,
-                        distant_id: distant.id,
-                        cpu_usage_percent: distant.cpu_usage_percent as f64,
-                        disk_usage_bytes: distant.disk_usage_bytes as i64,
-                        ip_v4: existing.ip_v4,
-                        max_cpu_cores: distant.max_cpu_cores as i32,
-                        max_disk_bytes: distant.max_disk_bytes as i64,
-                        max_memory_bytes: distant.max_memory_bytes as i64,
-                        memory_usage_bytes: distant.memory_usage_bytes as i64,
-                        name: distant.name,
-                        status: distant.status,
-                        created_at: existing.created_at,
-                        updated_at: existing.updated_at,
-                    })
+    Ok(report)
+}
+
+/// Synchronizes the recorded instances with the ones of a hypervisor.
+async fn synchronize_hypervisor<Auth: Authorize>(
+    app: &App<Auth>,
+    hypervisor: &Hypervisor,
+    default_slug: &str,
+    grace_period: Duration,
+) -> Result<SynchronizationReport, Error> {
+    let mut report = SynchronizationReport::default();
+
+    tracing::info!(hypervisor_id = %hypervisor.id, hypervisor_url = %hypervisor.url, "Synchronizing hypervisor");
+
+    // Probe the hypervisor, leaving its instances untouched when it does not answer
+    let hypervisor = app.hypervisors.probe(hypervisor).await?;
+    if hypervisor.health == HypervisorHealth::Unreachable {
+        return Err(Error::Unreachable);
+    }
+
+    let service = hypervisor.resolve(&app.config.hypervisor_resolver)?;
+    let distant_instances = service.list().await?;
+    let listed: Vec<String> = distant_instances
+        .iter()
+        .map(|distant| distant.id.clone())
+        .collect();
+    let instances = stream::iter(distant_instances)
+        .map(|distant| {
+            let pool = app.db.clone();
+            let service = Clone::clone(&service);
+            let default_slug = default_slug.to_owned();
+
+            async move {
+                let existing = Instance::query()
+                    .select()
+                    .r#where(Instance::DISTANT_ID, "=", distant.id.clone())
+                    .r#where(Instance::HYPERVISOR_ID, "=", hypervisor.id)
+                    .first(&pool)
+                    .await?;
+                let created = existing.is_none();
+                let mut existing = existing.unwrap_or(Instance {
+                    id: Uuid::new_v4(),
+                    project_slug: default_slug.clone(),
+                    ..Default::default()
+                });
+
+                // Try to retrieve the ip address if it is not known yet
+                if existing.ip_v4.is_empty() {
+                    let ip = match service.get_ip_address(&distant.id).await {
+                        Ok(value) => Ok(value),
+                        Err(hypervisor::Error::InstanceNotRunning(_)) => Ok(None),
+                        Err(err) => Err(err),
+                    }?;
+
+                    if let Some(ip) = ip {
+                        existing.ip_v4 = ip.to_string();
+                    }
                 }
-            })
-            .buffer_unordered(4)
-            .try_collect::<Vec<Instance>>()
-            .await?;
 
-        let instances = Instance::upsert(&app.db, &instances).await?;
+                let instance = Instance {
+                    id: existing.id,
+                    hypervisor_id: hypervisor.id,
+                    project_slug: existing.project_slug.clone(),
+                    zero_trust_network_id: existing.zero_trust_network_id,
+                    distant_id: distant.id,
+                    cpu_usage_percent: distant.cpu_usage_percent as f64,
+                    disk_usage_bytes: distant.disk_usage_bytes as i64,
+                    ip_v4: existing.ip_v4,
+                    max_cpu_cores: distant.max_cpu_cores as i32,
+                    max_disk_bytes: distant.max_disk_bytes as i64,
+                    max_memory_bytes: distant.max_memory_bytes as i64,
+                    memory_usage_bytes: distant.memory_usage_bytes as i64,
+                    name: distant.name,
+                    status: distant.status,
+                    created_at: existing.created_at,
+                    updated_at: existing.updated_at,
+                    missing_since: None,
+                };
 
-        let relationships: Vec<Relationship> = instances
-            .iter()
-            .map(|instance| {
-                Relationship::new(
-                    &Project::some(instance.project_slug.clone()),
-                    Relation::Parent,
-                    instance,
-                )
-            })
-            .collect();
+                Ok::<(Instance, bool), Error>((instance, created))
+            }
+        })
+        .buffer_unordered(4)
+        .try_collect::<Vec<(Instance, bool)>>()
+        .await?;
 
-        for relationship in &relationships {
-            app.auth.write_relationship(relationship).await?;
+    for (instance, created) in &instances {
+        if *created {
+            report.created.push(instance.id);
+        } else {
+            report.updated.push(instance.id);
         }
     }
+    let instances: Vec<Instance> = instances
+        .into_iter()
+        .map(|(instance, _)| instance)
+        .collect();
+    let instances = Instance::upsert(&app.db, &instances).await?;
 
-    Ok(())
+    let relationships: Vec<Relationship> = instances
+        .iter()
+        .map(|instance| {
+            Relationship::new(
+                &Project::some(instance.project_slug.clone()),
+                Relation::Parent,
+                instance,
+            )
+        })
+        .collect();
+
+    let mut auth = app.auth.clone();
+    for relationship in &relationships {
+        auth.write_relationship(relationship).await?;
+    }
+
+    // Re-apply the security groups of the instances whose firewall drifted
+    for instance in &instances {
+        match app.security_groups.enforce(instance).await {
+            Ok(true) => {
+                tracing::info!(instance_id = %instance.id, "Re-applied drifted security groups")
+            }
+            Ok(false) => {}
+            Err(e) => {
+                tracing::error!(instance_id = %instance.id, error = %e, "Failed to enforce security groups")
+            }
+        }
+    }
+
+    reconcile(app, hypervisor.id, &listed, grace_period, &mut report).await?;
+
+    Ok(report)
 }
 
-pub async fn heartbeat(client: &reqwest::Client, url: &Option<String>) {
-    if let Some(url) = url
-        && let Err(e) = client.get(url).send().await
-    {
-        tracing::error!(error = %e, "Failed to ping heartbeat");
+/// Reconciles the recorded instances of a hypervisor with the ones it lists.
+///
+/// Instances it does not list anymore are first marked as missing, then
+/// deleted along with their parent relationship once they stayed missing for
+/// `grace_period`, whatever their updates meanwhile. Listing them again clears
+/// the time they went missing. Instances still provisioning, or whose
+/// provisioning failed, are not created on the hypervisor, and are left
+/// untouched.
+async fn reconcile<Auth: Authorize>(
+    app: &App<Auth>,
+    hypervisor_id: Uuid,
+    listed: &[String],
+    grace_period: Duration,
+    report: &mut SynchronizationReport,
+) -> Result<(), Error> {
+    let recorded = Instance::query()
+        .select()
+        .r#where(Instance::HYPERVISOR_ID, "=", hypervisor_id)
+        .r#where(Instance::STATUS, "!=", Status::Provisioning.to_string())
+        .r#where(Instance::STATUS, "!=", Status::Failed.to_string())
+        .get(&app.db)
+        .await?;
+
+    for instance in recorded {
+        if listed.contains(&instance.distant_id) {
+            continue;
+        }
+
+        let Some(missing_since) = instance.missing_since else {
+            tracing::warn!(instance_id = %instance.id, distant_id = %instance.distant_id, "Instance missing from its hypervisor");
+            let now = Utc::now();
+            Instance::update()
+                .set(Instance::STATUS, Status::Missing.to_string())
+                .set(Instance::MISSING_SINCE, Some(now))
+                .set(Instance::UPDATED_AT, now)
+                .r#where(Instance::ID, "=", instance.id)
+                .execute(&app.db)
+                .await?;
+            report.missing.push(instance.id);
+            continue;
+        };
+
+        if missing_since <= Utc::now() - grace_period {
+            tracing::warn!(instance_id = %instance.id, distant_id = %instance.distant_id, "Deleting instance vanished from its hypervisor");
+            app.auth
+                .clone()
+                .delete_relationship(&Relationship::new(
+                    &Project::some(instance.project_slug.clone()),
+                    Relation::Parent,
+                    &instance,
+                ))
+                .await?;
+            Instance::destroy(&app.db, instance.id).await?;
+            report.vanished.push(instance.id);
+        }
     }
+
+    Ok(())
 }
@@ -1,6 +1,6 @@
 use frn_core::App;
-use std::{error::Error, sync::Arc, time::Duration};
-use synchronizer::{heartbeat, synchronize};
+use std::{error::Error, net::SocketAddr, sync::Arc, time::Duration};
+use synchronizer::{Backoff, leader::LeaderElection, metrics, synchronize};
 use tokio::{sync::Mutex, time};
 use tracing::{error, info, warn};
 
@@ -15,6 +15,13 @@ async fn main() -> Result<(), Box<dyn Error>> {
 
     let mut app = App::new().await.expect("could not bootstrap app");
 
+    // Serve the Prometheus metrics
+    let metrics_address = std::env::var("METRICS_ADDR")
+        .ok()
+        .and_then(|value| value.parse::<SocketAddr>().ok())
+        .unwrap_or_else(|| SocketAddr::from(([0, 0, 0, 0], 9090)));
+    metrics::install(metrics_address)?;
+
     // Setup ticker
     let tick = std::env::var("INTERVAL")
         .unwrap_or_else(|_| String::from("60"))
@@ -23,9 +30,21 @@ async fn main() -> Result<(), Box<dyn Error>> {
     let sync_in_progress = Arc::new(Mutex::new(false));
     let mut interval = time::interval(Duration::from_secs(tick));
 
-    // Setup heartbeat
-    let client = reqwest::Client::new();
-    let heartbeat_url = std::env::var("HEARTBEAT_URL").ok();
+    // Setup the grace period before deleting the instances missing from their hypervisor
+    let grace_period = std::env::var("MISSING_GRACE_PERIOD")
+        .ok()
+        .and_then(|value| value.parse::<i64>().ok())
+        .map(chrono::Duration::seconds)
+        .unwrap_or_else(|| chrono::Duration::minutes(10));
+
+    // Setup the backoff of the failing hypervisors, from one tick up to ten minutes
+    let mut backoff = Backoff::new(
+        Duration::from_secs(tick),
+        Duration::from_secs(tick.max(600)),
+    );
+
+    // Only the replica holding the leader lock synchronizes
+    let mut election = LeaderElection::new(app.db.clone());
 
     loop {
         // Start the ticker
@@ -37,10 +56,25 @@ async fn main() -> Result<(), Box<dyn Error>> {
             continue;
         }
 
+        // Check this replica leads the synchronization
+        match election.is_leader().await {
+            Ok(is_leader) => {
+                metrics::leader(is_leader);
+                if !is_leader {
+                    continue;
+                }
+            }
+            Err(e) => {
+                error!(error = %e, "Leader election failed");
+                metrics::leader(false);
+                continue;
+            }
+        }
+
         // Call the synchronization process
-        match synchronize(&mut app).await {
-            // If the synchronization worked, trigger a heartbeat if the url is defined
-            Ok(_) => heartbeat(&client, &heartbeat_url).await,
+        match synchronize(&mut app, grace_period, &mut backoff).await {
+            // If the synchronization worked, report it
+            Ok(report) => report.log(),
             // Otherwise log an error
             Err(e) => {
                 error!("Problem happened: {:#?}", &e);
@@ -0,0 +1,47 @@
+use chrono::Utc;
+use metrics_exporter_prometheus::{BuildError, PrometheusBuilder};
+use sqlx::types::Uuid;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+/// Installs the Prometheus recorder, serving the metrics on `address`.
+pub fn install(address: SocketAddr) -> Result<(), BuildError> {
+    PrometheusBuilder::new()
+        .with_http_listener(address)
+        .install()
+}
+
+/// Records a successful synchronization of a hypervisor.
+pub fn synchronized(hypervisor_id: Uuid, duration: Duration) {
+    let hypervisor_id = hypervisor_id.to_string();
+    metrics::gauge!(
+        "synchronizer_hypervisor_last_success_timestamp_seconds",
+        "hypervisor_id" => hypervisor_id.clone()
+    )
+    .set(Utc::now().timestamp() as f64);
+    metrics::gauge!(
+        "synchronizer_hypervisor_sync_duration_seconds",
+        "hypervisor_id" => hypervisor_id
+    )
+    .set(duration.as_secs_f64());
+}
+
+/// Records a failed synchronization of a hypervisor.
+pub fn failed(hypervisor_id: Uuid, duration: Duration) {
+    let hypervisor_id = hypervisor_id.to_string();
+    metrics::counter!(
+        "synchronizer_hypervisor_failures_total",
+        "hypervisor_id" => hypervisor_id.clone()
+    )
+    .increment(1);
+    metrics::gauge!(
+        "synchronizer_hypervisor_sync_duration_seconds",
+        "hypervisor_id" => hypervisor_id
+    )
+    .set(duration.as_secs_f64());
+}
+
+/// Records whether this replica leads the synchronization.
+pub fn leader(is_leader: bool) {
+    metrics::gauge!("synchronizer_leader").set(if is_leader { 1.0 } else { 0.0 });
+}
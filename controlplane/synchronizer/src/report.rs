@@ -0,0 +1,43 @@
+use sqlx::types::Uuid;
+
+/// The changes a synchronization pass made to the recorded instances.
+#[derive(Debug, Default)]
+pub struct SynchronizationReport {
+    /// Instances discovered on their hypervisor.
+    pub created: Vec<Uuid>,
+
+    /// Instances refreshed from their hypervisor.
+    pub updated: Vec<Uuid>,
+
+    /// Instances their hypervisor stopped listing, marked as missing.
+    pub missing: Vec<Uuid>,
+
+    /// Instances missing for longer than the grace period, deleted.
+    pub vanished: Vec<Uuid>,
+
+    /// Hypervisors which failed to synchronize.
+    pub failed: Vec<Uuid>,
+}
+
+impl SynchronizationReport {
+    /// Merges the report of another hypervisor into this one.
+    pub fn merge(&mut self, other: SynchronizationReport) {
+        self.created.extend(other.created);
+        self.updated.extend(other.updated);
+        self.missing.extend(other.missing);
+        self.vanished.extend(other.vanished);
+        self.failed.extend(other.failed);
+    }
+
+    /// Logs the report as a structured event.
+    pub fn log(&self) {
+        tracing::info!(
+            created = self.created.len(),
+            updated = self.updated.len(),
+            missing = ?self.missing,
+            vanished = ?self.vanished,
+            failed = ?self.failed,
+            "Synchronization pass completed"
+        );
+    }
+}
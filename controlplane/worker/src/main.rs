@@ -16,6 +16,7 @@ use frn_rpc::v1::workflow::{
     workflow_engine_client::WorkflowEngineClient,
 };
 use futures::FutureExt;
+use hypervisor::{KubeVirtConfig, Resolver, SnippetsConfig, StoragesConfig};
 use kube::Client as KubeClient;
 use spicedb::SpiceDB;
 use sqlx::PgPool;
@@ -80,6 +81,19 @@ async fn main() -> Result<(), Box<dyn StdError>> {
         )
         .expect("KUBECONFIG_ENCRYPTION_KEY must be base64-encoded 32 bytes"),
     );
+    let hypervisor_resolver = Resolver::new(
+        Arc::new(
+            Kek::from_base64(
+                &env::var("HYPERVISOR_TOKEN_ENCRYPTION_KEY")
+                    .expect("HYPERVISOR_TOKEN_ENCRYPTION_KEY must be set"),
+            )
+            .expect("HYPERVISOR_TOKEN_ENCRYPTION_KEY must be base64-encoded 32 bytes"),
+        ),
+        SnippetsConfig::from_env(),
+        StoragesConfig::from_env(),
+        KubeVirtConfig::from_env()
+            .expect("KUBEVIRT_CA_CERTIFICATES must be a PEM bundle of certificates"),
+    );
 
     let pool = PgPool::connect(&database_url).await?;
     let spicedb = SpiceDB::connect(&spicedb_url, &spicedb_token).await?;
@@ -99,6 +113,7 @@ async fn main() -> Result<(), Box<dyn StdError>> {
             deployment_annotations: std::collections::BTreeMap::new(),
         },
         kek,
+        hypervisor_resolver,
         kubeconfig_path: None,
     };
 
@@ -332,6 +347,7 @@ async fn process_execution(
         let (errors, successes): (Vec<_>, Vec<_>) = results.into_iter().partition(Result::is_err);
         let errors: Vec<_> = errors.into_iter().filter_map(Result::err).collect();
         let successes: Vec<_> = successes.into_iter().filter_map(Result::ok).collect();
+        execution.definition.record_operations(&successes);
         rollbacks.extend(successes);
 
         if !errors.is_empty() {
@@ -14,6 +14,7 @@ use std::sync::Arc;
 
 pub use frn_core::managed::PlatformConfig;
 use frn_crypto::Kek;
+use hypervisor::Resolver;
 use kube::Client as KubeClient;
 use spicedb::SpiceDB;
 use sqlx::PgPool;
@@ -26,6 +27,7 @@ pub struct WorkerContext {
     pub kube: KubeClient,
     pub platform_config: PlatformConfig,
     pub kek: Arc<Kek>,
+    pub hypervisor_resolver: Resolver,
     pub kubeconfig_path: Option<PathBuf>,
 }
 
@@ -0,0 +1,54 @@
+use frn_core::compute::Instances;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::WorkerContext;
+use crate::execution::WorkflowExecutionId;
+
+/// Records the instance provisioned on its hypervisor, ending its
+/// provisioning.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompleteComputeInstanceOp {
+    pub instance_id: Uuid,
+    pub distant_id: String,
+}
+
+#[derive(Debug, Error, crate::OperationError)]
+pub enum CompleteComputeInstanceError {
+    #[error("{0}")]
+    Compute(#[from] frn_core::Error),
+}
+
+impl crate::operations::Operation for CompleteComputeInstanceOp {
+    type Error = CompleteComputeInstanceError;
+
+    async fn execute(
+        self,
+        ctx: WorkerContext,
+        _execution_id: WorkflowExecutionId,
+    ) -> Result<Self, Self::Error> {
+        let instances = Instances::new(ctx.spicedb, ctx.pool, ctx.hypervisor_resolver);
+
+        instances
+            .complete_provisioning(self.instance_id, &self.distant_id)
+            .await?;
+
+        info!(instance_id = %self.instance_id, distant_id = %self.distant_id, "compute instance provisioned");
+
+        Ok(self)
+    }
+
+    async fn rollback(
+        self,
+        ctx: WorkerContext,
+        _execution_id: WorkflowExecutionId,
+    ) -> Result<(), Self::Error> {
+        let instances = Instances::new(ctx.spicedb, ctx.pool, ctx.hypervisor_resolver);
+
+        instances.reset_provisioning(self.instance_id).await?;
+
+        Ok(())
+    }
+}
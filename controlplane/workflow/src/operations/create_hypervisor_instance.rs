@@ -0,0 +1,90 @@
+use frn_core::compute::Instances;
+use hypervisor::instance::InstanceCreateRequest;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::WorkerContext;
+use crate::execution::WorkflowExecutionId;
+
+/// Creates the VM of an instance on its hypervisor, and deletes it when the
+/// provisioning is rolled back.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateHypervisorInstanceOp {
+    pub hypervisor_id: Uuid,
+    /// The project of the instance, whose other instances on the hypervisor
+    /// it is kept apart from.
+    #[serde(default)]
+    pub project_slug: String,
+    pub name: String,
+    pub cores: u8,
+    pub disk_bytes: u64,
+    pub disk_image: String,
+    pub memory_bytes: u64,
+    pub snippet: String,
+    /// Populated during execute to allow rollback.
+    pub distant_id: Option<String>,
+}
+
+#[derive(Debug, Error, crate::OperationError)]
+pub enum CreateHypervisorInstanceError {
+    #[error("{0}")]
+    Compute(#[from] frn_core::Error),
+}
+
+impl crate::operations::Operation for CreateHypervisorInstanceOp {
+    type Error = CreateHypervisorInstanceError;
+
+    async fn execute(
+        mut self,
+        ctx: WorkerContext,
+        _execution_id: WorkflowExecutionId,
+    ) -> Result<Self, Self::Error> {
+        let instances = Instances::new(ctx.spicedb, ctx.pool, ctx.hypervisor_resolver);
+
+        let anti_affinity = instances
+            .anti_affinity(self.hypervisor_id, &self.project_slug)
+            .await?;
+        let distant_id = instances
+            .create_distant(
+                self.hypervisor_id,
+                InstanceCreateRequest {
+                    id: String::new(),
+                    cores: self.cores,
+                    disk_bytes: self.disk_bytes,
+                    disk_image: self.disk_image.clone(),
+                    memory_bytes: self.memory_bytes,
+                    name: self.name.clone(),
+                    snippet: self.snippet.clone(),
+                    anti_affinity,
+                },
+            )
+            .await?;
+
+        info!(hypervisor_id = %self.hypervisor_id, distant_id, "hypervisor instance created");
+        self.distant_id = Some(distant_id);
+
+        Ok(self)
+    }
+
+    async fn rollback(
+        self,
+        ctx: WorkerContext,
+        _execution_id: WorkflowExecutionId,
+    ) -> Result<(), Self::Error> {
+        let Some(distant_id) = self.distant_id else {
+            info!("no hypervisor instance created, skipping rollback");
+            return Ok(());
+        };
+
+        let instances = Instances::new(ctx.spicedb, ctx.pool, ctx.hypervisor_resolver);
+        instances
+            .delete_distant(self.hypervisor_id, &distant_id)
+            .await?;
+
+        info!(hypervisor_id = %self.hypervisor_id, distant_id, "hypervisor instance deleted (rollback)");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,62 @@
+use frn_core::compute::Overlays;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::WorkerContext;
+use crate::execution::WorkflowExecutionId;
+
+/// Makes an instance a peer of the overlay of its zero trust network, and
+/// injects its WireGuard configuration into its snippet. The instance leaves
+/// the overlay when the provisioning is rolled back.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JoinZeroTrustNetworkOp {
+    pub instance_id: Uuid,
+    pub zero_trust_network_id: Uuid,
+    pub snippet: String,
+    /// Populated during execute with the snippet holding the WireGuard
+    /// configuration.
+    pub prepared_snippet: Option<String>,
+}
+
+#[derive(Debug, Error, crate::OperationError)]
+pub enum JoinZeroTrustNetworkError {
+    #[error("{0}")]
+    Compute(#[from] frn_core::Error),
+}
+
+impl crate::operations::Operation for JoinZeroTrustNetworkOp {
+    type Error = JoinZeroTrustNetworkError;
+
+    async fn execute(
+        mut self,
+        ctx: WorkerContext,
+        _execution_id: WorkflowExecutionId,
+    ) -> Result<Self, Self::Error> {
+        let overlays = Overlays::new(ctx.pool, ctx.hypervisor_resolver);
+
+        let peer = overlays
+            .join(self.zero_trust_network_id, self.instance_id)
+            .await?;
+        self.prepared_snippet = Some(overlays.inject(self.instance_id, &self.snippet).await?);
+
+        info!(instance_id = %self.instance_id, overlay_ip = %peer.overlay_ip, "zero trust network joined");
+
+        Ok(self)
+    }
+
+    async fn rollback(
+        self,
+        ctx: WorkerContext,
+        _execution_id: WorkflowExecutionId,
+    ) -> Result<(), Self::Error> {
+        let overlays = Overlays::new(ctx.pool, ctx.hypervisor_resolver);
+
+        overlays.leave(self.instance_id).await?;
+
+        info!(instance_id = %self.instance_id, "zero trust network left (rollback)");
+
+        Ok(())
+    }
+}
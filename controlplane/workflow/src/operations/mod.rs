@@ -6,6 +6,8 @@ use crate::execution::WorkflowExecutionId;
 
 pub mod assert_namespace_absent;
 pub mod check_permission;
+pub mod complete_compute_instance;
+pub mod create_hypervisor_instance;
 pub mod create_k8s_secret;
 pub mod create_namespace;
 pub mod delete_k8s_secret;
@@ -15,7 +17,11 @@ pub mod helm_common;
 pub mod helm_install;
 pub mod helm_uninstall;
 pub mod helm_upgrade;
+pub mod join_zero_trust_network;
 pub mod k8s_common;
+pub mod push_zero_trust_network;
+pub mod reserve_compute_instance;
+pub mod setup_hoop_access;
 pub mod update_instance_status;
 pub mod update_instance_version;
 pub mod update_k8s_secret;
@@ -97,6 +103,8 @@ macro_rules! operation_enum {
 
 use assert_namespace_absent::AssertNamespaceAbsentOp;
 use check_permission::CheckPermissionOp;
+use complete_compute_instance::CompleteComputeInstanceOp;
+use create_hypervisor_instance::CreateHypervisorInstanceOp;
 use create_k8s_secret::CreateK8sSecretOp;
 use create_namespace::CreateNamespaceOp;
 use delete_k8s_secret::DeleteK8sSecretOp;
@@ -105,6 +113,10 @@ use delete_relationships::DeleteRelationshipsOp;
 use helm_install::HelmInstallOp;
 use helm_uninstall::HelmUninstallOp;
 use helm_upgrade::HelmUpgradeOp;
+use join_zero_trust_network::JoinZeroTrustNetworkOp;
+use push_zero_trust_network::PushZeroTrustNetworkOp;
+use reserve_compute_instance::ReserveComputeInstanceOp;
+use setup_hoop_access::SetupHoopAccessOp;
 use update_instance_status::UpdateInstanceStatusOp;
 use update_instance_version::UpdateInstanceVersionOp;
 use update_k8s_secret::UpdateK8sSecretOp;
@@ -113,6 +125,8 @@ use write_relationships::WriteRelationshipsOp;
 operation_enum! {
     AssertNamespaceAbsent,
     CheckPermission,
+    CompleteComputeInstance,
+    CreateHypervisorInstance,
     CreateK8sSecret,
     CreateNamespace,
     DeleteK8sSecret,
@@ -121,6 +135,10 @@ operation_enum! {
     HelmInstall,
     HelmUninstall,
     HelmUpgrade,
+    JoinZeroTrustNetwork,
+    PushZeroTrustNetwork,
+    ReserveComputeInstance,
+    SetupHoopAccess,
     UpdateInstanceStatus,
     UpdateInstanceVersion,
     UpdateK8sSecret,
@@ -0,0 +1,48 @@
+use frn_core::compute::Overlays;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::WorkerContext;
+use crate::execution::WorkflowExecutionId;
+
+/// Pushes their WireGuard configuration to the running peers of the overlay
+/// of a zero trust network, once it gained or lost a peer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PushZeroTrustNetworkOp {
+    pub zero_trust_network_id: Uuid,
+}
+
+#[derive(Debug, Error, crate::OperationError)]
+pub enum PushZeroTrustNetworkError {
+    #[error("{0}")]
+    Compute(#[from] frn_core::Error),
+}
+
+impl crate::operations::Operation for PushZeroTrustNetworkOp {
+    type Error = PushZeroTrustNetworkError;
+
+    async fn execute(
+        self,
+        ctx: WorkerContext,
+        _execution_id: WorkflowExecutionId,
+    ) -> Result<Self, Self::Error> {
+        let overlays = Overlays::new(ctx.pool, ctx.hypervisor_resolver);
+
+        let unreachable = overlays.push(self.zero_trust_network_id).await?;
+
+        info!(zero_trust_network_id = %self.zero_trust_network_id, unreachable = unreachable.len(), "zero trust network configuration pushed");
+
+        Ok(self)
+    }
+
+    async fn rollback(
+        self,
+        _ctx: WorkerContext,
+        _execution_id: WorkflowExecutionId,
+    ) -> Result<(), Self::Error> {
+        // The peers are configured again on the next change of the overlay.
+        Ok(())
+    }
+}
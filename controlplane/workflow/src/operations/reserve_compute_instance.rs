@@ -0,0 +1,54 @@
+use frn_core::compute::Instances;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::WorkerContext;
+use crate::execution::WorkflowExecutionId;
+
+/// Checks the instance recorded by its creation awaits its provisioning and
+/// relates it to its project, and marks it as failed when the provisioning is
+/// rolled back.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReserveComputeInstanceOp {
+    pub instance_id: Uuid,
+}
+
+#[derive(Debug, Error, crate::OperationError)]
+pub enum ReserveComputeInstanceError {
+    #[error("{0}")]
+    Compute(#[from] frn_core::Error),
+}
+
+impl crate::operations::Operation for ReserveComputeInstanceOp {
+    type Error = ReserveComputeInstanceError;
+
+    async fn execute(
+        self,
+        ctx: WorkerContext,
+        _execution_id: WorkflowExecutionId,
+    ) -> Result<Self, Self::Error> {
+        let instances = Instances::new(ctx.spicedb, ctx.pool, ctx.hypervisor_resolver);
+
+        instances.ensure_reserved(self.instance_id).await?;
+
+        info!(instance_id = %self.instance_id, "compute instance reserved");
+
+        Ok(self)
+    }
+
+    async fn rollback(
+        self,
+        ctx: WorkerContext,
+        _execution_id: WorkflowExecutionId,
+    ) -> Result<(), Self::Error> {
+        let instances = Instances::new(ctx.spicedb, ctx.pool, ctx.hypervisor_resolver);
+
+        instances.release(self.instance_id).await?;
+
+        info!(instance_id = %self.instance_id, "compute instance failed (rollback)");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,58 @@
+use frn_core::compute::Instances;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::info;
+
+use crate::WorkerContext;
+use crate::execution::WorkflowExecutionId;
+
+/// Sets up the Hoop SSH bastion access of an instance, and removes it when the
+/// provisioning is rolled back.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetupHoopAccessOp {
+    pub instance_name: String,
+    pub snippet: String,
+    /// Populated during execute with the snippet holding the bastion
+    /// credentials.
+    pub prepared_snippet: Option<String>,
+}
+
+#[derive(Debug, Error, crate::OperationError)]
+pub enum SetupHoopAccessError {
+    #[error("{0}")]
+    Compute(#[from] frn_core::Error),
+}
+
+impl crate::operations::Operation for SetupHoopAccessOp {
+    type Error = SetupHoopAccessError;
+
+    async fn execute(
+        mut self,
+        ctx: WorkerContext,
+        _execution_id: WorkflowExecutionId,
+    ) -> Result<Self, Self::Error> {
+        let instances = Instances::new(ctx.spicedb, ctx.pool, ctx.hypervisor_resolver);
+
+        self.prepared_snippet = Some(
+            instances
+                .setup_hoop_access(&self.instance_name, self.snippet.clone())
+                .await?,
+        );
+
+        Ok(self)
+    }
+
+    async fn rollback(
+        self,
+        ctx: WorkerContext,
+        _execution_id: WorkflowExecutionId,
+    ) -> Result<(), Self::Error> {
+        let instances = Instances::new(ctx.spicedb, ctx.pool, ctx.hypervisor_resolver);
+
+        instances.cleanup_hoop_access(&self.instance_name).await;
+
+        info!(instance_name = %self.instance_name, "hoop access removed (rollback)");
+
+        Ok(())
+    }
+}
@@ -64,6 +64,7 @@ impl From<TransitionError> for WorkflowExecutionError {
 pub struct FetchWorkflowStatus {
     pub status: WorkflowExecutionStatus,
     pub next_retry_at: DateTime<Utc>,
+    pub initiated_by_user: Option<Uuid>,
 }
 
 impl WorkflowExecutionRepository {
@@ -304,9 +305,10 @@ impl WorkflowExecutionRepository {
         execution_id: WorkflowExecutionId,
     ) -> Result<FetchWorkflowStatus, TransitionError> {
         // Raw SQL: lib_fsm joins to resolve the status name plus a GREATEST() over next_retry_at/locked_until.
-        let row: (WorkflowExecutionStatus, DateTime<Utc>) = sqlx::query_as(
+        let row: (WorkflowExecutionStatus, DateTime<Utc>, Option<Uuid>) = sqlx::query_as(
             r#"SELECT abs.name AS status,
-                      GREATEST(exec.next_retry_at, coalesce(exec.locked_until, now())) AS next_retry_at
+                      GREATEST(exec.next_retry_at, coalesce(exec.locked_until, now())) AS next_retry_at,
+                      exec.initiated_by_user
                FROM workflow.execution exec
                INNER JOIN lib_fsm.state_machine sm ON sm.state_machine__id = exec.status
                INNER JOIN lib_fsm.abstract_state abs ON abs.abstract_state__id = sm.abstract_state__id
@@ -319,6 +321,7 @@ impl WorkflowExecutionRepository {
         Ok(FetchWorkflowStatus {
             status: row.0,
             next_retry_at: row.1,
+            initiated_by_user: row.2,
         })
     }
 }
@@ -1,14 +1,17 @@
+use frn_core::compute::ProvisionInstanceParams;
 use frn_core::managed::{
     DeleteManagedServiceParams, DeployManagedServiceParams, UpgradeManagedServiceParams,
 };
 use frn_core::workflow::WorkflowScheduler;
 use sqlx::PgConnection;
+use uuid::Uuid;
 
 use crate::execution::WorkflowInitiator;
 use crate::service::WorkflowService;
 use crate::workflows::WorkflowDefinitions;
 use crate::workflows::delete_managed_service::DeleteManagedServiceWorkflow;
 use crate::workflows::deploy_managed_service::DeployManagedServiceWorkflow;
+use crate::workflows::provision_instance::ProvisionInstanceWorkflow;
 use crate::workflows::upgrade_managed_service::UpgradeManagedServiceWorkflow;
 
 const WORKFLOW_MAX_RETRY: i32 = 3;
@@ -21,7 +24,7 @@ impl WorkflowScheduler<DeployManagedServiceParams> for ManagedWorkflowScheduler
         &self,
         conn: &mut PgConnection,
         params: DeployManagedServiceParams,
-    ) -> Result<(), String> {
+    ) -> Result<Uuid, String> {
         WorkflowService::schedule_workflow(
             conn,
             WorkflowDefinitions::DeployManagedService(DeployManagedServiceWorkflow::new(
@@ -44,7 +47,7 @@ impl WorkflowScheduler<DeployManagedServiceParams> for ManagedWorkflowScheduler
             None,
         )
         .await
-        .map(|_| ())
+        .map(|execution| execution.execution_id.as_uuid())
         .map_err(|e| e.to_string())
     }
 }
@@ -54,7 +57,7 @@ impl WorkflowScheduler<UpgradeManagedServiceParams> for ManagedWorkflowScheduler
         &self,
         conn: &mut PgConnection,
         params: UpgradeManagedServiceParams,
-    ) -> Result<(), String> {
+    ) -> Result<Uuid, String> {
         WorkflowService::schedule_workflow(
             conn,
             WorkflowDefinitions::UpgradeManagedService(UpgradeManagedServiceWorkflow::new(
@@ -74,7 +77,7 @@ impl WorkflowScheduler<UpgradeManagedServiceParams> for ManagedWorkflowScheduler
             None,
         )
         .await
-        .map(|_| ())
+        .map(|execution| execution.execution_id.as_uuid())
         .map_err(|e| e.to_string())
     }
 }
@@ -84,7 +87,7 @@ impl WorkflowScheduler<DeleteManagedServiceParams> for ManagedWorkflowScheduler
         &self,
         conn: &mut PgConnection,
         params: DeleteManagedServiceParams,
-    ) -> Result<(), String> {
+    ) -> Result<Uuid, String> {
         WorkflowService::schedule_workflow(
             conn,
             WorkflowDefinitions::DeleteManagedService(DeleteManagedServiceWorkflow::new(
@@ -104,7 +107,40 @@ impl WorkflowScheduler<DeleteManagedServiceParams> for ManagedWorkflowScheduler
             None,
         )
         .await
-        .map(|_| ())
+        .map(|execution| execution.execution_id.as_uuid())
+        .map_err(|e| e.to_string())
+    }
+}
+
+#[derive(Clone)]
+pub struct ComputeWorkflowScheduler;
+
+impl WorkflowScheduler<ProvisionInstanceParams> for ComputeWorkflowScheduler {
+    async fn schedule(
+        &self,
+        conn: &mut PgConnection,
+        params: ProvisionInstanceParams,
+    ) -> Result<Uuid, String> {
+        WorkflowService::schedule_workflow(
+            conn,
+            WorkflowDefinitions::ProvisionInstance(ProvisionInstanceWorkflow::new(
+                params.instance_id,
+                params.hypervisor_id,
+                params.project_slug,
+                params.name,
+                params.cores,
+                params.disk_bytes,
+                params.disk_image,
+                params.memory_bytes,
+                params.snippet,
+                params.zero_trust_network_id,
+            )),
+            WORKFLOW_MAX_RETRY,
+            WorkflowInitiator::User(params.principal_id),
+            None,
+        )
+        .await
+        .map(|execution| execution.execution_id.as_uuid())
         .map_err(|e| e.to_string())
     }
 }
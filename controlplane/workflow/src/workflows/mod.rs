@@ -8,6 +8,7 @@ use crate::operations::Operations;
 
 pub mod delete_managed_service;
 pub mod deploy_managed_service;
+pub mod provision_instance;
 pub mod upgrade_managed_service;
 pub mod write_relationships;
 
@@ -68,6 +69,13 @@ pub trait WorkflowDefinition: Debug + Sized + Send {
         None
     }
 
+    /// Records the operations executed by the last round.
+    ///
+    /// Lets workflows pick the outputs of their operations, such as ids
+    /// assigned by an external system, up for the following rounds. Defaults
+    /// to ignoring them.
+    fn record_operations(&mut self, _operations: &[Operations]) {}
+
     fn name(&self) -> &str;
 }
 
@@ -115,6 +123,12 @@ macro_rules! workflow_enum {
                     }
                 }
 
+                fn record_operations(&mut self, operations: &[$crate::operations::Operations]) {
+                    match self {
+                        $(Self::$workflow_name(workflow) => workflow.record_operations(operations)),*
+                    }
+                }
+
                 fn name(&self) -> &str {
                     match self {
                         $(Self::$workflow_name(workflow) => workflow.name()),*
@@ -127,12 +141,14 @@ macro_rules! workflow_enum {
 
 use delete_managed_service::DeleteManagedServiceWorkflow;
 use deploy_managed_service::DeployManagedServiceWorkflow;
+use provision_instance::ProvisionInstanceWorkflow;
 use upgrade_managed_service::UpgradeManagedServiceWorkflow;
 use write_relationships::WriteRelationshipsWorkflow;
 
 workflow_enum! {
     DeleteManagedService,
     DeployManagedService,
+    ProvisionInstance,
     UpgradeManagedService,
     WriteRelationships,
 }
@@ -0,0 +1,219 @@
+use std::error::Error as StdError;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::WorkerContext;
+use crate::operations::Operations;
+use crate::operations::complete_compute_instance::CompleteComputeInstanceOp;
+use crate::operations::create_hypervisor_instance::CreateHypervisorInstanceOp;
+use crate::operations::join_zero_trust_network::JoinZeroTrustNetworkOp;
+use crate::operations::push_zero_trust_network::PushZeroTrustNetworkOp;
+use crate::operations::reserve_compute_instance::ReserveComputeInstanceOp;
+use crate::operations::setup_hoop_access::SetupHoopAccessOp;
+use crate::workflows::WorkflowDefinition;
+
+/// Provisions a compute instance accepted for creation: its Hoop bastion
+/// access, its peer of the overlay of its zero trust network, its VM on the
+/// hypervisor selected for it, and its records.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProvisionInstanceWorkflow {
+    pub instance_id: Uuid,
+    pub hypervisor_id: Uuid,
+    pub project_slug: String,
+    pub name: String,
+    pub cores: u8,
+    pub disk_bytes: u64,
+    pub disk_image: String,
+    pub memory_bytes: u64,
+    pub snippet: String,
+    /// The zero trust network the instance joins the overlay of, if any.
+    #[serde(default)]
+    pub zero_trust_network_id: Option<Uuid>,
+
+    #[serde(default, skip_serializing, skip_deserializing)]
+    status: ProvisionStatus,
+    /// The snippet holding the bastion credentials, once set up.
+    #[serde(default, skip_serializing, skip_deserializing)]
+    prepared_snippet: Option<String>,
+    /// The snippet also holding the WireGuard configuration, once the
+    /// instance joined the overlay.
+    #[serde(default, skip_serializing, skip_deserializing)]
+    overlay_snippet: Option<String>,
+    /// The id of the VM on the hypervisor, once created.
+    #[serde(default, skip_serializing, skip_deserializing)]
+    distant_id: Option<String>,
+}
+
+#[derive(Debug, Default)]
+enum ProvisionStatus {
+    #[default]
+    ReservingInstance,
+    SettingUpHoopAccess,
+    JoiningZeroTrustNetwork,
+    CreatingHypervisorInstance,
+    CompletingInstance,
+    PushingZeroTrustNetwork,
+    Done,
+}
+
+impl ProvisionInstanceWorkflow {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        instance_id: Uuid,
+        hypervisor_id: Uuid,
+        project_slug: String,
+        name: String,
+        cores: u8,
+        disk_bytes: u64,
+        disk_image: String,
+        memory_bytes: u64,
+        snippet: String,
+        zero_trust_network_id: Option<Uuid>,
+    ) -> Self {
+        Self {
+            instance_id,
+            hypervisor_id,
+            project_slug,
+            name,
+            cores,
+            disk_bytes,
+            disk_image,
+            memory_bytes,
+            snippet,
+            zero_trust_network_id,
+            status: ProvisionStatus::ReservingInstance,
+            prepared_snippet: None,
+            overlay_snippet: None,
+            distant_id: None,
+        }
+    }
+}
+
+impl WorkflowDefinition for ProvisionInstanceWorkflow {
+    type Error = Box<dyn StdError>;
+
+    async fn next_operations(
+        &mut self,
+        _ctx: WorkerContext,
+    ) -> Result<Vec<Operations>, Self::Error> {
+        match self.status {
+            ProvisionStatus::ReservingInstance => {
+                self.status = ProvisionStatus::SettingUpHoopAccess;
+                Ok(vec![Operations::ReserveComputeInstance(
+                    ReserveComputeInstanceOp {
+                        instance_id: self.instance_id,
+                    },
+                )])
+            }
+            ProvisionStatus::SettingUpHoopAccess => {
+                self.status = match self.zero_trust_network_id {
+                    Some(_) => ProvisionStatus::JoiningZeroTrustNetwork,
+                    None => ProvisionStatus::CreatingHypervisorInstance,
+                };
+                Ok(vec![Operations::SetupHoopAccess(SetupHoopAccessOp {
+                    instance_name: self.name.clone(),
+                    snippet: self.snippet.clone(),
+                    prepared_snippet: None,
+                })])
+            }
+            ProvisionStatus::JoiningZeroTrustNetwork => {
+                let zero_trust_network_id = self
+                    .zero_trust_network_id
+                    .ok_or("the instance has no zero trust network")?;
+                let snippet = self
+                    .prepared_snippet
+                    .clone()
+                    .ok_or("the hoop access of the instance was not set up")?;
+
+                self.status = ProvisionStatus::CreatingHypervisorInstance;
+                Ok(vec![Operations::JoinZeroTrustNetwork(
+                    JoinZeroTrustNetworkOp {
+                        instance_id: self.instance_id,
+                        zero_trust_network_id,
+                        snippet,
+                        prepared_snippet: None,
+                    },
+                )])
+            }
+            ProvisionStatus::CreatingHypervisorInstance => {
+                let snippet = match self.zero_trust_network_id {
+                    Some(_) => self
+                        .overlay_snippet
+                        .clone()
+                        .ok_or("the instance did not join its zero trust network")?,
+                    None => self
+                        .prepared_snippet
+                        .clone()
+                        .ok_or("the hoop access of the instance was not set up")?,
+                };
+
+                self.status = ProvisionStatus::CompletingInstance;
+                Ok(vec![Operations::CreateHypervisorInstance(
+                    CreateHypervisorInstanceOp {
+                        hypervisor_id: self.hypervisor_id,
+                        project_slug: self.project_slug.clone(),
+                        name: self.name.clone(),
+                        cores: self.cores,
+                        disk_bytes: self.disk_bytes,
+                        disk_image: self.disk_image.clone(),
+                        memory_bytes: self.memory_bytes,
+                        snippet,
+                        distant_id: None,
+                    },
+                )])
+            }
+            ProvisionStatus::CompletingInstance => {
+                let distant_id = self
+                    .distant_id
+                    .clone()
+                    .ok_or("the hypervisor instance was not created")?;
+
+                self.status = match self.zero_trust_network_id {
+                    Some(_) => ProvisionStatus::PushingZeroTrustNetwork,
+                    None => ProvisionStatus::Done,
+                };
+                Ok(vec![Operations::CompleteComputeInstance(
+                    CompleteComputeInstanceOp {
+                        instance_id: self.instance_id,
+                        distant_id,
+                    },
+                )])
+            }
+            ProvisionStatus::PushingZeroTrustNetwork => {
+                let zero_trust_network_id = self
+                    .zero_trust_network_id
+                    .ok_or("the instance has no zero trust network")?;
+
+                self.status = ProvisionStatus::Done;
+                Ok(vec![Operations::PushZeroTrustNetwork(
+                    PushZeroTrustNetworkOp {
+                        zero_trust_network_id,
+                    },
+                )])
+            }
+            ProvisionStatus::Done => Ok(vec![]),
+        }
+    }
+
+    fn record_operations(&mut self, operations: &[Operations]) {
+        for operation in operations {
+            match operation {
+                Operations::SetupHoopAccess(op) => {
+                    self.prepared_snippet = op.prepared_snippet.clone();
+                }
+                Operations::JoinZeroTrustNetwork(op) => {
+                    self.overlay_snippet = op.prepared_snippet.clone();
+                }
+                Operations::CreateHypervisorInstance(op) => {
+                    self.distant_id = op.distant_id.clone();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn name(&self) -> &str {
+        "ProvisionInstance"
+    }
+}
@@ -22,6 +22,12 @@ pub fn context_with_spicedb(spicedb: SpiceDB) -> WorkerContext {
             deployment_annotations: std::collections::BTreeMap::new(),
         },
         kek: Arc::new(frn_crypto::Kek::from_bytes([42u8; 32])),
+        hypervisor_resolver: hypervisor::Resolver::new(
+            Arc::new(frn_crypto::Kek::from_bytes([42u8; 32])),
+            hypervisor::SnippetsConfig::default(),
+            hypervisor::StoragesConfig::default(),
+            hypervisor::KubeVirtConfig::default(),
+        ),
         kubeconfig_path: None,
     }
 }
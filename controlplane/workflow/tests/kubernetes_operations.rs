@@ -70,6 +70,12 @@ async fn context_with(client: kube::Client) -> WorkerContext {
             deployment_annotations: BTreeMap::new(),
         },
         kek: Arc::new(frn_crypto::Kek::from_bytes([42u8; 32])),
+        hypervisor_resolver: hypervisor::Resolver::new(
+            Arc::new(frn_crypto::Kek::from_bytes([42u8; 32])),
+            hypervisor::SnippetsConfig::default(),
+            hypervisor::StoragesConfig::default(),
+            hypervisor::KubeVirtConfig::default(),
+        ),
         kubeconfig_path: None,
     }
 }
@@ -365,6 +365,13 @@ so we only assert the TLS handshake succeeds (any HTTP status), not a 2xx.
       echo "public endpoints ready"
 {{- end }}
 
+{{/*
+Apply the database migrations, then encrypt the hypervisor tokens left in
+plaintext: the KEK never reaches the database, so no migration can encrypt them
+itself. The plaintext column is dropped by a later release, once every
+deployment has encrypted its tokens; until then the encryption is a no-op once
+done.
+*/}}
 {{- define "plateforme.runAtlasMigrations" -}}
 - name: run-atlas-migrations
   image: {{ include "plateforme.imageWithOverrides" (dict "component" "controlplane" "imageConfig" .Values.controlplane.image "context" .) }}
@@ -382,6 +389,23 @@ so we only assert the TLS handshake succeeds (any HTTP status), not a 2xx.
         secretKeyRef:
           name: {{ include "plateforme.secretName" . }}
           key: database-url
+- name: encrypt-hypervisor-tokens
+  image: {{ include "plateforme.imageWithOverrides" (dict "component" "controlplane" "imageConfig" .Values.controlplane.image "context" .) }}
+  command: ["/usr/bin/server"]
+  args:
+    - hypervisors
+    - encrypt-tokens
+  env:
+    - name: DATABASE_URL
+      valueFrom:
+        secretKeyRef:
+          name: {{ include "plateforme.secretName" . }}
+          key: database-url
+    - name: HYPERVISOR_TOKEN_ENCRYPTION_KEY
+      valueFrom:
+        secretKeyRef:
+          name: {{ include "plateforme.secretName" . }}
+          key: hypervisor-token-encryption-key
 {{- end }}
 
 {{- define "plateforme.waitForMigrations" -}}